@@ -16,39 +16,214 @@ pub struct KEntry {
     pub count: u32,
 }
 
+/// Requests as sent over the socket: `{"method": "...", "params": {...}}`. `params` may
+/// be left out for any request whose parameters are all optional, e.g.
+/// `{"method": "pause"}` (see the `Deserialize` impl below).
 #[derive(Debug, Serialize, Deserialize)]
-#[serde(tag = "method", content = "params")]
+#[serde(tag = "method", content = "params", remote = "Self")]
 pub enum Request {
     #[serde(rename = "query_outputs")]
     QueryOutputs,
+    /// Playback state of every output (see `OutputStatus`)
+    #[serde(rename = "status")]
+    Status,
+    /// With `tags`, picks among files carrying every one of them, this once
     #[serde(rename = "next")]
-    Next { output: Option<String> },
+    Next {
+        output: Option<String>,
+        #[serde(default)]
+        tags: Vec<String>,
+    },
     #[serde(rename = "prev")]
     Prev { output: Option<String> },
+    /// Shows `path` right away on `output` (comma-separated names or globs; every output
+    /// if left out), outside the queue. `transition` (a builtin, a `[transition.NAME]`
+    /// definition, `cut` or `random`) and `duration` (seconds) apply to this switch only.
+    #[serde(rename = "set")]
+    Set {
+        path: String,
+        #[serde(default)]
+        output: Option<String>,
+        #[serde(default)]
+        transition: Option<String>,
+        #[serde(default)]
+        duration: Option<f32>,
+    },
+    /// Changes the transition of `output` (comma-separated names or globs; every output
+    /// if left out) until the next reload: a builtin, a `[transition.NAME]` definition,
+    /// `random` or `auto`. `duration` (seconds) replaces `transition-time` as well.
+    #[serde(rename = "set_transition")]
+    SetTransition {
+        #[serde(default)]
+        output: Option<String>,
+        transition: String,
+        #[serde(default)]
+        duration: Option<f32>,
+    },
+    /// Changes the video volume (0-100) of `output` (comma-separated names or globs; every
+    /// output if left out) until the next reload, including the video playing now
+    #[serde(rename = "set_volume")]
+    SetVolume {
+        #[serde(default)]
+        output: Option<String>,
+        volume: u8,
+    },
+    /// Silences (true) or unsilences (false) videos on `output`, keeping their volume;
+    /// each output is toggled when `muted` is left out
+    #[serde(rename = "mute")]
+    Mute {
+        #[serde(default)]
+        output: Option<String>,
+        #[serde(default)]
+        muted: Option<bool>,
+    },
+    /// Jumps the `[kiosk]` sequence to item `index` (0-based) on every output
+    #[serde(rename = "goto")]
+    Goto { index: usize },
+    /// Without `path` or `paths`, the wallpaper currently shown on `output` (or on every
+    /// output, if they all show the same file) is targeted. `paths` loves many files in
+    /// one request.
     #[serde(rename = "love")]
-    Love { path: String, multiplier: f32 },
+    Love {
+        #[serde(default)]
+        path: Option<String>,
+        #[serde(default)]
+        paths: Vec<String>,
+        multiplier: f32,
+        #[serde(default)]
+        output: Option<String>,
+    },
     #[serde(rename = "unlove")]
-    Unlove { path: String },
+    Unlove {
+        #[serde(default)]
+        path: Option<String>,
+        #[serde(default)]
+        paths: Vec<String>,
+        #[serde(default)]
+        output: Option<String>,
+    },
     #[serde(rename = "loveitlist")]
     LoveitList,
+    /// `output` (comma-separated names or globs) limits the pause to those outputs
     #[serde(rename = "pause")]
-    Pause,
+    Pause {
+        #[serde(default)]
+        output: Option<String>,
+    },
     #[serde(rename = "resume")]
-    Resume,
+    Resume {
+        #[serde(default)]
+        output: Option<String>,
+    },
     #[serde(rename = "stop")]
     Stop,
     #[serde(rename = "reload")]
     Reload,
+    /// Re-reads just the config sections of `output` (comma-separated names or globs)
+    #[serde(rename = "reload_output")]
+    ReloadOutput { output: String },
     #[serde(rename = "clear")]
-    Clear { output: Option<String> },
+    Clear {
+        output: Option<String>,
+        /// Solid color to show instead of black ("#rrggbb" / "#rrggbbaa")
+        #[serde(default)]
+        color: Option<String>,
+    },
     #[serde(rename = "kill")]
     Kill,
     #[serde(rename = "playlist")]
     Playlist(PlaylistCommand),
     #[serde(rename = "blacklist")]
     Blacklist(BlacklistCommand),
+    #[serde(rename = "quarantine")]
+    Quarantine(QuarantineCommand),
+    #[serde(rename = "tag")]
+    Tag(TagCommand),
     #[serde(rename = "history")]
     History { output: Option<String> },
+    /// The next `count` files the queue would pick, without picking them
+    #[serde(rename = "pick_preview")]
+    PickPreview {
+        output: Option<String>,
+        count: usize,
+    },
+    #[serde(rename = "cache")]
+    Cache(CacheCommand),
+    #[serde(rename = "fetch")]
+    Fetch(FetchCommand),
+    #[serde(rename = "behavior")]
+    Behavior(BehaviorCommand),
+    /// Restricts picks to loved files (on) or lifts the restriction (off)
+    #[serde(rename = "favorites")]
+    Favorites {
+        enabled: bool,
+        #[serde(default)]
+        output: Option<String>,
+    },
+    /// Runs the requests in order with no other client in between, answering with
+    /// `Response::Batch`. After a failed request the rest are skipped. A bare JSON
+    /// array of requests on the socket is treated the same way.
+    #[serde(rename = "batch")]
+    Batch(Vec<Request>),
+    /// Frame, transition, texture pool and error stats, overall and per output
+    #[serde(rename = "metrics")]
+    Metrics,
+    /// Writes the wallpaper shown on `output` (as loaded, before cover-cropping) to
+    /// `path` as a PNG, blurred by `blur` dual Kawase passes (0 = sharp). `output` may be
+    /// left out when there is only one.
+    #[serde(rename = "snapshot")]
+    Snapshot {
+        #[serde(default)]
+        output: Option<String>,
+        path: String,
+        #[serde(default)]
+        blur: u32,
+    },
+    /// Writes the last frame presented on `output` to `path` as a PNG, at output
+    /// resolution and mid-transition if one is running. `output` may be left out when
+    /// there is only one.
+    #[serde(rename = "capture")]
+    Capture {
+        #[serde(default)]
+        output: Option<String>,
+        path: String,
+    },
+    /// Respond once in-flight content switches and transitions have finished
+    #[serde(rename = "wait")]
+    Wait {
+        output: Option<String>,
+        /// Give up after this many seconds
+        timeout: Option<u64>,
+    },
+    #[serde(rename = "session")]
+    Session(SessionCommand),
+    /// Keeps the connection open and writes an `Event` as one JSON line each time
+    /// something happens, until the client hangs up. Needs a connection of its own.
+    #[serde(rename = "subscribe")]
+    Subscribe,
+}
+
+impl Serialize for Request {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Request::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Request {
+    /// A request without `params` is retried with `"params": {}`, so variants that
+    /// gained optional parameters (`pause`, `resume`) keep accepting the bare form
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+        let mut value = serde_json::Value::deserialize(deserializer)?;
+        let parsed = Request::deserialize(&value);
+        match value.as_object_mut() {
+            Some(object) if parsed.is_err() && !object.contains_key("params") => {
+                object.insert("params".to_string(), serde_json::json!({}));
+                Request::deserialize(&value).map_err(D::Error::custom)
+            }
+            _ => parsed.map_err(D::Error::custom),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -66,19 +241,148 @@ pub enum PlaylistCommand {
     Load { name: Option<String> },
     #[serde(rename = "list")]
     List,
+    /// Contents and schedule of one playlist
+    #[serde(rename = "show")]
+    Show { name: String },
+    /// Fills a playlist (created if needed) from a plain or M3U list of paths and globs
+    /// in `file`, an absolute path the daemon reads
+    #[serde(rename = "import")]
+    Import {
+        name: String,
+        file: String,
+        /// Add to the playlist instead of replacing its files
+        #[serde(default)]
+        append: bool,
+    },
+    /// Writes a playlist's files to `file` (absolute), as M3U for `.m3u`/`.m3u8`
+    #[serde(rename = "export")]
+    Export { name: String, file: String },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "action", content = "params")]
+pub enum CacheCommand {
+    /// Evict least-recently-used files until the cache fits its size limit
+    #[serde(rename = "gc")]
+    Gc {
+        /// Limit to enforce instead of the configured `cache-max-size` (e.g. "500MB")
+        #[serde(default)]
+        max_size: Option<String>,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "action", content = "params")]
+pub enum FetchCommand {
+    /// Pull from the `[fetch]` service right away instead of at the next interval,
+    /// answering with `Response::Fetch` once the pull is done
+    #[serde(rename = "now")]
+    Now,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "action", content = "params")]
+pub enum BehaviorCommand {
+    /// Switch `monitor-behavior` without restarting, until the next reload. `mode` is
+    /// "independent", "synchronized" or "grouped"; `groups` lists the outputs of each
+    /// group and is only used (and required) for "grouped".
+    #[serde(rename = "set")]
+    Set {
+        mode: String,
+        #[serde(default)]
+        groups: Vec<Vec<String>>,
+    },
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(tag = "action", content = "params")]
 pub enum BlacklistCommand {
+    /// Without `path` or `paths`, blacklists the wallpaper currently shown (see
+    /// `Request::Love`)
     #[serde(rename = "add")]
-    Add { path: String },
+    Add {
+        #[serde(default)]
+        path: Option<String>,
+        #[serde(default)]
+        paths: Vec<String>,
+        #[serde(default)]
+        output: Option<String>,
+    },
     #[serde(rename = "remove")]
-    Remove { path: String },
+    Remove {
+        #[serde(default)]
+        path: Option<String>,
+        #[serde(default)]
+        paths: Vec<String>,
+    },
+    #[serde(rename = "list")]
+    List,
+}
+
+/// Labels on files that outputs' `tags` and `next --tag` pick by
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "action", content = "params")]
+pub enum TagCommand {
+    /// Without `path` or `paths`, tags the wallpaper currently shown (see `Request::Love`)
+    #[serde(rename = "add")]
+    Add {
+        #[serde(default)]
+        path: Option<String>,
+        #[serde(default)]
+        paths: Vec<String>,
+        #[serde(default)]
+        output: Option<String>,
+        tags: Vec<String>,
+    },
+    #[serde(rename = "remove")]
+    Remove {
+        #[serde(default)]
+        path: Option<String>,
+        #[serde(default)]
+        paths: Vec<String>,
+        #[serde(default)]
+        output: Option<String>,
+        tags: Vec<String>,
+    },
+    /// Every tag with its files, or just `tag`
+    #[serde(rename = "list")]
+    List {
+        #[serde(default)]
+        tag: Option<String>,
+    },
+}
+
+/// Named snapshots of every output's setup: wallpaper, playlist, pause, favorites and
+/// transition
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "action", content = "params")]
+pub enum SessionCommand {
+    /// Save the current setup under `name`, replacing a session of that name
+    #[serde(rename = "save")]
+    Save { name: String },
+    /// Bring back a saved setup on the outputs it knows that are connected now
+    #[serde(rename = "restore")]
+    Restore { name: String },
+    #[serde(rename = "delete")]
+    Delete { name: String },
     #[serde(rename = "list")]
     List,
 }
 
+/// Files that failed to load too often and are skipped until released
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "action", content = "params")]
+pub enum QuarantineCommand {
+    #[serde(rename = "list")]
+    List,
+    /// Put a file back into rotation
+    #[serde(rename = "remove")]
+    Remove { path: String },
+    /// Release every quarantined file
+    #[serde(rename = "clear")]
+    Clear,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
 #[serde(rename_all = "kebab-case", tag = "type")]
 pub enum Transition {
@@ -501,6 +805,8 @@ pub enum Transition {
         zoom_quickness: f32,
     },
     Random,
+    /// Picked per switch from the outgoing and incoming content (`[auto-transition]`)
+    Auto,
     Custom {
         shader: String,
         #[serde(default)]
@@ -508,113 +814,115 @@ pub enum Transition {
     },
 }
 
+/// Builtin transitions `Transition::Random` picks from, by `Transition::from_name` name
+pub const RANDOM_TRANSITIONS: &[&str] = &[
+    "angular",
+    "bookflip",
+    "bounce",
+    "bowtiehorizontal",
+    "bowtievertical",
+    "bowtiewithparameter",
+    "burn",
+    "butterflywavescrawler",
+    "cannabisleaf",
+    "circle",
+    "circlecrop",
+    "circleopen",
+    "colorphase",
+    "coord-from-in",
+    "crazyparametricfun",
+    "colourdistance",
+    "crosshatch",
+    "crosswarp",
+    "crosszoom",
+    "cube",
+    "directional",
+    "directionaleasing",
+    "directionalscaled",
+    "directionalwarp",
+    "directionalwipe",
+    "displacement",
+    "dissolve",
+    "doom",
+    "doorway",
+    "dreamy",
+    "dreamyzoom",
+    "edge",
+    "fade",
+    "fadecolor",
+    "fadegrayscale",
+    "filmburn",
+    "flyeye",
+    "glitchdisplace",
+    "glitchmemories",
+    "gridflip",
+    "heart",
+    "hexagonalize",
+    "horizontalclose",
+    "horizontalopen",
+    "invertedpagecurl",
+    "kaleidoscope",
+    "leftright",
+    "linearblur",
+    "luma",
+    "luminancemelt",
+    "morph",
+    "mosaic",
+    "mosaic_transition",
+    "multiplyblend",
+    "overexposure",
+    "perlin",
+    "pinwheel",
+    "pixelize",
+    "polarfunction",
+    "polkadotscurtain",
+    "powerkaleido",
+    "radial",
+    "randomnoisex",
+    "randomsquares",
+    "rectangle",
+    "rectanglecrop",
+    "ripple",
+    "rolls",
+    "rotate",
+    "rotatescalefade",
+    "rotatescalevanish",
+    "scale_in",
+    "simplezoom",
+    "simplezoomout",
+    "slides",
+    "squareswire",
+    "squeeze",
+    "staticfade",
+    "static_wipe",
+    "stereoviewer",
+    "swap",
+    "swirl",
+    "tangentmotionblur",
+    "topbottom",
+    "tvstatic",
+    "undulatingburnout",
+    "verticalclose",
+    "verticalopen",
+    "waterdrop",
+    "wind",
+    "windowblinds",
+    "windowslice",
+    "wipedown",
+    "wipeleft",
+    "wiperight",
+    "wipeup",
+    "x-axis-translation",
+    "zoomincircles",
+    "zoomleftwipe",
+    "zoomrightwipe",
+];
+
 impl Transition {
     pub fn pick_random() -> Self {
         use rand::Rng;
         let mut rng = rand::thread_rng();
-        let variants = [
-            "angular",
-            "bookflip",
-            "bounce",
-            "bowtiehorizontal",
-            "bowtievertical",
-            "bowtiewithparameter",
-            "burn",
-            "butterflywavescrawler",
-            "cannabisleaf",
-            "circle",
-            "circlecrop",
-            "circleopen",
-            "colorphase",
-            "coord-from-in",
-            "crazyparametricfun",
-            "colourdistance",
-            "crosshatch",
-            "crosswarp",
-            "crosszoom",
-            "cube",
-            "directional",
-            "directionaleasing",
-            "directionalscaled",
-            "directionalwarp",
-            "directionalwipe",
-            "displacement",
-            "dissolve",
-            "doom",
-            "doorway",
-            "dreamy",
-            "dreamyzoom",
-            "edge",
-            "fade",
-            "fadecolor",
-            "fadegrayscale",
-            "filmburn",
-            "flyeye",
-            "glitchdisplace",
-            "glitchmemories",
-            "gridflip",
-            "heart",
-            "hexagonalize",
-            "horizontalclose",
-            "horizontalopen",
-            "invertedpagecurl",
-            "kaleidoscope",
-            "leftright",
-            "linearblur",
-            "luma",
-            "luminancemelt",
-            "morph",
-            "mosaic",
-            "mosaic_transition",
-            "multiplyblend",
-            "overexposure",
-            "perlin",
-            "pinwheel",
-            "pixelize",
-            "polarfunction",
-            "polkadotscurtain",
-            "powerkaleido",
-            "radial",
-            "randomnoisex",
-            "randomsquares",
-            "rectangle",
-            "rectanglecrop",
-            "ripple",
-            "rolls",
-            "rotate",
-            "rotatescalefade",
-            "rotatescalevanish",
-            "scale_in",
-            "simplezoom",
-            "simplezoomout",
-            "slides",
-            "squareswire",
-            "squeeze",
-            "staticfade",
-            "static_wipe",
-            "stereoviewer",
-            "swap",
-            "swirl",
-            "tangentmotionblur",
-            "topbottom",
-            "tvstatic",
-            "undulatingburnout",
-            "verticalclose",
-            "verticalopen",
-            "waterdrop",
-            "wind",
-            "windowblinds",
-            "windowslice",
-            "wipedown",
-            "wipeleft",
-            "wiperight",
-            "wipeup",
-            "x-axis-translation",
-            "zoomincircles",
-            "zoomleftwipe",
-            "zoomrightwipe",
-        ];
-        let name = variants[rng.gen_range(0..variants.len())];
+        let name = RANDOM_TRANSITIONS[rng.gen_range(0..RANDOM_TRANSITIONS.len())];
         Self::from_name(name)
     }
 
@@ -882,6 +1190,7 @@ impl Transition {
                 zoom_quickness: 0.8,
             },
             "random" => Transition::Random,
+            "auto" => Transition::Auto,
             _ => Transition::Fade,
         }
     }
@@ -989,6 +1298,7 @@ impl Transition {
             Transition::ZoomLeftWipe { .. } => "ZoomLeftWipe".to_string(),
             Transition::ZoomRightWipe { .. } => "ZoomRigthWipe".to_string(),
             Transition::Random => "random".to_string(),
+            Transition::Auto => "auto".to_string(),
             Transition::Custom { shader, .. } => shader.clone(),
         }
     }
@@ -1032,7 +1342,8 @@ impl Transition {
             | Transition::WindowBlinds
             | Transition::XAxisTranslation
             | Transition::ZoomInCircles
-            | Transition::Random => {}
+            | Transition::Random
+            | Transition::Auto => {}
             Transition::Angular { starting_angle } => {
                 p[0] = *starting_angle;
             }
@@ -1407,12 +1718,385 @@ impl Transition {
 #[derive(Debug, Serialize, Deserialize)]
 pub enum Response {
     Ok,
-    Error(String),
+    /// The request failed as a whole
+    Error(CommandError),
     OutputInfo(Vec<OutputInfo>),
+    Status(Vec<OutputStatus>),
     LoveitList(Vec<KEntry>),
     Playlists(Vec<String>),
+    Playlist(PlaylistInfo),
+    PlaylistImport(PlaylistImportReport),
+    Sessions(Vec<SessionInfo>),
     Blacklist(Vec<String>),
+    Quarantine(Vec<QuarantineEntry>),
+    Tags(Vec<TagInfo>),
     History(Vec<String>),
+    /// One preview per queue (see `Request::PickPreview`)
+    Picks(Vec<PickPreview>),
+    /// Outcome of a command that acts on several outputs
+    OutputResults(Vec<OutputResult>),
+    Cache(CacheReport),
+    Fetch(FetchReport),
+    Metrics(MetricsReport),
+    /// One reply per request of a `Request::Batch`, in order
+    Batch(Vec<Response>),
+}
+
+impl Response {
+    pub fn error(code: ErrorCode, message: impl Into<String>) -> Self {
+        Response::Error(CommandError::new(code, message))
+    }
+
+    /// Whether the request failed, fully or on any output
+    pub fn is_failure(&self) -> bool {
+        self.error_code().is_some()
+    }
+
+    /// Code of the first failure in the reply (the whole request, an output or a
+    /// batched request), None if everything succeeded
+    pub fn error_code(&self) -> Option<ErrorCode> {
+        match self {
+            Response::Error(e) => Some(e.code),
+            Response::OutputResults(results) => results
+                .iter()
+                .find(|r| !r.success)
+                .map(|r| r.code.unwrap_or(ErrorCode::Failed)),
+            Response::Batch(responses) => responses.iter().find_map(Response::error_code),
+            _ => None,
+        }
+    }
+}
+
+/// What kind of failure a request or one of its outputs ran into, for scripts to
+/// branch on. `kldctl` exits with `exit_status()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// Anything not covered below, e.g. a file that failed to decode or write
+    Failed,
+    /// Malformed or contradicting arguments
+    InvalidArgument,
+    /// An output name or pattern that matches no connected output
+    UnknownOutput,
+    /// A file, playlist or list entry that doesn't exist
+    NotFound,
+    /// Not possible right now: nothing displayed, kiosk mode off, static output, ...
+    Unavailable,
+    /// `wait` gave up before the transitions finished
+    Timeout,
+    /// The config file failed to load
+    Config,
+    /// Not run because an earlier request of the batch failed
+    Skipped,
+}
+
+impl ErrorCode {
+    pub const ALL: [ErrorCode; 8] = [
+        ErrorCode::Failed,
+        ErrorCode::InvalidArgument,
+        ErrorCode::UnknownOutput,
+        ErrorCode::NotFound,
+        ErrorCode::Unavailable,
+        ErrorCode::Timeout,
+        ErrorCode::Config,
+        ErrorCode::Skipped,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::Failed => "failed",
+            ErrorCode::InvalidArgument => "invalid_argument",
+            ErrorCode::UnknownOutput => "unknown_output",
+            ErrorCode::NotFound => "not_found",
+            ErrorCode::Unavailable => "unavailable",
+            ErrorCode::Timeout => "timeout",
+            ErrorCode::Config => "config",
+            ErrorCode::Skipped => "skipped",
+        }
+    }
+
+    /// Process exit status for this failure; 1 for generic ones
+    pub fn exit_status(self) -> i32 {
+        match self {
+            ErrorCode::Failed | ErrorCode::Skipped => 1,
+            ErrorCode::InvalidArgument => 2,
+            ErrorCode::UnknownOutput => 3,
+            ErrorCode::NotFound => 4,
+            ErrorCode::Unavailable => 5,
+            ErrorCode::Timeout => 6,
+            ErrorCode::Config => 7,
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A failed request: its code, a message for humans, and the outputs it concerns
+/// (e.g. the unknown one), if any
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandError {
+    pub code: ErrorCode,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub outputs: Vec<String>,
+}
+
+impl CommandError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            outputs: Vec::new(),
+        }
+    }
+
+    pub fn with_outputs(mut self, outputs: Vec<String>) -> Self {
+        self.outputs = outputs;
+        self
+    }
+}
+
+impl std::fmt::Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MetricsReport {
+    pub uptime_secs: u64,
+    /// Main loop iteration time
+    pub frame_time_avg_ms: f64,
+    pub frame_time_p95_ms: f64,
+    pub transitions: u64,
+    /// 0.0-1.0
+    pub texture_pool_hit_rate: f64,
+    pub errors: u64,
+    pub outputs: Vec<OutputMetricsReport>,
+    /// GPU time per transition shader, most expensive first. Empty when the GPU has no
+    /// timestamp queries.
+    #[serde(default)]
+    pub transitions_gpu: Vec<TransitionGpuReport>,
+    /// Resident memory of the daemon, None where it can't be read
+    #[serde(default)]
+    pub resident_mb: Option<f64>,
+    /// Times idle memory was released (`idle-trim`)
+    #[serde(default)]
+    pub idle_trims: u64,
+    /// Resident memory before and after the last idle trim
+    #[serde(default)]
+    pub last_trim_mb: Option<(f64, f64)>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct OutputMetricsReport {
+    pub output: String,
+    /// Time spent rendering one frame of this output (last 100 frames)
+    pub frame_time_avg_ms: f64,
+    pub frame_time_p95_ms: f64,
+    pub frame_time_max_ms: f64,
+    /// GPU time of one frame (last 100 timed frames), None without timestamp queries
+    #[serde(default)]
+    pub gpu_time_avg_ms: Option<f64>,
+    pub transitions: u64,
+    /// Average over the last 50 transitions
+    pub transition_avg_ms: f64,
+    /// 0.0-1.0
+    pub texture_pool_hit_rate: f64,
+    pub errors: u64,
+    /// Images scaled down before upload (`max-image-scale`)
+    #[serde(default)]
+    pub images_downscaled: u64,
+    /// Texture memory those downscales saved, mipmaps included
+    #[serde(default)]
+    pub downscale_saved_mb: f64,
+}
+
+/// GPU time of one transition shader's pass per frame, over its last 50 timed frames
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TransitionGpuReport {
+    pub transition: String,
+    pub samples: usize,
+    pub avg_ms: f64,
+    pub p95_ms: f64,
+    pub max_ms: f64,
+}
+
+/// Result of a cache garbage collection run
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CacheReport {
+    pub files_removed: u64,
+    pub bytes_reclaimed: u64,
+    /// Cache size after the run, in bytes
+    pub size: u64,
+    /// Limit that was enforced, if any
+    pub limit: Option<u64>,
+}
+
+/// Result of a `[fetch]` pull
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FetchReport {
+    /// "wallhaven" or "unsplash"
+    pub provider: String,
+    pub playlist: String,
+    /// New wallpapers downloaded
+    pub added: usize,
+    /// Old ones pruned by age or size
+    pub removed: usize,
+    /// Wallpapers in the playlist now
+    pub total: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PickPreview {
+    /// Outputs fed by this queue
+    pub outputs: Vec<String>,
+    pub strategy: String,
+    pub pool_size: usize,
+    pub candidates: Vec<PickCandidate>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PickCandidate {
+    pub path: String,
+    /// Loveit weight (count, recency and love multiplier); None for sequential strategies
+    pub weight: Option<f64>,
+    /// Chance (0.0-1.0) that the next pick lands on this file; None for sequential
+    /// strategies, which pick in order
+    pub probability: Option<f64>,
+    /// Why the pick would be passed over (load backoff, avoid-twins), if it would be
+    pub skipped: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionInfo {
+    pub name: String,
+    /// Unix timestamp of the save
+    pub saved_at: u64,
+    /// Outputs the session has a setup for
+    pub outputs: Vec<String>,
+}
+
+/// A stored playlist, as `kldctl playlist show` prints it
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PlaylistInfo {
+    pub name: String,
+    pub paths: Vec<String>,
+    /// When the config loads it automatically, e.g. "mon-fri 09:00-17:00"; empty if
+    /// it has no `[playlist.NAME]` rule
+    pub schedule: Vec<String>,
+    /// Outputs the schedule is limited to (empty: every output)
+    pub schedule_outputs: Vec<String>,
+    /// Outputs whose queue has it loaded right now
+    pub loaded_on: Vec<String>,
+}
+
+/// Outcome of `kldctl playlist import`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PlaylistImportReport {
+    pub name: String,
+    /// Files the import added (globs count every file they matched)
+    pub added: usize,
+    /// Files in the playlist afterwards
+    pub total: usize,
+    /// Entries left out: missing or unsupported files and globs matching nothing
+    pub skipped: Vec<String>,
+}
+
+/// Pushed to `subscribe` clients, e.g. `{"event":"wallpaper_changed","output":"DP-1",...}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// Also sent for each mirror of the output
+    WallpaperChanged {
+        output: String,
+        path: String,
+        /// "image", "video", "animated" or "shader"
+        #[serde(rename = "type")]
+        content_type: String,
+    },
+    /// Not sent for switches without a transition
+    TransitionStarted {
+        output: String,
+        transition: String,
+        duration_ms: u64,
+    },
+    /// The new wallpaper is fully on screen
+    TransitionCompleted {
+        output: String,
+    },
+    OutputAdded {
+        output: String,
+    },
+    OutputRemoved {
+        output: String,
+    },
+    /// Cycling paused; `output` is None when everything paused
+    Paused {
+        output: Option<String>,
+    },
+    Resumed {
+        output: Option<String>,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TagInfo {
+    pub name: String,
+    pub files: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QuarantineEntry {
+    pub path: String,
+    /// Failed loads before it was quarantined
+    pub failures: u32,
+    /// Error of the last failed load
+    pub error: String,
+    /// Unix timestamp of the quarantine
+    pub since: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OutputResult {
+    pub output: String,
+    pub success: bool,
+    pub message: String,
+    /// Kind of failure, set when `success` is false
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<ErrorCode>,
+}
+
+impl OutputResult {
+    pub fn ok(output: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            output: output.into(),
+            success: true,
+            message: message.into(),
+            code: None,
+        }
+    }
+
+    /// A generic failure (`ErrorCode::Failed`); see `with_code`
+    pub fn err(output: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            output: output.into(),
+            success: false,
+            message: message.into(),
+            code: Some(ErrorCode::Failed),
+        }
+    }
+
+    pub fn with_code(mut self, code: ErrorCode) -> Self {
+        self.code = Some(code);
+        self
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -1421,6 +2105,70 @@ pub struct OutputInfo {
     pub width: u32,
     pub height: u32,
     pub current_wallpaper: Option<String>,
+    /// Missing for outputs without a renderer (--headless)
+    #[serde(default)]
+    pub transition: Option<TransitionStatus>,
+    /// Why the output shows its fallback instead of content (path missing, nothing
+    /// playable); None while it plays normally
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// What an output is playing and for how long, as `kldctl status` shows it
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OutputStatus {
+    pub name: String,
+    /// File on screen, None before the first pick
+    pub path: Option<String>,
+    /// "image", "video", "animated" or "shader"
+    pub content_type: Option<String>,
+    /// How long the current wallpaper has been on screen
+    pub elapsed_ms: Option<u64>,
+    /// Time left until the next switch; None for static outputs and while paused,
+    /// since resuming restarts the timer
+    pub remaining_ms: Option<u64>,
+    /// Whether cycling is paused on this output
+    pub paused: bool,
+    /// Missing for outputs without a renderer (--headless)
+    #[serde(default)]
+    pub transition: Option<TransitionStatus>,
+    /// Files the output's queue picks from (shared with its group or other outputs
+    /// when it has no queue of its own)
+    pub queue_size: usize,
+    pub playlist: Option<String>,
+    /// Playback position of the video on screen
+    #[serde(default)]
+    pub video_position_ms: Option<u64>,
+    #[serde(default)]
+    pub video_duration_ms: Option<u64>,
+    /// Why the output shows its fallback instead of content, if it does
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// Where an output is in switching wallpapers, for tools that animate alongside
+/// the transition (e.g. a bar fading its background in sync)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransitionStatus {
+    pub state: TransitionState,
+    /// Transition that is running, or will run once the new content has loaded
+    pub name: String,
+    /// 0.0-1.0; 1.0 while idle
+    pub progress: f32,
+    pub duration_ms: u64,
+    /// Start of the running transition in milliseconds since the Unix epoch, so
+    /// clients can interpolate on their own instead of polling every frame
+    pub started_at_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TransitionState {
+    /// Nothing is changing
+    Idle,
+    /// New content is being decoded; the transition starts once it is ready
+    Loading,
+    Running,
 }
 
 fn default_wipe_direction() -> [f32; 2] {
@@ -1606,3 +2354,47 @@ fn df_1_6() -> f32 {
 fn df_0_03() -> f32 {
     0.03
 }
+
+/// Tag of the graphical session this process runs in, from `WAYLAND_DISPLAY` (checked
+/// first, like the daemon's backend choice) or `DISPLAY`: "wayland-1", "x11-0", ...
+/// The daemon and kldctl derive their socket from it, so two sessions (or a nested
+/// compositor) each talk to their own daemon. None outside a graphical session.
+pub fn session_tag() -> Option<String> {
+    let tag = if let Some(wayland) = std::env::var_os("WAYLAND_DISPLAY") {
+        // May be an absolute socket path; only its name identifies the session
+        let wayland = std::path::PathBuf::from(wayland);
+        wayland.file_name()?.to_string_lossy().into_owned()
+    } else {
+        let display = std::env::var("DISPLAY").ok()?;
+        // ":0", ":0.0" and ":0.1" are the same session: one daemon covers every screen
+        let display = match display.rsplit_once('.') {
+            Some((display, screen))
+                if display.contains(':') && screen.bytes().all(|b| b.is_ascii_digit()) =>
+            {
+                display
+            }
+            _ => &display,
+        };
+        format!("x11-{}", display.trim_start_matches(':'))
+    };
+    let tag: String = tag
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    (!tag.is_empty()).then_some(tag)
+}
+
+/// Name of a per-session runtime file: "kaleidux-wayland-1.sock", or
+/// "kaleidux.sock" outside a graphical session
+pub fn runtime_file_name(extension: &str) -> String {
+    match session_tag() {
+        Some(tag) => format!("kaleidux-{}.{}", tag, extension),
+        None => format!("kaleidux.{}", extension),
+    }
+}
@@ -0,0 +1,140 @@
+use anyhow::{bail, Context, Result};
+use image::codecs::gif::GifDecoder;
+use image::codecs::png::PngDecoder;
+use image::codecs::webp::WebPDecoder;
+use image::{AnimationDecoder, ImageDecoder, ImageFormat};
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Frames asking for less than this are shown for `DEFAULT_DELAY` instead, like browsers
+/// do; many GIFs are made with a delay of 0 and rely on it
+const MIN_DELAY: Duration = Duration::from_millis(20);
+const DEFAULT_DELAY: Duration = Duration::from_millis(100);
+
+fn open(path: &Path) -> Result<(ImageFormat, BufReader<File>)> {
+    let reader = image::ImageReader::open(path)
+        .with_context(|| format!("Failed to open {:?}", path))?
+        .with_guessed_format()?;
+    let Some(format) = reader.format() else {
+        bail!("Unknown image format: {:?}", path);
+    };
+    Ok((format, reader.into_inner()))
+}
+
+/// Whether `path` is a GIF, APNG or WebP with more than one frame. Single-frame files of
+/// these formats stay plain images.
+pub fn is_animated(path: &Path) -> bool {
+    let animated = || -> Result<bool> {
+        Ok(match open(path)? {
+            (ImageFormat::Gif, reader) => gif_frames(reader)? > 1,
+            (ImageFormat::Png, reader) => PngDecoder::new(reader)?.is_apng()?,
+            (ImageFormat::WebP, reader) => WebPDecoder::new(reader)?.has_animation(),
+            _ => false,
+        })
+    };
+    animated().unwrap_or(false)
+}
+
+/// Counts the frames of a GIF up to two by walking its blocks, without decompressing
+/// any. GIFs don't say up front whether they are animated, and this runs for every GIF
+/// a queue looks at.
+fn gif_frames(mut reader: BufReader<File>) -> Result<usize> {
+    fn byte(reader: &mut BufReader<File>) -> Result<u8> {
+        let mut byte = [0u8; 1];
+        reader.read_exact(&mut byte)?;
+        Ok(byte[0])
+    }
+    // Color table of 3 * 2^(size + 1) bytes, if the flags say there is one
+    fn skip_color_table(reader: &mut BufReader<File>, flags: u8) -> Result<()> {
+        if flags & 0x80 != 0 {
+            reader.seek_relative(3 << ((flags & 0x07) + 1))?;
+        }
+        Ok(())
+    }
+    // Sub-blocks: a length byte and that many bytes, until a length of 0
+    fn skip_sub_blocks(reader: &mut BufReader<File>) -> Result<()> {
+        loop {
+            match byte(reader)? {
+                0 => return Ok(()),
+                len => reader.seek_relative(len as i64)?,
+            }
+        }
+    }
+
+    let mut header = [0u8; 13];
+    reader.read_exact(&mut header)?;
+    skip_color_table(&mut reader, header[10])?;
+    let mut frames = 0;
+    while frames < 2 {
+        match byte(&mut reader)? {
+            // Extension: label, then sub-blocks
+            0x21 => {
+                byte(&mut reader)?;
+                skip_sub_blocks(&mut reader)?;
+            }
+            // Image descriptor, local color table, LZW code size, then sub-blocks
+            0x2C => {
+                frames += 1;
+                let mut descriptor = [0u8; 9];
+                reader.read_exact(&mut descriptor)?;
+                skip_color_table(&mut reader, descriptor[8])?;
+                byte(&mut reader)?;
+                skip_sub_blocks(&mut reader)?;
+            }
+            // Trailer
+            0x3B => break,
+            other => bail!("Unexpected GIF block 0x{:02x}", other),
+        }
+    }
+    Ok(frames)
+}
+
+/// An animated GIF, APNG or WebP. Frames come out composed onto the full canvas, so
+/// every one has the size of the first.
+pub struct Animation {
+    path: PathBuf,
+    width: u32,
+    height: u32,
+}
+
+impl Animation {
+    pub fn open(path: &Path) -> Result<Self> {
+        let (width, height) = match open(path)? {
+            (ImageFormat::Gif, reader) => GifDecoder::new(reader)?.dimensions(),
+            (ImageFormat::Png, reader) => PngDecoder::new(reader)?.dimensions(),
+            (ImageFormat::WebP, reader) => WebPDecoder::new(reader)?.dimensions(),
+            (format, _) => bail!("{:?} is a {:?}, which can't be animated", path, format),
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            width,
+            height,
+        })
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// One pass over the frames, decoded as they are taken
+    pub fn frames(&self) -> Result<image::Frames<'static>> {
+        Ok(match open(&self.path)? {
+            (ImageFormat::Gif, reader) => GifDecoder::new(reader)?.into_frames(),
+            (ImageFormat::Png, reader) => PngDecoder::new(reader)?.apng()?.into_frames(),
+            (ImageFormat::WebP, reader) => WebPDecoder::new(reader)?.into_frames(),
+            (format, _) => bail!("{:?} is a {:?}, which can't be animated", self.path, format),
+        })
+    }
+}
+
+/// How long `frame` stays on screen
+pub fn delay(frame: &image::Frame) -> Duration {
+    let delay = Duration::from(frame.delay());
+    if delay < MIN_DELAY {
+        DEFAULT_DELAY
+    } else {
+        delay
+    }
+}
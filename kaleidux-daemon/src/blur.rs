@@ -0,0 +1,377 @@
+use crate::renderer::WgpuContext;
+use anyhow::Result;
+use std::sync::Arc;
+
+/// Format of every blur texture (same as the content textures it reads from)
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+/// More passes than this shrink a 4K image below a few pixels; the result stops changing
+pub const MAX_ITERATIONS: u32 = 8;
+
+/// Reusable dual Kawase blur. Blurred variants of the wallpaper (lock screens,
+/// `kldctl snapshot --blur`) are generated on the GPU from the texture already
+/// uploaded, instead of decoding and blurring the file again on the CPU.
+pub struct BlurPass {
+    bind_group_layout: wgpu::BindGroupLayout,
+    down: wgpu::RenderPipeline,
+    up: wgpu::RenderPipeline,
+    sampler: wgpu::Sampler,
+}
+
+impl BlurPass {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Blur Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shaders/blur.wgsl").into()),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Blur Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Blur Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = |label: &str, entry_point: &str| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: Some("vs_main"),
+                    compilation_options: Default::default(),
+                    buffers: &[],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: Some(entry_point),
+                    compilation_options: Default::default(),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: FORMAT,
+                        blend: Some(wgpu::BlendState::REPLACE),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+                cache: None,
+            })
+        };
+
+        // Clamp so samples past the edge repeat the border instead of wrapping around
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Blur Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        Self {
+            down: pipeline("Blur Downsample Pipeline", "fs_down"),
+            up: pipeline("Blur Upsample Pipeline", "fs_up"),
+            bind_group_layout,
+            sampler,
+        }
+    }
+
+    /// Blurs the first mip level of `source` into a new texture of the same size that
+    /// can be sampled, rendered to and copied out. Each of the `iterations` (capped at
+    /// `MAX_ITERATIONS` and by the image size) roughly doubles the radius; 0 returns a
+    /// plain copy.
+    pub fn run(&self, ctx: &WgpuContext, source: &wgpu::Texture, iterations: u32) -> wgpu::Texture {
+        let (width, height) = (source.width(), source.height());
+        let iterations = iterations
+            .min(MAX_ITERATIONS)
+            .min(width.min(height).max(1).ilog2());
+
+        // Level 0 is full size, each further level half the previous one
+        let levels: Vec<wgpu::Texture> = (0..=iterations)
+            .map(|level| {
+                ctx.device.create_texture(&wgpu::TextureDescriptor {
+                    label: Some("Blur Texture"),
+                    size: wgpu::Extent3d {
+                        width: (width >> level).max(1),
+                        height: (height >> level).max(1),
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: FORMAT,
+                    usage: wgpu::TextureUsages::TEXTURE_BINDING
+                        | wgpu::TextureUsages::RENDER_ATTACHMENT
+                        | wgpu::TextureUsages::COPY_SRC,
+                    view_formats: &[],
+                })
+            })
+            .collect();
+        let view = |texture: &wgpu::Texture| {
+            texture.create_view(&wgpu::TextureViewDescriptor {
+                format: Some(FORMAT),
+                base_mip_level: 0,
+                mip_level_count: Some(1),
+                ..Default::default()
+            })
+        };
+
+        let mut encoder = ctx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Blur Encoder"),
+            });
+
+        // Copy into level 0 (the sample offset is zero at equal size), then walk down
+        // the levels and back up into level 0
+        let source_view = view(source);
+        self.pass(ctx, &mut encoder, &self.down, &source_view, &levels[0], 0.0);
+        for level in 1..levels.len() {
+            let src = view(&levels[level - 1]);
+            self.pass(ctx, &mut encoder, &self.down, &src, &levels[level], 1.0);
+        }
+        for level in (0..levels.len() - 1).rev() {
+            let src = view(&levels[level + 1]);
+            self.pass(ctx, &mut encoder, &self.up, &src, &levels[level], 1.0);
+        }
+        ctx.queue.submit(Some(encoder.finish()));
+
+        levels.into_iter().next().expect("level 0 always exists")
+    }
+
+    fn pass(
+        &self,
+        ctx: &WgpuContext,
+        encoder: &mut wgpu::CommandEncoder,
+        pipeline: &wgpu::RenderPipeline,
+        source: &wgpu::TextureView,
+        target: &wgpu::Texture,
+        offset: f32,
+    ) {
+        // Each pass gets its own buffer: queue writes all land before the submit
+        let half_pixel = [
+            offset * 0.5 / target.width() as f32,
+            offset * 0.5 / target.height() as f32,
+            0.0,
+            0.0,
+        ];
+        let uniforms = ctx.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Blur Uniforms"),
+            size: std::mem::size_of_val(&half_pixel) as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        ctx.queue
+            .write_buffer(&uniforms, 0, bytemuck::cast_slice(&half_pixel));
+
+        let bind_group = ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Blur Bind Group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(source),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: uniforms.as_entire_binding(),
+                },
+            ],
+        });
+
+        let target_view = target.create_view(&wgpu::TextureViewDescriptor::default());
+        let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Blur Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &target_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        rpass.set_pipeline(pipeline);
+        rpass.set_bind_group(0, &bind_group, &[]);
+        rpass.draw(0..3, 0..1);
+    }
+}
+
+/// Copies an RGBA8 texture (made with COPY_SRC) back to the CPU. Blocks until the GPU
+/// has finished the copy.
+pub fn read_texture(ctx: &Arc<WgpuContext>, texture: &wgpu::Texture) -> Result<image::RgbaImage> {
+    Readback::start(ctx, texture).wait()
+}
+
+/// An image on its way back from the GPU; `is_ready` tells without blocking when
+/// `wait` would return at once
+pub struct Readback(ReadbackState);
+
+enum ReadbackState {
+    Done(image::RgbaImage),
+    Copying {
+        ctx: Arc<WgpuContext>,
+        buffer: wgpu::Buffer,
+        width: u32,
+        height: u32,
+        padded_row_bytes: u32,
+        mapped: std::sync::mpsc::Receiver<std::result::Result<(), wgpu::BufferAsyncError>>,
+        /// Outcome of the mapping, once it arrived
+        result: Option<std::result::Result<(), wgpu::BufferAsyncError>>,
+    },
+}
+
+impl Readback {
+    /// An image that needs no copy, e.g. the solid color of an output without content
+    pub fn done(image: image::RgbaImage) -> Self {
+        Self(ReadbackState::Done(image))
+    }
+
+    /// Submits the copy of an RGBA8 texture (made with COPY_SRC) to a mappable buffer
+    pub fn start(ctx: &Arc<WgpuContext>, texture: &wgpu::Texture) -> Self {
+        let (width, height) = (texture.width(), texture.height());
+        // Rows of a texture-to-buffer copy have to be padded to 256 bytes
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_row_bytes = (width * 4).div_ceil(align) * align;
+
+        let buffer = ctx.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Readback Buffer"),
+            size: padded_row_bytes as u64 * height as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = ctx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Readback Encoder"),
+            });
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row_bytes),
+                    rows_per_image: Some(height),
+                },
+            },
+            texture.size(),
+        );
+        ctx.queue.submit(Some(encoder.finish()));
+
+        let (tx, mapped) = std::sync::mpsc::channel();
+        buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                let _ = tx.send(result);
+            });
+        Self(ReadbackState::Copying {
+            ctx: ctx.clone(),
+            buffer,
+            width,
+            height,
+            padded_row_bytes,
+            mapped,
+            result: None,
+        })
+    }
+
+    /// Whether the GPU has finished the copy (or failed it)
+    pub fn is_ready(&mut self) -> bool {
+        let ReadbackState::Copying {
+            ctx,
+            mapped,
+            result,
+            ..
+        } = &mut self.0
+        else {
+            return true;
+        };
+        if result.is_none() {
+            ctx.device.poll(wgpu::Maintain::Poll);
+            match mapped.try_recv() {
+                Ok(mapping) => *result = Some(mapping),
+                Err(std::sync::mpsc::TryRecvError::Empty) => return false,
+                // The callback was dropped without running; `wait` reports it
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => {}
+            }
+        }
+        true
+    }
+
+    /// The image, blocking until the GPU has finished the copy
+    pub fn wait(self) -> Result<image::RgbaImage> {
+        let (ctx, buffer, width, height, padded_row_bytes, mapped, result) = match self.0 {
+            ReadbackState::Done(image) => return Ok(image),
+            ReadbackState::Copying {
+                ctx,
+                buffer,
+                width,
+                height,
+                padded_row_bytes,
+                mapped,
+                result,
+            } => (ctx, buffer, width, height, padded_row_bytes, mapped, result),
+        };
+        match result {
+            Some(mapping) => mapping?,
+            None => {
+                ctx.device.poll(wgpu::Maintain::Wait);
+                mapped.recv()??;
+            }
+        }
+
+        let row_bytes = width * 4;
+        let mut pixels = Vec::with_capacity((row_bytes * height) as usize);
+        {
+            let data = buffer.slice(..).get_mapped_range();
+            for row in data.chunks(padded_row_bytes as usize) {
+                pixels.extend_from_slice(&row[..row_bytes as usize]);
+            }
+        }
+        buffer.unmap();
+
+        image::RgbaImage::from_raw(width, height, pixels)
+            .ok_or_else(|| anyhow::anyhow!("Readback of {}x{} texture was short", width, height))
+    }
+}
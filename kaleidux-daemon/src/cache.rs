@@ -1,4 +1,5 @@
 use anyhow::{Context, Result};
+use notify::event::{ModifyKind, RenameMode};
 use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
 use redb::{Database, ReadableTable, TableDefinition};
 use serde::{Deserialize, Serialize};
@@ -8,46 +9,149 @@ use std::time::UNIX_EPOCH;
 use tokio::sync::mpsc;
 
 // Table definitions for redb
-const FILE_CACHE_TABLE: TableDefinition<&[u8], &[u8]> = TableDefinition::new("file_cache");
+/// v2 tells animated GIF, APNG and WebP files apart from images
+const FILE_CACHE_TABLE: TableDefinition<&[u8], &[u8]> = TableDefinition::new("file_cache_v2");
+/// Index from before v2, dropped at startup; discovery fills the new one again
+const LEGACY_FILE_CACHE_TABLE: TableDefinition<&[u8], &[u8]> = TableDefinition::new("file_cache");
 const FILE_STATS_TABLE: TableDefinition<&[u8], &[u8]> = TableDefinition::new("file_stats");
 const PLAYLISTS_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("playlists");
 const BLACKLIST_TABLE: TableDefinition<&[u8], bool> = TableDefinition::new("blacklist");
+const QUARANTINE_TABLE: TableDefinition<&[u8], &[u8]> = TableDefinition::new("quarantine");
+const TAGS_TABLE: TableDefinition<&[u8], &[u8]> = TableDefinition::new("tags");
 const HISTORY_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("history");
+const LAST_WALLPAPER_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("last_wallpaper");
+const KIOSK_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("kiosk");
+const TONE_TABLE: TableDefinition<&[u8], &[u8]> = TableDefinition::new("tones");
+const SESSIONS_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("sessions");
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileMetadata {
     pub mtime: u64, // Unix timestamp
     pub size: u64,
-    pub content_type: u8,   // 0 = Image, 1 = Video
+    pub content_type: u8,   // 0 = Image, 1 = Video, 2 = Animated
     pub discovered_at: u64, // Unix timestamp
 }
 
 pub struct FileCache {
     db: Database,
+    /// flock on `<database>.lock`, taken before the first open and held for the
+    /// daemon's lifetime. redb's own lock ends whenever the database is closed for a
+    /// backup or restore; this one keeps other processes out in between.
+    _lock: std::fs::File,
 }
 
 impl FileCache {
-    pub fn new() -> Result<Self> {
+    /// Opens the database that holds the file index, loves, playlists, blacklist and
+    /// history. redb commits are atomic, so a crash never leaves a half-written
+    /// transaction behind; this additionally keeps up to `backups` daily copies and
+    /// falls back to the newest readable one if the database itself is damaged.
+    pub fn new(backups: usize) -> Result<Self> {
         let cache_dir = dirs::cache_dir()
             .context("Failed to get cache directory")?
             .join("kaleidux");
         std::fs::create_dir_all(&cache_dir)?;
 
-        let db_path = cache_dir.join("cache.redb");
-        let db = Database::create(&db_path)?;
+        let (db_path, lock) = lock_shared_or_own(&cache_dir)?;
+        if !db_path.exists() {
+            return Self::init(Database::create(&db_path)?, &lock);
+        }
+
+        match Self::open_valid(&db_path, &lock) {
+            Ok(cache) => {
+                if backups == 0 {
+                    return Ok(cache);
+                }
+                // Copy only while closed so the backup is a consistent file; `lock`
+                // stays held, so nothing else opens it in the meantime
+                drop(cache);
+                if let Err(e) = rotate_backups(&db_path, backups) {
+                    tracing::warn!("[CACHE] Failed to back up {:?}: {}", db_path, e);
+                }
+                Self::init(Database::create(&db_path)?, &lock)
+            }
+            Err(e) if !is_damaged(&e) => Err(e.context(format!("Failed to open {:?}", db_path))),
+            Err(e) => {
+                let corrupt = db_path.with_extension("redb.corrupt");
+                tracing::warn!(
+                    "[CACHE] {:?} is unreadable ({:#}), moving it to {:?}",
+                    db_path,
+                    e,
+                    corrupt
+                );
+                std::fs::rename(&db_path, &corrupt)?;
+
+                for n in 1..=backups {
+                    let backup = backup_path(&db_path, n);
+                    if !backup.exists() {
+                        continue;
+                    }
+                    std::fs::copy(&backup, &db_path)?;
+                    match Self::open_valid(&db_path, &lock) {
+                        Ok(cache) => {
+                            tracing::warn!(
+                                "[CACHE] Restored loves, playlists and history from {:?}; \
+                                 changes made since that backup are lost",
+                                backup
+                            );
+                            return Ok(cache);
+                        }
+                        Err(e) => {
+                            tracing::warn!(
+                                "[CACHE] Backup {:?} is unreadable too: {:#}",
+                                backup,
+                                e
+                            );
+                            let _ = std::fs::remove_file(&db_path);
+                        }
+                    }
+                }
+
+                tracing::error!(
+                    "[CACHE] No readable backup found, starting with empty stats \
+                     (the damaged database is kept at {:?})",
+                    corrupt
+                );
+                Self::init(Database::create(&db_path)?, &lock)
+            }
+        }
+    }
 
-        // Initialize tables
+    /// Creates any missing tables
+    fn init(db: Database, lock: &std::fs::File) -> Result<Self> {
         let write_txn = db.begin_write()?;
         {
             let _ = write_txn.open_table(FILE_CACHE_TABLE)?;
             let _ = write_txn.open_table(FILE_STATS_TABLE)?;
             let _ = write_txn.open_table(PLAYLISTS_TABLE)?;
             let _ = write_txn.open_table(BLACKLIST_TABLE)?;
+            let _ = write_txn.open_table(QUARANTINE_TABLE)?;
+            let _ = write_txn.open_table(TAGS_TABLE)?;
             let _ = write_txn.open_table(HISTORY_TABLE)?;
+            let _ = write_txn.open_table(LAST_WALLPAPER_TABLE)?;
+            let _ = write_txn.open_table(KIOSK_TABLE)?;
+            let _ = write_txn.open_table(TONE_TABLE)?;
+            let _ = write_txn.open_table(SESSIONS_TABLE)?;
+            write_txn.delete_table(LEGACY_FILE_CACHE_TABLE)?;
         }
         write_txn.commit()?;
 
-        Ok(Self { db })
+        // A duplicate shares the flock, which lasts until the last descriptor closes
+        Ok(Self {
+            db,
+            _lock: lock.try_clone()?,
+        })
+    }
+
+    /// Opens an existing database and reads back everything the queues load at startup,
+    /// so damage is detected here rather than as silently empty stats later
+    fn open_valid(path: &Path, lock: &std::fs::File) -> Result<Self> {
+        let cache = Self::init(Database::open(path)?, lock)?;
+        cache.get_all_file_stats()?;
+        cache.get_all_playlists()?;
+        cache.get_all_blacklisted()?;
+        cache.get_all_quarantined()?;
+        cache.get_all_tags()?;
+        Ok(cache)
     }
 
     pub fn get_file_metadata(&self, path: &Path) -> Result<Option<FileMetadata>> {
@@ -103,20 +207,6 @@ impl FileCache {
         }
     }
 
-    #[allow(dead_code)]
-    pub fn set_file_stats(&self, path: &Path, stats: &crate::queue::FileStats) -> Result<()> {
-        let write_txn = self.db.begin_write()?;
-        {
-            let mut table = write_txn.open_table(FILE_STATS_TABLE)?;
-            let path_str = path.to_string_lossy();
-            let path_bytes = path_str.as_bytes();
-            let data = bincode::serialize(stats)?;
-            table.insert(path_bytes, data.as_slice())?;
-        }
-        write_txn.commit()?;
-        Ok(())
-    }
-
     pub fn batch_set_file_stats(
         &self,
         updates: &[(PathBuf, crate::queue::FileStats)],
@@ -214,16 +304,19 @@ impl FileCache {
         Ok(table.get(path_bytes)?.is_some())
     }
 
-    pub fn set_blacklisted(&self, path: &Path, blacklisted: bool) -> Result<()> {
+    /// Adds or removes all of `paths` in one write transaction
+    pub fn set_blacklisted(&self, paths: &[PathBuf], blacklisted: bool) -> Result<()> {
         let write_txn = self.db.begin_write()?;
         {
             let mut table = write_txn.open_table(BLACKLIST_TABLE)?;
-            let path_str = path.to_string_lossy();
-            let path_bytes = path_str.as_bytes();
-            if blacklisted {
-                table.insert(path_bytes, true)?;
-            } else {
-                table.remove(path_bytes)?;
+            for path in paths {
+                let path_str = path.to_string_lossy();
+                let path_bytes = path_str.as_bytes();
+                if blacklisted {
+                    table.insert(path_bytes, true)?;
+                } else {
+                    table.remove(path_bytes)?;
+                }
             }
         }
         write_txn.commit()?;
@@ -244,6 +337,87 @@ impl FileCache {
         Ok(blacklist)
     }
 
+    /// Quarantines `path`, or releases it with `None`
+    pub fn set_quarantined(
+        &self,
+        path: &Path,
+        record: Option<&crate::queue::QuarantineRecord>,
+    ) -> Result<()> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(QUARANTINE_TABLE)?;
+            let path_str = path.to_string_lossy();
+            let path_bytes = path_str.as_bytes();
+            match record {
+                Some(record) => {
+                    let data = bincode::serialize(record)?;
+                    table.insert(path_bytes, data.as_slice())?;
+                }
+                None => {
+                    table.remove(path_bytes)?;
+                }
+            }
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    pub fn get_all_quarantined(
+        &self,
+    ) -> Result<std::collections::HashMap<PathBuf, crate::queue::QuarantineRecord>> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(QUARANTINE_TABLE)?;
+        let mut quarantine = std::collections::HashMap::new();
+
+        for item in table.iter()? {
+            let (key, value) = item?;
+            let path = PathBuf::from(String::from_utf8_lossy(key.value()).to_string());
+            let record: crate::queue::QuarantineRecord = bincode::deserialize(value.value())?;
+            quarantine.insert(path, record);
+        }
+
+        Ok(quarantine)
+    }
+
+    /// Sets the tags of each file in one write transaction; an empty set removes the entry
+    pub fn set_tags(
+        &self,
+        entries: &[(PathBuf, std::collections::BTreeSet<String>)],
+    ) -> Result<()> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(TAGS_TABLE)?;
+            for (path, tags) in entries {
+                let path_str = path.to_string_lossy();
+                let path_bytes = path_str.as_bytes();
+                if tags.is_empty() {
+                    table.remove(path_bytes)?;
+                } else {
+                    let data = bincode::serialize(tags)?;
+                    table.insert(path_bytes, data.as_slice())?;
+                }
+            }
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    pub fn get_all_tags(
+        &self,
+    ) -> Result<std::collections::HashMap<PathBuf, std::collections::BTreeSet<String>>> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(TAGS_TABLE)?;
+        let mut tags = std::collections::HashMap::new();
+
+        for item in table.iter()? {
+            let (key, value) = item?;
+            let path = PathBuf::from(String::from_utf8_lossy(key.value()).to_string());
+            tags.insert(path, bincode::deserialize(value.value())?);
+        }
+
+        Ok(tags)
+    }
+
     pub fn set_history(&self, output_name: &str, history: &[PathBuf]) -> Result<()> {
         let write_txn = self.db.begin_write()?;
         {
@@ -270,6 +444,137 @@ impl FileCache {
         }
     }
 
+    /// Remembers what outputs are showing so the next daemon start can restore it
+    pub fn set_last_wallpapers(
+        &self,
+        wallpapers: &[(String, (PathBuf, crate::queue::ContentType))],
+    ) -> Result<()> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(LAST_WALLPAPER_TABLE)?;
+            for (output_name, (path, content_type)) in wallpapers {
+                let kind = content_type.code();
+                let data = bincode::serialize(&(path.to_string_lossy().to_string(), kind))?;
+                table.insert(output_name.as_str(), data.as_slice())?;
+            }
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    pub fn get_last_wallpaper(
+        &self,
+        output_name: &str,
+    ) -> Result<Option<(PathBuf, crate::queue::ContentType)>> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(LAST_WALLPAPER_TABLE)?;
+        if let Some(data) = table.get(output_name)? {
+            let (path, kind): (String, u8) = bincode::deserialize(data.value())?;
+            let content_type = crate::queue::ContentType::from_code(kind)
+                .unwrap_or(crate::queue::ContentType::Image);
+            Ok(Some((PathBuf::from(path), content_type)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Saves the kiosk position: item index and path, and when (Unix milliseconds) the
+    /// item went on screen
+    pub fn set_kiosk_state(&self, index: usize, path: &Path, started_ms: u64) -> Result<()> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(KIOSK_TABLE)?;
+            let data = bincode::serialize(&(
+                index as u64,
+                path.to_string_lossy().to_string(),
+                started_ms,
+            ))?;
+            table.insert("state", data.as_slice())?;
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    pub fn get_kiosk_state(&self) -> Result<Option<(usize, PathBuf, u64)>> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(KIOSK_TABLE)?;
+        if let Some(data) = table.get("state")? {
+            let (index, path, started_ms): (u64, String, u64) = bincode::deserialize(data.value())?;
+            Ok(Some((index as usize, PathBuf::from(path), started_ms)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Measured tone of the image at `path`, if it hasn't changed on disk since
+    pub fn get_tone(&self, path: &Path) -> Result<Option<crate::tone::Tone>> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(TONE_TABLE)?;
+        let path_str = path.to_string_lossy();
+        let Some(data) = table.get(path_str.as_bytes())? else {
+            return Ok(None);
+        };
+        let (mtime, tone): (u64, crate::tone::Tone) = bincode::deserialize(data.value())?;
+        Ok((file_mtime(path)? == mtime).then_some(tone))
+    }
+
+    pub fn set_tone(&self, path: &Path, tone: &crate::tone::Tone) -> Result<()> {
+        let data = bincode::serialize(&(file_mtime(path)?, tone))?;
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(TONE_TABLE)?;
+            let path_str = path.to_string_lossy();
+            table.insert(path_str.as_bytes(), data.as_slice())?;
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    /// Stored as JSON: sessions hold transitions, which bincode can't decode
+    pub fn set_session(&self, name: &str, session: &crate::session::Session) -> Result<()> {
+        let data = serde_json::to_vec(session)?;
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(SESSIONS_TABLE)?;
+            table.insert(name, data.as_slice())?;
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    pub fn get_session(&self, name: &str) -> Result<Option<crate::session::Session>> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(SESSIONS_TABLE)?;
+        match table.get(name)? {
+            Some(data) => Ok(Some(serde_json::from_slice(data.value())?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Every saved session by name, in name order
+    pub fn get_all_sessions(&self) -> Result<Vec<(String, crate::session::Session)>> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(SESSIONS_TABLE)?;
+        let mut sessions = Vec::new();
+        for item in table.iter()? {
+            let (key, value) = item?;
+            let session = serde_json::from_slice(value.value())?;
+            sessions.push((key.value().to_string(), session));
+        }
+        Ok(sessions)
+    }
+
+    /// Whether there was a session `name` to delete
+    pub fn delete_session(&self, name: &str) -> Result<bool> {
+        let write_txn = self.db.begin_write()?;
+        let removed = write_txn
+            .open_table(SESSIONS_TABLE)?
+            .remove(name)?
+            .is_some();
+        write_txn.commit()?;
+        Ok(removed)
+    }
+
     #[allow(dead_code)]
     pub fn clear_file_cache(&self) -> Result<()> {
         // Clear cache atomically using a single write transaction
@@ -294,22 +599,362 @@ impl FileCache {
     pub fn invalidate_file(&self, path: &Path) -> Result<()> {
         let write_txn = self.db.begin_write()?;
         {
-            let mut table = write_txn.open_table(FILE_CACHE_TABLE)?;
             let path_str = path.to_string_lossy();
             let path_bytes = path_str.as_bytes();
-            table.remove(path_bytes)?;
+            write_txn.open_table(FILE_CACHE_TABLE)?.remove(path_bytes)?;
+            write_txn.open_table(TONE_TABLE)?.remove(path_bytes)?;
         }
         write_txn.commit()?;
         Ok(())
     }
 }
 
-/// Directory watcher for cache invalidation
+/// Locks the shared `cache.redb`. If the daemon of another graphical session holds it
+/// (redb lets only one process open the file), this session's daemon uses a database of
+/// its own instead, `cache-wayland-1.redb`, which starts empty.
+fn lock_shared_or_own(cache_dir: &Path) -> Result<(PathBuf, std::fs::File)> {
+    let shared = cache_dir.join("cache.redb");
+    if let Some(lock) = lock_database(&shared)? {
+        return Ok((shared, lock));
+    }
+    let Some(tag) = kaleidux_common::session_tag() else {
+        anyhow::bail!("{:?} is in use by another process", shared);
+    };
+    let own = cache_dir.join(format!("cache-{}.redb", tag));
+    let lock = lock_database(&own)?
+        .with_context(|| format!("{:?} and {:?} are in use by other processes", shared, own))?;
+    tracing::warn!(
+        "[CACHE] {:?} is in use by the daemon of another session; this one keeps its \
+         loves, playlists and history in {:?}",
+        shared,
+        own
+    );
+    Ok((own, lock))
+}
+
+/// Takes the exclusive flock on `<db_path>.lock`; None if another process holds it
+fn lock_database(db_path: &Path) -> Result<Option<std::fs::File>> {
+    use std::os::unix::io::AsRawFd;
+    let path = db_path.with_extension("redb.lock");
+    let file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&path)
+        .with_context(|| format!("Failed to open lock file {:?}", path))?;
+    let ret = unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) };
+    if ret != 0 {
+        let err = std::io::Error::last_os_error();
+        if err.kind() == std::io::ErrorKind::WouldBlock {
+            return Ok(None);
+        }
+        return Err(err).with_context(|| format!("Failed to lock {:?}", path));
+    }
+    Ok(Some(file))
+}
+
+/// Whether `name` is a stats database, one of its backups or its lock file
+fn is_database_file(name: &str) -> bool {
+    name.starts_with("cache") && name.contains(".redb")
+}
+
+fn file_mtime(path: &Path) -> Result<u64> {
+    Ok(std::fs::metadata(path)?
+        .modified()?
+        .duration_since(UNIX_EPOCH)?
+        .as_secs())
+}
+
+/// Whether opening the database failed because the file itself is damaged, as opposed
+/// to being locked or unreachable. Only then is it replaced by a backup.
+fn is_damaged(e: &anyhow::Error) -> bool {
+    use redb::StorageError;
+    let corrupted = |s: &StorageError| matches!(s, StorageError::Corrupted(_));
+    e.chain().any(|cause| {
+        if cause.is::<bincode::Error>() {
+            // A stored value that no longer decodes
+            return true;
+        }
+        if let Some(e) = cause.downcast_ref::<StorageError>() {
+            return corrupted(e);
+        }
+        if let Some(e) = cause.downcast_ref::<redb::DatabaseError>() {
+            return matches!(e, redb::DatabaseError::Storage(s) if corrupted(s));
+        }
+        if let Some(e) = cause.downcast_ref::<redb::TransactionError>() {
+            return matches!(e, redb::TransactionError::Storage(s) if corrupted(s));
+        }
+        if let Some(e) = cause.downcast_ref::<redb::TableError>() {
+            return matches!(e, redb::TableError::Storage(s) if corrupted(s));
+        }
+        if let Some(e) = cause.downcast_ref::<redb::CommitError>() {
+            return matches!(e, redb::CommitError::Storage(s) if corrupted(s));
+        }
+        matches!(
+            cause.downcast_ref::<redb::Error>(),
+            Some(redb::Error::Corrupted(_))
+        )
+    })
+}
+
+/// Rotated copy `n` of the database (1 is the newest)
+fn backup_path(db_path: &Path, n: usize) -> PathBuf {
+    db_path.with_extension(format!("redb.{}", n))
+}
+
+/// Backups are taken at most this often, so frequent restarts don't push the
+/// older, known-good copies out of the rotation
+const BACKUP_INTERVAL: std::time::Duration = std::time::Duration::from_secs(24 * 3600);
+
+/// Shifts `cache.redb.1..keep` down by one and copies the database to `cache.redb.1`
+fn rotate_backups(db_path: &Path, keep: usize) -> Result<()> {
+    let newest = backup_path(db_path, 1);
+    let fresh = std::fs::metadata(&newest)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.elapsed().ok())
+        .is_some_and(|age| age < BACKUP_INTERVAL);
+    if fresh {
+        return Ok(());
+    }
+
+    let _ = std::fs::remove_file(backup_path(db_path, keep));
+    for n in (1..keep).rev() {
+        let from = backup_path(db_path, n);
+        if from.exists() {
+            std::fs::rename(&from, backup_path(db_path, n + 1))?;
+        }
+    }
+    // Copy to a temp name first so a crash mid-copy never leaves a truncated backup
+    let tmp = db_path.with_extension("redb.tmp");
+    std::fs::copy(db_path, &tmp)?;
+    std::fs::rename(&tmp, &newest)?;
+    tracing::debug!("[CACHE] Backed up {:?} to {:?}", db_path, newest);
+    Ok(())
+}
+
+/// Startup snapshots: a downscaled JPEG of each output's current wallpaper, decoded
+/// in a few milliseconds on the next start while the full image is still loading.
+/// One directory per graphical session, whose outputs may share names with another's.
+fn snapshot_dir() -> Option<PathBuf> {
+    let dir = cache_root()?.join("snapshots");
+    Some(match kaleidux_common::session_tag() {
+        Some(tag) => dir.join(tag),
+        None => dir,
+    })
+}
+
+/// Snapshot file for `source` on `output_name`. The source path is hashed into the name
+/// so a snapshot left over from an older wallpaper is never shown.
+pub fn snapshot_path(output_name: &str, source: &Path) -> Option<PathBuf> {
+    use std::os::unix::ffi::OsStrExt;
+    let hash = stable_hash(source.as_os_str().as_bytes());
+    let output = output_name.replace('/', "_");
+    snapshot_dir().map(|d| d.join(format!("{}-{:016x}.jpg", output, hash)))
+}
+
+/// 64-bit FNV-1a of `data`, for names of files kept across restarts. Unlike std's
+/// `DefaultHasher` its output is fixed, so a toolchain upgrade never renames them.
+pub fn stable_hash(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// Scales `image` down to just cover a `width`x`height` output (never up)
+pub fn scale_for_snapshot<C>(
+    image: &image::ImageBuffer<image::Rgba<u8>, C>,
+    width: u32,
+    height: u32,
+) -> image::RgbImage
+where
+    C: std::ops::Deref<Target = [u8]>,
+{
+    let (iw, ih) = image.dimensions();
+    let scale = (width as f32 / iw as f32)
+        .max(height as f32 / ih as f32)
+        .min(1.0);
+    let sw = ((iw as f32 * scale).round() as u32).max(1);
+    let sh = ((ih as f32 * scale).round() as u32).max(1);
+    let scaled = image::imageops::thumbnail(image, sw, sh);
+    image::DynamicImage::ImageRgba8(scaled).to_rgb8()
+}
+
+/// Writes the snapshot for `source` and removes older ones of the same output
+pub fn write_snapshot(output_name: &str, source: &Path, image: &image::RgbImage) -> Result<()> {
+    let path = snapshot_path(output_name, source).context("Failed to get cache directory")?;
+    let dir = path.parent().context("Snapshot path has no parent")?;
+    std::fs::create_dir_all(dir)?;
+
+    // Write to a temp file first so a crash never leaves a truncated snapshot behind
+    let tmp = path.with_extension("tmp");
+    {
+        let file = std::io::BufWriter::new(std::fs::File::create(&tmp)?);
+        let mut encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(file, 85);
+        encoder.encode_image(image)?;
+    }
+    std::fs::rename(&tmp, &path)?;
+
+    let prefix = format!("{}-", output_name.replace('/', "_"));
+    for entry in std::fs::read_dir(dir)?.flatten() {
+        let entry_path = entry.path();
+        // "<output>-<16 hex digits>.jpg" exactly, so "HDMI-A" never matches "HDMI-A-1"
+        let is_stale = entry_path != path
+            && entry
+                .file_name()
+                .to_str()
+                .and_then(|n| n.strip_prefix(&prefix)?.strip_suffix(".jpg"))
+                .is_some_and(|h| h.len() == 16 && h.chars().all(|c| c.is_ascii_hexdigit()));
+        if is_stale {
+            let _ = std::fs::remove_file(entry_path);
+        }
+    }
+    Ok(())
+}
+
+/// Everything Kaleidux keeps in the user cache directory lives below this
+pub fn cache_root() -> Option<PathBuf> {
+    dirs::cache_dir().map(|d| d.join("kaleidux"))
+}
+
+/// Parses a size such as "2GB", "500 MiB", "1.5G" or "1048576" (plain bytes).
+/// K/M/G/T are binary multiples with or without a trailing "B"/"iB".
+pub fn parse_size(s: &str) -> Result<u64> {
+    let s = s.trim();
+    let split = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let number: f64 = number
+        .parse()
+        .with_context(|| format!("Invalid size {:?}", s))?;
+    let unit = unit.trim().to_ascii_lowercase();
+    let multiplier: u64 = match unit.trim_end_matches("ib").trim_end_matches('b') {
+        "" => 1,
+        "k" => 1 << 10,
+        "m" => 1 << 20,
+        "g" => 1 << 30,
+        "t" => 1 << 40,
+        _ => anyhow::bail!("Unknown size unit {:?} in {:?}", unit, s),
+    };
+    Ok((number * multiplier as f64) as u64)
+}
+
+/// Temp files older than this are leftovers of an interrupted write
+const STALE_TEMP_AGE: std::time::Duration = std::time::Duration::from_secs(3600);
+
+/// Evicts least-recently-used files below the cache root (snapshots, pre-processed
+/// artifacts, ...) until it fits in `limit`, and removes stale temp files. The index
+/// database, the `palette` color scheme, downloads of URL sources and the files in
+/// `keep` (what is on screen right now) are never touched.
+/// Blocking; call from spawn_blocking.
+pub fn collect_garbage(
+    limit: Option<u64>,
+    keep: &std::collections::HashSet<PathBuf>,
+) -> Result<kaleidux_common::CacheReport> {
+    let root = cache_root().context("Failed to get cache directory")?;
+    let mut report = kaleidux_common::CacheReport {
+        limit,
+        ..Default::default()
+    };
+    if !root.exists() {
+        return Ok(report);
+    }
+
+    let now = std::time::SystemTime::now();
+    // (last used, size, path) of every file that may be evicted
+    let mut candidates = Vec::new();
+    for entry in walkdir::WalkDir::new(&root).into_iter().flatten() {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let Ok(meta) = entry.metadata() else { continue };
+        let path = entry.into_path();
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        let in_root = path.parent() == Some(root.as_path());
+        if is_database_file(name) || (in_root && crate::palette::is_palette_file(name)) {
+            continue;
+        }
+
+        let modified = meta.modified().unwrap_or(UNIX_EPOCH);
+        // atime is often not updated (relatime/noatime), so mtime counts as a use too
+        let last_used = meta.accessed().map_or(modified, |a| a.max(modified));
+        let is_temp = name.starts_with(".tmp-") || name.ends_with(".tmp");
+        let age = now.duration_since(last_used).unwrap_or_default();
+        if is_temp && age >= STALE_TEMP_AGE {
+            if std::fs::remove_file(&path).is_ok() {
+                report.files_removed += 1;
+                report.bytes_reclaimed += meta.len();
+            }
+            continue;
+        }
+
+        report.size += meta.len();
+        // Downloads of URL sources and fetched wallpapers are the library itself, kept
+        // in sync by `remote` and `fetch`
+        let remote =
+            path.starts_with(root.join("remote")) || path.starts_with(root.join("fetched"));
+        if !is_temp && !keep.contains(&path) && !remote {
+            candidates.push((last_used, meta.len(), path));
+        }
+    }
+
+    if let Some(limit) = limit {
+        candidates.sort_by_key(|(last_used, _, _)| *last_used);
+        for (_, len, path) in candidates {
+            if report.size <= limit {
+                break;
+            }
+            match std::fs::remove_file(&path) {
+                Ok(()) => {
+                    report.files_removed += 1;
+                    report.bytes_reclaimed += len;
+                    report.size -= len;
+                }
+                Err(e) => tracing::debug!("[CACHE] Failed to evict {:?}: {}", path, e),
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// How long a watched single file has to stay quiet after a change before it is
+/// reported, so an editor's truncate-then-write shows up as one change
+const FILE_SETTLE_TIME: std::time::Duration = std::time::Duration::from_millis(300);
+
+/// Files and folders of the watched libraries that changed while the daemon runs
+#[derive(Debug, Default)]
+pub struct LibraryChanges {
+    /// Paths that are there after the change: new, moved in, renamed to or rewritten
+    pub added: Vec<PathBuf>,
+    /// Paths that are gone: deleted, moved out or renamed from. A folder stands for
+    /// everything that was in it.
+    pub removed: Vec<PathBuf>,
+    /// Renames within the libraries, old path first; both ends are listed above too
+    pub renamed: Vec<(PathBuf, PathBuf)>,
+}
+
+impl LibraryChanges {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Directory watcher for cache invalidation and keeping queues in line with their
+/// library; also watches the single files of static outputs (see
+/// `OutputConfig::static_file`)
 pub struct DirectoryWatcher {
     watcher: RecommendedWatcher,
     event_rx: mpsc::Receiver<notify::Result<Event>>,
     cache: Arc<FileCache>,
     watched_dirs: Vec<PathBuf>,
+    /// Watched single files, with the time of their last unreported change
+    watched_files: std::collections::HashMap<PathBuf, Option<std::time::Instant>>,
+    /// Library paths with unreported changes and the time of the last one. They settle
+    /// like single files, so a file still being copied isn't picked half-written.
+    library_changes: std::collections::HashMap<PathBuf, std::time::Instant>,
+    renames: Vec<(PathBuf, PathBuf)>,
 }
 
 impl DirectoryWatcher {
@@ -325,25 +970,50 @@ impl DirectoryWatcher {
             event_rx,
             cache,
             watched_dirs: Vec::new(),
+            watched_files: std::collections::HashMap::new(),
+            library_changes: std::collections::HashMap::new(),
+            renames: Vec::new(),
         })
     }
 
-    /// Watch a directory for changes
+    /// Watch a directory for changes. A single file is watched through its parent
+    /// directory, since editors often replace files by renaming a new copy over them.
     pub fn watch(&mut self, path: &Path) -> Result<()> {
         if path.exists() && path.is_dir() {
             self.watcher.watch(path, RecursiveMode::Recursive)?;
             self.watched_dirs.push(path.to_path_buf());
             tracing::info!("[CACHE] Watching directory for changes: {}", path.display());
+        } else if path.is_file() && !self.watched_files.contains_key(path) {
+            let parent = path.parent().context("File has no parent directory")?;
+            self.watcher.watch(parent, RecursiveMode::NonRecursive)?;
+            self.watched_files.insert(path.to_path_buf(), None);
+            tracing::info!("[STATIC] Watching file for changes: {}", path.display());
         }
         Ok(())
     }
 
-    /// Process file system events and invalidate cache entries
-    pub async fn process_events(&mut self) {
+    /// Process file system events and invalidate cache entries. Returns the watched
+    /// single files that changed and have settled since (see `FILE_SETTLE_TIME`).
+    pub async fn process_events(&mut self) -> Vec<PathBuf> {
         while let Ok(Ok(event)) = self.event_rx.try_recv() {
+            let in_library = |path: &PathBuf| self.watched_dirs.iter().any(|d| path.starts_with(d));
+            if let (EventKind::Modify(ModifyKind::Name(RenameMode::Both)), [from, to]) =
+                (&event.kind, event.paths.as_slice())
+            {
+                if in_library(from) && in_library(to) {
+                    self.renames.push((from.clone(), to.clone()));
+                }
+            }
             match event.kind {
                 EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_) => {
                     for path in event.paths {
+                        if let Some(changed) = self.watched_files.get_mut(&path) {
+                            *changed = Some(std::time::Instant::now());
+                        }
+                        if in_library(&path) {
+                            self.library_changes
+                                .insert(path.clone(), std::time::Instant::now());
+                        }
                         if path.is_file() {
                             // Invalidate cache entry for this file
                             if let Err(e) = self.cache.invalidate_file(&path) {
@@ -368,5 +1038,41 @@ impl DirectoryWatcher {
                 _ => {}
             }
         }
+
+        let mut settled = Vec::new();
+        for (path, changed) in &mut self.watched_files {
+            // A removed file keeps showing its last version until it comes back
+            if changed.is_some_and(|t| t.elapsed() >= FILE_SETTLE_TIME) && path.is_file() {
+                *changed = None;
+                settled.push(path.clone());
+            }
+        }
+        settled
+    }
+
+    /// Library paths whose changes have settled since the last call (see
+    /// `FILE_SETTLE_TIME`), sorted by whether they still exist
+    pub fn library_changes(&mut self) -> LibraryChanges {
+        let mut changes = LibraryChanges::default();
+        self.library_changes.retain(|path, changed| {
+            if changed.elapsed() < FILE_SETTLE_TIME {
+                return true;
+            }
+            if path.exists() {
+                changes.added.push(path.clone());
+            } else {
+                changes.removed.push(path.clone());
+            }
+            false
+        });
+        let pending = &self.library_changes;
+        let (settled, waiting) = std::mem::take(&mut self.renames)
+            .into_iter()
+            .partition(|(from, to)| !pending.contains_key(from) && !pending.contains_key(to));
+        changes.renamed = settled;
+        self.renames = waiting;
+        changes.added.sort();
+        changes.removed.sort();
+        changes
     }
 }
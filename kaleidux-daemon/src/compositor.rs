@@ -0,0 +1,286 @@
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::UnixStream;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+use tracing::{info, warn};
+
+/// Wait before following the compositor again after its socket went away (it restarted)
+const RECONNECT_DELAY: Duration = Duration::from_secs(3);
+
+/// Active workspace of each output, by output name
+type Workspaces = HashMap<String, String>;
+
+/// Compositors whose IPC reports the active workspace of each output
+enum Compositor {
+    Hyprland,
+    Niri(PathBuf),
+    /// Sway and i3 speak the same protocol
+    I3(PathBuf),
+}
+
+impl Compositor {
+    fn detect() -> Option<Self> {
+        if std::env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some() {
+            return Some(Self::Hyprland);
+        }
+        if let Some(path) = std::env::var_os("NIRI_SOCKET") {
+            return Some(Self::Niri(path.into()));
+        }
+        std::env::var_os("SWAYSOCK")
+            .or_else(|| std::env::var_os("I3SOCK"))
+            .map(|path| Self::I3(path.into()))
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Hyprland => "Hyprland",
+            Self::Niri(_) => "niri",
+            Self::I3(_) => "Sway/i3",
+        }
+    }
+}
+
+/// Follows the compositor's active workspaces for `[workspace.NAME]` rules
+pub struct WorkspaceWatcher {
+    rx: Option<UnboundedReceiver<Workspaces>>,
+    active: Workspaces,
+}
+
+impl WorkspaceWatcher {
+    pub fn new() -> Self {
+        Self {
+            rx: None,
+            active: HashMap::new(),
+        }
+    }
+
+    /// Outputs whose active workspace changed since the last call, with the new one.
+    /// Starts following the compositor the first time it is `wanted`.
+    pub fn poll(&mut self, wanted: bool) -> Vec<(String, String)> {
+        let rx = match &mut self.rx {
+            Some(rx) => rx,
+            None if wanted => {
+                let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+                tokio::spawn(follow(tx));
+                self.rx.insert(rx)
+            }
+            None => return Vec::new(),
+        };
+        let mut switched: Vec<(String, String)> = Vec::new();
+        while let Ok(workspaces) = rx.try_recv() {
+            for (output, workspace) in workspaces {
+                if self.active.get(&output) == Some(&workspace) {
+                    continue;
+                }
+                self.active.insert(output.clone(), workspace.clone());
+                switched.retain(|(o, _)| *o != output);
+                switched.push((output, workspace));
+            }
+        }
+        switched
+    }
+}
+
+async fn follow(tx: UnboundedSender<Workspaces>) {
+    let Some(compositor) = Compositor::detect() else {
+        info!("[WORKSPACE] No Hyprland, Sway, i3 or niri IPC found, workspace rules are inactive");
+        return;
+    };
+    info!(
+        "[WORKSPACE] Following workspaces over {} IPC",
+        compositor.name()
+    );
+    loop {
+        let result = match &compositor {
+            Compositor::Hyprland => follow_hyprland(&tx).await,
+            Compositor::Niri(path) => follow_niri(path, &tx).await,
+            Compositor::I3(path) => follow_i3(path, &tx).await,
+        };
+        if tx.is_closed() {
+            return;
+        }
+        if let Err(e) = result {
+            warn!(
+                "[WORKSPACE] {} IPC: {:#}, retrying in {:?}",
+                compositor.name(),
+                e,
+                RECONNECT_DELAY
+            );
+        }
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+/// Socket folder of the running Hyprland instance. A restarted Hyprland has a new
+/// instance signature, so the newest folder is taken when ours is gone.
+fn hyprland_dir() -> Option<PathBuf> {
+    let runtime = std::env::var_os("XDG_RUNTIME_DIR").map(|d| Path::new(&d).join("hypr"));
+    let signature = std::env::var_os("HYPRLAND_INSTANCE_SIGNATURE")?;
+    let ours = runtime
+        .iter()
+        .chain([&PathBuf::from("/tmp/hypr")])
+        .map(|base| base.join(&signature))
+        .find(|dir| dir.join(".socket2.sock").exists());
+    ours.or_else(|| {
+        std::fs::read_dir(runtime?)
+            .ok()?
+            .flatten()
+            .map(|e| e.path())
+            .filter(|dir| dir.join(".socket2.sock").exists())
+            .max_by_key(|dir| dir.metadata().and_then(|m| m.modified()).ok())
+    })
+}
+
+async fn hyprland_workspaces(dir: &Path) -> Result<Workspaces> {
+    let mut stream = UnixStream::connect(dir.join(".socket.sock")).await?;
+    stream.write_all(b"j/monitors").await?;
+    let mut reply = Vec::new();
+    stream.read_to_end(&mut reply).await?;
+    let monitors: Vec<Value> =
+        serde_json::from_slice(&reply).context("Unexpected reply to j/monitors")?;
+    Ok(monitors
+        .iter()
+        .filter_map(|m| {
+            let name = m["name"].as_str()?;
+            let workspace = m.pointer("/activeWorkspace/name")?.as_str()?;
+            Some((name.to_string(), workspace.to_string()))
+        })
+        .collect())
+}
+
+async fn follow_hyprland(tx: &UnboundedSender<Workspaces>) -> Result<()> {
+    let dir = hyprland_dir().context("Hyprland socket not found")?;
+    let events = UnixStream::connect(dir.join(".socket2.sock")).await?;
+    tx.send(hyprland_workspaces(&dir).await?)?;
+    let mut lines = BufReader::new(events).lines();
+    while let Some(line) = lines.next_line().await? {
+        let event = line.split(">>").next().unwrap_or_default();
+        let relevant = matches!(
+            event,
+            "workspace"
+                | "workspacev2"
+                | "focusedmon"
+                | "focusedmonv2"
+                | "moveworkspace"
+                | "moveworkspacev2"
+                | "renameworkspace"
+                | "monitoradded"
+                | "monitoraddedv2"
+        );
+        if relevant {
+            tx.send(hyprland_workspaces(&dir).await?)?;
+        }
+    }
+    Ok(())
+}
+
+/// Active workspaces in a niri workspace list, by name or else by index
+fn niri_active(workspaces: &[Value]) -> Workspaces {
+    workspaces
+        .iter()
+        .filter(|w| w["is_active"].as_bool() == Some(true))
+        .filter_map(|w| {
+            let output = w["output"].as_str()?;
+            let name = match w["name"].as_str() {
+                Some(name) => name.to_string(),
+                None => w["idx"].as_u64()?.to_string(),
+            };
+            Some((output.to_string(), name))
+        })
+        .collect()
+}
+
+async fn follow_niri(path: &Path, tx: &UnboundedSender<Workspaces>) -> Result<()> {
+    let mut stream = UnixStream::connect(path).await?;
+    stream.write_all(b"\"EventStream\"\n").await?;
+    let mut lines = BufReader::new(stream).lines();
+    let mut workspaces: Vec<Value> = Vec::new();
+    while let Some(line) = lines.next_line().await? {
+        let event: Value = serde_json::from_str(&line).context("Unexpected event")?;
+        if let Some(error) = event.get("Err") {
+            bail!("{}", error);
+        }
+        if let Some(list) = event.pointer("/WorkspacesChanged/workspaces") {
+            workspaces = list.as_array().cloned().unwrap_or_default();
+        } else if let Some(id) = event.pointer("/WorkspaceActivated/id") {
+            let Some(output) = workspaces
+                .iter()
+                .find(|w| &w["id"] == id)
+                .map(|w| w["output"].clone())
+            else {
+                continue;
+            };
+            for w in workspaces.iter_mut().filter(|w| w["output"] == output) {
+                w["is_active"] = Value::Bool(&w["id"] == id);
+            }
+        } else {
+            continue;
+        }
+        tx.send(niri_active(&workspaces))?;
+    }
+    Ok(())
+}
+
+const I3_MAGIC: &[u8] = b"i3-ipc";
+const I3_GET_WORKSPACES: u32 = 1;
+const I3_SUBSCRIBE: u32 = 2;
+
+async fn i3_send(stream: &mut UnixStream, kind: u32, payload: &[u8]) -> Result<()> {
+    let mut message = Vec::with_capacity(I3_MAGIC.len() + 8 + payload.len());
+    message.extend_from_slice(I3_MAGIC);
+    message.extend_from_slice(&(payload.len() as u32).to_ne_bytes());
+    message.extend_from_slice(&kind.to_ne_bytes());
+    message.extend_from_slice(payload);
+    stream.write_all(&message).await?;
+    Ok(())
+}
+
+/// The payload of the next reply or event
+async fn i3_read(stream: &mut UnixStream) -> Result<Vec<u8>> {
+    let mut header = [0u8; 14];
+    stream.read_exact(&mut header).await?;
+    if &header[..6] != I3_MAGIC {
+        bail!("Not an i3-ipc message");
+    }
+    let len = u32::from_ne_bytes([header[6], header[7], header[8], header[9]]);
+    let mut payload = vec![0; len as usize];
+    stream.read_exact(&mut payload).await?;
+    Ok(payload)
+}
+
+async fn i3_workspaces(path: &Path) -> Result<Workspaces> {
+    let mut stream = UnixStream::connect(path).await?;
+    i3_send(&mut stream, I3_GET_WORKSPACES, b"").await?;
+    let workspaces: Vec<Value> = serde_json::from_slice(&i3_read(&mut stream).await?)
+        .context("Unexpected reply to GET_WORKSPACES")?;
+    Ok(workspaces
+        .iter()
+        .filter(|w| w["visible"].as_bool() == Some(true))
+        .filter_map(|w| {
+            Some((
+                w["output"].as_str()?.to_string(),
+                w["name"].as_str()?.to_string(),
+            ))
+        })
+        .collect())
+}
+
+async fn follow_i3(path: &Path, tx: &UnboundedSender<Workspaces>) -> Result<()> {
+    let mut events = UnixStream::connect(path).await?;
+    i3_send(&mut events, I3_SUBSCRIBE, br#"["workspace","output"]"#).await?;
+    let reply: Value = serde_json::from_slice(&i3_read(&mut events).await?)?;
+    if reply["success"].as_bool() != Some(true) {
+        bail!("Subscribing to workspace events failed: {}", reply);
+    }
+    tx.send(i3_workspaces(path).await?)?;
+    // Every event is followed by a fresh listing; the events alone don't say what is
+    // visible on other outputs
+    loop {
+        i3_read(&mut events).await?;
+        tx.send(i3_workspaces(path).await?)?;
+    }
+}
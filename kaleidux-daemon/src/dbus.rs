@@ -0,0 +1,401 @@
+//! `org.kaleidux.Daemon` on the session bus: the IPC socket's requests as DBus methods
+//! and `subscribe`'s events as signals, for desktop scripts and tools that already speak
+//! DBus. Requests go to the main loop over the same channel as the socket's.
+//!
+//! DBus has no optional arguments, so an empty string stands for a left-out one (every
+//! output, the configured transition, ...) and a duration of 0 for the configured one.
+
+use kaleidux_common::{ErrorCode, Event, Request, Response};
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tracing::{debug, info, warn};
+use zbus::object_server::SignalEmitter;
+
+const NAME: &str = "org.kaleidux.Daemon";
+const PATH: &str = "/org/kaleidux/Daemon";
+
+type CommandSender = mpsc::UnboundedSender<(Request, oneshot::Sender<Response>)>;
+
+/// Failures as `org.kaleidux.Daemon.Error.<code>`, one per `ErrorCode`
+#[derive(Debug, zbus::DBusError)]
+#[zbus(prefix = "org.kaleidux.Daemon.Error")]
+enum Error {
+    #[zbus(error)]
+    ZBus(zbus::Error),
+    Failed(String),
+    InvalidArgument(String),
+    UnknownOutput(String),
+    NotFound(String),
+    Unavailable(String),
+    Timeout(String),
+    Config(String),
+    Skipped(String),
+}
+
+impl Error {
+    fn new(code: ErrorCode, message: String) -> Self {
+        match code {
+            ErrorCode::Failed => Error::Failed(message),
+            ErrorCode::InvalidArgument => Error::InvalidArgument(message),
+            ErrorCode::UnknownOutput => Error::UnknownOutput(message),
+            ErrorCode::NotFound => Error::NotFound(message),
+            ErrorCode::Unavailable => Error::Unavailable(message),
+            ErrorCode::Timeout => Error::Timeout(message),
+            ErrorCode::Config => Error::Config(message),
+            ErrorCode::Skipped => Error::Skipped(message),
+        }
+    }
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+fn optional(value: &str) -> Option<String> {
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+fn seconds(value: f64) -> Option<f32> {
+    (value > 0.0).then_some(value as f32)
+}
+
+/// The message of the first failure in `response`, as the socket reports it
+fn failure(response: &Response) -> Option<String> {
+    match response {
+        Response::Error(e) => Some(e.message.clone()),
+        Response::OutputResults(results) => {
+            let failed: Vec<String> = results
+                .iter()
+                .filter(|r| !r.success)
+                .map(|r| format!("{}: {}", r.output, r.message))
+                .collect();
+            (!failed.is_empty()).then(|| failed.join("; "))
+        }
+        Response::Batch(responses) => responses.iter().find_map(failure),
+        _ => None,
+    }
+}
+
+struct Daemon {
+    cmd_tx: CommandSender,
+}
+
+impl Daemon {
+    async fn send(&self, request: Request) -> Result<Response> {
+        let (resp_tx, resp_rx) = oneshot::channel();
+        let shutting_down = || Error::Unavailable("The daemon is shutting down".to_string());
+        self.cmd_tx
+            .send((request, resp_tx))
+            .map_err(|_| shutting_down())?;
+        resp_rx.await.map_err(|_| shutting_down())
+    }
+
+    /// Sends `request`, turning a failed reply into the matching DBus error
+    async fn call(&self, request: Request) -> Result<Response> {
+        let response = self.send(request).await?;
+        match (response.error_code(), failure(&response)) {
+            (Some(code), Some(message)) => Err(Error::new(code, message)),
+            _ => Ok(response),
+        }
+    }
+
+    async fn call_json(&self, request: Request) -> Result<String> {
+        let response = self.call(request).await?;
+        serde_json::to_string(&response).map_err(|e| Error::Failed(e.to_string()))
+    }
+}
+
+#[zbus::interface(name = "org.kaleidux.Daemon")]
+impl Daemon {
+    /// Any socket request as JSON (see `kaleidux_common::Request`), answered with the
+    /// JSON reply the socket would give, failures included. `subscribe` isn't
+    /// supported; listen to the signals instead.
+    async fn request(&self, json: &str) -> Result<String> {
+        let request: Request = serde_json::from_str(json)
+            .map_err(|e| Error::InvalidArgument(format!("Invalid request: {}", e)))?;
+        if let Request::Subscribe = request {
+            return Err(Error::InvalidArgument(
+                "subscribe is not available over DBus, use the signals".to_string(),
+            ));
+        }
+        let response = self.send(request).await?;
+        serde_json::to_string(&response).map_err(|e| Error::Failed(e.to_string()))
+    }
+
+    /// Outputs as JSON, the `OutputInfo` list of `kldctl query --json`
+    async fn query_outputs(&self) -> Result<String> {
+        match self.call(Request::QueryOutputs).await? {
+            Response::OutputInfo(outputs) => {
+                serde_json::to_string(&outputs).map_err(|e| Error::Failed(e.to_string()))
+            }
+            other => serde_json::to_string(&other).map_err(|e| Error::Failed(e.to_string())),
+        }
+    }
+
+    /// Playback state of every output as JSON, the `Status` list of `kldctl status --json`
+    async fn status(&self) -> Result<String> {
+        match self.call(Request::Status).await? {
+            Response::Status(outputs) => {
+                serde_json::to_string(&outputs).map_err(|e| Error::Failed(e.to_string()))
+            }
+            other => serde_json::to_string(&other).map_err(|e| Error::Failed(e.to_string())),
+        }
+    }
+
+    async fn next(&self, output: &str) -> Result<()> {
+        let output = optional(output);
+        self.call(Request::Next {
+            output,
+            tags: Vec::new(),
+        })
+        .await
+        .map(drop)
+    }
+
+    async fn prev(&self, output: &str) -> Result<()> {
+        let output = optional(output);
+        self.call(Request::Prev { output }).await.map(drop)
+    }
+
+    async fn set(&self, path: &str, output: &str, transition: &str, duration: f64) -> Result<()> {
+        let request = Request::Set {
+            path: path.to_string(),
+            output: optional(output),
+            transition: optional(transition),
+            duration: seconds(duration),
+        };
+        self.call(request).await.map(drop)
+    }
+
+    async fn set_transition(&self, output: &str, transition: &str, duration: f64) -> Result<()> {
+        let request = Request::SetTransition {
+            output: optional(output),
+            transition: transition.to_string(),
+            duration: seconds(duration),
+        };
+        self.call(request).await.map(drop)
+    }
+
+    async fn set_volume(&self, output: &str, volume: u8) -> Result<()> {
+        let output = optional(output);
+        self.call(Request::SetVolume { output, volume })
+            .await
+            .map(drop)
+    }
+
+    async fn mute(&self, output: &str, muted: bool) -> Result<()> {
+        let request = Request::Mute {
+            output: optional(output),
+            muted: Some(muted),
+        };
+        self.call(request).await.map(drop)
+    }
+
+    async fn toggle_mute(&self, output: &str) -> Result<()> {
+        let request = Request::Mute {
+            output: optional(output),
+            muted: None,
+        };
+        self.call(request).await.map(drop)
+    }
+
+    async fn goto(&self, index: u32) -> Result<()> {
+        let index = index as usize;
+        self.call(Request::Goto { index }).await.map(drop)
+    }
+
+    /// An empty `path` loves the wallpaper shown on `output`
+    async fn love(&self, path: &str, multiplier: f64, output: &str) -> Result<()> {
+        let request = Request::Love {
+            path: optional(path),
+            paths: Vec::new(),
+            multiplier: multiplier as f32,
+            output: optional(output),
+        };
+        self.call(request).await.map(drop)
+    }
+
+    async fn unlove(&self, path: &str, output: &str) -> Result<()> {
+        let request = Request::Unlove {
+            path: optional(path),
+            paths: Vec::new(),
+            output: optional(output),
+        };
+        self.call(request).await.map(drop)
+    }
+
+    async fn favorites(&self, enabled: bool, output: &str) -> Result<()> {
+        let output = optional(output);
+        self.call(Request::Favorites { enabled, output })
+            .await
+            .map(drop)
+    }
+
+    async fn pause(&self, output: &str) -> Result<()> {
+        let output = optional(output);
+        self.call(Request::Pause { output }).await.map(drop)
+    }
+
+    async fn resume(&self, output: &str) -> Result<()> {
+        let output = optional(output);
+        self.call(Request::Resume { output }).await.map(drop)
+    }
+
+    /// An empty `color` clears to black
+    async fn clear(&self, output: &str, color: &str) -> Result<()> {
+        let request = Request::Clear {
+            output: optional(output),
+            color: optional(color),
+        };
+        self.call(request).await.map(drop)
+    }
+
+    async fn snapshot(&self, output: &str, path: &str, blur: u32) -> Result<()> {
+        let request = Request::Snapshot {
+            output: optional(output),
+            path: path.to_string(),
+            blur,
+        };
+        self.call(request).await.map(drop)
+    }
+
+    async fn capture(&self, output: &str, path: &str) -> Result<()> {
+        let request = Request::Capture {
+            output: optional(output),
+            path: path.to_string(),
+        };
+        self.call(request).await.map(drop)
+    }
+
+    /// Returns once in-flight switches and transitions are done; `timeout` in seconds,
+    /// 0 to wait as long as it takes
+    async fn wait(&self, output: &str, timeout: u64) -> Result<()> {
+        let request = Request::Wait {
+            output: optional(output),
+            timeout: (timeout > 0).then_some(timeout),
+        };
+        self.call(request).await.map(drop)
+    }
+
+    async fn history(&self, output: &str) -> Result<Vec<String>> {
+        let output = optional(output);
+        match self.call(Request::History { output }).await? {
+            Response::History(history) => Ok(history),
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    async fn metrics(&self) -> Result<String> {
+        self.call_json(Request::Metrics).await
+    }
+
+    async fn reload(&self) -> Result<()> {
+        self.call(Request::Reload).await.map(drop)
+    }
+
+    async fn reload_output(&self, output: &str) -> Result<()> {
+        let output = output.to_string();
+        self.call(Request::ReloadOutput { output }).await.map(drop)
+    }
+
+    async fn stop(&self) -> Result<()> {
+        self.call(Request::Stop).await.map(drop)
+    }
+
+    async fn kill(&self) -> Result<()> {
+        self.call(Request::Kill).await.map(drop)
+    }
+
+    /// Also sent for each mirror of the output; `content_type` is "image", "video",
+    /// "animated" or "shader"
+    #[zbus(signal)]
+    async fn wallpaper_changed(
+        emitter: &SignalEmitter<'_>,
+        output: &str,
+        path: &str,
+        content_type: &str,
+    ) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn transition_started(
+        emitter: &SignalEmitter<'_>,
+        output: &str,
+        transition: &str,
+        duration_ms: u64,
+    ) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn transition_completed(emitter: &SignalEmitter<'_>, output: &str) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn output_added(emitter: &SignalEmitter<'_>, output: &str) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn output_removed(emitter: &SignalEmitter<'_>, output: &str) -> zbus::Result<()>;
+
+    /// `output` is empty when everything paused
+    #[zbus(signal)]
+    async fn paused(emitter: &SignalEmitter<'_>, output: &str) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn resumed(emitter: &SignalEmitter<'_>, output: &str) -> zbus::Result<()>;
+}
+
+async fn emit(emitter: &SignalEmitter<'_>, event: Event) -> zbus::Result<()> {
+    match event {
+        Event::WallpaperChanged {
+            output,
+            path,
+            content_type,
+        } => Daemon::wallpaper_changed(emitter, &output, &path, &content_type).await,
+        Event::TransitionStarted {
+            output,
+            transition,
+            duration_ms,
+        } => Daemon::transition_started(emitter, &output, &transition, duration_ms).await,
+        Event::TransitionCompleted { output } => {
+            Daemon::transition_completed(emitter, &output).await
+        }
+        Event::OutputAdded { output } => Daemon::output_added(emitter, &output).await,
+        Event::OutputRemoved { output } => Daemon::output_removed(emitter, &output).await,
+        Event::Paused { output } => {
+            Daemon::paused(emitter, output.as_deref().unwrap_or_default()).await
+        }
+        Event::Resumed { output } => {
+            Daemon::resumed(emitter, output.as_deref().unwrap_or_default()).await
+        }
+    }
+}
+
+async fn serve(cmd_tx: CommandSender) -> zbus::Result<()> {
+    // Subscribed before the name is taken so nothing sent meanwhile is missed
+    let mut events = crate::events::subscribe();
+    let connection = zbus::connection::Builder::session()?
+        .name(NAME)?
+        .serve_at(PATH, Daemon { cmd_tx })?
+        .build()
+        .await?;
+    info!("[DBUS] Serving {} at {}", NAME, PATH);
+
+    let emitter = SignalEmitter::new(&connection, PATH)?;
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                warn!("[DBUS] Fell behind on events, dropped {}", missed);
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => return Ok(()),
+        };
+        if let Err(e) = emit(&emitter, event).await {
+            debug!("[DBUS] Failed to emit signal: {}", e);
+        }
+    }
+}
+
+/// Serves the DBus interface in the background. Without a session bus, or with the
+/// name taken by another daemon, only the socket is available.
+pub fn spawn(cmd_tx: CommandSender) {
+    tokio::spawn(async move {
+        if let Err(e) = serve(cmd_tx).await {
+            warn!("[DBUS] Not available on the session bus: {}", e);
+        }
+    });
+}
@@ -0,0 +1,262 @@
+use std::collections::HashSet;
+
+/// Outcome of one diagnostic check
+#[derive(Clone, Copy, PartialEq)]
+enum Status {
+    Pass,
+    /// Works, but something optional is missing
+    Warn,
+    Fail,
+}
+
+struct Report {
+    failed: bool,
+}
+
+impl Report {
+    fn check(&mut self, status: Status, name: &str, detail: impl std::fmt::Display) {
+        let mark = match status {
+            Status::Pass => "✓",
+            Status::Warn => "!",
+            Status::Fail => {
+                self.failed = true;
+                "✗"
+            }
+        };
+        println!("{} {:<14} {}", mark, name, detail);
+    }
+}
+
+/// GStreamer elements every video needs (playbin pipeline + RGBA appsink)
+const REQUIRED_ELEMENTS: &[&str] = &["playbin", "appsink", "videoconvert", "decodebin"];
+/// Demuxers for the containers discovery accepts (MP4/MOV, MKV/WebM)
+const DEMUXERS: &[&str] = &["qtdemux", "matroskademux"];
+/// At least one of these should exist or most videos won't decode
+const DECODERS: &[&str] = &[
+    "avdec_h264",
+    "openh264dec",
+    "vah264dec",
+    "nvh264dec",
+    "vp9dec",
+    "vp8dec",
+    "dav1ddec",
+    "av1dec",
+];
+
+/// `kaleidux-daemon --doctor`: checks the display session, GPU adapters, GStreamer
+/// plugins and the config file, printing one line per check. Returns false if any
+/// check failed. Does not touch the running daemon (no lock, socket or database).
+pub fn run() -> bool {
+    let mut report = Report { failed: false };
+
+    check_config(&mut report);
+    check_display(&mut report);
+    check_gpu(&mut report);
+    check_gstreamer(&mut report);
+
+    !report.failed
+}
+
+fn check_config(report: &mut Report) {
+    let path = match crate::orchestration::Config::path() {
+        Ok(path) => path,
+        Err(e) => return report.check(Status::Fail, "config", e),
+    };
+    if !path.exists() {
+        return report.check(
+            Status::Pass,
+            "config",
+            format!("no file at {} (using defaults)", path.display()),
+        );
+    }
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) => {
+            return report.check(Status::Fail, "config", format!("{}: {}", path.display(), e))
+        }
+    };
+    let (config, errors) = match crate::orchestration::Config::parse(&content) {
+        Ok(parsed) => parsed,
+        Err(e) => return report.check(Status::Fail, "config", format!("{:#}", e)),
+    };
+    if errors.is_empty() {
+        report.check(
+            Status::Pass,
+            "config",
+            format!(
+                "{} ({} output sections)",
+                path.display(),
+                config.outputs.len()
+            ),
+        );
+    }
+    for error in errors {
+        report.check(Status::Fail, "config", error);
+    }
+    check_remote(report, &config);
+}
+
+/// URL sources are downloaded with curl
+fn check_remote(report: &mut Report, config: &crate::orchestration::Config) {
+    let urls = config.remote_urls();
+    let what = match (urls.len(), &config.fetch) {
+        (0, None) => return,
+        (0, Some(fetch)) => format!("[fetch] from {}", fetch.provider.name()),
+        (n, None) => format!("{} URL sources", n),
+        (n, Some(fetch)) => format!(
+            "{} URL sources and [fetch] from {}",
+            n,
+            fetch.provider.name()
+        ),
+    };
+    match std::process::Command::new("curl").arg("--version").output() {
+        Ok(output) if output.status.success() => {
+            report.check(Status::Pass, "remote", format!("{}, curl found", what))
+        }
+        _ => report.check(
+            Status::Fail,
+            "remote",
+            format!("{}, but curl is not installed", what),
+        ),
+    }
+}
+
+fn check_display(report: &mut Report) {
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        check_wayland(report);
+    } else if std::env::var_os("DISPLAY").is_some() {
+        match x11rb::connect(None) {
+            Ok((conn, _)) => {
+                use x11rb::connection::Connection;
+                let screens = conn.setup().roots.len();
+                report.check(
+                    Status::Pass,
+                    "x11",
+                    format!("connected ({} screens)", screens),
+                );
+            }
+            Err(e) => report.check(Status::Fail, "x11", format!("cannot connect: {}", e)),
+        }
+    } else {
+        report.check(
+            Status::Fail,
+            "session",
+            "neither WAYLAND_DISPLAY nor DISPLAY is set (only --headless works)",
+        );
+    }
+}
+
+fn check_wayland(report: &mut Report) {
+    let conn = match wayland_client::Connection::connect_to_env() {
+        Ok(conn) => conn,
+        Err(e) => return report.check(Status::Fail, "wayland", format!("cannot connect: {}", e)),
+    };
+    let globals =
+        match wayland_client::globals::registry_queue_init::<crate::wayland::WaylandBackend>(&conn)
+        {
+            Ok((globals, _queue)) => globals,
+            Err(e) => return report.check(Status::Fail, "wayland", format!("registry: {}", e)),
+        };
+    let interfaces: HashSet<String> = globals
+        .contents()
+        .with_list(|list| list.iter().map(|g| g.interface.clone()).collect());
+    report.check(
+        Status::Pass,
+        "wayland",
+        format!("connected ({} globals)", interfaces.len()),
+    );
+
+    // (interface, required, what it is used for)
+    let protocols = [
+        ("zwlr_layer_shell_v1", true, "wallpaper surfaces"),
+        ("wp_viewporter", false, "scaled surfaces"),
+        (
+            "wp_fractional_scale_manager_v1",
+            false,
+            "fractional HiDPI scaling",
+        ),
+    ];
+    for (interface, required, purpose) in protocols {
+        let status = match (interfaces.contains(interface), required) {
+            (true, _) => Status::Pass,
+            (false, true) => Status::Fail,
+            (false, false) => Status::Warn,
+        };
+        let availability = if status == Status::Pass {
+            "available"
+        } else {
+            "missing"
+        };
+        report.check(
+            status,
+            "protocol",
+            format!("{} {} ({})", interface, availability, purpose),
+        );
+    }
+}
+
+fn check_gpu(report: &mut Report) {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+    let adapters = instance.enumerate_adapters(wgpu::Backends::all());
+    if adapters.is_empty() {
+        return report.check(Status::Fail, "gpu", "no wgpu adapters found");
+    }
+    for (index, adapter) in adapters.iter().enumerate() {
+        let info = adapter.get_info();
+        // A CPU adapter works but renders transitions in software
+        let status = if info.device_type == wgpu::DeviceType::Cpu {
+            Status::Warn
+        } else {
+            Status::Pass
+        };
+        report.check(
+            status,
+            "gpu",
+            format!(
+                "{}: {} ({:?}, {:?}, driver {})",
+                index, info.name, info.backend, info.device_type, info.driver
+            ),
+        );
+    }
+}
+
+fn check_gstreamer(report: &mut Report) {
+    if let Err(e) = gstreamer::init() {
+        return report.check(Status::Fail, "gstreamer", format!("init failed: {}", e));
+    }
+    let (major, minor, micro, _) = gstreamer::version();
+    report.check(
+        Status::Pass,
+        "gstreamer",
+        format!("{}.{}.{}", major, minor, micro),
+    );
+
+    let has = |name: &str| gstreamer::ElementFactory::find(name).is_some();
+    for &element in REQUIRED_ELEMENTS.iter().chain(DEMUXERS) {
+        if has(element) {
+            report.check(Status::Pass, "plugin", element);
+        } else {
+            report.check(Status::Fail, "plugin", format!("{} missing", element));
+        }
+    }
+    let decoders: Vec<&str> = DECODERS.iter().copied().filter(|d| has(d)).collect();
+    if decoders.is_empty() {
+        report.check(
+            Status::Warn,
+            "decoders",
+            "no H.264/VP8/VP9/AV1 decoder found; most videos will fail to play",
+        );
+    } else {
+        report.check(Status::Pass, "decoders", decoders.join(", "));
+    }
+    let hardware = crate::video::hardware_decoder_names();
+    if hardware.is_empty() {
+        report.check(
+            Status::Warn,
+            "hw decoders",
+            "none found (install gst-plugins-bad for VA-API/NVDEC); videos decode on the CPU",
+        );
+    } else {
+        report.check(Status::Pass, "hw decoders", hardware.join(", "));
+    }
+}
@@ -0,0 +1,17 @@
+use kaleidux_common::Event;
+use tokio::sync::broadcast;
+
+/// Events a subscriber may fall behind by before it starts missing some
+const BACKLOG: usize = 256;
+
+static EVENTS: once_cell::sync::Lazy<broadcast::Sender<Event>> =
+    once_cell::sync::Lazy::new(|| broadcast::channel(BACKLOG).0);
+
+/// Sends `event` to every `subscribe` client; dropped when nobody listens
+pub fn emit(event: Event) {
+    let _ = EVENTS.send(event);
+}
+
+pub fn subscribe() -> broadcast::Receiver<Event> {
+    EVENTS.subscribe()
+}
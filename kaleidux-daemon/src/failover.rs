@@ -0,0 +1,33 @@
+use anyhow::{Context, Result};
+use image::RgbaImage;
+
+/// Logo drawn by the `default` fallback
+const LOGO: &[u8] = include_bytes!("../../assets/kaleidux.png");
+const BACKGROUND: [u8; 4] = [22, 22, 30, 255];
+/// Widest the logo gets, as a fraction of the output width
+const LOGO_WIDTH: f32 = 0.4;
+
+/// The built-in fallback image: the Kaleidux logo centered on a dark background, at
+/// `width`x`height` (1920x1080 while the output size isn't known yet)
+pub fn default_image(width: u32, height: u32) -> Result<RgbaImage> {
+    let (width, height) = if width > 0 && height > 0 {
+        (width, height)
+    } else {
+        (1920, 1080)
+    };
+    let mut logo = image::load_from_memory(LOGO)
+        .context("Failed to decode built-in logo")?
+        .to_rgba8();
+    let scale = (width as f32 * LOGO_WIDTH / logo.width() as f32).min(1.0);
+    if scale < 1.0 {
+        let w = ((logo.width() as f32 * scale).round() as u32).max(1);
+        let h = ((logo.height() as f32 * scale).round() as u32).max(1);
+        logo = image::imageops::resize(&logo, w, h, image::imageops::FilterType::Triangle);
+    }
+
+    let mut canvas = RgbaImage::from_pixel(width, height, image::Rgba(BACKGROUND));
+    let x = (width as i64 - logo.width() as i64) / 2;
+    let y = (height as i64 - logo.height() as i64) / 2;
+    image::imageops::overlay(&mut canvas, &logo, x, y);
+    Ok(canvas)
+}
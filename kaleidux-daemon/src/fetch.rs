@@ -0,0 +1,306 @@
+use crate::orchestration::{FetchConfig, FetchProvider, RemoteConfig};
+use crate::queue::SmartQueue;
+use anyhow::{bail, Context, Result};
+use kaleidux_common::Response;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+use tracing::warn;
+
+/// Shortest `interval` honored, to stay within the services' rate limits
+const MIN_INTERVAL: Duration = Duration::from_secs(5 * 60);
+/// Next try after a failed pull, unless `interval` is shorter
+const RETRY_DELAY: Duration = Duration::from_secs(15 * 60);
+
+fn fetch_dir() -> Option<PathBuf> {
+    crate::cache::cache_root().map(|d| d.join("fetched"))
+}
+
+/// The fetch folder after a pull
+pub struct Pulled {
+    pub provider: FetchProvider,
+    pub playlist: String,
+    /// Every wallpaper in the folder, sorted
+    pub paths: Vec<PathBuf>,
+    pub added: usize,
+    /// Pruned by `max-age` or `max-size`; still on disk until
+    /// `MonitorManager::delete_files` has dropped them from the queues
+    pub removed: Vec<PathBuf>,
+}
+
+/// Downloads the wallpapers a search for `config` returns that aren't in the fetch
+/// folder yet, then picks what to prune by `max-age` and `max-size`. Blocking; call
+/// from spawn_blocking.
+pub fn pull(config: &FetchConfig, remote: &RemoteConfig) -> Result<Pulled> {
+    let dir = fetch_dir().context("Failed to get cache directory")?;
+    std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let listing = dir.join(".tmp-search.json");
+    crate::remote::download(&search_url(config), &auth_headers(config), remote, &listing)
+        .with_context(|| format!("{} search failed", config.provider.name()))?;
+    let json = std::fs::read(&listing);
+    let _ = std::fs::remove_file(&listing);
+    let json: serde_json::Value = serde_json::from_slice(&json?)
+        .with_context(|| format!("Unexpected {} reply", config.provider.name()))?;
+
+    let mut added = 0;
+    for (id, url) in results(config.provider, &json)?
+        .into_iter()
+        .take(config.count)
+    {
+        let id: String = id
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+            .collect();
+        let name = format!("{}-{}.{}", config.provider.name(), id, extension(&url));
+        let target = dir.join(name);
+        if target.exists() {
+            continue;
+        }
+        match crate::remote::download(&url, &[], remote, &target) {
+            Ok(()) if SmartQueue::get_content_type(&target).is_some() => added += 1,
+            Ok(()) => {
+                let _ = std::fs::remove_file(&target);
+                warn!("[FETCH] {}: not a supported image, skipped", url);
+            }
+            Err(e) => warn!("[FETCH] {}: {:#}", url, e),
+        }
+    }
+
+    let removed = prune(&dir, config);
+    let mut paths: Vec<PathBuf> = wallpapers(&dir)
+        .into_iter()
+        .map(|(p, _)| p)
+        .filter(|p| !removed.contains(p))
+        .collect();
+    paths.sort();
+    Ok(Pulled {
+        provider: config.provider,
+        playlist: config.playlist.clone(),
+        paths,
+        added,
+        removed,
+    })
+}
+
+/// Search request for `config`, returning random results. The API key goes in a
+/// header (see `auth_headers`), never in the URL.
+fn search_url(config: &FetchConfig) -> String {
+    let query = encode(&config.query);
+    match config.provider {
+        FetchProvider::Wallhaven => {
+            let purity: String = ["sfw", "sketchy", "nsfw"]
+                .iter()
+                .map(|level| {
+                    if config.purity.iter().any(|p| p == level) {
+                        '1'
+                    } else {
+                        '0'
+                    }
+                })
+                .collect();
+            let mut url = format!(
+                "https://wallhaven.cc/api/v1/search?sorting=random&categories=111&purity={}&q={}",
+                purity, query
+            );
+            if let Some(ratio) = &config.ratio {
+                url.push_str(&format!("&ratios={}", encode(ratio)));
+            }
+            url
+        }
+        FetchProvider::Unsplash => {
+            let strict = config.purity.iter().all(|p| p == "sfw");
+            let mut url = format!(
+                "https://api.unsplash.com/photos/random?count={}&content_filter={}",
+                config.count.clamp(1, 30),
+                if strict { "high" } else { "low" },
+            );
+            if !query.is_empty() {
+                url.push_str(&format!("&query={}", query));
+            }
+            if let Some(orientation) = config.ratio.as_deref().and_then(orientation) {
+                url.push_str(&format!("&orientation={}", orientation));
+            }
+            url
+        }
+    }
+}
+
+/// Headers authenticating a search with the `api-key`, if one is set
+fn auth_headers(config: &FetchConfig) -> Vec<String> {
+    let Some(key) = config.api_key.as_deref() else {
+        return Vec::new();
+    };
+    vec![match config.provider {
+        FetchProvider::Wallhaven => format!("X-API-Key: {}", key),
+        FetchProvider::Unsplash => format!("Authorization: Client-ID {}", key),
+    }]
+}
+
+/// Unsplash orientation for a `ratio`: "16x9" is landscape, "9x16" portrait
+fn orientation(ratio: &str) -> Option<&'static str> {
+    match ratio {
+        "landscape" => return Some("landscape"),
+        "portrait" => return Some("portrait"),
+        "squarish" => return Some("squarish"),
+        _ => {}
+    }
+    let (width, height) = ratio.split_once('x')?;
+    let (width, height): (f32, f32) = (width.trim().parse().ok()?, height.trim().parse().ok()?);
+    Some(if width > height {
+        "landscape"
+    } else if width < height {
+        "portrait"
+    } else {
+        "squarish"
+    })
+}
+
+/// (id, image URL) of each result in a search reply
+fn results(provider: FetchProvider, json: &serde_json::Value) -> Result<Vec<(String, String)>> {
+    let (items, image) = match provider {
+        FetchProvider::Wallhaven => (json.get("data"), "/path"),
+        FetchProvider::Unsplash => (Some(json), "/urls/full"),
+    };
+    let Some(items) = items.and_then(|items| items.as_array()) else {
+        bail!("Unexpected {} reply: {}", provider.name(), json);
+    };
+    Ok(items
+        .iter()
+        .filter_map(|item| {
+            let id = item.get("id")?.as_str()?;
+            let url = item.pointer(image)?.as_str()?;
+            Some((id.to_string(), url.to_string()))
+        })
+        .collect())
+}
+
+/// File extension of an image URL, "jpg" if it has none (Unsplash)
+fn extension(url: &str) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    Path::new(path.rsplit('/').next().unwrap_or_default())
+        .extension()
+        .and_then(|e| e.to_str())
+        .filter(|e| (1..=4).contains(&e.len()) && e.chars().all(|c| c.is_ascii_alphanumeric()))
+        .unwrap_or("jpg")
+        .to_ascii_lowercase()
+}
+
+/// Percent-encodes a query parameter
+fn encode(text: &str) -> String {
+    text.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Files in the fetch folder with their metadata, leaving out temp files
+fn wallpapers(dir: &Path) -> Vec<(PathBuf, std::fs::Metadata)> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|e| !e.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(|e| Some((e.path(), e.metadata().ok().filter(|m| m.is_file())?)))
+        .collect()
+}
+
+/// Wallpapers past `max-age`, then the oldest while the folder is bigger than
+/// `max-size`. Only picks them; the main loop deletes them once no queue holds them.
+fn prune(dir: &Path, config: &FetchConfig) -> Vec<PathBuf> {
+    let now = SystemTime::now();
+    let limit = config
+        .max_size
+        .as_deref()
+        .and_then(|size| crate::cache::parse_size(size).ok());
+    let mut files: Vec<(SystemTime, u64, PathBuf)> = wallpapers(dir)
+        .into_iter()
+        .map(|(path, meta)| (meta.modified().unwrap_or(now), meta.len(), path))
+        .collect();
+    files.sort();
+    let mut size: u64 = files.iter().map(|(_, len, _)| len).sum();
+    let mut removed = Vec::new();
+    for (modified, len, path) in files {
+        let expired = config
+            .max_age
+            .is_some_and(|age| now.duration_since(modified).unwrap_or_default() > age);
+        let over = limit.is_some_and(|limit| size > limit);
+        if expired || over {
+            size -= len;
+            removed.push(path);
+        }
+    }
+    removed
+}
+
+/// Runs `[fetch]` pulls: on startup, every `interval` after that and on `kldctl fetch
+/// now`. Pulls run on the blocking pool; `poll` hands over the one that finished.
+pub struct Fetcher {
+    due: Option<Instant>,
+    running: bool,
+    /// `kldctl fetch now` requests answered when the running pull is done
+    waiting: Vec<tokio::sync::oneshot::Sender<Response>>,
+    done_tx: std::sync::mpsc::Sender<Result<Pulled>>,
+    done_rx: std::sync::mpsc::Receiver<Result<Pulled>>,
+}
+
+impl Fetcher {
+    pub fn new() -> Self {
+        let (done_tx, done_rx) = std::sync::mpsc::channel();
+        Self {
+            due: None,
+            running: false,
+            waiting: Vec::new(),
+            done_tx,
+            done_rx,
+        }
+    }
+
+    /// Starts a pull unless one is running already; `reply` waits for its outcome
+    pub fn start(
+        &mut self,
+        config: &FetchConfig,
+        remote: &RemoteConfig,
+        reply: Option<tokio::sync::oneshot::Sender<Response>>,
+    ) {
+        self.waiting.extend(reply);
+        if self.running {
+            return;
+        }
+        self.running = true;
+        let (config, remote) = (config.clone(), remote.clone());
+        let done_tx = self.done_tx.clone();
+        tokio::task::spawn_blocking(move || {
+            let _ = done_tx.send(pull(&config, &remote));
+        });
+    }
+
+    /// The pull that finished since the last call, with the requests waiting for it.
+    /// Starts the next one when it is due.
+    #[allow(clippy::type_complexity)]
+    pub fn poll(
+        &mut self,
+        config: Option<&FetchConfig>,
+        remote: &RemoteConfig,
+    ) -> Option<(Result<Pulled>, Vec<tokio::sync::oneshot::Sender<Response>>)> {
+        if let Ok(result) = self.done_rx.try_recv() {
+            self.running = false;
+            let interval = config.map_or(MIN_INTERVAL, |c| c.interval.max(MIN_INTERVAL));
+            let delay = match &result {
+                Ok(_) => interval,
+                Err(_) => RETRY_DELAY.min(interval),
+            };
+            self.due = Some(Instant::now() + delay);
+            return Some((result, std::mem::take(&mut self.waiting)));
+        }
+        if let Some(config) = config {
+            if !self.running && self.due.is_none_or(|due| due <= Instant::now()) {
+                self.start(config, remote, None);
+            }
+        }
+        None
+    }
+}
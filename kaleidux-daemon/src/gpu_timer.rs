@@ -0,0 +1,199 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+
+/// Query slots: transition pass begin/end, then blit pass begin/end
+const QUERY_COUNT: u32 = 4;
+/// Resolve targets must be aligned to this, so each pass gets its own block
+const BLOCK: u64 = wgpu::QUERY_RESOLVE_BUFFER_ALIGNMENT;
+
+const IDLE: u8 = 0;
+const MAPPING: u8 = 1;
+const MAPPED: u8 = 2;
+const FAILED: u8 = 3;
+
+/// GPU time of one measured frame, in milliseconds
+pub struct GpuFrameTime {
+    /// Transition shader that ran and the time its pass took
+    pub transition: Option<(String, f64)>,
+    /// All passes of the frame
+    pub total_ms: f64,
+}
+
+/// A frame whose passes have timestamps written or resolved
+struct Frame {
+    transition: Option<String>,
+    blit: bool,
+}
+
+/// Measures how long an output's render passes take on the GPU, using timestamp
+/// queries written at the start and end of each pass. CPU-side frame times only show
+/// command recording; this shows what a transition shader actually costs.
+///
+/// One frame is measured at a time: while its timestamps are read back, further
+/// frames go untimed, so measuring never makes rendering wait on the GPU.
+pub struct GpuTimer {
+    query_set: wgpu::QuerySet,
+    resolve: wgpu::Buffer,
+    readback: wgpu::Buffer,
+    /// Nanoseconds per timestamp tick
+    period: f64,
+    state: Arc<AtomicU8>,
+    /// Passes recorded into the current frame's encoder
+    recording: Option<Frame>,
+    /// Frame being read back
+    in_flight: Option<Frame>,
+}
+
+impl GpuTimer {
+    /// None if the device was created without `TIMESTAMP_QUERY`
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Option<Self> {
+        if !device.features().contains(wgpu::Features::TIMESTAMP_QUERY) {
+            return None;
+        }
+        let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("GPU Timer Queries"),
+            ty: wgpu::QueryType::Timestamp,
+            count: QUERY_COUNT,
+        });
+        let buffer = |label, usage| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size: 2 * BLOCK,
+                usage,
+                mapped_at_creation: false,
+            })
+        };
+        Some(Self {
+            query_set,
+            resolve: buffer(
+                "GPU Timer Resolve",
+                wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            ),
+            readback: buffer(
+                "GPU Timer Readback",
+                wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            ),
+            period: queue.get_timestamp_period() as f64,
+            state: Arc::new(AtomicU8::new(IDLE)),
+            recording: None,
+            in_flight: None,
+        })
+    }
+
+    /// Timestamp writes for the transition pass running `transition`, if this frame
+    /// is measured
+    pub fn transition_pass(
+        &mut self,
+        transition: &str,
+    ) -> Option<wgpu::RenderPassTimestampWrites<'_>> {
+        if self.in_flight.is_some() {
+            return None;
+        }
+        self.recording
+            .get_or_insert(Frame {
+                transition: None,
+                blit: false,
+            })
+            .transition = Some(transition.to_string());
+        Some(self.writes(0))
+    }
+
+    /// Timestamp writes for the blit pass, if this frame is measured
+    pub fn blit_pass(&mut self) -> Option<wgpu::RenderPassTimestampWrites<'_>> {
+        if self.in_flight.is_some() {
+            return None;
+        }
+        self.recording
+            .get_or_insert(Frame {
+                transition: None,
+                blit: false,
+            })
+            .blit = true;
+        Some(self.writes(2))
+    }
+
+    fn writes(&self, first: u32) -> wgpu::RenderPassTimestampWrites<'_> {
+        wgpu::RenderPassTimestampWrites {
+            query_set: &self.query_set,
+            beginning_of_pass_write_index: Some(first),
+            end_of_pass_write_index: Some(first + 1),
+        }
+    }
+
+    /// Copies the frame's timestamps out for reading; call before `encoder` is finished.
+    /// Only written queries are resolved, one block per pass.
+    pub fn resolve(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        let Some(frame) = self.recording.take() else {
+            return;
+        };
+        if frame.transition.is_some() {
+            encoder.resolve_query_set(&self.query_set, 0..2, &self.resolve, 0);
+        }
+        if frame.blit {
+            encoder.resolve_query_set(&self.query_set, 2..4, &self.resolve, BLOCK);
+        }
+        encoder.copy_buffer_to_buffer(&self.resolve, 0, &self.readback, 0, 2 * BLOCK);
+        self.in_flight = Some(frame);
+    }
+
+    /// Starts reading back a resolved frame; call after the encoder was submitted
+    pub fn submitted(&mut self) {
+        if self.in_flight.is_none() || self.state.load(Ordering::Acquire) != IDLE {
+            return;
+        }
+        self.state.store(MAPPING, Ordering::Release);
+        let state = self.state.clone();
+        self.readback
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                let next = if result.is_ok() { MAPPED } else { FAILED };
+                state.store(next, Ordering::Release);
+            });
+    }
+
+    /// Starts a frame, dropping passes recorded for one that was never submitted.
+    /// Returns the times of a frame read back since the last call, if any; the device
+    /// has to be polled in between (the main loop does).
+    pub fn begin_frame(&mut self) -> Option<GpuFrameTime> {
+        self.recording = None;
+        match self.state.load(Ordering::Acquire) {
+            MAPPED => {}
+            MAPPING => return None,
+            FAILED => {
+                self.state.store(IDLE, Ordering::Release);
+                self.in_flight = None;
+                return None;
+            }
+            // Resolved, but the encoder was dropped instead of submitted
+            _ => {
+                self.in_flight = None;
+                return None;
+            }
+        }
+        let frame = self.in_flight.take()?;
+        let ticks: Vec<u64> = {
+            let data = self.readback.slice(..).get_mapped_range();
+            data.chunks_exact(8)
+                .map(|b| u64::from_le_bytes(b.try_into().expect("8-byte chunk")))
+                .collect()
+        };
+        self.readback.unmap();
+        self.state.store(IDLE, Ordering::Release);
+
+        // Some drivers report an end before the beginning for very short passes
+        let to_ms = |begin: u64, end: u64| end.saturating_sub(begin) as f64 * self.period / 1e6;
+        let blit_slot = (BLOCK / 8) as usize;
+        let transition = frame
+            .transition
+            .map(|name| (name, to_ms(ticks[0], ticks[1])));
+        let blit_ms = if frame.blit {
+            to_ms(ticks[blit_slot], ticks[blit_slot + 1])
+        } else {
+            0.0
+        };
+        Some(GpuFrameTime {
+            total_ms: blit_ms + transition.as_ref().map_or(0.0, |t| t.1),
+            transition,
+        })
+    }
+}
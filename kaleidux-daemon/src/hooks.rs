@@ -0,0 +1,94 @@
+use crate::orchestration::OutputConfig;
+use anyhow::{Context, Result};
+use gst::prelude::*;
+use gstreamer as gst;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tracing::{debug, warn};
+
+/// Longest a cue sound may play; a long file picked by mistake is cut off here
+const MAX_SOUND_SECONDS: u64 = 30;
+
+/// Batch the last cue fired for. A synchronized switch reaches every output with the
+/// same batch id, and should sound (and run its command) once, not once per output.
+static LAST_BATCH: AtomicU64 = AtomicU64::new(0);
+
+/// `on-change-sound` / `on-change-command`: plays the cue and runs the command when
+/// `name` switches to `path`. Returns immediately; both run in the background.
+pub fn on_change(name: &str, path: &Path, config: &OutputConfig, batch_id: Option<u64>) {
+    if config.on_change_sound.is_none() && config.on_change_command.is_none() {
+        return;
+    }
+    if let Some(batch) = batch_id {
+        if LAST_BATCH.swap(batch, Ordering::Relaxed) == batch {
+            debug!("[HOOK] {}: Cue already fired for this batch", name);
+            return;
+        }
+    }
+
+    if let Some(sound) = config.on_change_sound.clone() {
+        let volume = config.on_change_volume;
+        let name = name.to_string();
+        // Its own thread: the bus wait blocks for the length of the sound
+        std::thread::spawn(move || {
+            if let Err(e) = play(&sound, volume) {
+                warn!("[HOOK] {}: Failed to play {:?}: {:#}", name, sound, e);
+            }
+        });
+    }
+
+    if let Some(command) = &config.on_change_command {
+        let cmdline = command
+            .replace("{output}", &crate::preprocess::shell_quote(Path::new(name)))
+            .replace("{path}", &crate::preprocess::shell_quote(path));
+        debug!("[HOOK] {}: Running {}", name, cmdline);
+        match tokio::process::Command::new("sh")
+            .arg("-c")
+            .arg(&cmdline)
+            .stdin(std::process::Stdio::null())
+            .spawn()
+        {
+            Ok(mut child) => {
+                let name = name.to_string();
+                tokio::spawn(async move {
+                    match child.wait().await {
+                        Ok(status) if !status.success() => {
+                            warn!("[HOOK] {}: on-change-command exited with {}", name, status)
+                        }
+                        Ok(_) => {}
+                        Err(e) => warn!("[HOOK] {}: on-change-command failed: {}", name, e),
+                    }
+                });
+            }
+            Err(e) => warn!("[HOOK] {}: Failed to spawn on-change-command: {}", name, e),
+        }
+    }
+}
+
+/// Plays an audio file to the end (or `MAX_SOUND_SECONDS`) at `volume` (0-100)
+fn play(path: &Path, volume: u8) -> Result<()> {
+    gst::init()?;
+    let absolute = std::path::absolute(path)?;
+    let uri = gst::glib::filename_to_uri(&absolute, None)?;
+
+    let playbin = gst::ElementFactory::make("playbin").build()?;
+    playbin.set_property("uri", uri.as_str());
+    // Audio only: cover art in the file must not open a video window
+    playbin.set_property_from_str("flags", "audio");
+    playbin.set_property("volume", volume as f64 / 100.0);
+    playbin.set_state(gst::State::Playing)?;
+
+    let bus = playbin.bus().context("playbin has no bus")?;
+    let result = match bus.timed_pop_filtered(
+        gst::ClockTime::from_seconds(MAX_SOUND_SECONDS),
+        &[gst::MessageType::Eos, gst::MessageType::Error],
+    ) {
+        Some(msg) => match msg.view() {
+            gst::MessageView::Error(err) => Err(anyhow::anyhow!("{}", err.error())),
+            _ => Ok(()),
+        },
+        None => Ok(()),
+    };
+    playbin.set_state(gst::State::Null)?;
+    result
+}
@@ -0,0 +1,142 @@
+use anyhow::{bail, Context, Result};
+use kaleidux_common::Request;
+use std::fs::File;
+use std::io::{Read, Seek, Write};
+use std::os::unix::io::AsRawFd;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tracing::{info, warn};
+
+/// How long `--replace` waits for the running daemon to release its lock
+const REPLACE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Path of a per-user, per-session runtime file (XDG_RUNTIME_DIR, falling back to /tmp).
+/// Keyed by the display (see `kaleidux_common::session_tag`) so each graphical session
+/// gets its own lock and socket.
+fn runtime_file(name: &str) -> PathBuf {
+    let file_name = kaleidux_common::runtime_file_name(name);
+    dirs::runtime_dir()
+        .map(|d| d.join(&file_name))
+        .unwrap_or_else(|| {
+            let uid = std::env::var("USER").unwrap_or_else(|_| "kaleidux".to_string());
+            PathBuf::from(format!("/tmp/{}-{}", uid, file_name))
+        })
+}
+
+pub fn socket_path() -> PathBuf {
+    runtime_file("sock")
+}
+
+pub fn lock_path() -> PathBuf {
+    runtime_file("lock")
+}
+
+pub fn state_path() -> PathBuf {
+    runtime_file("state.json")
+}
+
+/// Exclusive flock() on the runtime lock file, held for the lifetime of the daemon.
+/// The kernel drops the lock when the process exits, so a crashed daemon never leaves it stale.
+pub struct InstanceLock {
+    _file: File,
+}
+
+impl InstanceLock {
+    /// Takes the instance lock. If another daemon holds it, either fails with a clear
+    /// error or, with `replace`, asks it to shut down over IPC and waits for it to exit.
+    pub async fn acquire(replace: bool) -> Result<Self> {
+        let path = lock_path();
+        let mut file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .with_context(|| format!("Failed to open lock file {:?}", path))?;
+
+        if !try_lock(&file)? {
+            let pid = read_pid(&mut file);
+            let pid_str = pid.map(|p| p.to_string()).unwrap_or_else(|| "unknown".into());
+
+            if !replace {
+                bail!(
+                    "Another kaleidux-daemon is already running (pid {}). \
+                     Use --replace to take over, or `kldctl kill --wait` to stop it.",
+                    pid_str
+                );
+            }
+
+            info!(
+                "[STARTUP] Replacing running daemon (pid {}), asking it to exit...",
+                pid_str
+            );
+            if let Err(e) = request_kill().await {
+                warn!("[STARTUP] Failed to send kill request to running daemon: {}", e);
+            }
+
+            let start = Instant::now();
+            loop {
+                if try_lock(&file)? {
+                    break;
+                }
+                if start.elapsed() >= REPLACE_TIMEOUT {
+                    bail!(
+                        "Timed out after {:?} waiting for the running daemon (pid {}) to exit",
+                        REPLACE_TIMEOUT,
+                        pid_str
+                    );
+                }
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+            info!(
+                "[STARTUP] Previous daemon exited after {:.2}s",
+                start.elapsed().as_secs_f64()
+            );
+        }
+
+        // Record our PID for error messages of later instances
+        file.set_len(0)?;
+        file.rewind()?;
+        write!(file, "{}", std::process::id())?;
+        file.flush()?;
+
+        Ok(Self { _file: file })
+    }
+}
+
+/// Non-blocking exclusive lock. Returns false if another process holds it.
+fn try_lock(file: &File) -> Result<bool> {
+    let ret = unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) };
+    if ret == 0 {
+        return Ok(true);
+    }
+    let err = std::io::Error::last_os_error();
+    if err.kind() == std::io::ErrorKind::WouldBlock {
+        Ok(false)
+    } else {
+        Err(err).context("Failed to lock instance file")
+    }
+}
+
+fn read_pid(file: &mut File) -> Option<u32> {
+    let mut content = String::new();
+    file.rewind().ok()?;
+    file.read_to_string(&mut content).ok()?;
+    content.trim().parse().ok()
+}
+
+/// Sends a Kill request to the daemon currently owning the socket
+async fn request_kill() -> Result<()> {
+    let mut stream = tokio::net::UnixStream::connect(socket_path()).await?;
+    let req = serde_json::to_string(&Request::Kill)?;
+    stream.write_all(req.as_bytes()).await?;
+    stream.write_all(b"\n").await?;
+    let mut response = String::new();
+    let _ = tokio::time::timeout(
+        Duration::from_secs(2),
+        stream.read_to_string(&mut response),
+    )
+    .await;
+    Ok(())
+}
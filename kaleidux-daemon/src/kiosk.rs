@@ -0,0 +1,190 @@
+use crate::cache::FileCache;
+use crate::orchestration::KioskConfig;
+use crate::queue::{ContentType, SmartQueue};
+use anyhow::{bail, Result};
+use kaleidux_common::{CommandError, ErrorCode};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+/// Shortest item time; keeps a `0s` item from stalling the resume walk
+const MIN_ITEM_DURATION: Duration = Duration::from_secs(1);
+/// A switch this late (suspend, long pause) restarts the schedule from now instead of
+/// catching up through every item missed meanwhile
+const MAX_LATENESS: Duration = Duration::from_secs(1);
+
+/// Signage player for `[kiosk]`: walks the configured items in order, switching exactly
+/// when an item's duration is up (timed from the switch, not the end of the transition,
+/// so the loop never drifts). The position is saved on every switch and restored on
+/// startup, so a restarted daemon continues where the loop would be by now.
+pub struct Kiosk {
+    items: Vec<(PathBuf, ContentType, Duration)>,
+    index: usize,
+    started: Instant,
+    /// The current item still has to be sent to the outputs (startup, reload)
+    pending: bool,
+    /// No saved position was restored: the first item's time starts when it is shown
+    fresh: bool,
+    cache: Arc<FileCache>,
+}
+
+impl Kiosk {
+    /// None if no item is a playable image or video
+    pub fn new(config: &KioskConfig, cache: Arc<FileCache>) -> Option<Self> {
+        let items: Vec<_> = config
+            .items
+            .iter()
+            .filter_map(|item| match SmartQueue::get_content_type(&item.path) {
+                Some(content_type) => Some((
+                    item.path.clone(),
+                    content_type,
+                    item.duration
+                        .unwrap_or(config.duration)
+                        .max(MIN_ITEM_DURATION),
+                )),
+                None => {
+                    warn!("[KIOSK] Skipping {:?}: not an image or video", item.path);
+                    None
+                }
+            })
+            .collect();
+        if items.is_empty() {
+            warn!("[KIOSK] No playable items, kiosk mode disabled");
+            return None;
+        }
+
+        let mut kiosk = Self {
+            items,
+            index: 0,
+            started: Instant::now(),
+            pending: true,
+            fresh: true,
+            cache,
+        };
+        kiosk.resume();
+        Some(kiosk)
+    }
+
+    /// Moves to where the loop is now, given the saved item and when it started
+    fn resume(&mut self) {
+        let (index, path, started_ms) = match self.cache.get_kiosk_state() {
+            Ok(Some(state)) => state,
+            Ok(None) => return,
+            Err(e) => {
+                warn!("[KIOSK] Failed to read saved position: {}", e);
+                return;
+            }
+        };
+        // The saved index only counts if it is still the same file (items may have
+        // been edited since); otherwise look the file up, or start over
+        let index = if self.items.get(index).is_some_and(|item| item.0 == path) {
+            index
+        } else if let Some(index) = self.items.iter().position(|item| item.0 == path) {
+            index
+        } else {
+            info!(
+                "[KIOSK] Saved item {:?} is no longer listed, starting over",
+                path
+            );
+            return;
+        };
+
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let total: Duration = self.items.iter().map(|item| item.2).sum();
+        let elapsed = Duration::from_millis(now_ms.saturating_sub(started_ms));
+        let mut elapsed = Duration::from_nanos((elapsed.as_nanos() % total.as_nanos()) as u64);
+        let mut index = index;
+        while elapsed >= self.items[index].2 {
+            elapsed -= self.items[index].2;
+            index = (index + 1) % self.items.len();
+        }
+
+        self.index = index;
+        self.started = Instant::now()
+            .checked_sub(elapsed)
+            .unwrap_or_else(Instant::now);
+        self.fresh = false;
+        info!(
+            "[KIOSK] Resuming at item {} ({:?}), {:.1}s in",
+            index,
+            self.items[index].0,
+            elapsed.as_secs_f64()
+        );
+    }
+
+    /// Item to switch every output to, if one is due
+    pub fn tick(&mut self, now: Instant) -> Option<(PathBuf, ContentType)> {
+        if self.pending {
+            let start = if self.fresh { now } else { self.started };
+            self.fresh = false;
+            return Some(self.show(self.index, start));
+        }
+        let due = self.started + self.items[self.index].2;
+        if now < due {
+            return None;
+        }
+        let start = if now.duration_since(due) <= MAX_LATENESS {
+            due
+        } else {
+            now
+        };
+        Some(self.show((self.index + 1) % self.items.len(), start))
+    }
+
+    /// Jumps to item `index` (0-based) with a fresh duration
+    pub fn goto(&mut self, index: usize) -> Result<(PathBuf, ContentType)> {
+        if index >= self.items.len() {
+            bail!(CommandError::new(
+                ErrorCode::InvalidArgument,
+                format!(
+                    "Kiosk has {} items, index must be 0-{}",
+                    self.items.len(),
+                    self.items.len() - 1
+                )
+            ));
+        }
+        Ok(self.show(index, Instant::now()))
+    }
+
+    /// Moves `forward` or back one item (next/prev), wrapping around
+    pub fn step(&mut self, forward: bool) -> (PathBuf, ContentType) {
+        let len = self.items.len();
+        let index = if forward {
+            (self.index + 1) % len
+        } else {
+            (self.index + len - 1) % len
+        };
+        self.show(index, Instant::now())
+    }
+
+    /// Sends the current item again on the next tick (an output was added)
+    pub fn resend(&mut self) {
+        self.pending = true;
+    }
+
+    fn show(&mut self, index: usize, start: Instant) -> (PathBuf, ContentType) {
+        self.index = index;
+        self.started = start;
+        self.pending = false;
+        let (path, content_type, duration) = &self.items[index];
+        info!(
+            "[KIOSK] Showing item {}: {:?} for {:?}",
+            index, path, duration
+        );
+
+        let started_ms = SystemTime::now()
+            .checked_sub(Instant::now().saturating_duration_since(start))
+            .unwrap_or_else(SystemTime::now)
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        if let Err(e) = self.cache.set_kiosk_state(index, path, started_ms) {
+            warn!("[KIOSK] Failed to save position: {}", e);
+        }
+        (path.clone(), *content_type)
+    }
+}
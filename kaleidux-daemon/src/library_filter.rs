@@ -0,0 +1,137 @@
+use serde::Deserialize;
+use std::path::{Component, Path};
+
+/// Which files under a wallpaper folder join the queue: `max-depth`, `include` and
+/// `exclude`, all relative to the folder
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LibraryFilter {
+    pub max_depth: Option<usize>,
+    pub include: Vec<Pattern>,
+    pub exclude: Vec<Pattern>,
+}
+
+impl LibraryFilter {
+    /// Whether nothing is filtered out
+    pub fn is_empty(&self) -> bool {
+        self.max_depth.is_none() && self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// Whether `path`, found under the folder `root`, is let through. Paths outside
+    /// `root` always are.
+    pub fn allows(&self, root: &Path, path: &Path) -> bool {
+        let Ok(relative) = path.strip_prefix(root) else {
+            return true;
+        };
+        if self
+            .max_depth
+            .is_some_and(|depth| relative.components().count() > depth)
+        {
+            return false;
+        }
+        let relative = relative.to_string_lossy();
+        (self.include.is_empty() || self.include.iter().any(|p| p.matches(&relative, false)))
+            && !self.exclude.iter().any(|p| p.matches(&relative, true))
+    }
+}
+
+/// An `include`/`exclude` entry: a glob, or a regex after `regex:`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Pattern {
+    text: String,
+    regex: regex::Regex,
+    /// A glob without `/`, matched against names rather than the relative path
+    name_only: bool,
+}
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.text == other.text
+    }
+}
+
+impl TryFrom<String> for Pattern {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        let (regex, name_only) = match text.strip_prefix("regex:") {
+            Some(regex) => (regex::Regex::new(regex), false),
+            None => {
+                let glob = text.trim_start_matches('/');
+                (glob_regex(glob), !glob.contains('/'))
+            }
+        };
+        let regex = regex.map_err(|e| format!("invalid pattern '{}': {}", text, e))?;
+        Ok(Self {
+            text,
+            regex,
+            name_only,
+        })
+    }
+}
+
+impl Pattern {
+    /// Whether `relative` (a path below the folder) matches. A name-only glob looks at
+    /// the file name, or with `any_folder` at the name of every folder on the way too,
+    /// so excluding `screenshots` leaves out everything in such folders.
+    fn matches(&self, relative: &str, any_folder: bool) -> bool {
+        if !self.name_only {
+            return self.regex.is_match(relative);
+        }
+        let path = Path::new(relative);
+        if !any_folder {
+            return path
+                .file_name()
+                .is_some_and(|name| self.regex.is_match(&name.to_string_lossy()));
+        }
+        path.components().any(|c| match c {
+            Component::Normal(name) => self.regex.is_match(&name.to_string_lossy()),
+            _ => false,
+        })
+    }
+}
+
+/// A glob as an anchored regex: `*` and `?` stay within one folder, `**` crosses any
+/// number of them, `[...]` is a character class and `{a,b}` either alternative.
+/// Shared by library filters, playlist files and `--output` values.
+pub fn glob_regex(glob: &str) -> Result<regex::Regex, regex::Error> {
+    let mut pattern = String::from("^");
+    let mut chars = glob.chars().peekable();
+    let mut braces = 0;
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                // `**/` also matches no folder at all
+                if chars.next_if_eq(&'/').is_some() {
+                    pattern.push_str("(?:.*/)?");
+                } else {
+                    pattern.push_str(".*");
+                }
+            }
+            '*' => pattern.push_str("[^/]*"),
+            '?' => pattern.push_str("[^/]"),
+            '[' => {
+                let class: String = chars.by_ref().take_while(|&c| c != ']').collect();
+                let class = class
+                    .strip_prefix('!')
+                    .map_or(class.clone(), |c| format!("^{}", c));
+                pattern.push('[');
+                pattern.push_str(&class.replace('\\', r"\\"));
+                pattern.push(']');
+            }
+            '{' => {
+                braces += 1;
+                pattern.push_str("(?:");
+            }
+            '}' if braces > 0 => {
+                braces -= 1;
+                pattern.push(')');
+            }
+            ',' if braces > 0 => pattern.push('|'),
+            _ => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    pattern.push('$');
+    regex::Regex::new(&pattern)
+}
@@ -0,0 +1,62 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+static NEXT_EXPORT: AtomicU64 = AtomicU64::new(0);
+/// Newest export started for each path; an older one finishing later is dropped
+static LATEST: once_cell::sync::Lazy<parking_lot::Mutex<HashMap<PathBuf, u64>>> =
+    once_cell::sync::Lazy::new(Default::default);
+
+/// Encodes `frame` on the blocking pool and puts it at `path` (`lock-image`) in one
+/// rename, so lock screens never read a half-written file. The format follows the
+/// extension; JPEG drops the alpha channel.
+pub fn export(name: String, path: PathBuf, frame: Arc<image::RgbaImage>) {
+    let id = NEXT_EXPORT.fetch_add(1, Ordering::Relaxed);
+    LATEST.lock().insert(path.clone(), id);
+    tokio::task::spawn_blocking(move || match write(&path, &frame, id) {
+        Ok(true) => debug!("[LOCK] {}: Wrote {}", name, path.display()),
+        Ok(false) => {}
+        Err(e) => warn!(
+            "[LOCK] {}: Failed to write {}: {:#}",
+            name,
+            path.display(),
+            e
+        ),
+    });
+}
+
+/// Whether the file was replaced; false when a newer export for it came along
+fn write(path: &Path, frame: &image::RgbaImage, id: u64) -> anyhow::Result<bool> {
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("lock-image must name a file"))?;
+    // Same extension, so the encoder picks the same format. The `.tmp-` prefix lets
+    // cache garbage collection sweep leftovers when the file is below the cache root.
+    let temp = path.with_file_name(format!(".tmp-{}.{}", id, file_name.to_string_lossy()));
+    let jpeg = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("jpg") || e.eq_ignore_ascii_case("jpeg"));
+    let saved = if jpeg {
+        image::DynamicImage::ImageRgba8(frame.clone())
+            .to_rgb8()
+            .save(&temp)
+    } else {
+        frame.save(&temp)
+    };
+    if let Err(e) = saved {
+        let _ = std::fs::remove_file(&temp);
+        return Err(e.into());
+    }
+    if LATEST.lock().get(path) != Some(&id) {
+        let _ = std::fs::remove_file(&temp);
+        return Ok(false);
+    }
+    std::fs::rename(&temp, path)?;
+    Ok(true)
+}
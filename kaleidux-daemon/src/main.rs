@@ -1,5 +1,7 @@
-use kaleidux_common::{Request, Response, Transition};
-use std::collections::HashMap;
+use kaleidux_common::{
+    CommandError, ErrorCode, FetchCommand, OutputResult, Request, Response, Transition,
+};
+use std::collections::{HashMap, HashSet};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
@@ -16,23 +18,120 @@ use x11rb::connection::Connection as X11Connection;
 #[global_allocator]
 static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
 
+/// Returns the free pages of every jemalloc arena to the OS now, instead of waiting for
+/// them to decay
+fn purge_allocator() {
+    // MALLCTL_ARENAS_ALL
+    let name = c"arena.4096.purge";
+    let ret = unsafe {
+        tikv_jemalloc_sys::mallctl(
+            name.as_ptr(),
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            0,
+        )
+    };
+    if ret != 0 {
+        debug!("[TRIM] jemalloc purge failed: error {}", ret);
+    }
+}
+
+/// Main loop period while every output is in `stop` standby
+const STANDBY_FRAME_TIME: std::time::Duration = std::time::Duration::from_millis(500);
+/// Main loop period while nothing animates. Commands and display events cut the sleep
+/// short; schedules, overlays and scripts are checked at least this often.
+const IDLE_FRAME_TIME: std::time::Duration = std::time::Duration::from_millis(250);
+/// How often `cache-max-size` is enforced in the background
+const CACHE_GC_INTERVAL: std::time::Duration = std::time::Duration::from_secs(600);
+/// How often `[playlist.NAME] active = ...` rules are re-evaluated
+const PLAYLIST_SCHEDULE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+/// How often the `publish-state` file is checked for changes
+const STATE_PUBLISH_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
+
 // Global semaphore to limit concurrent image decode tasks (prevents memory spikes)
 // Limit to 2 concurrent decodes since each can be 35-40MB
 static IMAGE_DECODE_SEMAPHORE: once_cell::sync::Lazy<Arc<Semaphore>> =
     once_cell::sync::Lazy::new(|| Arc::new(Semaphore::new(2)));
 
+/// One decode of an image, handed to every output waiting for it
+type SharedDecode = Arc<tokio::sync::OnceCell<Result<DecodedImage, String>>>;
+/// A file and the `preprocess` command it is decoded through
+type SourceKey = (PathBuf, Option<String>);
+/// A file, its `preprocess` command and the size it is scaled down to cover
+/// (`max-image-scale`)
+type DecodeKey = (SourceKey, Option<(u32, u32)>);
+
+// Decodes in flight. Outputs switching to the same file together (synchronized and
+// grouped queues) wait for one full-size decode of it, and for one scaled variant per
+// size limit, instead of each decoding it again.
+static IN_FLIGHT_SOURCES: once_cell::sync::Lazy<
+    parking_lot::Mutex<HashMap<SourceKey, SharedDecode>>,
+> = once_cell::sync::Lazy::new(Default::default);
+static IN_FLIGHT_DECODES: once_cell::sync::Lazy<
+    parking_lot::Mutex<HashMap<DecodeKey, SharedDecode>>,
+> = once_cell::sync::Lazy::new(Default::default);
+
+/// The decode of `key` in flight, joined or newly started
+fn join_decode<K: Eq + std::hash::Hash + Clone>(
+    in_flight: &parking_lot::Mutex<HashMap<K, SharedDecode>>,
+    key: &K,
+) -> SharedDecode {
+    in_flight.lock().entry(key.clone()).or_default().clone()
+}
+
+/// Forgets `decode` once it is done, so later switches to the file decode it afresh
+fn leave_decode<K: Eq + std::hash::Hash>(
+    in_flight: &parking_lot::Mutex<HashMap<K, SharedDecode>>,
+    key: &K,
+    decode: &SharedDecode,
+) {
+    let mut in_flight = in_flight.lock();
+    if in_flight.get(key).is_some_and(|d| Arc::ptr_eq(d, decode)) {
+        in_flight.remove(key);
+    }
+}
+
+mod animation;
+mod blur;
 mod cache;
+mod compositor;
+#[cfg(feature = "dbus")]
+mod dbus;
+mod doctor;
+mod events;
+mod failover;
+mod fetch;
+mod gpu_timer;
+mod hooks;
+mod instance;
+mod kiosk;
+mod library_filter;
+mod lock_image;
 mod metrics;
 mod monitor;
 mod monitor_manager;
 mod orchestration;
+mod overlay;
+mod palette;
+mod playlist_file;
+mod power;
+mod preprocess;
 mod queue;
+mod remote;
 mod renderer;
 mod scripting;
+mod sequence;
+mod session;
+mod shader_wallpaper;
 mod shaders;
+mod state;
+mod systemd;
+mod tone;
 mod video;
 mod wayland;
 mod x11;
+mod yuv;
 
 use std::path::{Path, PathBuf};
 use std::time::Instant;
@@ -40,15 +139,82 @@ use std::time::Instant;
 #[derive(Debug, Clone)]
 struct LoadedImage {
     name: String,
-    data: Option<Vec<u8>>,
+    /// RGBA rows, shared with every output that decoded the same file at once
+    data: Option<Arc<Vec<u8>>>,
+    width: u32,
+    height: u32,
+    path: PathBuf,
+    /// Why decoding failed, when `data` is None
+    error: Option<String>,
+    /// Measured for outputs on `transition = "auto"` and for `publish-state`
+    tone: Option<tone::Tone>,
+}
+
+#[derive(Debug, Clone)]
+struct DecodedImage {
+    data: Arc<Vec<u8>>,
     width: u32,
     height: u32,
-    _path: PathBuf,
+    tone: Option<tone::Tone>,
+    /// Size of the file before it was scaled down, if it was
+    source_size: Option<(u32, u32)>,
 }
 
 enum VideoPlayerResult {
-    Success(String, u64, video::VideoPlayer),
-    Failure(String, u64),
+    /// Output, session, source file and the started player
+    Success(String, u64, PathBuf, video::VideoPlayer),
+    /// Output, session, source file and error
+    Failure(String, u64, PathBuf, String),
+}
+
+/// A `kldctl wait` request parked until its outputs finish loading and transitioning
+struct PendingWait {
+    output: Option<String>,
+    deadline: Option<Instant>,
+    resp: tokio::sync::oneshot::Sender<Response>,
+}
+
+/// Answers every parked wait whose outputs are idle or whose timeout has passed
+fn resolve_waits(
+    waits: &mut Vec<PendingWait>,
+    renderers: &HashMap<String, renderer::Renderer>,
+) {
+    if waits.is_empty() {
+        return;
+    }
+    let now = Instant::now();
+    for wait in std::mem::take(waits) {
+        // Client went away (e.g. Ctrl+C on kldctl)
+        if wait.resp.is_closed() {
+            continue;
+        }
+        let busy = match &wait.output {
+            Some(name) => match renderers.get(name) {
+                Some(r) => r.is_busy(),
+                None => {
+                    let _ = wait.resp.send(Response::Error(
+                        CommandError::new(
+                            ErrorCode::UnknownOutput,
+                            format!("Output not found: {}", name),
+                        )
+                        .with_outputs(vec![name.clone()]),
+                    ));
+                    continue;
+                }
+            },
+            None => renderers.values().any(|r| r.is_busy()),
+        };
+        if !busy {
+            let _ = wait.resp.send(Response::Ok);
+        } else if wait.deadline.is_some_and(|d| now >= d) {
+            let _ = wait.resp.send(Response::error(
+                ErrorCode::Timeout,
+                "Timed out waiting for transitions to finish",
+            ));
+        } else {
+            waits.push(wait);
+        }
+    }
 }
 
 use chrono::Local;
@@ -72,7 +238,7 @@ fn switch_wallpaper_content(
     content_type: crate::queue::ContentType,
     next_session_id: &mut u64,
     frame_tx: &tokio::sync::mpsc::Sender<(Arc<String>, video::VideoEvent)>,
-    monitor_manager: &monitor_manager::MonitorManager,
+    monitor_manager: &mut monitor_manager::MonitorManager,
     renderers: &mut HashMap<String, renderer::Renderer>,
     video_players: &mut HashMap<String, video::VideoPlayer>,
     batch_id: Option<u64>,
@@ -81,7 +247,26 @@ fn switch_wallpaper_content(
     player_tx: &tokio::sync::mpsc::UnboundedSender<VideoPlayerResult>,
     log_prefix: &str,
 ) {
+    if monitor_manager.resolve_mirror(name) != name {
+        debug!(
+            "[MIRROR] {}: Ignoring {} switch, content follows mirrored output",
+            name, log_prefix
+        );
+        return;
+    }
+
     info!("{}: {} -> {:?}", log_prefix, name, path.display());
+    monitor_manager.remember_wallpaper(name, path, content_type);
+    for output in content_targets(name, monitor_manager) {
+        events::emit(kaleidux_common::Event::WallpaperChanged {
+            output,
+            path: path.display().to_string(),
+            content_type: content_type.name().to_string(),
+        });
+    }
+    if let Some(orchestrator) = monitor_manager.outputs.get(name) {
+        hooks::on_change(name, path, &orchestrator.config, batch_id);
+    }
     debug!(
         "[SWITCH] {}: content_type={:?}, renderer exists={}",
         name,
@@ -104,6 +289,27 @@ fn switch_wallpaper_content(
 
     // CRITICAL: Ensure renderer exists before switching content
     // This prevents race conditions where content is switched before renderer is ready
+    let one_shot = monitor_manager
+        .take_transition_override(name)
+        .unwrap_or_default();
+    let auto = one_shot.transition.is_none()
+        && monitor_manager
+            .outputs
+            .get(name)
+            .is_some_and(|o| matches!(o.config.transition, Transition::Auto));
+    let auto_pick = if auto {
+        let pick = monitor_manager.pick_auto_transition(name, path, content_type);
+        match &pick {
+            Some((_, true)) => debug!("[TRANSITION] {}: Auto picked a cut", name),
+            Some((t, false)) => debug!("[TRANSITION] {}: Auto picked {}", name, t.name()),
+            None => debug!("[TRANSITION] {}: Auto pick waits for the image", name),
+        }
+        pick
+    } else {
+        None
+    };
+
+    let max_size = decode_size_limit(name, monitor_manager, renderers);
     if let Some(r) = renderers.get_mut(name) {
         r.active_batch_id = batch_id;
         r.batch_start_time = batch_trigger_time;
@@ -111,24 +317,82 @@ fn switch_wallpaper_content(
 
         // Resolve Random transition if configured for this output
         if let Some(orchestrator) = monitor_manager.outputs.get(name) {
-            if matches!(orchestrator.config.transition, Transition::Random) {
-                let picked = Transition::pick_random();
+            let config = &orchestrator.config;
+            r.next_crop = random_crop(name, path, batch_id, config.random_crop);
+            r.next_focal = focal_point(path);
+            let mut transition = config.transition.clone();
+            // Timing is set on every switch, so a one-shot duration lasts one switch
+            let time = one_shot.time.unwrap_or(config.transition_time);
+            r.transition_duration = (time as f32 / 1000.0).max(0.001);
+            r.instant_transitions =
+                time == 0 || one_shot.cut || monitor_manager.power_cuts_transitions();
+            let overridden = one_shot.transition.is_some();
+            if let Some(picked) = one_shot.transition {
+                transition = picked;
+            } else if matches!(transition, Transition::Random) {
+                transition = monitor_manager.pick_random_transition();
                 debug!(
                     "[TRANSITION] {}: Resolved Random transition to: {}",
                     name,
-                    picked.name()
+                    transition.name()
                 );
-                r.active_transition = picked;
             }
+            if matches!(transition, Transition::Auto) {
+                // Stays Auto until the incoming image is measured (see apply_auto_transition)
+                if let Some((picked, cut)) = auto_pick {
+                    transition = picked;
+                    r.instant_transitions |= cut;
+                }
+            }
+            // A random pick may lack some of the parameters; only a fixed transition
+            // reports them as misconfigured. A one-shot transition is shown as given.
+            if !overridden && !config.transition_randomize.is_empty() {
+                match shaders::randomize_params(&transition, &config.transition_randomize) {
+                    Ok(randomized) => {
+                        debug!("[TRANSITION] {}: Randomized to {:?}", name, randomized);
+                        transition = randomized;
+                    }
+                    Err(e) if !matches!(config.transition, Transition::Random) => {
+                        warn!("[TRANSITION] {}: transition-randomize: {}", name, e)
+                    }
+                    Err(_) => {}
+                }
+            }
+            r.active_transition = transition;
         }
 
         r.switch_content();
+        r.expect_palette(path);
 
         if content_type == crate::queue::ContentType::Image {
             let name_clone = name.to_string();
             let path_clone = path.to_path_buf();
             let tx = image_tx.clone();
-            let semaphore = IMAGE_DECODE_SEMAPHORE.clone();
+            // Output size for the fast-start snapshot (see restore-last).
+            // Snapshots are JPEGs, so transparent outputs go without.
+            let transparent = monitor_manager
+                .outputs
+                .get(name)
+                .is_some_and(|o| o.config.transparent);
+            let snapshot_size = (monitor_manager.restore_last()
+                && !transparent
+                && r.config.width > 0
+                && r.config.height > 0)
+                .then_some((r.config.width, r.config.height));
+            let preprocess_cmd = monitor_manager
+                .outputs
+                .get(name)
+                .and_then(|o| o.config.preprocess.clone());
+            let metrics = monitor_manager.metrics().cloned();
+            let tone_cache = monitor_manager
+                .measures_tones()
+                .then(|| monitor_manager.get_cache());
+            // Joined here rather than in the task, so every output of this switch
+            // finds the decodes the first one started
+            let source_key = (path_clone.clone(), preprocess_cmd.clone());
+            let source = join_decode(&IN_FLIGHT_SOURCES, &source_key);
+            let key = (source_key.clone(), max_size);
+            let decode = join_decode(&IN_FLIGHT_DECODES, &key);
 
             debug!(
                 "[ASSET] {}: Offloading image decode: {}",
@@ -136,76 +400,89 @@ fn switch_wallpaper_content(
                 path.display()
             );
             tokio::spawn(async move {
-                // Acquire permit before decoding to limit concurrent tasks
-                let _permit = match semaphore.acquire().await {
-                    Ok(p) => p,
-                    Err(_) => {
-                        debug!(
-                            "[ASSET] {}: Semaphore closed, skipping image decode",
-                            name_clone
-                        );
-                        return;
-                    }
-                };
-
-                // Decode image in blocking task
-                let decode_result =
-                    tokio::task::spawn_blocking(move || match image::open(&path_clone) {
-                        Ok(img) => {
-                            let rgba = img.to_rgba8();
-                            let (width, height) = rgba.dimensions();
-                            let image_data = rgba.into_raw();
-                            Ok((name_clone.clone(), image_data, width, height, path_clone))
-                        }
-                        Err(e) => {
-                            error!("Failed to decode image {}: {}", path_clone.display(), e);
-                            Err((name_clone, path_clone))
-                        }
-                    })
-                    .await;
-
-                // Send decoded image (or error) to channel
-                match decode_result {
-                    Ok(Ok((name, image_data, width, height, path))) => {
-                        // Use send().await for bounded channel - may wait briefly if channel is full
-                        if let Err(e) = tx
-                            .send(LoadedImage {
-                                name: name.clone(),
-                                data: Some(image_data),
-                                width,
-                                height,
-                                _path: path,
+                let decoded = decode
+                    .get_or_init(|| async {
+                        let full = source
+                            .get_or_init(|| {
+                                decode_image(path_clone.clone(), preprocess_cmd, tone_cache)
                             })
                             .await
-                        {
-                            debug!(
-                                "[ASSET] {}: Failed to send decoded image (channel closed): {}",
-                                name, e
-                            );
+                            .clone()?;
+                        scale_image(full, max_size, &path_clone).await
+                    })
+                    .await
+                    .clone();
+                leave_decode(&IN_FLIGHT_DECODES, &key, &decode);
+                leave_decode(&IN_FLIGHT_SOURCES, &source_key, &source);
+
+                let msg = match decoded {
+                    Ok(image) => {
+                        if let (Some((w, h)), Some(metrics)) = (image.source_size, &metrics) {
+                            let saved = texture_bytes(w, h)
+                                .saturating_sub(texture_bytes(image.width, image.height));
+                            metrics.record_downscale(&name_clone, saved);
+                        }
+                        if let Some((w, h)) = snapshot_size {
+                            let (name, path, image) =
+                                (name_clone.clone(), path_clone.clone(), image.clone());
+                            tokio::task::spawn_blocking(move || {
+                                let Some(rgba) = image::ImageBuffer::<image::Rgba<u8>, _>::from_raw(
+                                    image.width,
+                                    image.height,
+                                    image.data.as_slice(),
+                                ) else {
+                                    return;
+                                };
+                                let snapshot = cache::scale_for_snapshot(&rgba, w, h);
+                                if let Err(e) = cache::write_snapshot(&name, &path, &snapshot) {
+                                    debug!("[RESTORE] {}: Failed to write snapshot: {}", name, e);
+                                }
+                            });
+                        }
+                        LoadedImage {
+                            name: name_clone,
+                            data: Some(image.data),
+                            width: image.width,
+                            height: image.height,
+                            path: path_clone,
+                            error: None,
+                            tone: image.tone,
                         }
                     }
-                    Ok(Err((name, path))) => {
-                        // Send error case - may wait briefly if channel is full
-                        let _ = tx
-                            .send(LoadedImage {
-                                name,
-                                data: None,
-                                width: 0,
-                                height: 0,
-                                _path: path,
-                            })
-                            .await;
-                    }
-                    Err(e) => {
-                        error!("Image decode task panicked: {}", e);
-                    }
+                    Err(error) => LoadedImage {
+                        name: name_clone,
+                        data: None,
+                        width: 0,
+                        height: 0,
+                        path: path_clone,
+                        error: Some(error),
+                        tone: None,
+                    },
+                };
+                // Use send().await for bounded channel - may wait briefly if channel is full
+                let name = msg.name.clone();
+                if let Err(e) = tx.send(msg).await {
+                    debug!(
+                        "[ASSET] {}: Failed to send decoded image (channel closed): {}",
+                        name, e
+                    );
                 }
-                // _permit is dropped here, releasing the semaphore
             });
+        } else if content_type == crate::queue::ContentType::Shader {
+            match r.load_shader(path) {
+                Ok(()) => monitor_manager.record_load_success(name, path),
+                Err(e) => {
+                    let error = format!("{:#}", e);
+                    error!("[SHADER] {}: {}", name, error);
+                    r.abort_transition();
+                    monitor_manager.record_load_failure(name, path, &error);
+                }
+            }
         }
     }
 
-    if content_type == crate::queue::ContentType::Video {
+    // No renderer (e.g. --headless): nothing would consume the frames
+    if content_type.is_played() && renderers.contains_key(name) {
         let session_id = *next_session_id;
         *next_session_id += 1;
         debug!(
@@ -214,6 +491,7 @@ fn switch_wallpaper_content(
         );
         create_and_start_video_player(
             path,
+            content_type,
             name,
             session_id,
             frame_tx,
@@ -222,150 +500,1198 @@ fn switch_wallpaper_content(
             player_tx,
         );
     }
+
+    sync_mirrors(name, monitor_manager, renderers, video_players);
+}
+
+/// Decodes `path` (its pre-processed copy, if configured) to RGBA at full size,
+/// measuring its tone when `tone_cache` is given. Holds a decode permit while decoding.
+async fn decode_image(
+    path: PathBuf,
+    preprocess_cmd: Option<String>,
+    tone_cache: Option<Arc<cache::FileCache>>,
+) -> Result<DecodedImage, String> {
+    // The pre-processed copy is decoded, if configured; tones stay keyed by the source.
+    // The hook runs before taking a permit, so a slow one doesn't hold up other decodes.
+    let source = path.clone();
+    let file = tokio::task::spawn_blocking(move || {
+        preprocess::resolve(&source, preprocess_cmd.as_deref())
+    })
+    .await
+    .map_err(|e| format!("preprocess task panicked: {}", e))?;
+    let _permit = IMAGE_DECODE_SEMAPHORE
+        .acquire()
+        .await
+        .map_err(|_| "image decoding is shutting down".to_string())?;
+    let decoded = tokio::task::spawn_blocking(move || {
+        let rgba = match image::open(&file) {
+            Ok(img) => img.to_rgba8(),
+            Err(e) => {
+                error!("Failed to decode image {}: {}", file.display(), e);
+                return Err(e.to_string());
+            }
+        };
+        let tone = tone_cache.map(|c| tone::lookup(&c, &path, &rgba));
+        let (width, height) = rgba.dimensions();
+        Ok(DecodedImage {
+            data: Arc::new(rgba.into_raw()),
+            width,
+            height,
+            tone,
+            source_size: None,
+        })
+    })
+    .await;
+    match decoded {
+        Ok(result) => result,
+        Err(e) => {
+            error!("Image decode task panicked: {}", e);
+            Err(format!("decode task panicked: {}", e))
+        }
+    }
 }
 
-fn create_and_start_video_player(
+/// `image` scaled down, keeping its aspect ratio, until it just covers `max_size`.
+/// Images already within that are returned as they are, sharing their buffer.
+async fn scale_image(
+    image: DecodedImage,
+    max_size: Option<(u32, u32)>,
     path: &Path,
-    name: &str,
-    session_id: u64,
-    frame_tx: &tokio::sync::mpsc::Sender<(Arc<String>, video::VideoEvent)>,
+) -> Result<DecodedImage, String> {
+    let (w, h) = (image.width, image.height);
+    let Some((width, height)) = max_size.and_then(|limit| cover_size((w, h), limit)) else {
+        return Ok(image);
+    };
+    let _permit = IMAGE_DECODE_SEMAPHORE
+        .acquire()
+        .await
+        .map_err(|_| "image decoding is shutting down".to_string())?;
+    let scaled = tokio::task::spawn_blocking(move || {
+        let view = image::ImageBuffer::<image::Rgba<u8>, _>::from_raw(w, h, image.data.as_slice())?;
+        let scaled =
+            image::imageops::resize(&view, width, height, image::imageops::FilterType::Triangle);
+        Some(DecodedImage {
+            data: Arc::new(scaled.into_raw()),
+            width,
+            height,
+            tone: image.tone,
+            source_size: Some((w, h)),
+        })
+    })
+    .await
+    .map_err(|e| format!("scale task panicked: {}", e))?;
+    debug!(
+        "[ASSET] Scaled {} down from {}x{} to {}x{}",
+        path.display(),
+        w,
+        h,
+        width,
+        height
+    );
+    scaled.ok_or_else(|| "decoded image has the wrong size".to_string())
+}
+
+/// Size an image of `size` is scaled down to so that it just covers `limit`, keeping
+/// its aspect ratio; None if it is already within that
+fn cover_size((w, h): (u32, u32), (width, height): (u32, u32)) -> Option<(u32, u32)> {
+    let factor = (width as f64 / w as f64).max(height as f64 / h as f64);
+    if factor >= 1.0 {
+        return None;
+    }
+    let scaled_w = ((w as f64 * factor).round() as u32).max(1);
+    let scaled_h = ((h as f64 * factor).round() as u32).max(1);
+    Some((scaled_w, scaled_h))
+}
+
+/// Size images for `source` are scaled down to cover: large enough for the source and
+/// each of its mirrors, which are all uploaded the same decode. None to keep them whole.
+fn decode_size_limit(
+    source: &str,
     monitor_manager: &monitor_manager::MonitorManager,
-    renderers: &mut HashMap<String, renderer::Renderer>,
-    player_tx: &tokio::sync::mpsc::UnboundedSender<VideoPlayerResult>,
-) {
-    if let Some(r) = renderers.get_mut(name) {
-        r.active_video_session_id = session_id;
+    renderers: &HashMap<String, renderer::Renderer>,
+) -> Option<(u32, u32)> {
+    let mut limit: Option<(u32, u32)> = None;
+    for target in content_targets(source, monitor_manager) {
+        let Some(r) = renderers.get(&target) else {
+            continue;
+        };
+        let config = &monitor_manager.outputs.get(&target)?.config;
+        let (w, h) = image_size_limit(config, r.config.width, r.config.height)?;
+        limit = Some(limit.map_or((w, h), |(lw, lh)| (lw.max(w), lh.max(h))));
     }
+    limit
+}
 
-    let path_str = path.to_string_lossy().into_owned();
-    let name_arc = Arc::new(name.to_string());
-    let name_str = name.to_string();
-    let frame_tx_clone = frame_tx.clone();
-    let player_tx_clone = player_tx.clone();
+/// Size images for an output are scaled down to cover (`max-image-scale`), None to keep
+/// them whole. Modes showing content at native size keep it, and `random-crop` zooms in
+/// need that much more detail.
+fn image_size_limit(
+    config: &orchestration::OutputConfig,
+    width: u32,
+    height: u32,
+) -> Option<(u32, u32)> {
+    let scale = config.max_image_scale?;
+    if width == 0
+        || height == 0
+        || matches!(
+            config.fit_mode,
+            orchestration::FitMode::Center | orchestration::FitMode::Tile
+        )
+    {
+        return None;
+    }
+    let scale = scale * config.random_crop.max(1.0);
+    Some((
+        (width as f32 * scale).ceil() as u32,
+        (height as f32 * scale).ceil() as u32,
+    ))
+}
 
-    let vol = monitor_manager
-        .outputs
-        .get(name)
-        .map(|o| o.config.volume as f64 / 100.0)
-        .unwrap_or(1.0);
+/// GPU memory of an RGBA image texture with its full mip chain
+fn texture_bytes(width: u32, height: u32) -> u64 {
+    width as u64 * height as u64 * 4 * 4 / 3
+}
 
-    tokio::task::spawn_blocking(move || {
-        let name_for_panic = name_str.clone();
-        let player_tx_panic = player_tx_clone.clone();
-        let session_id_panic = session_id;
+/// Records the tone of a decoded image and settles a `transition = "auto"` switch that
+/// waited for it, on the output and its mirrors, before the upload starts the
+/// transition
+fn apply_auto_transition(
+    msg: &LoadedImage,
+    monitor_manager: &mut monitor_manager::MonitorManager,
+    renderers: &mut HashMap<String, renderer::Renderer>,
+) {
+    let (Some(_), Some(tone)) = (&msg.data, msg.tone) else {
+        return;
+    };
+    let picked = monitor_manager.tone_measured(&msg.name, &msg.path, tone);
+    let Some((transition, cut)) = picked else {
+        return;
+    };
+    if cut {
+        debug!("[TRANSITION] {}: Auto picked a cut", msg.name);
+    } else {
+        debug!(
+            "[TRANSITION] {}: Auto picked {}",
+            msg.name,
+            transition.name()
+        );
+    }
+    for target in content_targets(&msg.name, monitor_manager) {
+        if let Some(r) = renderers.get_mut(&target) {
+            r.active_transition = transition.clone();
+            r.instant_transitions |= cut;
+        }
+    }
+}
 
-        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-            match video::VideoPlayer::new(&path_str, name_arc, session_id, frame_tx_clone) {
-                Ok(mut vp) => {
-                    vp.set_volume(vol);
-                    if let Err(e) = vp.prebuffer() {
-                        debug!(
-                            "[VIDEO] {}: Pre-buffering failed (non-fatal): {}",
-                            name_str, e
-                        );
-                    }
-                    if vp.start().is_ok() {
-                        if let Err(e) = player_tx_clone
-                            .send(VideoPlayerResult::Success(name_str, session_id, vp))
-                        {
-                            error!("Failed to send video player back: {}", e);
-                        }
-                    } else {
-                        error!("Failed to start video player");
-                        let _ = player_tx_clone
-                            .send(VideoPlayerResult::Failure(name_str, session_id));
-                    }
-                }
-                Err(e) => {
-                    error!("Failed to create video player: {}", e);
-                    let _ =
-                        player_tx_clone.send(VideoPlayerResult::Failure(name_str, session_id));
+/// Puts every output mirroring `source` into the same switch state as `source`.
+/// Mirrors never decode anything themselves: the main loop fans out the source's
+/// decoded images and video frames to them, so they share assets and batch timing.
+fn sync_mirrors(
+    source: &str,
+    monitor_manager: &monitor_manager::MonitorManager,
+    renderers: &mut HashMap<String, renderer::Renderer>,
+    video_players: &mut HashMap<String, video::VideoPlayer>,
+) {
+    let Some(src) = renderers.get(source) else {
+        return;
+    };
+    let transition = src.active_transition.clone();
+    let transition_duration = src.transition_duration;
+    let instant_transitions = src.instant_transitions;
+    let content_type = src.valid_content_type;
+    let session_id = src.active_video_session_id;
+    let batch_id = src.active_batch_id;
+    let batch_start_time = src.batch_start_time;
+    let crop = src.next_crop;
+    let focal = src.next_focal;
+    let shader = src.shader_path().map(Path::to_path_buf);
+
+    for mirror in monitor_manager.mirrors_of(source) {
+        // A mirror may have been playing its own content before its source appeared
+        if let Some(mut vp) = video_players.remove(&mirror) {
+            tokio::spawn(async move {
+                let _ = vp.stop();
+            });
+        }
+        if let Some(r) = renderers.get_mut(&mirror) {
+            debug!("[MIRROR] {}: Following {}", mirror, source);
+            r.active_batch_id = batch_id;
+            r.batch_start_time = batch_start_time;
+            r.set_content_type(content_type);
+            r.active_transition = transition.clone();
+            r.transition_duration = transition_duration;
+            r.instant_transitions = instant_transitions;
+            r.active_video_session_id = session_id;
+            r.next_crop = crop;
+            r.next_focal = focal;
+            r.switch_content();
+            if let Some(path) = &shader {
+                if let Err(e) = r.load_shader(path) {
+                    error!("[MIRROR] {}: {:#}", mirror, e);
+                    r.abort_transition();
                 }
             }
-        }));
-
-        if result.is_err() {
-            error!(
-                "[VIDEO] {}: Video player task panicked! Sending failure.",
-                name_for_panic
-            );
-            let _ = player_tx_panic
-                .send(VideoPlayerResult::Failure(name_for_panic, session_id_panic));
         }
-    });
+    }
 }
 
-#[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
-struct Args {
-    /// Show version information
-    #[arg(short = 'v', long = "version", action = clap::ArgAction::Version)]
-    version: Option<bool>,
+/// Crop window for `random-crop`: a zoom up to `max_zoom` around a random center.
+/// Seeded by output, file and batch, so each output of a synchronized cycle gets its own
+/// stable view of the shared image. Switches outside a batch get a fresh random one.
+fn random_crop(name: &str, path: &Path, batch_id: Option<u64>, max_zoom: f32) -> [f32; 4] {
+    use rand::{Rng, SeedableRng};
+    use std::hash::{Hash, Hasher};
 
-    #[arg(long)]
-    demo: bool,
-    #[arg(long, value_parser = clap::value_parser!(u8).range(1..=4))]
-    log: Option<u8>,
+    // NaN (`random-crop = nan` parses) would make the ranges below panic
+    if max_zoom.is_nan() || max_zoom <= 1.0 {
+        return renderer::NO_CROP;
+    }
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    (name, path, batch_id.unwrap_or_else(rand::random)).hash(&mut hasher);
+    let mut rng = rand::rngs::StdRng::seed_from_u64(hasher.finish());
+
+    let zoom = rng.gen_range(1.0..=max_zoom.min(4.0));
+    // Keep the window inside the content
+    let half = 0.5 / zoom;
+    [
+        zoom,
+        rng.gen_range(half..=1.0 - half),
+        rng.gen_range(half..=1.0 - half),
+        0.0,
+    ]
 }
 
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
-    let args = Args::parse();
-
-    // 1. Initialize Logging
-    let log_level = args.log;
-    let _guards = {
-        let filter = match log_level {
-            Some(1) => LevelFilter::WARN,
-            Some(2) => LevelFilter::INFO,
-            Some(3) => LevelFilter::DEBUG,
-            Some(4) => LevelFilter::TRACE,
-            None => LevelFilter::INFO,
-            _ => LevelFilter::INFO,
-        };
+/// Focal point from the sidecar `<file>.focal` next to `path`: two fractions of the
+/// content's width and height from its top-left corner, e.g. "0.62 0.35"
+fn focal_point(path: &Path) -> Option<[f32; 2]> {
+    let mut sidecar = path.as_os_str().to_owned();
+    sidecar.push(".focal");
+    let text = std::fs::read_to_string(&sidecar).ok()?;
+    let mut values = text
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|v| !v.is_empty())
+        .map(|v| v.parse::<f32>().ok().filter(|v| (0.0..=1.0).contains(v)));
+    match (values.next(), values.next()) {
+        (Some(Some(x)), Some(Some(y))) => Some([x, y]),
+        _ => {
+            warn!(
+                "[CROP] Ignoring {:?}: expected \"x y\" between 0 and 1",
+                sidecar
+            );
+            None
+        }
+    }
+}
 
-        let env_filter = EnvFilter::builder()
-            .with_default_directive(filter.into())
-            .from_env_lossy()
-            .add_directive("wgpu_core=warn".parse().unwrap())
-            .add_directive("wgpu_hal=warn".parse().unwrap())
-            .add_directive("naga=warn".parse().unwrap())
-            .add_directive("calloop=warn".parse().unwrap())
-            .add_directive("smithay_client_toolkit=warn".parse().unwrap());
+/// Output names in a stable order for per-output command results
+fn sorted_names(renderers: &HashMap<String, renderer::Renderer>) -> Vec<String> {
+    let mut names: Vec<String> = renderers.keys().cloned().collect();
+    names.sort();
+    names
+}
 
-        if let Some(level) = log_level {
-            let config_dir = dirs::config_dir()
-                .ok_or_else(|| anyhow::anyhow!("Could not find config directory"))?
-                .join("kaleidux")
-                .join("logs");
-            std::fs::create_dir_all(&config_dir)?;
+/// The renderer a single-output command (snapshot, capture) is aimed at; `output` may
+/// be left out when there is only one
+fn single_renderer(
+    output: Option<String>,
+    renderers: &HashMap<String, renderer::Renderer>,
+) -> Result<(String, &renderer::Renderer), CommandError> {
+    let name = match output {
+        Some(name) => name,
+        None if renderers.len() == 1 => renderers.keys().next().cloned().unwrap_or_default(),
+        None => {
+            let names = sorted_names(renderers);
+            return Err(CommandError::new(
+                ErrorCode::InvalidArgument,
+                format!("Several outputs, pass --output ({})", names.join(", ")),
+            )
+            .with_outputs(names));
+        }
+    };
+    match renderers.get(&name) {
+        Some(renderer) => Ok((name, renderer)),
+        None => Err(CommandError::new(
+            ErrorCode::UnknownOutput,
+            format!("Unknown output: {}", name),
+        )
+        .with_outputs(vec![name])),
+    }
+}
 
-            let timestamp = Local::now().format("%Y-%m-%d_%H-%M-%S");
-            let log_path = config_dir.join(format!("kaleidux-daemon-{}.log", timestamp));
-            let file = std::fs::File::create(&log_path)?;
-            println!("Logging to file: {}", log_path.display());
-            let (non_blocking_file, file_guard) = tracing_appender::non_blocking(file);
-            let (non_blocking_stdout, stdout_guard) =
-                tracing_appender::non_blocking(std::io::stdout());
+/// Renderers an `--output` spec (see `monitor_manager::match_outputs`) names, or all of
+/// them without one
+fn targeted_names(
+    output: Option<&str>,
+    renderers: &HashMap<String, renderer::Renderer>,
+) -> anyhow::Result<Vec<String>> {
+    match output {
+        Some(spec) => monitor_manager::match_outputs(spec, renderers.keys()),
+        None => Ok(sorted_names(renderers)),
+    }
+}
 
-            let file_layer = subscriber_fmt::layer()
-                .with_writer(non_blocking_file)
-                .with_ansi(false)
-                .with_timer(CustomTimer);
+/// Content each output switches to, as returned by next/prev/goto
+type Switches = HashMap<String, (PathBuf, crate::queue::ContentType)>;
 
-            let stdout_layer = subscriber_fmt::layer()
-                .with_writer(non_blocking_stdout)
-                .with_timer(CustomTimer);
+/// Steps the queues behind an `--output` spec once each with `step` (next or prev).
+/// Without a spec every queue is stepped. Named outputs that had nothing to switch to
+/// come back as failed results.
+fn step_outputs<F>(
+    monitor_manager: &mut monitor_manager::MonitorManager,
+    output: Option<&str>,
+    step: F,
+) -> anyhow::Result<(Switches, Vec<OutputResult>)>
+where
+    F: Fn(&mut monitor_manager::MonitorManager, Option<String>) -> Switches,
+{
+    let Some(spec) = output else {
+        return Ok((step(monitor_manager, None), Vec::new()));
+    };
+    let names = monitor_manager::match_outputs(spec, monitor_manager.outputs.keys())?;
+    let mut changes = HashMap::new();
+    let mut failed = Vec::new();
+    for name in monitor_manager.queue_targets(&names) {
+        let stepped = step(monitor_manager, Some(name.clone()));
+        if stepped.is_empty() {
+            let reason = if monitor_manager
+                .outputs
+                .get(&name)
+                .is_some_and(|o| o.static_file.is_some())
+            {
+                "shows a static file".to_string()
+            } else {
+                monitor_manager
+                    .failover_error(&name)
+                    .unwrap_or("nothing to switch to")
+                    .to_string()
+            };
+            failed.push(OutputResult::err(&name, reason).with_code(ErrorCode::Unavailable));
+        }
+        changes.extend(stepped);
+    }
+    Ok((changes, failed))
+}
 
-            Registry::default()
-                .with(env_filter)
-                .with(file_layer)
-                .with(stdout_layer)
-                .init();
+/// Reply to next/prev/goto: what each output switched to, followed by the named
+/// outputs that couldn't switch. Fails as a whole if there was nothing to switch.
+fn switch_results(changes: &Switches, failed: Vec<OutputResult>) -> Response {
+    if changes.is_empty() && failed.is_empty() {
+        return Response::error(ErrorCode::Unavailable, "Nothing to switch to");
+    }
+    let mut switched: Vec<(&String, &PathBuf)> = changes
+        .iter()
+        .map(|(name, (path, _))| (name, path))
+        .collect();
+    switched.sort();
+    let mut results: Vec<OutputResult> = switched
+        .into_iter()
+        .map(|(name, path)| OutputResult::ok(name, format!("switched to {}", path.display())))
+        .collect();
+    results.extend(failed);
+    Response::OutputResults(results)
+}
 
-            info!(
-                "Kaleidux Daemon starting... (Level {}, File: {})",
-                level,
+/// Stops the output's video player and releases its textures via `Renderer::clear`.
+/// Returns whether a video was playing.
+fn stop_output(
+    name: &str,
+    renderers: &mut HashMap<String, renderer::Renderer>,
+    video_players: &mut HashMap<String, video::VideoPlayer>,
+) -> bool {
+    let had_video = match video_players.remove(name) {
+        Some(mut vp) => {
+            tokio::spawn(async move {
+                let _ = vp.stop();
+            });
+            true
+        }
+        None => false,
+    };
+    if let Some(r) = renderers.get_mut(name) {
+        r.clear();
+    }
+    had_video
+}
+
+/// Picks new content for outputs whose last pick failed to load, once their retry
+/// backoff has passed (see `MonitorManager::record_load_failure`)
+#[allow(clippy::too_many_arguments)]
+fn retry_failed_loads(
+    monitor_manager: &mut monitor_manager::MonitorManager,
+    renderers: &mut HashMap<String, renderer::Renderer>,
+    video_players: &mut HashMap<String, video::VideoPlayer>,
+    frame_tx: &tokio::sync::mpsc::Sender<(Arc<String>, video::VideoEvent)>,
+    image_tx: &tokio::sync::mpsc::Sender<LoadedImage>,
+    player_tx: &tokio::sync::mpsc::UnboundedSender<VideoPlayerResult>,
+    next_session_id: &mut u64,
+    loop_start: Instant,
+) {
+    for name in monitor_manager.due_retries() {
+        let changes = monitor_manager.handle_next(Some(name));
+        let batch = rand::random::<u64>();
+        for (name, (path, content_type)) in changes {
+            switch_wallpaper_content(
+                &name,
+                &path,
+                content_type,
+                next_session_id,
+                frame_tx,
+                monitor_manager,
+                renderers,
+                video_players,
+                Some(batch),
+                Some(loop_start),
+                image_tx,
+                player_tx,
+                "RETRY",
+            );
+        }
+    }
+}
+
+/// Shows the configured `fallback` on outputs that just ran out of content (see
+/// `MonitorManager::check_content`) and on their mirrors, replacing whatever they
+/// showed. Images are decoded off the main thread but awaited, like the startup restore.
+async fn show_failovers(
+    monitor_manager: &mut monitor_manager::MonitorManager,
+    renderers: &mut HashMap<String, renderer::Renderer>,
+    video_players: &mut HashMap<String, video::VideoPlayer>,
+) {
+    for name in monitor_manager.check_content().await {
+        let Some(fallback) = monitor_manager.get_output_config(&name).map(|c| c.fallback) else {
+            continue;
+        };
+        let targets = content_targets(&name, monitor_manager);
+        // Headless outputs only report the error state
+        if !targets.iter().any(|t| renderers.contains_key(t)) {
+            continue;
+        }
+        for target in &targets {
+            stop_output(target, renderers, video_players);
+        }
+        let file = match fallback {
+            orchestration::Fallback::Color(color) => {
+                for target in &targets {
+                    if let Some(r) = renderers.get_mut(target) {
+                        r.solid_color = Some(color);
+                        r.needs_redraw = true;
+                    }
+                }
+                info!("[FAILOVER] {}: Showing fallback color", name);
+                continue;
+            }
+            orchestration::Fallback::Last => monitor_manager.last_good_image(&name),
+            orchestration::Fallback::Default => None,
+        };
+
+        let (width, height) = renderers
+            .get(&name)
+            .map_or((0, 0), |r| (r.config.width, r.config.height));
+        let source = file.clone();
+        let decoded = tokio::task::spawn_blocking(move || {
+            if let Some(file) = &source {
+                match image::open(file) {
+                    Ok(img) => return Ok(img.to_rgba8()),
+                    Err(e) => warn!("[FAILOVER] Failed to decode {}: {}", file.display(), e),
+                }
+            }
+            failover::default_image(width, height)
+        })
+        .await;
+        let rgba = match decoded {
+            Ok(Ok(rgba)) => rgba,
+            Ok(Err(e)) => {
+                warn!("[FAILOVER] {}: Failed to build fallback image: {}", name, e);
+                continue;
+            }
+            Err(e) => {
+                error!("[FAILOVER] {}: Decode task panicked: {}", name, e);
+                continue;
+            }
+        };
+        let (width, height) = rgba.dimensions();
+        let data = rgba.into_raw();
+        for target in &targets {
+            let Some(r) = renderers.get_mut(target) else {
+                continue;
+            };
+            // Stopping emptied the renderer, so this upload shows without a transition
+            if let Err(e) = r.upload_image_data(&data, width, height) {
+                warn!("[FAILOVER] {}: Failed to upload fallback: {}", target, e);
+                continue;
+            }
+            r.transition_just_completed = false;
+        }
+        match file {
+            Some(file) => info!("[FAILOVER] {}: Showing {}", name, file.display()),
+            None => info!("[FAILOVER] {}: Showing the default image", name),
+        }
+    }
+}
+
+/// Resumes video players and restores the current wallpaper on `names` where stop
+/// emptied them. Shared by `resume` and by `next`/`prev` waking the daemon from standby.
+#[allow(clippy::too_many_arguments)]
+fn resume_outputs(
+    names: Vec<String>,
+    monitor_manager: &mut monitor_manager::MonitorManager,
+    renderers: &mut HashMap<String, renderer::Renderer>,
+    video_players: &mut HashMap<String, video::VideoPlayer>,
+    frame_tx: &tokio::sync::mpsc::Sender<(Arc<String>, video::VideoEvent)>,
+    image_tx: &tokio::sync::mpsc::Sender<LoadedImage>,
+    player_tx: &tokio::sync::mpsc::UnboundedSender<VideoPlayerResult>,
+    next_session_id: &mut u64,
+    loop_start: Instant,
+) -> Vec<OutputResult> {
+    let mut results = Vec::new();
+    for name in names {
+        if let Some(player) = video_players.get(&name) {
+            results.push(match player.resume() {
+                Ok(_) => OutputResult::ok(&name, "video resumed"),
+                Err(e) => {
+                    error!("[CMD] Failed to resume video for {}: {}", name, e);
+                    OutputResult::err(&name, format!("failed to resume video: {}", e))
+                }
+            });
+            continue;
+        }
+
+        // Outputs emptied by stop get their current wallpaper back; cleared ones wait
+        // for a next/prev/set
+        let idle = renderers
+            .get(&name)
+            .is_some_and(|r| !r.has_any_content() && !r.content_pending)
+            && !monitor_manager.is_cleared(&name);
+        let current = monitor_manager
+            .outputs
+            .get(monitor_manager.resolve_mirror(&name))
+            .and_then(|o| o.current_path.clone());
+        match (idle, current) {
+            (true, Some(path)) if monitor_manager.resolve_mirror(&name) == name => {
+                match crate::queue::SmartQueue::get_content_type(&path) {
+                    Some(content_type) => {
+                        switch_wallpaper_content(
+                            &name,
+                            &path,
+                            content_type,
+                            next_session_id,
+                            frame_tx,
+                            monitor_manager,
+                            renderers,
+                            video_players,
+                            None,
+                            Some(loop_start),
+                            image_tx,
+                            player_tx,
+                            "RESUME",
+                        );
+                        results.push(OutputResult::ok(&name, "wallpaper restored"));
+                    }
+                    None => results.push(
+                        OutputResult::err(&name, format!("cannot restore {}", path.display()))
+                            .with_code(ErrorCode::Unavailable),
+                    ),
+                }
+            }
+            _ => results.push(OutputResult::ok(&name, "cycling resumed")),
+        }
+    }
+    results
+}
+
+/// Pauses a video that started on an output while its cycling is paused (`next`, a
+/// monitor plugged in), so it holds still like the rest until `resume`, or while
+/// `[power]` saving holds videos or the output is covered
+fn pause_if_held(
+    name: &str,
+    player: &video::VideoPlayer,
+    monitor_manager: &monitor_manager::MonitorManager,
+) {
+    if monitor_manager.holds_video(name) {
+        debug!("[VIDEO] {}: Holding the new video paused", name);
+        if let Err(e) = player.pause() {
+            warn!("[VIDEO] {}: Failed to pause video: {}", name, e);
+        }
+    }
+}
+
+/// Enters or leaves `[power]` saving: pauses every video, or resumes the ones whose
+/// cycling isn't paused
+fn apply_power_saving(
+    saving: bool,
+    monitor_manager: &mut monitor_manager::MonitorManager,
+    video_players: &HashMap<String, video::VideoPlayer>,
+) {
+    monitor_manager.set_power_saving(saving);
+    if !monitor_manager.power_config().pause_videos {
+        return;
+    }
+    for (name, player) in video_players {
+        let result = if saving {
+            player.pause()
+        } else if monitor_manager.holds_video(name) {
+            continue;
+        } else {
+            player.resume()
+        };
+        if let Err(e) = result {
+            warn!("[POWER] {}: Failed to pause or resume video: {}", name, e);
+        }
+    }
+}
+
+/// Holds video on outputs nobody sees any more (a fullscreen window, the session
+/// idle) and brings revealed ones back, redrawing what they show
+fn apply_covered(
+    fullscreen: HashSet<String>,
+    idle: bool,
+    monitor_manager: &mut monitor_manager::MonitorManager,
+    renderers: &mut HashMap<String, renderer::Renderer>,
+    video_players: &HashMap<String, video::VideoPlayer>,
+) {
+    let (hidden, revealed) = monitor_manager.set_covered(fullscreen, idle);
+    for name in hidden {
+        if let Some(player) = video_players.get(&name) {
+            if let Err(e) = player.pause() {
+                warn!("[COVER] {}: Failed to pause video: {}", name, e);
+            }
+        }
+    }
+    for name in revealed {
+        if let Some(r) = renderers.get_mut(&name) {
+            r.needs_redraw = true;
+        }
+        let Some(player) = video_players.get(&name) else {
+            continue;
+        };
+        if !monitor_manager.holds_video(&name) {
+            if let Err(e) = player.resume() {
+                warn!("[COVER] {}: Failed to resume video: {}", name, e);
+            }
+        }
+    }
+}
+
+fn all_in_standby(renderers: &HashMap<String, renderer::Renderer>) -> bool {
+    !renderers.is_empty() && renderers.values().all(|r| r.standby)
+}
+
+/// Whether no output changes until a command or display event arrives: nothing is
+/// transitioning, loading, waiting for a redraw or playing (unpaused video or shader
+/// wallpaper) on an output someone sees
+fn nothing_animates(
+    renderers: &HashMap<String, renderer::Renderer>,
+    video_players: &HashMap<String, video::VideoPlayer>,
+    monitor_manager: &monitor_manager::MonitorManager,
+) -> bool {
+    video_players.values().all(video::VideoPlayer::is_paused)
+        && renderers.iter().all(|(name, r)| {
+            monitor_manager.is_covered(name)
+                || (!r.is_busy() && !r.needs_redraw && r.shader_path().is_none())
+        })
+}
+
+/// Sleeps up to `duration` while nothing animates. Returns early with the first command,
+/// which the caller handles before the rest of the channel, or once the display
+/// connection behind `display_fd` (if connected) has events to read.
+async fn idle_sleep<T>(
+    duration: std::time::Duration,
+    cmd_rx: &mut tokio::sync::mpsc::UnboundedReceiver<T>,
+    display_fd: Option<&tokio::io::unix::AsyncFd<std::os::unix::io::RawFd>>,
+) -> Option<T> {
+    tokio::select! {
+        _ = tokio::time::sleep(duration) => None,
+        cmd = cmd_rx.recv() => cmd,
+        Some(ready) = async { Some(display_fd?.readable().await) } => {
+            // Cleared so the next sleep waits for new events; the loop reads these ones
+            if let Ok(mut guard) = ready {
+                guard.clear_ready();
+            }
+            None
+        }
+    }
+}
+
+/// Brings every output out of `stop` standby. Returns whether any output was in standby.
+fn leave_standby(renderers: &mut HashMap<String, renderer::Renderer>) -> bool {
+    let mut woke = false;
+    for r in renderers.values_mut() {
+        woke |= r.standby;
+        r.exit_standby();
+    }
+    woke
+}
+
+/// How long every output has shown static content, for `idle-trim`
+struct IdleTracker {
+    since: Instant,
+    trimmed: bool,
+}
+
+impl IdleTracker {
+    fn new() -> Self {
+        Self {
+            since: Instant::now(),
+            trimmed: false,
+        }
+    }
+
+    /// Whether to trim now: once per idle stretch, `after` into it (never if zero)
+    fn due(&mut self, busy: bool, after: std::time::Duration) -> bool {
+        if busy {
+            self.since = Instant::now();
+            self.trimmed = false;
+            return false;
+        }
+        if self.trimmed || after.is_zero() || self.since.elapsed() < after {
+            return false;
+        }
+        self.trimmed = true;
+        true
+    }
+}
+
+/// Refreshes the `publish-state` file, and on X11 the root window property, when what
+/// the outputs show has changed
+fn publish_state(
+    publisher: &mut state::Publisher,
+    monitor_manager: &monitor_manager::MonitorManager,
+    x11: Option<&x11::X11Backend>,
+) {
+    let Some(change) = publisher.update(monitor_manager.state()) else {
+        return;
+    };
+    if let Some(backend) = x11 {
+        let json = match &change {
+            state::Change::Published(json) => Some(json.as_str()),
+            state::Change::Withdrawn => None,
+        };
+        if let Err(e) = backend.set_state(json) {
+            warn!("[STATE] Failed to set _KALEIDUX_STATE: {}", e);
+        }
+    }
+}
+
+/// Releases what only transitions and decoding need once nothing has moved for
+/// `idle-trim`: transition targets, pooled textures, pipelines of transitions no output
+/// is set to, and free allocator pages. The next switch rebuilds what it uses.
+fn trim_idle_memory(
+    renderers: &mut HashMap<String, renderer::Renderer>,
+    gpu_contexts: &GpuContexts,
+    metrics: &metrics::PerformanceMetrics,
+) {
+    let before = metrics::resident_mb();
+    for r in renderers.values_mut() {
+        r.trim();
+    }
+    for (_, ctx) in gpu_contexts {
+        let keep: Vec<String> = renderers
+            .values()
+            .filter(|r| Arc::ptr_eq(&r.ctx, ctx))
+            .map(|r| r.active_transition.name())
+            .collect();
+        ctx.trim(&keep);
+    }
+    purge_allocator();
+    let after = metrics::resident_mb();
+    metrics.record_idle_trim(before, after);
+    match before.zip(after) {
+        Some((before, after)) => info!(
+            "[TRIM] Idle, released memory: {:.1}MB -> {:.1}MB resident",
+            before, after
+        ),
+        None => info!("[TRIM] Idle, released memory"),
+    }
+}
+
+/// Source output followed by its mirrors, i.e. every renderer that shows `source`'s content
+fn content_targets(
+    source: &str,
+    monitor_manager: &monitor_manager::MonitorManager,
+) -> Vec<String> {
+    let mut targets = vec![source.to_string()];
+    targets.extend(monitor_manager.mirrors_of(source));
+    targets
+}
+
+/// Image wallpapers to restore on startup (see `restore-last`), one per non-mirror output.
+/// Outputs that last showed a video wait for the first pick.
+fn last_wallpapers(
+    monitor_manager: &monitor_manager::MonitorManager,
+    renderers: &HashMap<String, renderer::Renderer>,
+) -> Vec<(String, PathBuf)> {
+    let mut wallpapers = Vec::new();
+    for name in sorted_names(renderers) {
+        if monitor_manager.resolve_mirror(&name) != name {
+            continue; // Filled from the mirrored output
+        }
+        let Some((path, content_type)) = monitor_manager.last_wallpaper(&name) else {
+            continue;
+        };
+        if content_type != crate::queue::ContentType::Image {
+            debug!("[RESTORE] {}: Last wallpaper was a video, skipping", name);
+            continue;
+        }
+        wallpapers.push((name, path));
+    }
+    wallpapers
+}
+
+/// Evicts old cache files in the background if `cache-max-size` is set
+fn enforce_cache_limit(monitor_manager: &monitor_manager::MonitorManager) {
+    let Some(limit) = monitor_manager.cache_limit() else {
+        return;
+    };
+    let keep = monitor_manager.cache_in_use();
+    tokio::task::spawn_blocking(move || match cache::collect_garbage(Some(limit), &keep) {
+        Ok(report) if report.files_removed > 0 => info!(
+            "[CACHE] Evicted {} files, reclaimed {} bytes ({} of {} bytes used)",
+            report.files_removed, report.bytes_reclaimed, report.size, limit
+        ),
+        Ok(_) => {}
+        Err(e) => warn!("[CACHE] Garbage collection failed: {}", e),
+    });
+}
+
+/// Shows the last wallpapers before the first pick, without a transition.
+/// With `from_snapshot` the small cached copies are decoded (a few ms each, missing ones
+/// skipped) so the first frames aren't black; the originals are then swapped in with a
+/// second call. Decodes happen in parallel but are awaited.
+async fn restore_last_wallpapers(
+    monitor_manager: &monitor_manager::MonitorManager,
+    renderers: &mut HashMap<String, renderer::Renderer>,
+    wallpapers: &[(String, PathBuf)],
+    from_snapshot: bool,
+) {
+    let start = Instant::now();
+    let mut decodes = Vec::new();
+    for (name, path) in wallpapers {
+        let file = if from_snapshot {
+            match cache::snapshot_path(name, path) {
+                Some(snapshot) if snapshot.exists() => snapshot,
+                _ => continue,
+            }
+        } else {
+            // Only an already-cached artifact: startup never waits on the hook
+            let command = monitor_manager
+                .outputs
+                .get(name)
+                .and_then(|o| o.config.preprocess.as_deref());
+            preprocess::existing(path, command).unwrap_or_else(|| path.clone())
+        };
+        let path = path.clone();
+        let handle = tokio::task::spawn_blocking(move || {
+            image::open(&file).map(|img| {
+                let rgba = img.to_rgba8();
+                let (width, height) = rgba.dimensions();
+                (rgba.into_raw(), width, height, path)
+            })
+        });
+        decodes.push((name.clone(), handle));
+    }
+
+    for (name, handle) in decodes {
+        let (data, width, height, path) = match handle.await {
+            Ok(Ok(decoded)) => decoded,
+            Ok(Err(e)) => {
+                warn!("[RESTORE] {}: Failed to decode last wallpaper: {}", name, e);
+                continue;
+            }
+            Err(e) => {
+                error!("[RESTORE] {}: Decode task panicked: {}", name, e);
+                continue;
+            }
+        };
+        let targets = content_targets(&name, monitor_manager);
+        for target in targets {
+            let Some(r) = renderers.get_mut(&target) else {
+                continue;
+            };
+            // A fresh renderer has no previous texture, so this upload is instant
+            if let Err(e) = r.upload_image_data(&data, width, height) {
+                warn!("[RESTORE] {}: Failed to upload last wallpaper: {}", target, e);
+                continue;
+            }
+            // The first pick replaces this image, nothing to report as completed
+            r.transition_just_completed = false;
+            info!(
+                "[RESTORE] {}: Showing last wallpaper {}{}",
+                target,
+                path.display(),
+                if from_snapshot { " (snapshot)" } else { "" }
+            );
+        }
+    }
+    debug!(
+        "[RESTORE] {} restored in {:.2}ms",
+        if from_snapshot { "Snapshots" } else { "Originals" },
+        start.elapsed().as_secs_f64() * 1000.0
+    );
+}
+
+/// WGPU contexts keyed by the outputs' `gpu` setting; outputs with the same setting
+/// share one device, so PRIME setups render each output on the GPU that scans it out.
+type GpuContexts = Vec<(Option<String>, Arc<renderer::WgpuContext>)>;
+
+/// Context for an output with the given `gpu` setting, created on first use. Settings
+/// naming the same adapter differently (an index, a name, none) share one context. A
+/// new context also returns the presentation surface it was probed with, which must be
+/// reused for that output since a window can't get a second one.
+async fn context_for_output(
+    contexts: &mut GpuContexts,
+    gpu: Option<String>,
+    window: Arc<
+        impl raw_window_handle::HasWindowHandle
+            + raw_window_handle::HasDisplayHandle
+            + Sync
+            + Send
+            + 'static,
+    >,
+    metrics: &metrics::PerformanceMetrics,
+) -> anyhow::Result<(Arc<renderer::WgpuContext>, Option<wgpu::Surface<'static>>)> {
+    if let Some((_, ctx)) = contexts.iter().find(|(g, _)| *g == gpu) {
+        return Ok((ctx.clone(), None));
+    }
+
+    info!(
+        "Initializing WGPU context (gpu = {})...",
+        gpu.as_deref().unwrap_or("default")
+    );
+    let wgpu_start = Instant::now();
+    let (instance, surface, adapter) =
+        renderer::WgpuContext::probe_adapter(window, gpu.as_deref()).await?;
+    let info = adapter.get_info();
+    if let Some(ctx) = contexts
+        .iter()
+        .map(|(_, ctx)| ctx.clone())
+        .find(|ctx| ctx.runs_on(&info))
+    {
+        info!(
+            "[GPU] gpu = {} picks {:?}, which already has a context",
+            gpu.as_deref().unwrap_or("default"),
+            info.name
+        );
+        // The probed surface belongs to another instance; the renderer creates its own
+        // on the shared context's once this one is gone
+        drop(surface);
+        drop(adapter);
+        drop(instance);
+        contexts.push((gpu, ctx.clone()));
+        return Ok((ctx, None));
+    }
+    let ctx = renderer::WgpuContext::new(instance, adapter).await?;
+    if contexts.is_empty() {
+        metrics.record_wgpu_init(wgpu_start.elapsed());
+    }
+    info!("WGPU initialized on GPU: {:?}", ctx.adapter.get_info().name);
+    contexts.push((gpu, ctx.clone()));
+    Ok((ctx, Some(surface)))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn create_and_start_video_player(
+    path: &Path,
+    content_type: crate::queue::ContentType,
+    name: &str,
+    session_id: u64,
+    frame_tx: &tokio::sync::mpsc::Sender<(Arc<String>, video::VideoEvent)>,
+    monitor_manager: &monitor_manager::MonitorManager,
+    renderers: &mut HashMap<String, renderer::Renderer>,
+    player_tx: &tokio::sync::mpsc::UnboundedSender<VideoPlayerResult>,
+) {
+    if let Some(r) = renderers.get_mut(name) {
+        r.active_video_session_id = session_id;
+    }
+
+    let source = path.to_path_buf();
+    let name_arc = Arc::new(name.to_string());
+    let name_str = name.to_string();
+    let frame_tx_clone = frame_tx.clone();
+    let player_tx_clone = player_tx.clone();
+
+    let vol = monitor_manager.volume(name);
+    let preprocess_cmd = monitor_manager
+        .outputs
+        .get(name)
+        .and_then(|o| o.config.preprocess.clone());
+    let clip = monitor_manager
+        .outputs
+        .get(name)
+        .map(|o| o.config.clip_for(path))
+        .unwrap_or_default();
+    let sequence_fps = monitor_manager
+        .outputs
+        .get(name)
+        .map(|o| o.config.sequence_fps)
+        .unwrap_or(24);
+
+    tokio::task::spawn_blocking(move || {
+        let name_for_panic = name_str.clone();
+        let player_tx_panic = player_tx_clone.clone();
+        let session_id_panic = session_id;
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let failure = |error: String| {
+                VideoPlayerResult::Failure(name_str.clone(), session_id, source.clone(), error)
+            };
+            // Folders of numbered frames are image sequences; neither they nor
+            // animations are preprocessed
+            let player = match sequence::frames(&source) {
+                Some(frames) => video::VideoPlayer::new_sequence(
+                    frames,
+                    sequence_fps,
+                    name_arc,
+                    session_id,
+                    frame_tx_clone,
+                ),
+                None if content_type == crate::queue::ContentType::Animated => {
+                    video::VideoPlayer::new_animation(&source, name_arc, session_id, frame_tx_clone)
+                }
+                None => {
+                    let path_str = preprocess::resolve(&source, preprocess_cmd.as_deref())
+                        .to_string_lossy()
+                        .into_owned();
+                    video::VideoPlayer::new(&path_str, name_arc, session_id, frame_tx_clone)
+                }
+            };
+            match player {
+                Ok(mut vp) => {
+                    vp.set_volume(vol);
+                    vp.set_clip(clip.start, clip.end);
+                    if let Err(e) = vp.prebuffer() {
+                        debug!(
+                            "[VIDEO] {}: Pre-buffering failed (non-fatal): {}",
+                            name_str, e
+                        );
+                    }
+                    match vp.start() {
+                        Ok(_) => {
+                            if let Err(e) = player_tx_clone.send(VideoPlayerResult::Success(
+                                name_str.clone(),
+                                session_id,
+                                source.clone(),
+                                vp,
+                            )) {
+                                error!("Failed to send video player back: {}", e);
+                            }
+                        }
+                        Err(e) => {
+                            error!("Failed to start video player: {}", e);
+                            let _ = player_tx_clone.send(failure(e.to_string()));
+                        }
+                    }
+                }
+                Err(e) => {
+                    error!("Failed to create video player: {}", e);
+                    let _ = player_tx_clone.send(failure(e.to_string()));
+                }
+            }
+        }));
+
+        if result.is_err() {
+            error!(
+                "[VIDEO] {}: Video player task panicked! Sending failure.",
+                name_for_panic
+            );
+            let _ = player_tx_panic.send(VideoPlayerResult::Failure(
+                name_for_panic,
+                session_id_panic,
+                source,
+                "video player panicked".to_string(),
+            ));
+        }
+    });
+}
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// Show version information
+    #[arg(short = 'v', long = "version", action = clap::ArgAction::Version)]
+    version: Option<bool>,
+
+    #[arg(long)]
+    demo: bool,
+    #[arg(long, value_parser = clap::value_parser!(u8).range(1..=4))]
+    log: Option<u8>,
+    /// Take over from an already running daemon instead of refusing to start
+    #[arg(long)]
+    replace: bool,
+    /// Check the display session, GPU, GStreamer plugins and config, then exit
+    #[arg(long)]
+    doctor: bool,
+    /// Run without a display: queues, scheduling, IPC and scripts only, nothing rendered
+    #[arg(long)]
+    headless: bool,
+    /// Virtual output for --headless (repeatable, matched against config sections)
+    #[arg(long = "headless-output", value_name = "NAME", default_value = "HEADLESS-1")]
+    headless_outputs: Vec<String>,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
+    // Diagnostics print their own report; no logging, lock or socket involved
+    if args.doctor {
+        std::process::exit(if doctor::run() { 0 } else { 1 });
+    }
+
+    // 1. Initialize Logging
+    let log_level = args.log;
+    let _guards = {
+        let filter = match log_level {
+            Some(1) => LevelFilter::WARN,
+            Some(2) => LevelFilter::INFO,
+            Some(3) => LevelFilter::DEBUG,
+            Some(4) => LevelFilter::TRACE,
+            None => LevelFilter::INFO,
+            _ => LevelFilter::INFO,
+        };
+
+        let env_filter = EnvFilter::builder()
+            .with_default_directive(filter.into())
+            .from_env_lossy()
+            .add_directive("wgpu_core=warn".parse().unwrap())
+            .add_directive("wgpu_hal=warn".parse().unwrap())
+            .add_directive("naga=warn".parse().unwrap())
+            .add_directive("calloop=warn".parse().unwrap())
+            .add_directive("smithay_client_toolkit=warn".parse().unwrap());
+
+        if let Some(level) = log_level {
+            let config_dir = dirs::config_dir()
+                .ok_or_else(|| anyhow::anyhow!("Could not find config directory"))?
+                .join("kaleidux")
+                .join("logs");
+            std::fs::create_dir_all(&config_dir)?;
+
+            let timestamp = Local::now().format("%Y-%m-%d_%H-%M-%S");
+            let log_path = config_dir.join(format!("kaleidux-daemon-{}.log", timestamp));
+            let file = std::fs::File::create(&log_path)?;
+            println!("Logging to file: {}", log_path.display());
+            let (non_blocking_file, file_guard) = tracing_appender::non_blocking(file);
+            let (non_blocking_stdout, stdout_guard) =
+                tracing_appender::non_blocking(std::io::stdout());
+
+            let file_layer = subscriber_fmt::layer()
+                .with_writer(non_blocking_file)
+                .with_ansi(false)
+                .with_timer(CustomTimer);
+
+            let stdout_layer = subscriber_fmt::layer()
+                .with_writer(non_blocking_stdout)
+                .with_timer(CustomTimer);
+
+            Registry::default()
+                .with(env_filter)
+                .with(file_layer)
+                .with(stdout_layer)
+                .init();
+
+            info!(
+                "Kaleidux Daemon starting... (Level {}, File: {})",
+                level,
                 config_dir.display()
             );
             (Some(file_guard), Some(stdout_guard))
@@ -374,51 +1700,795 @@ async fn main() -> anyhow::Result<()> {
                 .with_writer(std::io::stdout)
                 .with_timer(CustomTimer);
 
-            Registry::default()
-                .with(env_filter)
-                .with(stdout_layer)
-                .init();
-            info!("Kaleidux Daemon starting...");
-            (None, None)
+            Registry::default()
+                .with(env_filter)
+                .with(stdout_layer)
+                .init();
+            info!("Kaleidux Daemon starting...");
+            (None, None)
+        }
+    };
+
+    // 2. Single instance: the lock is held until the process exits
+    let _instance_lock = instance::InstanceLock::acquire(args.replace).await?;
+
+    // 3. Load Configuration
+    let mut config = match orchestration::Config::load().await {
+        Ok(cfg) => cfg,
+        Err(e) => {
+            warn!("Failed to load configuration: {}. Using defaults.", e);
+            orchestration::Config::default()
+        }
+    };
+
+    if args.demo {
+        info!("Demo mode enabled! Overriding configuration to use current directory...");
+        let current_dir = std::env::current_dir()?;
+        config.any.path = Some(current_dir);
+        config.any.sources = None;
+        config.any.duration = Some(std::time::Duration::from_secs(10));
+        config.global.video_ratio = Some(100); // 100% video for the demo file
+        config.any.transition_time = Some(1500); // Nice 1.5s transitions
+        config.any.transition = Some(Transition::Random); // Cycle through transitions
+    }
+
+    if args.headless {
+        info!("Starting headless mode (no display backend)...");
+        return run_headless_loop(config, args.headless_outputs).await;
+    }
+
+    // 4. Initialize GStreamer
+    let gstreamer_start = Instant::now();
+    gstreamer::init()?;
+    video::prefer_decoders(config.global.video_decoder);
+    let gstreamer_duration = gstreamer_start.elapsed();
+    info!("GStreamer initialized.");
+
+    // 5. Resource Monitor will be started in backend loops with metrics
+
+    // Detect Backend
+    let use_x11 = std::env::var("WAYLAND_DISPLAY").is_err() && std::env::var("DISPLAY").is_ok();
+
+    if use_x11 {
+        info!("Starting X11 Backend...");
+        run_x11_loop(config, log_level, gstreamer_duration).await
+    } else {
+        info!("Starting Wayland Backend...");
+        run_wayland_loop(config, log_level, gstreamer_duration).await
+    }
+}
+
+/// Binds the IPC socket, or takes the one systemd passed in, and forwards each request
+/// to the main loop over `cmd_tx`. With the `dbus` feature the same requests are served
+/// on the session bus as well.
+fn spawn_ipc_listener(
+    cmd_tx: tokio::sync::mpsc::UnboundedSender<(Request, tokio::sync::oneshot::Sender<Response>)>,
+) -> anyhow::Result<()> {
+    let listener = match systemd::take_listener() {
+        // Clients that connected before we started are waiting in its backlog
+        Some(listener) => UnixListener::from_std(listener)?,
+        None => bind_ipc_socket()?,
+    };
+
+    #[cfg(feature = "dbus")]
+    dbus::spawn(cmd_tx.clone());
+
+    tokio::spawn(async move {
+        loop {
+            if let Ok((mut stream, _)) = listener.accept().await {
+                let cmd_tx = cmd_tx.clone();
+                tokio::spawn(async move {
+                    let request = match read_request(&mut stream).await {
+                        Ok(Some(request)) => request,
+                        Ok(None) => return,
+                        Err(message) => {
+                            let response = Response::error(ErrorCode::InvalidArgument, message);
+                            if let Ok(json) = serde_json::to_string(&response) {
+                                let _ = stream.write_all(json.as_bytes()).await;
+                            }
+                            return;
+                        }
+                    };
+                    let req_str = request.trim();
+                    // A bare JSON array is a batch, answered with a bare array
+                    let bare_array = req_str.starts_with('[');
+                    let req = if bare_array {
+                        serde_json::from_str::<Vec<Request>>(req_str).map(Request::Batch)
+                    } else {
+                        serde_json::from_str::<Request>(req_str)
+                    };
+                    let req = match req {
+                        Ok(req) => req,
+                        Err(e) => {
+                            let response = Response::error(
+                                ErrorCode::InvalidArgument,
+                                format!("Invalid request: {}", e),
+                            );
+                            if let Ok(json) = serde_json::to_string(&response) {
+                                let _ = stream.write_all(json.as_bytes()).await;
+                            }
+                            return;
+                        }
+                    };
+                    if let Request::Subscribe = req {
+                        stream_events(stream).await;
+                        return;
+                    }
+                    let (resp_tx, resp_rx) = tokio::sync::oneshot::channel();
+                    if cmd_tx.send((req, resp_tx)).is_ok() {
+                        if let Ok(response) = resp_rx.await {
+                            let json = match response {
+                                Response::Batch(responses) if bare_array => {
+                                    serde_json::to_string(&responses)
+                                }
+                                response => serde_json::to_string(&response),
+                            };
+                            if let Ok(json) = json {
+                                let _ = stream.write_all(json.as_bytes()).await;
+                            }
+                        }
+                    }
+                });
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Largest request read from the socket; bulk love/blacklist/tag requests carry one
+/// path per file, so this leaves room for hundreds of thousands of them
+const MAX_REQUEST_SIZE: usize = 16 * 1024 * 1024;
+
+/// Reads one request: up to EOF, or until what arrived is a complete JSON value (clients
+/// that wait for the reply, or subscribe, keep the connection open). None if the client
+/// hung up without sending anything; Err with the reason to reply with otherwise.
+async fn read_request(stream: &mut tokio::net::UnixStream) -> Result<Option<String>, String> {
+    let mut buf = Vec::new();
+    let mut chunk = vec![0u8; 64 * 1024];
+    loop {
+        let n = match stream.read(&mut chunk).await {
+            Ok(n) => n,
+            Err(e) => return Err(format!("Failed to read request: {}", e)),
+        };
+        if n == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if buf.len() > MAX_REQUEST_SIZE {
+            return Err(format!(
+                "Request larger than {} MiB",
+                MAX_REQUEST_SIZE / (1024 * 1024)
+            ));
+        }
+        // Only a chunk ending in a closing bracket or whitespace can end the value
+        let ends_value = buf
+            .last()
+            .is_some_and(|b| matches!(b, b'}' | b']') || b.is_ascii_whitespace());
+        if ends_value {
+            match serde_json::from_slice::<serde::de::IgnoredAny>(&buf) {
+                Err(e) if e.is_eof() => {}
+                // Complete, or malformed in a way more data can't fix
+                _ => break,
+            }
+        }
+    }
+    if buf.iter().all(u8::is_ascii_whitespace) {
+        return Ok(None);
+    }
+    String::from_utf8(buf)
+        .map(Some)
+        .map_err(|_| "Invalid request: not UTF-8".to_string())
+}
+
+fn bind_ipc_socket() -> anyhow::Result<UnixListener> {
+    info!("[STARTUP] Setting up IPC socket");
+    let socket_path = instance::socket_path();
+
+    info!("[STARTUP] IPC socket path: {:?}", socket_path);
+    let _ = std::fs::remove_file(&socket_path);
+    let listener = UnixListener::bind(&socket_path)?;
+    info!("[STARTUP] IPC socket bound successfully");
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if let Ok(metadata) = std::fs::metadata(&socket_path) {
+            let mut perms = metadata.permissions();
+            perms.set_mode(0o600);
+            let _ = std::fs::set_permissions(&socket_path, perms);
+        }
+    }
+    Ok(listener)
+}
+
+/// Resolves on Ctrl+C or SIGTERM, which is how systemd stops the service
+async fn shutdown_signal() {
+    use tokio::signal::unix::{signal, SignalKind};
+    match signal(SignalKind::terminate()) {
+        Ok(mut term) => {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = term.recv() => {}
+            }
+        }
+        Err(_) => {
+            let _ = tokio::signal::ctrl_c().await;
+        }
+    }
+}
+
+/// Serves a `subscribe` connection: one JSON event per line until the client hangs up
+async fn stream_events(mut stream: tokio::net::UnixStream) {
+    let mut events = events::subscribe();
+    let (mut reader, mut writer) = stream.split();
+    let mut buf = [0u8; 64];
+    debug!("[IPC] Event subscriber connected");
+    loop {
+        let event = tokio::select! {
+            // Anything still sent (the request's newline) is ignored; EOF is a hang-up
+            read = reader.read(&mut buf) => match read {
+                Ok(n) if n > 0 => continue,
+                _ => break,
+            },
+            event = events.recv() => event,
+        };
+        let event = match event {
+            Ok(event) => event,
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(missed)) => {
+                warn!("[IPC] Event subscriber fell behind, dropped {}", missed);
+                continue;
+            }
+            Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+        };
+        let Ok(mut line) = serde_json::to_string(&event) else {
+            continue;
+        };
+        line.push('\n');
+        if writer.write_all(line.as_bytes()).await.is_err() {
+            break;
+        }
+    }
+    debug!("[IPC] Event subscriber disconnected");
+}
+
+/// How often the headless loop ticks; nothing animates, so this only bounds timer accuracy
+const HEADLESS_TICK: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// `--headless`: queues, scheduling, IPC, scripting and stats without any display
+/// backend. The outputs are virtual and nothing is decoded or rendered; every switch
+/// is logged and counts as displayed right away, so durations, history, stats and
+/// scripts behave as they would on screen. Meant for CI and for servers that only
+/// consume the daemon's decisions.
+async fn run_headless_loop(
+    config: orchestration::Config,
+    output_names: Vec<String>,
+) -> anyhow::Result<()> {
+    let script_path = config.global.script_path.clone();
+    let script_tick_interval = config.global.script_tick_interval;
+    let metrics = Arc::new(metrics::PerformanceMetrics::new());
+    metrics.record_startup_start();
+
+    let mut monitor_manager =
+        monitor_manager::MonitorManager::new_with_metrics(config.clone(), Some(metrics.clone()))?;
+    for name in output_names {
+        let identity = orchestration::OutputIdentity {
+            name,
+            description: "Headless output".to_string(),
+            make: String::new(),
+            model: String::new(),
+        };
+        if !config.is_output_enabled(&identity) {
+            info!("[HEADLESS] {}: disabled in config, skipping", identity.name);
+            continue;
+        }
+        info!("[HEADLESS] Adding virtual output {}", identity.name);
+        monitor_manager.add_output(&identity).await;
+        events::emit(kaleidux_common::Event::OutputAdded {
+            output: identity.name.clone(),
+        });
+    }
+
+    // Only needed to satisfy the shared command/switch code; nothing reads from them
+    // because no output has a renderer
+    let (frame_tx, _frame_rx) = tokio::sync::mpsc::channel::<(Arc<String>, video::VideoEvent)>(1);
+    let (image_tx, _image_rx) = tokio::sync::mpsc::channel::<LoadedImage>(1);
+    let (player_tx, _player_rx) = tokio::sync::mpsc::unbounded_channel::<VideoPlayerResult>();
+    let mut renderers: HashMap<String, renderer::Renderer> = HashMap::new();
+    let mut video_players: HashMap<String, video::VideoPlayer> = HashMap::new();
+    let mut next_session_id = 1u64;
+
+    let (cmd_tx, mut cmd_rx) =
+        tokio::sync::mpsc::unbounded_channel::<(Request, tokio::sync::oneshot::Sender<Response>)>();
+    spawn_ipc_listener(cmd_tx.clone())?;
+
+    let shutdown_flag = Arc::new(AtomicBool::new(false));
+    let shutdown_clone = shutdown_flag.clone();
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        warn!("Received shutdown signal, cleaning up...");
+        shutdown_clone.store(true, Ordering::SeqCst);
+    });
+
+    let mut script_manager = scripting::ScriptManager::new(cmd_tx);
+    if let Some(path) = &script_path {
+        info!("[STARTUP] Loading script from: {:?}", path);
+        let _ = script_manager.load(path).await;
+    }
+    let mut last_script_tick = Instant::now();
+    let mut last_stats_flush = Instant::now();
+    let mut last_cache_gc = Instant::now();
+    // Due immediately, so rules active at startup apply on the first pass
+    let mut last_playlist_schedule = Instant::now()
+        .checked_sub(PLAYLIST_SCHEDULE_INTERVAL)
+        .unwrap_or_else(Instant::now);
+    let mut state_publisher = state::Publisher::new(instance::state_path());
+    let mut power = power::PowerMonitor::new();
+    let mut remote_sync = remote::RemoteSync::new();
+    let mut watchdog = systemd::Watchdog::new();
+    let mut last_state_publish = Instant::now();
+    info!(
+        "[HEADLESS] Running with {} virtual outputs",
+        monitor_manager.outputs.len()
+    );
+    systemd::notify("READY=1");
+
+    loop {
+        let loop_start = Instant::now();
+        if shutdown_flag.load(Ordering::SeqCst) {
+            systemd::notify("STOPPING=1");
+            break;
+        }
+
+        let scheduled_changes = monitor_manager.tick();
+        if !scheduled_changes.is_empty() {
+            let batch_id = rand::random::<u64>();
+            for (name, (path, content_type)) in scheduled_changes {
+                switch_wallpaper_content(
+                    &name,
+                    &path,
+                    content_type,
+                    &mut next_session_id,
+                    &frame_tx,
+                    &mut monitor_manager,
+                    &mut renderers,
+                    &mut video_players,
+                    Some(batch_id),
+                    Some(loop_start),
+                    &image_tx,
+                    &player_tx,
+                    "SCHEDULED",
+                );
+            }
+        }
+        show_failovers(&mut monitor_manager, &mut renderers, &mut video_players).await;
+
+        if last_script_tick.elapsed().as_secs() >= script_tick_interval {
+            script_manager.tick();
+            last_script_tick = Instant::now();
+        }
+
+        while let Ok((req, resp)) = cmd_rx.try_recv() {
+            // Nothing ever transitions, so waits are satisfied immediately
+            if let Request::Wait { .. } = req {
+                let _ = resp.send(Response::Ok);
+                continue;
+            }
+            if let Request::Fetch(FetchCommand::Now) = req {
+                monitor_manager.fetch_now(resp);
+                continue;
+            }
+            let response = handle_command(
+                req,
+                &mut monitor_manager,
+                &mut renderers,
+                &mut video_players,
+                &frame_tx,
+                &image_tx,
+                &player_tx,
+                &mut next_session_id,
+                loop_start,
+                &shutdown_flag,
+            )
+            .await;
+            let _ = resp.send(response);
+        }
+
+        // Content is "on screen" as soon as it is picked
+        let names: Vec<String> = monitor_manager.outputs.keys().cloned().collect();
+        for name in &names {
+            monitor_manager.mark_transition_completed(name);
+        }
+
+        if last_stats_flush.elapsed().as_secs() >= 5 {
+            let _ = monitor_manager.flush_all_stats();
+            last_stats_flush = Instant::now();
+        }
+        if last_cache_gc.elapsed() >= CACHE_GC_INTERVAL {
+            enforce_cache_limit(&monitor_manager);
+            last_cache_gc = Instant::now();
+        }
+        if last_playlist_schedule.elapsed() >= PLAYLIST_SCHEDULE_INTERVAL {
+            monitor_manager.apply_playlist_schedule();
+            last_playlist_schedule = Instant::now();
+        }
+        if let Some(saving) = power.poll(monitor_manager.power_config()) {
+            apply_power_saving(saving, &mut monitor_manager, &video_players);
+        }
+        let stale = remote_sync.poll(monitor_manager.config());
+        monitor_manager.delete_files(&stale);
+        monitor_manager.poll_fetch();
+        watchdog.poll();
+        if last_state_publish.elapsed() >= STATE_PUBLISH_INTERVAL {
+            publish_state(&mut state_publisher, &monitor_manager, None);
+            last_state_publish = Instant::now();
+        }
+
+        let elapsed = loop_start.elapsed();
+        if elapsed < HEADLESS_TICK {
+            tokio::time::sleep(HEADLESS_TICK - elapsed).await;
         }
-    };
+    }
 
-    // 2. Load Configuration
-    let mut config = match orchestration::Config::load().await {
-        Ok(cfg) => cfg,
+    let _ = monitor_manager.flush_all_stats();
+    Ok(())
+}
+
+/// Renderer for a new wallpaper surface, on the GPU its output is configured for.
+/// None (logged and counted) if creating it fails or takes longer than 5s.
+async fn create_renderer(
+    name: &str,
+    surface_arc: Arc<wayland::RawHandleSurface>,
+    monitor_manager: &monitor_manager::MonitorManager,
+    gpu_contexts: &mut GpuContexts,
+    metrics: &Arc<metrics::PerformanceMetrics>,
+) -> anyhow::Result<Option<renderer::Renderer>> {
+    let gpu = monitor_manager
+        .get_output_config(name)
+        .and_then(|c| c.gpu.clone());
+    let (ctx, init_surf) =
+        context_for_output(gpu_contexts, gpu, surface_arc.clone(), metrics).await?;
+    let ctx_clone = ctx.clone();
+
+    let metrics_clone = metrics.clone();
+
+    // Offload WGPU surface creation to blocking thread to avoid checking generic runtime
+    let name_for_bg = name.to_string();
+    let spawn_handler = tokio::task::spawn_blocking(move || {
+        renderer::Renderer::new(
+            name_for_bg,
+            ctx_clone,
+            surface_arc,
+            init_surf,
+            Some(metrics_clone),
+        )
+    });
+
+    // Set a timeout strictly for the initialization
+    let renderer =
+        match tokio::time::timeout(std::time::Duration::from_secs(5), spawn_handler).await {
+            Ok(join_res) => match join_res {
+                Ok(render_res) => match render_res {
+                    Ok(mut r) => {
+                        if let Some(output_config) = monitor_manager.get_output_config(name) {
+                            r.apply_config(output_config);
+                        }
+                        Some(r)
+                    }
+                    Err(e) => {
+                        error!("Failed to create renderer for output {}: {}", name, e);
+                        metrics.record_error("renderer_creation");
+                        None
+                    }
+                },
+                Err(e) => {
+                    error!("Thread join error for output {}: {}", name, e);
+                    metrics.record_error("renderer_thread_error");
+                    None
+                }
+            },
+            Err(_) => {
+                // Timeout occurred
+                error!(
+                    "TIMEOUT: Renderer initialization for {} took longer than 5s. Skipping.",
+                    name
+                );
+                metrics.record_error("renderer_creation_timeout");
+                None
+            }
+        };
+    // Poll device to process submission/initialization commands
+    ctx.device.poll(wgpu::Maintain::Poll);
+    Ok(renderer)
+}
+
+/// Creates the wallpaper surface of an output known to `monitor_manager` and its
+/// renderer. Returns whether that worked.
+#[allow(clippy::too_many_arguments)]
+async fn add_output_renderer(
+    backend: &mut wayland::WaylandBackend,
+    qh: &wayland_client::QueueHandle<wayland::WaylandBackend>,
+    display_ptr: *mut std::ffi::c_void,
+    output: &wayland_client::protocol::wl_output::WlOutput,
+    name: &str,
+    gpu_contexts: &mut GpuContexts,
+    metrics: &Arc<metrics::PerformanceMetrics>,
+    monitor_manager: &monitor_manager::MonitorManager,
+    renderers: &mut HashMap<String, renderer::Renderer>,
+    log_prefix: &str,
+) -> bool {
+    let Some(output_config) = monitor_manager.get_output_config(name) else {
+        return false;
+    };
+    let layer_surface =
+        match backend.create_wallpaper_surface(output, qh, name.to_string(), output_config) {
+            Ok(surface) => surface,
+            Err(e) => {
+                error!("[{}] {}: Failed to create surface: {}", log_prefix, name, e);
+                return false;
+            }
+        };
+    let surface_arc = Arc::new(wayland::RawHandleSurface {
+        layer_surface,
+        display_ptr,
+    });
+    match create_renderer(name, surface_arc, monitor_manager, gpu_contexts, metrics).await {
+        Ok(Some(r)) => {
+            renderers.insert(name.to_string(), r);
+            info!("[{}] {}: Renderer initialized", log_prefix, name);
+            true
+        }
+        Ok(None) => {
+            backend.remove_surface(name);
+            false
+        }
         Err(e) => {
-            warn!("Failed to load configuration: {}. Using defaults.", e);
-            orchestration::Config::default()
+            error!(
+                "[{}] {}: No GPU context for the output: {:#}",
+                log_prefix, name, e
+            );
+            backend.remove_surface(name);
+            false
         }
-    };
+    }
+}
 
-    if args.demo {
-        info!("Demo mode enabled! Overriding configuration to use current directory...");
-        let current_dir = std::env::current_dir()?;
-        config.any.path = Some(current_dir);
-        config.any.duration = Some(std::time::Duration::from_secs(10));
-        config.global.video_ratio = Some(100); // 100% video for the demo file
-        config.any.transition_time = Some(1500); // Nice 1.5s transitions
-        config.any.transition = Some(Transition::Random); // Cycle through transitions
+/// Sets up outputs plugged in while running, like the ones found at startup: their
+/// queue, a wallpaper surface and a renderer, then a first wallpaper right away
+#[allow(clippy::too_many_arguments)]
+async fn add_hotplugged_outputs(
+    backend: &mut wayland::WaylandBackend,
+    qh: &wayland_client::QueueHandle<wayland::WaylandBackend>,
+    display_ptr: *mut std::ffi::c_void,
+    gpu_contexts: &mut GpuContexts,
+    metrics: &Arc<metrics::PerformanceMetrics>,
+    monitor_manager: &mut monitor_manager::MonitorManager,
+    renderers: &mut HashMap<String, renderer::Renderer>,
+    video_players: &mut HashMap<String, video::VideoPlayer>,
+    frame_tx: &tokio::sync::mpsc::Sender<(Arc<String>, video::VideoEvent)>,
+    image_tx: &tokio::sync::mpsc::Sender<LoadedImage>,
+    player_tx: &tokio::sync::mpsc::UnboundedSender<VideoPlayerResult>,
+    next_session_id: &mut u64,
+    loop_start: Instant,
+) {
+    for output in std::mem::take(&mut backend.new_outputs) {
+        let Some(identity) = backend.output_identity(&output) else {
+            continue;
+        };
+        let name = identity.name.clone();
+        if renderers.contains_key(&name) {
+            continue;
+        }
+        if !monitor_manager.is_output_enabled(&identity) {
+            info!(
+                "[HOTPLUG] Output {} ({}) connected - disabled in config, skipping",
+                name, identity.description
+            );
+            continue;
+        }
+        info!(
+            "[HOTPLUG] Output {} ({}) connected",
+            name, identity.description
+        );
+        monitor_manager.add_output(&identity).await;
+        events::emit(kaleidux_common::Event::OutputAdded {
+            output: name.clone(),
+        });
+
+        if !add_output_renderer(
+            backend,
+            qh,
+            display_ptr,
+            &output,
+            &name,
+            gpu_contexts,
+            metrics,
+            monitor_manager,
+            renderers,
+            "HOTPLUG",
+        )
+        .await
+        {
+            continue;
+        }
+
+        // A mirror gets its source's wallpaper by switching the source to it again
+        let source = monitor_manager.resolve_mirror(&name).to_string();
+        let first = if source == name {
+            monitor_manager.first_pick(&name)
+        } else {
+            monitor_manager
+                .outputs
+                .get(&source)
+                .and_then(|o| o.current_path.clone())
+                .and_then(|path| {
+                    let content_type = crate::queue::SmartQueue::get_content_type(&path)?;
+                    Some((path, content_type))
+                })
+        };
+        // Otherwise the next tick picks (a kiosk, or a queue with nothing yet)
+        if let Some((path, content_type)) = first {
+            switch_wallpaper_content(
+                &source,
+                &path,
+                content_type,
+                next_session_id,
+                frame_tx,
+                monitor_manager,
+                renderers,
+                video_players,
+                None,
+                Some(loop_start),
+                image_tx,
+                player_tx,
+                "HOTPLUG",
+            );
+        }
     }
+}
 
-    // 3. Initialize GStreamer
-    let gstreamer_start = Instant::now();
-    gstreamer::init()?;
-    let gstreamer_duration = gstreamer_start.elapsed();
-    info!("GStreamer initialized.");
+/// How often the compositor is looked for again after the connection to it was lost
+const WAYLAND_RECONNECT_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
 
-    // 4. Resource Monitor will be started in backend loops with metrics
+/// Connects to the compositor at WAYLAND_DISPLAY and binds its globals
+fn connect_wayland() -> anyhow::Result<(
+    Connection,
+    wayland_client::EventQueue<wayland::WaylandBackend>,
+    wayland::WaylandBackend,
+)> {
+    let conn = Connection::connect_to_env()?;
+    let (globals, mut event_queue) = registry_queue_init(&conn)?;
+    let qh = event_queue.handle();
+    let mut backend = wayland::WaylandBackend::new(&globals, &qh)?;
+    event_queue.roundtrip(&mut backend)?;
+    Ok((conn, event_queue, backend))
+}
 
-    // Detect Backend
-    let use_x11 = std::env::var("WAYLAND_DISPLAY").is_err() && std::env::var("DISPLAY").is_ok();
+/// Watches the connection's socket, so compositor events wake an idle loop
+fn watch_wayland(
+    conn: &Connection,
+) -> anyhow::Result<tokio::io::unix::AsyncFd<std::os::unix::io::RawFd>> {
+    use std::os::unix::io::{AsFd, AsRawFd};
+    let fd = conn.as_fd().as_raw_fd();
+    tokio::io::unix::AsyncFd::with_interest(fd, tokio::io::Interest::READABLE)
+        .map_err(|e| anyhow::anyhow!("Failed to watch the Wayland connection: {}", e))
+}
 
-    if use_x11 {
-        info!("Starting X11 Backend...");
-        run_x11_loop(config, log_level, gstreamer_duration).await
-    } else {
-        info!("Starting Wayland Backend...");
-        run_wayland_loop(config, log_level, gstreamer_duration).await
+/// Drops the renderers and GPU contexts of a compositor connection that went away and
+/// stops their videos. Outputs keep their queues and current wallpapers, and count their
+/// transitions as finished, until `restore_wayland_outputs` puts them back on screen.
+fn drop_wayland_outputs(
+    monitor_manager: &mut monitor_manager::MonitorManager,
+    renderers: &mut HashMap<String, renderer::Renderer>,
+    video_players: &mut HashMap<String, video::VideoPlayer>,
+    gpu_contexts: &mut GpuContexts,
+) {
+    for name in renderers.keys() {
+        monitor_manager.mark_transition_completed(name);
+    }
+    for (_, mut player) in video_players.drain() {
+        tokio::spawn(async move {
+            let _ = player.stop();
+        });
+    }
+    renderers.clear();
+    gpu_contexts.clear();
+}
+
+/// Sets the outputs back up on a new compositor connection: a surface and renderer for
+/// each output the daemon had, showing its current wallpaper again without a transition
+/// (videos restart). Outputs that didn't come back are removed; new ones are left in
+/// `new_outputs` for `add_hotplugged_outputs`.
+#[allow(clippy::too_many_arguments)]
+async fn restore_wayland_outputs(
+    backend: &mut wayland::WaylandBackend,
+    qh: &wayland_client::QueueHandle<wayland::WaylandBackend>,
+    display_ptr: *mut std::ffi::c_void,
+    gpu_contexts: &mut GpuContexts,
+    metrics: &Arc<metrics::PerformanceMetrics>,
+    monitor_manager: &mut monitor_manager::MonitorManager,
+    renderers: &mut HashMap<String, renderer::Renderer>,
+    video_players: &mut HashMap<String, video::VideoPlayer>,
+    frame_tx: &tokio::sync::mpsc::Sender<(Arc<String>, video::VideoEvent)>,
+    image_tx: &tokio::sync::mpsc::Sender<LoadedImage>,
+    player_tx: &tokio::sync::mpsc::UnboundedSender<VideoPlayerResult>,
+    next_session_id: &mut u64,
+    loop_start: Instant,
+) {
+    let mut returned = HashSet::new();
+    for output in std::mem::take(&mut backend.new_outputs) {
+        let Some(identity) = backend.output_identity(&output) else {
+            continue;
+        };
+        if !monitor_manager.outputs.contains_key(&identity.name) {
+            backend.new_outputs.push(output);
+            continue;
+        }
+        returned.insert(identity.name.clone());
+        add_output_renderer(
+            backend,
+            qh,
+            display_ptr,
+            &output,
+            &identity.name,
+            gpu_contexts,
+            metrics,
+            monitor_manager,
+            renderers,
+            "RECONNECT",
+        )
+        .await;
+    }
+    let gone: Vec<String> = monitor_manager
+        .outputs
+        .keys()
+        .filter(|name| !returned.contains(*name))
+        .cloned()
+        .collect();
+    for name in gone {
+        info!("[RECONNECT] Output {} did not come back", name);
+        monitor_manager.remove_output(&name);
+        events::emit(kaleidux_common::Event::OutputRemoved { output: name });
+    }
+
+    let mut images = Vec::new();
+    for name in sorted_names(renderers) {
+        if monitor_manager.resolve_mirror(&name) != name {
+            continue; // Filled from the mirrored output
+        }
+        // A cleared output stays empty until a next/prev/set aimed at it
+        if monitor_manager.is_cleared(&name) {
+            continue;
+        }
+        let Some(path) = monitor_manager
+            .outputs
+            .get(&name)
+            .and_then(|o| o.current_path.clone())
+        else {
+            continue;
+        };
+        match crate::queue::SmartQueue::get_content_type(&path) {
+            Some(crate::queue::ContentType::Image) => images.push((name, path)),
+            Some(content_type) => switch_wallpaper_content(
+                &name,
+                &path,
+                content_type,
+                next_session_id,
+                frame_tx,
+                monitor_manager,
+                renderers,
+                video_players,
+                None,
+                Some(loop_start),
+                image_tx,
+                player_tx,
+                "RECONNECT",
+            ),
+            None => {}
+        }
+    }
+    restore_last_wallpapers(monitor_manager, renderers, &images, false).await;
+    for (name, _) in &images {
+        monitor_manager.mark_transition_completed(name);
     }
 }
 
@@ -448,10 +2518,13 @@ async fn run_wayland_loop(
     // Directory watcher for cache invalidation (used in main loop)
     let mut dir_watcher = match cache::DirectoryWatcher::new(cache.clone()) {
         Ok(mut watcher) => {
-            // Watch all content directories from config
-            for output_config in config.outputs.values() {
-                if let Some(path) = &output_config.path {
-                    if let Err(e) = watcher.watch(path) {
+            // Watch all content directories (and static files) from config
+            for output_config in std::iter::once(&config.any).chain(config.outputs.values()) {
+                let sources = output_config.sources.iter().flatten().map(|s| &s.path);
+                for path in output_config.path.iter().chain(sources) {
+                    // URL sources are watched in the folder they are downloaded to
+                    let path = remote::local_path(path);
+                    if let Err(e) = watcher.watch(&path) {
                         tracing::warn!(
                             "[CACHE] Failed to watch directory {}: {}",
                             path.display(),
@@ -474,15 +2547,10 @@ async fn run_wayland_loop(
     }
 
     // Initialize Wayland
-    let conn = Connection::connect_to_env()?;
-    let (globals, mut event_queue) = registry_queue_init(&conn)?;
-    let qh = event_queue.handle();
-    let mut backend = wayland::WaylandBackend::new(&globals, &qh)?;
+    let (mut conn, mut event_queue, mut backend) = connect_wayland()?;
+    let mut qh = event_queue.handle();
 
-    event_queue.roundtrip(&mut backend)?;
-
-    let mut wgpu_ctx: Option<Arc<renderer::WgpuContext>> = None;
-    let mut initial_surface: Option<wgpu::Surface<'static>> = None;
+    let mut gpu_contexts: GpuContexts = Vec::new();
 
     // Frame channel: keep small to cap memory (each frame ~30-40MB).
     // 6 slots = ~2 per video source + slack; prevents ~900MB+ spike when loop is slow.
@@ -490,35 +2558,44 @@ async fn run_wayland_loop(
         tokio::sync::mpsc::channel::<(Arc<String>, video::VideoEvent)>(6);
     let mut renderers = HashMap::new();
     let outputs: Vec<_> = backend.output_state.outputs().collect();
+    // Set up below; only outputs announced from here on are hot-plugged
+    backend.new_outputs.clear();
 
-    let display_ptr = {
-        let backend_ref = conn.backend();
-        backend_ref.display_ptr() as *mut std::ffi::c_void
-    };
+    let mut display_ptr = conn.backend().display_ptr() as *mut std::ffi::c_void;
 
     // Phase 1: Collect all output info first (fast, no IO)
-    let mut output_infos: Vec<(String, String, wayland_client::protocol::wl_output::WlOutput)> =
-        Vec::new();
+    let mut output_infos: Vec<(
+        orchestration::OutputIdentity,
+        wayland_client::protocol::wl_output::WlOutput,
+    )> = Vec::new();
     for output in outputs {
-        let info = match backend.output_state.info(&output) {
-            Some(i) => i,
-            None => continue,
+        let Some(identity) = backend.output_identity(&output) else {
+            continue;
         };
-        let name = info.name.as_deref().unwrap_or("unknown").to_string();
-        let description = info.description.as_deref().unwrap_or("unknown").to_string();
-        info!("Found output: {} ({})", name, description);
-        output_infos.push((name, description, output));
+        if !config.is_output_enabled(&identity) {
+            info!(
+                "Found output: {} ({}) - disabled in config, skipping",
+                identity.name, identity.description
+            );
+            continue;
+        }
+        info!("Found output: {} ({})", identity.name, identity.description);
+        output_infos.push((identity, output));
     }
 
     // Phase 2: Initialize all outputs sequentially but with shared file discovery cache.
     // The monitor_manager's add_output will reuse cached file lists for duplicate paths.
-    for (name, description, _) in &output_infos {
-        monitor_manager.add_output(name, description).await;
+    for (identity, _) in &output_infos {
+        monitor_manager.add_output(identity).await;
+        events::emit(kaleidux_common::Event::OutputAdded {
+            output: identity.name.clone(),
+        });
     }
 
     // Phase 3: Create Wayland surfaces (fast, no IO)
     let mut surface_infos = Vec::new();
-    for (name, _description, output) in &output_infos {
+    for (identity, output) in &output_infos {
+        let name = &identity.name;
         let output_config = match monitor_manager.get_output_config(name) {
             Some(cfg) => cfg,
             None => continue,
@@ -528,7 +2605,7 @@ async fn run_wayland_loop(
             output,
             &qh,
             name.clone(),
-            output_config.layer.clone().into(),
+            output_config,
         )?;
 
         let raw_handle_surface = wayland::RawHandleSurface {
@@ -539,79 +2616,26 @@ async fn run_wayland_loop(
         surface_infos.push((name.clone(), surface_arc));
     }
 
-    if let Some((_, first_surface_arc)) = surface_infos.first() {
-        info!("Initializing WGPU context with first surface as compatible...");
-        let wgpu_start = Instant::now();
-        let (ctx, surface) = renderer::WgpuContext::with_surface(first_surface_arc.clone()).await?;
-        let wgpu_duration = wgpu_start.elapsed();
-        metrics.record_wgpu_init(wgpu_duration);
-        let adapter_name = ctx.adapter.get_info().name.clone();
-        wgpu_ctx = Some(ctx);
-        initial_surface = Some(surface);
-        info!("WGPU initialized on GPU: {:?}", adapter_name);
+    if surface_infos.is_empty() {
+        warn!("[STARTUP] No outputs to render on, no WGPU context created!");
     }
 
-    if let Some(ctx) = wgpu_ctx.clone() {
-        let first_name = surface_infos.first().map(|(n, _)| n.clone());
-
-        for (name, surface_arc) in surface_infos {
-            let ctx_clone = ctx.clone();
-            let is_first = Some(&name) == first_name.as_ref();
-            let init_surf = if is_first {
-                initial_surface.take()
-            } else {
-                None
-            };
-
-            let metrics_clone = metrics.clone();
-
-            info!("[STARTUP] Initializing renderer for {}", name);
-
-            // Offload WGPU surface creation to blocking thread to avoid checking generic runtime
-            let name_for_bg = name.clone();
-            let spawn_handler = tokio::task::spawn_blocking(move || {
-                renderer::Renderer::new(
-                    name_for_bg,
-                    ctx_clone,
-                    surface_arc,
-                    init_surf,
-                    Some(metrics_clone),
-                )
-            });
-
-            // Set a timeout strictly for the initialization
-            match tokio::time::timeout(std::time::Duration::from_secs(5), spawn_handler).await {
-                Ok(join_res) => match join_res {
-                    Ok(render_res) => match render_res {
-                        Ok(mut r) => {
-                            if let Some(output_config) = monitor_manager.get_output_config(&name) {
-                                r.apply_config(output_config);
-                            }
-                            renderers.insert(name.clone(), r);
-                            info!("[STARTUP] Renderer initialized successfully for {}", name);
-                        }
-                        Err(e) => {
-                            error!("Failed to create renderer for output {}: {}", name, e);
-                            metrics.record_error("renderer_creation");
-                        }
-                    },
-                    Err(e) => {
-                        error!("Thread join error for output {}: {}", name, e);
-                        metrics.record_error("renderer_thread_error");
-                    }
-                },
-                Err(_) => {
-                    // Timeout occurred
-                    error!(
-                        "TIMEOUT: Renderer initialization for {} took longer than 5s. Skipping.",
-                        name
-                    );
-                    metrics.record_error("renderer_creation_timeout");
-                }
-            }
-            // Poll device to process submission/initialization commands
-            ctx.device.poll(wgpu::Maintain::Poll);
+    for (name, surface_arc) in surface_infos {
+        info!("[STARTUP] Initializing renderer for {}", name);
+        let renderer = create_renderer(
+            &name,
+            surface_arc,
+            &monitor_manager,
+            &mut gpu_contexts,
+            &metrics,
+        )
+        .await?;
+        if let Some(r) = renderer {
+            renderers.insert(name.clone(), r);
+            info!("[STARTUP] Renderer initialized successfully for {}", name);
         }
+    }
+    if !gpu_contexts.is_empty() {
         // All renderers created - full initialization complete
         metrics.record_full_init();
         if log_level.map(|l| l >= 3).unwrap_or(false) {
@@ -621,8 +2645,6 @@ async fn run_wayland_loop(
             "[STARTUP] All renderers created, count: {}",
             renderers.len()
         );
-    } else {
-        warn!("[STARTUP] No WGPU context available, cannot create renderers!");
     }
 
     info!("[STARTUP] Creating video players HashMap");
@@ -638,64 +2660,12 @@ async fn run_wayland_loop(
     let script_cmd_tx = cmd_tx.clone();
 
     // IPC Socket Setup
-    info!("[STARTUP] Setting up IPC socket");
-    let socket_path = dirs::runtime_dir()
-        .map(|d| d.join("kaleidux.sock"))
-        .unwrap_or_else(|| {
-            let uid = std::env::var("USER").unwrap_or_else(|_| "kaleidux".to_string());
-            std::path::PathBuf::from(format!("/tmp/kaleidux-{}.sock", uid))
-        });
-
-    info!("[STARTUP] IPC socket path: {:?}", socket_path);
-    let _ = std::fs::remove_file(&socket_path);
-    let listener = UnixListener::bind(&socket_path)?;
-    info!("[STARTUP] IPC socket bound successfully");
-
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        if let Ok(metadata) = std::fs::metadata(&socket_path) {
-            let mut perms = metadata.permissions();
-            perms.set_mode(0o600);
-            let _ = std::fs::set_permissions(&socket_path, perms);
-        }
-    }
-
-    // Spawn IPC Listener
-    let cmd_tx_clone = cmd_tx.clone();
-    tokio::spawn(async move {
-        loop {
-            if let Ok((mut stream, _)) = listener.accept().await {
-                let cmd_tx = cmd_tx_clone.clone();
-                tokio::spawn(async move {
-                    const MAX_MESSAGE_SIZE: usize = 8192;
-                    let mut temp_buf = [0u8; MAX_MESSAGE_SIZE];
-                    if let Ok(n) = stream.read(&mut temp_buf).await {
-                        if n == 0 || n >= MAX_MESSAGE_SIZE {
-                            return;
-                        }
-                        if let Ok(req_str) = std::str::from_utf8(&temp_buf[..n]) {
-                            if let Ok(req) = serde_json::from_str::<Request>(req_str.trim()) {
-                                let (resp_tx, resp_rx) = tokio::sync::oneshot::channel();
-                                if cmd_tx.send((req, resp_tx)).is_ok() {
-                                    if let Ok(response) = resp_rx.await {
-                                        if let Ok(json) = serde_json::to_string(&response) {
-                                            let _ = stream.write_all(json.as_bytes()).await;
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                });
-            }
-        }
-    });
+    spawn_ipc_listener(cmd_tx.clone())?;
 
     let shutdown_flag = Arc::new(AtomicBool::new(false));
     let shutdown_clone = shutdown_flag.clone();
     tokio::spawn(async move {
-        let _ = tokio::signal::ctrl_c().await;
+        shutdown_signal().await;
         warn!("Received shutdown signal, cleaning up...");
         shutdown_clone.store(true, Ordering::SeqCst);
     });
@@ -709,12 +2679,28 @@ async fn run_wayland_loop(
     let mut last_script_tick = Instant::now();
     info!("[STARTUP] Script manager initialized");
 
-    let target_frame_time = std::time::Duration::from_micros(16667); // ~60 FPS
-    let mut connection_error_count = 0u32;
-    const MAX_CONSECUTIVE_ERRORS: u32 = 3;
-    let mut connection_dead = false;
-    let mut last_error_time = Instant::now();
+    let mut overlay_manager = overlay::OverlayManager::new(&config.overlay);
+    let mut pending_waits: Vec<PendingWait> = Vec::new();
+    // Outputs whose change settled since the last export_settled_frames
+    let mut settled: HashSet<String> = HashSet::new();
+    // Their frames on the way back from the GPU
+    let mut frame_readbacks: Vec<(String, blur::Readback)> = Vec::new();
+
+    // Set when the compositor went away, until it is back
+    let mut wayland_lost: Option<Instant> = None;
     let mut last_pool_cleanup = Instant::now();
+    let mut idle = IdleTracker::new();
+    let mut last_cache_gc = Instant::now();
+    // Due immediately, so rules active at startup apply on the first pass
+    let mut last_playlist_schedule = Instant::now()
+        .checked_sub(PLAYLIST_SCHEDULE_INTERVAL)
+        .unwrap_or_else(Instant::now);
+    let mut state_publisher = state::Publisher::new(instance::state_path());
+    let mut power = power::PowerMonitor::new();
+    let mut remote_sync = remote::RemoteSync::new();
+    let mut watchdog = systemd::Watchdog::new();
+    let mut last_state_publish = Instant::now();
+    let mut system_sampler = monitor::SystemSampler::new();
     let mut last_stats_flush = Instant::now();
     let mut first_frame_recorded = false;
 
@@ -771,6 +2757,9 @@ async fn run_wayland_loop(
         let resizes: Vec<_> = backend.pending_resizes.drain(..).collect();
         for (name, w, h, _) in resizes {
             if let Some(r) = renderers.get_mut(&name) {
+                r.awaiting_configure = false;
+                // Configure sizes are logical; render at physical resolution on HiDPI
+                let (w, h) = backend.apply_buffer_scale(&name, w, h);
                 let width = if w == 0 { r.config.width } else { w };
                 let height = if h == 0 { r.config.height } else { h };
                 let _ = r.resize_checked(width, height);
@@ -830,6 +2819,25 @@ async fn run_wayland_loop(
         }
     }
 
+    // Snapshots first so the first frames show something, then the full-quality originals
+    let last = last_wallpapers(&monitor_manager, &renderers);
+    restore_last_wallpapers(&monitor_manager, &mut renderers, &last, true).await;
+    for (name, r) in renderers.iter_mut() {
+        if r.configured && r.has_any_content() {
+            if let Some((_, layer_surface)) = backend.surfaces.iter().find(|(n, _)| n == name) {
+                let _ = r.render(
+                    renderer::BackendContext::Wayland {
+                        surface: layer_surface,
+                        qh: &qh,
+                    },
+                    Instant::now(),
+                );
+            }
+        }
+    }
+    let _ = conn.flush();
+    restore_last_wallpapers(&monitor_manager, &mut renderers, &last, false).await;
+
     info!("[STARTUP] About to call monitor_manager.tick()");
     let initial_changes = monitor_manager.tick();
     info!(
@@ -866,7 +2874,7 @@ async fn run_wayland_loop(
             content_type,
             &mut next_session_id,
             &frame_tx,
-            &monitor_manager,
+            &mut monitor_manager,
             &mut renderers,
             &mut video_players,
             Some(batch_id),
@@ -877,28 +2885,75 @@ async fn run_wayland_loop(
         );
     }
 
+    // Outputs whose frame callback came in before transition-fps-cap allowed a new frame;
+    // they are rendered from the loop once the interval has passed
+    let mut held_frames: HashSet<String> = HashSet::new();
+
+    // Watched while idle, so compositor events wake the loop as soon as they arrive
+    let mut display_fd = Some(watch_wayland(&conn)?);
+    // A command that ended an idle sleep, handled ahead of the channel
+    let mut woken_by = None;
+
+    // Outputs are configured and their first wallpapers on the way
+    systemd::notify("READY=1");
+
     // Main Loop (Wayland)
     loop {
         let loop_start = Instant::now();
         if shutdown_flag.load(Ordering::SeqCst) {
+            systemd::notify("STOPPING=1");
             for player in video_players.values_mut() {
                 let _ = player.stop();
             }
+            let _ = monitor_manager.flush_all_stats();
             break;
         }
 
-        if connection_dead {
-            if last_error_time.elapsed().as_secs() > 5 {
-                connection_dead = false;
-                connection_error_count = 0;
-            } else {
-                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
-                continue;
+        // The compositor went away: look for it again, then set the outputs back up
+        if let Some(last_attempt) = wayland_lost {
+            if last_attempt.elapsed() >= WAYLAND_RECONNECT_INTERVAL {
+                let connected =
+                    connect_wayland().and_then(|(c, q, b)| Ok((watch_wayland(&c)?, c, q, b)));
+                match connected {
+                    Ok((fd, new_conn, new_queue, new_backend)) => {
+                        info!("[RECONNECT] Compositor is back, restoring outputs");
+                        (conn, event_queue, backend) = (new_conn, new_queue, new_backend);
+                        qh = event_queue.handle();
+                        display_ptr = conn.backend().display_ptr() as *mut std::ffi::c_void;
+                        display_fd = Some(fd);
+                        wayland_lost = None;
+                        restore_wayland_outputs(
+                            &mut backend,
+                            &qh,
+                            display_ptr,
+                            &mut gpu_contexts,
+                            &metrics,
+                            &mut monitor_manager,
+                            &mut renderers,
+                            &mut video_players,
+                            &frame_tx,
+                            &image_tx,
+                            &player_tx,
+                            &mut next_session_id,
+                            loop_start,
+                        )
+                        .await;
+                    }
+                    Err(e) => {
+                        debug!("[RECONNECT] Compositor not back yet: {:#}", e);
+                        wayland_lost = Some(Instant::now());
+                    }
+                }
             }
         }
 
-        // Wayland Event Polling
-        if let Some(guard) = conn.prepare_read() {
+        // Wayland Event Polling, once the compositor is there
+        let guard = if wayland_lost.is_none() {
+            conn.prepare_read()
+        } else {
+            None
+        };
+        if let Some(guard) = guard {
             use std::os::unix::io::{AsFd, AsRawFd};
             let fd = conn.as_fd().as_raw_fd();
             let mut poll_fd = libc::pollfd {
@@ -906,24 +2961,38 @@ async fn run_wayland_loop(
                 events: libc::POLLIN,
                 revents: 0,
             };
-            let ret = unsafe { libc::poll(&mut poll_fd, 1, 5) };
+            // Wake up in time for held transition frames
+            let timeout = held_frames
+                .iter()
+                .filter_map(|name| renderers.get(name))
+                .filter_map(|r| r.transition_frame_wait(Instant::now()))
+                .min()
+                .map_or(5, |wait| wait.as_millis().min(5) as i32);
+            let ret = unsafe { libc::poll(&mut poll_fd, 1, timeout) };
             if ret > 0 && (poll_fd.revents & libc::POLLIN != 0) {
                 let _ = guard.read();
             }
         }
 
-        if let Err(e) = event_queue.dispatch_pending(&mut backend) {
-            let error_str = e.to_string();
-            if error_str.contains("Broken pipe") {
-                connection_error_count += 1;
-                last_error_time = Instant::now();
-                if connection_error_count >= MAX_CONSECUTIVE_ERRORS {
-                    connection_dead = true;
-                }
+        // Any error leaves the connection unusable, typically a compositor that crashed
+        // or restarted
+        if wayland_lost.is_none() {
+            if let Err(e) = event_queue.dispatch_pending(&mut backend) {
+                error!("[RECONNECT] Lost the Wayland connection: {}", e);
+                // Unregistered before the connection's socket closes
+                display_fd = None;
+                held_frames.clear();
+                drop_wayland_outputs(
+                    &mut monitor_manager,
+                    &mut renderers,
+                    &mut video_players,
+                    &mut gpu_contexts,
+                );
+                wayland_lost = Some(Instant::now());
             }
         }
 
-        if !connection_dead {
+        if wayland_lost.is_none() {
             let needs_flush = renderers
                 .values()
                 .any(|r| r.needs_redraw || r.transition_active);
@@ -933,30 +3002,54 @@ async fn run_wayland_loop(
         }
 
         // Logic (Common)
-        {
+        if wayland_lost.is_none() {
             // Remove orphaned renderers
             let active_output_names: std::collections::HashSet<String> = backend
                 .output_state
                 .outputs()
                 .filter_map(|o| backend.output_state.info(&o).and_then(|i| i.name.clone()))
                 .collect();
-            renderers.retain(|name, _| {
-                if !active_output_names.contains(name) {
-                    if let Some(mut vp) = video_players.remove(name) {
-                        tokio::spawn(async move {
-                            let _ = vp.stop();
-                        });
-                    }
-                    false
-                } else {
-                    true
+            let removed: Vec<String> = renderers
+                .keys()
+                .filter(|name| !active_output_names.contains(*name))
+                .cloned()
+                .collect();
+            for name in removed {
+                info!("[HOTPLUG] Output {} disconnected", name);
+                renderers.remove(&name);
+                if let Some(mut vp) = video_players.remove(&name) {
+                    tokio::spawn(async move {
+                        let _ = vp.stop();
+                    });
                 }
-            });
+                backend.remove_surface(&name);
+                monitor_manager.remove_output(&name);
+                events::emit(kaleidux_common::Event::OutputRemoved { output: name });
+            }
+
+            add_hotplugged_outputs(
+                &mut backend,
+                &qh,
+                display_ptr,
+                &mut gpu_contexts,
+                &metrics,
+                &mut monitor_manager,
+                &mut renderers,
+                &mut video_players,
+                &frame_tx,
+                &image_tx,
+                &player_tx,
+                &mut next_session_id,
+                loop_start,
+            )
+            .await;
 
             // Handle Resizes
             let resizes: Vec<_> = backend.pending_resizes.drain(..).collect();
             for (name, w, h, _) in resizes {
                 if let Some(r) = renderers.get_mut(&name) {
+                    r.awaiting_configure = false;
+                    let (w, h) = backend.apply_buffer_scale(&name, w, h);
                     let width = if w == 0 { r.config.width } else { w };
                     let height = if h == 0 { r.config.height } else { h };
                     let _ = r.resize_checked(width, height);
@@ -980,8 +3073,12 @@ async fn run_wayland_loop(
             }
         }
 
-        // Automated Changes
-        let scheduled_changes = monitor_manager.tick();
+        // Automated Changes, held while there is no compositor to show them
+        let scheduled_changes = if wayland_lost.is_none() {
+            monitor_manager.tick()
+        } else {
+            Default::default()
+        };
         if !scheduled_changes.is_empty() {
             let batch_id = rand::random::<u64>();
             for (name, (path, content_type)) in scheduled_changes {
@@ -991,7 +3088,7 @@ async fn run_wayland_loop(
                     content_type,
                     &mut next_session_id,
                     &frame_tx,
-                    &monitor_manager,
+                    &mut monitor_manager,
                     &mut renderers,
                     &mut video_players,
                     Some(batch_id),
@@ -1002,6 +3099,19 @@ async fn run_wayland_loop(
                 );
             }
         }
+        if wayland_lost.is_none() {
+            retry_failed_loads(
+                &mut monitor_manager,
+                &mut renderers,
+                &mut video_players,
+                &frame_tx,
+                &image_tx,
+                &player_tx,
+                &mut next_session_id,
+                loop_start,
+            );
+            show_failovers(&mut monitor_manager, &mut renderers, &mut video_players).await;
+        }
 
         // Scripting
         if last_script_tick.elapsed().as_secs() >= script_tick_interval {
@@ -1009,8 +3119,25 @@ async fn run_wayland_loop(
             last_script_tick = Instant::now();
         }
 
+        // Text Overlay
+        update_overlays(&mut overlay_manager, &mut renderers);
+
         // Handle Commands
-        while let Ok((req, resp)) = cmd_rx.try_recv() {
+        while let Some((req, resp)) = woken_by.take().or_else(|| cmd_rx.try_recv().ok()) {
+            // Waits are answered later, once the outputs they target settle
+            if let Request::Wait { output, timeout } = req {
+                pending_waits.push(PendingWait {
+                    output,
+                    deadline: timeout.map(|t| Instant::now() + std::time::Duration::from_secs(t)),
+                    resp,
+                });
+                continue;
+            }
+            // Answered when the pull finishes (see MonitorManager::poll_fetch)
+            if let Request::Fetch(FetchCommand::Now) = req {
+                monitor_manager.fetch_now(resp);
+                continue;
+            }
             let response = handle_command(
                 req,
                 &mut monitor_manager,
@@ -1026,6 +3153,30 @@ async fn run_wayland_loop(
             .await;
             let _ = resp.send(response);
         }
+        resolve_waits(&mut pending_waits, &renderers);
+
+        // Outputs under a fullscreen window, or all while the session is idle
+        if wayland_lost.is_none() {
+            backend.watch_idle(monitor_manager.pause_on_idle(), &qh);
+        }
+        apply_covered(
+            backend.covered_outputs(),
+            backend.idle,
+            &mut monitor_manager,
+            &mut renderers,
+            &video_players,
+        );
+
+        // Unmap surfaces of outputs that entered standby, remap those that left it
+        for (name, r) in renderers.iter_mut() {
+            if r.standby != r.surface_hidden {
+                backend.set_surface_mapped(name, !r.standby);
+                r.surface_hidden = r.standby;
+                // A remapped layer surface must not present until it is configured again
+                r.awaiting_configure = !r.standby;
+                let _ = conn.flush();
+            }
+        }
 
         // Handle Frames
         // CRITICAL: Process ALL frames in channel, not just latest per source
@@ -1046,7 +3197,7 @@ async fn run_wayland_loop(
                 }
                 video::VideoEvent::Error(msg) => {
                     error!("Video error {}: {}", source_id, msg);
-                    metrics.record_error("video_decode");
+                    metrics.record_output_error(&source_id, "video_decode");
                 }
             }
         }
@@ -1060,13 +3211,16 @@ async fn run_wayland_loop(
                 );
             }
         }
-        // Process all frames (one per source, the latest)
+        // Process all frames (one per source, the latest), fanned out to mirrors
         for (source_id, frame) in latest_frames {
-            if let Some(r) = renderers.get_mut(source_id.as_str()) {
+            for target in content_targets(source_id.as_str(), &monitor_manager) {
+                let Some(r) = renderers.get_mut(&target) else {
+                    continue;
+                };
                 // Video: upload unless frame callbacks are stuck (prevents memory leak from
                 // WGPU staging buffers accumulating when compositor isn't consuming frames).
                 // Images: only upload when we'll present (callback not pending) or first frame.
-                let should_upload = if r.valid_content_type == crate::queue::ContentType::Video {
+                let should_upload = if r.valid_content_type.is_played() {
                     // Always upload first frame (needed to start transition/display).
                     // After that, throttle if callbacks are stuck >1s to prevent memory balloon.
                     !r.has_current_texture() || !r.frame_callback_pending_too_long(1000)
@@ -1079,18 +3233,11 @@ async fn run_wayland_loop(
                     r.upload_frame(&frame);
                     let video_duration = _video_start.elapsed();
                     metrics.record_video_cpu_time(video_duration);
-                    drop(frame);
-                } else {
-                    // Frame throttled - drop immediately to release gst::Buffer
-                    // This prevents buffer accumulation when renderer is busy or stuck
-                    drop(frame);
                 }
 
-                if r.valid_content_type == crate::queue::ContentType::Video {
-                    if let Some((_, layer_surface)) = backend
-                        .surfaces
-                        .iter()
-                        .find(|(n, _)| n == source_id.as_str())
+                if r.valid_content_type.is_played() {
+                    if let Some((_, layer_surface)) =
+                        backend.surfaces.iter().find(|(n, _)| n == &target)
                     {
                         // Deadlock fix: if this is the first frame of a transition (progress == 0),
                         // we MUST render and commit it to trigger the Wayland frame callback loop,
@@ -1111,14 +3258,13 @@ async fn run_wayland_loop(
                         }
                     }
                 }
-            } else {
-                // Renderer doesn't exist - drop frame immediately
-                drop(frame);
             }
+            // Release the gst::Buffer as soon as every target has uploaded it
+            drop(frame);
         }
         // Let GPU process uploads when we handled frames to limit staging queue growth
         if frames_received > 0 {
-            if let Some(ctx) = &wgpu_ctx {
+            for (_, ctx) in &gpu_contexts {
                 ctx.device.poll(wgpu::Maintain::Poll);
             }
         }
@@ -1134,18 +3280,37 @@ async fn run_wayland_loop(
                 msg.width,
                 msg.height
             );
-            if let Some(r) = renderers.get_mut(&msg.name) {
-                if let Some(data) = msg.data {
+            if !renderers.contains_key(&msg.name) {
+                // CRITICAL: Renderer doesn't exist - drop image data immediately to prevent memory leak
+                warn!(
+                    "[IMAGE] {}: Renderer not found, dropping image data to prevent memory leak",
+                    msg.name
+                );
+                // msg.data is dropped here, releasing its share of the buffer
+                continue;
+            }
+            match &msg.error {
+                Some(error) => monitor_manager.record_load_failure(&msg.name, &msg.path, error),
+                None => monitor_manager.record_load_success(&msg.name, &msg.path),
+            }
+            apply_auto_transition(&msg, &mut monitor_manager, &mut renderers);
+            // Decoded once, uploaded to the source and each of its mirrors
+            let targets = content_targets(&msg.name, &monitor_manager);
+            for target in targets {
+                let Some(r) = renderers.get_mut(&target) else {
+                    continue;
+                };
+                if let Some(target_data) = &msg.data {
                     debug!(
                         "[IMAGE] Uploading image data for {}: {} bytes",
-                        msg.name,
-                        data.len()
+                        target,
+                        target_data.len()
                     );
-                    let _ = r.upload_image_data(data, msg.width, msg.height);
-                    debug!("[IMAGE] Rendering after upload for {}", msg.name);
+                    let _ = r.upload_image_data(target_data, msg.width, msg.height);
+                    debug!("[IMAGE] Rendering after upload for {}", target);
                     if r.configured {
                         if let Some((_, layer_surface)) =
-                            backend.surfaces.iter().find(|(n, _)| n == &msg.name)
+                            backend.surfaces.iter().find(|(n, _)| n == &target)
                         {
                             let _ = r.render(
                                 renderer::BackendContext::Wayland {
@@ -1163,13 +3328,6 @@ async fn run_wayland_loop(
                 } else {
                     r.abort_transition();
                 }
-            } else {
-                // CRITICAL: Renderer doesn't exist - drop image data immediately to prevent memory leak
-                warn!(
-                    "[IMAGE] {}: Renderer not found, dropping image data to prevent memory leak",
-                    msg.name
-                );
-                // msg.data is dropped here, freeing the Vec<u8>
             }
         }
         // Track image channel usage for memory leak detection
@@ -1180,8 +3338,10 @@ async fn run_wayland_loop(
         // Async Video Players
         while let Ok(res) = player_rx.try_recv() {
             match res {
-                VideoPlayerResult::Success(name, session_id, mut player) => {
+                VideoPlayerResult::Success(name, session_id, path, mut player) => {
                     if renderers.get(&name).map(|r| r.active_video_session_id) == Some(session_id) {
+                        monitor_manager.record_load_success(&name, &path);
+                        pause_if_held(&name, &player, &monitor_manager);
                         if let Some(mut old) = video_players.insert(name, player) {
                             tokio::spawn(async move {
                                 let _ = old.stop();
@@ -1194,22 +3354,55 @@ async fn run_wayland_loop(
                         });
                     }
                 }
-                VideoPlayerResult::Failure(name, session_id) => {
+                VideoPlayerResult::Failure(name, session_id, path, error) => {
                     if renderers.get(&name).map(|r| r.active_video_session_id) == Some(session_id) {
-                        if let Some(r) = renderers.get_mut(&name) {
-                            r.abort_transition();
+                        for target in content_targets(&name, &monitor_manager) {
+                            if let Some(r) = renderers.get_mut(&target) {
+                                r.abort_transition();
+                            }
                         }
+                        monitor_manager.record_load_failure(&name, &path, &error);
                     }
                 }
             }
         }
 
         // Rendering
-        let frame_ready_names: Vec<String> = backend.frame_callback_ready.drain().collect();
+        let now = Instant::now();
+        // Shader wallpapers draw their next frame, presented like any other redraw
+        for (name, r) in renderers.iter_mut() {
+            if r.shader_path().is_none() || monitor_manager.is_covered(name) {
+                continue;
+            }
+            if let Some(position) = backend.pointer_position(name) {
+                r.set_pointer(position);
+            }
+            r.advance_shader(now);
+        }
+        let mut frame_ready_names: Vec<String> = backend
+            .frame_callback_ready
+            .drain()
+            .filter(|name| !held_frames.contains(name))
+            .collect();
+        // Held frames whose transition-fps-cap interval has passed go out now
+        held_frames.retain(|name| {
+            let waiting = renderers
+                .get(name)
+                .is_some_and(|r| r.transition_frame_wait(now).is_some());
+            if !waiting {
+                frame_ready_names.push(name.clone());
+            }
+            waiting
+        });
         for name in frame_ready_names {
             if let Some(r) = renderers.get_mut(&name) {
                 r.frame_callback_pending = false;
                 r.last_frame_request = None;
+                // Too early for transition-fps-cap: hold the frame instead of rendering it
+                if r.transition_frame_wait(now).is_some() {
+                    held_frames.insert(name);
+                    continue;
+                }
                 if let Some((_, layer_surface)) = backend.surfaces.iter().find(|(n, _)| n == &name)
                 {
                     let _ = r.render(
@@ -1232,31 +3425,89 @@ async fn run_wayland_loop(
             // Only request frame callbacks when we have content to render.
             // Without a texture (current or prev), the renderer can't commit a frame,
             // so the compositor will never send a callback -> infinite stuck loop.
-            let should_request = r.has_any_content() && (r.needs_redraw || r.transition_active);
+            let should_request = r.has_any_content()
+                && (r.needs_redraw || r.transition_active)
+                && !held_frames.contains(name)
+                && !monitor_manager.is_covered(name);
             if should_request {
                 if let Some((_, layer_surface)) = backend.surfaces.iter().find(|(n, _)| n == name) {
                     r.request_frame_callback(layer_surface, &qh);
                 }
+            } else if r.solid_color.is_some() && r.needs_redraw && !r.frame_callback_pending {
+                // Cleared to a color: nothing is committed that would produce a callback,
+                // so draw the fill directly (render() requests the next callback itself)
+                if let Some((_, layer_surface)) = backend.surfaces.iter().find(|(n, _)| n == name) {
+                    let _ = r.render(
+                        renderer::BackendContext::Wayland {
+                            surface: layer_surface,
+                            qh: &qh,
+                        },
+                        loop_start,
+                    );
+                }
             }
             // Check if transition just completed (for cases where render wasn't called this loop)
             if r.transition_just_completed {
                 r.transition_just_completed = false; // Clear flag
                 monitor_manager.mark_transition_completed(name);
+                settled.insert(name.clone());
             }
         }
+        if !settled.is_empty() || !frame_readbacks.is_empty() {
+            export_settled_frames(
+                &mut settled,
+                &mut frame_readbacks,
+                &renderers,
+                &monitor_manager,
+                None,
+            );
+        }
 
         // Record frame time
         let frame_time = loop_start.elapsed();
         metrics.record_frame_time(frame_time);
 
+        // Refresh the time/load/battery uniforms seen by custom shaders (once per second)
+        if !all_in_standby(&renderers) {
+            if let Some(block) = system_sampler.poll() {
+                for r in renderers.values_mut() {
+                    r.set_system_uniforms(block);
+                }
+            }
+        }
+
         // Cleanup texture pool periodically (every 3 seconds for more aggressive cleanup)
         if last_pool_cleanup.elapsed().as_secs() >= 3 {
-            if let Some(ctx) = &wgpu_ctx {
+            for (_, ctx) in &gpu_contexts {
                 ctx.cleanup_texture_pool(Some(&metrics));
                 // Removed blocking poll(Wait) to prevent UI freezes/deadlocks
             }
             last_pool_cleanup = Instant::now();
         }
+        let busy = !video_players.is_empty() || renderers.values().any(|r| r.is_busy());
+        if idle.due(busy, monitor_manager.idle_trim()) {
+            trim_idle_memory(&mut renderers, &gpu_contexts, &metrics);
+        }
+
+        if last_cache_gc.elapsed() >= CACHE_GC_INTERVAL {
+            enforce_cache_limit(&monitor_manager);
+            last_cache_gc = Instant::now();
+        }
+        if last_playlist_schedule.elapsed() >= PLAYLIST_SCHEDULE_INTERVAL {
+            monitor_manager.apply_playlist_schedule();
+            last_playlist_schedule = Instant::now();
+        }
+        if let Some(saving) = power.poll(monitor_manager.power_config()) {
+            apply_power_saving(saving, &mut monitor_manager, &video_players);
+        }
+        let stale = remote_sync.poll(monitor_manager.config());
+        monitor_manager.delete_files(&stale);
+        monitor_manager.poll_fetch();
+        watchdog.poll();
+        if last_state_publish.elapsed() >= STATE_PUBLISH_INTERVAL {
+            publish_state(&mut state_publisher, &monitor_manager, None);
+            last_state_publish = Instant::now();
+        }
 
         // Flush stats every 5 seconds (batched writes)
         if last_stats_flush.elapsed().as_secs() >= 5 {
@@ -1264,19 +3515,41 @@ async fn run_wayland_loop(
             last_stats_flush = Instant::now();
         }
 
-        // Process directory watcher events (cache invalidation)
+        // Process directory watcher events (cache invalidation, static file and library
+        // changes)
         if let Some(ref mut watcher) = dir_watcher {
-            watcher.process_events().await;
+            for path in watcher.process_events().await {
+                monitor_manager.static_file_changed(&path);
+            }
+            let changes = watcher.library_changes();
+            if !changes.is_empty() {
+                monitor_manager.library_changed(&changes);
+            }
         }
 
         // Log metrics summary every 30 seconds (or 10 seconds for testing)
         if last_metrics_log.elapsed().as_secs() >= 10 {
             // Record resource counts for leak detection
-            if let Some(ctx) = &wgpu_ctx {
-                let texture_count = ctx.texture_pool.lock().values().map(|v| v.len()).sum();
-                let pipeline_count = ctx.transition_pipelines.lock().len()
-                    + ctx.blit_pipelines.lock().len()
-                    + ctx.mipmap_pipelines.lock().len();
+            if !gpu_contexts.is_empty() {
+                let texture_count = gpu_contexts
+                    .iter()
+                    .map(|(_, ctx)| {
+                        ctx.texture_pool
+                            .lock()
+                            .values()
+                            .map(|v| v.len())
+                            .sum::<usize>()
+                    })
+                    .sum();
+                let pipeline_count = gpu_contexts
+                    .iter()
+                    .map(|(_, ctx)| {
+                        ctx.transition_pipelines.lock().len()
+                            + ctx.blit_pipelines.lock().len()
+                            + ctx.mipmap_pipelines.lock().len()
+                            + ctx.overlay_pipelines.lock().len()
+                    })
+                    .sum();
                 metrics.record_texture_count(texture_count);
                 metrics.record_pipeline_count(pipeline_count);
             }
@@ -1284,12 +3557,31 @@ async fn run_wayland_loop(
             last_metrics_log = Instant::now();
         }
 
-        // Timing
+        // Timing: while nothing animates, sleep until a command or compositor event
+        // instead of ticking at the frame rate
         let elapsed = loop_start.elapsed();
-        if elapsed < target_frame_time {
-            tokio::time::sleep(target_frame_time - elapsed).await;
+        let standby = all_in_standby(&renderers);
+        let idle_loop = standby
+            || (held_frames.is_empty()
+                && nothing_animates(&renderers, &video_players, &monitor_manager));
+        let frame_time = if standby {
+            STANDBY_FRAME_TIME
+        } else if idle_loop {
+            monitor_manager.idle_frame_time(IDLE_FRAME_TIME)
+        } else {
+            monitor_manager.frame_time()
+        };
+        if elapsed < frame_time {
+            if idle_loop {
+                if wayland_lost.is_none() {
+                    let _ = conn.flush();
+                }
+                woken_by = idle_sleep(frame_time - elapsed, &mut cmd_rx, display_fd.as_ref()).await;
+            } else {
+                tokio::time::sleep(frame_time - elapsed).await;
+            }
         }
-        if let Some(ctx) = &wgpu_ctx {
+        for (_, ctx) in &gpu_contexts {
             ctx.device.poll(wgpu::Maintain::Poll);
         }
     }
@@ -1325,10 +3617,13 @@ async fn run_x11_loop(
     let cache = monitor_manager.get_cache();
     let mut dir_watcher = match cache::DirectoryWatcher::new(cache.clone()) {
         Ok(mut watcher) => {
-            // Watch all content directories from config
-            for output_config in config.outputs.values() {
-                if let Some(path) = &output_config.path {
-                    if let Err(e) = watcher.watch(path) {
+            // Watch all content directories (and static files) from config
+            for output_config in std::iter::once(&config.any).chain(config.outputs.values()) {
+                let sources = output_config.sources.iter().flatten().map(|s| &s.path);
+                for path in output_config.path.iter().chain(sources) {
+                    // URL sources are watched in the folder they are downloaded to
+                    let path = remote::local_path(path);
+                    if let Err(e) = watcher.watch(&path) {
                         tracing::warn!(
                             "[CACHE] Failed to watch directory {}: {}",
                             path.display(),
@@ -1351,91 +3646,90 @@ async fn run_x11_loop(
     }
 
     let mut backend = x11::X11Backend::new()?;
-    // Query RandR for monitors
+    // Query RandR for monitors on every X screen
     let monitors = backend.get_monitors()?;
-    let mut wgpu_ctx: Option<Arc<renderer::WgpuContext>> = None;
+    let mut gpu_contexts: GpuContexts = Vec::new();
     let mut renderers: HashMap<String, renderer::Renderer> = HashMap::new();
     let mut window_to_renderer = HashMap::new();
-    let mut initial_surface: Option<wgpu::Surface<'static>> = None;
 
     let mut surface_infos = Vec::new();
-    for (name, x, y, width, height) in monitors {
-        monitor_manager.add_output(&name, "X11 Display").await;
-        let win = backend.create_wallpaper_window(&name, x, y, width, height)?;
+    for monitor in monitors {
+        let name = monitor.name.clone();
+        let identity = orchestration::OutputIdentity::new(name.clone(), "X11 Display");
+        if !config.is_output_enabled(&identity) {
+            info!("[STARTUP-X11] Monitor {} disabled in config, skipping", name);
+            continue;
+        }
+        monitor_manager.add_output(&identity).await;
+        events::emit(kaleidux_common::Event::OutputAdded {
+            output: identity.name.clone(),
+        });
+        let win = backend.create_wallpaper_window(&monitor)?;
         window_to_renderer.insert(win, name.clone());
 
         let raw_handle = x11::RawX11Surface {
             window_id: win,
             connection: backend.conn.clone(),
-            screen: backend.screen_num as i32,
+            screen: monitor.screen as i32,
         };
         let surface_arc = Arc::new(raw_handle);
-        surface_infos.push((name, surface_arc, width, height));
-    }
-
-    if let Some((_, surface_arc, _, _)) = surface_infos.first() {
-        info!("Initializing WGPU context with first surface as compatible...");
-        let wgpu_start = Instant::now();
-        let (ctx, surface) = renderer::WgpuContext::with_surface(surface_arc.clone()).await?;
-        let wgpu_duration = wgpu_start.elapsed();
-        metrics.record_wgpu_init(wgpu_duration);
-        wgpu_ctx = Some(ctx);
-        initial_surface = Some(surface);
+        surface_infos.push((name, surface_arc, monitor.width, monitor.height));
     }
 
-    if let Some(ctx) = wgpu_ctx.clone() {
-        let first_name = surface_infos.first().map(|(n, _, _, _)| n.clone());
-
-        for (name, surface_arc, width, height) in surface_infos {
-            let ctx_clone = ctx.clone();
-            let is_first = Some(&name) == first_name.as_ref();
-            let init_surf = if is_first {
-                initial_surface.take()
-            } else {
-                match ctx_clone.instance.create_surface(surface_arc.clone()) {
-                    Ok(s) => Some(s),
-                    Err(e) => {
-                        error!("Failed to create surface for {}: {}", name, e);
-                        None
-                    }
+    for (name, surface_arc, width, height) in surface_infos {
+        let gpu = monitor_manager
+            .get_output_config(&name)
+            .and_then(|c| c.gpu.clone());
+        let (ctx, probe_surface) =
+            context_for_output(&mut gpu_contexts, gpu, surface_arc.clone(), &metrics).await?;
+        let ctx_clone = ctx.clone();
+        let init_surf = match probe_surface {
+            Some(surface) => Some(surface),
+            None => match ctx_clone.instance.create_surface(surface_arc.clone()) {
+                Ok(s) => Some(s),
+                Err(e) => {
+                    error!("Failed to create surface for {}: {}", name, e);
+                    None
                 }
-            };
+            },
+        };
 
-            let metrics_clone = metrics.clone();
-
-            info!("[STARTUP-X11] Initializing renderer for {}", name);
-            let name_for_bg = name.clone();
-            let spawn_handler = tokio::task::spawn_blocking(move || {
-                renderer::Renderer::new(
-                    name_for_bg,
-                    ctx_clone,
-                    surface_arc,
-                    init_surf,
-                    Some(metrics_clone),
-                )
-            });
+        let metrics_clone = metrics.clone();
+
+        info!("[STARTUP-X11] Initializing renderer for {}", name);
+        let name_for_bg = name.clone();
+        let spawn_handler = tokio::task::spawn_blocking(move || {
+            renderer::Renderer::new(
+                name_for_bg,
+                ctx_clone,
+                surface_arc,
+                init_surf,
+                Some(metrics_clone),
+            )
+        });
 
-            match tokio::time::timeout(std::time::Duration::from_secs(5), spawn_handler).await {
-                Ok(join_res) => match join_res {
-                    Ok(render_res) => match render_res {
-                        Ok(mut r) => {
-                            let _ = r.resize_checked(width as u32, height as u32);
-                            if let Some(cfg) = monitor_manager.get_output_config(&name) {
-                                r.apply_config(cfg);
-                            }
-                            renderers.insert(name, r);
+        match tokio::time::timeout(std::time::Duration::from_secs(5), spawn_handler).await {
+            Ok(join_res) => match join_res {
+                Ok(render_res) => match render_res {
+                    Ok(mut r) => {
+                        let _ = r.resize_checked(width as u32, height as u32);
+                        if let Some(cfg) = monitor_manager.get_output_config(&name) {
+                            r.apply_config(cfg);
                         }
-                        Err(e) => error!("Failed to create renderer for {}: {}", name, e),
-                    },
-                    Err(e) => error!("Thread join error for output {}: {}", name, e),
+                        renderers.insert(name, r);
+                    }
+                    Err(e) => error!("Failed to create renderer for {}: {}", name, e),
                 },
-                Err(_) => error!(
-                    "TIMEOUT: Renderer initialization for {} took longer than 5s. Skipping.",
-                    name
-                ),
-            }
+                Err(e) => error!("Thread join error for output {}: {}", name, e),
+            },
+            Err(_) => error!(
+                "TIMEOUT: Renderer initialization for {} took longer than 5s. Skipping.",
+                name
+            ),
         }
+    }
 
+    if !gpu_contexts.is_empty() {
         // All renderers created - full initialization complete
         metrics.record_full_init();
         if log_level.map(|l| l >= 3).unwrap_or(false) {
@@ -1456,36 +3750,8 @@ async fn run_x11_loop(
     let (image_tx, mut image_rx) = tokio::sync::mpsc::channel::<LoadedImage>(6);
     let (player_tx, mut player_rx) = tokio::sync::mpsc::unbounded_channel::<VideoPlayerResult>();
 
-    // IPC Listener (duplicated setup for now to avoid complexity extracting)
-    let socket_path = dirs::runtime_dir()
-        .map(|d| d.join("kaleidux.sock"))
-        .unwrap_or_else(|| {
-            let uid = std::env::var("USER").unwrap_or_else(|_| "kaleidux".to_string());
-            std::path::PathBuf::from(format!("/tmp/kaleidux-{}.sock", uid))
-        });
-    let _ = std::fs::remove_file(&socket_path);
-    let listener = UnixListener::bind(&socket_path)?;
-    let cmd_tx_clone = cmd_tx.clone();
-    tokio::spawn(async move {
-        loop {
-            // Simplified IPC loop
-            if let Ok((mut stream, _)) = listener.accept().await {
-                let cmd_tx = cmd_tx_clone.clone();
-                tokio::spawn(async move {
-                    let mut buf = [0u8; 8192];
-                    if let Ok(n) = stream.read(&mut buf).await {
-                        if let Ok(req) = serde_json::from_slice::<Request>(&buf[..n]) {
-                            let (tx, rx) = tokio::sync::oneshot::channel();
-                            let _ = cmd_tx.send((req, tx));
-                            if let Ok(resp) = rx.await {
-                                let _ = stream.write_all(&serde_json::to_vec(&resp).unwrap()).await;
-                            }
-                        }
-                    }
-                });
-            }
-        }
-    });
+    // IPC Listener
+    spawn_ipc_listener(cmd_tx.clone())?;
 
     let mut next_session_id = 1u64;
     // Initial Load
@@ -1495,6 +3761,16 @@ async fn run_x11_loop(
         "[STARTUP] Reached Initial Load section, renderers count: {}",
         renderers.len()
     );
+    // Snapshots first so the first frames show something, then the full-quality originals
+    let last = last_wallpapers(&monitor_manager, &renderers);
+    restore_last_wallpapers(&monitor_manager, &mut renderers, &last, true).await;
+    for r in renderers.values_mut() {
+        if r.has_any_content() {
+            let _ = r.render(renderer::BackendContext::X11, Instant::now());
+        }
+    }
+    restore_last_wallpapers(&monitor_manager, &mut renderers, &last, false).await;
+
     info!("[STARTUP] About to call monitor_manager.tick()");
     let initial_changes = monitor_manager.tick();
     info!(
@@ -1528,7 +3804,7 @@ async fn run_x11_loop(
             content_type,
             &mut next_session_id,
             &frame_tx,
-            &monitor_manager,
+            &mut monitor_manager,
             &mut renderers,
             &mut video_players,
             Some(batch_id),
@@ -1544,21 +3820,54 @@ async fn run_x11_loop(
         drop(script_manager.load(path));
     }
     let mut last_script_tick = Instant::now();
-    let target_frame_time = std::time::Duration::from_micros(16667);
     let mut last_pool_cleanup_x11 = Instant::now();
+    let mut idle = IdleTracker::new();
+    let mut last_cache_gc = Instant::now();
+    // Due immediately, so rules active at startup apply on the first pass
+    let mut last_playlist_schedule = Instant::now()
+        .checked_sub(PLAYLIST_SCHEDULE_INTERVAL)
+        .unwrap_or_else(Instant::now);
+    let mut state_publisher = state::Publisher::new(instance::state_path());
+    let mut power = power::PowerMonitor::new();
+    let mut remote_sync = remote::RemoteSync::new();
+    let mut watchdog = systemd::Watchdog::new();
+    let mut last_state_publish = Instant::now();
+    let mut system_sampler = monitor::SystemSampler::new();
+    let mut overlay_manager = overlay::OverlayManager::new(&config.overlay);
+    let mut pending_waits: Vec<PendingWait> = Vec::new();
+    // Outputs whose change settled since the last export_settled_frames
+    let mut settled: HashSet<String> = HashSet::new();
+    // Their frames on the way back from the GPU
+    let mut frame_readbacks: Vec<(String, blur::Readback)> = Vec::new();
 
     let shutdown_flag = Arc::new(AtomicBool::new(false));
     let shutdown_clone = shutdown_flag.clone();
     tokio::spawn(async move {
-        let _ = tokio::signal::ctrl_c().await;
+        shutdown_signal().await;
         warn!("Received shutdown signal, cleaning up...");
         shutdown_clone.store(true, Ordering::SeqCst);
     });
 
+    // Watched while idle, so X events wake the loop as soon as they arrive
+    let display_fd = {
+        use std::os::unix::io::AsRawFd;
+        tokio::io::unix::AsyncFd::with_interest(
+            backend.conn.as_raw_fd(),
+            tokio::io::Interest::READABLE,
+        )
+        .map_err(|e| anyhow::anyhow!("Failed to watch the X11 connection: {}", e))?
+    };
+    // A command that ended an idle sleep, handled ahead of the channel
+    let mut woken_by = None;
+
+    systemd::notify("READY=1");
+
     // X11 Loop
     loop {
         let loop_start = Instant::now();
         if shutdown_flag.load(Ordering::SeqCst) {
+            systemd::notify("STOPPING=1");
+            let _ = monitor_manager.flush_all_stats();
             break;
         }
 
@@ -1598,6 +3907,7 @@ async fn run_x11_loop(
             script_manager.tick();
             last_script_tick = Instant::now();
         }
+        update_overlays(&mut overlay_manager, &mut renderers);
 
         // Automated Changes
         let scheduled_changes = monitor_manager.tick();
@@ -1610,7 +3920,7 @@ async fn run_x11_loop(
                     content_type,
                     &mut next_session_id,
                     &frame_tx,
-                    &monitor_manager,
+                    &mut monitor_manager,
                     &mut renderers,
                     &mut video_players,
                     Some(batch_id),
@@ -1621,9 +3931,34 @@ async fn run_x11_loop(
                 );
             }
         }
+        retry_failed_loads(
+            &mut monitor_manager,
+            &mut renderers,
+            &mut video_players,
+            &frame_tx,
+            &image_tx,
+            &player_tx,
+            &mut next_session_id,
+            loop_start,
+        );
+        show_failovers(&mut monitor_manager, &mut renderers, &mut video_players).await;
 
         // Commands
-        while let Ok((req, resp)) = cmd_rx.try_recv() {
+        while let Some((req, resp)) = woken_by.take().or_else(|| cmd_rx.try_recv().ok()) {
+            // Waits are answered later, once the outputs they target settle
+            if let Request::Wait { output, timeout } = req {
+                pending_waits.push(PendingWait {
+                    output,
+                    deadline: timeout.map(|t| Instant::now() + std::time::Duration::from_secs(t)),
+                    resp,
+                });
+                continue;
+            }
+            // Answered when the pull finishes (see MonitorManager::poll_fetch)
+            if let Request::Fetch(FetchCommand::Now) = req {
+                monitor_manager.fetch_now(resp);
+                continue;
+            }
             let response = handle_command(
                 req,
                 &mut monitor_manager,
@@ -1639,6 +3974,17 @@ async fn run_x11_loop(
             .await;
             let _ = resp.send(response);
         }
+        resolve_waits(&mut pending_waits, &renderers);
+
+        // Unmap windows of outputs that entered standby, map those that left it
+        for (name, r) in renderers.iter_mut() {
+            if r.standby != r.surface_hidden {
+                if let Err(e) = backend.set_window_mapped(name, !r.standby) {
+                    warn!("[STANDBY] {}: Failed to toggle window mapping: {}", name, e);
+                }
+                r.surface_hidden = r.standby;
+            }
+        }
 
         // Frames / Images / Video Players
         // CRITICAL: Process ALL frames in channel, not just latest per source
@@ -1668,12 +4014,15 @@ async fn run_x11_loop(
             }
         }
         for (src, frame) in latest_frames {
-            if let Some(r) = renderers.get_mut(src.as_str()) {
+            for target in content_targets(src.as_str(), &monitor_manager) {
+                let Some(r) = renderers.get_mut(&target) else {
+                    continue;
+                };
                 // THROTTLING FIX (Updated):
                 // For video: Always upload frames - X11 doesn't use frame callbacks, so no throttling needed.
                 // For images: Use strict throttling - only upload when callback not pending or first frame.
                 // Note: X11 doesn't use frame callbacks, but keeping logic consistent with Wayland path.
-                let should_upload = if r.valid_content_type == crate::queue::ContentType::Video {
+                let should_upload = if r.valid_content_type.is_played() {
                     // Video: always upload (X11 has no callback mechanism)
                     true
                 } else {
@@ -1687,12 +4036,6 @@ async fn run_x11_loop(
                     // Record video CPU time (frame processing)
                     let video_duration = _video_start.elapsed();
                     metrics.record_video_cpu_time(video_duration);
-                    // CRITICAL: Explicitly drop frame after processing to release gst::Buffer
-                    drop(frame);
-                } else {
-                    // Frame throttled - drop immediately to release gst::Buffer
-                    // This prevents buffer accumulation when renderer is busy
-                    drop(frame);
                 }
 
                 // X11: Render immediately if video
@@ -1704,35 +4047,48 @@ async fn run_x11_loop(
                 // Check if transition just completed and mark it
                 if r.transition_just_completed {
                     r.transition_just_completed = false; // Clear flag
-                    monitor_manager.mark_transition_completed(src.as_str());
+                    monitor_manager.mark_transition_completed(&target);
+                    settled.insert(target.clone());
                 }
-            } else {
-                // Renderer doesn't exist - drop frame immediately
-                drop(frame);
             }
+            // CRITICAL: Explicitly drop frame after processing to release gst::Buffer
+            drop(frame);
         }
         let mut images_received_x11 = 0;
         while let Ok(msg) = image_rx.try_recv() {
             images_received_x11 += 1;
-            if let Some(r) = renderers.get_mut(&msg.name) {
-                if let Some(data) = msg.data {
-                    let _ = r.upload_image_data(data, msg.width, msg.height);
+            if !renderers.contains_key(&msg.name) {
+                // CRITICAL: Renderer doesn't exist - drop image data immediately to prevent memory leak
+                warn!(
+                    "[IMAGE] {}: Renderer not found, dropping image data to prevent memory leak",
+                    msg.name
+                );
+                // msg.data is dropped here, releasing its share of the buffer
+                continue;
+            }
+            match &msg.error {
+                Some(error) => monitor_manager.record_load_failure(&msg.name, &msg.path, error),
+                None => monitor_manager.record_load_success(&msg.name, &msg.path),
+            }
+            apply_auto_transition(&msg, &mut monitor_manager, &mut renderers);
+            // Decoded once, uploaded to the source and each of its mirrors
+            let targets = content_targets(&msg.name, &monitor_manager);
+            for target in targets {
+                let Some(r) = renderers.get_mut(&target) else {
+                    continue;
+                };
+                if let Some(target_data) = &msg.data {
+                    let _ = r.upload_image_data(target_data, msg.width, msg.height);
                     let _ = r.render(renderer::BackendContext::X11, loop_start);
                     // Check if transition just completed and mark it
                     if r.transition_just_completed {
                         r.transition_just_completed = false; // Clear flag
-                        monitor_manager.mark_transition_completed(&msg.name);
+                        monitor_manager.mark_transition_completed(&target);
+                        settled.insert(target.clone());
                     }
                 } else {
                     r.abort_transition();
                 }
-            } else {
-                // CRITICAL: Renderer doesn't exist - drop image data immediately to prevent memory leak
-                warn!(
-                    "[IMAGE] {}: Renderer not found, dropping image data to prevent memory leak",
-                    msg.name
-                );
-                // msg.data is dropped here, freeing the Vec<u8>
             }
         }
         // Track image channel usage for memory leak detection
@@ -1741,8 +4097,10 @@ async fn run_x11_loop(
         }
         while let Ok(msg) = player_rx.try_recv() {
             match msg {
-                VideoPlayerResult::Success(name, session_id, mut p) => {
+                VideoPlayerResult::Success(name, session_id, path, mut p) => {
                     if renderers.get(&name).map(|r| r.active_video_session_id) == Some(session_id) {
+                        monitor_manager.record_load_success(&name, &path);
+                        pause_if_held(&name, &p, &monitor_manager);
                         if let Some(mut existing) = video_players.insert(name, p) {
                             tokio::spawn(async move {
                                 let _ = existing.stop();
@@ -1755,22 +4113,36 @@ async fn run_x11_loop(
                         });
                     }
                 }
-                VideoPlayerResult::Failure(name, session_id) => {
+                VideoPlayerResult::Failure(name, session_id, path, error) => {
                     if renderers.get(&name).map(|r| r.active_video_session_id) == Some(session_id) {
-                        if let Some(r) = renderers.get_mut(&name) {
-                            r.abort_transition();
+                        for target in content_targets(&name, &monitor_manager) {
+                            if let Some(r) = renderers.get_mut(&target) {
+                                r.abort_transition();
+                            }
                         }
+                        monitor_manager.record_load_failure(&name, &path, &error);
                     }
                 }
             }
         }
 
+        // Shader wallpapers draw their next frame, presented like any other redraw
+        for (name, r) in renderers.iter_mut() {
+            if r.shader_path().is_none() {
+                continue;
+            }
+            if let Some(position) = backend.pointer_position(name) {
+                r.set_pointer(position);
+            }
+            r.advance_shader(loop_start);
+        }
+
         // Render Loop for Transitions / Redraws
         for (name, r) in renderers.iter_mut() {
-            if r.needs_redraw
-                || r.transition_active
-                || r.valid_content_type == crate::queue::ContentType::Video
-            {
+            if r.transition_frame_wait(loop_start).is_some() {
+                continue;
+            }
+            if r.needs_redraw || r.transition_active || r.valid_content_type.is_played() {
                 let _ = r.render(renderer::BackendContext::X11, loop_start);
                 if !first_frame_recorded_x11 {
                     metrics.record_first_frame();
@@ -1780,10 +4152,21 @@ async fn run_x11_loop(
                 if r.transition_just_completed {
                     r.transition_just_completed = false; // Clear flag
                     monitor_manager.mark_transition_completed(name);
+                    settled.insert(name.clone());
                 }
             }
         }
 
+        if !settled.is_empty() || !frame_readbacks.is_empty() {
+            export_settled_frames(
+                &mut settled,
+                &mut frame_readbacks,
+                &renderers,
+                &monitor_manager,
+                Some(&backend),
+            );
+        }
+
         // Ensure X11 commands are sent - only if we actually rendered something
         let needs_flush = renderers
             .values()
@@ -1796,17 +4179,57 @@ async fn run_x11_loop(
         let frame_time = loop_start.elapsed();
         metrics.record_frame_time(frame_time);
 
+        // Refresh the time/load/battery uniforms seen by custom shaders (once per second)
+        if !all_in_standby(&renderers) {
+            if let Some(block) = system_sampler.poll() {
+                for r in renderers.values_mut() {
+                    r.set_system_uniforms(block);
+                }
+            }
+        }
+
         // Cleanup texture pool periodically (every 3 seconds for more aggressive cleanup)
         if last_pool_cleanup_x11.elapsed().as_secs() >= 3 {
-            if let Some(ctx) = &wgpu_ctx {
+            for (_, ctx) in &gpu_contexts {
                 ctx.cleanup_texture_pool(Some(&metrics));
             }
             last_pool_cleanup_x11 = Instant::now();
         }
+        let busy = !video_players.is_empty() || renderers.values().any(|r| r.is_busy());
+        if idle.due(busy, monitor_manager.idle_trim()) {
+            trim_idle_memory(&mut renderers, &gpu_contexts, &metrics);
+        }
+
+        if last_cache_gc.elapsed() >= CACHE_GC_INTERVAL {
+            enforce_cache_limit(&monitor_manager);
+            last_cache_gc = Instant::now();
+        }
+        if last_playlist_schedule.elapsed() >= PLAYLIST_SCHEDULE_INTERVAL {
+            monitor_manager.apply_playlist_schedule();
+            last_playlist_schedule = Instant::now();
+        }
+        if let Some(saving) = power.poll(monitor_manager.power_config()) {
+            apply_power_saving(saving, &mut monitor_manager, &video_players);
+        }
+        let stale = remote_sync.poll(monitor_manager.config());
+        monitor_manager.delete_files(&stale);
+        monitor_manager.poll_fetch();
+        watchdog.poll();
+        if last_state_publish.elapsed() >= STATE_PUBLISH_INTERVAL {
+            publish_state(&mut state_publisher, &monitor_manager, Some(&backend));
+            last_state_publish = Instant::now();
+        }
 
-        // Process directory watcher events (cache invalidation)
+        // Process directory watcher events (cache invalidation, static file and library
+        // changes)
         if let Some(ref mut watcher) = dir_watcher {
-            let _ = watcher.process_events().await;
+            for path in watcher.process_events().await {
+                monitor_manager.static_file_changed(&path);
+            }
+            let changes = watcher.library_changes();
+            if !changes.is_empty() {
+                monitor_manager.library_changed(&changes);
+            }
         }
 
         // Flush stats every 5 seconds (batched writes)
@@ -1818,28 +4241,164 @@ async fn run_x11_loop(
         // Log metrics summary every 10 seconds
         if last_metrics_log.elapsed().as_secs() >= 10 {
             // Record resource counts for leak detection
-            if let Some(ctx) = &wgpu_ctx {
-                let texture_count = ctx.texture_pool.lock().values().map(|v| v.len()).sum();
-                let pipeline_count = ctx.transition_pipelines.lock().len()
-                    + ctx.blit_pipelines.lock().len()
-                    + ctx.mipmap_pipelines.lock().len();
+            if !gpu_contexts.is_empty() {
+                let texture_count = gpu_contexts
+                    .iter()
+                    .map(|(_, ctx)| {
+                        ctx.texture_pool
+                            .lock()
+                            .values()
+                            .map(|v| v.len())
+                            .sum::<usize>()
+                    })
+                    .sum();
+                let pipeline_count = gpu_contexts
+                    .iter()
+                    .map(|(_, ctx)| {
+                        ctx.transition_pipelines.lock().len()
+                            + ctx.blit_pipelines.lock().len()
+                            + ctx.mipmap_pipelines.lock().len()
+                            + ctx.overlay_pipelines.lock().len()
+                    })
+                    .sum();
                 metrics.record_texture_count(texture_count);
                 metrics.record_pipeline_count(pipeline_count);
             }
-            metrics.log_summary();
-            last_metrics_log = Instant::now();
-        }
+            metrics.log_summary();
+            last_metrics_log = Instant::now();
+        }
+
+        // While nothing animates, sleep until a command or X event instead of ticking at
+        // the frame rate
+        let elapsed = loop_start.elapsed();
+        let standby = all_in_standby(&renderers);
+        let idle_loop = standby || nothing_animates(&renderers, &video_players, &monitor_manager);
+        let frame_time = if standby {
+            STANDBY_FRAME_TIME
+        } else if idle_loop {
+            monitor_manager.idle_frame_time(IDLE_FRAME_TIME)
+        } else {
+            monitor_manager.frame_time()
+        };
+        if elapsed < frame_time {
+            if idle_loop {
+                let _ = backend.conn.flush();
+                woken_by = idle_sleep(frame_time - elapsed, &mut cmd_rx, Some(&display_fd)).await;
+            } else {
+                tokio::time::sleep(frame_time - elapsed).await;
+            }
+        }
+        for (_, ctx) in &gpu_contexts {
+            ctx.device.poll(wgpu::Maintain::Poll);
+        }
+    }
+
+    // The state file goes with the publisher; the property has to be taken down here
+    if monitor_manager.publish_state() {
+        let _ = backend.set_state(None);
+    }
+    Ok(())
+}
+
+/// Reads back the frames of outputs whose change settled, once, for what wants them:
+/// `lock-image` files and on X11 with `x11-mode = "root-pixmap"` the root pixmap.
+/// The copies are started here and collected on later calls, so the main loop never
+/// waits for the GPU; the frames are exported together once all of them are back.
+fn export_settled_frames(
+    settled: &mut HashSet<String>,
+    readbacks: &mut Vec<(String, blur::Readback)>,
+    renderers: &HashMap<String, renderer::Renderer>,
+    monitor_manager: &monitor_manager::MonitorManager,
+    x11: Option<&x11::X11Backend>,
+) {
+    let mut names: Vec<String> = settled.drain().collect();
+    let root_pixmap =
+        x11.filter(|_| monitor_manager.x11_mode() == orchestration::X11Mode::RootPixmap);
+    if root_pixmap.is_none() {
+        names.retain(|name| monitor_manager.lock_image(name).is_some());
+    }
+    for name in names {
+        let Some(renderer) = renderers.get(&name) else {
+            continue;
+        };
+        match renderer.start_frame_readback() {
+            Ok(readback) => {
+                // A newer frame replaces one still on its way
+                readbacks.retain(|(n, _)| *n != name);
+                readbacks.push((name, readback));
+            }
+            Err(e) => debug!("[EXPORT] {}: Frame not read back: {}", name, e),
+        }
+    }
+
+    if !readbacks.iter_mut().all(|(_, readback)| readback.is_ready()) {
+        return;
+    }
+    readbacks.sort_by(|(a, _), (b, _)| a.cmp(b));
+    let frames: Vec<(String, Arc<image::RgbaImage>)> = readbacks
+        .drain(..)
+        .filter_map(|(name, readback)| match readback.wait() {
+            Ok(frame) => Some((name, Arc::new(frame))),
+            Err(e) => {
+                debug!("[EXPORT] {}: Frame not read back: {}", name, e);
+                None
+            }
+        })
+        .collect();
 
-        let elapsed = loop_start.elapsed();
-        if elapsed < target_frame_time {
-            tokio::time::sleep(target_frame_time - elapsed).await;
+    for (name, frame) in &frames {
+        if let Some(path) = monitor_manager.lock_image(name) {
+            lock_image::export(name.clone(), path, frame.clone());
         }
-        if let Some(ctx) = &wgpu_ctx {
-            ctx.device.poll(wgpu::Maintain::Poll);
+    }
+
+    let Some(backend) = root_pixmap else {
+        return;
+    };
+    let monitors = match backend.get_monitors() {
+        Ok(monitors) => monitors,
+        Err(e) => {
+            warn!("[X11] Root pixmap not updated, monitor query failed: {}", e);
+            return;
         }
+    };
+    let placed: Vec<(x11::X11Monitor, &image::RgbaImage)> = monitors
+        .into_iter()
+        .filter_map(|m| {
+            let (_, frame) = frames.iter().find(|(name, _)| *name == m.name)?;
+            Some((m, frame.as_ref()))
+        })
+        .collect();
+    if placed.is_empty() {
+        return;
+    }
+    match x11::set_root_pixmap(&placed) {
+        Ok(()) => debug!("[X11] Root pixmap updated for {} output(s)", placed.len()),
+        Err(e) => warn!("[X11] Failed to set the root pixmap: {}", e),
     }
+}
 
-    Ok(())
+/// Refreshes text widgets and rebuilds overlay layers for renderers whose
+/// overlay content or surface size is out of date.
+fn update_overlays(
+    overlay_manager: &mut overlay::OverlayManager,
+    renderers: &mut HashMap<String, renderer::Renderer>,
+) {
+    if overlay_manager.is_empty() {
+        return;
+    }
+    overlay_manager.tick();
+    let generation = overlay_manager.generation();
+    for (name, r) in renderers.iter_mut() {
+        if !r.configured || r.standby {
+            continue;
+        }
+        let state = (generation, r.config.width, r.config.height);
+        if r.overlay_state != Some(state) {
+            let bitmaps = overlay_manager.layout(name, r.config.width, r.config.height);
+            r.set_overlay(&bitmaps, generation);
+        }
+    }
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -1857,22 +4416,69 @@ async fn handle_command(
 ) -> Response {
     match req {
         Request::QueryOutputs => {
-            let outputs = renderers
-                .iter()
-                .map(|(n, r)| kaleidux_common::OutputInfo {
+            // Outputs without a renderer (--headless) are listed with a 0x0 size
+            let mut names: Vec<&String> = monitor_manager.outputs.keys().collect();
+            names.extend(renderers.keys().filter(|n| !monitor_manager.outputs.contains_key(*n)));
+            let outputs = names
+                .into_iter()
+                .map(|n| kaleidux_common::OutputInfo {
                     name: n.clone(),
-                    width: r.config.width,
-                    height: r.config.height,
+                    width: renderers.get(n).map_or(0, |r| r.config.width),
+                    height: renderers.get(n).map_or(0, |r| r.config.height),
                     current_wallpaper: monitor_manager
                         .outputs
-                        .get(n)
+                        .get(monitor_manager.resolve_mirror(n))
                         .and_then(|o| o.current_path.as_ref().map(|p| p.display().to_string())),
+                    transition: renderers.get(n).map(|r| r.transition_status()),
+                    error: monitor_manager.failover_error(n).map(str::to_string),
                 })
                 .collect();
             Response::OutputInfo(outputs)
         }
-        Request::Next { output } => {
-            let changes = monitor_manager.handle_next(output);
+        Request::Status => {
+            let mut names: Vec<&String> = monitor_manager.outputs.keys().collect();
+            names.extend(
+                renderers
+                    .keys()
+                    .filter(|n| !monitor_manager.outputs.contains_key(*n)),
+            );
+            names.sort();
+            let outputs = names
+                .into_iter()
+                .map(|n| {
+                    let mut status = monitor_manager.output_status(n);
+                    status.transition = renderers.get(n).map(|r| r.transition_status());
+                    if let Some(player) = video_players.get(n) {
+                        let (position, duration) = player.progress();
+                        status.video_position_ms = position.map(|d| d.as_millis() as u64);
+                        status.video_duration_ms = duration.map(|d| d.as_millis() as u64);
+                    }
+                    status
+                })
+                .collect();
+            Response::Status(outputs)
+        }
+        Request::Next { output, tags } => {
+            monitor_manager.lift_clear(output.as_deref());
+            if let Err(e) = monitor_manager.set_once_tags(&monitor_manager::parse_tags(&tags)) {
+                return monitor_manager::error_response(e);
+            }
+            let stepped = step_outputs(
+                monitor_manager,
+                output.as_deref(),
+                monitor_manager::MonitorManager::handle_next,
+            );
+            let _ = monitor_manager.set_once_tags(&[]);
+            let (changes, failed) = match stepped {
+                Ok(stepped) => stepped,
+                Err(e) => return monitor_manager::error_response(e),
+            };
+            let response = switch_results(&changes, failed);
+            // Switching content wakes everything up from `stop` standby
+            let woke = leave_standby(renderers);
+            if woke {
+                monitor_manager.set_paused(false);
+            }
             let batch = rand::random::<u64>();
             for (name, (path, content_type)) in changes {
                 switch_wallpaper_content(
@@ -1891,10 +4497,38 @@ async fn handle_command(
                     "NEXT",
                 );
             }
-            Response::Ok
+            if woke {
+                // Outputs the switch didn't touch get their previous wallpaper back
+                resume_outputs(
+                    sorted_names(renderers),
+                    monitor_manager,
+                    renderers,
+                    video_players,
+                    frame_tx,
+                    image_tx,
+                    player_tx,
+                    next_session_id,
+                    loop_start,
+                );
+            }
+            response
         }
         Request::Prev { output } => {
-            let changes = monitor_manager.handle_prev(output);
+            monitor_manager.lift_clear(output.as_deref());
+            let (changes, failed) = match step_outputs(
+                monitor_manager,
+                output.as_deref(),
+                monitor_manager::MonitorManager::handle_prev,
+            ) {
+                Ok(stepped) => stepped,
+                Err(e) => return monitor_manager::error_response(e),
+            };
+            let response = switch_results(&changes, failed);
+            // Switching content wakes everything up from `stop` standby
+            let woke = leave_standby(renderers);
+            if woke {
+                monitor_manager.set_paused(false);
+            }
             let batch = rand::random::<u64>();
             for (name, (path, content_type)) in changes {
                 switch_wallpaper_content(
@@ -1913,99 +4547,610 @@ async fn handle_command(
                     "PREV",
                 );
             }
-            Response::Ok
+            if woke {
+                // Outputs the switch didn't touch get their previous wallpaper back
+                resume_outputs(
+                    sorted_names(renderers),
+                    monitor_manager,
+                    renderers,
+                    video_players,
+                    frame_tx,
+                    image_tx,
+                    player_tx,
+                    next_session_id,
+                    loop_start,
+                );
+            }
+            response
+        }
+        Request::Set {
+            path,
+            output,
+            transition,
+            duration,
+        } => {
+            monitor_manager.lift_clear(output.as_deref());
+            let changes = match monitor_manager.set_wallpaper(
+                Path::new(&path),
+                output.as_deref(),
+                transition.as_deref(),
+                duration,
+            ) {
+                Ok(changes) => changes,
+                Err(e) => return monitor_manager::error_response(e),
+            };
+            let response = switch_results(&changes, Vec::new());
+            let woke = leave_standby(renderers);
+            if woke {
+                monitor_manager.set_paused(false);
+            }
+            let batch = rand::random::<u64>();
+            for (name, (path, content_type)) in changes {
+                switch_wallpaper_content(
+                    &name,
+                    &path,
+                    content_type,
+                    next_session_id,
+                    frame_tx,
+                    monitor_manager,
+                    renderers,
+                    video_players,
+                    Some(batch),
+                    Some(loop_start),
+                    image_tx,
+                    player_tx,
+                    "SET",
+                );
+            }
+            if woke {
+                resume_outputs(
+                    sorted_names(renderers),
+                    monitor_manager,
+                    renderers,
+                    video_players,
+                    frame_tx,
+                    image_tx,
+                    player_tx,
+                    next_session_id,
+                    loop_start,
+                );
+            }
+            response
+        }
+        Request::SetTransition {
+            output,
+            transition,
+            duration,
+        } => {
+            let results =
+                match monitor_manager.set_transition(output.as_deref(), &transition, duration) {
+                    Ok(results) => results,
+                    Err(e) => return monitor_manager::error_response(e),
+                };
+            // Mirrors copy their source's transition on each switch (see sync_mirrors)
+            for result in &results {
+                if let (Some(r), Some(cfg)) = (
+                    renderers.get_mut(&result.output),
+                    monitor_manager.get_output_config(&result.output),
+                ) {
+                    r.apply_config(cfg);
+                }
+            }
+            Response::OutputResults(results)
+        }
+        Request::SetVolume { output, volume } => {
+            let results = match monitor_manager.set_volume(output.as_deref(), volume) {
+                Ok(results) => results,
+                Err(e) => return monitor_manager::error_response(e),
+            };
+            for result in &results {
+                if let Some(vp) = video_players.get_mut(&result.output) {
+                    vp.set_volume(monitor_manager.volume(&result.output));
+                }
+            }
+            Response::OutputResults(results)
+        }
+        Request::Mute { output, muted } => {
+            let results = match monitor_manager.set_muted(output.as_deref(), muted) {
+                Ok(results) => results,
+                Err(e) => return monitor_manager::error_response(e),
+            };
+            for result in &results {
+                if let Some(vp) = video_players.get_mut(&result.output) {
+                    vp.set_volume(monitor_manager.volume(&result.output));
+                }
+            }
+            Response::OutputResults(results)
+        }
+        Request::Goto { index } => {
+            let changes = match monitor_manager.kiosk_goto(index) {
+                Ok(changes) => changes,
+                Err(e) => return monitor_manager::error_response(e),
+            };
+            let response = switch_results(&changes, Vec::new());
+            let woke = leave_standby(renderers);
+            if woke {
+                monitor_manager.set_paused(false);
+            }
+            let batch = rand::random::<u64>();
+            for (name, (path, content_type)) in changes {
+                switch_wallpaper_content(
+                    &name,
+                    &path,
+                    content_type,
+                    next_session_id,
+                    frame_tx,
+                    monitor_manager,
+                    renderers,
+                    video_players,
+                    Some(batch),
+                    Some(loop_start),
+                    image_tx,
+                    player_tx,
+                    "GOTO",
+                );
+            }
+            if woke {
+                resume_outputs(
+                    sorted_names(renderers),
+                    monitor_manager,
+                    renderers,
+                    video_players,
+                    frame_tx,
+                    image_tx,
+                    player_tx,
+                    next_session_id,
+                    loop_start,
+                );
+            }
+            response
+        }
+        Request::Session(kaleidux_common::SessionCommand::Restore { name }) => {
+            let session = match monitor_manager.load_session(&name) {
+                Ok(session) => session,
+                Err(e) => return monitor_manager::error_response(e),
+            };
+            // Wake up first: leaving standby resumes cycling everywhere, and the session
+            // may pause some outputs again
+            let woke = leave_standby(renderers);
+            if woke {
+                monitor_manager.set_paused(false);
+            }
+            info!("[CMD] Restoring session '{}'", name);
+            let (changes, results) = monitor_manager.restore_session(session);
+            for result in &results {
+                if let (Some(r), Some(cfg)) = (
+                    renderers.get_mut(&result.output),
+                    monitor_manager.get_output_config(&result.output),
+                ) {
+                    r.apply_config(cfg);
+                }
+            }
+            let batch = rand::random::<u64>();
+            for (name, (path, content_type)) in changes {
+                switch_wallpaper_content(
+                    &name,
+                    &path,
+                    content_type,
+                    next_session_id,
+                    frame_tx,
+                    monitor_manager,
+                    renderers,
+                    video_players,
+                    Some(batch),
+                    Some(loop_start),
+                    image_tx,
+                    player_tx,
+                    "SESSION",
+                );
+            }
+            if woke {
+                resume_outputs(
+                    sorted_names(renderers),
+                    monitor_manager,
+                    renderers,
+                    video_players,
+                    frame_tx,
+                    image_tx,
+                    player_tx,
+                    next_session_id,
+                    loop_start,
+                );
+            }
+            Response::OutputResults(results)
         }
+        Request::Session(cmd) => monitor_manager.handle_session_command(cmd),
         Request::Kill => {
             shutdown_flag.store(true, Ordering::SeqCst);
             Response::Ok
         }
         Request::Playlist(cmd) => monitor_manager.handle_playlist_command(cmd),
         Request::Blacklist(cmd) => monitor_manager.handle_blacklist_command(cmd),
+        Request::Quarantine(cmd) => monitor_manager.handle_quarantine_command(cmd),
+        Request::Tag(cmd) => monitor_manager.handle_tag_command(cmd),
         Request::LoveitList => Response::LoveitList(monitor_manager.get_loveitlist()),
-        Request::Love { path, multiplier } => monitor_manager
-            .love_file(path, multiplier)
-            .map(|_| Response::Ok)
-            .unwrap_or_else(|e| Response::Error(e.to_string())),
-        Request::Unlove { path } => monitor_manager
-            .unlove_file(path)
-            .map(|_| Response::Ok)
-            .unwrap_or_else(|e| Response::Error(e.to_string())),
+        Request::Love {
+            path,
+            paths,
+            multiplier,
+            output,
+        } => monitor_manager
+            .target_paths(path, paths, output.as_deref())
+            .and_then(|(paths, shown_on)| {
+                monitor_manager.love_files(&paths, multiplier)?;
+                Ok(monitor_manager::target_response(shown_on, "loved", &paths))
+            })
+            .unwrap_or_else(monitor_manager::error_response),
+        Request::Unlove {
+            path,
+            paths,
+            output,
+        } => monitor_manager
+            .target_paths(path, paths, output.as_deref())
+            .and_then(|(paths, shown_on)| {
+                monitor_manager.unlove_files(&paths)?;
+                Ok(monitor_manager::target_response(
+                    shown_on, "unloved", &paths,
+                ))
+            })
+            .unwrap_or_else(monitor_manager::error_response),
         Request::History { output } => Response::History(monitor_manager.get_history(output)),
+        Request::PickPreview { output, count } => {
+            match monitor_manager.preview_picks(output, count) {
+                Ok(previews) => Response::Picks(previews),
+                Err(e) => monitor_manager::error_response(e),
+            }
+        }
+        Request::Metrics => match monitor_manager.metrics() {
+            Some(metrics) => Response::Metrics(metrics.report()),
+            None => Response::error(ErrorCode::Unavailable, "Metrics are not being collected"),
+        },
+        Request::Cache(kaleidux_common::CacheCommand::Gc { max_size }) => {
+            let limit = match max_size.as_deref().map(cache::parse_size) {
+                Some(Ok(limit)) => Some(limit),
+                Some(Err(e)) => return Response::error(ErrorCode::InvalidArgument, e.to_string()),
+                None => monitor_manager.cache_limit(),
+            };
+            let keep = monitor_manager.cache_in_use();
+            match tokio::task::spawn_blocking(move || cache::collect_garbage(limit, &keep)).await {
+                Ok(Ok(report)) => {
+                    info!(
+                        "[CMD] Cache gc: removed {} files, reclaimed {} bytes",
+                        report.files_removed, report.bytes_reclaimed
+                    );
+                    Response::Cache(report)
+                }
+                Ok(Err(e)) => Response::error(ErrorCode::Failed, e.to_string()),
+                Err(e) => {
+                    Response::error(ErrorCode::Failed, format!("Cache gc task failed: {}", e))
+                }
+            }
+        }
+        Request::Snapshot { output, path, blur } => {
+            let (name, renderer) = match single_renderer(output, renderers) {
+                Ok(found) => found,
+                Err(e) => return Response::Error(e),
+            };
+            let image = match renderer.snapshot(blur) {
+                Ok(image) => image,
+                Err(e) => return monitor_manager::error_response(e),
+            };
+            let target = PathBuf::from(&path);
+            match tokio::task::spawn_blocking(move || image.save(&target)).await {
+                Ok(Ok(())) => {
+                    info!(
+                        "[CMD] {}: Snapshot (blur {}) written to {}",
+                        name, blur, path
+                    );
+                    Response::OutputResults(vec![OutputResult::ok(name, format!("saved {}", path))])
+                }
+                Ok(Err(e)) => Response::error(
+                    ErrorCode::Failed,
+                    format!("Failed to write {}: {}", path, e),
+                ),
+                Err(e) => {
+                    Response::error(ErrorCode::Failed, format!("Snapshot task failed: {}", e))
+                }
+            }
+        }
+        Request::Capture { output, path } => {
+            let (name, renderer) = match single_renderer(output, renderers) {
+                Ok(found) => found,
+                Err(e) => return Response::Error(e),
+            };
+            let image = match renderer.read_frame() {
+                Ok(image) => image,
+                Err(e) => return monitor_manager::error_response(e),
+            };
+            let target = PathBuf::from(&path);
+            let save = move || image.save_with_format(&target, image::ImageFormat::Png);
+            match tokio::task::spawn_blocking(save).await {
+                Ok(Ok(())) => {
+                    info!("[CMD] {}: Frame captured to {}", name, path);
+                    Response::OutputResults(vec![OutputResult::ok(name, format!("saved {}", path))])
+                }
+                Ok(Err(e)) => Response::error(
+                    ErrorCode::Failed,
+                    format!("Failed to write {}: {}", path, e),
+                ),
+                Err(e) => Response::error(ErrorCode::Failed, format!("Capture task failed: {}", e)),
+            }
+        }
+        // Parked and answered by the main loop (see resolve_waits), never dispatched here
+        Request::Wait { .. } => {
+            Response::error(ErrorCode::Failed, "wait must go through the main loop")
+        }
+        Request::Fetch(_) => {
+            Response::error(ErrorCode::Failed, "fetch must go through the main loop")
+        }
+        Request::Subscribe => Response::error(
+            ErrorCode::InvalidArgument,
+            "subscribe needs a connection of its own",
+        ),
+        Request::Batch(requests) => {
+            // The whole batch runs within one main loop iteration, so no other
+            // client's request can land in between
+            let mut responses = Vec::with_capacity(requests.len());
+            let mut failed = false;
+            for req in requests {
+                let response = if failed {
+                    Response::error(
+                        ErrorCode::Skipped,
+                        "Skipped: an earlier request in the batch failed",
+                    )
+                } else if matches!(
+                    req,
+                    Request::Batch(_) | Request::Wait { .. } | Request::Fetch(_)
+                ) {
+                    Response::error(
+                        ErrorCode::InvalidArgument,
+                        "batch, wait and fetch requests can't be batched",
+                    )
+                } else {
+                    Box::pin(handle_command(
+                        req,
+                        monitor_manager,
+                        renderers,
+                        video_players,
+                        frame_tx,
+                        image_tx,
+                        player_tx,
+                        next_session_id,
+                        loop_start,
+                        shutdown_flag,
+                    ))
+                    .await
+                };
+                failed |= response.is_failure();
+                responses.push(response);
+            }
+            info!("[CMD] Ran batch of {} requests", responses.len());
+            Response::Batch(responses)
+        }
         Request::Reload => {
             info!("Reloading configuration...");
-            match orchestration::Config::load().await {
+            match orchestration::Config::load_strict().await {
                 Ok(new_config) => {
-                    monitor_manager.update_config(new_config);
-                    // Refresh renderers with new config
+                    let results = monitor_manager.reload(new_config).await;
+                    // Refresh renderers and running videos with new config
                     for (name, r) in renderers.iter_mut() {
                         if let Some(cfg) = monitor_manager.get_output_config(name) {
                             r.apply_config(cfg);
                         }
                     }
+                    for (name, vp) in video_players.iter_mut() {
+                        vp.set_volume(monitor_manager.volume(name));
+                    }
                     info!("Configuration reloaded successfully");
-                    Response::Ok
+                    Response::OutputResults(results)
                 }
                 Err(e) => {
                     error!("Failed to reload config: {}", e);
-                    Response::Error(format!("Failed to reload config: {}", e))
+                    Response::error(ErrorCode::Config, format!("Failed to reload config: {}", e))
                 }
             }
         }
-        Request::Pause => {
-            info!("[CMD] Pausing all video players and wallpaper cycling");
-            for (name, player) in video_players.iter() {
-                if let Err(e) = player.pause() {
-                    error!("[CMD] Failed to pause video for {}: {}", name, e);
+        Request::ReloadOutput { output } => {
+            info!("Reloading configuration of {}...", output);
+            let new_config = match orchestration::Config::load_strict().await {
+                Ok(config) => config,
+                Err(e) => {
+                    error!("Failed to reload config: {}", e);
+                    return Response::error(
+                        ErrorCode::Config,
+                        format!("Failed to reload config: {}", e),
+                    );
                 }
-            }
-            monitor_manager.set_paused(true);
-            Response::Ok
-        }
-        Request::Resume => {
-            info!("[CMD] Resuming all video players and wallpaper cycling");
-            for (name, player) in video_players.iter() {
-                if let Err(e) = player.resume() {
-                    error!("[CMD] Failed to resume video for {}: {}", name, e);
+            };
+            let results = match monitor_manager.reload_outputs(new_config, &output).await {
+                Ok(results) => results,
+                Err(e) => return monitor_manager::error_response(e),
+            };
+            for result in &results {
+                let name = &result.output;
+                let Some(cfg) = monitor_manager.get_output_config(name) else {
+                    continue;
+                };
+                if let Some(r) = renderers.get_mut(name) {
+                    r.apply_config(cfg);
+                }
+                if let Some(vp) = video_players.get_mut(name) {
+                    vp.set_volume(monitor_manager.volume(name));
                 }
             }
-            monitor_manager.set_paused(false);
-            Response::Ok
+            Response::OutputResults(results)
         }
-        Request::Stop => {
-            info!("[CMD] Stopping all video players");
-            let names: Vec<String> = video_players.keys().cloned().collect();
-            for name in names {
-                if let Some(mut player) = video_players.remove(&name) {
-                    tokio::spawn(async move {
-                        let _ = player.stop();
-                    });
+        Request::Behavior(kaleidux_common::BehaviorCommand::Set { mode, groups }) => {
+            let behavior = match (mode.as_str(), groups.is_empty()) {
+                ("independent", true) => orchestration::MonitorBehavior::Independent,
+                ("synchronized", true) => orchestration::MonitorBehavior::Synchronized,
+                ("grouped", false) => orchestration::MonitorBehavior::Grouped(groups),
+                ("grouped", true) => {
+                    return Response::error(
+                        ErrorCode::InvalidArgument,
+                        "grouped needs at least one group",
+                    )
                 }
-            }
-            Response::Ok
+                ("independent" | "synchronized", false) => {
+                    return Response::error(
+                        ErrorCode::InvalidArgument,
+                        format!("{} takes no groups", mode),
+                    )
+                }
+                _ => {
+                    return Response::error(
+                        ErrorCode::InvalidArgument,
+                        format!(
+                            "unknown behavior '{}' (independent, synchronized or grouped)",
+                            mode
+                        ),
+                    )
+                }
+            };
+            info!("[CMD] Setting monitor behavior: {:?}", behavior);
+            Response::OutputResults(monitor_manager.set_behavior(behavior).await)
         }
-        Request::Clear { output } => {
-            info!("[CMD] Clearing output: {:?}", output);
-            let targets: Vec<String> = match output {
-                Some(ref name) => {
-                    if renderers.contains_key(name) {
-                        vec![name.clone()]
-                    } else {
-                        return Response::Error(format!("Output not found: {}", name));
+        Request::Favorites { enabled, output } => {
+            let targets = match targeted_names(output.as_deref(), renderers) {
+                Ok(targets) => targets,
+                Err(e) => return monitor_manager::error_response(e),
+            };
+            info!(
+                "[CMD] Turning favorites {} on {:?}",
+                if enabled { "on" } else { "off" },
+                targets
+            );
+            Response::OutputResults(monitor_manager.set_favorites(&targets, enabled))
+        }
+        Request::Pause { output } => {
+            let targets = match targeted_names(output.as_deref(), renderers) {
+                Ok(targets) => targets,
+                Err(e) => return monitor_manager::error_response(e),
+            };
+            match output {
+                Some(_) => {
+                    info!("[CMD] Pausing video and wallpaper cycling on {:?}", targets);
+                    for name in &targets {
+                        monitor_manager.set_output_paused(name, true);
                     }
                 }
-                None => renderers.keys().cloned().collect(),
+                None => {
+                    info!("[CMD] Pausing all video players and wallpaper cycling");
+                    monitor_manager.set_paused(true);
+                }
+            }
+            let results = targets
+                .into_iter()
+                .map(|name| match video_players.get(&name) {
+                    Some(player) => match player.pause() {
+                        Ok(_) => OutputResult::ok(name, "video paused"),
+                        Err(e) => {
+                            error!("[CMD] Failed to pause video for {}: {}", name, e);
+                            OutputResult::err(name, format!("failed to pause video: {}", e))
+                        }
+                    },
+                    None => OutputResult::ok(name, "cycling paused"),
+                })
+                .collect();
+            Response::OutputResults(results)
+        }
+        Request::Resume { output } => {
+            let targets = match targeted_names(output.as_deref(), renderers) {
+                Ok(targets) => targets,
+                Err(e) => return monitor_manager::error_response(e),
             };
-            for name in targets {
-                if let Some(mut vp) = video_players.remove(&name) {
-                    tokio::spawn(async move {
-                        let _ = vp.stop();
-                    });
+            match output {
+                // Standby and the global pause cover every output, so they stay in effect
+                Some(_) => {
+                    info!(
+                        "[CMD] Resuming video and wallpaper cycling on {:?}",
+                        targets
+                    );
+                    for name in &targets {
+                        monitor_manager.set_output_paused(name, false);
+                    }
                 }
-                if let Some(r) = renderers.get_mut(&name) {
-                    r.clear();
+                None => {
+                    info!("[CMD] Resuming all video players and wallpaper cycling");
+                    monitor_manager.set_paused(false);
+                    leave_standby(renderers);
                 }
             }
-            Response::Ok
+            let results = resume_outputs(
+                targets,
+                monitor_manager,
+                renderers,
+                video_players,
+                frame_tx,
+                image_tx,
+                player_tx,
+                next_session_id,
+                loop_start,
+            );
+            Response::OutputResults(results)
+        }
+        Request::Stop => {
+            info!("[CMD] Entering standby: stopping playback and releasing GPU resources");
+            // Nothing may switch in behind us until resume/next
+            monitor_manager.set_paused(true);
+            let mut ctxs: Vec<Arc<renderer::WgpuContext>> = Vec::new();
+            let results = sorted_names(renderers)
+                .into_iter()
+                .map(|name| {
+                    let had_video = stop_output(&name, renderers, video_players);
+                    if let Some(r) = renderers.get_mut(&name) {
+                        r.enter_standby();
+                        if !ctxs.iter().any(|c| Arc::ptr_eq(c, &r.ctx)) {
+                            ctxs.push(r.ctx.clone());
+                        }
+                    }
+                    OutputResult::ok(
+                        name,
+                        if had_video {
+                            "video stopped, standby"
+                        } else {
+                            "standby"
+                        },
+                    )
+                })
+                .collect();
+            for ctx in ctxs {
+                ctx.drain_texture_pool();
+            }
+            Response::OutputResults(results)
+        }
+        Request::Clear { output, color } => {
+            info!("[CMD] Clearing output: {:?} (color: {:?})", output, color);
+            let fill = match color.as_deref() {
+                Some(c) => match crate::overlay::parse_color(c) {
+                    Some(rgba) => rgba,
+                    None => {
+                        return Response::error(
+                            ErrorCode::InvalidArgument,
+                            format!("Invalid color: {}", c),
+                        )
+                    }
+                },
+                None => [0, 0, 0, 255],
+            };
+            let targets = match targeted_names(output.as_deref(), renderers) {
+                Ok(targets) => targets,
+                Err(e) => return monitor_manager::error_response(e),
+            };
+            let results = targets
+                .into_iter()
+                .map(|name| {
+                    stop_output(&name, renderers, video_players);
+                    // Held until a next/prev/set aimed at it, so the timer can't refill it
+                    monitor_manager.set_cleared(&name, true);
+                    if let Some(r) = renderers.get_mut(&name) {
+                        r.solid_color = Some(fill);
+                        r.needs_redraw = true;
+                    }
+                    OutputResult::ok(name, "cleared")
+                })
+                .collect();
+            Response::OutputResults(results)
         }
     }
 }
@@ -1,4 +1,4 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
@@ -66,6 +66,81 @@ pub struct PerformanceMetrics {
     video_samples: Arc<parking_lot::Mutex<VecDeque<f64>>>,    // Last 100 video times in ms
     file_discovery_samples: Arc<parking_lot::Mutex<VecDeque<f64>>>, // Last 20 file discovery times in ms
     shader_compile_samples: Arc<parking_lot::Mutex<VecDeque<f64>>>, // Last 50 shader compile times in ms
+
+    // The same numbers broken out per output name, so one slow output stands out
+    per_output: Arc<parking_lot::Mutex<HashMap<String, OutputMetrics>>>,
+
+    // GPU time of each transition shader's pass, from timestamp queries (last 50 each)
+    gpu_transition_times: Arc<parking_lot::Mutex<HashMap<String, VecDeque<f64>>>>,
+
+    // Idle trims so far, and the resident memory before and after the last one (MB)
+    idle_trims: Arc<AtomicU64>,
+    last_trim: Arc<parking_lot::Mutex<Option<(f64, f64)>>>,
+}
+
+/// Per-output counterpart of the global frame, transition, pool and error stats
+#[derive(Debug, Default)]
+struct OutputMetrics {
+    render_times: VecDeque<f64>,     // Last 100 render times in ms
+    transition_times: VecDeque<f64>, // Last 50 transition times in ms
+    transition_count: u64,
+    texture_pool_hits: u64,
+    texture_pool_misses: u64,
+    error_count: u64,
+    gpu_times: VecDeque<f64>, // Last 100 GPU frame times in ms (timestamp queries)
+    images_downscaled: u64,
+    downscale_saved_bytes: u64,
+}
+
+impl OutputMetrics {
+    fn report(&self, name: &str) -> kaleidux_common::OutputMetricsReport {
+        let (p95, max) = p95_max(&self.render_times);
+        let pool_total = self.texture_pool_hits + self.texture_pool_misses;
+        kaleidux_common::OutputMetricsReport {
+            output: name.to_string(),
+            frame_time_avg_ms: mean(&self.render_times),
+            frame_time_p95_ms: p95,
+            frame_time_max_ms: max,
+            gpu_time_avg_ms: (!self.gpu_times.is_empty()).then(|| mean(&self.gpu_times)),
+            transitions: self.transition_count,
+            transition_avg_ms: mean(&self.transition_times),
+            texture_pool_hit_rate: if pool_total == 0 {
+                0.0
+            } else {
+                self.texture_pool_hits as f64 / pool_total as f64
+            },
+            errors: self.error_count,
+            images_downscaled: self.images_downscaled,
+            downscale_saved_mb: self.downscale_saved_bytes as f64 / (1024.0 * 1024.0),
+        }
+    }
+}
+
+fn mean(samples: &VecDeque<f64>) -> f64 {
+    if samples.is_empty() {
+        0.0
+    } else {
+        samples.iter().sum::<f64>() / samples.len() as f64
+    }
+}
+
+/// 95th percentile and maximum of the samples (0 when there are none)
+fn p95_max(samples: &VecDeque<f64>) -> (f64, f64) {
+    let mut sorted: Vec<f64> = samples.iter().copied().collect();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let p95 = sorted
+        .get(((sorted.len() as f64 * 0.95) as usize).min(sorted.len().saturating_sub(1)))
+        .copied()
+        .unwrap_or(0.0);
+    (p95, sorted.last().copied().unwrap_or(0.0))
+}
+
+/// Appends a sample, keeping at most `cap`
+fn push_capped(samples: &mut VecDeque<f64>, value: f64, cap: usize) {
+    samples.push_back(value);
+    if samples.len() > cap {
+        samples.pop_front();
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -126,6 +201,10 @@ impl PerformanceMetrics {
             video_samples: Arc::new(parking_lot::Mutex::new(VecDeque::with_capacity(100))),
             file_discovery_samples: Arc::new(parking_lot::Mutex::new(VecDeque::with_capacity(20))),
             shader_compile_samples: Arc::new(parking_lot::Mutex::new(VecDeque::with_capacity(50))),
+            per_output: Arc::new(parking_lot::Mutex::new(HashMap::new())),
+            gpu_transition_times: Arc::new(parking_lot::Mutex::new(HashMap::new())),
+            idle_trims: Arc::new(AtomicU64::new(0)),
+            last_trim: Arc::new(parking_lot::Mutex::new(None)),
         }
     }
 
@@ -138,6 +217,74 @@ impl PerformanceMetrics {
         }
     }
 
+    /// Records an error and attributes it to `output`
+    pub fn record_output_error(&self, output: &str, error_type: &str) {
+        self.record_error(error_type);
+        self.with_output(output, |o| o.error_count += 1);
+    }
+
+    fn with_output(&self, output: &str, f: impl FnOnce(&mut OutputMetrics)) {
+        let mut per_output = self.per_output.lock();
+        match per_output.get_mut(output) {
+            Some(o) => f(o),
+            None => f(per_output.entry(output.to_string()).or_default()),
+        }
+    }
+
+    /// An image for `output` scaled down before upload, saving `saved_bytes` of texture
+    pub fn record_downscale(&self, output: &str, saved_bytes: u64) {
+        self.with_output(output, |o| {
+            o.images_downscaled += 1;
+            o.downscale_saved_bytes += saved_bytes;
+        });
+    }
+
+    /// Time `output` spent rendering one frame
+    pub fn record_output_frame_time(&self, output: &str, duration: Duration) {
+        let ms = duration.as_secs_f64() * 1000.0;
+        self.with_output(output, |o| push_capped(&mut o.render_times, ms, 100));
+    }
+
+    /// GPU time of one frame on `output` (all its render passes), and of the transition
+    /// shader's pass if the frame drew one
+    pub fn record_gpu_frame_time(
+        &self,
+        output: &str,
+        frame_ms: f64,
+        transition: Option<(&str, f64)>,
+    ) {
+        self.with_output(output, |o| push_capped(&mut o.gpu_times, frame_ms, 100));
+        if let Some((name, ms)) = transition {
+            let mut times = self.gpu_transition_times.lock();
+            match times.get_mut(name) {
+                Some(samples) => push_capped(samples, ms, 50),
+                None => {
+                    times.insert(name.to_string(), VecDeque::from([ms]));
+                }
+            }
+        }
+    }
+
+    /// GPU time per transition shader, most expensive first
+    pub fn transition_gpu_reports(&self) -> Vec<kaleidux_common::TransitionGpuReport> {
+        let times = self.gpu_transition_times.lock();
+        let mut reports: Vec<_> = times
+            .iter()
+            .map(|(name, samples)| {
+                let (p95, max) = p95_max(samples);
+                kaleidux_common::TransitionGpuReport {
+                    transition: name.clone(),
+                    samples: samples.len(),
+                    avg_ms: mean(samples),
+                    p95_ms: p95,
+                    max_ms: max,
+                }
+            })
+            .collect();
+        reports.sort_by(|a, b| b.avg_ms.total_cmp(&a.avg_ms));
+        reports
+    }
+
     pub fn get_error_rate(&self) -> f64 {
         let samples = self.error_samples.lock();
         if samples.len() < 2 {
@@ -184,6 +331,11 @@ impl PerformanceMetrics {
         }
     }
 
+    pub fn record_idle_trim(&self, before_mb: Option<f64>, after_mb: Option<f64>) {
+        self.idle_trims.fetch_add(1, Ordering::Relaxed);
+        *self.last_trim.lock() = before_mb.zip(after_mb);
+    }
+
     pub fn get_memory_growth_rate(&self) -> Option<f64> {
         let samples = self.memory_samples.lock();
         if samples.len() < 2 {
@@ -422,15 +574,17 @@ impl PerformanceMetrics {
             .store((avg * 1000.0) as u64, Ordering::Relaxed);
     }
 
-    pub fn record_texture_pool_hit(&self) {
+    pub fn record_texture_pool_hit(&self, output: &str) {
         self.texture_pool_hits.fetch_add(1, Ordering::Relaxed);
+        self.with_output(output, |o| o.texture_pool_hits += 1);
     }
 
-    pub fn record_texture_pool_miss(&self) {
+    pub fn record_texture_pool_miss(&self, output: &str) {
         self.texture_pool_misses.fetch_add(1, Ordering::Relaxed);
+        self.with_output(output, |o| o.texture_pool_misses += 1);
     }
 
-    pub fn record_transition(&self, duration: std::time::Duration) {
+    pub fn record_transition(&self, output: &str, duration: std::time::Duration) {
         self.transition_count.fetch_add(1, Ordering::Relaxed);
         let ms = duration.as_secs_f64() * 1000.0;
         let mut times = self.transition_times.lock();
@@ -438,6 +592,10 @@ impl PerformanceMetrics {
         if times.len() > 50 {
             times.pop_front();
         }
+        self.with_output(output, |o| {
+            o.transition_count += 1;
+            push_capped(&mut o.transition_times, ms, 50);
+        });
     }
 
     pub fn record_video_first_frame(&self, duration: std::time::Duration) {
@@ -695,9 +853,72 @@ impl PerformanceMetrics {
             component_cpu,
             leak_msg
         );
+        for o in self.output_reports() {
+            let gpu = o
+                .gpu_time_avg_ms
+                .map(|ms| format!("{:.2}ms", ms))
+                .unwrap_or_else(|| "N/A".to_string());
+            tracing::info!(
+                "[METRICS] {}: Render time: avg={:.2}ms p95={:.2}ms max={:.2}ms | GPU: avg={} | \
+                 Transitions: {} avg={:.0}ms | Texture pool: hit_rate={:.1}% | Errors: {}",
+                o.output,
+                o.frame_time_avg_ms,
+                o.frame_time_p95_ms,
+                o.frame_time_max_ms,
+                gpu,
+                o.transitions,
+                o.transition_avg_ms,
+                o.texture_pool_hit_rate * 100.0,
+                o.errors
+            );
+        }
+        if let Some(t) = self.transition_gpu_reports().first() {
+            tracing::info!(
+                "[METRICS] Most expensive transition on the GPU: {} avg={:.2}ms p95={:.2}ms \
+                 max={:.2}ms ({} frames)",
+                t.transition,
+                t.avg_ms,
+                t.p95_ms,
+                t.max_ms,
+                t.samples
+            );
+        }
+    }
+
+    /// Per-output stats, sorted by output name
+    pub fn output_reports(&self) -> Vec<kaleidux_common::OutputMetricsReport> {
+        let per_output = self.per_output.lock();
+        let mut reports: Vec<_> = per_output.iter().map(|(name, o)| o.report(name)).collect();
+        reports.sort_by(|a, b| a.output.cmp(&b.output));
+        reports
+    }
+
+    /// Snapshot for `kldctl metrics`
+    pub fn report(&self) -> kaleidux_common::MetricsReport {
+        kaleidux_common::MetricsReport {
+            uptime_secs: self.get_uptime_seconds(),
+            frame_time_avg_ms: self.get_avg_frame_time_ms(),
+            frame_time_p95_ms: self.get_p95_frame_time_ms(),
+            transitions: self.transition_count.load(Ordering::Relaxed),
+            texture_pool_hit_rate: self.get_texture_pool_hit_rate(),
+            errors: self.get_error_count(),
+            outputs: self.output_reports(),
+            transitions_gpu: self.transition_gpu_reports(),
+            resident_mb: resident_mb(),
+            idle_trims: self.idle_trims.load(Ordering::Relaxed),
+            last_trim_mb: *self.last_trim.lock(),
+        }
     }
 }
 
+/// Resident memory of the daemon right now, from /proc/self/statm
+pub fn resident_mb() -> Option<f64> {
+    let statm = std::fs::read_to_string("/proc/self/statm").ok()?;
+    let pages: u64 = statm.split_whitespace().nth(1)?.parse().ok()?;
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    (page_size > 0).then(|| (pages * page_size as u64) as f64 / 1024.0 / 1024.0)
+}
+
 impl Default for PerformanceMetrics {
     fn default() -> Self {
         Self::new()
@@ -1,6 +1,7 @@
+use chrono::{Datelike, Timelike};
 use std::fs;
 use std::process::Command;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System};
 use tokio::time::interval;
 use tracing::{debug, info, warn};
@@ -168,3 +169,81 @@ impl SystemMonitor {
         }
     }
 }
+
+/// Samples the machine state exposed to shaders as the `sys_time`/`sys_state` uniforms.
+/// Kept separate from `SystemMonitor` because it runs in the main loop, every output
+/// reads the same values, and it must stay cheap: only CPU usage is refreshed.
+pub struct SystemSampler {
+    sys: System,
+    started: Instant,
+    last_sample: Option<Instant>,
+    block: [[f32; 4]; 2],
+}
+
+impl SystemSampler {
+    const INTERVAL: Duration = Duration::from_secs(1);
+
+    pub fn new() -> Self {
+        let mut sys = System::new();
+        sys.refresh_cpu_usage();
+        Self {
+            sys,
+            started: Instant::now(),
+            last_sample: None,
+            block: [[0.0; 4]; 2],
+        }
+    }
+
+    /// Returns a fresh block if the last one is older than a second, otherwise None
+    pub fn poll(&mut self) -> Option<[[f32; 4]; 2]> {
+        if self.last_sample.is_some_and(|t| t.elapsed() < Self::INTERVAL) {
+            return None;
+        }
+        self.last_sample = Some(Instant::now());
+
+        let now = chrono::Local::now();
+        let time_of_day =
+            now.num_seconds_from_midnight() as f32 + now.nanosecond() as f32 / 1_000_000_000.0;
+        self.sys.refresh_cpu_usage();
+        let cpu_load = self.sys.global_cpu_usage() / 100.0;
+
+        self.block = [
+            [
+                time_of_day,
+                now.day() as f32,
+                now.month() as f32,
+                now.year() as f32,
+            ],
+            [
+                System::uptime() as f32,
+                cpu_load.clamp(0.0, 1.0),
+                battery_level().unwrap_or(-1.0),
+                self.started.elapsed().as_secs_f32(),
+            ],
+        ];
+        Some(self.block)
+    }
+}
+
+/// Average charge (0-1) over all batteries in /sys/class/power_supply, None without one
+pub fn battery_level() -> Option<f32> {
+    let mut total = 0.0;
+    let mut count = 0;
+    for entry in fs::read_dir("/sys/class/power_supply").ok()?.flatten() {
+        let path = entry.path();
+        let is_battery = fs::read_to_string(path.join("type"))
+            .map(|t| t.trim() == "Battery")
+            .unwrap_or(false);
+        if !is_battery {
+            continue;
+        }
+        if let Some(capacity) = fs::read_to_string(path.join("capacity"))
+            .ok()
+            .and_then(|c| c.trim().parse::<f32>().ok())
+        {
+            total += capacity;
+            count += 1;
+        }
+    }
+    (count > 0).then(|| total / count as f32 / 100.0)
+}
@@ -1,20 +1,22 @@
 use crate::cache::FileCache;
 use crate::metrics::PerformanceMetrics;
-use crate::orchestration::{Config, MonitorBehavior, OutputConfig};
+use crate::orchestration::{Config, MonitorBehavior, OutputConfig, OutputIdentity};
 use crate::queue::Playlist;
-use crate::queue::SmartQueue;
+use crate::queue::{SmartQueue, StatsStore};
 use anyhow::Result;
-use kaleidux_common::{BlacklistCommand, KEntry, PlaylistCommand, Response};
+use kaleidux_common::{
+    BlacklistCommand, CommandError, ErrorCode, KEntry, OutputResult, PlaylistCommand,
+    QuarantineCommand, QuarantineEntry, Response, SessionCommand, TagCommand, TagInfo,
+};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use tracing::{debug, error, info, warn};
 
 pub struct OutputOrchestrator {
     pub _name: String,
-    #[allow(dead_code)]
-    pub description: String,
+    pub identity: OutputIdentity,
     pub config: OutputConfig,
     pub queue: Option<SmartQueue>,
     pub current_path: Option<PathBuf>,
@@ -24,29 +26,37 @@ pub struct OutputOrchestrator {
     pub next_content_type: Option<crate::queue::ContentType>, // Type of next content
     pub next_change: Option<Instant>,
     pub display_start_time: Option<Instant>, // When content actually started displaying
+    /// Set when `path` is a single file (see `OutputConfig::static_file`)
+    pub static_file: Option<PathBuf>,
 }
 
 impl OutputOrchestrator {
     pub async fn new(
         name: String,
-        description: String,
+        identity: OutputIdentity,
         config: OutputConfig,
-        cache: Arc<FileCache>,
+        stats: Arc<StatsStore>,
         metrics: Option<Arc<PerformanceMetrics>>,
     ) -> Self {
-        let queue = if let Some(path) = &config.path {
+        let static_file = config.static_file().map(Path::to_path_buf);
+        let queue = if let Some(file) = &static_file {
+            info!("[STATIC] {}: Showing {:?}, cycling disabled", name, file);
+            None
+        } else if let Some(path) = &config.path {
             info!("[QUEUE] {}: Initializing queue for path: {:?}", name, path);
             match SmartQueue::new_with_cache(
-                path,
+                &config.library(),
+                config.library_filter(),
                 config.video_ratio,
                 config.sorting,
-                cache,
+                stats,
                 metrics.clone(),
             )
             .await
             {
                 Ok(mut q) => {
                     info!("[QUEUE] {}: Queue initialized successfully", name);
+                    q.tags = config.tags.clone();
                     if let Some(pl_name) = &config.default_playlist {
                         if let Err(e) = q.set_playlist(Some(pl_name.clone())) {
                             error!(
@@ -69,7 +79,7 @@ impl OutputOrchestrator {
 
         Self {
             _name: name,
-            description,
+            identity,
             config,
             queue,
             current_path: None,
@@ -77,10 +87,30 @@ impl OutputOrchestrator {
             next_content_type: None,
             next_change: None,
             display_start_time: None,
+            static_file,
         }
     }
 
     pub fn tick(&mut self) -> Option<(PathBuf, crate::queue::ContentType)> {
+        // A static output switches only when it has nothing on screen yet, or its file
+        // changed (see `MonitorManager::static_file_changed`)
+        if let Some(file) = &self.static_file {
+            if self.current_path.is_some() {
+                return None;
+            }
+            self.current_path = Some(file.clone());
+            return match crate::queue::SmartQueue::get_content_type(file) {
+                Some(content_type) => Some((file.clone(), content_type)),
+                None => {
+                    error!(
+                        "[STATIC] {}: {:?} is not an image or video",
+                        self._name, file
+                    );
+                    None
+                }
+            };
+        }
+
         let now = Instant::now();
 
         // If content is displaying, check if duration has elapsed based on actual display start time
@@ -125,9 +155,8 @@ impl OutputOrchestrator {
     pub fn pick_next(&mut self) -> Option<(PathBuf, crate::queue::ContentType)> {
         if let Some(queue) = &mut self.queue {
             info!("[PICK] {}: Calling queue.pick_next()", self._name);
-            if let Some(path) = queue.pick_next() {
+            if let Some((path, content_type)) = queue.pick_next_playable() {
                 info!("[PICK] {}: Selected path: {:?}", self._name, path);
-                let content_type = crate::queue::SmartQueue::get_content_type(&path).unwrap(); // Already validated in discovery
                 self.current_path = Some(path.clone());
                 // Reset display start time - will be set when content actually starts displaying
                 // Reset display start time - will be set when content actually starts displaying
@@ -175,8 +204,7 @@ impl OutputOrchestrator {
 
     pub fn pick_prev(&mut self) -> Option<(PathBuf, crate::queue::ContentType)> {
         if let Some(queue) = &mut self.queue {
-            if let Some(path) = queue.pick_prev() {
-                let content_type = crate::queue::SmartQueue::get_content_type(&path).unwrap();
+            if let Some((path, content_type)) = queue.pick_prev_playable() {
                 self.current_path = Some(path.clone());
                 // Reset display start time - will be set when content actually starts displaying
                 self.display_start_time = None;
@@ -190,6 +218,221 @@ impl OutputOrchestrator {
     }
 }
 
+/// Longest wait between retries of an output whose picks keep failing to load
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Longest the main loop sleeps in the e-ink profile while waiting for the next
+/// switch; commands and display events still wake it at once
+const EINK_IDLE_TICK: Duration = Duration::from_secs(5);
+
+/// How often outputs are checked for having nothing to play
+const CONTENT_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+/// How often missing queues are rebuilt while an output shows its fallback, so a path
+/// that appears later (a mounted drive, a created folder) is picked up
+const QUEUE_RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Pending re-pick for an output whose content failed to load
+struct LoadRetry {
+    /// Failed loads in a row on this output
+    attempts: u32,
+    /// Last file that failed
+    path: PathBuf,
+    due: Option<Instant>,
+}
+
+/// Reply to a love/unlove/blacklist request: names the file when it was taken from the
+/// screen (see `MonitorManager::target_path`), plain OK when the client passed a path
+pub fn target_response(shown_on: Option<String>, action: &str, paths: &[PathBuf]) -> Response {
+    match (shown_on, paths) {
+        (Some(output), [path]) => Response::OutputResults(vec![OutputResult::ok(
+            output,
+            format!("{} {}", action, path.display()),
+        )]),
+        _ => {
+            if paths.len() > 1 {
+                info!("[CMD] {} {} files", action, paths.len());
+            }
+            Response::Ok
+        }
+    }
+}
+
+/// Tags as given over IPC ("nature,dark" or a list), lowercased and deduplicated
+pub fn parse_tags(tags: &[String]) -> Vec<String> {
+    let mut parsed: Vec<String> = Vec::new();
+    for tag in tags.iter().flat_map(|t| t.split(',')) {
+        let tag = tag.trim().to_lowercase();
+        if !tag.is_empty() && !parsed.contains(&tag) {
+            parsed.push(tag);
+        }
+    }
+    parsed
+}
+
+/// Reply for a request that failed with `e`: the `CommandError` it was raised with,
+/// or a generic failure
+pub fn error_response(e: anyhow::Error) -> Response {
+    match e.downcast::<CommandError>() {
+        Ok(e) => Response::Error(e),
+        Err(e) => Response::error(ErrorCode::Failed, e.to_string()),
+    }
+}
+
+/// Resolves an `--output` value against the known output names: a comma-separated
+/// list of exact names and globs (see `library_filter::glob_regex`).
+/// Every entry has to match at least one output. Returns sorted, deduplicated names.
+pub fn match_outputs<'a>(
+    spec: &str,
+    known: impl IntoIterator<Item = &'a String>,
+) -> Result<Vec<String>> {
+    let known: Vec<&String> = known.into_iter().collect();
+    let mut matched = std::collections::BTreeSet::new();
+    for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let hits: Vec<&String> = if part.contains(['*', '?']) {
+            let re = crate::library_filter::glob_regex(part).map_err(|e| {
+                CommandError::new(
+                    ErrorCode::InvalidArgument,
+                    format!("Invalid pattern '{}': {}", part, e),
+                )
+            })?;
+            known.iter().copied().filter(|n| re.is_match(n)).collect()
+        } else {
+            known
+                .iter()
+                .copied()
+                .filter(|n| n.as_str() == part)
+                .collect()
+        };
+        if hits.is_empty() {
+            anyhow::bail!(CommandError::new(
+                ErrorCode::UnknownOutput,
+                format!("No output matches '{}'", part)
+            )
+            .with_outputs(vec![part.to_string()]));
+        }
+        matched.extend(hits.into_iter().cloned());
+    }
+    if matched.is_empty() {
+        anyhow::bail!(CommandError::new(
+            ErrorCode::InvalidArgument,
+            "No output given"
+        ));
+    }
+    Ok(matched.into_iter().collect())
+}
+
+/// Error for a playlist edit that found no playlist `name`
+fn playlist_found(found: bool, name: &str) -> Result<()> {
+    if !found {
+        anyhow::bail!(CommandError::new(
+            ErrorCode::NotFound,
+            format!("Playlist '{}' not found", name)
+        ));
+    }
+    Ok(())
+}
+
+/// A transition duration given in seconds over IPC, as `transition-time` milliseconds
+fn transition_millis(seconds: f32) -> Result<u32> {
+    if !seconds.is_finite() || seconds < 0.0 {
+        anyhow::bail!(CommandError::new(
+            ErrorCode::InvalidArgument,
+            format!("Invalid transition duration {}", seconds)
+        ));
+    }
+    Ok((seconds * 1000.0).round() as u32)
+}
+
+/// Factor on every `duration`: `[power] rotation-multiplier` while saving power
+fn rotation_scale(config: &Config, power_saving: bool) -> f32 {
+    if power_saving {
+        config.power.rotation_multiplier
+    } else {
+        1.0
+    }
+}
+
+fn with_rotation_scale(mut config: OutputConfig, scale: f32) -> OutputConfig {
+    config.duration = config.duration.mul_f32(scale);
+    config
+}
+
+fn session_not_found(name: &str) -> anyhow::Error {
+    CommandError::new(ErrorCode::NotFound, format!("Session '{}' not found", name)).into()
+}
+
+/// Files recently picked by each queue, so queues stay clear of what the others just
+/// showed (`avoid-twins`). Owners are output names for per-output queues and "group.N"
+/// for group queues; the synchronized shared queue is one queue and never twins.
+struct TwinGuard {
+    window: Option<Duration>,
+    /// File -> (owner that picked it, when)
+    shown: HashMap<PathBuf, (String, Instant)>,
+}
+
+impl TwinGuard {
+    fn new(window: Option<Duration>) -> Self {
+        Self {
+            window,
+            shown: HashMap::new(),
+        }
+    }
+
+    /// Sets what `queue` should avoid before `owner` picks from it
+    fn prepare(&mut self, owner: &str, queue: Option<&mut SmartQueue>) {
+        let Some(queue) = queue else {
+            return;
+        };
+        queue.avoid.clear();
+        let Some(window) = self.window else {
+            return;
+        };
+        self.shown.retain(|_, (_, at)| at.elapsed() < window);
+        queue.avoid.extend(
+            self.shown
+                .iter()
+                .filter(|(_, (by, _))| by != owner)
+                .map(|(path, _)| path.clone()),
+        );
+    }
+
+    fn record(&mut self, owner: &str, path: &Path) {
+        if self.window.is_some() {
+            self.shown
+                .insert(path.to_path_buf(), (owner.to_string(), Instant::now()));
+        }
+    }
+}
+
+/// Name a queue's history is stored under: the output owning it, "group.N" for a group
+/// queue, "shared" for the synchronized one
+enum HistoryKey<'a> {
+    Shared,
+    Group(usize),
+    Output(&'a str),
+}
+
+impl std::fmt::Display for HistoryKey<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HistoryKey::Shared => write!(f, "shared"),
+            HistoryKey::Group(gid) => write!(f, "group.{}", gid),
+            HistoryKey::Output(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// Transition for an output's next switch only (`kldctl set --transition/--duration`)
+#[derive(Debug, Clone, Default)]
+pub struct TransitionOverride {
+    /// None keeps the configured transition
+    pub transition: Option<crate::shaders::Transition>,
+    /// `cut`: the switch shows without a transition
+    pub cut: bool,
+    /// Milliseconds; None keeps `transition-time`
+    pub time: Option<u32>,
+}
+
 pub struct MonitorManager {
     config: Config,
     pub outputs: HashMap<String, OutputOrchestrator>,
@@ -199,11 +442,60 @@ pub struct MonitorManager {
     shared_display_start_time: Option<Instant>, // For synchronized outputs - shared display start time
     group_display_start_times: HashMap<usize, Instant>, // For grouped outputs - per-group display start time
     cache: Arc<FileCache>,                              // Shared cache instance for all queues
+    stats: Arc<StatsStore>, // Loves, counts, playlists, blacklist shared by all queues
     metrics: Option<Arc<PerformanceMetrics>>,           // Shared metrics instance
     paused: bool,                                       // Global pause state for wallpaper cycling
     // In-memory cache of discovered file lists per directory path.
     // Avoids re-scanning the same directory when multiple outputs share the same path.
     discovered_files_cache: HashMap<PathBuf, Vec<PathBuf>>,
+    // Playlist each output's schedule asked for at the last check (None = default content).
+    // Queues only switch when this changes, so a manual `playlist load` holds until then.
+    scheduled_playlists: HashMap<String, Option<String>>,
+    // Resolved `[group.N]` sections; groups without one run on their first output's config
+    group_configs: HashMap<usize, OutputConfig>,
+    load_retries: HashMap<String, LoadRetry>,
+    // Outputs paused on their own (`pause --output`); a shared or group queue holds
+    // while any output drawing from it is paused
+    paused_outputs: std::collections::HashSet<String>,
+    // Outputs blanked by `clear`; they take no content from their queue until a
+    // next/prev/set aimed at them
+    cleared: std::collections::HashSet<String>,
+    // Outputs silenced by `mute`; their videos keep the configured volume for `unmute`
+    muted: std::collections::HashSet<String>,
+    // Wallpapers outputs switched to since the last flush_all_stats, saved with the
+    // play counts for `restore-last`
+    unsaved_wallpapers: HashMap<String, (PathBuf, crate::queue::ContentType)>,
+    // Outputs nobody sees (a fullscreen window over them, or the session idle);
+    // their videos and rendering hold until they are revealed. Cycling goes on.
+    covered: std::collections::HashSet<String>,
+    twins: TwinGuard,
+    // `[kiosk]` sequence; while set it drives every output instead of the queues
+    kiosk: Option<crate::kiosk::Kiosk>,
+    // Outputs with nothing to play (path missing, no playable files) and why; they show
+    // their `fallback` until content is back
+    failover: HashMap<String, String>,
+    last_content_check: Option<Instant>,
+    last_queue_retry: Instant,
+    // What each output switched to last, for `transition = "auto"`
+    looks: HashMap<String, crate::tone::Look>,
+    // Outputs whose auto transition waits for the incoming image to be measured, with
+    // the look they switched away from
+    auto_pending: HashMap<String, Option<crate::tone::Look>>,
+    // Tone of the image each output decoded last, for `publish-state`
+    tones: HashMap<String, (PathBuf, crate::tone::Tone)>,
+    // Set by `kldctl set`, taken by the switch it makes
+    transition_overrides: HashMap<String, TransitionOverride>,
+    // Whether `[power]` saving is on (on battery or power-saver); durations of outputs
+    // and group configs are scaled by `rotation-multiplier` while it is
+    power_saving: bool,
+    // `[fetch]` pulls into the fetched playlist
+    fetcher: crate::fetch::Fetcher,
+    // Active workspaces reported by the compositor, followed while `[workspace.NAME]`
+    // rules exist
+    workspaces: crate::compositor::WorkspaceWatcher,
+    // The rule of the workspace active on each output, for outputs on a bound one. A
+    // `path` rule holds the output on its file.
+    workspace_bindings: HashMap<String, crate::orchestration::WorkspaceRule>,
 }
 
 impl MonitorManager {
@@ -216,12 +508,23 @@ impl MonitorManager {
         self.cache.clone()
     }
 
+    pub fn metrics(&self) -> Option<&Arc<PerformanceMetrics>> {
+        self.metrics.as_ref()
+    }
+
     pub fn new_with_metrics(
         config: Config,
         metrics: Option<Arc<PerformanceMetrics>>,
     ) -> Result<Self> {
         // Create shared cache instance once for all queues
-        let cache = Arc::new(FileCache::new()?);
+        let cache = Arc::new(FileCache::new(config.global.stats_backups)?);
+        let stats = Arc::new(StatsStore::load(cache.clone())?);
+        let group_configs = Self::resolve_group_configs(&config, 1.0);
+        let twins = TwinGuard::new(config.global.avoid_twins);
+        let kiosk = config
+            .kiosk
+            .as_ref()
+            .and_then(|k| crate::kiosk::Kiosk::new(k, cache.clone()));
 
         Ok(Self {
             config,
@@ -232,38 +535,487 @@ impl MonitorManager {
             shared_display_start_time: None,
             group_display_start_times: HashMap::new(),
             cache,
+            stats,
             metrics,
             paused: false,
             discovered_files_cache: HashMap::new(),
+            scheduled_playlists: HashMap::new(),
+            group_configs,
+            load_retries: HashMap::new(),
+            paused_outputs: std::collections::HashSet::new(),
+            cleared: std::collections::HashSet::new(),
+            muted: std::collections::HashSet::new(),
+            unsaved_wallpapers: HashMap::new(),
+            covered: std::collections::HashSet::new(),
+            twins,
+            kiosk,
+            failover: HashMap::new(),
+            last_content_check: None,
+            looks: HashMap::new(),
+            auto_pending: HashMap::new(),
+            tones: HashMap::new(),
+            transition_overrides: HashMap::new(),
+            last_queue_retry: Instant::now(),
+            power_saving: false,
+            fetcher: crate::fetch::Fetcher::new(),
+            workspaces: crate::compositor::WorkspaceWatcher::new(),
+            workspace_bindings: HashMap::new(),
         })
     }
 
-    #[allow(dead_code)]
-    pub fn update_config(&mut self, config: Config) {
+    /// Applies a freshly loaded config.
+    ///
+    /// Outputs whose content settings (path, video ratio, sorting, default playlist) changed
+    /// get their queue rebuilt and rescanned; the rest only pick up the new settings. Any
+    /// content change while queues are shared rebuilds everything; a change of monitor
+    /// behavior regroups the existing queues instead (see `set_behavior`).
+    /// What's on screen keeps playing until its duration runs out.
+    ///
+    /// Rendering settings (duration, transition, volume, ...) are applied in place, so
+    /// outputs that only changed those keep their queue position and history.
+    pub async fn reload(&mut self, config: Config) -> Vec<OutputResult> {
+        let behavior_changed = config.global.monitor_behavior != self.config.global.monitor_behavior;
+        let scale = rotation_scale(&config, self.power_saving);
+        let new_group_configs = Self::resolve_group_configs(&config, scale);
+        // A `[group.N]` section changing what its queue draws from rebuilds the queues too
+        let groups_changed = new_group_configs.len() != self.group_configs.len()
+            || new_group_configs.iter().any(|(gid, new)| {
+                self.group_configs
+                    .get(gid)
+                    .is_none_or(|old| new.queue_differs(old))
+            });
+        self.group_configs = new_group_configs;
+        self.twins.window = config.global.avoid_twins;
+        if config.global.video_decoder != self.config.global.video_decoder {
+            crate::video::prefer_decoders(config.global.video_decoder);
+        }
+        // Outputs still without content show their (possibly changed) fallback again
+        self.failover.clear();
+        self.last_content_check = None;
+        if config.kiosk != self.config.kiosk {
+            // Resumes from the saved position, which survives edits to other items
+            let was_kiosk = self.kiosk.is_some();
+            self.kiosk = config
+                .kiosk
+                .as_ref()
+                .and_then(|k| crate::kiosk::Kiosk::new(k, self.cache.clone()));
+            // Leaving kiosk mode: the queues pick fresh content right away
+            if was_kiosk && self.kiosk.is_none() {
+                for orch in self.outputs.values_mut() {
+                    orch.current_path = None;
+                }
+            }
+        }
         self.config = config;
+        self.discovered_files_cache.clear();
 
-        // Refresh all output configurations
+        let mut rebuild = Vec::new();
+        let mut applied: HashMap<String, Vec<&'static str>> = HashMap::new();
         for (name, orch) in &mut self.outputs {
-            // Re-match config for this output using its stored description
-            let output_config = self.config.get_config_for_output(name, &orch.description);
-            orch.config = output_config;
+            let new_config =
+                with_rotation_scale(self.config.get_config_for_output(&orch.identity), scale);
+            if new_config.queue_differs(&orch.config) {
+                rebuild.push(name.clone());
+            }
+            applied.insert(name.clone(), new_config.live_changes(&orch.config));
+            orch.config = new_config;
+        }
+
+        if behavior_changed {
+            // Queues whose settings changed are rebuilt rather than handed over
+            for name in &rebuild {
+                if let Some(orch) = self.outputs.get_mut(name) {
+                    orch.queue = None;
+                }
+            }
+            if groups_changed {
+                self.shared_queue = None;
+                self.group_queues.clear();
+            }
+            let mut results = self.migrate_queues().await;
+            for result in &mut results {
+                let changes = applied.remove(&result.output).unwrap_or_default();
+                if !changes.is_empty() {
+                    info!(
+                        "[RELOAD] {}: Applied {} in place",
+                        result.output,
+                        changes.join(", ")
+                    );
+                    result.message = format!("{}, applied {}", result.message, changes.join(", "));
+                }
+            }
+            return results;
+        }
+
+        let shared = !matches!(
+            self.config.global.monitor_behavior,
+            MonitorBehavior::Independent
+        );
+        if shared && groups_changed {
+            rebuild = self.outputs.keys().cloned().collect();
+        }
+        if !rebuild.is_empty() && shared {
+            rebuild = self.outputs.keys().cloned().collect();
+            self.shared_queue = None;
+            self.group_queues.clear();
+            self.output_groups.clear();
+            self.shared_display_start_time = None;
+            self.group_display_start_times.clear();
+        }
+
+        let mut results = Vec::new();
+        for name in self.outputs.keys() {
+            if rebuild.contains(name) {
+                continue;
+            }
+            let changes = applied.remove(name).unwrap_or_default();
+            if changes.is_empty() {
+                results.push(OutputResult::ok(name, "unchanged"));
+            } else {
+                info!("[RELOAD] {}: Applied {} in place", name, changes.join(", "));
+                results.push(OutputResult::ok(
+                    name,
+                    format!("applied {}", changes.join(", ")),
+                ));
+            }
+        }
+
+        for name in rebuild {
+            if let Some(result) = self.rebuild_output(&name).await {
+                results.push(result);
+            }
+        }
+
+        results.sort_by(|a, b| a.output.cmp(&b.output));
+        results
+    }
+
+    /// `kldctl reload --output`: re-reads the sections of the outputs `spec` names from
+    /// `config` and applies them the way `reload` does, to those outputs only. Global
+    /// settings, `[any]`, `[group.N]`, definitions and all other outputs stay as they
+    /// are, and so do their queues, history and what they show.
+    pub async fn reload_outputs(
+        &mut self,
+        config: Config,
+        spec: &str,
+    ) -> Result<Vec<OutputResult>> {
+        let names = match_outputs(spec, self.outputs.keys())?;
+        let mut results = Vec::new();
+        for name in names {
+            let Some(identity) = self.outputs.get(&name).map(|o| o.identity.clone()) else {
+                continue;
+            };
+            self.config.take_output_sections(&config, &identity);
+            let scale = rotation_scale(&self.config, self.power_saving);
+            let new_config =
+                with_rotation_scale(self.config.get_config_for_output(&identity), scale);
+            let Some(orch) = self.outputs.get_mut(&name) else {
+                continue;
+            };
+            let changes = new_config.live_changes(&orch.config);
+            let rebuild = new_config.queue_differs(&orch.config);
+            orch.config = new_config;
+            // It shows its (possibly changed) fallback again if it still has no content
+            self.failover.remove(&name);
+            self.last_content_check = None;
+
+            if !changes.is_empty() {
+                info!("[RELOAD] {}: Applied {} in place", name, changes.join(", "));
+            }
+            let applied = (!changes.is_empty()).then(|| format!("applied {}", changes.join(", ")));
+            if !rebuild {
+                let message = applied.unwrap_or_else(|| "unchanged".to_string());
+                results.push(OutputResult::ok(&name, message));
+                continue;
+            }
+            if self.config.group_index(&name).is_some() {
+                // Its queue is shared, so rebuilding it would restart the other outputs
+                warn!(
+                    "[RELOAD] {}: Content settings changed on a shared queue, not rebuilt",
+                    name
+                );
+                results.push(
+                    OutputResult::err(
+                        &name,
+                        "content settings are shared with other outputs, run a full reload",
+                    )
+                    .with_code(ErrorCode::Unavailable),
+                );
+                continue;
+            }
+            if let Some(mut result) = self.rebuild_output(&name).await {
+                if let Some(applied) = applied {
+                    result.message = format!("{}, {}", result.message, applied);
+                }
+                results.push(result);
+            }
+        }
+        Ok(results)
+    }
+
+    /// Re-adds `name` with a fresh queue built from its current config, keeping what it
+    /// shows and its timer; the new queue takes over on the next switch
+    async fn rebuild_output(&mut self, name: &str) -> Option<OutputResult> {
+        let old = self.outputs.remove(name)?;
+        info!("[RELOAD] {}: Rebuilding content queue", name);
+        self.add_output(&old.identity).await;
+
+        let Some(orch) = self.outputs.get_mut(name) else {
+            return Some(OutputResult::err(name, "failed to re-add output"));
+        };
+        orch.current_path = old.current_path;
+        orch.display_start_time = old.display_start_time;
+        orch.next_change = old.next_change;
+
+        let pool_size = orch
+            .queue
+            .as_ref()
+            .or(self.shared_queue.as_ref())
+            .or_else(|| {
+                self.output_groups
+                    .get(name)
+                    .and_then(|gid| self.group_queues.get(gid))
+            })
+            .map(|q| q.pool.len());
+        Some(match (pool_size, &orch.config.path) {
+            (Some(n), _) => OutputResult::ok(name, format!("queue rebuilt ({} files)", n)),
+            (None, Some(path)) => OutputResult::err(
+                name,
+                format!("no playable content in {}", path.display()),
+            )
+            .with_code(ErrorCode::Unavailable),
+            (None, None) => OutputResult::ok(name, "no path configured"),
+        })
+    }
+
+    /// Switches `monitor-behavior` at runtime (until the next reload restores the configured
+    /// one). See `migrate_queues`.
+    pub async fn set_behavior(&mut self, behavior: MonitorBehavior) -> Vec<OutputResult> {
+        info!(
+            "[BEHAVIOR] Switching from {:?} to {:?}",
+            self.config.global.monitor_behavior, behavior
+        );
+        self.config.global.monitor_behavior = behavior;
+        self.migrate_queues().await
+    }
 
-            // TODO: Full queue refresh if path changes.
+    /// Regroups the outputs into the queues the current monitor behavior calls for, in
+    /// place: outputs keep their orchestrator, what they show and its timer. A queue that
+    /// already draws from the directory a new queue needs is handed over with its history
+    /// and position, and file lists already scanned are reused, so only directories no
+    /// queue covered are scanned again. The new queues take over on the next switch.
+    async fn migrate_queues(&mut self) -> Vec<OutputResult> {
+        let mut spare: Vec<SmartQueue> = self
+            .shared_queue
+            .take()
+            .into_iter()
+            .chain(self.group_queues.drain().map(|(_, q)| q))
+            .chain(self.outputs.values_mut().filter_map(|o| o.queue.take()))
+            .collect();
+        let mut pools = self.discovered_files_cache.clone();
+        // Unfiltered file lists of one folder only; a mix of sources is not reused
+        for q in spare
+            .iter()
+            .filter(|q| !q.is_discovering() && q.sources.len() == 1 && q.filter.is_empty())
+        {
+            pools.insert(q.root_path.clone(), q.pool.clone());
+        }
+        self.output_groups.clear();
+        self.shared_display_start_time = None;
+        self.group_display_start_times.clear();
+        // Rules re-apply to the new queues on the next schedule check
+        self.scheduled_playlists.clear();
+
+        let synchronized = matches!(
+            self.config.global.monitor_behavior,
+            MonitorBehavior::Synchronized
+        );
+        let mut names: Vec<String> = self.outputs.keys().cloned().collect();
+        names.sort();
+        let mut results = Vec::new();
+        for name in names {
+            let Some(orch) = self.outputs.get(&name) else {
+                continue;
+            };
+            if orch.static_file.is_some() {
+                results.push(OutputResult::ok(&name, "static file, no queue"));
+                continue;
+            }
+            let output_config = orch.config.clone();
+            let (label, queue) = match self.config.group_index(&name) {
+                None => {
+                    let queue = self.take_queue(&mut spare, &pools, &output_config).await;
+                    let orch = self.outputs.get_mut(&name).expect("output checked above");
+                    orch.queue = queue;
+                    ("own queue".to_string(), orch.queue.as_ref())
+                }
+                Some(_) if synchronized => {
+                    if self.shared_queue.is_none() {
+                        let config = self.queue_config(0, &output_config);
+                        self.shared_queue = self.take_queue(&mut spare, &pools, &config).await;
+                    }
+                    ("shared queue".to_string(), self.shared_queue.as_ref())
+                }
+                Some(gid) => {
+                    self.output_groups.insert(name.clone(), gid);
+                    if !self.group_queues.contains_key(&gid) {
+                        let config = self.queue_config(gid, &output_config);
+                        if let Some(q) = self.take_queue(&mut spare, &pools, &config).await {
+                            self.group_queues.insert(gid, q);
+                        }
+                    }
+                    (format!("group {} queue", gid), self.group_queues.get(&gid))
+                }
+            };
+            results.push(match (queue, &output_config.path) {
+                (Some(q), _) => {
+                    OutputResult::ok(&name, format!("{} ({} files)", label, q.pool.len()))
+                }
+                (None, Some(path)) => OutputResult::err(
+                    &name,
+                    format!("no playable content in {}", path.display()),
+                )
+                .with_code(ErrorCode::Unavailable),
+                (None, None) => OutputResult::ok(&name, "no path configured"),
+            });
+        }
+        info!(
+            "[BEHAVIOR] Regrouped {} outputs, {} old queues dropped",
+            results.len(),
+            spare.len()
+        );
+        results
+    }
+
+    /// Queue for `config`: a spare one drawing from the same directories, else one built
+    /// from a file list scanned before, else a fresh scan
+    async fn take_queue(
+        &self,
+        spare: &mut Vec<SmartQueue>,
+        pools: &HashMap<PathBuf, Vec<PathBuf>>,
+        config: &OutputConfig,
+    ) -> Option<SmartQueue> {
+        let path = config.path.as_ref()?;
+        let library = config.library();
+        let filter = config.library_filter();
+        let same_folders = |q: &&SmartQueue| {
+            q.sources
+                .iter()
+                .map(|s| &s.path)
+                .eq(library.iter().map(|s| &s.path))
+        };
+        if let Some(i) = spare
+            .iter()
+            .position(|q| same_folders(&q) && q.filter == filter)
+        {
+            let mut q = spare.swap_remove(i);
+            q.sources = library;
+            q.video_ratio = config.video_ratio;
+            q.strategy = config.sorting;
+            q.tags = config.tags.clone();
+            return Some(q);
+        }
+        let mut q = match pools.get(path).filter(|_| library.len() == 1) {
+            Some(pool) => SmartQueue::new_from_pool(
+                &library,
+                filter,
+                pool.clone(),
+                config.video_ratio,
+                config.sorting,
+                self.stats.clone(),
+            )
+            .ok()?,
+            None => SmartQueue::new_with_cache(
+                &library,
+                filter,
+                config.video_ratio,
+                config.sorting,
+                self.stats.clone(),
+                self.metrics.clone(),
+            )
+            .await
+            .ok()?,
+        };
+        q.tags = config.tags.clone();
+        if let Some(pl_name) = &config.default_playlist {
+            let _ = q.set_playlist(Some(pl_name.clone()));
+        }
+        Some(q)
+    }
+
+    fn resolve_group_configs(config: &Config, scale: f32) -> HashMap<usize, OutputConfig> {
+        config
+            .groups
+            .keys()
+            .filter_map(|&gid| {
+                config
+                    .get_config_for_group(gid)
+                    .map(|c| (gid, with_rotation_scale(c, scale)))
+            })
+            .collect()
+    }
+
+    /// Config a shared (gid 0 when synchronized) or group queue is built from: the
+    /// group's own section, or the config of the output creating it
+    fn queue_config(&self, gid: usize, output_config: &OutputConfig) -> OutputConfig {
+        self.group_configs
+            .get(&gid)
+            .cloned()
+            .unwrap_or_else(|| output_config.clone())
+    }
+
+    pub async fn add_output(&mut self, identity: &OutputIdentity) {
+        self.create_output(identity).await;
+        let (chain, looped) = self.mirror_chain(&identity.name);
+        if looped {
+            warn!(
+                "[MIRROR] {}: Mirror chain loops back on itself ({}), its outputs show their own content",
+                identity.name,
+                chain.join(" -> ")
+            );
         }
     }
 
-    pub async fn add_output(&mut self, name: &str, description: &str) {
-        let output_config = self.config.get_config_for_output(name, description);
+    async fn create_output(&mut self, identity: &OutputIdentity) {
+        let name = identity.name.as_str();
+        let output_config = with_rotation_scale(
+            self.config.get_config_for_output(identity),
+            rotation_scale(&self.config, self.power_saving),
+        );
+        // The new queue starts on the default playlist; the next schedule check re-applies rules
+        self.scheduled_playlists.remove(name);
+        // The next tick puts the current kiosk item on the new output
+        if let Some(kiosk) = &mut self.kiosk {
+            kiosk.resend();
+        }
         info!(
             "[ADD_OUTPUT] {}: path={:?}, behavior={:?}",
             name, output_config.path, self.config.global.monitor_behavior
         );
 
+        // A single file needs no queue, so the output stays out of shared and group queues
+        if output_config.static_file().is_some() {
+            let orch = OutputOrchestrator::new(
+                name.to_string(),
+                identity.clone(),
+                output_config,
+                self.stats.clone(),
+                self.metrics.clone(),
+            )
+            .await;
+            self.outputs.insert(name.to_string(), orch);
+            return;
+        }
+
         match &self.config.global.monitor_behavior {
             MonitorBehavior::Independent => {
                 info!("[ADD_OUTPUT] {}: Creating independent queue", name);
-                // Check if we already discovered files for this path (avoids re-scanning)
-                let cached_path = output_config.path.clone();
+                // Check if we already discovered files for this path (avoids re-scanning).
+                // Mixed sources are scanned anew.
+                let cached_path = output_config
+                    .path
+                    .clone()
+                    .filter(|_| output_config.sources.len() <= 1);
                 let orch = if let Some(path) = &cached_path {
                     if let Some(cached_files) = self.discovered_files_cache.get(path) {
                         info!(
@@ -273,14 +1025,16 @@ impl MonitorManager {
                             path
                         );
                         let queue = SmartQueue::new_from_pool(
-                            path,
+                            &output_config.library(),
+                            output_config.library_filter(),
                             cached_files.clone(),
                             output_config.video_ratio,
                             output_config.sorting,
-                            self.cache.clone(),
+                            self.stats.clone(),
                         )
                         .ok()
                         .map(|mut q| {
+                            q.tags = output_config.tags.clone();
                             if let Some(pl_name) = &output_config.default_playlist {
                                 let _ = q.set_playlist(Some(pl_name.clone()));
                             }
@@ -288,7 +1042,7 @@ impl MonitorManager {
                         });
                         OutputOrchestrator {
                             _name: name.to_string(),
-                            description: description.to_string(),
+                            identity: identity.clone(),
                             config: output_config,
                             queue,
                             current_path: None,
@@ -296,18 +1050,25 @@ impl MonitorManager {
                             next_content_type: None,
                             next_change: None,
                             display_start_time: None,
+                            static_file: None,
                         }
                     } else {
                         let orch = OutputOrchestrator::new(
                             name.to_string(),
-                            description.to_string(),
+                            identity.clone(),
                             output_config,
-                            self.cache.clone(),
+                            self.stats.clone(),
                             self.metrics.clone(),
                         )
                         .await;
-                        // Cache the discovered file list for subsequent outputs with the same path
-                        if let Some(q) = &orch.queue {
+                        // Cache the discovered file list for subsequent outputs with the same
+                        // path, unless it is only the first part of a still running scan or
+                        // leaves files out
+                        if let Some(q) = orch
+                            .queue
+                            .as_ref()
+                            .filter(|q| !q.is_discovering() && q.filter.is_empty())
+                        {
                             self.discovered_files_cache
                                 .insert(path.clone(), q.pool.clone());
                         }
@@ -316,9 +1077,9 @@ impl MonitorManager {
                 } else {
                     OutputOrchestrator::new(
                         name.to_string(),
-                        description.to_string(),
+                        identity.clone(),
                         output_config,
-                        self.cache.clone(),
+                        self.stats.clone(),
                         self.metrics.clone(),
                     )
                     .await
@@ -332,17 +1093,20 @@ impl MonitorManager {
             }
             MonitorBehavior::Synchronized => {
                 if self.shared_queue.is_none() {
-                    if let Some(path) = &output_config.path {
+                    let queue_config = self.queue_config(0, &output_config);
+                    if queue_config.path.is_some() {
                         if let Ok(mut q) = SmartQueue::new_with_cache(
-                            path,
-                            output_config.video_ratio,
-                            output_config.sorting,
-                            self.cache.clone(),
+                            &queue_config.library(),
+                            queue_config.library_filter(),
+                            queue_config.video_ratio,
+                            queue_config.sorting,
+                            self.stats.clone(),
                             self.metrics.clone(),
                         )
                         .await
                         {
-                            if let Some(pl_name) = &output_config.default_playlist {
+                            q.tags = queue_config.tags.clone();
+                            if let Some(pl_name) = &queue_config.default_playlist {
                                 let _ = q.set_playlist(Some(pl_name.clone()));
                             }
                             self.shared_queue = Some(q);
@@ -351,9 +1115,9 @@ impl MonitorManager {
                 }
                 let mut orch = OutputOrchestrator::new(
                     name.to_string(),
-                    description.to_string(),
+                    identity.clone(),
                     output_config,
-                    self.cache.clone(),
+                    self.stats.clone(),
                     self.metrics.clone(),
                 )
                 .await;
@@ -375,17 +1139,20 @@ impl MonitorManager {
 
                     // Initialize group queue if needed
                     if !self.group_queues.contains_key(&gid) {
-                        if let Some(path) = &output_config.path {
+                        let queue_config = self.queue_config(gid, &output_config);
+                        if queue_config.path.is_some() {
                             if let Ok(mut q) = SmartQueue::new_with_cache(
-                                path,
-                                output_config.video_ratio,
-                                output_config.sorting,
-                                self.cache.clone(),
+                                &queue_config.library(),
+                                queue_config.library_filter(),
+                                queue_config.video_ratio,
+                                queue_config.sorting,
+                                self.stats.clone(),
                                 self.metrics.clone(),
                             )
                             .await
                             {
-                                if let Some(pl_name) = &output_config.default_playlist {
+                                q.tags = queue_config.tags.clone();
+                                if let Some(pl_name) = &queue_config.default_playlist {
                                     let _ = q.set_playlist(Some(pl_name.clone()));
                                 }
                                 self.group_queues.insert(gid, q);
@@ -395,9 +1162,9 @@ impl MonitorManager {
 
                     let mut orch = OutputOrchestrator::new(
                         name.to_string(),
-                        description.to_string(),
+                        identity.clone(),
                         output_config,
-                        self.cache.clone(),
+                        self.stats.clone(),
                         self.metrics.clone(),
                     )
                     .await;
@@ -408,9 +1175,9 @@ impl MonitorManager {
                     info!("Output {} not in any group, treating as independent", name);
                     let orch = OutputOrchestrator::new(
                         name.to_string(),
-                        description.to_string(),
+                        identity.clone(),
                         output_config,
-                        self.cache.clone(),
+                        self.stats.clone(),
                         self.metrics.clone(),
                     )
                     .await;
@@ -420,8 +1187,79 @@ impl MonitorManager {
         }
     }
 
+    /// First content of an output plugged in while running, rather than waiting for the
+    /// next tick (which holds while paused). One joining a shared or group queue takes
+    /// what the rest of that queue shows, so it doesn't switch the others.
+    pub fn first_pick(&mut self, name: &str) -> Option<(PathBuf, crate::queue::ContentType)> {
+        // The kiosk puts its current item on the new output itself (see `add_output`)
+        if self.kiosk.is_some() {
+            return None;
+        }
+        let orch = self.outputs.get_mut(name)?;
+        if orch.queue.is_some() || orch.static_file.is_some() {
+            self.twins.prepare(name, orch.queue.as_mut());
+            let pick = orch.tick();
+            if let Some((path, _)) = &pick {
+                self.twins.record(name, path);
+            }
+            return pick;
+        }
+
+        let group = self.output_groups.get(name).copied();
+        let (_, sibling) = self.outputs.iter().find(|(n, o)| {
+            *n != name
+                && o.queue.is_none()
+                && o.static_file.is_none()
+                && o.current_path.is_some()
+                && self.output_groups.get(*n).copied() == group
+        })?;
+        let path = sibling.current_path.clone()?;
+        let next_change = sibling.next_change;
+        let next_path = sibling.next_path.clone();
+        let next_content_type = sibling.next_content_type;
+        let content_type = SmartQueue::get_content_type(&path)?;
+
+        info!("[HOTPLUG] {}: Joining its queue at {:?}", name, path);
+        let orch = self.outputs.get_mut(name)?;
+        orch.current_path = Some(path.clone());
+        orch.next_change = next_change;
+        orch.next_path = next_path;
+        orch.next_content_type = next_content_type;
+        Some((path, content_type))
+    }
+
+    /// Drops an output that was unplugged, so queues stop picking for it and a
+    /// shared queue isn't held by its pause
+    pub fn remove_output(&mut self, name: &str) {
+        if self.outputs.remove(name).is_none() {
+            return;
+        }
+        info!("[REMOVE_OUTPUT] {}: Output removed", name);
+        self.output_groups.remove(name);
+        self.paused_outputs.remove(name);
+        self.cleared.remove(name);
+        self.muted.remove(name);
+        self.covered.remove(name);
+        self.scheduled_playlists.remove(name);
+        self.load_retries.remove(name);
+        self.failover.remove(name);
+        self.looks.remove(name);
+        self.auto_pending.remove(name);
+        self.tones.remove(name);
+        self.transition_overrides.remove(name);
+        self.workspace_bindings.remove(name);
+    }
+
     pub fn set_paused(&mut self, paused: bool) {
+        if self.paused != paused || !self.paused_outputs.is_empty() {
+            crate::events::emit(if paused {
+                kaleidux_common::Event::Paused { output: None }
+            } else {
+                kaleidux_common::Event::Resumed { output: None }
+            });
+        }
         self.paused = paused;
+        self.paused_outputs.clear();
         if paused {
             info!("[MONITOR_MANAGER] Wallpaper cycling paused");
         } else {
@@ -439,78 +1277,419 @@ impl MonitorManager {
         }
     }
 
-    pub fn tick(&mut self) -> HashMap<String, (PathBuf, crate::queue::ContentType)> {
-        let mut changes = HashMap::new();
-        // Don't cycle wallpapers when paused
-        if self.paused {
-            return changes;
+    /// Pauses or resumes cycling on one output. Resuming restarts the timer of the
+    /// queue it draws from, so content doesn't switch right away.
+    pub fn set_output_paused(&mut self, name: &str, paused: bool) {
+        let name = self.resolve_mirror(name).to_string();
+        if paused {
+            info!("[MONITOR_MANAGER] {}: Wallpaper cycling paused", name);
+            if self.paused_outputs.insert(name.clone()) {
+                crate::events::emit(kaleidux_common::Event::Paused { output: Some(name) });
+            }
+            return;
+        }
+        if !self.paused_outputs.remove(&name) {
+            return;
         }
+        crate::events::emit(kaleidux_common::Event::Resumed {
+            output: Some(name.clone()),
+        });
         let now = Instant::now();
-
         match &self.config.global.monitor_behavior {
-            MonitorBehavior::Independent => {
-                for (name, orch) in &mut self.outputs {
-                    if let Some(res) = orch.tick() {
-                        changes.insert(name.clone(), res);
-                    }
+            MonitorBehavior::Synchronized => {
+                for orch in self.outputs.values_mut() {
+                    orch.display_start_time = Some(now);
+                    orch.next_change = Some(now + orch.config.duration);
                 }
+                self.shared_display_start_time = Some(now);
             }
-            MonitorBehavior::Synchronized => {
-                let mut should_change = false;
-                // Use shared display start time for synchronized outputs
-                if let Some(shared_start) = self.shared_display_start_time {
-                    let elapsed = now.saturating_duration_since(shared_start);
-                    if let Some(first_orch) = self.outputs.values().next() {
-                        if elapsed >= first_orch.config.duration {
-                            should_change = true;
-                        }
-                    }
-                } else if let Some(first_orch) = self.outputs.values().next() {
-                    // Fallback: check individual times if shared time not set yet
-                    if let Some(display_start) = first_orch.display_start_time {
-                        let elapsed = now.saturating_duration_since(display_start);
-                        if elapsed >= first_orch.config.duration {
-                            should_change = true;
-                        }
-                    } else if let Some(next) = first_orch.next_change {
-                        if now >= next {
-                            should_change = true;
-                        }
-                    } else if first_orch.current_path.is_none() {
-                        should_change = true;
+            _ => {
+                let group = self.output_groups.get(&name).copied();
+                for (n, orch) in &mut self.outputs {
+                    if *n == name
+                        || (group.is_some() && self.output_groups.get(n) == group.as_ref())
+                    {
+                        orch.display_start_time = Some(now);
+                        orch.next_change = Some(now + orch.config.duration);
                     }
                 }
+                if let Some(start) = group.and_then(|g| self.group_display_start_times.get_mut(&g))
+                {
+                    *start = now;
+                }
+            }
+        }
+        info!(
+            "[MONITOR_MANAGER] {}: Wallpaper cycling resumed (timer reset)",
+            name
+        );
+    }
 
-                if should_change {
-                    if let Some(queue) = &mut self.shared_queue {
-                        if let Some(path) = queue.pick_next() {
-                            let content_type =
-                                crate::queue::SmartQueue::get_content_type(&path).unwrap();
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
 
-                            // Pre-buffer next content
-                            let (next_p, next_t) = if let Some((np, nt)) = queue.peek_next() {
-                                (Some(np), Some(nt))
-                            } else {
-                                (None, None)
-                            };
+    pub fn power_config(&self) -> &crate::orchestration::PowerConfig {
+        &self.config.power
+    }
 
-                            // Reset shared display start time for next cycle
-                            self.shared_display_start_time = None;
-                            for (name, orch) in &mut self.outputs {
-                                orch.current_path = Some(path.clone());
-                                orch.display_start_time = None;
-                                orch.next_change = Some(
-                                    now + orch.config.duration + std::time::Duration::from_secs(5),
-                                );
+    /// Enters or leaves `[power]` saving. Durations change right away and timers keep
+    /// running, so back on AC a wallpaper that has been up long enough switches.
+    pub fn set_power_saving(&mut self, saving: bool) {
+        if self.power_saving == saving {
+            return;
+        }
+        let multiplier = self.config.power.rotation_multiplier;
+        let factor = if saving { multiplier } else { 1.0 / multiplier };
+        self.power_saving = saving;
+        for orch in self.outputs.values_mut() {
+            orch.config.duration = orch.config.duration.mul_f32(factor);
+        }
+        for config in self.group_configs.values_mut() {
+            config.duration = config.duration.mul_f32(factor);
+        }
+    }
 
-                                orch.next_path = next_p.clone();
-                                orch.next_content_type = next_t;
+    /// Whether videos hold still to save power (`[power] pause-videos`)
+    pub fn power_pauses_videos(&self) -> bool {
+        self.power_saving && self.config.power.pause_videos
+    }
 
-                                changes.insert(name.clone(), (path.clone(), content_type));
-                            }
-                        }
-                    }
-                }
+    /// Whether switches cut without a transition to save power
+    /// (`[power] instant-transitions`)
+    pub fn power_cuts_transitions(&self) -> bool {
+        self.power_saving && self.config.power.instant_transitions
+    }
+
+    /// Outputs hidden right now, given the ones fullscreen windows cover and whether the
+    /// session is idle, as far as `pause-on-fullscreen` and `pause-on-idle` ask to hold
+    /// them. Returns the outputs that just got covered and the ones just revealed.
+    pub fn set_covered(
+        &mut self,
+        fullscreen: std::collections::HashSet<String>,
+        idle: bool,
+    ) -> (Vec<String>, Vec<String>) {
+        let global = &self.config.global;
+        let covered: std::collections::HashSet<String> = self
+            .outputs
+            .keys()
+            .filter(|name| {
+                (idle && global.pause_on_idle.is_some())
+                    || (global.pause_on_fullscreen && fullscreen.contains(*name))
+            })
+            .cloned()
+            .collect();
+        let mut hidden: Vec<String> = covered.difference(&self.covered).cloned().collect();
+        let mut revealed: Vec<String> = self.covered.difference(&covered).cloned().collect();
+        hidden.sort();
+        revealed.sort();
+        for name in &hidden {
+            debug!("[COVER] {}: Hidden, holding video and rendering", name);
+        }
+        for name in &revealed {
+            debug!("[COVER] {}: Revealed, resuming", name);
+        }
+        self.covered = covered;
+        (hidden, revealed)
+    }
+
+    pub fn is_covered(&self, name: &str) -> bool {
+        self.covered.contains(name)
+    }
+
+    /// `pause-on-idle` timeout, for the backend to watch
+    pub fn pause_on_idle(&self) -> Option<Duration> {
+        self.config.global.pause_on_idle
+    }
+
+    /// Whether a video on `name` should hold still: cycling is paused there, power saving
+    /// holds videos, or nobody sees the output
+    pub fn holds_video(&self, name: &str) -> bool {
+        self.is_paused(name) || self.power_pauses_videos() || self.is_covered(name)
+    }
+
+    /// Whether cycling on `name` is paused, by a plain `pause` or one aimed at it
+    pub fn is_paused(&self, name: &str) -> bool {
+        self.paused || self.paused_outputs.contains(self.resolve_mirror(name))
+    }
+
+    /// Holds `name` on the color `clear` left, or lets it take content again
+    pub fn set_cleared(&mut self, name: &str, cleared: bool) {
+        if cleared {
+            self.cleared.insert(name.to_string());
+        } else if self.cleared.remove(name) {
+            debug!("[MONITOR_MANAGER] {}: Clear lifted", name);
+        }
+    }
+
+    pub fn is_cleared(&self, name: &str) -> bool {
+        self.cleared.contains(name)
+    }
+
+    /// Lets the outputs `spec` matches (all without one) take content again after
+    /// `clear`. Bad specs are left for the command itself to report.
+    pub fn lift_clear(&mut self, spec: Option<&str>) {
+        let names = match spec {
+            Some(spec) => match_outputs(spec, self.outputs.keys()).unwrap_or_default(),
+            None => self.outputs.keys().cloned().collect(),
+        };
+        for name in names {
+            self.set_cleared(&name, false);
+        }
+    }
+
+    /// One output per queue among `names` (mirrors resolved to their source), so a
+    /// next/prev aimed at several outputs sharing a queue advances it only once
+    pub fn queue_targets(&self, names: &[String]) -> Vec<String> {
+        let mut leaders = Vec::new();
+        let mut targets = Vec::new();
+        for name in names {
+            let source = self.resolve_mirror(name).to_string();
+            let leader = self.queue_leader(&source);
+            if !leaders.contains(&leader) {
+                leaders.push(leader);
+                targets.push(source);
+            }
+        }
+        targets
+    }
+
+    pub fn tick(&mut self) -> HashMap<String, (PathBuf, crate::queue::ContentType)> {
+        self.load_histories();
+        let mut changes = self.due_changes();
+        // Outputs on a workspace bound to a file hold it
+        changes.retain(|name, _| {
+            self.workspace_bindings
+                .get(name)
+                .is_none_or(|rule| rule.path.is_none())
+        });
+        changes.extend(self.workspace_changes());
+        // Cleared outputs stay blank; a shared queue still advances for the others
+        changes.retain(|name, _| !self.cleared.contains(name));
+        changes
+    }
+
+    /// Switches outputs whose active workspace changed to what its `[workspace.NAME]`
+    /// rule binds, and outputs leaving a bound workspace back to their own content, with
+    /// `workspace-transition`
+    fn workspace_changes(&mut self) -> HashMap<String, (PathBuf, crate::queue::ContentType)> {
+        let mut changes = HashMap::new();
+        let switched = self.workspaces.poll(!self.config.workspaces.is_empty());
+        // Kiosk mode has one sequence for every output
+        if self.kiosk.is_some() {
+            return changes;
+        }
+        for (name, workspace) in switched {
+            // Mirrors show what the output they mirror shows
+            if self.resolve_mirror(&name) != name {
+                continue;
+            }
+            let Some(orch) = self.outputs.get(&name) else {
+                continue;
+            };
+            let rule = self
+                .config
+                .workspace_rule(&orch.identity, &workspace)
+                .cloned();
+            let previous = match &rule {
+                Some(rule) => self.workspace_bindings.insert(name.clone(), rule.clone()),
+                None => self.workspace_bindings.remove(&name),
+            };
+            if previous == rule {
+                continue;
+            }
+            info!(
+                "[WORKSPACE] {}: Workspace {} active ({})",
+                name,
+                workspace,
+                if rule.is_some() { "bound" } else { "unbound" }
+            );
+            let switch = match rule {
+                Some(crate::orchestration::WorkspaceRule {
+                    path: Some(path), ..
+                }) => self.show_workspace_file(&name, &path),
+                Some(rule) => self.play_workspace_playlist(&name, rule.playlist),
+                None => {
+                    // Back to the scheduled or default playlist, or the static file
+                    let wanted = match self.scheduled_playlists.get(&name) {
+                        Some(scheduled) => scheduled.clone(),
+                        None => orch.config.default_playlist.clone(),
+                    };
+                    match self.outputs.get_mut(&name) {
+                        Some(orch) if orch.static_file.is_some() => {
+                            orch.current_path = None;
+                            orch.tick().map(|c| (name.clone(), c)).into_iter().collect()
+                        }
+                        _ => self.play_workspace_playlist(&name, wanted),
+                    }
+                }
+            };
+            changes.extend(switch);
+        }
+
+        let global = &self.config.global;
+        let (transition, cut) = self.named_transition(&global.workspace_transition);
+        let one_shot = TransitionOverride {
+            transition: Some(transition),
+            cut,
+            time: Some(global.workspace_transition_time),
+        };
+        for name in changes.keys() {
+            self.transition_overrides
+                .insert(name.clone(), one_shot.clone());
+        }
+        changes
+    }
+
+    /// Holds `name` on a workspace's `path`
+    fn show_workspace_file(
+        &mut self,
+        name: &str,
+        path: &Path,
+    ) -> HashMap<String, (PathBuf, crate::queue::ContentType)> {
+        let Some(content_type) = SmartQueue::get_content_type(path) else {
+            warn!(
+                "[WORKSPACE] {}: {} is not an image or video",
+                name,
+                path.display()
+            );
+            return HashMap::new();
+        };
+        let Some(orch) = self.outputs.get_mut(name) else {
+            return HashMap::new();
+        };
+        orch.current_path = Some(path.to_path_buf());
+        orch.display_start_time = None;
+        orch.next_change = Some(Instant::now() + orch.config.duration + Duration::from_secs(5));
+        HashMap::from([(name.to_string(), (path.to_path_buf(), content_type))])
+    }
+
+    /// Switches the queue behind `name` to `playlist` (None: all of its content) and
+    /// advances it
+    fn play_workspace_playlist(
+        &mut self,
+        name: &str,
+        playlist: Option<String>,
+    ) -> HashMap<String, (PathBuf, crate::queue::ContentType)> {
+        if let Some(queue) = self.queue_for_mut(name) {
+            if queue.active_playlist != playlist {
+                if let Err(e) = queue.set_playlist(playlist.clone()) {
+                    warn!(
+                        "[WORKSPACE] {}: Failed to load playlist {:?}: {}",
+                        name, playlist, e
+                    );
+                }
+            }
+        }
+        self.handle_next(Some(name.to_string()))
+    }
+
+    fn due_changes(&mut self) -> HashMap<String, (PathBuf, crate::queue::ContentType)> {
+        let mut changes = HashMap::new();
+        // Don't cycle wallpapers when paused
+        if self.paused {
+            return changes;
+        }
+        let now = Instant::now();
+        // Mirrors follow their source in the main loop and never pick their own content
+        if let Some(kiosk) = &mut self.kiosk {
+            // Pausing any output holds the sequence, as with a shared queue
+            if !self.paused_outputs.is_empty() {
+                return changes;
+            }
+            return match kiosk.tick(now) {
+                Some(item) => self.show_kiosk_item(item),
+                None => changes,
+            };
+        }
+        let mirrors = self.active_mirrors();
+        let paused_outputs = &self.paused_outputs;
+        let cleared = &self.cleared;
+        let twins = &mut self.twins;
+
+        match &self.config.global.monitor_behavior {
+            MonitorBehavior::Independent => {
+                for (name, orch) in &mut self.outputs {
+                    if mirrors.contains(name)
+                        || paused_outputs.contains(name)
+                        || cleared.contains(name)
+                    {
+                        continue;
+                    }
+                    twins.prepare(name, orch.queue.as_mut());
+                    if let Some(res) = orch.tick() {
+                        twins.record(name, &res.0);
+                        changes.insert(name.clone(), res);
+                    }
+                }
+            }
+            MonitorBehavior::Synchronized => {
+                // Static outputs aren't part of the shared queue
+                for (name, orch) in &mut self.outputs {
+                    if orch.static_file.is_some() && !mirrors.contains(name) {
+                        if let Some(res) = orch.tick() {
+                            changes.insert(name.clone(), res);
+                        }
+                    }
+                }
+                // Pausing any output holds the shared queue
+                if !paused_outputs.is_empty() {
+                    return changes;
+                }
+                let mut should_change = false;
+                // [group.0] sets the shared duration; without it the first output's applies
+                let group_duration = self.group_configs.get(&0).map(|c| c.duration);
+                // Use shared display start time for synchronized outputs
+                if let Some(shared_start) = self.shared_display_start_time {
+                    let elapsed = now.saturating_duration_since(shared_start);
+                    if let Some(first_orch) = self.synchronized_outputs().next() {
+                        if elapsed >= group_duration.unwrap_or(first_orch.config.duration) {
+                            should_change = true;
+                        }
+                    }
+                } else if let Some(first_orch) = self.synchronized_outputs().next() {
+                    // Fallback: check individual times if shared time not set yet
+                    if let Some(display_start) = first_orch.display_start_time {
+                        let elapsed = now.saturating_duration_since(display_start);
+                        if elapsed >= group_duration.unwrap_or(first_orch.config.duration) {
+                            should_change = true;
+                        }
+                    } else if let Some(next) = first_orch.next_change {
+                        if now >= next {
+                            should_change = true;
+                        }
+                    } else if first_orch.current_path.is_none() {
+                        should_change = true;
+                    }
+                }
+
+                if should_change {
+                    if let Some(queue) = &mut self.shared_queue {
+                        if let Some((path, content_type)) = queue.pick_next_playable() {
+                            // Pre-buffer next content
+                            let (next_p, next_t) = if let Some((np, nt)) = queue.peek_next() {
+                                (Some(np), Some(nt))
+                            } else {
+                                (None, None)
+                            };
+
+                            // Reset shared display start time for next cycle
+                            self.shared_display_start_time = None;
+                            for (name, orch) in self.synchronized_outputs_mut() {
+                                orch.current_path = Some(path.clone());
+                                orch.display_start_time = None;
+                                orch.next_change = Some(
+                                    now + orch.config.duration + std::time::Duration::from_secs(5),
+                                );
+
+                                orch.next_path = next_p.clone();
+                                orch.next_content_type = next_t;
+
+                                changes.insert(name.clone(), (path.clone(), content_type));
+                            }
+                        }
+                    }
+                }
             }
             MonitorBehavior::Grouped(_) => {
                 // Check each group independently
@@ -521,14 +1700,18 @@ impl MonitorManager {
                 }
 
                 for (gid, output_names) in groups_to_tick {
+                    if output_names.iter().any(|n| paused_outputs.contains(n)) {
+                        continue;
+                    }
                     // Check if any output in this group needs a change
                     let mut should_change = false;
+                    let group_duration = self.group_configs.get(&gid).map(|c| c.duration);
                     // Use group display start time if available
                     if let Some(group_start) = self.group_display_start_times.get(&gid) {
                         let elapsed = now.saturating_duration_since(*group_start);
                         if let Some(first_name) = output_names.first() {
                             if let Some(orch) = self.outputs.get(first_name) {
-                                if elapsed >= orch.config.duration {
+                                if elapsed >= group_duration.unwrap_or(orch.config.duration) {
                                     should_change = true;
                                 }
                             }
@@ -538,7 +1721,7 @@ impl MonitorManager {
                         if let Some(orch) = self.outputs.get(first_name) {
                             if let Some(display_start) = orch.display_start_time {
                                 let elapsed = now.saturating_duration_since(display_start);
-                                if elapsed >= orch.config.duration {
+                                if elapsed >= group_duration.unwrap_or(orch.config.duration) {
                                     should_change = true;
                                 }
                             } else if let Some(next) = orch.next_change {
@@ -553,9 +1736,10 @@ impl MonitorManager {
 
                     if should_change {
                         if let Some(queue) = self.group_queues.get_mut(&gid) {
-                            if let Some(path) = queue.pick_next() {
-                                let content_type =
-                                    crate::queue::SmartQueue::get_content_type(&path).unwrap();
+                            let owner = format!("group.{}", gid);
+                            twins.prepare(&owner, Some(queue));
+                            if let Some((path, content_type)) = queue.pick_next_playable() {
+                                twins.record(&owner, &path);
 
                                 // Pre-buffer next content
                                 let (next_p, next_t) = if let Some((np, nt)) = queue.peek_next() {
@@ -588,8 +1772,13 @@ impl MonitorManager {
 
                 // Also tick independent outputs (not in any group)
                 for (name, orch) in &mut self.outputs {
-                    if !self.output_groups.contains_key(name) {
+                    if !self.output_groups.contains_key(name)
+                        && !mirrors.contains(name)
+                        && !paused_outputs.contains(name)
+                    {
+                        twins.prepare(name, orch.queue.as_mut());
                         if let Some(res) = orch.tick() {
+                            twins.record(name, &res.0);
                             changes.insert(name.clone(), res);
                         }
                     }
@@ -597,25 +1786,269 @@ impl MonitorManager {
             }
         }
 
+        changes.retain(|name, _| !mirrors.contains(name));
+        changes
+    }
+
+    /// Puts a kiosk item on every output not already showing it (mirrors follow their
+    /// source)
+    fn show_kiosk_item(
+        &mut self,
+        (path, content_type): (PathBuf, crate::queue::ContentType),
+    ) -> HashMap<String, (PathBuf, crate::queue::ContentType)> {
+        let mirrors = self.active_mirrors();
+        let mut changes = HashMap::new();
+        for (name, orch) in &mut self.outputs {
+            if orch.current_path.as_ref() == Some(&path) {
+                continue;
+            }
+            orch.current_path = Some(path.clone());
+            orch.display_start_time = None;
+            orch.next_change = None;
+            if !mirrors.contains(name) {
+                changes.insert(name.clone(), (path.clone(), content_type));
+            }
+        }
         changes
     }
 
+    /// `kldctl goto`: jumps the kiosk sequence to item `index` on every output
+    pub fn kiosk_goto(
+        &mut self,
+        index: usize,
+    ) -> Result<HashMap<String, (PathBuf, crate::queue::ContentType)>> {
+        let Some(kiosk) = &mut self.kiosk else {
+            anyhow::bail!(CommandError::new(
+                ErrorCode::Unavailable,
+                "Kiosk mode is off (add a [kiosk] section to the config)"
+            ));
+        };
+        let item = kiosk.goto(index)?;
+        Ok(self.show_kiosk_item(item))
+    }
+
+    /// `kldctl set`: shows `path` on the `output` spec (every output if None) without
+    /// asking the queue, which carries on from where it was after `duration`. Mirrors
+    /// stand for the output they mirror.
+    pub fn set_wallpaper(
+        &mut self,
+        path: &Path,
+        output: Option<&str>,
+        transition: Option<&str>,
+        duration: Option<f32>,
+    ) -> Result<HashMap<String, (PathBuf, crate::queue::ContentType)>> {
+        if !path.exists() {
+            anyhow::bail!(CommandError::new(
+                ErrorCode::NotFound,
+                format!("{} does not exist", path.display())
+            ));
+        }
+        let Some(content_type) = SmartQueue::get_content_type(path) else {
+            anyhow::bail!(CommandError::new(
+                ErrorCode::InvalidArgument,
+                format!("{} is not an image or video", path.display())
+            ));
+        };
+        let mut one_shot = TransitionOverride::default();
+        if let Some(name) = transition {
+            let lower = name.to_lowercase();
+            let known = self.config.transitions.contains_key(name)
+                || matches!(lower.as_str(), "cut" | "random")
+                || kaleidux_common::RANDOM_TRANSITIONS.contains(&lower.as_str());
+            if !known {
+                anyhow::bail!(CommandError::new(
+                    ErrorCode::InvalidArgument,
+                    format!("Unknown transition '{}'", name)
+                ));
+            }
+            let (picked, cut) = self.named_transition(name);
+            one_shot.transition = Some(picked);
+            one_shot.cut = cut;
+        }
+        one_shot.time = duration.map(transition_millis).transpose()?;
+
+        let names = match output {
+            Some(spec) => match_outputs(spec, self.outputs.keys())?,
+            None => self.outputs.keys().cloned().collect(),
+        };
+        let mut targets: Vec<String> = names
+            .iter()
+            .map(|n| self.resolve_mirror(n).to_string())
+            .collect();
+        targets.sort();
+        targets.dedup();
+
+        let now = Instant::now();
+        let mut changes = HashMap::new();
+        for name in targets {
+            let Some(orch) = self.outputs.get_mut(&name) else {
+                continue;
+            };
+            orch.current_path = Some(path.to_path_buf());
+            orch.display_start_time = None;
+            orch.next_change = Some(now + orch.config.duration + Duration::from_secs(5));
+            self.transition_overrides
+                .insert(name.clone(), one_shot.clone());
+            changes.insert(name, (path.to_path_buf(), content_type));
+        }
+        Ok(changes)
+    }
+
+    /// `kldctl transition set`: changes the transition (and with `duration`, in seconds,
+    /// its length) of the `output` spec's outputs, every output if None, until the next
+    /// reload. Mirrors stand for the output they mirror, whose transition they show.
+    pub fn set_transition(
+        &mut self,
+        output: Option<&str>,
+        transition: &str,
+        duration: Option<f32>,
+    ) -> Result<Vec<OutputResult>> {
+        let Some(resolved) = self.config.transition_named(transition) else {
+            anyhow::bail!(CommandError::new(
+                ErrorCode::InvalidArgument,
+                format!("Unknown transition '{}'", transition)
+            ));
+        };
+        let time = duration.map(transition_millis).transpose()?;
+        let mut results = Vec::new();
+        for name in self.source_targets(output)? {
+            let Some(orch) = self.outputs.get_mut(&name) else {
+                continue;
+            };
+            orch.config.transition = resolved.clone();
+            if let Some(time) = time {
+                orch.config.transition_time = time;
+            }
+            // An auto pick still waiting for its image would replace the new transition
+            self.auto_pending.remove(&name);
+            info!(
+                "[TRANSITION] {}: Set to {} ({}ms) until the next reload",
+                name, transition, orch.config.transition_time
+            );
+            let message = format!(
+                "transition {} ({}ms)",
+                transition, orch.config.transition_time
+            );
+            results.push(OutputResult::ok(&name, message));
+        }
+        Ok(results)
+    }
+
+    /// Outputs `output` matches (all without one), mirrors replaced by the output they
+    /// mirror, which owns their transition and plays their videos
+    fn source_targets(&self, output: Option<&str>) -> Result<Vec<String>> {
+        let names = match output {
+            Some(spec) => match_outputs(spec, self.outputs.keys())?,
+            None => self.outputs.keys().cloned().collect(),
+        };
+        let mut targets: Vec<String> = names
+            .iter()
+            .map(|n| self.resolve_mirror(n).to_string())
+            .collect();
+        targets.sort();
+        targets.dedup();
+        Ok(targets)
+    }
+
+    /// `kldctl volume`: video volume (0-100) of the outputs `output` matches until the
+    /// next reload. Apply `volume()` to their players afterwards.
+    pub fn set_volume(&mut self, output: Option<&str>, volume: u8) -> Result<Vec<OutputResult>> {
+        if volume > 100 {
+            anyhow::bail!(CommandError::new(
+                ErrorCode::InvalidArgument,
+                format!("Volume {} is out of range (0-100)", volume)
+            ));
+        }
+        let mut results = Vec::new();
+        for name in self.source_targets(output)? {
+            let Some(orch) = self.outputs.get_mut(&name) else {
+                continue;
+            };
+            orch.config.volume = volume;
+            let muted = self.muted.contains(&name);
+            info!(
+                "[VOLUME] {}: Set to {} until the next reload{}",
+                name,
+                volume,
+                if muted { " (muted)" } else { "" }
+            );
+            let message = if muted {
+                format!("volume {} (muted)", volume)
+            } else {
+                format!("volume {}", volume)
+            };
+            results.push(OutputResult::ok(&name, message));
+        }
+        Ok(results)
+    }
+
+    /// `kldctl mute` / `unmute`: silences the outputs `output` matches, or toggles each
+    /// of them when `muted` is None. Apply `volume()` to their players afterwards.
+    pub fn set_muted(
+        &mut self,
+        output: Option<&str>,
+        muted: Option<bool>,
+    ) -> Result<Vec<OutputResult>> {
+        let mut results = Vec::new();
+        for name in self.source_targets(output)? {
+            let mute = muted.unwrap_or(!self.muted.contains(&name));
+            if mute {
+                self.muted.insert(name.clone());
+            } else {
+                self.muted.remove(&name);
+            }
+            let message = if mute { "muted" } else { "unmuted" };
+            info!("[VOLUME] {}: {}", name, message);
+            results.push(OutputResult::ok(&name, message));
+        }
+        Ok(results)
+    }
+
+    /// Volume (0-1) videos on `name` play at: `volume`, or 0 while muted
+    pub fn volume(&self, name: &str) -> f64 {
+        let name = self.resolve_mirror(name);
+        if self.muted.contains(name) {
+            return 0.0;
+        }
+        self.outputs
+            .get(name)
+            .map_or(1.0, |o| o.config.volume as f64 / 100.0)
+    }
+
+    /// The `kldctl set` transition for `name`'s switch now in progress, if any
+    pub fn take_transition_override(&mut self, name: &str) -> Option<TransitionOverride> {
+        self.transition_overrides.remove(name)
+    }
+
     pub fn handle_next(
         &mut self,
         output_name: Option<String>,
     ) -> HashMap<String, (PathBuf, crate::queue::ContentType)> {
+        // Kiosk mode has one sequence for every output
+        if let Some(kiosk) = &mut self.kiosk {
+            let item = kiosk.step(true);
+            return self.show_kiosk_item(item);
+        }
+        // Commands aimed at a mirror act on the output it mirrors
+        let output_name = output_name.map(|n| self.resolve_mirror(&n).to_string());
+        let mirrors = self.active_mirrors();
         let mut changes = HashMap::new();
+        let twins = &mut self.twins;
         match &self.config.global.monitor_behavior {
             MonitorBehavior::Independent => {
                 if let Some(name) = output_name {
                     if let Some(orch) = self.outputs.get_mut(&name) {
+                        twins.prepare(&name, orch.queue.as_mut());
                         if let Some(res) = orch.pick_next() {
+                            twins.record(&name, &res.0);
                             changes.insert(name, res);
                         }
                     }
                 } else {
                     for (name, orch) in &mut self.outputs {
+                        twins.prepare(name, orch.queue.as_mut());
                         if let Some(res) = orch.pick_next() {
+                            twins.record(name, &res.0);
                             changes.insert(name.clone(), res);
                         }
                     }
@@ -623,9 +2056,7 @@ impl MonitorManager {
             }
             MonitorBehavior::Synchronized => {
                 if let Some(queue) = &mut self.shared_queue {
-                    if let Some(path) = queue.pick_next() {
-                        let content_type =
-                            crate::queue::SmartQueue::get_content_type(&path).unwrap();
+                    if let Some((path, content_type)) = queue.pick_next_playable() {
                         let now = Instant::now();
 
                         // Pre-buffer next content
@@ -637,7 +2068,7 @@ impl MonitorManager {
 
                         // Reset shared display start time for next cycle
                         self.shared_display_start_time = None;
-                        for (name, orch) in &mut self.outputs {
+                        for (name, orch) in self.synchronized_outputs_mut() {
                             orch.current_path = Some(path.clone());
                             orch.display_start_time = None;
                             orch.next_change = Some(
@@ -657,9 +2088,10 @@ impl MonitorManager {
                     // If target is in a group, advance all in group
                     if let Some(gid) = self.output_groups.get(&target_name).copied() {
                         if let Some(queue) = self.group_queues.get_mut(&gid) {
-                            if let Some(path) = queue.pick_next() {
-                                let content_type =
-                                    crate::queue::SmartQueue::get_content_type(&path).unwrap();
+                            let owner = format!("group.{}", gid);
+                            twins.prepare(&owner, Some(queue));
+                            if let Some((path, content_type)) = queue.pick_next_playable() {
+                                twins.record(&owner, &path);
 
                                 // Pre-buffer next content
                                 let (next_p, next_t) = if let Some((np, nt)) = queue.peek_next() {
@@ -694,7 +2126,9 @@ impl MonitorManager {
                     } else {
                         // Not in a group, treat as independent
                         if let Some(orch) = self.outputs.get_mut(&target_name) {
+                            twins.prepare(&target_name, orch.queue.as_mut());
                             if let Some(res) = orch.pick_next() {
+                                twins.record(&target_name, &res.0);
                                 changes.insert(target_name, res);
                             }
                         }
@@ -705,9 +2139,10 @@ impl MonitorManager {
                     for gid in self.output_groups.values() {
                         if !advanced_groups.contains(gid) {
                             if let Some(queue) = self.group_queues.get_mut(gid) {
-                                if let Some(path) = queue.pick_next() {
-                                    let content_type =
-                                        crate::queue::SmartQueue::get_content_type(&path).unwrap();
+                                let owner = format!("group.{}", gid);
+                                twins.prepare(&owner, Some(queue));
+                                if let Some((path, content_type)) = queue.pick_next_playable() {
+                                    twins.record(&owner, &path);
                                     // Reset group display start time for next cycle
                                     self.group_display_start_times.remove(gid);
                                     for (n, og) in &self.output_groups {
@@ -735,7 +2170,9 @@ impl MonitorManager {
                     // Also handle ungrouped outputs
                     for (name, orch) in &mut self.outputs {
                         if !self.output_groups.contains_key(name) {
+                            twins.prepare(name, orch.queue.as_mut());
                             if let Some(res) = orch.pick_next() {
+                                twins.record(name, &res.0);
                                 changes.insert(name.clone(), res);
                             }
                         }
@@ -743,6 +2180,7 @@ impl MonitorManager {
                 }
             }
         }
+        changes.retain(|name, _| !mirrors.contains(name));
         changes
     }
 
@@ -750,6 +2188,13 @@ impl MonitorManager {
         &mut self,
         output_name: Option<String>,
     ) -> HashMap<String, (PathBuf, crate::queue::ContentType)> {
+        if let Some(kiosk) = &mut self.kiosk {
+            let item = kiosk.step(false);
+            return self.show_kiosk_item(item);
+        }
+        // Commands aimed at a mirror act on the output it mirrors
+        let output_name = output_name.map(|n| self.resolve_mirror(&n).to_string());
+        let mirrors = self.active_mirrors();
         let mut changes = HashMap::new();
         match &self.config.global.monitor_behavior {
             MonitorBehavior::Independent => {
@@ -769,13 +2214,11 @@ impl MonitorManager {
             }
             MonitorBehavior::Synchronized => {
                 if let Some(queue) = &mut self.shared_queue {
-                    if let Some(path) = queue.pick_prev() {
-                        let content_type =
-                            crate::queue::SmartQueue::get_content_type(&path).unwrap();
+                    if let Some((path, content_type)) = queue.pick_prev_playable() {
                         let now = Instant::now();
                         // Reset shared display start time for next cycle
                         self.shared_display_start_time = None;
-                        for (name, orch) in &mut self.outputs {
+                        for (name, orch) in self.synchronized_outputs_mut() {
                             orch.current_path = Some(path.clone());
                             orch.display_start_time = None;
                             orch.next_change = Some(
@@ -790,9 +2233,7 @@ impl MonitorManager {
                 if let Some(target_name) = output_name {
                     if let Some(gid) = self.output_groups.get(&target_name).copied() {
                         if let Some(queue) = self.group_queues.get_mut(&gid) {
-                            if let Some(path) = queue.pick_prev() {
-                                let content_type =
-                                    crate::queue::SmartQueue::get_content_type(&path).unwrap();
+                            if let Some((path, content_type)) = queue.pick_prev_playable() {
                                 // Reset group display start time for next cycle
                                 self.group_display_start_times.remove(&gid);
                                 for (name, og) in &self.output_groups {
@@ -822,9 +2263,7 @@ impl MonitorManager {
                     for gid in self.output_groups.values() {
                         if !prev_groups.contains(gid) {
                             if let Some(queue) = self.group_queues.get_mut(gid) {
-                                if let Some(path) = queue.pick_prev() {
-                                    let content_type =
-                                        crate::queue::SmartQueue::get_content_type(&path).unwrap();
+                                if let Some((path, content_type)) = queue.pick_prev_playable() {
                                     // Reset group display start time for next cycle
                                     self.group_display_start_times.remove(gid);
                                     for (n, og) in &self.output_groups {
@@ -859,89 +2298,494 @@ impl MonitorManager {
                 }
             }
         }
+        changes.retain(|name, _| !mirrors.contains(name));
         changes
     }
 
-    pub fn love_file(&mut self, path: String, multiplier: f32) -> Result<()> {
-        let path = PathBuf::from(path);
-        if let Some(queue) = &mut self.shared_queue {
-            queue.love_file(path.clone(), multiplier)?;
-        }
-        for queue in self.group_queues.values_mut() {
-            queue.love_file(path.clone(), multiplier)?;
-        }
-        for orch in self.outputs.values_mut() {
-            if let Some(queue) = &mut orch.queue {
-                queue.love_file(path.clone(), multiplier)?;
-            }
-        }
-        Ok(())
+    pub fn love_files(&mut self, paths: &[PathBuf], multiplier: f32) -> Result<()> {
+        self.stats.set_love(paths, multiplier)
     }
 
-    pub fn unlove_file(&mut self, path: String) -> Result<()> {
-        self.love_file(path, 1.0)
+    pub fn unlove_files(&mut self, paths: &[PathBuf]) -> Result<()> {
+        self.love_files(paths, 1.0)
     }
 
-    pub fn get_loveitlist(&self) -> Vec<KEntry> {
-        let mut list = HashMap::new();
-        // Consolidate from all queues
-        if let Some(queue) = &self.shared_queue {
-            for (path, stats) in &queue.stats.files {
-                if stats.love_multiplier > 1.0 {
-                    list.insert(
-                        path.to_string_lossy().to_string(),
-                        (stats.love_multiplier, stats.count),
-                    );
-                }
-            }
+    /// Files a bulk love/unlove/blacklist request acts on: `path` and `paths` together,
+    /// or the wallpaper on screen if both are empty (see `target_path`)
+    pub fn target_paths(
+        &self,
+        path: Option<String>,
+        paths: Vec<String>,
+        output: Option<&str>,
+    ) -> Result<(Vec<PathBuf>, Option<String>)> {
+        if paths.is_empty() {
+            let (path, shown_on) = self.target_path(path, output)?;
+            return Ok((vec![path], shown_on));
         }
-        for queue in self.group_queues.values() {
-            for (path, stats) in &queue.stats.files {
-                if stats.love_multiplier > 1.0 {
-                    list.insert(
-                        path.to_string_lossy().to_string(),
-                        (stats.love_multiplier, stats.count),
-                    );
-                }
-            }
+        let paths = path.into_iter().chain(paths).map(PathBuf::from).collect();
+        Ok((paths, None))
+    }
+
+    /// File a love/unlove/blacklist request acts on: `path` if given, otherwise the
+    /// wallpaper on screen. In that case the output it was taken from is returned too,
+    /// so the reply can say which file was picked.
+    pub fn target_path(
+        &self,
+        path: Option<String>,
+        output: Option<&str>,
+    ) -> Result<(PathBuf, Option<String>)> {
+        if let Some(path) = path {
+            return Ok((PathBuf::from(path), None));
         }
-        for orch in self.outputs.values() {
-            if let Some(queue) = &orch.queue {
-                for (path, stats) in &queue.stats.files {
-                    if stats.love_multiplier > 1.0 {
-                        list.insert(
-                            path.to_string_lossy().to_string(),
-                            (stats.love_multiplier, stats.count),
-                        );
-                    }
-                }
+        if let Some(output) = output {
+            let source = self.resolve_mirror(output);
+            let orch = self.outputs.get(source).ok_or_else(|| {
+                CommandError::new(
+                    ErrorCode::UnknownOutput,
+                    format!("Unknown output: {}", output),
+                )
+                .with_outputs(vec![output.to_string()])
+            })?;
+            let current = orch.current_path.clone().ok_or_else(|| {
+                CommandError::new(
+                    ErrorCode::Unavailable,
+                    format!("Nothing is displayed on {}", output),
+                )
+                .with_outputs(vec![output.to_string()])
+            })?;
+            return Ok((current, Some(output.to_string())));
+        }
+
+        let mut shown: Vec<(&String, &PathBuf)> = self
+            .outputs
+            .iter()
+            .filter_map(|(name, orch)| orch.current_path.as_ref().map(|p| (name, p)))
+            .collect();
+        shown.sort();
+        match shown.first() {
+            None => anyhow::bail!(CommandError::new(
+                ErrorCode::Unavailable,
+                "Nothing is displayed"
+            )),
+            Some((name, path)) if shown.iter().all(|(_, p)| p == path) => {
+                Ok(((*path).clone(), Some((*name).clone())))
+            }
+            Some(_) => {
+                let names: Vec<String> = shown.iter().map(|(n, _)| n.to_string()).collect();
+                anyhow::bail!(CommandError::new(
+                    ErrorCode::InvalidArgument,
+                    format!(
+                        "Outputs show different wallpapers, pass --output ({})",
+                        names.join(", ")
+                    )
+                )
+                .with_outputs(names))
             }
         }
-        list.into_iter()
-            .map(|(path, (multiplier, count))| KEntry {
-                path,
-                multiplier,
-                count,
+    }
+
+    pub fn get_loveitlist(&self) -> Vec<KEntry> {
+        self.stats
+            .read()
+            .files
+            .iter()
+            .filter(|(_, stats)| stats.love_multiplier > 1.0)
+            .map(|(path, stats)| KEntry {
+                path: path.to_string_lossy().to_string(),
+                multiplier: stats.love_multiplier,
+                count: stats.count,
             })
             .collect()
     }
 
+    pub fn is_output_enabled(&self, identity: &OutputIdentity) -> bool {
+        self.config.is_output_enabled(identity)
+    }
+
     pub fn get_output_config(&self, name: &str) -> Option<&OutputConfig> {
         self.outputs.get(name).map(|o| &o.config)
     }
 
-    /// Mark that transition has completed for an output (called when transition progress >= 1.0)
-    /// For synchronized mode, uses shared display start time (first output to complete)
-    /// For grouped mode, uses group display start time (first output in group to complete)
-    /// For independent mode, each output has its own display start time
-    pub fn mark_transition_completed(&mut self, name: &str) {
+    /// Notes what `name` is now showing, for `restore-last` on the next start. Written
+    /// with the next stats flush, so switching never waits for the database.
+    pub fn remember_wallpaper(
+        &mut self,
+        name: &str,
+        path: &std::path::Path,
+        content_type: crate::queue::ContentType,
+    ) {
+        self.unsaved_wallpapers
+            .insert(name.to_string(), (path.to_path_buf(), content_type));
+    }
+
+    /// What `name` showed last: noted since the last flush, or else saved
+    fn remembered_wallpaper(
+        &self,
+        name: &str,
+    ) -> Result<Option<(PathBuf, crate::queue::ContentType)>> {
+        match self.unsaved_wallpapers.get(name) {
+            Some(wallpaper) => Ok(Some(wallpaper.clone())),
+            None => self.cache.get_last_wallpaper(name),
+        }
+    }
+
+    pub fn restore_last(&self) -> bool {
+        self.config.global.restore_last
+    }
+
+    pub fn idle_trim(&self) -> std::time::Duration {
+        self.config.global.idle_trim
+    }
+
+    /// Where `name`'s settled frames are written for lock screens (`lock-image`), if
+    /// anywhere
+    pub fn lock_image(&self, name: &str) -> Option<PathBuf> {
+        let template = self.config.global.lock_image.as_deref()?;
+        let path = template.replace("{output}", name);
+        Some(match path.strip_prefix("~/") {
+            Some(rest) => dirs::home_dir().unwrap_or_default().join(rest),
+            None => PathBuf::from(path),
+        })
+    }
+
+    pub fn x11_mode(&self) -> crate::orchestration::X11Mode {
+        self.config.global.x11_mode
+    }
+
+    /// Main loop period while something animates: `max-fps` (default 60), or a slow loop
+    /// for the e-ink profile where nothing does
+    pub fn frame_time(&self) -> std::time::Duration {
+        let global = &self.config.global;
+        if global.profile == crate::orchestration::Profile::Eink {
+            return std::time::Duration::from_millis(250);
+        }
+        let fps = global.max_fps.filter(|&fps| fps > 0).unwrap_or(60).min(240);
+        std::time::Duration::from_secs(1) / fps
+    }
+
+    /// Main loop period while nothing animates: `frame_time`, at least `min`. The e-ink
+    /// profile, whose switches are far apart, sleeps until the next one is due instead
+    /// (up to `EINK_IDLE_TICK`).
+    pub fn idle_frame_time(&self, min: Duration) -> Duration {
+        let frame_time = min.max(self.frame_time());
+        if self.config.global.profile != crate::orchestration::Profile::Eink {
+            return frame_time;
+        }
         let now = Instant::now();
+        self.outputs
+            .values()
+            .filter_map(|o| o.next_change)
+            .min()
+            .map_or(EINK_IDLE_TICK, |next| next.saturating_duration_since(now))
+            .clamp(frame_time, EINK_IDLE_TICK)
+    }
 
-        match &self.config.global.monitor_behavior {
-            MonitorBehavior::Synchronized => {
-                // For synchronized mode, use shared display start time
-                // Set it when first output completes transition
-                if self.shared_display_start_time.is_none() {
+    /// What `transition = "random"` resolves to: any builtin or `[transition.NAME]`
+    /// definition, all equally likely
+    pub fn pick_random_transition(&self) -> crate::shaders::Transition {
+        use rand::Rng;
+        let builtins = kaleidux_common::RANDOM_TRANSITIONS;
+        let named = &self.config.transitions;
+        let index = rand::thread_rng().gen_range(0..builtins.len() + named.len());
+        match index
+            .checked_sub(builtins.len())
+            .and_then(|i| named.values().nth(i))
+        {
+            Some(transition) => transition.clone(),
+            None => crate::shaders::Transition::from_name(builtins[index]),
+        }
+    }
+
+    /// What `transition = "auto"` resolves to for `name` switching to `path`, with
+    /// whether it is a cut (no transition at all). None when the pick depends on the
+    /// incoming image's tone, which `tone_measured` supplies once it is decoded.
+    pub fn pick_auto_transition(
+        &mut self,
+        name: &str,
+        path: &std::path::Path,
+        content_type: crate::queue::ContentType,
+    ) -> Option<(crate::shaders::Transition, bool)> {
+        use crate::tone::Look;
+        let to = if content_type.is_moving() {
+            Look::Video
+        } else {
+            Look::Image(self.cache.get_tone(path).ok().flatten())
+        };
+        let from = self.looks.insert(name.to_string(), to);
+        self.auto_pending.remove(name);
+        match crate::tone::pick(&self.config.auto_transition, from, to) {
+            Some(pick) => Some(self.named_transition(pick)),
+            None => {
+                self.auto_pending.insert(name.to_string(), from);
+                None
+            }
+        }
+    }
+
+    /// Records the tone of the image `name` just decoded from `path`; returns the auto
+    /// transition if its switch was waiting for it
+    pub fn tone_measured(
+        &mut self,
+        name: &str,
+        path: &Path,
+        tone: crate::tone::Tone,
+    ) -> Option<(crate::shaders::Transition, bool)> {
+        self.tones
+            .insert(name.to_string(), (path.to_path_buf(), tone));
+        let to = crate::tone::Look::Image(Some(tone));
+        self.looks.insert(name.to_string(), to);
+        let from = self.auto_pending.remove(name)?;
+        let pick = crate::tone::pick(&self.config.auto_transition, from, to)
+            .unwrap_or(&self.config.auto_transition.default)
+            .to_string();
+        Some(self.named_transition(&pick))
+    }
+
+    /// A transition named in `[auto-transition]`: `cut`, `random`, a `[transition.NAME]`
+    /// definition or a builtin
+    fn named_transition(&self, name: &str) -> (crate::shaders::Transition, bool) {
+        if let Some(transition) = self.config.transitions.get(name) {
+            return (transition.clone(), false);
+        }
+        match name.to_lowercase().as_str() {
+            "cut" => (crate::shaders::Transition::Fade, true),
+            "random" => (self.pick_random_transition(), false),
+            builtin => (crate::shaders::Transition::from_name(builtin), false),
+        }
+    }
+
+    /// Whether decoded images are measured for `publish-state`
+    pub fn publish_state(&self) -> bool {
+        self.config.global.publish_state
+    }
+
+    /// Whether decoded images are measured at all: for `publish-state`, or for an output
+    /// on `transition = "auto"`. Asked for every output alike, as outputs switching to
+    /// the same file share one decode.
+    pub fn measures_tones(&self) -> bool {
+        self.publish_state()
+            || self
+                .outputs
+                .values()
+                .any(|o| matches!(o.config.transition, crate::shaders::Transition::Auto))
+    }
+
+    /// What `publish-state` shows of every output (mirrors with their source's content),
+    /// None while it is off
+    pub fn state(&self) -> Option<crate::state::State> {
+        if !self.config.global.publish_state {
+            return None;
+        }
+        let outputs = self
+            .outputs
+            .keys()
+            .map(|name| {
+                let source = self.resolve_mirror(name);
+                let path = self
+                    .outputs
+                    .get(source)
+                    .and_then(|o| o.current_path.as_deref());
+                let tone = self
+                    .tones
+                    .get(source)
+                    .filter(|(measured, _)| Some(measured.as_path()) == path)
+                    .map(|(_, tone)| tone);
+                let state = crate::state::OutputState::new(
+                    path,
+                    path.and_then(SmartQueue::get_content_type),
+                    tone,
+                    self.failover_error(name),
+                );
+                (name.clone(), state)
+            })
+            .collect();
+        Some(crate::state::State {
+            version: crate::state::SCHEMA_VERSION,
+            pid: std::process::id(),
+            outputs,
+        })
+    }
+
+    /// Configured `cache-max-size` in bytes (None if unset or invalid)
+    pub fn cache_limit(&self) -> Option<u64> {
+        let size = self.config.global.cache_max_size.as_deref()?;
+        match crate::cache::parse_size(size) {
+            Ok(limit) => Some(limit),
+            Err(e) => {
+                warn!("[CACHE] Ignoring cache-max-size: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Cache files backing what is on screen now, which garbage collection must keep,
+    /// and the `lock-image` files of the outputs in case they are below the cache root
+    pub fn cache_in_use(&self) -> std::collections::HashSet<PathBuf> {
+        let mut keep = std::collections::HashSet::new();
+        for (name, orchestrator) in &self.outputs {
+            keep.extend(self.lock_image(name));
+            let Some(path) = &orchestrator.current_path else {
+                continue;
+            };
+            if let Some(snapshot) = crate::cache::snapshot_path(name, path) {
+                keep.insert(snapshot);
+            }
+            let command = orchestrator.config.preprocess.as_deref();
+            if let Some(artifact) = crate::preprocess::existing(path, command) {
+                keep.insert(artifact);
+            }
+        }
+        keep
+    }
+
+    /// The wallpaper `name` showed when the daemon last ran, if restoring is enabled
+    /// and the file still exists.
+    pub fn last_wallpaper(&self, name: &str) -> Option<(PathBuf, crate::queue::ContentType)> {
+        if !self.restore_last() {
+            return None;
+        }
+        match self.remembered_wallpaper(name) {
+            Ok(Some((path, content_type))) if path.exists() => Some((path, content_type)),
+            Ok(_) => None,
+            Err(e) => {
+                warn!("[RESTORE] {}: Failed to read last wallpaper: {}", name, e);
+                None
+            }
+        }
+    }
+
+    /// The output whose content `name` displays: itself, or the end of its `mirror` chain.
+    /// A mirror whose source isn't connected falls back to its own queue, and so does
+    /// one whose chain loops back on itself.
+    pub fn resolve_mirror<'a>(&'a self, name: &'a str) -> &'a str {
+        let (chain, looped) = self.mirror_chain(name);
+        if looped {
+            name
+        } else {
+            chain[chain.len() - 1]
+        }
+    }
+
+    /// `name` followed by the connected outputs its `mirror` chain passes through, and
+    /// whether the chain ends by coming back to an output already in it
+    fn mirror_chain<'a>(&'a self, name: &'a str) -> (Vec<&'a str>, bool) {
+        let mut chain = vec![name];
+        while let Some(source) = self
+            .outputs
+            .get(chain[chain.len() - 1])
+            .and_then(|o| o.config.mirror.as_deref())
+            .filter(|source| self.outputs.contains_key(*source))
+        {
+            if chain.contains(&source) {
+                chain.push(source);
+                return (chain, true);
+            }
+            chain.push(source);
+        }
+        (chain, false)
+    }
+
+    /// Outputs drawing from the synchronized shared queue (all but static ones)
+    fn synchronized_outputs(&self) -> impl Iterator<Item = &OutputOrchestrator> {
+        self.outputs.values().filter(|o| o.static_file.is_none())
+    }
+
+    fn synchronized_outputs_mut(
+        &mut self,
+    ) -> impl Iterator<Item = (&String, &mut OutputOrchestrator)> {
+        self.outputs
+            .iter_mut()
+            .filter(|(_, o)| o.static_file.is_none())
+    }
+
+    /// Static file `path` changed on disk: outputs showing it transition to the new
+    /// version on the next tick
+    pub fn static_file_changed(&mut self, path: &Path) {
+        for (name, orch) in &mut self.outputs {
+            if orch.static_file.as_deref() == Some(path) {
+                info!("[STATIC] {}: {:?} changed on disk, reloading", name, path);
+                orch.current_path = None;
+                orch.display_start_time = None;
+            }
+        }
+    }
+
+    /// Files were added to, removed from or renamed in a library: every queue takes
+    /// them in without a reload. Deleted files also leave the blacklist and quarantine,
+    /// renamed ones keep their love and entries there.
+    pub fn library_changed(&mut self, changes: &crate::cache::LibraryChanges) {
+        debug!(
+            "[WATCH] Library changed: {} added, {} removed, {} renamed",
+            changes.added.len(),
+            changes.removed.len(),
+            changes.renamed.len()
+        );
+        for (from, to) in &changes.renamed {
+            if let Err(e) = self.stats.rename(from, to) {
+                warn!(
+                    "[WATCH] Failed to move stats of {:?} to {:?}: {}",
+                    from, to, e
+                );
+            }
+        }
+        if let Err(e) = self.stats.forget(&changes.removed) {
+            warn!("[WATCH] Failed to forget deleted files: {}", e);
+        }
+        // File lists kept for outputs plugged in later are stale now
+        let changed = |root: &PathBuf| {
+            changes
+                .added
+                .iter()
+                .chain(&changes.removed)
+                .any(|p| p.starts_with(root))
+        };
+        self.discovered_files_cache.retain(|root, _| !changed(root));
+        self.apply_to_all_queues(|q| {
+            q.apply_library_changes(changes);
+            Ok(())
+        });
+    }
+
+    /// All outputs currently showing another output's content
+    fn active_mirrors(&self) -> std::collections::HashSet<String> {
+        self.outputs
+            .keys()
+            .filter(|n| self.resolve_mirror(n) != n.as_str())
+            .cloned()
+            .collect()
+    }
+
+    /// Outputs currently mirroring `source` (directly or through a chain), except
+    /// cleared ones
+    pub fn mirrors_of(&self, source: &str) -> Vec<String> {
+        self.outputs
+            .keys()
+            .filter(|n| n.as_str() != source && self.resolve_mirror(n) == source)
+            .filter(|n| !self.cleared.contains(*n))
+            .cloned()
+            .collect()
+    }
+
+    /// Mark that transition has completed for an output (called when transition progress >= 1.0)
+    /// For synchronized mode, uses shared display start time (first output to complete)
+    /// For grouped mode, uses group display start time (first output in group to complete)
+    /// For independent mode, each output has its own display start time
+    pub fn mark_transition_completed(&mut self, name: &str) {
+        let now = Instant::now();
+        // Static outputs have no timer and must not start the shared or group one
+        if self
+            .outputs
+            .get(name)
+            .is_some_and(|o| o.static_file.is_some())
+        {
+            return;
+        }
+
+        match &self.config.global.monitor_behavior {
+            MonitorBehavior::Synchronized => {
+                // For synchronized mode, use shared display start time
+                // Set it when first output completes transition
+                if self.shared_display_start_time.is_none() {
                     self.shared_display_start_time = Some(now);
                     debug!("Synchronized mode: First output ({}) completed transition - shared display timer started", name);
                 }
@@ -979,102 +2823,522 @@ impl MonitorManager {
     }
 
     pub fn handle_playlist_command(&mut self, cmd: PlaylistCommand) -> Response {
-        match cmd {
+        // Playlists live in the shared stats store, so edits happen once; only loading
+        // one touches the individual queues
+        let result = match cmd {
             PlaylistCommand::Create { name } => {
                 let playlist = Playlist {
                     paths: Vec::new(),
                     strategy: crate::orchestration::SortingStrategy::Loveit,
                     enabled: true,
                 };
-                self.apply_to_all_queues(|q| {
-                    q.stats.playlists.insert(name.clone(), playlist.clone());
-                    q.save_stats()
-                });
-                Response::Ok
-            }
-            PlaylistCommand::Delete { name } => {
-                self.apply_to_all_queues(|q| {
-                    q.stats.playlists.remove(&name);
-                    q.save_stats()
-                });
-                Response::Ok
+                self.stats.create_playlist(&name, playlist)
             }
+            PlaylistCommand::Delete { name } => self.stats.delete_playlist(&name),
             PlaylistCommand::Add { name, path } => {
                 let path_buf = PathBuf::from(path);
-                self.apply_to_all_queues(|q| {
-                    if let Some(pl) = q.stats.playlists.get_mut(&name) {
+                self.stats
+                    .edit_playlist(&name, |pl| {
                         if !pl.paths.contains(&path_buf) {
                             pl.paths.push(path_buf.clone());
                         }
-                    }
-                    q.save_stats()
-                });
-                Response::Ok
+                    })
+                    .and_then(|found| playlist_found(found, &name))
             }
             PlaylistCommand::Remove { name, path } => {
                 let path_buf = PathBuf::from(path);
-                self.apply_to_all_queues(|q| {
-                    if let Some(pl) = q.stats.playlists.get_mut(&name) {
-                        pl.paths.retain(|p| p != &path_buf);
-                    }
-                    q.save_stats()
-                });
-                Response::Ok
+                self.stats
+                    .edit_playlist(&name, |pl| pl.paths.retain(|p| p != &path_buf))
+                    .and_then(|found| playlist_found(found, &name))
             }
             PlaylistCommand::Load { name } => {
                 let mut error = None;
                 self.apply_to_all_queues(|q| {
                     if let Err(e) = q.set_playlist(name.clone()) {
-                        error = Some(e.to_string());
-                        let _ = q.save_stats(); // Save active playlist state? SmartQueue doesn't persist active_playlist yet
-                        Err(e)
-                    } else {
-                        q.save_stats()
+                        error.get_or_insert(e);
                     }
+                    Ok(())
                 });
-                if let Some(e) = error {
-                    Response::Error(e)
-                } else {
-                    Response::Ok
+                match error {
+                    Some(e) => Err(e),
+                    None => Ok(()),
                 }
             }
             PlaylistCommand::List => {
-                // Get from first available queue
-                if let Some(q) = self.get_any_queue() {
-                    let names: Vec<String> = q.stats.playlists.keys().cloned().collect();
-                    Response::Playlists(names)
-                } else {
-                    Response::Playlists(Vec::new())
-                }
+                let names: Vec<String> = self.stats.read().playlists.keys().cloned().collect();
+                return Response::Playlists(names);
             }
+            PlaylistCommand::Import { name, file, append } => {
+                return match self.import_playlist(&name, Path::new(&file), append) {
+                    Ok(report) => Response::PlaylistImport(report),
+                    Err(e) => error_response(e),
+                };
+            }
+            PlaylistCommand::Export { name, file } => {
+                let paths = self
+                    .stats
+                    .read()
+                    .playlists
+                    .get(&name)
+                    .map(|p| p.paths.clone());
+                playlist_found(paths.is_some(), &name).and_then(|()| {
+                    crate::playlist_file::write(Path::new(&file), &paths.unwrap_or_default())
+                })
+            }
+            PlaylistCommand::Show { name } => {
+                let Some(playlist) = self.stats.read().playlists.get(&name).cloned() else {
+                    return Response::error(
+                        ErrorCode::NotFound,
+                        format!("Playlist '{}' not found", name),
+                    );
+                };
+                let rule = self.config.playlists.get(&name);
+                let mut loaded_on: Vec<String> = self
+                    .outputs
+                    .keys()
+                    .filter(|output| {
+                        self.queue_for(output)
+                            .is_some_and(|q| q.active_playlist.as_ref() == Some(&name))
+                    })
+                    .cloned()
+                    .collect();
+                loaded_on.sort();
+                return Response::Playlist(kaleidux_common::PlaylistInfo {
+                    paths: playlist
+                        .paths
+                        .iter()
+                        .map(|p| p.display().to_string())
+                        .collect(),
+                    schedule: rule.map(|r| r.describe()).unwrap_or_default(),
+                    schedule_outputs: rule.map(|r| r.outputs.clone()).unwrap_or_default(),
+                    loaded_on,
+                    name,
+                });
+            }
+        };
+        match result {
+            Ok(()) => Response::Ok,
+            Err(e) => error_response(e),
         }
     }
 
     pub fn handle_blacklist_command(&mut self, cmd: BlacklistCommand) -> Response {
         match cmd {
-            BlacklistCommand::Add { path } => {
-                let path_buf = PathBuf::from(path);
-                self.apply_to_all_queues(|q| q.blacklist_file(path_buf.clone()));
-                Response::Ok
+            BlacklistCommand::Add {
+                path,
+                paths,
+                output,
+            } => {
+                let (paths, shown_on) = match self.target_paths(path, paths, output.as_deref()) {
+                    Ok(target) => target,
+                    Err(e) => return error_response(e),
+                };
+                self.apply_to_all_queues(|q| q.blacklist_files(&paths));
+                target_response(shown_on, "blacklisted", &paths)
             }
-            BlacklistCommand::Remove { path } => {
-                let path_buf = PathBuf::from(path);
-                self.apply_to_all_queues(|q| q.unblacklist_file(path_buf.clone()));
-                Response::Ok
+            BlacklistCommand::Remove { path, paths } => {
+                let paths: Vec<PathBuf> =
+                    path.into_iter().chain(paths).map(PathBuf::from).collect();
+                if paths.is_empty() {
+                    return Response::error(ErrorCode::InvalidArgument, "No path given");
+                }
+                self.apply_to_all_queues(|q| q.unblacklist_files(&paths));
+                target_response(None, "unblacklisted", &paths)
             }
             BlacklistCommand::List => {
-                if let Some(q) = self.get_any_queue() {
-                    let paths: Vec<String> = q
-                        .stats
-                        .blacklist
-                        .iter()
-                        .map(|p| p.to_string_lossy().to_string())
-                        .collect();
-                    Response::Blacklist(paths)
-                } else {
-                    Response::Blacklist(Vec::new())
+                let paths: Vec<String> = self
+                    .stats
+                    .read()
+                    .blacklist
+                    .iter()
+                    .map(|p| p.to_string_lossy().to_string())
+                    .collect();
+                Response::Blacklist(paths)
+            }
+        }
+    }
+
+    pub fn handle_tag_command(&mut self, cmd: TagCommand) -> Response {
+        let (path, paths, output, tags, add) = match cmd {
+            TagCommand::Add {
+                path,
+                paths,
+                output,
+                tags,
+            } => (path, paths, output, tags, true),
+            TagCommand::Remove {
+                path,
+                paths,
+                output,
+                tags,
+            } => (path, paths, output, tags, false),
+            TagCommand::List { tag } => {
+                let tag = tag.map(|t| t.trim().to_lowercase());
+                let mut files: std::collections::BTreeMap<&String, Vec<String>> =
+                    std::collections::BTreeMap::new();
+                let stats = self.stats.read();
+                for (path, tags) in &stats.tags {
+                    for name in tags.iter().filter(|t| tag.as_ref().is_none_or(|n| n == *t)) {
+                        files
+                            .entry(name)
+                            .or_default()
+                            .push(path.to_string_lossy().to_string());
+                    }
+                }
+                let tags = files
+                    .into_iter()
+                    .map(|(name, mut files)| {
+                        files.sort();
+                        TagInfo {
+                            name: name.clone(),
+                            files,
+                        }
+                    })
+                    .collect();
+                return Response::Tags(tags);
+            }
+        };
+        let tags = parse_tags(&tags);
+        if tags.is_empty() {
+            return Response::error(ErrorCode::InvalidArgument, "No tag given");
+        }
+        let (paths, shown_on) = match self.target_paths(path, paths, output.as_deref()) {
+            Ok(target) => target,
+            Err(e) => return error_response(e),
+        };
+        if let Err(e) = self.stats.set_tags(&paths, &tags, add) {
+            return error_response(e);
+        }
+        let action = if add { "tagged" } else { "untagged" };
+        target_response(shown_on, action, &paths)
+    }
+
+    /// Limits the picks of the next `next` to files carrying every one of `tags`; an
+    /// empty list lifts it. Fails if no file carries them all.
+    pub fn set_once_tags(&mut self, tags: &[String]) -> Result<()> {
+        let tagged = tags.is_empty()
+            || self
+                .stats
+                .read()
+                .tags
+                .values()
+                .any(|tagged| tags.iter().all(|tag| tagged.contains(tag)));
+        if !tagged {
+            anyhow::bail!(CommandError::new(
+                ErrorCode::NotFound,
+                format!("No file is tagged {}", tags.join(", "))
+            ));
+        }
+        self.apply_to_all_queues(|q| {
+            q.once_tags = tags.to_vec();
+            Ok(())
+        });
+        Ok(())
+    }
+
+    pub fn handle_quarantine_command(&mut self, cmd: QuarantineCommand) -> Response {
+        let paths: Vec<PathBuf> = match cmd {
+            QuarantineCommand::List => {
+                let mut entries: Vec<QuarantineEntry> = self
+                    .stats
+                    .read()
+                    .quarantine
+                    .iter()
+                    .map(|(path, record)| QuarantineEntry {
+                        path: path.to_string_lossy().to_string(),
+                        failures: record.failures,
+                        error: record.error.clone(),
+                        since: record.since,
+                    })
+                    .collect();
+                entries.sort_by_key(|e| e.since);
+                return Response::Quarantine(entries);
+            }
+            QuarantineCommand::Remove { path } => vec![PathBuf::from(path)],
+            QuarantineCommand::Clear => self.stats.read().quarantine.keys().cloned().collect(),
+        };
+
+        let mut released = Vec::new();
+        for path in paths {
+            match self.stats.release(&path) {
+                Ok(true) => released.push(path),
+                Ok(false) => {}
+                Err(e) => return error_response(e),
+            }
+        }
+        if released.is_empty() {
+            return Response::error(ErrorCode::NotFound, "Not quarantined");
+        }
+        info!("[QUARANTINE] Released {} file(s)", released.len());
+        self.apply_to_all_queues(|q| q.restore_files(&released));
+        Response::Ok
+    }
+
+    /// Handles content of `name` that failed to decode or start: counts the failure
+    /// against the file (quarantining it after `quarantine-after` failures) and
+    /// schedules a fresh pick for the output. The first retry is immediate; if the
+    /// output keeps failing, retries back off exponentially up to a minute.
+    pub fn record_load_failure(&mut self, name: &str, path: &Path, error: &str) {
+        // A failure for content the output has already moved on from changes nothing
+        if self.outputs.get(name).and_then(|o| o.current_path.as_deref()) != Some(path) {
+            debug!("[RETRY] {}: Ignoring stale failure of {:?}", name, path);
+            return;
+        }
+        if let Some(m) = &self.metrics {
+            m.record_output_error(name, "load_failed");
+        }
+        // Outputs sharing a queue fail on the same pick; count and retry it once
+        let leader = self.queue_leader(name);
+        if self
+            .load_retries
+            .get(&leader)
+            .is_some_and(|r| r.due.is_some() && r.path == path)
+        {
+            return;
+        }
+
+        let quarantine_after = self.config.global.quarantine_after;
+        match self.stats.record_failure(path, error, quarantine_after) {
+            Ok(true) => {
+                warn!(
+                    "[QUARANTINE] {:?} failed to load {} times, quarantined (last error: {})",
+                    path, quarantine_after, error
+                );
+                self.apply_to_all_queues(|q| {
+                    q.remove_file(path);
+                    Ok(())
+                });
+            }
+            Ok(false) => {}
+            Err(e) => warn!("[QUARANTINE] Failed to quarantine {:?}: {}", path, e),
+        }
+
+        let retry = self.load_retries.entry(leader.clone()).or_insert(LoadRetry {
+            attempts: 0,
+            path: PathBuf::new(),
+            due: None,
+        });
+        let delay = match retry.attempts {
+            0 => Duration::ZERO,
+            n => Duration::from_secs(1 << (n - 1).min(6)).min(MAX_RETRY_BACKOFF),
+        };
+        retry.attempts += 1;
+        retry.path = path.to_path_buf();
+        retry.due = Some(Instant::now() + delay);
+        info!(
+            "[RETRY] {}: {:?} failed to load, picking another in {:?}",
+            leader, path, delay
+        );
+    }
+
+    /// Output whose name stands for the queue `name` draws from: itself if it has its
+    /// own queue, otherwise the first (by name) output sharing its group or shared queue
+    fn queue_leader(&self, name: &str) -> String {
+        // The kiosk sequence is one queue for every output
+        if self.kiosk.is_some() {
+            if let Some(first) = self.outputs.keys().min() {
+                return first.clone();
+            }
+        }
+        if self
+            .outputs
+            .get(name)
+            .is_some_and(|o| o.queue.is_some() || o.static_file.is_some())
+        {
+            return name.to_string();
+        }
+        let group = self.output_groups.get(name).copied();
+        self.outputs
+            .iter()
+            .filter(|(n, o)| {
+                o.queue.is_none()
+                    && o.static_file.is_none()
+                    && self.output_groups.get(*n).copied() == group
+            })
+            .map(|(n, _)| n)
+            .min()
+            .cloned()
+            .unwrap_or_else(|| name.to_string())
+    }
+
+    /// Content of `name` loaded; resets its retry backoff and the file's failure count
+    pub fn record_load_success(&mut self, name: &str, path: &Path) {
+        let leader = self.queue_leader(name);
+        self.load_retries.remove(&leader);
+        self.stats.record_success(path);
+    }
+
+    /// Outputs whose retry after a failed load is due. Held while cycling is paused.
+    pub fn due_retries(&mut self) -> Vec<String> {
+        if self.paused {
+            return Vec::new();
+        }
+        let now = Instant::now();
+        let paused_outputs = &self.paused_outputs;
+        let mut due: Vec<String> = self
+            .load_retries
+            .iter_mut()
+            .filter(|(name, r)| r.due.is_some_and(|d| d <= now) && !paused_outputs.contains(*name))
+            .map(|(name, r)| {
+                r.due = None;
+                name.clone()
+            })
+            .collect();
+        due.retain(|name| self.outputs.contains_key(name) && !self.cleared.contains(name));
+        due.sort();
+        due
+    }
+
+    /// `kldctl playlist import`: fills playlist `name` (creating it) from a list file and
+    /// reloads it in the queues that have it loaded. Nothing changes if no entry of the
+    /// file resolves to a wallpaper.
+    fn import_playlist(
+        &mut self,
+        name: &str,
+        file: &Path,
+        append: bool,
+    ) -> Result<kaleidux_common::PlaylistImportReport> {
+        let import = crate::playlist_file::read(file)
+            .map_err(|e| CommandError::new(ErrorCode::NotFound, format!("{:#}", e)))?;
+        if import.paths.is_empty() {
+            anyhow::bail!(CommandError::new(
+                ErrorCode::InvalidArgument,
+                format!("No wallpapers found in {}", file.display())
+            ));
+        }
+        let mut added = 0;
+        let mut total = 0;
+        let mut fill = |playlist: &mut Playlist| {
+            if !append {
+                playlist.paths.clear();
+            }
+            for path in &import.paths {
+                if !playlist.paths.contains(path) {
+                    playlist.paths.push(path.clone());
+                    added += 1;
                 }
             }
+            total = playlist.paths.len();
+        };
+        if !self.stats.edit_playlist(name, &mut fill)? {
+            let mut playlist = Playlist {
+                paths: Vec::new(),
+                strategy: crate::orchestration::SortingStrategy::Loveit,
+                enabled: true,
+            };
+            fill(&mut playlist);
+            self.stats.create_playlist(name, playlist)?;
+        }
+        self.apply_to_all_queues(|q| {
+            if q.active_playlist.as_deref() == Some(name) {
+                q.set_playlist(Some(name.to_string()))?;
+            }
+            Ok(())
+        });
+        info!(
+            "[PLAYLIST] Imported {} files into {} from {} ({} skipped)",
+            added,
+            name,
+            file.display(),
+            import.skipped.len()
+        );
+        Ok(kaleidux_common::PlaylistImportReport {
+            name: name.to_string(),
+            added,
+            total,
+            skipped: import.skipped,
+        })
+    }
+
+    /// Starts a `[fetch]` pull (`kldctl fetch now`); `reply` gets its report once
+    /// `poll_fetch` sees it finish
+    pub fn fetch_now(&mut self, reply: tokio::sync::oneshot::Sender<Response>) {
+        let Some(config) = &self.config.fetch else {
+            let _ = reply.send(Response::error(
+                ErrorCode::Unavailable,
+                "No [fetch] section in the config",
+            ));
+            return;
+        };
+        self.fetcher.start(config, &self.config.remote, Some(reply));
+    }
+
+    /// Puts what the last `[fetch]` pull left in the fetch folder into its playlist,
+    /// answering `kldctl fetch now`, and starts the next pull when it is due
+    pub fn poll_fetch(&mut self) {
+        let Some((result, waiting)) = self
+            .fetcher
+            .poll(self.config.fetch.as_ref(), &self.config.remote)
+        else {
+            return;
+        };
+        let report = result.and_then(|pulled| self.store_fetched(pulled));
+        match &report {
+            Ok(report) => info!(
+                "[FETCH] {} new from {} in {} ({} pruned, {} total)",
+                report.added, report.provider, report.playlist, report.removed, report.total
+            ),
+            Err(e) => warn!("[FETCH] Pull failed: {:#}", e),
+        }
+        for reply in waiting {
+            let _ = reply.send(match &report {
+                Ok(report) => Response::Fetch(report.clone()),
+                Err(e) => Response::error(ErrorCode::Failed, format!("{:#}", e)),
+            });
+        }
+    }
+
+    fn store_fetched(
+        &mut self,
+        pulled: crate::fetch::Pulled,
+    ) -> Result<kaleidux_common::FetchReport> {
+        let name = pulled.playlist.as_str();
+        let fill = |playlist: &mut Playlist| playlist.paths = pulled.paths.clone();
+        if !self.stats.edit_playlist(name, fill)? {
+            let mut playlist = Playlist {
+                paths: Vec::new(),
+                strategy: crate::orchestration::SortingStrategy::Loveit,
+                enabled: true,
+            };
+            fill(&mut playlist);
+            self.stats.create_playlist(name, playlist)?;
+        }
+        if !pulled.removed.is_empty() {
+            self.stats.forget(&pulled.removed)?;
+        }
+        self.apply_to_all_queues(|q| {
+            if q.active_playlist.as_deref() == Some(name) {
+                q.set_playlist(Some(name.to_string()))?;
+            }
+            Ok(())
+        });
+        self.delete_files(&pulled.removed);
+        Ok(kaleidux_common::FetchReport {
+            provider: pulled.provider.name().to_string(),
+            playlist: pulled.playlist.clone(),
+            added: pulled.added,
+            removed: pulled.removed.len(),
+            total: pulled.paths.len(),
+        })
+    }
+
+    /// Drops `paths` from every queue, then deletes them: downloads a URL source no
+    /// longer lists and fetched wallpapers past their limits. In this order so no pick
+    /// lands on a file that is already gone.
+    pub fn delete_files(&mut self, paths: &[PathBuf]) {
+        if paths.is_empty() {
+            return;
+        }
+        self.apply_to_all_queues(|q| {
+            for path in paths {
+                q.remove_file(path);
+            }
+            Ok(())
+        });
+        for path in paths {
+            if let Err(e) = std::fs::remove_file(path) {
+                warn!("[REMOTE] Failed to delete {:?}: {}", path, e);
+            }
         }
     }
 
@@ -1095,35 +3359,605 @@ impl MonitorManager {
         }
     }
 
-    /// Flush pending stats updates from all queues (batched write)
+    /// Re-evaluates `[playlist.NAME]` rules and loads the scheduled playlist (or the
+    /// output's default content once no rule is active) into queues whose schedule
+    /// changed since the last check. The current wallpaper keeps its remaining time.
+    pub fn apply_playlist_schedule(&mut self) {
+        if self.config.playlists.is_empty() && self.scheduled_playlists.is_empty() {
+            return;
+        }
+        let now = chrono::Local::now().naive_local();
+        let mut names: Vec<String> = self.outputs.keys().cloned().collect();
+        names.sort();
+
+        // Outputs sharing a queue follow the first of them by name
+        let mut handled_groups = std::collections::HashSet::new();
+        let mut handled_shared = false;
+        for name in names {
+            let Some(orch) = self.outputs.get(&name) else {
+                continue;
+            };
+            let wanted = self
+                .config
+                .scheduled_playlist(&orch.identity, now)
+                .or_else(|| orch.config.default_playlist.clone());
+            let owns_queue = orch.queue.is_some();
+            let group = self.output_groups.get(&name).copied();
+            if !owns_queue {
+                let first = match group {
+                    Some(gid) => handled_groups.insert(gid),
+                    None => !std::mem::replace(&mut handled_shared, true),
+                };
+                if !first {
+                    continue;
+                }
+            }
+            if self.scheduled_playlists.get(&name) == Some(&wanted) {
+                continue;
+            }
+            self.scheduled_playlists.insert(name.clone(), wanted.clone());
+            // A workspace playlist holds; leaving the workspace restores this one
+            if self
+                .workspace_bindings
+                .get(&name)
+                .is_some_and(|rule| rule.playlist.is_some())
+            {
+                continue;
+            }
+
+            let queue = if owns_queue {
+                self.outputs.get_mut(&name).and_then(|o| o.queue.as_mut())
+            } else if let Some(gid) = group {
+                self.group_queues.get_mut(&gid)
+            } else {
+                self.shared_queue.as_mut()
+            };
+            let Some(queue) = queue else {
+                continue;
+            };
+            if queue.active_playlist == wanted {
+                continue;
+            }
+            match queue.set_playlist(wanted.clone()) {
+                Ok(()) => info!(
+                    "[PLAYLIST] {}: Scheduled switch to {}",
+                    name,
+                    wanted.as_deref().unwrap_or("default content")
+                ),
+                Err(e) => warn!(
+                    "[PLAYLIST] {}: Failed to load scheduled playlist {:?}: {}",
+                    name, wanted, e
+                ),
+            }
+        }
+    }
+
+    /// Why `name` has nothing to play, if so. Kiosk mode and mirrors take their content
+    /// from elsewhere and never fail over on their own.
+    fn content_error(&self, name: &str) -> Option<String> {
+        let orch = self.outputs.get(name)?;
+        if self.kiosk.is_some() || self.resolve_mirror(name) != name {
+            return None;
+        }
+        if let Some(file) = &orch.static_file {
+            return (!file.exists())
+                .then(|| format!("static file {} does not exist", file.display()));
+        }
+        if let Some(queue) = self.queue_for(name) {
+            return if !queue.sources.iter().any(|s| s.path.exists()) {
+                Some(format!("path {} does not exist", queue.root_path.display()))
+            } else if queue.pool.is_empty() && !queue.is_discovering() {
+                Some(format!(
+                    "no playable content in {}",
+                    queue.root_path.display()
+                ))
+            } else {
+                None
+            };
+        }
+        let config = match self.config.group_index(name) {
+            Some(gid) => self.queue_config(gid, &orch.config),
+            None => orch.config.clone(),
+        };
+        Some(match config.path {
+            None => "no path configured".to_string(),
+            Some(url) if crate::remote::is_url(&url) => {
+                format!("nothing downloaded from {} yet", url.display())
+            }
+            Some(path) if !path.exists() => format!("path {} does not exist", path.display()),
+            Some(path) => format!("no playable content in {}", path.display()),
+        })
+    }
+
+    /// Re-checks every output for having nothing to play and returns the ones that just
+    /// started failing; they should show their `fallback` now. Outputs whose content is
+    /// back pick again on the next tick. Missing queues are rebuilt every
+    /// `QUEUE_RETRY_INTERVAL` while any output is failing.
+    pub async fn check_content(&mut self) -> Vec<String> {
+        if self
+            .last_content_check
+            .is_some_and(|t| t.elapsed() < CONTENT_CHECK_INTERVAL)
+        {
+            return Vec::new();
+        }
+        self.last_content_check = Some(Instant::now());
+        if !self.failover.is_empty() && self.last_queue_retry.elapsed() >= QUEUE_RETRY_INTERVAL {
+            self.last_queue_retry = Instant::now();
+            self.fill_missing_queues().await;
+        }
+
+        let mut names: Vec<String> = self.outputs.keys().cloned().collect();
+        names.sort();
+        let mut failed = Vec::new();
+        for name in names {
+            match self.content_error(&name) {
+                Some(error) => {
+                    if self.failover.get(&name) != Some(&error) {
+                        warn!("[FAILOVER] {}: {}, showing fallback", name, error);
+                    }
+                    if self.failover.insert(name.clone(), error).is_none() {
+                        failed.push(name);
+                    }
+                }
+                None => {
+                    if self.failover.remove(&name).is_some() {
+                        info!("[FAILOVER] {}: Content is back, resuming", name);
+                        self.restart_output(&name);
+                    }
+                }
+            }
+        }
+        self.failover
+            .retain(|name, _| self.outputs.contains_key(name));
+        failed
+    }
+
+    /// Builds the queues that couldn't be created so far (see `add_output`), in place:
+    /// timers and other outputs' queues are left alone
+    async fn fill_missing_queues(&mut self) {
+        let mut names: Vec<String> = self.failover.keys().cloned().collect();
+        names.sort();
+        let pools = self.discovered_files_cache.clone();
+        let synchronized = self.config.global.monitor_behavior == MonitorBehavior::Synchronized;
+        for name in names {
+            let Some(orch) = self.outputs.get(&name) else {
+                continue;
+            };
+            if orch.static_file.is_some() || self.queue_for(&name).is_some() {
+                continue;
+            }
+            // None for independent outputs, including grouped ones outside every group
+            let gid = self.config.group_index(&name);
+            let config = match gid {
+                Some(gid) => self.queue_config(gid, &orch.config),
+                None => orch.config.clone(),
+            };
+            let Some(queue) = self.take_queue(&mut Vec::new(), &pools, &config).await else {
+                continue;
+            };
+            info!(
+                "[FAILOVER] {}: Queue for {:?} created ({} files)",
+                name,
+                queue.root_path,
+                queue.pool.len()
+            );
+            match gid {
+                Some(_) if synchronized => self.shared_queue = Some(queue),
+                Some(gid) => {
+                    self.group_queues.insert(gid, queue);
+                }
+                None => {
+                    if let Some(orch) = self.outputs.get_mut(&name) {
+                        orch.queue = Some(queue);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Drops what `name` (and the queue it shares) is timed on, so the next tick picks
+    /// for it right away
+    fn restart_output(&mut self, name: &str) {
+        if let Some(orch) = self.outputs.get_mut(name) {
+            orch.current_path = None;
+            orch.display_start_time = None;
+            orch.next_change = None;
+        }
+        match self.config.group_index(name) {
+            Some(_) if self.config.global.monitor_behavior == MonitorBehavior::Synchronized => {
+                self.shared_display_start_time = None;
+            }
+            Some(gid) => {
+                self.group_display_start_times.remove(&gid);
+            }
+            None => {}
+        }
+    }
+
+    /// Why `name` shows its fallback instead of content, if it does (`kldctl query`).
+    /// Mirrors report their source's state.
+    pub fn failover_error(&self, name: &str) -> Option<&str> {
+        self.failover
+            .get(self.resolve_mirror(name))
+            .map(String::as_str)
+    }
+
+    /// What `name` plays and how long it stays (`kldctl status`); the renderer and video
+    /// fields are left for the caller. Mirrors report their source's state.
+    pub fn output_status(&self, name: &str) -> kaleidux_common::OutputStatus {
+        let source = self.resolve_mirror(name);
+        let orch = self.outputs.get(source);
+        let path = orch.and_then(|o| o.current_path.as_ref());
+        let elapsed = orch
+            .and_then(|o| o.display_start_time)
+            .map(|start| start.elapsed());
+        let paused = self.is_paused(source);
+        let remaining = orch
+            .filter(|o| o.static_file.is_none() && !paused)
+            .zip(elapsed)
+            .map(|(o, elapsed)| o.config.duration.saturating_sub(elapsed));
+        let queue = self.queue_for(source);
+        kaleidux_common::OutputStatus {
+            name: name.to_string(),
+            path: path.map(|p| p.display().to_string()),
+            content_type: path
+                .and_then(|p| SmartQueue::get_content_type(p))
+                .map(|t| t.name().to_string()),
+            elapsed_ms: elapsed.map(|d| d.as_millis() as u64),
+            remaining_ms: remaining.map(|d| d.as_millis() as u64),
+            paused,
+            transition: None,
+            queue_size: queue.map_or(0, |q| q.pool.len()),
+            playlist: queue.and_then(|q| q.active_playlist.clone()),
+            video_position_ms: None,
+            video_duration_ms: None,
+            error: self.failover_error(name).map(str::to_string),
+        }
+    }
+
+    /// Image the `last` fallback shows on `name`: the cached snapshot of its last
+    /// wallpaper, or the file itself if it still exists and is an image
+    pub fn last_good_image(&self, name: &str) -> Option<PathBuf> {
+        let (path, content_type) = self.remembered_wallpaper(name).ok().flatten()?;
+        if let Some(snapshot) = crate::cache::snapshot_path(name, &path) {
+            if snapshot.exists() {
+                return Some(snapshot);
+            }
+        }
+        (content_type == crate::queue::ContentType::Image && path.is_file()).then_some(path)
+    }
+
+    /// Flush pending play-count updates, changed queue histories and the wallpapers
+    /// for `restore-last` (batched write)
     pub fn flush_all_stats(&mut self) -> Result<()> {
-        if let Some(q) = &mut self.shared_queue {
-            let _ = q.flush_stats();
+        let cache = self.cache.clone();
+        if !self.unsaved_wallpapers.is_empty() {
+            let wallpapers: Vec<_> = self.unsaved_wallpapers.drain().collect();
+            if let Err(e) = cache.set_last_wallpapers(&wallpapers) {
+                warn!("[RESTORE] Failed to persist last wallpapers: {}", e);
+            }
         }
-        for q in self.group_queues.values_mut() {
-            let _ = q.flush_stats();
+        for (key, queue) in self.keyed_queues_mut() {
+            if !queue.take_history_change() {
+                continue;
+            }
+            if let Err(e) = cache.set_history(&key.to_string(), &queue.history) {
+                warn!("[HISTORY] Failed to save the history of {}: {}", key, e);
+            }
         }
-        for orch in self.outputs.values_mut() {
-            if let Some(q) = &mut orch.queue {
-                let _ = q.flush_stats();
+        self.stats.flush()
+    }
+
+    /// Every queue with the key its history is stored under
+    fn keyed_queues_mut(&mut self) -> impl Iterator<Item = (HistoryKey<'_>, &mut SmartQueue)> {
+        let outputs = self
+            .outputs
+            .iter_mut()
+            .filter_map(|(name, o)| Some((HistoryKey::Output(name), o.queue.as_mut()?)));
+        self.shared_queue
+            .iter_mut()
+            .map(|q| (HistoryKey::Shared, q))
+            .chain(
+                self.group_queues
+                    .iter_mut()
+                    .map(|(gid, q)| (HistoryKey::Group(*gid), q)),
+            )
+            .chain(outputs)
+    }
+
+    /// Gives queues created since the last call the history they had when the daemon
+    /// last ran, so `prev` and `kldctl history` reach back past a restart
+    fn load_histories(&mut self) {
+        let cache = self.cache.clone();
+        for (key, queue) in self.keyed_queues_mut() {
+            if queue.history_loaded() {
+                continue;
+            }
+            let saved = cache.get_history(&key.to_string()).unwrap_or_else(|e| {
+                warn!("[HISTORY] Failed to read the history of {}: {}", key, e);
+                Vec::new()
+            });
+            if !saved.is_empty() {
+                debug!("[HISTORY] {}: Restored {} entries", key, saved.len());
             }
+            queue.restore_history(saved);
         }
-        Ok(())
     }
 
-    fn get_any_queue(&self) -> Option<&SmartQueue> {
-        if let Some(q) = &self.shared_queue {
+    /// Queue that picks content for `name`: its own, its group's or the shared one
+    fn queue_for(&self, name: &str) -> Option<&SmartQueue> {
+        let orch = self.outputs.get(name)?;
+        if orch.static_file.is_some() {
+            return None;
+        }
+        if let Some(q) = &orch.queue {
             return Some(q);
         }
-        if let Some(q) = self.group_queues.values().next() {
+        if let Some(gid) = self.output_groups.get(name) {
+            return self.group_queues.get(gid);
+        }
+        self.shared_queue.as_ref()
+    }
+
+    fn queue_for_mut(&mut self, name: &str) -> Option<&mut SmartQueue> {
+        let orch = self.outputs.get_mut(name)?;
+        if orch.static_file.is_some() {
+            return None;
+        }
+        if let Some(q) = &mut orch.queue {
             return Some(q);
         }
-        for orch in self.outputs.values() {
-            if let Some(q) = &orch.queue {
-                return Some(q);
+        if let Some(gid) = self.output_groups.get(name) {
+            return self.group_queues.get_mut(gid);
+        }
+        self.shared_queue.as_mut()
+    }
+
+    /// `kldctl favorites on|off`: restricts picks of the queues behind `names` to loved
+    /// files, on top of their sorting, until turned off again
+    pub fn set_favorites(&mut self, names: &[String], on: bool) -> Vec<OutputResult> {
+        let mut results = Vec::new();
+        for name in self.queue_targets(names) {
+            let Some(queue) = self.queue_for_mut(&name) else {
+                results.push(
+                    OutputResult::err(&name, "shows a static file")
+                        .with_code(ErrorCode::Unavailable),
+                );
+                continue;
+            };
+            queue.favorites = on;
+            if !on {
+                info!("[MONITOR_MANAGER] {}: Favorites mode off", name);
+                results.push(OutputResult::ok(&name, "favorites off"));
+                continue;
             }
+            let (loved, total) = (queue.loved_count(), queue.pool.len());
+            info!(
+                "[MONITOR_MANAGER] {}: Favorites mode on ({} of {} files loved)",
+                name, loved, total
+            );
+            let message = if loved == 0 {
+                "favorites on, but no file is loved yet; picking from all files".to_string()
+            } else {
+                format!("favorites on ({} of {} files loved)", loved, total)
+            };
+            results.push(OutputResult::ok(&name, message));
         }
-        None
+        results
+    }
+
+    /// `kldctl session save|delete|list`; restoring goes through `load_session` and
+    /// `restore_session`, since it switches wallpapers
+    pub fn handle_session_command(&mut self, cmd: SessionCommand) -> Response {
+        let result = match cmd {
+            SessionCommand::Save { name } => self.save_session(&name),
+            SessionCommand::Delete { name } => match self.cache.delete_session(&name) {
+                Ok(true) => Ok(()),
+                Ok(false) => Err(session_not_found(&name)),
+                Err(e) => Err(e),
+            },
+            SessionCommand::List => {
+                return match self.cache.get_all_sessions() {
+                    Ok(sessions) => Response::Sessions(
+                        sessions
+                            .iter()
+                            .map(|(name, session)| session.info(name))
+                            .collect(),
+                    ),
+                    Err(e) => error_response(e),
+                };
+            }
+            SessionCommand::Restore { .. } => unreachable!("restored by the caller"),
+        };
+        match result {
+            Ok(()) => Response::Ok,
+            Err(e) => error_response(e),
+        }
+    }
+
+    /// Saves what every output (mirrors aside, they follow their source) shows and how
+    /// it cycles under `name`
+    fn save_session(&self, name: &str) -> Result<()> {
+        if self.kiosk.is_some() {
+            anyhow::bail!(CommandError::new(
+                ErrorCode::Unavailable,
+                "sessions are not available in kiosk mode"
+            ));
+        }
+        let mut outputs = std::collections::BTreeMap::new();
+        for (output, orch) in &self.outputs {
+            if self.resolve_mirror(output) != output {
+                continue;
+            }
+            let queue = self.queue_for(output);
+            outputs.insert(
+                output.clone(),
+                crate::session::OutputSession {
+                    wallpaper: orch
+                        .current_path
+                        .clone()
+                        .filter(|_| !self.failover.contains_key(output)),
+                    playlist: queue.and_then(|q| q.active_playlist.clone()),
+                    paused: self.paused || self.paused_outputs.contains(output),
+                    favorites: queue.is_some_and(|q| q.favorites),
+                    transition: orch.config.transition.clone(),
+                    transition_time: orch.config.transition_time,
+                },
+            );
+        }
+        let session = crate::session::Session {
+            saved_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            outputs,
+        };
+        self.cache.set_session(name, &session)?;
+        info!(
+            "[SESSION] Saved '{}' ({} outputs)",
+            name,
+            session.outputs.len()
+        );
+        Ok(())
+    }
+
+    pub fn load_session(&self, name: &str) -> Result<crate::session::Session> {
+        if self.kiosk.is_some() {
+            anyhow::bail!(CommandError::new(
+                ErrorCode::Unavailable,
+                "sessions are not available in kiosk mode"
+            ));
+        }
+        self.cache
+            .get_session(name)?
+            .ok_or_else(|| session_not_found(name))
+    }
+
+    /// Applies `session` to the outputs it has a setup for: playlist, favorites, pause
+    /// and transition (until the next reload) right away, and returns the wallpapers to
+    /// switch to. Outputs it doesn't know keep their setup.
+    pub fn restore_session(
+        &mut self,
+        session: crate::session::Session,
+    ) -> (
+        HashMap<String, (PathBuf, crate::queue::ContentType)>,
+        Vec<OutputResult>,
+    ) {
+        let mut changes = HashMap::new();
+        let mut results = Vec::new();
+        for (name, saved) in session.outputs {
+            if !self.outputs.contains_key(&name) {
+                results.push(
+                    OutputResult::err(&name, "not connected").with_code(ErrorCode::UnknownOutput),
+                );
+                continue;
+            }
+            let mut problems = Vec::new();
+            if let Some(queue) = self.queue_for_mut(&name) {
+                if queue.active_playlist != saved.playlist {
+                    if let Err(e) = queue.set_playlist(saved.playlist.clone()) {
+                        problems.push((ErrorCode::NotFound, e.to_string()));
+                    }
+                }
+                queue.favorites = saved.favorites;
+            }
+            self.set_output_paused(&name, saved.paused);
+
+            match saved.wallpaper {
+                Some(path) if !path.exists() => problems.push((
+                    ErrorCode::NotFound,
+                    format!("{} no longer exists", path.display()),
+                )),
+                Some(path) => match SmartQueue::get_content_type(&path) {
+                    Some(content_type) => {
+                        self.restart_output(&name);
+                        if let Some(orch) = self.outputs.get_mut(&name) {
+                            orch.current_path = Some(path.clone());
+                            orch.next_change = Some(
+                                Instant::now() + orch.config.duration + Duration::from_secs(5),
+                            );
+                            let next = orch.peek_next();
+                            orch.next_path = next.as_ref().map(|n| n.0.clone());
+                            orch.next_content_type = next.map(|n| n.1);
+                        }
+                        changes.insert(name.clone(), (path, content_type));
+                    }
+                    None => problems.push((
+                        ErrorCode::Unavailable,
+                        format!("{} is not an image or video", path.display()),
+                    )),
+                },
+                None => {}
+            }
+            if let Some(orch) = self.outputs.get_mut(&name) {
+                orch.config.transition = saved.transition;
+                orch.config.transition_time = saved.transition_time;
+            }
+
+            results.push(match problems.into_iter().next() {
+                Some((code, message)) => {
+                    warn!("[SESSION] {}: Restored partly: {}", name, message);
+                    OutputResult::err(&name, message).with_code(code)
+                }
+                None => OutputResult::ok(&name, "restored"),
+            });
+        }
+        (changes, results)
+    }
+
+    /// What each queue (or just `output`'s) would pick next, for `kldctl pick --dry-run`.
+    /// Read-only: no counters, history or queue positions change.
+    pub fn preview_picks(
+        &self,
+        output: Option<String>,
+        count: usize,
+    ) -> Result<Vec<kaleidux_common::PickPreview>> {
+        let names: Vec<String> = match output {
+            Some(name) => {
+                let Some(orch) = self.outputs.get(&name) else {
+                    anyhow::bail!(CommandError::new(
+                        ErrorCode::UnknownOutput,
+                        format!("Output {} not found", name)
+                    )
+                    .with_outputs(vec![name]));
+                };
+                if orch.static_file.is_some() {
+                    anyhow::bail!(CommandError::new(
+                        ErrorCode::Unavailable,
+                        format!("Output {} shows a static file and does not pick", name)
+                    )
+                    .with_outputs(vec![name]));
+                }
+                vec![name]
+            }
+            None => {
+                let mut names: Vec<String> = self.outputs.keys().cloned().collect();
+                names.sort();
+                names
+            }
+        };
+
+        // Outputs sharing a queue (synchronized, grouped) get one preview between them
+        let mut previews: Vec<(&SmartQueue, kaleidux_common::PickPreview)> = Vec::new();
+        for name in names {
+            let Some(queue) = self.queue_for(&name) else {
+                continue;
+            };
+            if let Some((_, preview)) = previews.iter_mut().find(|(q, _)| std::ptr::eq(*q, queue)) {
+                preview.outputs.push(name);
+                continue;
+            }
+            previews.push((
+                queue,
+                kaleidux_common::PickPreview {
+                    outputs: vec![name],
+                    strategy: format!("{:?}", queue.strategy).to_lowercase(),
+                    pool_size: queue.pool.len(),
+                    candidates: queue.preview(count),
+                },
+            ));
+        }
+        Ok(previews.into_iter().map(|(_, preview)| preview).collect())
     }
 
     pub fn get_history(&self, output_name: Option<String>) -> Vec<String> {
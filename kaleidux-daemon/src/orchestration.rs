@@ -2,10 +2,10 @@ use anyhow::{Context, Result};
 use regex::Regex;
 use serde::Deserialize;
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
-#[derive(Debug, Clone, Deserialize, Default)]
+#[derive(Debug, Clone, Deserialize, Default, PartialEq)]
 #[serde(rename_all = "kebab-case")]
 pub enum MonitorBehavior {
     #[default]
@@ -14,6 +14,42 @@ pub enum MonitorBehavior {
     Grouped(Vec<Vec<String>>),
 }
 
+/// Global rendering profile
+#[derive(Debug, Clone, Copy, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Profile {
+    #[default]
+    Default,
+    /// E-ink / minimal power: instant swaps, grayscale, images only, long durations,
+    /// and a slow main loop since nothing is animated between switches
+    Eink,
+}
+
+/// Which video decoders GStreamer picks (`video-decoder`)
+#[derive(Debug, Clone, Copy, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum VideoDecoder {
+    /// GStreamer's own ranking
+    #[default]
+    Auto,
+    /// VA-API, NVDEC, V4L2 and other hardware decoders first, software as a fallback
+    Hardware,
+    /// Software decoders only
+    Software,
+}
+
+/// How the X11 backend shows wallpapers (`x11-mode`)
+#[derive(Debug, Clone, Copy, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum X11Mode {
+    /// A desktop window per monitor
+    #[default]
+    Window,
+    /// The windows, plus each settled frame copied into the root pixmap
+    /// (`_XROOTPMAP_ID`) for pseudo-transparent terminals
+    RootPixmap,
+}
+
 #[derive(Debug, Clone, Copy, Deserialize, Default, PartialEq, serde::Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum SortingStrategy {
@@ -22,19 +58,38 @@ pub enum SortingStrategy {
     Random,
     Ascending,
     Descending,
+    /// Loveit, but only among loved files (love multiplier above 1)
+    LovedOnly,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct OutputConfig {
     pub path: Option<PathBuf>,
+    /// Folders picked from together, each in proportion to its weight. Takes the place
+    /// of `path`, which is set to the first of them.
+    #[serde(default)]
+    pub sources: Vec<Source>,
+    /// Levels of folders searched, counting the wallpaper folder itself as 1
+    pub max_depth: Option<usize>,
+    /// Only files matching one of these (globs, or regexes after `regex:`) are used
+    #[serde(default)]
+    pub include: Vec<crate::library_filter::Pattern>,
+    /// Files matching any of these are left out, as are folders matching a name-only one
+    #[serde(default)]
+    pub exclude: Vec<crate::library_filter::Pattern>,
     #[serde(with = "humantime_serde", default = "default_duration")]
     pub duration: Duration,
     #[serde(default = "default_video_ratio")]
     pub video_ratio: u8,
     pub transition: crate::shaders::Transition,
+    /// Transition parameters redrawn from these ranges every time the transition runs
+    #[serde(default)]
+    pub transition_randomize: HashMap<String, crate::shaders::ParamRange>,
     #[serde(default = "default_transition_time")]
     pub transition_time: u32,
+    /// Highest frame rate transitions are rendered at; unset follows the refresh rate
+    pub transition_fps_cap: Option<u32>,
     #[serde(default = "default_volume")]
     pub volume: u8,
     #[serde(default)]
@@ -42,6 +97,246 @@ pub struct OutputConfig {
     #[serde(default = "default_layer")]
     pub layer: Layer,
     pub default_playlist: Option<String>,
+    /// Only files carrying every one of these tags (`kldctl tag add`) are picked, unless
+    /// none in the queue does
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Render content in grayscale
+    #[serde(default)]
+    pub grayscale: bool,
+    /// Highest average brightness content is shown at, as a fraction of full white (0-1).
+    /// Brighter wallpapers are dimmed down to it; darker ones are left alone.
+    #[serde(default)]
+    pub max_luminance: Option<f32>,
+    /// Keep the alpha channel of wallpapers so what the compositor draws below shows through
+    #[serde(default)]
+    pub transparent: bool,
+    /// Layer-shell namespace, lets compositor layer rules target Kaleidux surfaces
+    #[serde(default = "default_namespace")]
+    pub namespace: String,
+    /// Layer-shell exclusive zone (-1 = draw under panels and ignore their zones)
+    #[serde(default = "default_exclusive_zone")]
+    pub exclusive_zone: i32,
+    #[serde(default)]
+    pub keyboard_interactivity: KeyboardInteractivity,
+    /// Edges the layer surface is anchored to (default: all four, i.e. fullscreen)
+    #[serde(default = "default_anchor")]
+    pub anchor: Vec<Edge>,
+    /// Margins from the anchored edges: [top, right, bottom, left]
+    #[serde(default)]
+    pub margin: [i32; 4],
+    /// Requested surface size [width, height]; 0 stretches between the anchored edges
+    #[serde(default)]
+    pub size: [u32; 2],
+    /// When false, no surface, renderer or queue is created for this output
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Always show exactly what this other output shows (content and transitions)
+    pub mirror: Option<String>,
+    /// GPU that renders this output: adapter index or part of its name.
+    /// Outputs on the same adapter share a device; unset uses the default adapter.
+    pub gpu: Option<String>,
+    /// Maximum zoom of a random crop picked per output and cycle (1.0 = show everything)
+    #[serde(default = "default_random_crop")]
+    pub random_crop: f32,
+    /// Screen edges covered by persistent UI, in pixels: [top, right, bottom, left].
+    /// Cropping centers the content's focal point in the rest of the screen.
+    #[serde(default)]
+    pub safe_area: [u32; 4],
+    /// How content that doesn't share the output's aspect ratio is scaled onto it
+    #[serde(default)]
+    pub fit_mode: FitMode,
+    /// Color around `contain`ed or `center`ed content, "#rrggbb" or "#rrggbbaa"; black
+    /// when unset
+    #[serde(default, deserialize_with = "deserialize_color")]
+    pub fit_color: Option<[u8; 4]>,
+    /// Shell command run once per file into the cache; `{input}` and `{output}` are
+    /// replaced by the source and the artifact path, and the artifact is shown instead
+    pub preprocess: Option<String>,
+    /// Images larger than this many times the output resolution are scaled down after
+    /// decoding, before their texture (and mipmaps) is made; unset keeps full size
+    pub max_image_scale: Option<f32>,
+    /// Sound played whenever this output switches, e.g. an audible cue for signage
+    pub on_change_sound: Option<PathBuf>,
+    /// Volume of `on-change-sound` (0-100)
+    #[serde(default = "default_volume")]
+    pub on_change_volume: u8,
+    /// Shell command run whenever this output switches; `{output}` and `{path}` are
+    /// replaced by the output name and the new file
+    pub on_change_command: Option<String>,
+    /// Extract a color scheme from each wallpaper once it is fully shown (see palette.rs)
+    #[serde(default)]
+    pub palette: bool,
+    /// Shell command run after each `palette` extraction; `{output}`, `{path}`,
+    /// `{colors}` and `{json}` are replaced
+    pub palette_command: Option<String>,
+    /// Offset videos start (and loop back) at, e.g. "00:01:10"
+    #[serde(default, deserialize_with = "deserialize_timestamp")]
+    pub video_start: Option<Duration>,
+    /// Offset videos loop at instead of playing to the end
+    #[serde(default, deserialize_with = "deserialize_timestamp")]
+    pub video_end: Option<Duration>,
+    /// Per-file clip ranges keyed by file name or full path
+    #[serde(default)]
+    pub clips: HashMap<String, ClipRange>,
+    /// Frame rate image sequences (folders of numbered frames) are played at
+    #[serde(default = "default_sequence_fps")]
+    pub sequence_fps: u32,
+    /// Highest frame rate shader wallpapers (.glsl/.wgsl files) are drawn at
+    #[serde(default = "default_shader_fps")]
+    pub shader_fps: u32,
+    /// What to show while there is nothing to play (path missing, no playable files)
+    #[serde(default)]
+    pub fallback: Fallback,
+}
+
+/// One folder of an output's `sources`
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct Source {
+    pub path: PathBuf,
+    /// Share of the picks relative to the other sources, however many files each holds
+    #[serde(default = "default_source_weight")]
+    pub weight: f32,
+}
+
+fn default_source_weight() -> f32 {
+    1.0
+}
+
+/// Content shown on an output whose path is missing or holds nothing playable,
+/// instead of leaving it black
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq)]
+#[serde(try_from = "String")]
+pub enum Fallback {
+    /// The last wallpaper shown there, or the default image if there is none
+    #[default]
+    Last,
+    /// The built-in Kaleidux image
+    Default,
+    /// A solid color, "#rrggbb" or "#rrggbbaa"
+    Color([u8; 4]),
+}
+
+impl TryFrom<String> for Fallback {
+    type Error = String;
+
+    fn try_from(text: String) -> std::result::Result<Self, Self::Error> {
+        match text.trim() {
+            "last" => Ok(Self::Last),
+            "default" => Ok(Self::Default),
+            color => crate::overlay::parse_color(color)
+                .map(Self::Color)
+                .ok_or_else(|| {
+                    format!(
+                        "invalid fallback '{}', expected \"last\", \"default\" or a color",
+                        text
+                    )
+                }),
+        }
+    }
+}
+
+/// `fit-mode`: how content is scaled onto an output
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum FitMode {
+    /// Scale to cover the whole output, cropping what sticks out
+    #[default]
+    #[serde(alias = "cover")]
+    Fill,
+    /// Scale to fit inside the output, with `fit-color` bars on the sides left over
+    Contain,
+    /// Show at native size in the middle, cropped or surrounded by `fit-color`
+    Center,
+    /// Scale each axis to the output, ignoring the aspect ratio
+    Stretch,
+    /// Repeat at native size from the top left corner
+    Tile,
+}
+
+impl FitMode {
+    /// Value of the `fit` shader uniform
+    pub fn code(self) -> f32 {
+        match self {
+            Self::Fill => 0.0,
+            Self::Contain => 1.0,
+            Self::Center => 2.0,
+            Self::Stretch => 3.0,
+            Self::Tile => 4.0,
+        }
+    }
+}
+
+/// Part of a video that is played and looped
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq)]
+pub struct ClipRange {
+    #[serde(default, deserialize_with = "deserialize_timestamp")]
+    pub start: Option<Duration>,
+    #[serde(default, deserialize_with = "deserialize_timestamp")]
+    pub end: Option<Duration>,
+}
+
+/// Accepts "HH:MM:SS", "MM:SS" or "SS" (seconds may be fractional) or a number of seconds
+fn deserialize_timestamp<'de, D>(deserializer: D) -> std::result::Result<Option<Duration>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Raw {
+        Seconds(f64),
+        Text(String),
+    }
+
+    let seconds = match Raw::deserialize(deserializer)? {
+        Raw::Seconds(seconds) => seconds,
+        Raw::Text(text) => parse_timestamp(&text).ok_or_else(|| {
+            serde::de::Error::custom(format!("invalid timestamp '{}', expected HH:MM:SS", text))
+        })?,
+    };
+    Duration::try_from_secs_f64(seconds)
+        .map(Some)
+        .map_err(|_| serde::de::Error::custom(format!("invalid timestamp {}", seconds)))
+}
+
+/// Accepts "#rrggbb" or "#rrggbbaa"
+fn deserialize_color<'de, D>(deserializer: D) -> std::result::Result<Option<[u8; 4]>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let text = String::deserialize(deserializer)?;
+    crate::overlay::parse_color(&text).map(Some).ok_or_else(|| {
+        serde::de::Error::custom(format!("invalid color '{}', expected #rrggbb[aa]", text))
+    })
+}
+
+fn parse_timestamp(text: &str) -> Option<f64> {
+    let mut parts = text.trim().rsplit(':');
+    let seconds: f64 = parts.next()?.parse().ok()?;
+    let mut total = seconds;
+    let mut unit = 60.0;
+    for (i, part) in parts.enumerate() {
+        // At most hours:minutes:seconds
+        if i >= 2 {
+            return None;
+        }
+        total += part.parse::<u64>().ok()? as f64 * unit;
+        unit *= 60.0;
+    }
+    (seconds >= 0.0).then_some(total)
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Edge {
+    Top,
+    Right,
+    Bottom,
+    Left,
+}
+
+fn default_anchor() -> Vec<Edge> {
+    vec![Edge::Top, Edge::Right, Edge::Bottom, Edge::Left]
 }
 
 #[derive(Debug, Clone, Deserialize, Default, PartialEq)]
@@ -65,6 +360,169 @@ impl From<Layer> for smithay_client_toolkit::shell::wlr_layer::Layer {
     }
 }
 
+/// Keyboard focus policy of the layer surface
+#[derive(Debug, Clone, Copy, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum KeyboardInteractivity {
+    #[default]
+    None,
+    Exclusive,
+    OnDemand,
+}
+
+impl From<KeyboardInteractivity> for smithay_client_toolkit::shell::wlr_layer::KeyboardInteractivity {
+    fn from(k: KeyboardInteractivity) -> Self {
+        use smithay_client_toolkit::shell::wlr_layer::KeyboardInteractivity as Sctk;
+        match k {
+            KeyboardInteractivity::None => Sctk::None,
+            KeyboardInteractivity::Exclusive => Sctk::Exclusive,
+            KeyboardInteractivity::OnDemand => Sctk::OnDemand,
+        }
+    }
+}
+
+impl OutputConfig {
+    /// Whether switching from `other` to this config requires rebuilding the content queue
+    pub fn queue_differs(&self, other: &OutputConfig) -> bool {
+        self.path != other.path
+            || self.sources != other.sources
+            || self.max_depth != other.max_depth
+            || self.include != other.include
+            || self.exclude != other.exclude
+            || self.video_ratio != other.video_ratio
+            || self.sorting != other.sorting
+            || self.default_playlist != other.default_playlist
+            || self.tags != other.tags
+    }
+
+    /// Folders the queue draws from: `sources`, or `path` alone with weight 1. A URL
+    /// stands for the folder it is downloaded to.
+    pub fn library(&self) -> Vec<Source> {
+        let sources = if self.sources.is_empty() {
+            self.path
+                .iter()
+                .map(|path| Source {
+                    path: path.clone(),
+                    weight: default_source_weight(),
+                })
+                .collect()
+        } else {
+            self.sources.clone()
+        };
+        sources
+            .into_iter()
+            .map(|source| Source {
+                path: crate::remote::local_path(&source.path),
+                ..source
+            })
+            .collect()
+    }
+
+    /// `max-depth`, `include` and `exclude`, applied below each folder of `library`
+    pub fn library_filter(&self) -> crate::library_filter::LibraryFilter {
+        crate::library_filter::LibraryFilter {
+            max_depth: self.max_depth,
+            include: self.include.clone(),
+            exclude: self.exclude.clone(),
+        }
+    }
+
+    /// `path` when it names a single file: the output shows just that file, with no
+    /// queue or schedule, and transitions to it again whenever it changes on disk
+    pub fn static_file(&self) -> Option<&Path> {
+        self.path.as_deref().filter(|p| p.is_file())
+    }
+
+    /// Settings that differ from `other` and take effect in place on reload, without
+    /// touching the queue or what's on screen (config key names, for reload results)
+    pub fn live_changes(&self, other: &OutputConfig) -> Vec<&'static str> {
+        [
+            ("duration", self.duration != other.duration),
+            ("transition", self.transition != other.transition),
+            (
+                "transition-randomize",
+                self.transition_randomize != other.transition_randomize,
+            ),
+            (
+                "transition-time",
+                self.transition_time != other.transition_time,
+            ),
+            (
+                "transition-fps-cap",
+                self.transition_fps_cap != other.transition_fps_cap,
+            ),
+            ("volume", self.volume != other.volume),
+            ("grayscale", self.grayscale != other.grayscale),
+            ("max-luminance", self.max_luminance != other.max_luminance),
+            ("safe-area", self.safe_area != other.safe_area),
+            ("fit-mode", self.fit_mode != other.fit_mode),
+            ("fit-color", self.fit_color != other.fit_color),
+            (
+                "max-image-scale",
+                self.max_image_scale != other.max_image_scale,
+            ),
+            ("fallback", self.fallback != other.fallback),
+            ("shader-fps", self.shader_fps != other.shader_fps),
+            ("transparent", self.transparent != other.transparent),
+            (
+                "on-change-sound",
+                self.on_change_sound != other.on_change_sound,
+            ),
+            (
+                "on-change-volume",
+                self.on_change_volume != other.on_change_volume,
+            ),
+            (
+                "on-change-command",
+                self.on_change_command != other.on_change_command,
+            ),
+            ("palette", self.palette != other.palette),
+            (
+                "palette-command",
+                self.palette_command != other.palette_command,
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(key, changed)| changed.then_some(key))
+        .collect()
+    }
+
+    /// Clip range for a video: a `clips` entry for the file (full path first, then file
+    /// name) overrides `video-start`/`video-end` field by field
+    pub fn clip_for(&self, path: &Path) -> ClipRange {
+        let entry = path
+            .to_str()
+            .and_then(|p| self.clips.get(p))
+            .or_else(|| {
+                path.file_name()
+                    .and_then(|n| n.to_str())
+                    .and_then(|n| self.clips.get(n))
+            })
+            .copied()
+            .unwrap_or_default();
+        ClipRange {
+            start: entry.start.or(self.video_start),
+            end: entry.end.or(self.video_end),
+        }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_random_crop() -> f32 {
+    1.0
+}
+
+fn default_namespace() -> String {
+    "kaleidux-wallpaper".to_string()
+}
+
+fn default_exclusive_zone() -> i32 {
+    -1
+}
+
 fn default_layer() -> Layer {
     Layer::Background
 }
@@ -81,6 +539,14 @@ fn default_transition_time() -> u32 {
     1000
 }
 
+fn default_sequence_fps() -> u32 {
+    24
+}
+
+fn default_shader_fps() -> u32 {
+    30
+}
+
 fn default_volume() -> u8 {
     100
 }
@@ -91,11 +557,535 @@ pub struct Config {
     pub global: GlobalConfig,
     #[serde(default)]
     pub any: PartialOutputConfig,
+    #[serde(default)]
+    pub overlay: crate::overlay::OverlayConfig,
+    /// `[group.N]` sections: settings shared by a monitor-behavior group
+    #[serde(default, rename = "group")]
+    pub groups: HashMap<usize, PartialOutputConfig>,
+    /// `[playlist.NAME]` activation rules
+    #[serde(default, rename = "playlist")]
+    pub playlists: HashMap<String, PlaylistSchedule>,
+    /// `[transition.NAME]` definitions, usable as `transition = "NAME"` and picked by
+    /// `transition = "random"` alongside the builtins
+    #[serde(default, rename = "transition")]
+    pub transitions: HashMap<String, crate::shaders::Transition>,
+    /// `[kiosk]`: one fixed sequence on every output instead of the queues
+    #[serde(default)]
+    pub kiosk: Option<KioskConfig>,
+    /// `[auto-transition]`: how `transition = "auto"` picks
+    #[serde(default)]
+    pub auto_transition: AutoTransitionConfig,
+    /// `[power]`: what changes while running on battery or in power-saver mode
+    #[serde(default)]
+    pub power: PowerConfig,
+    /// `[remote]`: downloading URL sources
+    #[serde(default)]
+    pub remote: RemoteConfig,
+    /// `[fetch]`: pulling wallpapers from an online service
+    #[serde(default)]
+    pub fetch: Option<FetchConfig>,
+    /// `[workspace.NAME]` rules: what outputs show while workspace NAME is active
+    #[serde(default, rename = "workspace")]
+    pub workspaces: HashMap<String, WorkspaceRule>,
     #[serde(flatten)]
     pub outputs: HashMap<String, PartialOutputConfig>,
 }
 
-#[derive(Debug, Clone, Deserialize, Default)]
+/// Signage mode: `items` play in order on every output and loop, each for exactly its
+/// `duration`
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub struct KioskConfig {
+    pub items: Vec<KioskItem>,
+    /// Display time of items that don't set their own
+    #[serde(with = "humantime_serde", default = "default_kiosk_duration")]
+    pub duration: Duration,
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct KioskItem {
+    pub path: PathBuf,
+    #[serde(with = "humantime_serde", default)]
+    pub duration: Option<Duration>,
+}
+
+fn default_kiosk_duration() -> Duration {
+    Duration::from_secs(10)
+}
+
+/// Rules for `transition = "auto"`, checked in order against the outgoing and incoming
+/// content on every switch; the first that matches names the transition
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub struct AutoTransitionConfig {
+    #[serde(default = "default_auto_rules")]
+    pub rules: Vec<AutoTransitionRule>,
+    /// Transition when no rule matches
+    #[serde(default = "default_auto_fallback")]
+    pub default: String,
+}
+
+impl Default for AutoTransitionConfig {
+    fn default() -> Self {
+        Self {
+            rules: default_auto_rules(),
+            default: default_auto_fallback(),
+        }
+    }
+}
+
+/// One `[[auto-transition.rules]]` entry. Every condition it sets must hold; colors are
+/// compared between the dominant colors of both images, brightness between their mean
+/// brightness, both as fractions of the full range (0-1).
+#[derive(Debug, Clone, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct AutoTransitionRule {
+    /// Whether either side is a video
+    pub video: Option<bool>,
+    pub max_color_distance: Option<f32>,
+    pub min_color_distance: Option<f32>,
+    pub max_brightness_change: Option<f32>,
+    pub min_brightness_change: Option<f32>,
+    /// A builtin, a `[transition.NAME]` definition, `"random"`, or `"cut"` to switch
+    /// without a transition
+    pub transition: String,
+}
+
+impl AutoTransitionRule {
+    /// Whether the rule compares the images themselves, which needs both tones
+    pub fn needs_tones(&self) -> bool {
+        self.max_color_distance.is_some()
+            || self.min_color_distance.is_some()
+            || self.max_brightness_change.is_some()
+            || self.min_brightness_change.is_some()
+    }
+}
+
+fn default_auto_rules() -> Vec<AutoTransitionRule> {
+    vec![
+        AutoTransitionRule {
+            video: Some(true),
+            transition: "cut".to_string(),
+            ..Default::default()
+        },
+        AutoTransitionRule {
+            max_color_distance: Some(0.15),
+            transition: "fade".to_string(),
+            ..Default::default()
+        },
+        AutoTransitionRule {
+            min_brightness_change: Some(0.35),
+            transition: "wipeleft".to_string(),
+            ..Default::default()
+        },
+    ]
+}
+
+fn default_auto_fallback() -> String {
+    "random".to_string()
+}
+
+/// Power saving: while on battery (or in the power-saver profile) videos pause,
+/// switches cut without a transition and wallpapers stay up longer; all of it ends
+/// once back on AC
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct PowerConfig {
+    /// Save power while running on battery
+    #[serde(default = "default_true")]
+    pub on_battery: bool,
+    /// Save power while the power-saver profile is active, on AC too
+    #[serde(default = "default_true")]
+    pub on_power_saver: bool,
+    #[serde(default = "default_true")]
+    pub pause_videos: bool,
+    #[serde(default = "default_true")]
+    pub instant_transitions: bool,
+    /// Factor on every `duration`; 1 keeps the rotation as configured
+    #[serde(default = "default_rotation_multiplier")]
+    pub rotation_multiplier: f32,
+}
+
+impl Default for PowerConfig {
+    fn default() -> Self {
+        Self {
+            on_battery: true,
+            on_power_saver: true,
+            pause_videos: true,
+            instant_transitions: true,
+            rotation_multiplier: default_rotation_multiplier(),
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_rotation_multiplier() -> f32 {
+    2.0
+}
+
+/// `[remote]`: how http(s) `path`s and `sources` are downloaded
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct RemoteConfig {
+    /// How often each URL is checked for changes
+    #[serde(with = "humantime_serde", default = "default_remote_refresh")]
+    pub refresh: Duration,
+    /// Largest file downloaded (e.g. "50MB"); bigger ones are skipped
+    #[serde(default = "default_remote_max_size")]
+    pub max_size: String,
+    /// Longest a single download may take
+    #[serde(with = "humantime_serde", default = "default_remote_timeout")]
+    pub timeout: Duration,
+}
+
+impl Default for RemoteConfig {
+    fn default() -> Self {
+        Self {
+            refresh: default_remote_refresh(),
+            max_size: default_remote_max_size(),
+            timeout: default_remote_timeout(),
+        }
+    }
+}
+
+impl RemoteConfig {
+    /// `max-size` in bytes
+    pub fn max_bytes(&self) -> u64 {
+        crate::cache::parse_size(&self.max_size).unwrap_or(50 << 20)
+    }
+}
+
+/// `[fetch]`: wallpapers pulled from Wallhaven or Unsplash every `interval` into
+/// `~/.cache/kaleidux/fetched` and listed in the playlist `playlist`
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct FetchConfig {
+    pub provider: FetchProvider,
+    /// Needed for Unsplash, and for NSFW results on Wallhaven
+    pub api_key: Option<String>,
+    /// Search terms; empty takes anything
+    #[serde(default)]
+    pub query: String,
+    /// Aspect ratio like "16x9", or "landscape"/"portrait"
+    pub ratio: Option<String>,
+    /// Wallhaven purity levels allowed: "sfw", "sketchy", "nsfw". On Unsplash anything
+    /// beyond "sfw" turns off the strict content filter.
+    #[serde(default = "default_fetch_purity")]
+    pub purity: Vec<String>,
+    #[serde(with = "humantime_serde", default = "default_fetch_interval")]
+    pub interval: Duration,
+    /// Wallpapers requested per pull (Unsplash allows up to 30)
+    #[serde(default = "default_fetch_count")]
+    pub count: usize,
+    #[serde(default = "default_fetch_playlist")]
+    pub playlist: String,
+    /// Fetched wallpapers older than this are deleted
+    #[serde(with = "humantime_serde", default)]
+    pub max_age: Option<Duration>,
+    /// The oldest fetched wallpapers are deleted while the folder is bigger (e.g. "1GB")
+    pub max_size: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum FetchProvider {
+    Wallhaven,
+    Unsplash,
+}
+
+impl FetchProvider {
+    pub fn name(self) -> &'static str {
+        match self {
+            FetchProvider::Wallhaven => "wallhaven",
+            FetchProvider::Unsplash => "unsplash",
+        }
+    }
+}
+
+impl FetchConfig {
+    fn validate(&self) -> std::result::Result<(), String> {
+        if self.provider == FetchProvider::Unsplash && self.api_key.is_none() {
+            return Err("unsplash needs an api-key".to_string());
+        }
+        if let Some(purity) = self
+            .purity
+            .iter()
+            .find(|p| !matches!(p.as_str(), "sfw" | "sketchy" | "nsfw"))
+        {
+            return Err(format!(
+                "unknown purity '{}', expected sfw, sketchy or nsfw",
+                purity
+            ));
+        }
+        if let Some(size) = &self.max_size {
+            crate::cache::parse_size(size).map_err(|e| format!("max-size: {}", e))?;
+        }
+        Ok(())
+    }
+}
+
+fn default_fetch_purity() -> Vec<String> {
+    vec!["sfw".to_string()]
+}
+
+fn default_fetch_interval() -> Duration {
+    Duration::from_secs(6 * 60 * 60)
+}
+
+fn default_fetch_count() -> usize {
+    24
+}
+
+fn default_fetch_playlist() -> String {
+    "fetched".to_string()
+}
+
+fn default_remote_refresh() -> Duration {
+    Duration::from_secs(60 * 60)
+}
+
+fn default_remote_max_size() -> String {
+    "50MB".to_string()
+}
+
+fn default_remote_timeout() -> Duration {
+    Duration::from_secs(120)
+}
+
+/// One message per cycle of `mirror` settings among output sections, e.g. [DP-1]
+/// mirroring HDMI-A-1 while [HDMI-A-1] mirrors DP-1. The outputs in a cycle show their
+/// own content (see `MonitorManager::resolve_mirror`).
+fn mirror_cycles(outputs: &HashMap<String, PartialOutputConfig>) -> Vec<String> {
+    let mut keys: Vec<&String> = outputs.keys().collect();
+    keys.sort();
+    let mut errors = Vec::new();
+    for key in keys {
+        let mut chain = vec![key.as_str()];
+        while let Some(source) = outputs[*chain.last().unwrap()].mirror.as_deref() {
+            if let Some(start) = chain.iter().position(|n| *n == source) {
+                // Reported once, from the cycle's alphabetically first output
+                if start == 0 {
+                    chain.push(source);
+                    errors.push(format!(
+                        "mirror settings loop back on themselves ({}); these outputs show their own content",
+                        chain.join(" -> ")
+                    ));
+                }
+                break;
+            }
+            if !outputs.contains_key(source) {
+                break;
+            }
+            chain.push(source);
+        }
+    }
+    errors
+}
+
+/// When a stored playlist is loaded automatically
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct PlaylistSchedule {
+    /// Daily windows like "20:00-02:00" (may wrap past midnight); one string or a list
+    #[serde(deserialize_with = "deserialize_time_windows")]
+    pub active: Vec<TimeWindow>,
+    /// Weekdays the windows start on, like "mon-fri" or ["sat", "sun"] (default: every day)
+    #[serde(default, deserialize_with = "deserialize_weekdays")]
+    pub days: Vec<chrono::Weekday>,
+    /// Output section keys the rule applies to (default: every output)
+    #[serde(default)]
+    pub outputs: Vec<String>,
+}
+
+/// `[workspace.NAME]`: an output switches to `path` or to `playlist` while workspace
+/// NAME is active on it (see `crate::compositor`)
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub struct WorkspaceRule {
+    /// Wallpaper held while the workspace is active
+    pub path: Option<PathBuf>,
+    /// Playlist the output's queue plays while the workspace is active
+    pub playlist: Option<String>,
+    /// Output section keys the rule applies to (default: every output)
+    #[serde(default)]
+    pub outputs: Vec<String>,
+}
+
+impl PlaylistSchedule {
+    /// Whether one of the windows contains `now`. A window wrapping past midnight
+    /// belongs to the day it started on.
+    pub fn is_active(&self, now: chrono::NaiveDateTime) -> bool {
+        use chrono::Datelike;
+        self.active.iter().any(|window| {
+            let day = if window.start > window.end && now.time() < window.end {
+                now.weekday().pred()
+            } else {
+                now.weekday()
+            };
+            window.contains(now.time()) && (self.days.is_empty() || self.days.contains(&day))
+        })
+    }
+
+    /// The windows as text, e.g. "mon-fri 09:00-17:00", for `kldctl playlist show`
+    pub fn describe(&self) -> Vec<String> {
+        use chrono::Weekday;
+        let mut days = self.days.clone();
+        days.sort_by_key(Weekday::num_days_from_monday);
+        days.dedup();
+        // Runs of consecutive days shrink to "first-last"
+        let mut runs: Vec<(Weekday, Weekday)> = Vec::new();
+        for day in days {
+            match runs.last_mut() {
+                Some((_, last)) if last.succ() == day => *last = day,
+                _ => runs.push((day, day)),
+            }
+        }
+        let short = |day: Weekday| day.to_string().to_lowercase();
+        let days = runs
+            .iter()
+            .map(|&(first, last)| {
+                if first == last {
+                    short(first)
+                } else {
+                    format!("{}-{}", short(first), short(last))
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        self.active
+            .iter()
+            .map(|window| {
+                if days.is_empty() {
+                    window.to_string()
+                } else {
+                    format!("{} {}", days, window)
+                }
+            })
+            .collect()
+    }
+}
+
+/// Daily local-time window, end exclusive. Equal start and end cover the whole day.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeWindow {
+    start: chrono::NaiveTime,
+    end: chrono::NaiveTime,
+}
+
+impl TimeWindow {
+    fn parse(text: &str) -> Option<Self> {
+        let (start, end) = text.split_once('-')?;
+        let time = |t: &str| chrono::NaiveTime::parse_from_str(t.trim(), "%H:%M").ok();
+        Some(Self {
+            start: time(start)?,
+            end: time(end)?,
+        })
+    }
+
+    pub fn contains(&self, time: chrono::NaiveTime) -> bool {
+        if self.start < self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+impl std::fmt::Display for TimeWindow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}-{}",
+            self.start.format("%H:%M"),
+            self.end.format("%H:%M")
+        )
+    }
+}
+
+fn deserialize_time_windows<'de, D>(
+    deserializer: D,
+) -> std::result::Result<Vec<TimeWindow>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Raw {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    let texts = match Raw::deserialize(deserializer)? {
+        Raw::One(text) => vec![text],
+        Raw::Many(texts) => texts,
+    };
+    texts
+        .iter()
+        .map(|text| {
+            TimeWindow::parse(text).ok_or_else(|| {
+                serde::de::Error::custom(format!(
+                    "invalid time window '{}', expected HH:MM-HH:MM",
+                    text
+                ))
+            })
+        })
+        .collect()
+}
+
+/// Day names ("mon", "monday"), ranges of them ("mon-fri", "fri-mon" wraps),
+/// "weekdays" and "weekends"; one string or a list
+fn deserialize_weekdays<'de, D>(
+    deserializer: D,
+) -> std::result::Result<Vec<chrono::Weekday>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use chrono::Weekday;
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Raw {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    let texts = match Raw::deserialize(deserializer)? {
+        Raw::One(text) => vec![text],
+        Raw::Many(texts) => texts,
+    };
+    let mut days = Vec::new();
+    for text in &texts {
+        let text = text.trim().to_lowercase();
+        let (first, last) = match text.as_str() {
+            "weekdays" => (Weekday::Mon, Weekday::Fri),
+            "weekends" => (Weekday::Sat, Weekday::Sun),
+            _ => {
+                let (first, last) = text.split_once('-').unwrap_or((&text, &text));
+                match (first.trim().parse(), last.trim().parse()) {
+                    (Ok(first), Ok(last)) => (first, last),
+                    _ => {
+                        return Err(serde::de::Error::custom(format!(
+                            "invalid day '{}', expected a day name like 'mon' or a range like 'mon-fri'",
+                            text
+                        )))
+                    }
+                }
+            }
+        };
+        let mut day = first;
+        days.push(day);
+        while day != last {
+            day = day.succ();
+            days.push(day);
+        }
+    }
+    Ok(days)
+}
+
+#[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct GlobalConfig {
     pub monitor_behavior: MonitorBehavior,
@@ -110,146 +1100,930 @@ pub struct GlobalConfig {
     #[serde(default = "default_script_tick_interval")]
     pub script_tick_interval: u64,
     pub default_playlist: Option<String>,
+    #[serde(default)]
+    pub profile: Profile,
+    /// Outputs Kaleidux never touches (names, or "re:" patterns on the description)
+    #[serde(default)]
+    pub ignore: Vec<String>,
+    /// Show each output's last wallpaper immediately on startup, before the first pick
+    #[serde(default = "default_restore_last")]
+    pub restore_last: bool,
+    /// Upper bound for ~/.cache/kaleidux (e.g. "2GB"); least-recently-used files are evicted
+    pub cache_max_size: Option<String>,
+    /// Daily copies of the stats database kept for recovery (0 disables backups)
+    #[serde(default = "default_stats_backups")]
+    pub stats_backups: usize,
+    /// Failed loads after which a file is quarantined (0 never quarantines)
+    #[serde(default = "default_quarantine_after")]
+    pub quarantine_after: u32,
+    /// Outputs with their own queue skip files another output showed within this window
+    #[serde(with = "humantime_serde", default)]
+    pub avoid_twins: Option<Duration>,
+    /// Caches and allocator memory are released after this long without transitions or
+    /// video (0 never trims)
+    #[serde(with = "humantime_serde", default = "default_idle_trim")]
+    pub idle_trim: Duration,
+    /// Keep a state file (and on X11 a root window property) with each output's
+    /// wallpaper and dominant colors for widgets
+    #[serde(default = "default_publish_state")]
+    pub publish_state: bool,
+    /// Highest rate the main loop renders video, transitions and shader wallpapers at
+    /// (default 60)
+    pub max_fps: Option<u32>,
+    /// Pause video and rendering on outputs a fullscreen window covers (Wayland,
+    /// needs wlr-foreign-toplevel)
+    #[serde(default = "default_true")]
+    pub pause_on_fullscreen: bool,
+    /// Pause video and rendering everywhere once the session has been idle this long
+    /// (Wayland, needs ext-idle-notify)
+    #[serde(with = "humantime_serde", default)]
+    pub pause_on_idle: Option<Duration>,
+    /// Prefer hardware or software video decoders over GStreamer's default choice
+    #[serde(default)]
+    pub video_decoder: VideoDecoder,
+    /// File each output's frame is written to whenever a change settles, for lock screens
+    /// (`{output}` is replaced by the output name, `~/` by the home folder)
+    pub lock_image: Option<String>,
+    /// Transition for switches made by `[workspace.NAME]` rules
+    #[serde(default = "default_workspace_transition")]
+    pub workspace_transition: String,
+    /// Length of `workspace-transition` in milliseconds
+    #[serde(default = "default_workspace_transition_time")]
+    pub workspace_transition_time: u32,
+    /// Whether X11 also keeps the root pixmap up to date for pseudo-transparency
+    #[serde(default, alias = "x11_mode")]
+    pub x11_mode: X11Mode,
+}
+
+// Hand-written so a missing [global] section gets the same defaults as an empty one
+impl Default for GlobalConfig {
+    fn default() -> Self {
+        Self {
+            monitor_behavior: MonitorBehavior::default(),
+            _custom_transitions: false,
+            video_ratio: None,
+            transition_time: None,
+            volume: None,
+            script_path: None,
+            sorting: None,
+            script_tick_interval: default_script_tick_interval(),
+            default_playlist: None,
+            profile: Profile::default(),
+            ignore: Vec::new(),
+            restore_last: default_restore_last(),
+            cache_max_size: None,
+            stats_backups: default_stats_backups(),
+            quarantine_after: default_quarantine_after(),
+            avoid_twins: None,
+            idle_trim: default_idle_trim(),
+            publish_state: default_publish_state(),
+            max_fps: None,
+            pause_on_fullscreen: true,
+            pause_on_idle: None,
+            video_decoder: VideoDecoder::default(),
+            lock_image: None,
+            workspace_transition: default_workspace_transition(),
+            workspace_transition_time: default_workspace_transition_time(),
+            x11_mode: X11Mode::default(),
+        }
+    }
 }
 
 fn default_script_tick_interval() -> u64 {
     1
 }
 
+fn default_workspace_transition() -> String {
+    "fade".to_string()
+}
+
+fn default_workspace_transition_time() -> u32 {
+    300
+}
+
+fn default_restore_last() -> bool {
+    true
+}
+
+fn default_stats_backups() -> usize {
+    3
+}
+
+fn default_quarantine_after() -> u32 {
+    3
+}
+
+fn default_idle_trim() -> Duration {
+    Duration::from_secs(300)
+}
+
+fn default_publish_state() -> bool {
+    true
+}
+
 #[derive(Debug, Clone, Deserialize, Default)]
 #[serde(rename_all = "kebab-case")]
 pub struct PartialOutputConfig {
     pub path: Option<PathBuf>,
+    pub sources: Option<Vec<Source>>,
+    pub max_depth: Option<usize>,
+    pub include: Option<Vec<crate::library_filter::Pattern>>,
+    pub exclude: Option<Vec<crate::library_filter::Pattern>>,
     #[serde(with = "humantime_serde", default)]
     pub duration: Option<Duration>,
     pub video_ratio: Option<u8>,
     pub transition: Option<crate::shaders::Transition>,
+    pub transition_randomize: Option<HashMap<String, crate::shaders::ParamRange>>,
     pub transition_time: Option<u32>,
+    pub transition_fps_cap: Option<u32>,
     pub volume: Option<u8>,
     pub sorting: Option<SortingStrategy>,
     pub layer: Option<Layer>,
     pub default_playlist: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub grayscale: Option<bool>,
+    pub max_luminance: Option<f32>,
+    pub transparent: Option<bool>,
+    pub namespace: Option<String>,
+    pub exclusive_zone: Option<i32>,
+    pub keyboard_interactivity: Option<KeyboardInteractivity>,
+    pub anchor: Option<Vec<Edge>>,
+    pub margin: Option<[i32; 4]>,
+    pub size: Option<[u32; 2]>,
+    pub enabled: Option<bool>,
+    pub mirror: Option<String>,
+    pub gpu: Option<String>,
+    pub random_crop: Option<f32>,
+    pub safe_area: Option<[u32; 4]>,
+    pub fit_mode: Option<FitMode>,
+    #[serde(default, deserialize_with = "deserialize_color")]
+    pub fit_color: Option<[u8; 4]>,
+    pub preprocess: Option<String>,
+    pub max_image_scale: Option<f32>,
+    pub on_change_sound: Option<PathBuf>,
+    pub on_change_volume: Option<u8>,
+    pub on_change_command: Option<String>,
+    pub palette: Option<bool>,
+    pub palette_command: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_timestamp")]
+    pub video_start: Option<Duration>,
+    #[serde(default, deserialize_with = "deserialize_timestamp")]
+    pub video_end: Option<Duration>,
+    pub clips: Option<HashMap<String, ClipRange>>,
+    pub sequence_fps: Option<u32>,
+    pub shader_fps: Option<u32>,
+    pub fallback: Option<Fallback>,
+}
+
+/// Everything the compositor tells us about an output that config sections can match on
+#[derive(Debug, Clone, Default)]
+pub struct OutputIdentity {
+    pub name: String,
+    pub description: String,
+    pub make: String,
+    pub model: String,
+}
+
+impl OutputIdentity {
+    pub fn new(name: impl Into<String>, description: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            description: description.into(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Output property a section key is matched against, in ascending priority
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum MatchField {
+    Make,
+    Model,
+    Description,
+    Name,
+    /// wl_output has no serial event; compositors include it in the description,
+    /// so a serial matches any whitespace-separated token of the description.
+    Serial,
+}
+
+/// Parsed output section key.
+///
+/// `DP-1` matches the connector name, `serial:`/`description:`/`make:`/`model:`/`name:`
+/// match those properties exactly, and a `re:` prefix turns the rest into a regex
+/// (`re:<pattern>` alone matches the description, as it always has).
+struct OutputMatcher<'a> {
+    field: MatchField,
+    pattern: &'a str,
+    regex: bool,
+}
+
+impl<'a> OutputMatcher<'a> {
+    fn parse(key: &'a str) -> Self {
+        let (regex, rest) = match key.strip_prefix("re:") {
+            Some(rest) => (true, rest),
+            None => (false, key),
+        };
+        let fields = [
+            ("name:", MatchField::Name),
+            ("serial:", MatchField::Serial),
+            ("description:", MatchField::Description),
+            ("desc:", MatchField::Description),
+            ("make:", MatchField::Make),
+            ("model:", MatchField::Model),
+        ];
+        for (prefix, field) in fields {
+            if let Some(pattern) = rest.strip_prefix(prefix) {
+                return Self {
+                    field,
+                    pattern,
+                    regex,
+                };
+            }
+        }
+        Self {
+            field: if regex {
+                MatchField::Description
+            } else {
+                MatchField::Name
+            },
+            pattern: rest,
+            regex,
+        }
+    }
+
+    /// Priority of this section for the output, or None if it doesn't match.
+    /// Exact matches always outrank regexes, then the more specific field wins.
+    fn score(&self, identity: &OutputIdentity) -> Option<u8> {
+        let values: Vec<&str> = match self.field {
+            MatchField::Name => vec![identity.name.as_str()],
+            MatchField::Description => vec![identity.description.as_str()],
+            MatchField::Make => vec![identity.make.as_str()],
+            MatchField::Model => vec![identity.model.as_str()],
+            MatchField::Serial => identity
+                .description
+                .split_whitespace()
+                .map(|t| t.trim_matches(|c| c == '(' || c == ')'))
+                .collect(),
+        };
+
+        let matches = if self.regex {
+            match Regex::new(self.pattern) {
+                Ok(re) => values.iter().any(|v| re.is_match(v)),
+                Err(e) => {
+                    tracing::warn!("[CONFIG] Invalid regex '{}': {}", self.pattern, e);
+                    false
+                }
+            }
+        } else {
+            values.iter().any(|v| !v.is_empty() && *v == self.pattern)
+        };
+
+        matches.then(|| {
+            let exact_bonus = if self.regex { 0 } else { 10 };
+            self.field as u8 + exact_bonus
+        })
+    }
 }
 
+/// Duration used by the e-ink profile when no duration is configured
+const EINK_DEFAULT_DURATION: Duration = Duration::from_secs(3600);
+
 impl Config {
-    pub async fn load() -> Result<Self> {
-        let config_dir = dirs::config_dir()
+    /// ~/.config/kaleidux/config.toml
+    pub fn path() -> Result<PathBuf> {
+        Ok(dirs::config_dir()
             .context("Failed to get config directory")?
-            .join("kaleidux");
-        let config_path = config_dir.join("config.toml");
+            .join("kaleidux")
+            .join("config.toml"))
+    }
+
+    pub async fn load() -> Result<Self> {
+        let (config, errors) = Self::read().await?;
+        for error in &errors {
+            tracing::error!("{}", error);
+        }
+        if !errors.is_empty() {
+            tracing::warn!(
+                "{} configuration section(s) had errors and fell back to defaults or were skipped",
+                errors.len()
+            );
+        }
+
+        tracing::info!("Loaded config with {} output overrides", config.outputs.len());
+
+        Ok(config)
+    }
+
+    /// Loads the config for `kldctl reload`. Unlike at startup, sections with errors
+    /// fail the whole load, listing every one, so the running config stays in place
+    /// instead of parts of it silently falling back to defaults.
+    pub async fn load_strict() -> Result<Self> {
+        let (config, errors) = Self::read().await?;
+        if !errors.is_empty() {
+            anyhow::bail!(
+                "{} config section(s) have errors:\n  {}",
+                errors.len(),
+                errors.join("\n  ")
+            );
+        }
+        Ok(config)
+    }
+
+    /// The config file parsed (see `parse`), or the defaults if there is none
+    async fn read() -> Result<(Self, Vec<String>)> {
+        let config_path = Self::path()?;
 
         if !config_path.exists() {
             tracing::warn!("No config file found at {:?}, using defaults", config_path);
-            return Ok(Self::default());
+            return Ok((Self::default(), Vec::new()));
         }
 
         let content = tokio::fs::read_to_string(&config_path)
             .await
             .with_context(|| format!("Failed to read config file: {:?}", config_path))?;
 
+        Self::parse(&content)
+    }
+
+    /// Parses a config file. Sections that fail to parse fall back to their defaults
+    /// (or are skipped, for outputs) and are described in the returned error list.
+    pub fn parse(content: &str) -> Result<(Self, Vec<String>)> {
         // Parse as raw TOML table first to work around serde(flatten) issues
         let table: toml::Table =
-            toml::from_str(&content).with_context(|| "Failed to parse config TOML")?;
+            toml::from_str(content).with_context(|| "Failed to parse config TOML")?;
+        let mut errors = Vec::new();
 
         // Extract reserved sections
         let global: GlobalConfig = if let Some(v) = table.get("global") {
             v.clone().try_into().unwrap_or_else(|e| {
-                tracing::error!("Failed to parse [global] config section: {}", e);
+                errors.push(format!("Failed to parse [global] config section: {}", e));
                 GlobalConfig::default()
             })
         } else {
             GlobalConfig::default()
         };
 
+        // Named transitions come first so output sections can refer to them
+        let mut transitions = HashMap::new();
+        if let Some(v) = table.get("transition") {
+            match v.as_table() {
+                Some(definitions) => {
+                    for (name, definition) in definitions {
+                        match parse_transition_definition(definition) {
+                            Ok(transition) => {
+                                transitions.insert(name.clone(), transition);
+                            }
+                            Err(e) => errors.push(format!(
+                                "Failed to parse [transition.{}] config section: {}",
+                                name, e
+                            )),
+                        }
+                    }
+                }
+                None => {
+                    errors.push("[transition] must contain [transition.NAME] tables".to_string())
+                }
+            }
+        }
+        let section = |v: &toml::Value| -> std::result::Result<PartialOutputConfig, String> {
+            resolve_transition_name(v, &transitions)?
+                .try_into()
+                .map_err(|e: toml::de::Error| e.to_string())
+        };
+
         let any: PartialOutputConfig = if let Some(v) = table.get("any") {
-            v.clone().try_into().unwrap_or_else(|e| {
-                tracing::error!("Failed to parse [any] config section: {}", e);
+            section(v).unwrap_or_else(|e| {
+                errors.push(format!("Failed to parse [any] config section: {}", e));
                 PartialOutputConfig::default()
             })
         } else {
             PartialOutputConfig::default()
         };
 
+        let overlay: crate::overlay::OverlayConfig = if let Some(v) = table.get("overlay") {
+            v.clone().try_into().unwrap_or_else(|e| {
+                errors.push(format!("Failed to parse [overlay] config section: {}", e));
+                crate::overlay::OverlayConfig::default()
+            })
+        } else {
+            crate::overlay::OverlayConfig::default()
+        };
+
+        // Rules are parsed one by one so a broken rule doesn't disable the others
+        let mut playlists = HashMap::new();
+        if let Some(v) = table.get("playlist") {
+            match v.as_table() {
+                Some(rules) => {
+                    for (name, rule) in rules {
+                        match rule.clone().try_into::<PlaylistSchedule>() {
+                            Ok(rule) => {
+                                playlists.insert(name.clone(), rule);
+                            }
+                            Err(e) => errors.push(format!(
+                                "Failed to parse [playlist.{}] config section: {}",
+                                name, e
+                            )),
+                        }
+                    }
+                }
+                None => errors.push("[playlist] must contain [playlist.NAME] tables".to_string()),
+            }
+        }
+
+        let mut workspaces = HashMap::new();
+        if let Some(v) = table.get("workspace") {
+            match v.as_table() {
+                Some(rules) => {
+                    for (name, rule) in rules {
+                        match rule.clone().try_into::<WorkspaceRule>() {
+                            Ok(rule) if rule.path.is_some() == rule.playlist.is_some() => {
+                                errors.push(format!(
+                                    "Invalid [workspace.{}] config section: set either path or playlist",
+                                    name
+                                ))
+                            }
+                            Ok(rule) => {
+                                workspaces.insert(name.clone(), rule);
+                            }
+                            Err(e) => errors.push(format!(
+                                "Failed to parse [workspace.{}] config section: {}",
+                                name, e
+                            )),
+                        }
+                    }
+                }
+                None => errors.push("[workspace] must contain [workspace.NAME] tables".to_string()),
+            }
+        }
+
+        let kiosk = match table.get("kiosk") {
+            Some(v) => match v.clone().try_into::<KioskConfig>() {
+                Ok(kiosk) => Some(kiosk),
+                Err(e) => {
+                    errors.push(format!("Failed to parse [kiosk] config section: {}", e));
+                    None
+                }
+            },
+            None => None,
+        };
+
+        // Rules naming a transition that doesn't exist are dropped, not the section
+        let auto_transition = match table.get("auto-transition") {
+            Some(v) => match v.clone().try_into::<AutoTransitionConfig>() {
+                Ok(mut auto) => {
+                    let known = |name: &str| {
+                        let builtin = name.to_lowercase();
+                        transitions.contains_key(name)
+                            || matches!(builtin.as_str(), "cut" | "random")
+                            || kaleidux_common::RANDOM_TRANSITIONS.contains(&&*builtin)
+                    };
+                    auto.rules.retain(|rule| {
+                        let keep = known(&rule.transition);
+                        if !keep {
+                            errors.push(format!(
+                                "Skipping [auto-transition] rule: unknown transition '{}'",
+                                rule.transition
+                            ));
+                        }
+                        keep
+                    });
+                    if !known(&auto.default) {
+                        errors.push(format!(
+                            "Unknown [auto-transition] default '{}', using random",
+                            auto.default
+                        ));
+                        auto.default = default_auto_fallback();
+                    }
+                    auto
+                }
+                Err(e) => {
+                    errors.push(format!(
+                        "Failed to parse [auto-transition] config section: {}",
+                        e
+                    ));
+                    AutoTransitionConfig::default()
+                }
+            },
+            None => AutoTransitionConfig::default(),
+        };
+
+        let power = match table.get("power") {
+            Some(v) => match v.clone().try_into::<PowerConfig>() {
+                Ok(mut power) => {
+                    let multiplier = power.rotation_multiplier;
+                    if !(multiplier.is_finite() && multiplier >= 1.0) {
+                        errors.push(format!(
+                            "Invalid [power] rotation-multiplier {}, using 1",
+                            multiplier
+                        ));
+                        power.rotation_multiplier = 1.0;
+                    }
+                    power
+                }
+                Err(e) => {
+                    errors.push(format!("Failed to parse [power] config section: {}", e));
+                    PowerConfig::default()
+                }
+            },
+            None => PowerConfig::default(),
+        };
+
+        let remote = match table.get("remote") {
+            Some(v) => match v.clone().try_into::<RemoteConfig>() {
+                Ok(mut remote) => {
+                    if let Err(e) = crate::cache::parse_size(&remote.max_size) {
+                        errors.push(format!("Invalid [remote] max-size: {}, using 50MB", e));
+                        remote.max_size = default_remote_max_size();
+                    }
+                    remote
+                }
+                Err(e) => {
+                    errors.push(format!("Failed to parse [remote] config section: {}", e));
+                    RemoteConfig::default()
+                }
+            },
+            None => RemoteConfig::default(),
+        };
+
+        let fetch = match table.get("fetch") {
+            Some(v) => match v.clone().try_into::<FetchConfig>() {
+                Ok(fetch) => match fetch.validate() {
+                    Ok(()) => Some(fetch),
+                    Err(e) => {
+                        errors.push(format!("Invalid [fetch] config section: {}", e));
+                        None
+                    }
+                },
+                Err(e) => {
+                    errors.push(format!("Failed to parse [fetch] config section: {}", e));
+                    None
+                }
+            },
+            None => None,
+        };
+
+        let mut groups = HashMap::new();
+        if let Some(v) = table.get("group") {
+            match v.as_table() {
+                Some(sections) => {
+                    for (key, value) in sections {
+                        let Ok(gid) = key.parse::<usize>() else {
+                            errors.push(format!(
+                                "Invalid [group.{}] config section: groups are numbered from 0",
+                                key
+                            ));
+                            continue;
+                        };
+                        match section(value) {
+                            Ok(cfg) => {
+                                groups.insert(gid, cfg);
+                            }
+                            Err(e) => errors.push(format!(
+                                "Failed to parse [group.{}] config section: {}",
+                                key, e
+                            )),
+                        }
+                    }
+                }
+                None => errors.push("[group] must contain [group.N] tables".to_string()),
+            }
+        }
+
         // Collect remaining sections as per-output configs
         let mut outputs = HashMap::new();
-        let mut config_errors = Vec::new();
+        let reserved = [
+            "global",
+            "any",
+            "overlay",
+            "playlist",
+            "group",
+            "transition",
+            "kiosk",
+            "auto-transition",
+            "power",
+            "remote",
+            "fetch",
+            "workspace",
+        ];
         for (key, value) in &table {
-            if key != "global" && key != "any" {
-                match value.clone().try_into::<PartialOutputConfig>() {
+            if !reserved.contains(&key.as_str()) {
+                match section(value) {
                     Ok(cfg) => {
                         outputs.insert(key.clone(), cfg);
                     }
                     Err(e) => {
-                        let error_msg =
-                            format!("Failed to parse output config for [{}]: {}", key, e);
-                        tracing::error!("{}", error_msg);
-                        config_errors.push(error_msg);
+                        errors.push(format!("Failed to parse output config for [{}]: {}", key, e));
                     }
                 }
             }
         }
+        errors.extend(mirror_cycles(&outputs));
 
-        if !config_errors.is_empty() {
-            tracing::warn!(
-                "{} output configuration section(s) had errors and were skipped",
-                config_errors.len()
-            );
-        }
+        Ok((
+            Config {
+                global,
+                any,
+                overlay,
+                groups,
+                playlists,
+                transitions,
+                kiosk,
+                auto_transition,
+                power,
+                remote,
+                fetch,
+                workspaces,
+                outputs,
+            },
+            errors,
+        ))
+    }
+
+    /// Every http(s) `path` and `sources` entry in the config, sorted
+    pub fn remote_urls(&self) -> Vec<String> {
+        let sections = std::iter::once(&self.any)
+            .chain(self.outputs.values())
+            .chain(self.groups.values());
+        let mut urls: Vec<String> = sections
+            .flat_map(|section| {
+                let sources = section.sources.iter().flatten().map(|s| &s.path);
+                section.path.iter().chain(sources)
+            })
+            .filter(|path| crate::remote::is_url(path))
+            .map(|path| path.to_string_lossy().to_string())
+            .collect();
+        urls.sort();
+        urls.dedup();
+        urls
+    }
 
-        tracing::info!("Loaded config with {} output overrides", outputs.len());
+    /// Whether Kaleidux should manage this output at all.
+    /// Checked before creating surfaces/renderers, both at startup and on hotplug.
+    pub fn is_output_enabled(&self, identity: &OutputIdentity) -> bool {
+        let ignored = self
+            .global
+            .ignore
+            .iter()
+            .any(|key| OutputMatcher::parse(key).score(identity).is_some());
+        !ignored && self.get_config_for_output(identity).enabled
+    }
 
-        Ok(Config {
-            global,
-            any,
-            outputs,
+    /// Playlist whose `active` window (on one of its `days`) contains `now` for this
+    /// output, if any. Overlapping rules resolve to the alphabetically first playlist name.
+    pub fn scheduled_playlist(
+        &self,
+        identity: &OutputIdentity,
+        now: chrono::NaiveDateTime,
+    ) -> Option<String> {
+        let mut names: Vec<&String> = self.playlists.keys().collect();
+        names.sort();
+        names
+            .into_iter()
+            .find(|name| {
+                let rule = &self.playlists[*name];
+                let applies = rule.outputs.is_empty()
+                    || rule
+                        .outputs
+                        .iter()
+                        .any(|key| OutputMatcher::parse(key).score(identity).is_some());
+                applies && rule.is_active(now)
+            })
+            .cloned()
+    }
+
+    /// The `[workspace.NAME]` rule for this output while `workspace` is active on it
+    pub fn workspace_rule(
+        &self,
+        identity: &OutputIdentity,
+        workspace: &str,
+    ) -> Option<&WorkspaceRule> {
+        self.workspaces.get(workspace).filter(|rule| {
+            rule.outputs.is_empty()
+                || rule
+                    .outputs
+                    .iter()
+                    .any(|key| OutputMatcher::parse(key).score(identity).is_some())
         })
     }
 
-    pub fn get_config_for_output(&self, name: &str, description: &str) -> OutputConfig {
-        // 1. Start with global defaults
-        let mut final_config = PartialOutputConfig {
+    /// monitor-behavior group `name` belongs to: its index in `grouped`, or 0 for every
+    /// output in synchronized mode
+    pub fn group_index(&self, name: &str) -> Option<usize> {
+        match &self.global.monitor_behavior {
+            MonitorBehavior::Independent => None,
+            MonitorBehavior::Synchronized => Some(0),
+            MonitorBehavior::Grouped(groups) => {
+                groups.iter().position(|group| group.iter().any(|n| n == name))
+            }
+        }
+    }
+
+    /// Settings a group's shared queue and timer run on, from `[global]`, `[any]` and
+    /// `[group.N]` only. None if the group has no section of its own.
+    pub fn get_config_for_group(&self, gid: usize) -> Option<OutputConfig> {
+        let group = self.groups.get(&gid)?;
+        let mut final_config = self.base_config();
+        final_config.merge(group);
+        Some(self.finish_config(final_config))
+    }
+
+    /// The transition an output's `transition = "NAME"` would get, None if unknown
+    pub fn transition_named(&self, name: &str) -> Option<crate::shaders::Transition> {
+        lookup_transition(name, &self.transitions)
+    }
+
+    /// Replaces the output sections matching `identity` with the ones `newer` has for it,
+    /// sections added or removed included (`kldctl reload --output`)
+    pub fn take_output_sections(&mut self, newer: &Config, identity: &OutputIdentity) {
+        let matches = |key: &String| OutputMatcher::parse(key).score(identity).is_some();
+        self.outputs.retain(|key, _| !matches(key));
+        for (key, section) in &newer.outputs {
+            if matches(key) {
+                self.outputs.insert(key.clone(), section.clone());
+            }
+        }
+    }
+
+    pub fn get_config_for_output(&self, identity: &OutputIdentity) -> OutputConfig {
+        // 1-2. Global defaults and [any]
+        let mut final_config = self.base_config();
+
+        // 3. Merge the [group.N] section of the output's group
+        if let Some(group) = self
+            .group_index(&identity.name)
+            .and_then(|gid| self.groups.get(&gid))
+        {
+            final_config.merge(group);
+        }
+
+        // 4. Merge every matching output section, least specific first so the
+        //    most specific match wins. Ties are broken by key for a stable order.
+        let mut matched: Vec<(u8, &String, &PartialOutputConfig)> = self
+            .outputs
+            .iter()
+            .filter_map(|(key, val)| {
+                OutputMatcher::parse(key)
+                    .score(identity)
+                    .map(|score| (score, key, val))
+            })
+            .collect();
+        matched.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(b.1)));
+
+        for (_, key, output_val) in matched {
+            tracing::debug!("[CONFIG] {}: applying section [{}]", identity.name, key);
+            final_config.merge(output_val);
+        }
+
+        self.finish_config(final_config)
+    }
+
+    /// Global defaults with the [any] fallback merged on top
+    fn base_config(&self) -> PartialOutputConfig {
+        let mut base = PartialOutputConfig {
             path: None,
+            sources: None,
+            max_depth: None,
+            include: None,
+            exclude: None,
             duration: None,
             video_ratio: self.global.video_ratio,
             transition: None,
+            transition_randomize: None,
             transition_time: self.global.transition_time,
+            transition_fps_cap: None,
             volume: self.global.volume,
             sorting: self.global.sorting,
             layer: None,
             default_playlist: self.global.default_playlist.clone(),
+            tags: None,
+            grayscale: None,
+            max_luminance: None,
+            transparent: None,
+            namespace: None,
+            exclusive_zone: None,
+            keyboard_interactivity: None,
+            anchor: None,
+            margin: None,
+            size: None,
+            enabled: None,
+            mirror: None,
+            gpu: None,
+            random_crop: None,
+            safe_area: None,
+            fit_mode: None,
+            fit_color: None,
+            preprocess: None,
+            max_image_scale: None,
+            on_change_sound: None,
+            on_change_volume: None,
+            on_change_command: None,
+            palette: None,
+            palette_command: None,
+            video_start: None,
+            video_end: None,
+            clips: None,
+            sequence_fps: None,
+            shader_fps: None,
+            fallback: None,
         };
+        base.merge(&self.any);
+        base
+    }
 
-        // 2. Merge [any] fallback
-        final_config.merge(&self.any);
-
-        // 3. Match specific output
-        let mut matched = None;
-        for (key, val) in &self.outputs {
-            if let Some(stripped) = key.strip_prefix("re:") {
-                if let Ok(re) = Regex::new(stripped) {
-                    if re.is_match(description) {
-                        matched = Some(val);
-                        break;
-                    }
-                }
-            } else if key == name {
-                matched = Some(val);
-                break;
+    /// Profile overrides win over everything else
+    fn finish_config(&self, mut final_config: PartialOutputConfig) -> OutputConfig {
+        if self.global.profile == Profile::Eink {
+            final_config.transition_time = Some(0);
+            final_config.video_ratio = Some(0);
+            final_config.grayscale = Some(true);
+            if final_config.duration.is_none() {
+                final_config.duration = Some(EINK_DEFAULT_DURATION);
             }
         }
 
-        if let Some(output_val) = matched {
-            final_config.merge(output_val);
-        }
-
         final_config.into_output_config()
     }
 }
 
+/// A `[transition.NAME]` table: a builtin with its parameters (`type = "cube"`, ...) or,
+/// without a `type`, a custom shader (`shader = "mywipe"`, `params = { ... }`)
+fn parse_transition_definition(value: &toml::Value) -> Result<crate::shaders::Transition> {
+    let mut table = value
+        .as_table()
+        .cloned()
+        .ok_or_else(|| anyhow::anyhow!("expected a table"))?;
+    table
+        .entry("type")
+        .or_insert_with(|| toml::Value::from("custom"));
+    let transition: crate::shaders::Transition = toml::Value::Table(table).try_into()?;
+    if transition == crate::shaders::Transition::Random {
+        anyhow::bail!("a named transition cannot be random");
+    }
+    if transition == crate::shaders::Transition::Auto {
+        anyhow::bail!("a named transition cannot be auto");
+    }
+    Ok(transition)
+}
+
+/// What `transition = "NAME"` stands for: a `[transition.NAME]` definition, a builtin,
+/// `random` or `auto`
+fn lookup_transition(
+    name: &str,
+    named: &HashMap<String, crate::shaders::Transition>,
+) -> Option<crate::shaders::Transition> {
+    if let Some(transition) = named.get(name) {
+        return Some(transition.clone());
+    }
+    let builtin = name.to_lowercase();
+    (matches!(builtin.as_str(), "random" | "auto")
+        || kaleidux_common::RANDOM_TRANSITIONS.contains(&&*builtin))
+    .then(|| crate::shaders::Transition::from_name(&builtin))
+}
+
+/// Replaces `transition = "NAME"` in an output section with the transition it names:
+/// a `[transition.NAME]` definition first, then a builtin (`"fade"`, `"random"`,
+/// `"auto"`, ...)
+fn resolve_transition_name(
+    value: &toml::Value,
+    named: &HashMap<String, crate::shaders::Transition>,
+) -> std::result::Result<toml::Value, String> {
+    let Some(name) = value.get("transition").and_then(|t| t.as_str()) else {
+        return Ok(value.clone());
+    };
+    let Some(transition) = lookup_transition(name, named) else {
+        return Err(format!(
+            "unknown transition '{}' (define it as [transition.{}])",
+            name, name
+        ));
+    };
+    let mut value = value.clone();
+    if let Some(table) = value.as_table_mut() {
+        let resolved = toml::Value::try_from(&transition).map_err(|e| e.to_string())?;
+        table.insert("transition".to_string(), resolved);
+    }
+    Ok(value)
+}
+
 impl PartialOutputConfig {
     fn merge(&mut self, other: &Self) {
         if other.path.is_some() {
             self.path = other.path.clone();
+            // A section setting only `path` replaces inherited sources
+            if other.sources.is_none() {
+                self.sources = None;
+            }
+        }
+        if other.sources.is_some() {
+            self.sources = other.sources.clone();
+        }
+        if other.max_depth.is_some() {
+            self.max_depth = other.max_depth;
+        }
+        if other.include.is_some() {
+            self.include = other.include.clone();
+        }
+        if other.exclude.is_some() {
+            self.exclude = other.exclude.clone();
         }
         if other.duration.is_some() {
             self.duration = other.duration;
@@ -260,9 +2034,15 @@ impl PartialOutputConfig {
         if other.transition.is_some() {
             self.transition = other.transition.clone();
         }
+        if other.transition_randomize.is_some() {
+            self.transition_randomize = other.transition_randomize.clone();
+        }
         if other.transition_time.is_some() {
             self.transition_time = other.transition_time;
         }
+        if other.transition_fps_cap.is_some() {
+            self.transition_fps_cap = other.transition_fps_cap;
+        }
         if other.volume.is_some() {
             self.volume = other.volume;
         }
@@ -275,19 +2055,171 @@ impl PartialOutputConfig {
         if other.default_playlist.is_some() {
             self.default_playlist = other.default_playlist.clone();
         }
+        if other.tags.is_some() {
+            self.tags = other.tags.clone();
+        }
+        if other.grayscale.is_some() {
+            self.grayscale = other.grayscale;
+        }
+        if other.max_luminance.is_some() {
+            self.max_luminance = other.max_luminance;
+        }
+        if other.transparent.is_some() {
+            self.transparent = other.transparent;
+        }
+        if other.namespace.is_some() {
+            self.namespace = other.namespace.clone();
+        }
+        if other.exclusive_zone.is_some() {
+            self.exclusive_zone = other.exclusive_zone;
+        }
+        if other.keyboard_interactivity.is_some() {
+            self.keyboard_interactivity = other.keyboard_interactivity;
+        }
+        if other.anchor.is_some() {
+            self.anchor = other.anchor.clone();
+        }
+        if other.margin.is_some() {
+            self.margin = other.margin;
+        }
+        if other.size.is_some() {
+            self.size = other.size;
+        }
+        if other.enabled.is_some() {
+            self.enabled = other.enabled;
+        }
+        if other.mirror.is_some() {
+            self.mirror = other.mirror.clone();
+        }
+        if other.gpu.is_some() {
+            self.gpu = other.gpu.clone();
+        }
+        if other.random_crop.is_some() {
+            self.random_crop = other.random_crop;
+        }
+        if other.safe_area.is_some() {
+            self.safe_area = other.safe_area;
+        }
+        if other.fit_mode.is_some() {
+            self.fit_mode = other.fit_mode;
+        }
+        if other.fit_color.is_some() {
+            self.fit_color = other.fit_color;
+        }
+        if other.preprocess.is_some() {
+            self.preprocess = other.preprocess.clone();
+        }
+        if other.max_image_scale.is_some() {
+            self.max_image_scale = other.max_image_scale;
+        }
+        if other.on_change_sound.is_some() {
+            self.on_change_sound = other.on_change_sound.clone();
+        }
+        if other.on_change_volume.is_some() {
+            self.on_change_volume = other.on_change_volume;
+        }
+        if other.on_change_command.is_some() {
+            self.on_change_command = other.on_change_command.clone();
+        }
+        if other.palette.is_some() {
+            self.palette = other.palette;
+        }
+        if other.palette_command.is_some() {
+            self.palette_command = other.palette_command.clone();
+        }
+        if other.video_start.is_some() {
+            self.video_start = other.video_start;
+        }
+        if other.video_end.is_some() {
+            self.video_end = other.video_end;
+        }
+        // Clips accumulate, so [any] and the output's own section can each name files
+        if let Some(clips) = &other.clips {
+            self.clips
+                .get_or_insert_with(HashMap::new)
+                .extend(clips.iter().map(|(k, v)| (k.clone(), *v)));
+        }
+        if other.sequence_fps.is_some() {
+            self.sequence_fps = other.sequence_fps;
+        }
+        if other.shader_fps.is_some() {
+            self.shader_fps = other.shader_fps;
+        }
+        if other.fallback.is_some() {
+            self.fallback = other.fallback;
+        }
     }
 
     fn into_output_config(self) -> OutputConfig {
+        let sources: Vec<Source> = self
+            .sources
+            .unwrap_or_default()
+            .into_iter()
+            .map(|source| Source {
+                weight: source.weight.max(0.0),
+                ..source
+            })
+            .collect();
         OutputConfig {
-            path: self.path,
+            path: sources.first().map(|s| s.path.clone()).or(self.path),
+            sources,
+            // 0 would leave nothing to find, like leaving it unset
+            max_depth: self.max_depth.filter(|&depth| depth > 0),
+            include: self.include.unwrap_or_default(),
+            exclude: self.exclude.unwrap_or_default(),
             duration: self.duration.unwrap_or_else(default_duration),
             video_ratio: self.video_ratio.unwrap_or(50),
             transition: self.transition.unwrap_or(crate::shaders::Transition::Fade),
+            transition_randomize: self.transition_randomize.unwrap_or_default(),
             transition_time: self.transition_time.unwrap_or(1000),
+            // 0 means no cap, like leaving it unset
+            transition_fps_cap: self.transition_fps_cap.filter(|&fps| fps > 0),
             volume: self.volume.unwrap_or(100),
             sorting: self.sorting.unwrap_or_default(),
             layer: self.layer.unwrap_or_default(),
             default_playlist: self.default_playlist,
+            tags: self
+                .tags
+                .unwrap_or_default()
+                .iter()
+                .map(|tag| tag.trim().to_lowercase())
+                .collect(),
+            grayscale: self.grayscale.unwrap_or(false),
+            max_luminance: self.max_luminance.map(|cap| cap.clamp(0.0, 1.0)),
+            transparent: self.transparent.unwrap_or(false),
+            namespace: self.namespace.unwrap_or_else(default_namespace),
+            exclusive_zone: self.exclusive_zone.unwrap_or(-1),
+            keyboard_interactivity: self.keyboard_interactivity.unwrap_or_default(),
+            anchor: self.anchor.unwrap_or_else(default_anchor),
+            margin: self.margin.unwrap_or_default(),
+            size: self.size.unwrap_or_default(),
+            enabled: self.enabled.unwrap_or(true),
+            mirror: self.mirror,
+            gpu: self.gpu,
+            random_crop: self.random_crop.unwrap_or_else(default_random_crop),
+            safe_area: self.safe_area.unwrap_or_default(),
+            fit_mode: self.fit_mode.unwrap_or_default(),
+            fit_color: self.fit_color,
+            preprocess: self.preprocess,
+            // Below 1x the image would be blurrier than the screen
+            max_image_scale: self.max_image_scale.map(|scale| scale.max(1.0)),
+            on_change_sound: self.on_change_sound,
+            on_change_volume: self.on_change_volume.unwrap_or(100),
+            on_change_command: self.on_change_command,
+            palette: self.palette.unwrap_or(false),
+            palette_command: self.palette_command,
+            video_start: self.video_start,
+            video_end: self.video_end,
+            clips: self.clips.unwrap_or_default(),
+            sequence_fps: self
+                .sequence_fps
+                .unwrap_or_else(default_sequence_fps)
+                .clamp(1, 240),
+            shader_fps: self
+                .shader_fps
+                .unwrap_or_else(default_shader_fps)
+                .clamp(1, 240),
+            fallback: self.fallback.unwrap_or_default(),
         }
     }
 }
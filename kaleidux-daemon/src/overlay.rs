@@ -0,0 +1,526 @@
+use ab_glyph::{point, Font, FontVec, PxScale, ScaleFont};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info, warn};
+
+/// Fonts tried in order when a widget does not specify one.
+const FALLBACK_FONTS: &[&str] = &[
+    "/usr/share/fonts/TTF/DejaVuSans.ttf",
+    "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+    "/usr/share/fonts/dejavu/DejaVuSans.ttf",
+    "/usr/share/fonts/noto/NotoSans-Regular.ttf",
+    "/usr/share/fonts/truetype/noto/NotoSans-Regular.ttf",
+    "/usr/share/fonts/google-noto/NotoSans-Regular.ttf",
+    "/usr/share/fonts/liberation/LiberationSans-Regular.ttf",
+    "/usr/share/fonts/truetype/liberation/LiberationSans-Regular.ttf",
+];
+
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct OverlayConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub widgets: Vec<WidgetConfig>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum WidgetKind {
+    /// Current time, formatted with `format` (default `%H:%M`)
+    Clock,
+    /// Current date, formatted with `format` (default `%A, %d %B`)
+    Date,
+    /// Static text from `text`
+    Text,
+    /// Stdout of `command`, re-run every `interval`
+    Command,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum WidgetPosition {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    #[default]
+    BottomRight,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct WidgetConfig {
+    pub kind: WidgetKind,
+    /// strftime-style format for clock/date widgets
+    pub format: Option<String>,
+    /// Static text for text widgets
+    pub text: Option<String>,
+    /// Shell command for command widgets (run via `sh -c`)
+    pub command: Option<String>,
+    /// How often command widgets are refreshed
+    #[serde(with = "humantime_serde", default = "default_command_interval")]
+    pub interval: Duration,
+    /// Path to a TTF/OTF font, falls back to common system fonts
+    pub font: Option<PathBuf>,
+    #[serde(default = "default_size")]
+    pub size: f32,
+    #[serde(default = "default_color")]
+    pub color: String,
+    #[serde(default)]
+    pub position: WidgetPosition,
+    /// Distance from the anchored screen edges in pixels [x, y]
+    #[serde(default = "default_margin")]
+    pub margin: [i32; 2],
+    #[serde(default = "default_shadow")]
+    pub shadow: bool,
+    #[serde(default = "default_shadow_color")]
+    pub shadow_color: String,
+    #[serde(default = "default_shadow_offset")]
+    pub shadow_offset: [i32; 2],
+    /// Restrict the widget to these outputs (all outputs if unset)
+    pub outputs: Option<Vec<String>>,
+}
+
+fn default_command_interval() -> Duration {
+    Duration::from_secs(3600)
+}
+
+fn default_size() -> f32 {
+    48.0
+}
+
+fn default_color() -> String {
+    "#ffffffff".to_string()
+}
+
+fn default_margin() -> [i32; 2] {
+    [32, 32]
+}
+
+fn default_shadow() -> bool {
+    true
+}
+
+fn default_shadow_color() -> String {
+    "#000000b0".to_string()
+}
+
+fn default_shadow_offset() -> [i32; 2] {
+    [2, 2]
+}
+
+/// Parses `#RRGGBB` or `#RRGGBBAA` into RGBA bytes.
+pub fn parse_color(s: &str) -> Option<[u8; 4]> {
+    let hex = s.trim().trim_start_matches('#');
+    if hex.len() != 6 && hex.len() != 8 {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    let alpha = if hex.len() == 8 { channel(6)? } else { 255 };
+    Some([channel(0)?, channel(2)?, channel(4)?, alpha])
+}
+
+/// A rasterized widget placed on an output, in pixels from the top-left corner.
+/// `data` is straight (non-premultiplied) RGBA8.
+#[derive(Clone)]
+pub struct WidgetBitmap {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub data: Arc<Vec<u8>>,
+}
+
+struct RasterizedText {
+    width: u32,
+    height: u32,
+    data: Arc<Vec<u8>>,
+}
+
+struct Widget {
+    config: WidgetConfig,
+    font: Arc<FontVec>,
+    color: [u8; 4],
+    shadow_color: [u8; 4],
+    /// Text the current raster was built from (None until first refresh)
+    text: Option<String>,
+    raster: Option<RasterizedText>,
+    last_refresh: Option<Instant>,
+    /// Latest command output, written by the background task
+    command_result: Arc<parking_lot::Mutex<Option<String>>>,
+    command_running: Arc<std::sync::atomic::AtomicBool>,
+}
+
+/// Owns the configured text widgets and keeps their rasterized bitmaps up to date.
+pub struct OverlayManager {
+    widgets: Vec<Widget>,
+    /// Bumped whenever any widget bitmap changes, renderers compare against it
+    generation: u64,
+}
+
+impl OverlayManager {
+    pub fn new(config: &OverlayConfig) -> Self {
+        let mut widgets = Vec::new();
+        if !config.enabled {
+            return Self {
+                widgets,
+                generation: 0,
+            };
+        }
+
+        let mut font_cache: HashMap<Option<PathBuf>, Option<Arc<FontVec>>> = HashMap::new();
+        for (idx, widget_config) in config.widgets.iter().enumerate() {
+            let font = font_cache
+                .entry(widget_config.font.clone())
+                .or_insert_with(|| load_font(widget_config.font.as_ref()))
+                .clone();
+            let Some(font) = font else {
+                error!(
+                    "[OVERLAY] Widget #{} ({:?}): No usable font found, skipping",
+                    idx, widget_config.kind
+                );
+                continue;
+            };
+
+            let color = parse_color(&widget_config.color).unwrap_or_else(|| {
+                warn!(
+                    "[OVERLAY] Widget #{}: Invalid color '{}', using white",
+                    idx, widget_config.color
+                );
+                [255, 255, 255, 255]
+            });
+            let shadow_color = parse_color(&widget_config.shadow_color).unwrap_or_else(|| {
+                warn!(
+                    "[OVERLAY] Widget #{}: Invalid shadow-color '{}', using black",
+                    idx, widget_config.shadow_color
+                );
+                [0, 0, 0, 176]
+            });
+
+            if widget_config.kind == WidgetKind::Command && widget_config.command.is_none() {
+                warn!(
+                    "[OVERLAY] Widget #{}: Command widget without 'command', skipping",
+                    idx
+                );
+                continue;
+            }
+
+            let mut widget_config = widget_config.clone();
+            if let Some(format) = widget_config.format.as_deref() {
+                if !is_valid_time_format(format) {
+                    warn!(
+                        "[OVERLAY] Widget #{}: Invalid format '{}', using the default",
+                        idx, format
+                    );
+                    widget_config.format = None;
+                }
+            }
+
+            widgets.push(Widget {
+                config: widget_config,
+                font,
+                color,
+                shadow_color,
+                text: None,
+                raster: None,
+                last_refresh: None,
+                command_result: Arc::new(parking_lot::Mutex::new(None)),
+                command_running: Arc::new(std::sync::atomic::AtomicBool::new(false)),
+            });
+        }
+
+        if !widgets.is_empty() {
+            info!("[OVERLAY] {} text widget(s) enabled", widgets.len());
+        }
+
+        Self {
+            widgets,
+            generation: 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.widgets.is_empty()
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Refreshes widget text and re-rasterizes anything that changed.
+    /// Returns true if any bitmap changed since the last call.
+    pub fn tick(&mut self) -> bool {
+        let now = Instant::now();
+        let mut changed = false;
+
+        for widget in &mut self.widgets {
+            let new_text = match widget.config.kind {
+                WidgetKind::Clock | WidgetKind::Date => {
+                    // Time widgets only need re-formatting once a second
+                    if widget
+                        .last_refresh
+                        .map(|t| now.duration_since(t) < Duration::from_secs(1))
+                        .unwrap_or(false)
+                    {
+                        continue;
+                    }
+                    widget.last_refresh = Some(now);
+                    let default_format = if widget.config.kind == WidgetKind::Clock {
+                        "%H:%M"
+                    } else {
+                        "%A, %d %B"
+                    };
+                    let format = widget.config.format.as_deref().unwrap_or(default_format);
+                    chrono::Local::now().format(format).to_string()
+                }
+                WidgetKind::Text => widget.config.text.clone().unwrap_or_default(),
+                WidgetKind::Command => {
+                    let due = widget
+                        .last_refresh
+                        .map(|t| now.duration_since(t) >= widget.config.interval)
+                        .unwrap_or(true);
+                    if due {
+                        widget.last_refresh = Some(now);
+                        spawn_command(widget);
+                    }
+                    match widget.command_result.lock().take() {
+                        Some(output) => output,
+                        None => continue,
+                    }
+                }
+            };
+
+            if widget.text.as_deref() != Some(new_text.as_str()) {
+                debug!(
+                    "[OVERLAY] {:?} widget text -> {:?}",
+                    widget.config.kind, new_text
+                );
+                widget.raster = rasterize(&new_text, widget);
+                widget.text = Some(new_text);
+                changed = true;
+            }
+        }
+
+        if changed {
+            self.generation = self.generation.wrapping_add(1);
+        }
+        changed
+    }
+
+    /// Lays out all widgets that apply to `output` on a surface of the given size.
+    pub fn layout(&self, output: &str, width: u32, height: u32) -> Vec<WidgetBitmap> {
+        let mut bitmaps = Vec::new();
+        for widget in &self.widgets {
+            if let Some(outputs) = &widget.config.outputs {
+                if !outputs.iter().any(|o| o == output) {
+                    continue;
+                }
+            }
+            let Some(raster) = &widget.raster else {
+                continue;
+            };
+
+            let (w, h) = (width as i32, height as i32);
+            let (bw, bh) = (raster.width as i32, raster.height as i32);
+            let [mx, my] = widget.config.margin;
+            let x = match widget.config.position {
+                WidgetPosition::TopLeft | WidgetPosition::Left | WidgetPosition::BottomLeft => mx,
+                WidgetPosition::Top | WidgetPosition::Center | WidgetPosition::Bottom => {
+                    (w - bw) / 2 + mx
+                }
+                _ => w - bw - mx,
+            };
+            let y = match widget.config.position {
+                WidgetPosition::TopLeft | WidgetPosition::Top | WidgetPosition::TopRight => my,
+                WidgetPosition::Left | WidgetPosition::Center | WidgetPosition::Right => {
+                    (h - bh) / 2 + my
+                }
+                _ => h - bh - my,
+            };
+
+            bitmaps.push(WidgetBitmap {
+                x,
+                y,
+                width: raster.width,
+                height: raster.height,
+                data: raster.data.clone(),
+            });
+        }
+        bitmaps
+    }
+}
+
+/// chrono panics while formatting a string with an unknown specifier, so
+/// formats are checked once up front.
+fn is_valid_time_format(format: &str) -> bool {
+    chrono::format::StrftimeItems::new(format)
+        .all(|item| !matches!(item, chrono::format::Item::Error))
+}
+
+fn load_font(path: Option<&PathBuf>) -> Option<Arc<FontVec>> {
+    let candidates: Vec<PathBuf> = match path {
+        Some(p) => vec![p.clone()],
+        None => FALLBACK_FONTS.iter().map(PathBuf::from).collect(),
+    };
+
+    for candidate in candidates {
+        let Ok(data) = std::fs::read(&candidate) else {
+            continue;
+        };
+        match FontVec::try_from_vec(data) {
+            Ok(font) => {
+                debug!("[OVERLAY] Loaded font {:?}", candidate);
+                return Some(Arc::new(font));
+            }
+            Err(e) => warn!("[OVERLAY] Failed to parse font {:?}: {}", candidate, e),
+        }
+    }
+    None
+}
+
+fn spawn_command(widget: &Widget) {
+    use std::sync::atomic::Ordering;
+    // Don't stack up invocations of a slow command
+    if widget.command_running.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let Some(command) = widget.config.command.clone() else {
+        widget.command_running.store(false, Ordering::SeqCst);
+        return;
+    };
+    let result = widget.command_result.clone();
+    let running = widget.command_running.clone();
+    tokio::spawn(async move {
+        match tokio::process::Command::new("sh")
+            .arg("-c")
+            .arg(&command)
+            .output()
+            .await
+        {
+            Ok(output) if output.status.success() => {
+                let text = String::from_utf8_lossy(&output.stdout)
+                    .trim_end()
+                    .to_string();
+                *result.lock() = Some(text);
+            }
+            Ok(output) => warn!(
+                "[OVERLAY] Command '{}' exited with {}: {}",
+                command,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+            Err(e) => warn!("[OVERLAY] Failed to run command '{}': {}", command, e),
+        }
+        running.store(false, Ordering::SeqCst);
+    });
+}
+
+/// Rasterizes (possibly multi-line) text with an optional drop shadow into straight RGBA8.
+fn rasterize(text: &str, widget: &Widget) -> Option<RasterizedText> {
+    if text.trim().is_empty() {
+        return None;
+    }
+
+    let scale = PxScale::from(widget.config.size.max(1.0));
+    let font = widget.font.as_scaled(scale);
+    let line_height = font.height() + font.line_gap();
+
+    // Lay out glyphs line by line
+    let mut glyphs = Vec::new();
+    let mut max_width = 0.0f32;
+    for (line_idx, line) in text.lines().enumerate() {
+        let baseline = font.ascent() + line_idx as f32 * line_height;
+        let mut caret = 0.0f32;
+        let mut prev = None;
+        for c in line.chars() {
+            let id = font.glyph_id(c);
+            if let Some(prev_id) = prev {
+                caret += font.kern(prev_id, id);
+            }
+            glyphs.push(id.with_scale_and_position(scale, point(caret, baseline)));
+            caret += font.h_advance(id);
+            prev = Some(id);
+        }
+        max_width = max_width.max(caret);
+    }
+    let line_count = text.lines().count().max(1);
+
+    let [sx, sy] = if widget.config.shadow {
+        widget.config.shadow_offset
+    } else {
+        [0, 0]
+    };
+    let pad = 2i32;
+    let origin_x = pad + (-sx).max(0);
+    let origin_y = pad + (-sy).max(0);
+    let width = (max_width.ceil() as i32 + 2 * pad + sx.abs()).max(1) as u32;
+    let height =
+        ((line_height * line_count as f32).ceil() as i32 + 2 * pad + sy.abs()).max(1) as u32;
+
+    // Glyph coverage at the text origin
+    let mut coverage = vec![0.0f32; (width * height) as usize];
+    for glyph in glyphs {
+        if let Some(outlined) = widget.font.outline_glyph(glyph) {
+            let bounds = outlined.px_bounds();
+            outlined.draw(|gx, gy, c| {
+                let px = bounds.min.x as i32 + gx as i32 + origin_x;
+                let py = bounds.min.y as i32 + gy as i32 + origin_y;
+                if px >= 0 && py >= 0 && (px as u32) < width && (py as u32) < height {
+                    let idx = (py as u32 * width + px as u32) as usize;
+                    coverage[idx] = (coverage[idx] + c).min(1.0);
+                }
+            });
+        }
+    }
+
+    // Composite text over its shadow
+    let mut data = vec![0u8; (width * height * 4) as usize];
+    let text_rgb = widget.color.map(|c| c as f32 / 255.0);
+    let shadow_rgb = widget.shadow_color.map(|c| c as f32 / 255.0);
+    for y in 0..height as i32 {
+        for x in 0..width as i32 {
+            let idx = (y as u32 * width + x as u32) as usize;
+            let text_a = coverage[idx] * text_rgb[3];
+            let shadow_a = if widget.config.shadow {
+                let (ox, oy) = (x - sx, y - sy);
+                if ox >= 0 && oy >= 0 && (ox as u32) < width && (oy as u32) < height {
+                    coverage[(oy as u32 * width + ox as u32) as usize] * shadow_rgb[3]
+                } else {
+                    0.0
+                }
+            } else {
+                0.0
+            };
+
+            let out_a = text_a + shadow_a * (1.0 - text_a);
+            if out_a <= 0.0 {
+                continue;
+            }
+            let px = &mut data[idx * 4..idx * 4 + 4];
+            for ch in 0..3 {
+                let v =
+                    (text_rgb[ch] * text_a + shadow_rgb[ch] * shadow_a * (1.0 - text_a)) / out_a;
+                px[ch] = (v * 255.0).round() as u8;
+            }
+            px[3] = (out_a * 255.0).round() as u8;
+        }
+    }
+
+    Some(RasterizedText {
+        width,
+        height,
+        data: Arc::new(data),
+    })
+}
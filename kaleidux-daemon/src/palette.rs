@@ -0,0 +1,335 @@
+//! `palette`: a 16-color scheme taken from each wallpaper once its transition has
+//! completed, written below the cache root as `colors.json` and in the formats pywal
+//! leaves in `~/.cache/wal` (`colors`, `colors.sh`, `colors.css`, `colors.Xresources`),
+//! so templates and scripts made for pywal can read it. `palette-command` runs after
+//! every write.
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, warn};
+
+/// Distinct colors median cut reduces the samples to; the scheme is built from them
+const DOMINANT: usize = 8;
+
+type Rgb = [u8; 3];
+
+/// Pixels of sRGB-encoded RGBA data on a grid of at most 64x64, enough to find the
+/// dominant colors while staying cheap on 4K frames. Fully transparent pixels are left
+/// out, so transparent wallpapers are judged by what they show.
+pub fn sample(data: &[u8], width: u32, height: u32, stride: u32) -> Vec<Rgb> {
+    let (step_x, step_y) = ((width / 64).max(1), (height / 64).max(1));
+    let mut samples = Vec::with_capacity(64 * 64);
+    for y in (0..height).step_by(step_y as usize) {
+        for x in (0..width).step_by(step_x as usize) {
+            let i = (y * stride + x * 4) as usize;
+            match data.get(i..i + 4) {
+                Some(px) if px[3] > 0 => samples.push([px[0], px[1], px[2]]),
+                _ => {}
+            }
+        }
+    }
+    samples
+}
+
+/// Median cut: the box of samples with the widest channel is split at its median
+/// until there are `count` boxes or nothing left to split. Returns each box's mean
+/// color and size, most common first.
+fn median_cut(mut samples: Vec<Rgb>, count: usize) -> Vec<(Rgb, usize)> {
+    // Widest channel of a box and its extent
+    let widest = |pixels: &[Rgb]| {
+        (0..3)
+            .map(|c| {
+                let (min, max) = pixels
+                    .iter()
+                    .fold((u8::MAX, 0), |(lo, hi), p| (lo.min(p[c]), hi.max(p[c])));
+                (c, max.saturating_sub(min))
+            })
+            .max_by_key(|&(_, extent)| extent)
+            .unwrap_or((0, 0))
+    };
+
+    let mut boxes: Vec<&mut [Rgb]> = vec![samples.as_mut_slice()];
+    while boxes.len() < count {
+        // Largest spread weighted by population, so big flat areas still get split
+        let Some((index, channel)) = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.len() > 1)
+            .map(|(i, b)| {
+                let (channel, extent) = widest(b);
+                (i, channel, extent as usize * b.len())
+            })
+            .filter(|&(_, _, score)| score > 0)
+            .max_by_key(|&(_, _, score)| score)
+            .map(|(i, channel, _)| (i, channel))
+        else {
+            break;
+        };
+        let pixels = boxes.swap_remove(index);
+        pixels.sort_unstable_by_key(|p| p[channel]);
+        let (low, high) = pixels.split_at_mut(pixels.len() / 2);
+        boxes.push(low);
+        boxes.push(high);
+    }
+
+    let mut colors: Vec<(Rgb, usize)> = boxes
+        .iter()
+        .filter(|b| !b.is_empty())
+        .map(|b| {
+            let mut sum = [0u64; 3];
+            for p in b.iter() {
+                for (sum, &value) in sum.iter_mut().zip(p) {
+                    *sum += value as u64;
+                }
+            }
+            let mean = sum.map(|s| (s / b.len() as u64) as u8);
+            (mean, b.len())
+        })
+        .collect();
+    colors.sort_by_key(|&(_, size)| std::cmp::Reverse(size));
+    colors
+}
+
+/// Relative luminance (0-1) of an sRGB color, for ordering from dark to light
+fn luminance(color: Rgb) -> f32 {
+    let [r, g, b] = color.map(|c| c as f32 / 255.0);
+    0.2126 * r + 0.7152 * g + 0.0722 * b
+}
+
+/// `a` moved towards `b` by `t` (0-1)
+fn mix(a: Rgb, b: Rgb, t: f32) -> Rgb {
+    [0, 1, 2].map(|c| (a[c] as f32 + (b[c] as f32 - a[c] as f32) * t).round() as u8)
+}
+
+const BLACK: Rgb = [0, 0, 0];
+const WHITE: Rgb = [255, 255, 255];
+
+fn hex(color: Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", color[0], color[1], color[2])
+}
+
+/// The extracted colors of one wallpaper
+pub struct Palette {
+    /// Most common first
+    pub dominant: Vec<Rgb>,
+    /// pywal's layout: 0 background, 1-6 accents, 7 foreground, 8-15 their
+    /// brighter variants
+    pub scheme: [Rgb; 16],
+}
+
+impl Palette {
+    /// None when there are no samples (a fully transparent wallpaper)
+    pub fn extract(samples: Vec<Rgb>) -> Option<Self> {
+        let colors = median_cut(samples, DOMINANT);
+        if colors.is_empty() {
+            return None;
+        }
+        let dominant: Vec<Rgb> = colors.iter().map(|&(color, _)| color).collect();
+
+        // Dark to light, stretched over all 8 slots when fewer colors were found
+        let mut by_luminance = dominant.clone();
+        by_luminance.sort_by(|a, b| luminance(*a).total_cmp(&luminance(*b)));
+        let slot = |i: usize| by_luminance[i * by_luminance.len() / DOMINANT];
+
+        let background = mix(slot(0), BLACK, 0.5);
+        let foreground = mix(slot(DOMINANT - 1), WHITE, 0.5);
+        let mut scheme = [BLACK; 16];
+        scheme[0] = background;
+        scheme[7] = foreground;
+        scheme[8] = mix(background, foreground, 0.3);
+        scheme[15] = foreground;
+        for i in 1..7 {
+            scheme[i] = slot(i);
+            scheme[i + 8] = mix(slot(i), WHITE, 0.2);
+        }
+        Some(Self { dominant, scheme })
+    }
+
+    fn background(&self) -> String {
+        hex(self.scheme[0])
+    }
+
+    fn foreground(&self) -> String {
+        hex(self.scheme[7])
+    }
+
+    fn json(&self, output: &str, wallpaper: &Path) -> Result<String> {
+        let colors: serde_json::Map<String, serde_json::Value> = self
+            .scheme
+            .iter()
+            .enumerate()
+            .map(|(i, &color)| (format!("color{}", i), hex(color).into()))
+            .collect();
+        let json = serde_json::json!({
+            "wallpaper": wallpaper,
+            "output": output,
+            "alpha": "100",
+            "special": {
+                "background": self.background(),
+                "foreground": self.foreground(),
+                "cursor": self.foreground(),
+            },
+            "colors": colors,
+            "dominant": self.dominant.iter().map(|&c| hex(c)).collect::<Vec<_>>(),
+        });
+        Ok(serde_json::to_string_pretty(&json)?)
+    }
+
+    fn plain(&self) -> String {
+        self.scheme.iter().map(|&c| hex(c) + "\n").collect()
+    }
+
+    fn shell(&self, wallpaper: &Path) -> String {
+        let mut out = format!(
+            "wallpaper={}\n\nbackground='{}'\nforeground='{}'\ncursor='{}'\n\n",
+            crate::preprocess::shell_quote(wallpaper),
+            self.background(),
+            self.foreground(),
+            self.foreground()
+        );
+        for (i, &color) in self.scheme.iter().enumerate() {
+            out += &format!("color{}='{}'\n", i, hex(color));
+        }
+        out
+    }
+
+    fn css(&self, wallpaper: &Path) -> String {
+        let mut out = format!(
+            ":root {{\n    --wallpaper: url({:?});\n\n    --background: {};\n    \
+             --foreground: {};\n    --cursor: {};\n\n",
+            wallpaper.to_string_lossy(),
+            self.background(),
+            self.foreground(),
+            self.foreground()
+        );
+        for (i, &color) in self.scheme.iter().enumerate() {
+            out += &format!("    --color{}: {};\n", i, hex(color));
+        }
+        out + "}\n"
+    }
+
+    fn xresources(&self) -> String {
+        let mut out = format!(
+            "*background: {}\n*foreground: {}\n*cursorColor: {}\n\n",
+            self.background(),
+            self.foreground(),
+            self.foreground()
+        );
+        for (i, &color) in self.scheme.iter().enumerate() {
+            out += &format!(
+                "*.color{}: {}\n*color{}: {}\n",
+                i,
+                hex(color),
+                i,
+                hex(color)
+            );
+        }
+        out
+    }
+
+    /// Writes every format below the cache root, each through a temp file so readers
+    /// never see a half-written scheme. Returns the path of `colors.json`.
+    fn write(&self, output: &str, wallpaper: &Path) -> Result<PathBuf> {
+        let dir = crate::cache::cache_root().context("Failed to get cache directory")?;
+        std::fs::create_dir_all(&dir)?;
+        let files = [
+            ("colors.json", self.json(output, wallpaper)?),
+            ("colors", self.plain()),
+            ("colors.sh", self.shell(wallpaper)),
+            ("colors.css", self.css(wallpaper)),
+            ("colors.Xresources", self.xresources()),
+        ];
+        for (name, contents) in files {
+            let path = dir.join(name);
+            let tmp = dir.join(format!(".tmp-{}", name));
+            std::fs::write(&tmp, contents).with_context(|| format!("Failed to write {:?}", tmp))?;
+            std::fs::rename(&tmp, &path)
+                .with_context(|| format!("Failed to replace {:?}", path))?;
+        }
+        Ok(dir.join("colors.json"))
+    }
+}
+
+/// Whether `name` directly below the cache root is one of the scheme's files, which
+/// cache eviction leaves alone
+pub fn is_palette_file(name: &str) -> bool {
+    name == "colors" || name.starts_with("colors.")
+}
+
+/// Extracts and writes the scheme of `wallpaper`, shown on `output`, then runs
+/// `command` with `{output}`, `{path}`, `{colors}` (the 16 colors as separate words)
+/// and `{json}` replaced. Returns immediately; the work happens in the background.
+pub fn on_change(output: &str, wallpaper: &Path, samples: Vec<Rgb>, command: Option<String>) {
+    let output = output.to_string();
+    let wallpaper = wallpaper.to_path_buf();
+    tokio::spawn(async move {
+        let scheme = {
+            let (output, wallpaper) = (output.clone(), wallpaper.clone());
+            tokio::task::spawn_blocking(move || {
+                let Some(palette) = Palette::extract(samples) else {
+                    return Ok(None);
+                };
+                let json = palette.write(&output, &wallpaper)?;
+                Ok::<_, anyhow::Error>(Some((palette, json)))
+            })
+            .await
+        };
+        let (palette, json) = match scheme {
+            Ok(Ok(Some(scheme))) => scheme,
+            Ok(Ok(None)) => {
+                debug!("[PALETTE] {}: Nothing visible to take colors from", output);
+                return;
+            }
+            Ok(Err(e)) => {
+                warn!("[PALETTE] {}: {:#}", output, e);
+                return;
+            }
+            Err(e) => {
+                warn!("[PALETTE] {}: Extraction task failed: {}", output, e);
+                return;
+            }
+        };
+        info!(
+            "[PALETTE] {}: {} -> background {}, foreground {}",
+            output,
+            wallpaper.display(),
+            palette.background(),
+            palette.foreground()
+        );
+
+        let Some(command) = command else {
+            return;
+        };
+        // Quoted, as a bare "#" would start a shell comment
+        let colors: Vec<String> = palette
+            .scheme
+            .iter()
+            .map(|&c| format!("'{}'", hex(c)))
+            .collect();
+        let cmdline = command
+            .replace(
+                "{output}",
+                &crate::preprocess::shell_quote(Path::new(&output)),
+            )
+            .replace("{path}", &crate::preprocess::shell_quote(&wallpaper))
+            .replace("{json}", &crate::preprocess::shell_quote(&json))
+            .replace("{colors}", &colors.join(" "));
+        debug!("[PALETTE] {}: Running {}", output, cmdline);
+        match tokio::process::Command::new("sh")
+            .arg("-c")
+            .arg(&cmdline)
+            .stdin(std::process::Stdio::null())
+            .status()
+            .await
+        {
+            Ok(status) if !status.success() => {
+                warn!(
+                    "[PALETTE] {}: palette-command exited with {}",
+                    output, status
+                )
+            }
+            Ok(_) => {}
+            Err(e) => warn!("[PALETTE] {}: Failed to run palette-command: {}", output, e),
+        }
+    });
+}
@@ -0,0 +1,148 @@
+use crate::queue::SmartQueue;
+use anyhow::{Context, Result};
+use std::path::{Component, Path, PathBuf};
+
+/// Entries of a playlist file that resolved to wallpapers, and the ones that did not
+pub struct Import {
+    pub paths: Vec<PathBuf>,
+    /// Entries as written: missing or unsupported files, and globs matching nothing
+    pub skipped: Vec<String>,
+}
+
+/// Reads a plain list (one path per line) or an M3U playlist; `#` lines are comments, so
+/// `#EXTM3U`/`#EXTINF` are skipped. Relative entries are taken from the file's folder,
+/// `~/` from home. Entries with `*`, `?` or `[...]` expand to the wallpapers they match,
+/// `**` crossing any number of folders.
+pub fn read(file: &Path) -> Result<Import> {
+    let text = std::fs::read_to_string(file)
+        .with_context(|| format!("Failed to read {}", file.display()))?;
+    let base = file.parent().unwrap_or(Path::new("/"));
+    let mut import = Import {
+        paths: Vec::new(),
+        skipped: Vec::new(),
+    };
+    let mut seen = std::collections::HashSet::new();
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let path = resolve(base, line.strip_prefix("file://").unwrap_or(line));
+        // An existing file wins over reading its name as a glob ("Clip [4K].mkv")
+        let found = if SmartQueue::get_content_type(&path).is_some() {
+            vec![path]
+        } else if line.contains(['*', '?', '[']) {
+            // A broken glob is skipped like an entry matching nothing
+            expand(&path).unwrap_or_else(|e| {
+                tracing::warn!(
+                    "[PLAYLIST] Skipping '{}' in {}: {}",
+                    line,
+                    file.display(),
+                    e
+                );
+                Vec::new()
+            })
+        } else {
+            Vec::new()
+        };
+        if found.is_empty() {
+            import.skipped.push(line.to_string());
+        }
+        for path in found {
+            if seen.insert(path.clone()) {
+                import.paths.push(path);
+            }
+        }
+    }
+    Ok(import)
+}
+
+/// Writes `paths` one per line, as M3U if `file` ends in `.m3u` or `.m3u8`
+pub fn write(file: &Path, paths: &[PathBuf]) -> Result<()> {
+    let m3u = file
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("m3u") || e.eq_ignore_ascii_case("m3u8"));
+    let mut text = String::new();
+    if m3u {
+        text.push_str("#EXTM3U\n");
+    }
+    for path in paths {
+        text.push_str(&path.to_string_lossy());
+        text.push('\n');
+    }
+    std::fs::write(file, text).with_context(|| format!("Failed to write {}", file.display()))
+}
+
+fn resolve(base: &Path, entry: &str) -> PathBuf {
+    match entry.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir().unwrap_or_default().join(rest),
+        None => base.join(entry),
+    }
+}
+
+/// Wallpapers matching the glob `pattern`, in walk order
+fn expand(pattern: &Path) -> Result<Vec<PathBuf>> {
+    // Components up to the first wildcard are a plain folder to start from
+    let mut root = PathBuf::new();
+    let mut rest = Vec::new();
+    for component in pattern.components() {
+        match component {
+            Component::Normal(part) if !rest.is_empty() || is_glob(part) => {
+                rest.push(part.to_string_lossy().to_string())
+            }
+            _ if !rest.is_empty() => rest.push(component.as_os_str().to_string_lossy().into()),
+            _ => root.push(component),
+        }
+    }
+    // A trailing `**` takes everything below, like `**/*`
+    if rest.last().is_some_and(|part| part == "**") {
+        rest.push("*".to_string());
+    }
+    let parts = rest
+        .iter()
+        .map(|part| match part.as_str() {
+            "**" => Ok(None),
+            _ => crate::library_filter::glob_regex(part).map(Some),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut found = Vec::new();
+    walk(&root, &parts, &mut found);
+    Ok(found)
+}
+
+fn is_glob(part: &std::ffi::OsStr) -> bool {
+    part.to_string_lossy().contains(['*', '?', '['])
+}
+
+/// Collects the wallpapers under `dir` matching `parts` (None for `**`). Hidden entries
+/// only match a part that starts with a dot, as in a shell.
+fn walk(dir: &Path, parts: &[Option<regex::Regex>], found: &mut Vec<PathBuf>) {
+    let Some((part, rest)) = parts.split_first() else {
+        if SmartQueue::get_content_type(dir).is_some() {
+            found.push(dir.to_path_buf());
+        }
+        return;
+    };
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut entries: Vec<_> = entries.flatten().map(|e| e.path()).collect();
+    entries.sort();
+    if part.is_none() {
+        // `**` matching no folder at all
+        walk(dir, rest, found);
+    }
+    for path in entries {
+        let Some(name) = path.file_name().map(|n| n.to_string_lossy().to_string()) else {
+            continue;
+        };
+        let hidden = name.starts_with('.');
+        match part {
+            Some(re) if re.is_match(&name) && (!hidden || re.as_str().starts_with(r"^\.")) => {
+                walk(&path, rest, found)
+            }
+            None if path.is_dir() && !hidden => walk(&path, parts, found),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use crate::orchestration::PowerConfig;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tracing::info;
+
+const POWER_SUPPLY_DIR: &str = "/sys/class/power_supply";
+/// Set to `low-power` by power-profiles-daemon's power-saver profile (and by firmware
+/// hotkeys) on machines with ACPI platform profiles
+const PLATFORM_PROFILE: &str = "/sys/firmware/acpi/platform_profile";
+
+/// Where the power state comes from right now
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PowerState {
+    pub on_battery: bool,
+    pub power_saver: bool,
+}
+
+impl PowerState {
+    pub fn read() -> Self {
+        Self {
+            on_battery: on_battery(),
+            power_saver: power_saver(),
+        }
+    }
+
+    /// Whether `config` asks to save power in this state
+    pub fn saving(&self, config: &PowerConfig) -> bool {
+        (self.on_battery && config.on_battery) || (self.power_saver && config.on_power_saver)
+    }
+}
+
+/// Decides when `[power]` saving applies: while on battery or in the power-saver
+/// profile. Polled from the main loop; a few sysfs reads every 5s.
+pub struct PowerMonitor {
+    last_check: Option<Instant>,
+    saving: bool,
+}
+
+impl PowerMonitor {
+    const INTERVAL: Duration = Duration::from_secs(5);
+
+    pub fn new() -> Self {
+        Self {
+            last_check: None,
+            saving: false,
+        }
+    }
+
+    /// Whether power saving should be on, when that changed since the last call (the
+    /// first call reports it only if it starts on)
+    pub fn poll(&mut self, config: &PowerConfig) -> Option<bool> {
+        if self
+            .last_check
+            .is_some_and(|t| t.elapsed() < Self::INTERVAL)
+        {
+            return None;
+        }
+        self.last_check = Some(Instant::now());
+        let state = PowerState::read();
+        let saving = state.saving(config);
+        if saving == self.saving {
+            return None;
+        }
+        self.saving = saving;
+        match (saving, state.on_battery) {
+            (true, true) => info!("[POWER] Running on battery, saving power"),
+            (true, false) => info!("[POWER] Power-saver profile active, saving power"),
+            (false, _) => info!("[POWER] Back on AC power, resuming normal behavior"),
+        }
+        Some(saving)
+    }
+}
+
+/// Whether the machine runs on battery: it has one, and no mains or USB supply is online.
+/// Desktops without a battery never are.
+pub fn on_battery() -> bool {
+    let Ok(entries) = fs::read_dir(POWER_SUPPLY_DIR) else {
+        return false;
+    };
+    let mut has_battery = false;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        match read_trimmed(&path.join("type")).as_deref() {
+            // Peripherals (mice, headsets) report batteries with scope Device
+            Some("Battery") if read_trimmed(&path.join("scope")).as_deref() != Some("Device") => {
+                has_battery = true;
+            }
+            Some("Mains" | "USB" | "USB_C" | "USB_PD")
+                if read_trimmed(&path.join("online")).as_deref() == Some("1") =>
+            {
+                return false;
+            }
+            _ => {}
+        }
+    }
+    has_battery
+}
+
+/// Whether the power-saver profile is active
+pub fn power_saver() -> bool {
+    read_trimmed(Path::new(PLATFORM_PROFILE)).as_deref() == Some("low-power")
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
@@ -0,0 +1,162 @@
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Instant, UNIX_EPOCH};
+use tracing::{debug, info, warn};
+
+/// One lock per artifact so two outputs showing the same file never run the command twice
+static IN_PROGRESS: once_cell::sync::Lazy<
+    parking_lot::Mutex<HashMap<PathBuf, Arc<parking_lot::Mutex<()>>>>,
+> = once_cell::sync::Lazy::new(|| parking_lot::Mutex::new(HashMap::new()));
+
+/// Artifacts whose command failed, so a broken hook is not retried on every switch.
+/// Cleared on restart (or when the file or command changes, since that changes the key).
+static FAILED: once_cell::sync::Lazy<parking_lot::Mutex<std::collections::HashSet<PathBuf>>> =
+    once_cell::sync::Lazy::new(|| parking_lot::Mutex::new(std::collections::HashSet::new()));
+
+fn preprocess_dir() -> Option<PathBuf> {
+    crate::cache::cache_root().map(|d| d.join("preprocessed"))
+}
+
+/// Cache location of the processed copy of `source`. The name hashes the source path,
+/// its size and mtime and the command itself, so editing the file or the hook
+/// produces a fresh artifact instead of serving a stale one.
+/// The source extension is kept so decoders pick the same format.
+pub fn artifact_path(source: &Path, command: &str) -> Option<PathBuf> {
+    use std::os::unix::ffi::OsStrExt;
+    let meta = std::fs::metadata(source).ok()?;
+    let mtime = meta
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+
+    let mut key = source.as_os_str().as_bytes().to_vec();
+    key.push(0);
+    key.extend_from_slice(&meta.len().to_le_bytes());
+    key.extend_from_slice(&mtime.to_le_bytes());
+    key.extend_from_slice(command.as_bytes());
+    let hash = crate::cache::stable_hash(&key);
+
+    let name = match source.extension().and_then(|e| e.to_str()) {
+        Some(ext) => format!("{:016x}.{}", hash, ext),
+        None => format!("{:016x}", hash),
+    };
+    preprocess_dir().map(|d| d.join(name))
+}
+
+/// Processed copy of `source` if it has already been produced (never runs the command)
+pub fn existing(source: &Path, command: Option<&str>) -> Option<PathBuf> {
+    let path = artifact_path(source, command?)?;
+    path.exists().then_some(path)
+}
+
+/// Path that should actually be loaded for `source`: the cached artifact, produced on
+/// first use by running `command`. Falls back to `source` itself if the hook fails,
+/// so a broken command degrades to showing the original file.
+/// Blocking; call from spawn_blocking.
+pub fn resolve(source: &Path, command: Option<&str>) -> PathBuf {
+    let Some(command) = command.filter(|c| !c.trim().is_empty()) else {
+        return source.to_path_buf();
+    };
+    let Some(artifact) = artifact_path(source, command) else {
+        return source.to_path_buf();
+    };
+    if artifact.exists() {
+        // Mark as recently used for cache eviction
+        if let Ok(file) = std::fs::File::options().write(true).open(&artifact) {
+            let _ = file.set_modified(std::time::SystemTime::now());
+        }
+        debug!(
+            "[PREPROCESS] Using cached artifact {:?} for {:?}",
+            artifact, source
+        );
+        return artifact;
+    }
+    if FAILED.lock().contains(&artifact) {
+        return source.to_path_buf();
+    }
+
+    let lock = IN_PROGRESS
+        .lock()
+        .entry(artifact.clone())
+        .or_default()
+        .clone();
+    let _guard = lock.lock();
+
+    // Another task may have produced it while we waited for the lock
+    let result = if artifact.exists() {
+        Ok(())
+    } else {
+        run(command, source, &artifact)
+    };
+    IN_PROGRESS.lock().remove(&artifact);
+
+    match result {
+        Ok(()) => artifact,
+        Err(e) => {
+            warn!(
+                "[PREPROCESS] Hook failed for {:?}, showing the original: {:#}",
+                source, e
+            );
+            FAILED.lock().insert(artifact);
+            source.to_path_buf()
+        }
+    }
+}
+
+/// Runs the hook with `{input}`/`{output}` substituted (shell-quoted) through `sh -c`.
+/// The command writes to a temp file that is renamed into place only on success,
+/// so an interrupted run never leaves a half-written artifact in the cache.
+fn run(command: &str, source: &Path, artifact: &Path) -> Result<()> {
+    let dir = artifact.parent().context("Artifact path has no parent")?;
+    std::fs::create_dir_all(dir)?;
+
+    let file_name = artifact
+        .file_name()
+        .and_then(|n| n.to_str())
+        .context("Invalid artifact name")?;
+    let tmp = dir.join(format!(".tmp-{}", file_name));
+    let _ = std::fs::remove_file(&tmp);
+
+    let cmdline = command
+        .replace("{input}", &shell_quote(source))
+        .replace("{output}", &shell_quote(&tmp));
+
+    info!("[PREPROCESS] Processing {:?}: {}", source, cmdline);
+    let start = Instant::now();
+    let output = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(&cmdline)
+        .stdin(std::process::Stdio::null())
+        .output()
+        .context("Failed to spawn sh")?;
+
+    if !output.status.success() {
+        let _ = std::fs::remove_file(&tmp);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("exited with {}: {}", output.status, stderr.trim());
+    }
+    if std::fs::metadata(&tmp)
+        .map(|m| m.len() == 0)
+        .unwrap_or(true)
+    {
+        let _ = std::fs::remove_file(&tmp);
+        bail!("command did not write {{output}}");
+    }
+
+    std::fs::rename(&tmp, artifact)?;
+    info!(
+        "[PREPROCESS] Cached {:?} in {:.2}s",
+        artifact,
+        start.elapsed().as_secs_f64()
+    );
+    Ok(())
+}
+
+/// Single-quotes a path for sh, escaping embedded single quotes
+pub fn shell_quote(path: &Path) -> String {
+    format!("'{}'", path.to_string_lossy().replace('\'', "'\\''"))
+}
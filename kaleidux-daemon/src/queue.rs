@@ -1,13 +1,28 @@
 use crate::cache::FileCache;
+use crate::library_filter::LibraryFilter;
+use crate::orchestration::Source;
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use jwalk::WalkDir;
+use kaleidux_common::{CommandError, ErrorCode};
 use rand::Rng;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// How long a file that failed to load sits out after its first failure; doubles with
+/// each further failure up to `MAX_FAILURE_BACKOFF`
+const FAILURE_BACKOFF: Duration = Duration::from_secs(60);
+const MAX_FAILURE_BACKOFF: Duration = Duration::from_secs(60 * 60);
+/// Re-picks allowed when the pick is backing off, before taking it anyway
+const MAX_BACKOFF_SKIPS: usize = 16;
+/// Files found before a new queue starts picking while the rest of a large library is
+/// still being scanned
+const PROVISIONAL_POOL: usize = 256;
+/// Picks each queue remembers for `prev` and `kldctl history`, across restarts too
+pub const HISTORY_LIMIT: usize = 100;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct LoveitData {
@@ -16,6 +31,27 @@ pub struct LoveitData {
     pub playlists: HashMap<String, Playlist>,
     #[serde(default)]
     pub blacklist: std::collections::HashSet<PathBuf>,
+    #[serde(default)]
+    pub quarantine: HashMap<PathBuf, QuarantineRecord>,
+    /// `kldctl tag` labels of each file, lowercase
+    #[serde(default)]
+    pub tags: HashMap<PathBuf, BTreeSet<String>>,
+}
+
+/// A file taken out of rotation after repeatedly failing to load
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuarantineRecord {
+    pub failures: u32,
+    pub error: String,
+    /// Unix timestamp
+    pub since: u64,
+}
+
+/// Load failures of a file not (yet) quarantined. Kept in memory only, so a restart
+/// gives every file a fresh start.
+struct FailureState {
+    count: u32,
+    retry_at: Instant,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -37,58 +73,590 @@ pub struct FileStats {
     pub love_multiplier: f32, // 1.0 = normal, 2.0 = double chance, etc.
 }
 
+/// Play counts, loves, playlists, the blacklist and quarantine, shared by every queue.
+/// There is one per daemon (owned by the MonitorManager), so per-output, group and
+/// shared queues all read and write the same data instead of flushing stale copies
+/// over each other.
+pub struct StatsStore {
+    cache: Arc<FileCache>,
+    data: parking_lot::RwLock<LoveitData>,
+    /// Paths whose play count changed since the last flush (batched writes)
+    dirty: parking_lot::Mutex<std::collections::HashSet<PathBuf>>,
+    failures: parking_lot::Mutex<HashMap<PathBuf, FailureState>>,
+}
+
+impl StatsStore {
+    pub fn load(cache: Arc<FileCache>) -> Result<Self> {
+        let data = LoveitData {
+            files: cache.get_all_file_stats()?,
+            playlists: cache.get_all_playlists()?,
+            blacklist: cache.get_all_blacklisted()?,
+            quarantine: cache.get_all_quarantined()?,
+            tags: cache.get_all_tags()?,
+        };
+        Ok(Self {
+            cache,
+            data: parking_lot::RwLock::new(data),
+            dirty: parking_lot::Mutex::new(std::collections::HashSet::new()),
+            failures: parking_lot::Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn cache(&self) -> &Arc<FileCache> {
+        &self.cache
+    }
+
+    /// Read access; keep the guard short-lived, every queue shares this lock
+    pub fn read(&self) -> parking_lot::RwLockReadGuard<'_, LoveitData> {
+        self.data.read()
+    }
+
+    fn record_play(&self, path: &Path) {
+        let mut data = self.data.write();
+        let stat = data
+            .files
+            .entry(path.to_path_buf())
+            .or_insert_with(|| FileStats {
+                count: 0,
+                last_seen: None,
+                love_multiplier: 1.0,
+            });
+        stat.count += 1;
+        stat.last_seen = Some(Utc::now());
+        self.dirty.lock().insert(path.to_path_buf());
+
+        // Limit stats growth (LRU-ish removal)
+        if data.files.len() > 5000 {
+            let oldest = data
+                .files
+                .iter()
+                .min_by_key(|(_, s)| s.last_seen.map(|d| d.timestamp()).unwrap_or(0))
+                .map(|(p, _)| p.clone());
+            if let Some(p) = oldest {
+                data.files.remove(&p);
+                self.dirty.lock().remove(&p);
+            }
+        }
+    }
+
+    /// Writes the play counts changed since the last flush in one transaction.
+    /// Current values are written, so a love set in between is never overwritten.
+    pub fn flush(&self) -> Result<()> {
+        let paths: Vec<PathBuf> = self.dirty.lock().drain().collect();
+        if paths.is_empty() {
+            return Ok(());
+        }
+        let updates: Vec<_> = {
+            let data = self.data.read();
+            paths
+                .into_iter()
+                .filter_map(|p| data.files.get(&p).cloned().map(|s| (p, s)))
+                .collect()
+        };
+        self.cache.batch_set_file_stats(&updates)
+    }
+
+    /// Sets the love multiplier of every file in `paths`, persisted in one write
+    pub fn set_love(&self, paths: &[PathBuf], multiplier: f32) -> Result<()> {
+        let updates: Vec<(PathBuf, FileStats)> = {
+            let mut data = self.data.write();
+            paths
+                .iter()
+                .map(|path| {
+                    let stat = data.files.entry(path.clone()).or_default();
+                    stat.love_multiplier = multiplier;
+                    (path.clone(), stat.clone())
+                })
+                .collect()
+        };
+        self.cache.batch_set_file_stats(&updates)
+    }
+
+    pub fn create_playlist(&self, name: &str, playlist: Playlist) -> Result<()> {
+        self.cache.set_playlist(name, &playlist)?;
+        self.data.write().playlists.insert(name.to_string(), playlist);
+        Ok(())
+    }
+
+    pub fn delete_playlist(&self, name: &str) -> Result<()> {
+        self.cache.delete_playlist(name)?;
+        self.data.write().playlists.remove(name);
+        Ok(())
+    }
+
+    /// Applies `edit` to a playlist and persists it. Returns false if it doesn't exist.
+    pub fn edit_playlist(&self, name: &str, edit: impl FnOnce(&mut Playlist)) -> Result<bool> {
+        let playlist = {
+            let mut data = self.data.write();
+            let Some(playlist) = data.playlists.get_mut(name) else {
+                return Ok(false);
+            };
+            edit(playlist);
+            playlist.clone()
+        };
+        self.cache.set_playlist(name, &playlist)?;
+        Ok(true)
+    }
+
+    /// Adds `paths` to the blacklist or takes them off it. Returns whether it changed.
+    pub fn set_blacklisted(&self, paths: &[PathBuf], blacklisted: bool) -> Result<bool> {
+        let changed: Vec<PathBuf> = {
+            let mut data = self.data.write();
+            paths
+                .iter()
+                .filter(|path| {
+                    if blacklisted {
+                        data.blacklist.insert(path.to_path_buf())
+                    } else {
+                        data.blacklist.remove(*path)
+                    }
+                })
+                .cloned()
+                .collect()
+        };
+        if !changed.is_empty() {
+            self.cache.set_blacklisted(&changed, blacklisted)?;
+        }
+        Ok(!changed.is_empty())
+    }
+
+    /// Adds `tags` to every file in `paths`, or removes them from it, persisted in one
+    /// write. Returns whether anything changed.
+    pub fn set_tags(&self, paths: &[PathBuf], tags: &[String], add: bool) -> Result<bool> {
+        let changed: Vec<(PathBuf, BTreeSet<String>)> = {
+            let mut data = self.data.write();
+            let mut changed = Vec::new();
+            for path in paths {
+                let current = data.tags.entry(path.clone()).or_default();
+                let before = current.len();
+                if add {
+                    current.extend(tags.iter().cloned());
+                } else {
+                    current.retain(|tag| !tags.contains(tag));
+                }
+                if current.len() != before {
+                    changed.push((path.clone(), current.clone()));
+                }
+                if current.is_empty() {
+                    data.tags.remove(path);
+                }
+            }
+            changed
+        };
+        if !changed.is_empty() {
+            self.cache.set_tags(&changed)?;
+        }
+        Ok(!changed.is_empty())
+    }
+
+    /// Files left out of discovery and playlists: blacklisted and quarantined ones
+    pub fn excluded(&self) -> std::collections::HashSet<PathBuf> {
+        let data = self.data.read();
+        data.blacklist
+            .iter()
+            .chain(data.quarantine.keys())
+            .cloned()
+            .collect()
+    }
+
+    /// Counts a failed load of `path`. The file sits out an exponentially growing
+    /// backoff, and after `quarantine_after` failures (0 = never) it is quarantined.
+    /// Returns whether it was quarantined.
+    pub fn record_failure(&self, path: &Path, error: &str, quarantine_after: u32) -> Result<bool> {
+        let count = {
+            let mut failures = self.failures.lock();
+            let state = failures.entry(path.to_path_buf()).or_insert(FailureState {
+                count: 0,
+                retry_at: Instant::now(),
+            });
+            state.count += 1;
+            let backoff = FAILURE_BACKOFF
+                .saturating_mul(1 << (state.count - 1).min(16))
+                .min(MAX_FAILURE_BACKOFF);
+            state.retry_at = Instant::now() + backoff;
+            state.count
+        };
+        if quarantine_after == 0 || count < quarantine_after {
+            return Ok(false);
+        }
+
+        self.failures.lock().remove(path);
+        let record = QuarantineRecord {
+            failures: count,
+            error: error.to_string(),
+            since: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+        };
+        self.cache.set_quarantined(path, Some(&record))?;
+        self.data.write().quarantine.insert(path.to_path_buf(), record);
+        Ok(true)
+    }
+
+    /// Forgets earlier failures once `path` has loaded
+    pub fn record_success(&self, path: &Path) {
+        self.failures.lock().remove(path);
+    }
+
+    /// Whether `path` failed recently and should not be picked yet
+    pub fn is_backing_off(&self, path: &Path) -> bool {
+        self.failures
+            .lock()
+            .get(path)
+            .is_some_and(|s| Instant::now() < s.retry_at)
+    }
+
+    /// Drops the blacklist, quarantine and tag entries of files deleted from disk, or of
+    /// everything in a deleted folder
+    pub fn forget(&self, removed: &[PathBuf]) -> Result<()> {
+        let gone = |p: &PathBuf| removed.iter().any(|r| p.starts_with(r));
+        let (blacklisted, quarantined, untagged) = {
+            let mut data = self.data.write();
+            let blacklisted: Vec<PathBuf> =
+                data.blacklist.iter().filter(|p| gone(p)).cloned().collect();
+            let quarantined: Vec<PathBuf> = data
+                .quarantine
+                .keys()
+                .filter(|p| gone(p))
+                .cloned()
+                .collect();
+            let untagged: Vec<(PathBuf, BTreeSet<String>)> = data
+                .tags
+                .keys()
+                .filter(|p| gone(p))
+                .map(|p| (p.clone(), BTreeSet::new()))
+                .collect();
+            data.blacklist.retain(|p| !gone(p));
+            data.quarantine.retain(|p, _| !gone(p));
+            data.tags.retain(|p, _| !gone(p));
+            (blacklisted, quarantined, untagged)
+        };
+        self.failures.lock().retain(|p, _| !gone(p));
+        if !blacklisted.is_empty() {
+            self.cache.set_blacklisted(&blacklisted, false)?;
+        }
+        for path in &quarantined {
+            self.cache.set_quarantined(path, None)?;
+        }
+        if !untagged.is_empty() {
+            self.cache.set_tags(&untagged)?;
+        }
+        Ok(())
+    }
+
+    /// Moves the love, play count, blacklist, quarantine and tag entries of a file renamed
+    /// from `from` to `to`, or of everything in a renamed folder
+    pub fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        let moved = |p: &Path| {
+            let rest = p.strip_prefix(from).ok()?;
+            Some(if rest.as_os_str().is_empty() {
+                to.to_path_buf()
+            } else {
+                to.join(rest)
+            })
+        };
+        let mut data = self.data.write();
+        let stats: Vec<(PathBuf, PathBuf)> = data
+            .files
+            .keys()
+            .filter_map(|p| moved(p).map(|m| (p.clone(), m)))
+            .collect();
+        let mut updates = Vec::new();
+        for (old, new) in stats {
+            if let Some(stat) = data.files.remove(&old) {
+                self.dirty.lock().remove(&old);
+                data.files.insert(new.clone(), stat.clone());
+                updates.push((new, stat));
+            }
+        }
+        let blacklisted: Vec<(PathBuf, PathBuf)> = data
+            .blacklist
+            .iter()
+            .filter_map(|p| moved(p).map(|m| (p.clone(), m)))
+            .collect();
+        for (old, new) in &blacklisted {
+            data.blacklist.remove(old);
+            data.blacklist.insert(new.clone());
+        }
+        let quarantined: Vec<(PathBuf, PathBuf)> = data
+            .quarantine
+            .keys()
+            .filter_map(|p| moved(p).map(|m| (p.clone(), m)))
+            .collect();
+        let mut records = Vec::new();
+        for (old, new) in quarantined {
+            if let Some(record) = data.quarantine.remove(&old) {
+                data.quarantine.insert(new.clone(), record.clone());
+                records.push((old, new, record));
+            }
+        }
+        let tagged: Vec<(PathBuf, PathBuf)> = data
+            .tags
+            .keys()
+            .filter_map(|p| moved(p).map(|m| (p.clone(), m)))
+            .collect();
+        let mut retagged = Vec::new();
+        for (old, new) in tagged {
+            if let Some(tags) = data.tags.remove(&old) {
+                data.tags.insert(new.clone(), tags.clone());
+                retagged.push((old, BTreeSet::new()));
+                retagged.push((new, tags));
+            }
+        }
+        drop(data);
+
+        if !updates.is_empty() {
+            self.cache.batch_set_file_stats(&updates)?;
+        }
+        if !blacklisted.is_empty() {
+            let (old, new): (Vec<PathBuf>, Vec<PathBuf>) = blacklisted.into_iter().unzip();
+            self.cache.set_blacklisted(&old, false)?;
+            self.cache.set_blacklisted(&new, true)?;
+        }
+        for (old, new, record) in records {
+            self.cache.set_quarantined(&old, None)?;
+            self.cache.set_quarantined(&new, Some(&record))?;
+        }
+        if !retagged.is_empty() {
+            self.cache.set_tags(&retagged)?;
+        }
+        Ok(())
+    }
+
+    /// Takes `path` out of quarantine. Returns whether it was quarantined.
+    pub fn release(&self, path: &Path) -> Result<bool> {
+        let released = self.data.write().quarantine.remove(path).is_some();
+        if released {
+            self.cache.set_quarantined(path, None)?;
+        }
+        Ok(released)
+    }
+}
+
+/// Whether `path` carries every one of `tags`
+fn has_tags(stats: &LoveitData, path: &Path, tags: &[String]) -> bool {
+    tags.is_empty()
+        || stats
+            .tags
+            .get(path)
+            .is_some_and(|tagged| tags.iter().all(|tag| tagged.contains(tag)))
+}
+
+/// The innermost source holding `path`, for sources inside one another
+fn source_index(sources: &[Source], path: &Path) -> Option<usize> {
+    sources
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| path.starts_with(&s.path))
+        .max_by_key(|(_, s)| s.path.components().count())
+        .map(|(i, _)| i)
+}
+
+/// Folder of the innermost source holding `path`
+fn source_of<'a>(sources: &'a [Source], path: &Path) -> Option<&'a Path> {
+    source_index(sources, path).map(|i| sources[i].path.as_path())
+}
+
+/// Folder of the first source, which a queue is known by
+fn source_root(sources: &[Source]) -> Result<&Path> {
+    sources
+        .first()
+        .map(|s| s.path.as_path())
+        .ok_or_else(|| anyhow::anyhow!("No wallpaper folder to discover"))
+}
+
+/// An entry of `pool` drawn in proportion to `weights`; evenly if they are all zero
+fn weighted_choice(pool: &[&PathBuf], weights: &[f64], rng: &mut impl Rng) -> Option<PathBuf> {
+    if pool.is_empty() {
+        return None;
+    }
+    let total: f64 = weights.iter().sum();
+    if total <= 0.0 {
+        return Some(pool[rng.gen_range(0..pool.len())].clone());
+    }
+    let mut choice = rng.gen_range(0.0..total);
+    for (path, weight) in pool.iter().zip(weights) {
+        choice -= weight;
+        if choice <= 0.0 {
+            return Some((*path).clone());
+        }
+    }
+    Some(pool[0].clone())
+}
+
+/// Favorites: files loved above the neutral multiplier of 1
+fn is_loved(stat: Option<&FileStats>) -> bool {
+    stat.is_some_and(|s| s.love_multiplier > 1.0)
+}
+
+/// Loveit pick weight: LoveMultiplier * 100 / (1 + Count) * RecencyFactor
+fn loveit_weight(stat: Option<&FileStats>, now: DateTime<Utc>) -> f32 {
+    let stat = stat.cloned().unwrap_or_default();
+    let count_score = 100.0 / (stat.count as f32 + 1.0);
+
+    let recency_factor = if let Some(last) = stat.last_seen {
+        let hours_since = (now - last).num_hours() as f32;
+        // Favor items not seen in a long time
+        (hours_since / 24.0).clamp(1.0, 10.0)
+    } else {
+        10.0 // Never seen is high priority
+    };
+
+    let love_weight = if stat.love_multiplier > 0.0 {
+        stat.love_multiplier
+    } else {
+        1.0
+    };
+
+    count_score * recency_factor * love_weight
+}
+
 pub struct SmartQueue {
     pub pool: Vec<PathBuf>,
-    pub stats: LoveitData,
+    pub stats: Arc<StatsStore>,
     pub video_ratio: u8,
     pub strategy: crate::orchestration::SortingStrategy,
     pub current_index: usize,
     pub history: Vec<PathBuf>,
     pub root_path: PathBuf,
+    /// Folders the pool is discovered from, `root_path` first. With more than one, each
+    /// gets its weight's share of random and loveit picks.
+    pub sources: Vec<Source>,
+    /// `max-depth`, `include` and `exclude`, applied below each source
+    pub filter: LibraryFilter,
     pub active_playlist: Option<String>,
     pub cache: Arc<FileCache>,
-    pending_stats_updates: HashMap<PathBuf, FileStats>,
+    /// Files to pass over if possible: recently shown on another output (`avoid-twins`)
+    pub avoid: std::collections::HashSet<PathBuf>,
+    /// `kldctl favorites on`: only loved files are picked, whatever the sorting
+    pub favorites: bool,
+    /// The output's `tags`: only files carrying all of them are picked, unless none is
+    pub tags: Vec<String>,
+    /// Tags of a single `kldctl next --tag`, set around that pick. Unlike `tags` they
+    /// never give way: without a matching file nothing is picked.
+    pub once_tags: Vec<String>,
+    /// Full file list of a discovery still running in the background; until it arrives
+    /// `pool` only holds the first files found (see `merge_discovery`)
+    pending_discovery: Option<tokio::sync::oneshot::Receiver<Result<Vec<PathBuf>>>>,
+    /// Whether the history saved by the last run was taken in (see `restore_history`)
+    history_loaded: bool,
+    /// History changed since it was last saved
+    history_changed: bool,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ContentType {
     Image,
     Video,
+    /// Animated GIF, APNG or WebP
+    Animated,
+    /// GLSL or WGSL fragment shader, rendered live (see `shader_wallpaper`)
+    Shader,
+}
+
+impl ContentType {
+    /// "image", "video", "animated" or "shader", as published to widgets and event
+    /// subscribers
+    pub fn name(self) -> &'static str {
+        match self {
+            ContentType::Image => "image",
+            ContentType::Video => "video",
+            ContentType::Animated => "animated",
+            ContentType::Shader => "shader",
+        }
+    }
+
+    /// Played frame by frame through a video player rather than decoded once
+    pub fn is_played(self) -> bool {
+        matches!(self, ContentType::Video | ContentType::Animated)
+    }
+
+    /// Moves on its own; `video-ratio` counts these as videos
+    pub fn is_moving(self) -> bool {
+        self.is_played() || self == ContentType::Shader
+    }
+
+    /// Code stored in the file cache
+    pub fn code(self) -> u8 {
+        match self {
+            ContentType::Image => 0,
+            ContentType::Video => 1,
+            ContentType::Animated => 2,
+            ContentType::Shader => 3,
+        }
+    }
+
+    pub fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0 => Some(ContentType::Image),
+            1 => Some(ContentType::Video),
+            2 => Some(ContentType::Animated),
+            3 => Some(ContentType::Shader),
+            _ => None,
+        }
+    }
 }
 
 impl SmartQueue {
     pub async fn new_with_cache(
-        path: &Path,
+        sources: &[Source],
+        filter: LibraryFilter,
         video_ratio: u8,
         strategy: crate::orchestration::SortingStrategy,
-        cache: Arc<FileCache>,
+        stats: Arc<StatsStore>,
         metrics: Option<Arc<crate::metrics::PerformanceMetrics>>,
     ) -> Result<Self> {
+        let path = source_root(sources)?;
         tracing::info!("[QUEUE] new_with_cache called for path: {:?}", path);
-        let stats = Self::load_stats_from_cache(&cache)?;
+        let cache = stats.cache().clone();
+        let excluded = stats.excluded();
         tracing::info!(
-            "[QUEUE] Loaded stats, blacklist size: {}",
-            stats.blacklist.len()
+            "[QUEUE] Using shared stats, {} blacklisted or quarantined files",
+            excluded.len()
         );
 
         // Run file discovery in background task to avoid blocking startup
-        let path_buf = path.to_path_buf();
-        let blacklist_clone = stats.blacklist.clone();
+        let roots: Vec<PathBuf> = sources.iter().map(|s| s.path.clone()).collect();
         let cache_clone = cache.clone();
         let metrics_clone = metrics.clone();
+        let filter_clone = filter.clone();
+
+        tracing::info!("[QUEUE] Starting file discovery for: {:?}", roots);
+        // Use spawn_blocking to run on thread pool (truly async, non-blocking).
+        // On a large library the first PROVISIONAL_POOL files arrive before the walk is
+        // done; the queue starts picking from those and merges the full list later, so
+        // the first wallpaper doesn't wait for the whole scan.
+        let (provisional_tx, provisional_rx) = tokio::sync::oneshot::channel();
+        let (done_tx, mut done_rx) = tokio::sync::oneshot::channel();
+        tokio::task::spawn_blocking(move || {
+            let result = Self::discover_content(
+                &roots,
+                &filter_clone,
+                &excluded,
+                cache_clone,
+                metrics_clone,
+                Some(provisional_tx),
+            );
+            let _ = done_tx.send(result);
+        });
+        let (mut pool, pending_discovery) = tokio::select! {
+            Ok(result) = &mut done_rx => (result?, None),
+            Ok(provisional) = provisional_rx => (provisional, Some(done_rx)),
+            else => anyhow::bail!("File discovery task for {:?} failed", path),
+        };
+        if pending_discovery.is_some() {
+            tracing::info!(
+                "[QUEUE] Starting with the first {} files, discovery continues in the background",
+                pool.len()
+            );
+        } else {
+            tracing::info!(
+                "[QUEUE] File discovery completed, found {} files",
+                pool.len()
+            );
+        }
 
-        tracing::info!("[QUEUE] Starting file discovery for: {:?}", path_buf);
-        // Use spawn_blocking to run on thread pool (truly async, non-blocking)
-        let pool = tokio::task::spawn_blocking(move || {
-            Self::discover_content(&path_buf, &blacklist_clone, cache_clone, metrics_clone)
-        })
-        .await??;
-        tracing::info!(
-            "[QUEUE] File discovery completed, found {} files",
-            pool.len()
-        );
-
-        let mut pool = pool;
         // Sort the pool initially for sequential strategies
         pool.sort();
 
@@ -106,22 +674,36 @@ impl SmartQueue {
             current_index,
             history: Vec::new(),
             root_path: path.to_path_buf(),
+            sources: sources.to_vec(),
+            filter,
             active_playlist: None,
             cache,
-            pending_stats_updates: HashMap::new(),
+            avoid: std::collections::HashSet::new(),
+            favorites: false,
+            tags: Vec::new(),
+            once_tags: Vec::new(),
+            pending_discovery,
+            history_loaded: false,
+            history_changed: false,
         })
     }
 
-    /// Create a queue from a pre-discovered file list (avoids re-scanning the directory)
+    /// Create a queue from a pre-discovered file list (avoids re-scanning the directory).
+    /// `filter` is applied to it, so the list may come from an unfiltered scan.
     pub fn new_from_pool(
-        path: &Path,
+        sources: &[Source],
+        filter: LibraryFilter,
         pool: Vec<PathBuf>,
         video_ratio: u8,
         strategy: crate::orchestration::SortingStrategy,
-        cache: Arc<FileCache>,
+        stats: Arc<StatsStore>,
     ) -> Result<Self> {
-        let stats = Self::load_stats_from_cache(&cache)?;
+        let path = source_root(sources)?;
+        let cache = stats.cache().clone();
         let mut pool = pool;
+        if !filter.is_empty() {
+            pool.retain(|p| filter.allows(source_of(sources, p).unwrap_or(path), p));
+        }
         pool.sort();
 
         let current_index = if strategy == crate::orchestration::SortingStrategy::Descending {
@@ -138,15 +720,31 @@ impl SmartQueue {
             current_index,
             history: Vec::new(),
             root_path: path.to_path_buf(),
+            sources: sources.to_vec(),
+            filter,
             active_playlist: None,
             cache,
-            pending_stats_updates: HashMap::new(),
+            avoid: std::collections::HashSet::new(),
+            favorites: false,
+            tags: Vec::new(),
+            once_tags: Vec::new(),
+            pending_discovery: None,
+            history_loaded: false,
+            history_changed: false,
         })
     }
 
     #[inline]
     pub fn get_content_type(path: &Path) -> Option<ContentType> {
         use std::io::Read;
+        // A folder of numbered frames plays like a video
+        if path.is_dir() {
+            return crate::sequence::frames(path).map(|_| ContentType::Video);
+        }
+        // Shader source is plain text, recognized by its extension alone
+        if crate::shader_wallpaper::is_shader_file(path) {
+            return Some(ContentType::Shader);
+        }
         let mut file = match std::fs::File::open(path) {
             Ok(f) => f,
             Err(_) => return None,
@@ -155,6 +753,13 @@ impl SmartQueue {
         if file.read_exact(&mut buffer).is_err() {
             return None;
         }
+        let image_or_animated = || {
+            if crate::animation::is_animated(path) {
+                ContentType::Animated
+            } else {
+                ContentType::Image
+            }
+        };
 
         // JPEG: FF D8 FF
         if buffer[0..3] == [0xFF, 0xD8, 0xFF] {
@@ -162,15 +767,15 @@ impl SmartQueue {
         }
         // PNG: 89 50 4E 47
         if buffer[0..4] == [0x89, 0x50, 0x4E, 0x47] {
-            return Some(ContentType::Image);
+            return Some(image_or_animated());
         }
         // GIF: GIF8
         if buffer[0..4] == *b"GIF8" {
-            return Some(ContentType::Image);
+            return Some(image_or_animated());
         }
         // WebP: RIFF .... WEBP
         if &buffer[0..4] == b"RIFF" && &buffer[8..12] == b"WEBP" {
-            return Some(ContentType::Image);
+            return Some(image_or_animated());
         }
         // EBML (MKV/WebM): 1A 45 DF A3
         if buffer[0..4] == [0x1A, 0x45, 0xDF, 0xA3] {
@@ -185,30 +790,61 @@ impl SmartQueue {
     }
 
     fn discover_content(
-        path: &Path,
-        blacklist: &std::collections::HashSet<PathBuf>,
+        roots: &[PathBuf],
+        filter: &LibraryFilter,
+        excluded: &std::collections::HashSet<PathBuf>,
         cache: Arc<FileCache>,
         metrics: Option<Arc<crate::metrics::PerformanceMetrics>>,
+        mut provisional: Option<tokio::sync::oneshot::Sender<Vec<PathBuf>>>,
     ) -> Result<Vec<PathBuf>> {
         let discovery_start = std::time::Instant::now();
         let mut files = Vec::new();
         let mut cache_updates = Vec::new();
 
-        // Use jwalk for parallel directory traversal
-        let walk_dir = WalkDir::new(path)
-            .follow_links(true)
-            .parallelism(jwalk::Parallelism::RayonNewPool(0)); // 0 = auto-detect CPU count, optimal thread usage
-
-        // Collect entries in parallel
-        let entries: Vec<_> = walk_dir
-            .into_iter()
-            .filter_map(|e| e.ok())
-            .filter(|e| e.file_type().is_file())
-            .collect();
+        // Use jwalk for parallel directory traversal, one root after the other
+        let walk_dirs = roots.iter().map(|path| {
+            let walk_dir = WalkDir::new(path)
+                .follow_links(true)
+                .parallelism(jwalk::Parallelism::RayonNewPool(0)); // 0 = auto-detect CPU count, optimal thread usage
+            let walk_dir = match filter.max_depth {
+                Some(depth) => walk_dir.max_depth(depth),
+                None => walk_dir,
+            };
+            (path, walk_dir)
+        });
+
+        // Entries stream in as the parallel walk finds them, so `provisional` can be
+        // answered long before the walk is done
+        let entries = walk_dirs.flat_map(|(root, walk_dir)| {
+            walk_dir
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .map(move |e| (root, e))
+        });
+        // Image sequences found so far; their frames are not wallpapers of their own.
+        // The walk yields a folder before its contents.
+        let mut sequences: Vec<PathBuf> = Vec::new();
+
+        for (root, entry) in entries {
+            if files.len() >= PROVISIONAL_POOL {
+                if let Some(tx) = provisional.take() {
+                    let _ = tx.send(files.clone());
+                }
+            }
 
-        for entry in entries {
             let p = entry.path().to_path_buf();
-            if blacklist.contains(&p) {
+            if entry.file_type().is_dir() {
+                // The library folder itself stays a folder of wallpapers
+                if entry.depth() == 0 || crate::sequence::frames(&p).is_none() {
+                    continue;
+                }
+                sequences.push(p.clone());
+            } else if !entry.file_type().is_file()
+                || p.parent().is_some_and(|dir| sequences.iter().any(|s| s == dir))
+            {
+                continue;
+            }
+            if excluded.contains(&p) || !filter.allows(root, &p) {
                 continue;
             }
 
@@ -221,11 +857,7 @@ impl SmartQueue {
                         if let Some(m) = &metrics {
                             m.record_cache_hit();
                         }
-                        match metadata.content_type {
-                            0 => Some(ContentType::Image),
-                            1 => Some(ContentType::Video),
-                            _ => None,
-                        }
+                        ContentType::from_code(metadata.content_type)
                     } else {
                         // File changed, re-check (cache miss due to invalidation)
                         if let Some(m) = &metrics {
@@ -271,10 +903,7 @@ impl SmartQueue {
                                         let file_metadata = crate::cache::FileMetadata {
                                             mtime,
                                             size,
-                                            content_type: match ct {
-                                                ContentType::Image => 0,
-                                                ContentType::Video => 1,
-                                            },
+                                            content_type: ct.code(),
                                             discovered_at,
                                         };
                                         cache_updates.push((p, file_metadata));
@@ -305,8 +934,17 @@ impl SmartQueue {
             let _ = cache.set_file_metadata(&path, &metadata);
         }
 
+        // Sources inside one another find the same files twice
+        if roots.len() > 1 {
+            files.sort();
+            files.dedup();
+        }
+
         if files.is_empty() {
-            anyhow::bail!("No supported images or videos found in {:?}", path);
+            match roots {
+                [path] => anyhow::bail!("No supported images or videos found in {:?}", path),
+                _ => anyhow::bail!("No supported images or videos found in {:?}", roots),
+            }
         }
 
         // Record file discovery CPU time
@@ -318,35 +956,150 @@ impl SmartQueue {
         Ok(files)
     }
 
+    /// Replaces the provisional pool with the full file list once background discovery
+    /// has finished. A loaded playlist keeps its pool. Sequential strategies continue
+    /// from the file they were at.
+    pub fn merge_discovery(&mut self) {
+        let Some(rx) = &mut self.pending_discovery else {
+            return;
+        };
+        let result = match rx.try_recv() {
+            Err(tokio::sync::oneshot::error::TryRecvError::Empty) => return,
+            Ok(result) => result,
+            Err(tokio::sync::oneshot::error::TryRecvError::Closed) => {
+                Err(anyhow::anyhow!("discovery task ended without a result"))
+            }
+        };
+        self.pending_discovery = None;
+
+        let mut pool = match result {
+            Ok(pool) => pool,
+            Err(e) => {
+                tracing::warn!(
+                    "[QUEUE] Background discovery of {:?} failed, keeping the {} files found \
+                     so far: {}",
+                    self.root_path,
+                    self.pool.len(),
+                    e
+                );
+                return;
+            }
+        };
+        tracing::info!(
+            "[QUEUE] Background discovery of {:?} finished, found {} files",
+            self.root_path,
+            pool.len()
+        );
+        if self.active_playlist.is_some() {
+            return;
+        }
+
+        // Blacklisted or quarantined while the walk was running
+        let excluded = self.stats.excluded();
+        pool.retain(|p| !excluded.contains(p));
+        pool.sort();
+        let current = self.pool.get(self.current_index).cloned();
+        self.pool = pool;
+        self.current_index = current
+            .and_then(|c| self.pool.binary_search(&c).ok())
+            .unwrap_or(0);
+    }
+
+    /// Whether the pool is still provisional (see `merge_discovery`)
+    pub fn is_discovering(&self) -> bool {
+        self.pending_discovery.is_some()
+    }
+
     #[inline]
     pub fn pick_next(&mut self) -> Option<PathBuf> {
+        self.merge_discovery();
         if self.pool.is_empty() {
             return None;
         }
+        if !self.once_tags.is_empty() {
+            let stats = self.stats.read();
+            if !self
+                .pool
+                .iter()
+                .any(|p| has_tags(&stats, p, &self.once_tags))
+            {
+                return None;
+            }
+        }
 
-        let picked = match self.strategy {
-            crate::orchestration::SortingStrategy::Loveit => self.pick_loveit(),
-            crate::orchestration::SortingStrategy::Random => self.pick_random(),
-            crate::orchestration::SortingStrategy::Ascending => self.pick_sequential(false),
-            crate::orchestration::SortingStrategy::Descending => self.pick_sequential(true),
-        };
+        let mut picked = self.pick_by_strategy();
+        // Files that just failed to load sit out their backoff, and files another output
+        // just showed are passed over, unless nearly everything is (then the pick stands
+        // rather than spinning)
+        for _ in 0..self.pool.len().min(MAX_BACKOFF_SKIPS) {
+            match &picked {
+                Some(p) if self.stats.is_backing_off(p) || self.avoid.contains(p) => {
+                    picked = self.pick_by_strategy()
+                }
+                _ => break,
+            }
+        }
 
         if let Some(ref p) = picked {
             self.update_stats(p);
-            // Add to history (limit to 50 items)
             if self.history.last() != Some(p) {
                 self.history.push(p.clone());
-                if self.history.len() > 50 {
+                if self.history.len() > HISTORY_LIMIT {
                     self.history.remove(0);
                 }
+                self.history_changed = true;
             }
         }
 
         picked
     }
 
+    /// `pick_next` with the pick's content type. A file deleted or changed into
+    /// something unplayable since discovery is dropped and the next one picked instead.
+    pub fn pick_next_playable(&mut self) -> Option<(PathBuf, ContentType)> {
+        self.pick_playable(Self::pick_next)
+    }
+
+    /// `pick_prev` with the pick's content type, skipping files gone since discovery
+    pub fn pick_prev_playable(&mut self) -> Option<(PathBuf, ContentType)> {
+        self.pick_playable(Self::pick_prev)
+    }
+
+    fn pick_playable(
+        &mut self,
+        pick: fn(&mut Self) -> Option<PathBuf>,
+    ) -> Option<(PathBuf, ContentType)> {
+        // Every miss removes the file from the pool and history, so this ends
+        for _ in 0..=self.pool.len() + self.history.len() {
+            let path = pick(self)?;
+            if let Some(content_type) = Self::get_content_type(&path) {
+                return Some((path, content_type));
+            }
+            tracing::warn!("[QUEUE] {:?} is no longer playable, dropping it", path);
+            self.remove_file(&path);
+            let len = self.history.len();
+            self.history.retain(|p| p != &path);
+            self.history_changed |= self.history.len() != len;
+        }
+        None
+    }
+
+    fn pick_by_strategy(&mut self) -> Option<PathBuf> {
+        match self.strategy {
+            crate::orchestration::SortingStrategy::Loveit
+            | crate::orchestration::SortingStrategy::LovedOnly => self.pick_loveit(),
+            crate::orchestration::SortingStrategy::Random => self.pick_random(),
+            crate::orchestration::SortingStrategy::Ascending => self.pick_sequential(false),
+            crate::orchestration::SortingStrategy::Descending => self.pick_sequential(true),
+        }
+    }
+
     /// Get the next content path without consuming it (for pre-buffering)
     pub fn peek_next(&self) -> Option<(PathBuf, ContentType)> {
+        // Stepping over files that aren't loved or tagged makes the next index unknown here
+        if self.favorites || !self.tags.is_empty() {
+            return None;
+        }
         // For sequential strategies, we can peek at the next index
         match self.strategy {
             crate::orchestration::SortingStrategy::Ascending
@@ -381,6 +1134,7 @@ impl SmartQueue {
 
     #[inline]
     pub fn pick_prev(&mut self) -> Option<PathBuf> {
+        self.merge_discovery();
         if self.pool.is_empty() {
             return None;
         }
@@ -398,6 +1152,7 @@ impl SmartQueue {
                 // For non-sequential, use history
                 if self.history.len() > 1 {
                     self.history.pop(); // Remove current
+                    self.history_changed = true;
                     self.history.last().cloned()
                 } else {
                     None
@@ -415,24 +1170,12 @@ impl SmartQueue {
     fn pick_random(&mut self) -> Option<PathBuf> {
         let mut rng = rand::thread_rng();
         let is_video_cycle = rng.gen_range(0..100) < self.video_ratio;
+        let stats = self.stats.read();
+        let active_pool = self.cycle_pool(is_video_cycle, &stats);
 
-        let sub_pool: Vec<&PathBuf> = self
-            .pool
-            .iter()
-            .filter(|p| {
-                let is_video = matches!(Self::get_content_type(p), Some(ContentType::Video));
-                is_video == is_video_cycle
-            })
-            .collect();
-
-        let active_pool = if sub_pool.is_empty() {
-            self.pool.iter().collect::<Vec<_>>()
-        } else {
-            sub_pool
-        };
-
-        let idx = rng.gen_range(0..active_pool.len());
-        Some(active_pool[idx].clone())
+        let mut weights = vec![1.0; active_pool.len()];
+        self.balance_sources(&active_pool, &mut weights);
+        weighted_choice(&active_pool, &weights, &mut rng)
     }
 
     fn pick_sequential(&mut self, descending: bool) -> Option<PathBuf> {
@@ -441,18 +1184,37 @@ impl SmartQueue {
         }
 
         let pool_len = self.pool.len();
-        let picked = self.pool[self.current_index].clone();
-
-        if descending {
-            if self.current_index == 0 {
-                self.current_index = pool_len - 1;
+        let step = |index: usize| {
+            if descending {
+                (index + pool_len - 1) % pool_len
             } else {
-                self.current_index -= 1;
+                (index + 1) % pool_len
+            }
+        };
+
+        // Favorites and tags step over files that don't match, unless none does
+        if self.filtered() {
+            let stats = self.stats.read();
+            let order: Vec<usize> =
+                std::iter::successors(Some(self.current_index), |&i| Some(step(i)))
+                    .take(pool_len)
+                    .collect();
+            let allowed = |i: &&usize| has_tags(&stats, &self.pool[**i], &self.once_tags);
+            let found = order
+                .iter()
+                .filter(allowed)
+                .find(|&&i| self.preferred(&self.pool[i], &stats))
+                .or_else(|| order.iter().find(allowed))
+                .copied();
+            drop(stats);
+            if let Some(index) = found {
+                self.current_index = index;
             }
-        } else {
-            self.current_index = (self.current_index + 1) % pool_len;
         }
 
+        let picked = self.pool[self.current_index].clone();
+        self.current_index = step(self.current_index);
+
         Some(picked)
     }
 
@@ -461,172 +1223,272 @@ impl SmartQueue {
 
         // 1. Filter by video_ratio probability
         let is_video_cycle = rng.gen_range(0..100) < self.video_ratio;
-
-        let sub_pool: Vec<&PathBuf> = self
-            .pool
-            .iter()
-            .filter(|p| {
-                let content_type = Self::get_content_type(p);
-                let is_video = matches!(content_type, Some(ContentType::Video));
-                is_video == is_video_cycle
-            })
-            .collect();
-
-        // Fallback if sub_pool is empty
-        let active_pool = if sub_pool.is_empty() {
-            self.pool.iter().collect::<Vec<_>>()
-        } else {
-            sub_pool
-        };
+        let stats = self.stats.read();
+        let active_pool = self.cycle_pool(is_video_cycle, &stats);
 
         // 2. Weighted Random Selection (Loveit + Recency)
         let mut weights = Vec::new();
         let now = Utc::now();
 
         for path in &active_pool {
-            let stat = self.stats.files.get(*path).cloned().unwrap_or_default();
+            weights.push(loveit_weight(stats.files.get(*path), now) as f64);
+        }
 
-            // Score = LoveMultiplier / (1 + Count) * RecencyFactor
-            let count_score = 100.0 / (stat.count as f32 + 1.0);
+        // 3. Each source's files share its weight
+        self.balance_sources(&active_pool, &mut weights);
+        weighted_choice(&active_pool, &weights, &mut rng)
+    }
 
-            let recency_factor = if let Some(last) = stat.last_seen {
-                let hours_since = (now - last).num_hours() as f32;
-                // Favor items not seen in a long time
-                (hours_since / 24.0).clamp(1.0, 10.0)
-            } else {
-                10.0 // Never seen is high priority
-            };
+    /// Whether `max-depth`, `include` and `exclude` let `path` into the pool
+    fn filter_allows(&self, path: &Path) -> bool {
+        source_of(&self.sources, path).is_none_or(|root| self.filter.allows(root, path))
+    }
 
-            let love_weight = if stat.love_multiplier > 0.0 {
-                stat.love_multiplier
-            } else {
-                1.0
-            };
+    /// Folders the pool is discovered from
+    fn roots(&self) -> Vec<PathBuf> {
+        self.sources.iter().map(|s| s.path.clone()).collect()
+    }
 
-            let weight = count_score * recency_factor * love_weight;
-            weights.push(weight);
+    /// Scales the pick weights of `pool` so each source's files together get that
+    /// source's share, however many there are. Files outside every source count as one
+    /// more source of weight 1. A loaded playlist is picked from as is.
+    fn balance_sources(&self, pool: &[&PathBuf], weights: &mut [f64]) {
+        if self.sources.len() < 2 || self.active_playlist.is_some() {
+            return;
         }
-
-        let total_weight: f32 = weights.iter().sum();
-        let mut choice = rng.gen_range(0.0..total_weight);
-
-        for (i, weight) in weights.iter().enumerate() {
-            choice -= weight;
-            if choice <= 0.0 {
-                return Some(active_pool[i].clone());
-            }
+        let sources: Vec<Option<usize>> = pool
+            .iter()
+            .map(|p| source_index(&self.sources, p))
+            .collect();
+        let mut totals: HashMap<Option<usize>, f64> = HashMap::new();
+        for (source, weight) in sources.iter().zip(weights.iter()) {
+            *totals.entry(*source).or_default() += weight;
         }
+        for (source, weight) in sources.iter().zip(weights.iter_mut()) {
+            let share = source.map_or(1.0, |i| self.sources[i].weight as f64);
+            let total = totals[source];
+            *weight = if total > 0.0 {
+                *weight * share / total
+            } else {
+                0.0
+            };
+        }
+    }
 
-        Some(active_pool[0].clone())
+    /// Whether picks are limited to loved files (`loved-only` or `kldctl favorites on`)
+    fn loved_only(&self) -> bool {
+        self.favorites || self.strategy == crate::orchestration::SortingStrategy::LovedOnly
     }
 
-    fn update_stats(&mut self, path: &Path) {
-        // Update the stat in memory
-        let stat = self
-            .stats
-            .files
-            .entry(path.to_path_buf())
-            .or_insert_with(|| FileStats {
-                count: 0,
-                last_seen: None,
-                love_multiplier: 1.0,
-            });
-        stat.count += 1;
-        stat.last_seen = Some(Utc::now());
+    /// Whether picks are narrowed down from the pool: favorites or tags
+    fn filtered(&self) -> bool {
+        self.loved_only() || !self.tags.is_empty() || !self.once_tags.is_empty()
+    }
 
-        // Add to pending updates for batched write
-        self.pending_stats_updates
-            .insert(path.to_path_buf(), stat.clone());
+    /// Whether `path` passes the favorites and `tags` filters, which give way when
+    /// nothing in the pool does
+    fn preferred(&self, path: &Path, stats: &LoveitData) -> bool {
+        (!self.loved_only() || is_loved(stats.files.get(path))) && has_tags(stats, path, &self.tags)
+    }
 
-        // Limit stats growth (LRU-ish removal)
-        if self.stats.files.len() > 5000 {
-            let oldest = self
-                .stats
-                .files
+    /// Videos or images for a video/image cycle, or the whole pool if there are none.
+    /// With favorites or `tags` only matching files count, unless none of the pool
+    /// matches; `once_tags` always apply.
+    fn cycle_pool(&self, videos: bool, stats: &LoveitData) -> Vec<&PathBuf> {
+        let mut pool: Vec<&PathBuf> = self
+            .pool
+            .iter()
+            .filter(|p| has_tags(stats, p, &self.once_tags))
+            .collect();
+        if self.loved_only() || !self.tags.is_empty() {
+            let preferred: Vec<&PathBuf> = pool
                 .iter()
-                .min_by_key(|(_, s)| s.last_seen.map(|d| d.timestamp()).unwrap_or(0))
-                .map(|(p, _)| p.clone());
-            if let Some(p) = oldest {
-                self.stats.files.remove(&p);
-                self.pending_stats_updates.remove(&p);
+                .copied()
+                .filter(|p| self.preferred(p, stats))
+                .collect();
+            if !preferred.is_empty() {
+                pool = preferred;
             }
         }
-    }
-
-    /// Flush pending stats updates to cache in a batch
-    pub fn flush_stats(&mut self) -> Result<()> {
-        if self.pending_stats_updates.is_empty() {
-            return Ok(());
-        }
 
-        let updates: Vec<_> = self.pending_stats_updates.drain().collect();
+        let sub_pool: Vec<&PathBuf> = pool
+            .iter()
+            .copied()
+            .filter(|p| Self::get_content_type(p).is_some_and(ContentType::is_moving) == videos)
+            .collect();
 
-        self.cache.batch_set_file_stats(&updates)?;
-        Ok(())
+        if sub_pool.is_empty() {
+            pool
+        } else {
+            sub_pool
+        }
     }
 
-    fn load_stats_from_cache(cache: &FileCache) -> Result<LoveitData> {
-        // Load from redb cache
-        let files = cache.get_all_file_stats()?;
-        let playlists = cache.get_all_playlists()?;
-        let blacklist = cache.get_all_blacklisted()?;
-
-        Ok(LoveitData {
-            files,
-            playlists,
-            blacklist,
-        })
+    /// Loved files in the pool, for `kldctl favorites`
+    pub fn loved_count(&self) -> usize {
+        let stats = self.stats.read();
+        self.pool
+            .iter()
+            .filter(|p| is_loved(stats.files.get(*p)))
+            .count()
     }
 
-    pub fn save_stats(&mut self) -> Result<()> {
-        // Flush any pending stats updates first
-        self.flush_stats()?;
+    /// The next `count` picks for `kldctl pick --dry-run`, without touching any state.
+    /// Sequential strategies list the upcoming files in order. Random and loveit list
+    /// the most likely candidates with their chance of being the next pick, combining
+    /// the video/image cycle roll with the file's share of its sub-pool's weight.
+    pub fn preview(&self, count: usize) -> Vec<kaleidux_common::PickCandidate> {
+        use crate::orchestration::SortingStrategy;
+        use kaleidux_common::PickCandidate;
+
+        let skipped = |path: &Path| {
+            if self.stats.is_backing_off(path) {
+                Some("backing off after a failed load".to_string())
+            } else if self.avoid.contains(path) {
+                Some("recently shown on another output".to_string())
+            } else {
+                None
+            }
+        };
 
-        // Save playlists
-        for (name, playlist) in &self.stats.playlists {
-            let _ = self.cache.set_playlist(name, playlist);
+        if self.pool.is_empty() {
+            return Vec::new();
         }
 
-        // Save blacklist
-        for path in &self.stats.blacklist {
-            let _ = self.cache.set_blacklisted(path, true);
+        if let SortingStrategy::Ascending | SortingStrategy::Descending = self.strategy {
+            let len = self.pool.len();
+            let descending = matches!(self.strategy, SortingStrategy::Descending);
+            let upcoming = (0..len).map(|step| {
+                let idx = if descending {
+                    (self.current_index + len - step % len) % len
+                } else {
+                    (self.current_index + step) % len
+                };
+                &self.pool[idx]
+            });
+            // Favorites and tags step over files that don't match, unless none does
+            let loved: Vec<&PathBuf> = if self.loved_only() || !self.tags.is_empty() {
+                let stats = self.stats.read();
+                upcoming
+                    .clone()
+                    .filter(|p| self.preferred(p, &stats))
+                    .take(count)
+                    .collect()
+            } else {
+                Vec::new()
+            };
+            let upcoming: Vec<&PathBuf> = if loved.is_empty() {
+                upcoming.take(count).collect()
+            } else {
+                loved
+            };
+            return upcoming
+                .into_iter()
+                .map(|path| PickCandidate {
+                    path: path.to_string_lossy().to_string(),
+                    weight: None,
+                    probability: None,
+                    skipped: skipped(path),
+                })
+                .collect();
         }
 
-        Ok(())
+        let now = Utc::now();
+        let stats = self.stats.read();
+        let video_chance = self.video_ratio as f64 / 100.0;
+        let mut candidates: HashMap<&PathBuf, (f64, f64)> = HashMap::new();
+        // Both cycles, each weighted by how often it is rolled. When one sub-pool is
+        // empty its cycle falls back to the whole pool, same as the real pick.
+        for (videos, chance) in [(true, video_chance), (false, 1.0 - video_chance)] {
+            if chance <= 0.0 {
+                continue;
+            }
+            let pool = self.cycle_pool(videos, &stats);
+            let weights: Vec<f64> = pool
+                .iter()
+                .map(|path| match self.strategy {
+                    SortingStrategy::Loveit | SortingStrategy::LovedOnly => {
+                        loveit_weight(stats.files.get(*path), now) as f64
+                    }
+                    _ => 1.0,
+                })
+                .collect();
+            let mut shares = weights.clone();
+            self.balance_sources(&pool, &mut shares);
+            let total: f64 = shares.iter().sum();
+            if total <= 0.0 {
+                continue;
+            }
+            for ((path, weight), share) in pool.into_iter().zip(weights).zip(shares) {
+                let entry = candidates.entry(path).or_insert((weight, 0.0));
+                entry.1 += chance * share / total;
+            }
+        }
+        drop(stats);
+
+        let loveit = matches!(
+            self.strategy,
+            SortingStrategy::Loveit | SortingStrategy::LovedOnly
+        );
+        let mut ranked: Vec<_> = candidates.into_iter().collect();
+        ranked.sort_by(|a, b| b.1 .1.total_cmp(&a.1 .1).then_with(|| a.0.cmp(b.0)));
+        ranked
+            .into_iter()
+            .take(count)
+            .map(|(path, (weight, probability))| PickCandidate {
+                path: path.to_string_lossy().to_string(),
+                weight: loveit.then_some(weight),
+                probability: Some(probability),
+                skipped: skipped(path),
+            })
+            .collect()
     }
 
-    pub fn love_file(&mut self, path: PathBuf, multiplier: f32) -> Result<()> {
-        let stat = self.stats.files.entry(path).or_default();
-        stat.love_multiplier = multiplier;
-        self.save_stats()
+    fn update_stats(&mut self, path: &Path) {
+        self.stats.record_play(path);
     }
 
     pub fn set_playlist(&mut self, name: Option<String>) -> Result<()> {
         if let Some(ref n) = name {
-            if let Some(playlist) = self.stats.playlists.get(n) {
+            let stats = self.stats.read();
+            if let Some(playlist) = stats.playlists.get(n) {
                 if !playlist.enabled {
-                    anyhow::bail!("Playlist '{}' is disabled", n);
+                    anyhow::bail!(CommandError::new(
+                        ErrorCode::Unavailable,
+                        format!("Playlist '{}' is disabled", n)
+                    ));
                 }
-                // Filter playlist paths against blacklist
+                // Filter playlist paths against blacklist and quarantine
                 self.pool = playlist
                     .paths
                     .iter()
-                    .filter(|p| !self.stats.blacklist.contains(*p))
+                    .filter(|p| {
+                        !stats.blacklist.contains(*p) && !stats.quarantine.contains_key(*p)
+                    })
                     .cloned()
                     .collect();
                 // If playlist has a strategy, use it? Or keep global?
                 // For now, let's stick to global strategy unless we want to override it.
             } else {
-                anyhow::bail!("Playlist '{}' not found", n);
+                anyhow::bail!(CommandError::new(
+                    ErrorCode::NotFound,
+                    format!("Playlist '{}' not found", n)
+                ));
             }
         } else {
-            // Reset to full discovery (no metrics available in this context)
+            // Reset to full discovery (no metrics available in this context).
+            // Cloned so the shared lock isn't held during the directory walk.
+            let excluded = self.stats.excluded();
             self.pool = Self::discover_content(
-                &self.root_path,
-                &self.stats.blacklist,
+                &self.roots(),
+                &self.filter,
+                &excluded,
                 self.cache.clone(),
                 None,
+                None,
             )?;
+            // This scan is complete, an older one still running has nothing to add
+            self.pending_discovery = None;
         }
 
         self.active_playlist = name;
@@ -635,19 +1497,140 @@ impl SmartQueue {
         Ok(())
     }
 
-    pub fn blacklist_file(&mut self, path: PathBuf) -> Result<()> {
-        self.stats.blacklist.insert(path.clone());
-        self.pool.retain(|p| p != &path);
-        self.save_stats()
+    pub fn blacklist_files(&mut self, paths: &[PathBuf]) -> Result<()> {
+        self.stats.set_blacklisted(paths, true)?;
+        let paths: std::collections::HashSet<&PathBuf> = paths.iter().collect();
+        self.pool.retain(|p| !paths.contains(p));
+        Ok(())
+    }
+
+    pub fn unblacklist_files(&mut self, paths: &[PathBuf]) -> Result<()> {
+        self.stats.set_blacklisted(paths, false)?;
+        self.restore_files(paths)
+    }
+
+    /// Brings the pool in line with files added to or removed from the library while
+    /// the daemon runs (see `DirectoryWatcher::library_changes`). A loaded playlist only
+    /// loses files. Sequential strategies continue from the file they were at.
+    pub fn apply_library_changes(&mut self, changes: &crate::cache::LibraryChanges) {
+        let gone = |p: &PathBuf| changes.removed.iter().any(|r| p.starts_with(r));
+        let mut added = Vec::new();
+        if self.active_playlist.is_none() {
+            let in_library = |p: &&PathBuf| self.sources.iter().any(|s| p.starts_with(&s.path));
+            for path in changes.added.iter().filter(in_library) {
+                added.extend(self.new_entries(path));
+            }
+            let excluded = self.stats.excluded();
+            added.sort();
+            added.dedup();
+            added.retain(|p| {
+                !excluded.contains(p) && !self.pool.contains(p) && self.filter_allows(p)
+            });
+        }
+        let history_len = self.history.len();
+        self.history.retain(|p| !gone(p));
+        self.history_changed |= self.history.len() != history_len;
+        if added.is_empty() && !self.pool.iter().any(gone) {
+            return;
+        }
+
+        let before = self.pool.len();
+        let next = self.pool.get(self.current_index).cloned();
+        self.pool.retain(|p| !gone(p));
+        // Frames of a folder that just became an image sequence go with it
+        self.pool
+            .retain(|p| !p.parent().is_some_and(|dir| added.iter().any(|a| a == dir)));
+        let removed = before - self.pool.len();
+        if !added.is_empty() {
+            tracing::info!(
+                "[WATCH] {:?}: {} new wallpapers, {} gone",
+                self.root_path,
+                added.len(),
+                removed
+            );
+            self.pool.append(&mut added);
+            self.pool.sort();
+        } else {
+            tracing::info!("[WATCH] {:?}: {} wallpapers gone", self.root_path, removed);
+        }
+        // The file that was up next, or the one after it if that is gone
+        self.current_index = next
+            .and_then(|n| self.pool.iter().position(|p| *p >= n))
+            .unwrap_or(0);
+    }
+
+    /// Wallpapers a path new in the library brings: itself if it can be played, the
+    /// image sequence it is a frame of, or the ones in a folder that was moved in
+    fn new_entries(&self, path: &Path) -> Vec<PathBuf> {
+        let sequence = |dir: &Path| {
+            !self.sources.iter().any(|s| s.path == dir) && crate::sequence::frames(dir).is_some()
+        };
+        if path.is_dir() {
+            if sequence(path) {
+                return vec![path.to_path_buf()];
+            }
+            let excluded = self.stats.excluded();
+            return Self::discover_content(
+                &[path.to_path_buf()],
+                &LibraryFilter::default(),
+                &excluded,
+                self.cache.clone(),
+                None,
+                None,
+            )
+            .unwrap_or_default();
+        }
+        match path.parent() {
+            Some(dir) if sequence(dir) => vec![dir.to_path_buf()],
+            _ if Self::get_content_type(path).is_some() => vec![path.to_path_buf()],
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn history_loaded(&self) -> bool {
+        self.history_loaded
+    }
+
+    /// Puts the history saved by the last run in front of what this queue picked so
+    /// far, leaving out files that are gone
+    pub fn restore_history(&mut self, saved: Vec<PathBuf>) {
+        self.history_loaded = true;
+        if saved.is_empty() {
+            return;
+        }
+        let mut history: Vec<PathBuf> = saved.into_iter().filter(|p| p.exists()).collect();
+        history.append(&mut self.history);
+        let excess = history.len().saturating_sub(HISTORY_LIMIT);
+        history.drain(..excess);
+        self.history = history;
+    }
+
+    /// Whether the history changed since the last call, i.e. needs saving
+    pub fn take_history_change(&mut self) -> bool {
+        std::mem::take(&mut self.history_changed)
+    }
+
+    /// Drops a file that was just quarantined (the store is already updated) or is
+    /// about to be deleted. The sequential position stays on the same next file.
+    pub fn remove_file(&mut self, path: &Path) {
+        while let Some(index) = self.pool.iter().position(|p| p == path) {
+            self.pool.remove(index);
+            if index < self.current_index {
+                self.current_index -= 1;
+            }
+        }
+        if self.current_index >= self.pool.len() {
+            self.current_index = 0;
+        }
     }
 
-    pub fn unblacklist_file(&mut self, path: PathBuf) -> Result<()> {
-        if self.stats.blacklist.remove(&path) {
-            // If we are currently in "All" mode (no playlist), add it back if it exists in root
-            // If we are in a playlist, add it back if it's in the playlist
-            // Simplest way is just to reload the current playlist/root
+    /// Brings released or unblacklisted files back. The store is shared, so only the
+    /// first queue sees the change; every queue missing any of the files reloads its
+    /// playlist/root once to pick them up again if they belong.
+    pub fn restore_files(&mut self, paths: &[PathBuf]) -> Result<()> {
+        let pool: std::collections::HashSet<&PathBuf> = self.pool.iter().collect();
+        if paths.iter().any(|p| !pool.contains(p)) {
             self.set_playlist(self.active_playlist.clone())?;
-            self.save_stats()?;
         }
         Ok(())
     }
@@ -0,0 +1,434 @@
+use crate::orchestration::{Config, RemoteConfig};
+use crate::queue::SmartQueue;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// First retry after a failed sync; doubles with every further failure, up to `refresh`
+const RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// Whether a `path` or `sources` entry is an http(s) URL rather than a local path
+pub fn is_url(path: &Path) -> bool {
+    path.to_str()
+        .is_some_and(|p| p.starts_with("http://") || p.starts_with("https://"))
+}
+
+fn remote_dir() -> Option<PathBuf> {
+    crate::cache::cache_root().map(|d| d.join("remote"))
+}
+
+/// Hex hash naming the download folder of a URL and the files in it
+fn key(url: &str) -> String {
+    format!("{:016x}", crate::cache::stable_hash(url.as_bytes()))
+}
+
+/// `key` as it was when folders were named with std's `DefaultHasher`, whose output
+/// may change with the toolchain; only used to carry such folders over
+fn legacy_key(url: &str) -> String {
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    url.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// Where a source is read from: for a URL the folder it is downloaded to (created if
+/// needed, so it can be watched before the first download), otherwise `path` itself
+pub fn local_path(path: &Path) -> PathBuf {
+    let Some(root) = remote_dir().filter(|_| is_url(path)) else {
+        return path.to_path_buf();
+    };
+    let url = path.to_string_lossy();
+    let dir = root.join(key(&url));
+    if !dir.exists() {
+        // Keep what was downloaded under the old name; its state file lists the files
+        let legacy = legacy_key(&url);
+        if std::fs::rename(root.join(&legacy), &dir).is_ok() {
+            let _ = std::fs::rename(
+                root.join(format!("{}.json", legacy)),
+                root.join(format!("{}.json", key(&url))),
+            );
+        }
+    }
+    if let Err(e) = std::fs::create_dir_all(&dir) {
+        warn!("[REMOTE] Failed to create {}: {}", dir.display(), e);
+    }
+    dir
+}
+
+/// What was downloaded for one source URL, kept next to its folder
+#[derive(Default, Serialize, Deserialize)]
+struct State {
+    /// URLs the source listed when it was a list rather than a wallpaper
+    manifest: Option<Vec<String>>,
+    /// Every URL downloaded for the source, the source itself included
+    entries: BTreeMap<String, Entry>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct Entry {
+    /// File name in the source's folder (empty for a list)
+    file: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+/// Outcome of a `sync`
+#[derive(Default)]
+pub struct Synced {
+    /// Whether files in the source's folder were downloaded or replaced
+    pub changed: bool,
+    /// Files the source no longer lists. Still on disk: they may be in a queue, so the
+    /// main loop drops them from every queue first and deletes them after.
+    pub stale: Vec<PathBuf>,
+}
+
+enum Fetched {
+    NotModified,
+    /// The new body is in the temp file
+    Body(Entry),
+}
+
+/// Brings the folder of the source `url` up to date. The URL either serves a
+/// wallpaper, or a list of wallpaper URLs (one per line, `#` lines are comments) that
+/// are each downloaded. Unchanged files are not downloaded again (ETag /
+/// If-Modified-Since). Blocking; call from spawn_blocking.
+pub fn sync(url: &str, config: &RemoteConfig) -> Result<Synced> {
+    let root = remote_dir().context("Failed to get cache directory")?;
+    let dir = local_path(Path::new(url));
+    let state_path = root.join(format!("{}.json", key(url)));
+    let mut state: State = std::fs::read(&state_path)
+        .ok()
+        .and_then(|data| serde_json::from_slice(&data).ok())
+        .unwrap_or_default();
+    let temp = root.join(format!(".tmp-{}", key(url)));
+
+    let mut synced = Synced::default();
+    let manifest = match fetch(url, state.entries.get(url), &[], config, &temp)? {
+        Fetched::NotModified => match &state.manifest {
+            Some(urls) => urls.clone(),
+            None => return Ok(synced),
+        },
+        Fetched::Body(entry) => {
+            if SmartQueue::get_content_type(&temp).is_some() {
+                state.manifest = None;
+                store(&dir, url, entry, &temp, &mut state, &mut synced)?;
+                Vec::new()
+            } else {
+                let text = std::fs::read_to_string(&temp);
+                let _ = std::fs::remove_file(&temp);
+                let urls: Vec<String> = text
+                    .unwrap_or_default()
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.starts_with('#') && is_url(Path::new(line)))
+                    .map(str::to_string)
+                    .collect();
+                if urls.is_empty() {
+                    bail!("{} is neither a wallpaper nor a list of URLs", url);
+                }
+                state.entries.insert(url.to_string(), entry);
+                state.manifest = Some(urls.clone());
+                urls
+            }
+        }
+    };
+
+    for entry_url in &manifest {
+        match fetch(entry_url, state.entries.get(entry_url), &[], config, &temp) {
+            Ok(Fetched::NotModified) => {}
+            Ok(Fetched::Body(entry)) if SmartQueue::get_content_type(&temp).is_some() => {
+                store(&dir, entry_url, entry, &temp, &mut state, &mut synced)?;
+            }
+            Ok(Fetched::Body(_)) => {
+                let _ = std::fs::remove_file(&temp);
+                warn!("[REMOTE] {}: not a supported wallpaper, skipped", entry_url);
+            }
+            // The file downloaded before stays until the next try
+            Err(e) => warn!("[REMOTE] {}: {:#}", entry_url, e),
+        }
+    }
+
+    // Files of URLs the source no longer lists, and anything else left in the folder
+    state
+        .entries
+        .retain(|entry_url, _| entry_url == url || manifest.contains(entry_url));
+    if state.manifest.is_some() {
+        if let Some(entry) = state.entries.get_mut(url) {
+            entry.file.clear();
+        }
+    }
+    for file in std::fs::read_dir(&dir).into_iter().flatten().flatten() {
+        let name = file.file_name().to_string_lossy().to_string();
+        if !state.entries.values().any(|e| e.file == name) {
+            synced.stale.push(file.path());
+        }
+    }
+
+    let data = serde_json::to_vec(&state)?;
+    std::fs::write(&state_path, data)
+        .with_context(|| format!("Failed to write {}", state_path.display()))?;
+    Ok(synced)
+}
+
+/// Downloads `url` to `target` within the `[remote]` size and time limits, without the
+/// revalidation of source URLs. `headers` ("Name: value") may carry secrets: they are
+/// never put on curl's command line. Blocking; call from spawn_blocking.
+pub fn download(url: &str, headers: &[String], config: &RemoteConfig, target: &Path) -> Result<()> {
+    let temp = target.with_file_name(format!(".tmp-{}", key(url)));
+    match fetch(url, None, headers, config, &temp)? {
+        Fetched::Body(_) => std::fs::rename(&temp, target)
+            .with_context(|| format!("Failed to move download to {}", target.display())),
+        // Only answers a request with validators
+        Fetched::NotModified => bail!("{}: unexpected 304 Not Modified", url),
+    }
+}
+
+/// Moves the download of `url` in `temp` into `dir`; the file it had before under
+/// another name becomes stale
+fn store(
+    dir: &Path,
+    url: &str,
+    mut entry: Entry,
+    temp: &Path,
+    state: &mut State,
+    synced: &mut Synced,
+) -> Result<()> {
+    entry.file = file_name(url);
+    let target = dir.join(&entry.file);
+    std::fs::rename(temp, &target)
+        .with_context(|| format!("Failed to move download to {}", target.display()))?;
+    if let Some(old) = state.entries.insert(url.to_string(), entry) {
+        if !old.file.is_empty() && dir.join(&old.file) != target {
+            synced.stale.push(dir.join(old.file));
+        }
+    }
+    synced.changed = true;
+    Ok(())
+}
+
+/// Name a URL's download is saved under: its last path segment, made unique per URL
+fn file_name(url: &str) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    let name: String = path
+        .rsplit('/')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+        .collect();
+    let name = name.trim_start_matches('.');
+    let name = if name.is_empty() { "wallpaper" } else { name };
+    format!("{}-{}", &key(url)[..8], name)
+}
+
+/// Downloads `url` into `temp` with curl, unless `cached` is still current. Follows
+/// redirects, retries transient errors and gives up past `max-size` or `timeout`.
+fn fetch(
+    url: &str,
+    cached: Option<&Entry>,
+    extra_headers: &[String],
+    config: &RemoteConfig,
+    temp: &Path,
+) -> Result<Fetched> {
+    let headers = temp.with_extension("headers");
+    let max_size = config.max_bytes();
+    let mut command = std::process::Command::new("curl");
+    command
+        .args(["--silent", "--show-error", "--fail", "--location"])
+        .args(["--proto", "=http,https", "--retry", "2"])
+        .arg("--max-filesize")
+        .arg(max_size.to_string())
+        .arg("--max-time")
+        .arg(config.timeout.as_secs().max(1).to_string())
+        .arg("--dump-header")
+        .arg(&headers)
+        .arg("--output")
+        .arg(temp)
+        .args(["--write-out", "%{http_code}"]);
+    if let Some(etag) = cached.and_then(|e| e.etag.as_deref()) {
+        command
+            .arg("--header")
+            .arg(format!("If-None-Match: {}", etag));
+    }
+    if let Some(date) = cached.and_then(|e| e.last_modified.as_deref()) {
+        command
+            .arg("--header")
+            .arg(format!("If-Modified-Since: {}", date));
+    }
+    if !extra_headers.is_empty() {
+        command.args(["--config", "-"]);
+    }
+    command.arg("--").arg(url);
+    let output =
+        run_curl(&mut command, extra_headers).context("Failed to run curl (is it installed?)")?;
+    let header_text = std::fs::read_to_string(&headers).unwrap_or_default();
+    let _ = std::fs::remove_file(&headers);
+
+    let discard = || {
+        let _ = std::fs::remove_file(temp);
+    };
+    if !output.status.success() {
+        discard();
+        // 63: the server announced a body larger than --max-filesize
+        if output.status.code() == Some(63) {
+            bail!("larger than max-size ({} bytes)", max_size);
+        }
+        bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    if String::from_utf8_lossy(&output.stdout).trim() == "304" {
+        discard();
+        return Ok(Fetched::NotModified);
+    }
+    // Servers that don't announce the length are only caught afterwards
+    if std::fs::metadata(temp).is_ok_and(|m| m.len() > max_size) {
+        discard();
+        bail!("larger than max-size ({} bytes)", max_size);
+    }
+
+    // Validators of the last response, after any redirects
+    let mut entry = Entry::default();
+    for line in header_text.lines() {
+        if line.starts_with("HTTP/") {
+            entry = Entry::default();
+        }
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = Some(value.trim().to_string()).filter(|v| !v.is_empty());
+        match name.trim().to_ascii_lowercase().as_str() {
+            "etag" => entry.etag = value,
+            "last-modified" => entry.last_modified = value,
+            _ => {}
+        }
+    }
+    Ok(Fetched::Body(entry))
+}
+
+/// Runs curl, handing it `headers` on stdin (`--config -`) rather than as arguments:
+/// any local user can read a process's command line from /proc
+fn run_curl(
+    command: &mut std::process::Command,
+    headers: &[String],
+) -> std::io::Result<std::process::Output> {
+    use std::io::Write;
+    use std::process::Stdio;
+    if headers.is_empty() {
+        return command.output();
+    }
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let config: String = headers
+        .iter()
+        .map(|header| {
+            let header = header
+                .chars()
+                .filter(|c| !c.is_control())
+                .collect::<String>()
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"");
+            format!("header = \"{}\"\n", header)
+        })
+        .collect();
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(config.as_bytes())?;
+    }
+    child.wait_with_output()
+}
+
+#[derive(Default)]
+struct Schedule {
+    due: Option<Instant>,
+    failures: u32,
+    running: bool,
+}
+
+/// Keeps the folders of URL sources up to date: every URL in the config is synced on
+/// startup and then every `refresh`, and retried sooner after a failure. Polled from
+/// the main loop; downloads run on the blocking pool and the directory watcher picks
+/// up the files they change.
+pub struct RemoteSync {
+    sources: HashMap<String, Schedule>,
+    done_tx: std::sync::mpsc::Sender<(String, Result<Synced>)>,
+    done_rx: std::sync::mpsc::Receiver<(String, Result<Synced>)>,
+    last_check: Option<Instant>,
+}
+
+impl RemoteSync {
+    const INTERVAL: Duration = Duration::from_secs(5);
+
+    pub fn new() -> Self {
+        let (done_tx, done_rx) = std::sync::mpsc::channel();
+        Self {
+            sources: HashMap::new(),
+            done_tx,
+            done_rx,
+            last_check: None,
+        }
+    }
+
+    /// Returns the stale files of the syncs that finished since the last call, for
+    /// `MonitorManager::delete_files`
+    pub fn poll(&mut self, config: &Config) -> Vec<PathBuf> {
+        let mut stale = Vec::new();
+        if self
+            .last_check
+            .is_some_and(|t| t.elapsed() < Self::INTERVAL)
+        {
+            return stale;
+        }
+        self.last_check = Some(Instant::now());
+        let refresh = config.remote.refresh.max(Duration::from_secs(60));
+
+        while let Ok((url, result)) = self.done_rx.try_recv() {
+            let Some(schedule) = self.sources.get_mut(&url) else {
+                continue;
+            };
+            schedule.running = false;
+            let delay = match result {
+                Ok(synced) => {
+                    if synced.changed {
+                        info!("[REMOTE] {}: downloaded new wallpapers", url);
+                    }
+                    stale.extend(synced.stale);
+                    schedule.failures = 0;
+                    refresh
+                }
+                Err(e) => {
+                    schedule.failures += 1;
+                    let delay = RETRY_DELAY
+                        .saturating_mul(1 << (schedule.failures - 1).min(10))
+                        .min(refresh);
+                    warn!(
+                        "[REMOTE] {}: sync failed ({} in a row), retrying in {:?}: {:#}",
+                        url, schedule.failures, delay, e
+                    );
+                    delay
+                }
+            };
+            schedule.due = Some(Instant::now() + delay);
+        }
+
+        let urls = config.remote_urls();
+        self.sources.retain(|url, _| urls.contains(url));
+        for url in urls {
+            let schedule = self.sources.entry(url.clone()).or_default();
+            if schedule.running || schedule.due.is_some_and(|due| due > Instant::now()) {
+                continue;
+            }
+            schedule.running = true;
+            let remote = config.remote.clone();
+            let done_tx = self.done_tx.clone();
+            tokio::task::spawn_blocking(move || {
+                let result = sync(&url, &remote);
+                let _ = done_tx.send((url, result));
+            });
+        }
+        stale
+    }
+}
@@ -16,6 +16,59 @@ struct TransitionUniforms {
     prev_aspect: f32,
     next_aspect: f32,
     params: [[f32; 4]; 7], // Total 128 bytes (aligned)
+    effects: [f32; 4],     // Blit post effects: x = grayscale, y = premultiply, z = exposure
+    system: [[f32; 4]; 2], // Machine state for custom shaders, see monitor::SystemSampler
+    crop: [[f32; 4]; 2],   // Crop windows (zoom, center x, center y, _) for prev and next
+    fit: [f32; 4],         // x = fit-mode (see FitMode::code)
+    fit_size: [f32; 4],    // Prev and next content size relative to the screen (w, h, w, h)
+    fit_color: [f32; 4],   // Linear color around contained or centered content
+}
+
+/// Crop window that leaves the content untouched
+pub const NO_CROP: [f32; 4] = [1.0, 0.5, 0.5, 0.0];
+
+/// Usage of video frame textures: NV12 frames are drawn into them by the YUV pass
+const VIDEO_TEXTURE_USAGE: wgpu::TextureUsages = wgpu::TextureUsages::TEXTURE_BINDING
+    .union(wgpu::TextureUsages::COPY_DST)
+    .union(wgpu::TextureUsages::RENDER_ATTACHMENT);
+
+/// How often a playing video's brightness is measured for `max-luminance`
+const LUMINANCE_SAMPLE_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
+/// Weight of a new video measurement, so exposure follows scene changes without flicker
+const LUMINANCE_SMOOTHING: f32 = 0.3;
+
+/// Linear value (0-1) of an sRGB-encoded channel
+fn srgb_to_linear(c: u8) -> f32 {
+    let c = c as f32 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Mean linear luminance (0-1) of sRGB-encoded RGBA pixels, from a grid of at most 64x64
+/// samples so even 4K frames cost a few microseconds
+fn mean_luminance(data: &[u8], width: u32, height: u32, stride: u32) -> f32 {
+    let (step_x, step_y) = ((width / 64).max(1), (height / 64).max(1));
+    let (mut sum, mut count) = (0.0, 0u32);
+    for y in (0..height).step_by(step_y as usize) {
+        for x in (0..width).step_by(step_x as usize) {
+            let i = (y * stride + x * 4) as usize;
+            let Some(px) = data.get(i..i + 3) else {
+                continue;
+            };
+            sum += 0.2126 * srgb_to_linear(px[0])
+                + 0.7152 * srgb_to_linear(px[1])
+                + 0.0722 * srgb_to_linear(px[2]);
+            count += 1;
+        }
+    }
+    if count == 0 {
+        0.0
+    } else {
+        sum / count as f32
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -89,6 +142,12 @@ impl PipelineLRU {
         self.pipelines.len()
     }
 
+    /// Drops every pipeline but those named in `keep`
+    fn retain(&mut self, keep: &[String]) {
+        self.pipelines.retain(|name, _| keep.contains(name));
+        self.access_order.retain(|name| keep.contains(name));
+    }
+
     #[allow(dead_code)]
     pub fn contains_key(&self, key: &str) -> bool {
         self.pipelines.contains_key(key)
@@ -104,46 +163,74 @@ pub struct WgpuContext {
     pub blit_pipelines: parking_lot::Mutex<HashMap<wgpu::TextureFormat, Arc<wgpu::RenderPipeline>>>,
     pub mipmap_pipelines:
         parking_lot::Mutex<HashMap<wgpu::TextureFormat, Arc<wgpu::RenderPipeline>>>,
+    pub overlay_pipelines:
+        parking_lot::Mutex<HashMap<wgpu::TextureFormat, Arc<wgpu::RenderPipeline>>>,
     pub blit_bind_group_layout: wgpu::BindGroupLayout,
     pub transition_bind_group_layout: wgpu::BindGroupLayout,
     pub mipmap_bind_group_layout: wgpu::BindGroupLayout,
     // Texture pool: (width, height) -> Vec of available textures
     pub texture_pool: parking_lot::Mutex<HashMap<(u32, u32), Vec<TexturePoolEntry>>>,
+    /// Built on first use, most sessions never blur anything
+    blur_pass: std::sync::OnceLock<crate::blur::BlurPass>,
+    /// Built with the first NV12 video frame
+    yuv_pass: std::sync::OnceLock<crate::yuv::YuvPass>,
 }
 
 const MAX_PIPELINE_CACHE_SIZE: usize = 50;
 const MAX_TEXTURE_POOL_SIZE: usize = 50; // Global limit on total textures in pool
 
 impl WgpuContext {
-    pub async fn with_surface(
+    /// Finds the adapter for `window`'s output. `gpu` (an output's `gpu` setting) picks
+    /// it by index or name; without it wgpu's high-performance choice is used. Returns
+    /// the instance and the surface the adapter was checked against along with it.
+    pub async fn probe_adapter(
         window: Arc<impl HasWindowHandle + HasDisplayHandle + Sync + Send + 'static>,
-    ) -> anyhow::Result<(Arc<Self>, Surface<'static>)> {
+        gpu: Option<&str>,
+    ) -> anyhow::Result<(Instance, Surface<'static>, Adapter)> {
         let instance = Instance::new(wgpu::InstanceDescriptor {
             backends: wgpu::Backends::all(),
             ..Default::default()
         });
         let compatible_surface = instance.create_surface(window)?;
 
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::HighPerformance,
-                compatible_surface: Some(&compatible_surface), // Restore this - required for presentation support
-                force_fallback_adapter: false,
-            })
-            .await
-            .ok_or_else(|| anyhow::anyhow!("Failed to find a suitable GPU adapter"))?;
+        let selected = gpu.and_then(|selector| {
+            let adapter = Self::select_adapter(&instance, &compatible_surface, selector);
+            if adapter.is_none() {
+                warn!(
+                    "[GPU] No adapter matches gpu = {:?}, using the default one",
+                    selector
+                );
+            }
+            adapter
+        });
+        let adapter = match selected {
+            Some(adapter) => adapter,
+            None => instance
+                .request_adapter(&wgpu::RequestAdapterOptions {
+                    power_preference: wgpu::PowerPreference::HighPerformance,
+                    compatible_surface: Some(&compatible_surface), // Restore this - required for presentation support
+                    force_fallback_adapter: false,
+                })
+                .await
+                .ok_or_else(|| anyhow::anyhow!("Failed to find a suitable GPU adapter"))?,
+        };
 
         info!(
             "WGPU picked adapter: {:?} with backend: {:?}",
             adapter.get_info().name,
             adapter.get_info().backend
         );
+        Ok((instance, compatible_surface, adapter))
+    }
 
+    /// Creates the device and shared state on an adapter from `probe_adapter`
+    pub async fn new(instance: Instance, adapter: Adapter) -> anyhow::Result<Arc<Self>> {
         let (device, queue) = adapter
             .request_device(
                 &wgpu::DeviceDescriptor {
                     label: Some("Kaleidux Shared Device"),
-                    required_features: wgpu::Features::empty(),
+                    // Timestamp queries feed GPU pass times into the metrics where supported
+                    required_features: adapter.features() & wgpu::Features::TIMESTAMP_QUERY,
                     required_limits: adapter.limits(),
                     memory_hints: wgpu::MemoryHints::default(),
                 },
@@ -251,24 +338,58 @@ impl WgpuContext {
                 ],
             });
 
-        Ok((
-            Arc::new(Self {
-                instance,
-                adapter,
-                device,
-                queue,
-                transition_pipelines: parking_lot::Mutex::new(PipelineLRU::new(
-                    MAX_PIPELINE_CACHE_SIZE,
-                )),
-                blit_pipelines: parking_lot::Mutex::new(HashMap::new()),
-                mipmap_pipelines: parking_lot::Mutex::new(HashMap::new()),
-                blit_bind_group_layout,
-                transition_bind_group_layout,
-                mipmap_bind_group_layout,
-                texture_pool: parking_lot::Mutex::new(HashMap::new()),
-            }),
-            compatible_surface,
-        ))
+        Ok(Arc::new(Self {
+            instance,
+            adapter,
+            device,
+            queue,
+            transition_pipelines: parking_lot::Mutex::new(PipelineLRU::new(
+                MAX_PIPELINE_CACHE_SIZE,
+            )),
+            blit_pipelines: parking_lot::Mutex::new(HashMap::new()),
+            mipmap_pipelines: parking_lot::Mutex::new(HashMap::new()),
+            overlay_pipelines: parking_lot::Mutex::new(HashMap::new()),
+            blit_bind_group_layout,
+            transition_bind_group_layout,
+            mipmap_bind_group_layout,
+            texture_pool: parking_lot::Mutex::new(HashMap::new()),
+            blur_pass: std::sync::OnceLock::new(),
+            yuv_pass: std::sync::OnceLock::new(),
+        }))
+    }
+
+    /// Whether this context runs on the GPU `info` describes (through the same backend).
+    /// Two identical cards can't be told apart this way and end up sharing a device.
+    pub fn runs_on(&self, info: &wgpu::AdapterInfo) -> bool {
+        let own = self.adapter.get_info();
+        (own.vendor, own.device, own.backend, &own.name)
+            == (info.vendor, info.device, info.backend, &info.name)
+    }
+
+    /// Finds the adapter for a `gpu` selector among those able to present to `surface`:
+    /// a number is an index into that list, anything else a case-insensitive name match.
+    fn select_adapter(instance: &Instance, surface: &Surface, selector: &str) -> Option<Adapter> {
+        let adapters: Vec<Adapter> = instance
+            .enumerate_adapters(wgpu::Backends::all())
+            .into_iter()
+            .filter(|a| a.is_surface_supported(surface))
+            .collect();
+        for (i, a) in adapters.iter().enumerate() {
+            let info = a.get_info();
+            info!(
+                "[GPU] Adapter {}: {} ({:?}, {:?})",
+                i, info.name, info.backend, info.device_type
+            );
+        }
+
+        let selector = selector.trim();
+        if let Ok(index) = selector.parse::<usize>() {
+            return adapters.into_iter().nth(index);
+        }
+        let needle = selector.to_lowercase();
+        adapters
+            .into_iter()
+            .find(|a| a.get_info().name.to_lowercase().contains(&needle))
     }
 
     pub fn get_blit_pipeline(&self, format: wgpu::TextureFormat) -> Arc<wgpu::RenderPipeline> {
@@ -390,13 +511,81 @@ impl WgpuContext {
         pipeline_arc
     }
 
+    /// Alpha-blended quad pipeline for text widgets, shares the blit bind group layout
+    pub fn get_overlay_pipeline(&self, format: wgpu::TextureFormat) -> Arc<wgpu::RenderPipeline> {
+        if let Some(pipe) = self.overlay_pipelines.lock().get(&format) {
+            return pipe.clone();
+        }
+
+        debug!("[RENDER] Compiling overlay pipeline for format: {:?}", format);
+
+        let shader = self
+            .device
+            .create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("Overlay Shader"),
+                source: wgpu::ShaderSource::Wgsl(include_str!("shaders/overlay.wgsl").into()),
+            });
+
+        let layout = self
+            .device
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Overlay Pipeline Layout"),
+                bind_group_layouts: &[&self.blit_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
+        let pipeline = self
+            .device
+            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Overlay Pipeline"),
+                layout: Some(&layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: Some("vs_main"),
+                    compilation_options: Default::default(),
+                    buffers: &[],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: Some("fs_main"),
+                    compilation_options: Default::default(),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format,
+                        blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+                cache: None,
+            });
+
+        let pipeline_arc = Arc::new(pipeline);
+        self.overlay_pipelines
+            .lock()
+            .insert(format, pipeline_arc.clone());
+        pipeline_arc
+    }
+
+    pub fn blur_pass(&self) -> &crate::blur::BlurPass {
+        self.blur_pass
+            .get_or_init(|| crate::blur::BlurPass::new(&self.device))
+    }
+
+    pub fn yuv_pass(&self) -> &crate::yuv::YuvPass {
+        self.yuv_pass
+            .get_or_init(|| crate::yuv::YuvPass::new(&self.device))
+    }
+
     /// Get a texture from the pool or create a new one
     pub fn get_texture_from_pool(
         &self,
         width: u32,
         height: u32,
         usage: wgpu::TextureUsages,
-        metrics: Option<&crate::metrics::PerformanceMetrics>,
+        metrics: Option<(&crate::metrics::PerformanceMetrics, &str)>,
     ) -> wgpu::Texture {
         let mut pool = self.texture_pool.lock();
         let key = (width, height);
@@ -408,16 +597,16 @@ impl WgpuContext {
             entries.retain(|e| now.duration_since(e.last_used).as_secs() < 5);
 
             if let Some(entry) = entries.pop() {
-                if let Some(m) = metrics {
-                    m.record_texture_pool_hit();
+                if let Some((m, output)) = metrics {
+                    m.record_texture_pool_hit(output);
                 }
                 return entry.texture;
             }
         }
 
         // No texture in pool, create new one
-        if let Some(m) = metrics {
-            m.record_texture_pool_miss();
+        if let Some((m, output)) = metrics {
+            m.record_texture_pool_miss(output);
         }
         self.device.create_texture(&wgpu::TextureDescriptor {
             label: Some("Pooled Texture"),
@@ -460,6 +649,27 @@ impl WgpuContext {
         // If pool is full for this resolution, texture is dropped (freed by WGPU)
     }
 
+    /// Drops every pooled texture (used when all outputs go into standby)
+    pub fn drain_texture_pool(&self) {
+        self.texture_pool.lock().clear();
+        self.device.poll(wgpu::Maintain::Poll);
+    }
+
+    /// Idle trim: empties the texture pool and keeps only the transition pipelines in
+    /// `keep` (the ones outputs on this device are set to)
+    pub fn trim(&self, keep: &[String]) {
+        let pipelines = {
+            let mut lru = self.transition_pipelines.lock();
+            lru.retain(keep);
+            lru.len()
+        };
+        self.drain_texture_pool();
+        debug!(
+            "[TRIM] Texture pool emptied, {} transition pipelines kept",
+            pipelines
+        );
+    }
+
     /// Clean up old textures from pool
     pub fn cleanup_texture_pool(&self, metrics: Option<&crate::metrics::PerformanceMetrics>) {
         let mut pool = self.texture_pool.lock();
@@ -522,11 +732,56 @@ pub struct Renderer {
     pub transition_start_time: Option<std::time::Instant>,
     pub transition_active: bool, // Explicit flag tracking if transition is active (following wpaperd pattern)
     pub transition_just_completed: bool, // Flag set when transition completes, cleared by main loop
+    /// Content was switched but its first image/frame hasn't been uploaded yet
+    pub content_pending: bool,
+    /// RGBA color drawn when there is no content (set by `kldctl clear --color`)
+    pub solid_color: Option<[u8; 4]>,
+    /// Time/date/load/battery block passed to shaders, refreshed by the main loop
+    system_uniforms: [[f32; 4]; 2],
+    system_sampled_at: std::time::Instant,
+    /// Crop window for the content being switched to (`random-crop`), taken over on upload
+    pub next_crop: [f32; 4],
+    /// Focal point of the content being switched to (content UV), from its sidecar
+    pub next_focal: Option<[f32; 2]>,
+    current_crop: [f32; 4],
+    prev_crop: [f32; 4],
+    /// `safe-area` insets in pixels: [top, right, bottom, left]
+    safe_area: [u32; 4],
+    fit_mode: crate::orchestration::FitMode,
+    /// `fit-color`, linear
+    fit_color: [f32; 4],
 
     // Transition Settings
     pub active_transition: Transition,
     pub transition_duration: f32,
     pub transition_stats: Option<TransitionStats>,
+    /// transition-time = 0: swap content without running a transition shader
+    pub instant_transitions: bool,
+    /// Minimum time between transition frames (`transition-fps-cap`), None = every frame
+    transition_frame_interval: Option<std::time::Duration>,
+    pub grayscale: bool,
+    /// `max-luminance`: highest mean brightness content is shown at (fraction of white)
+    max_luminance: Option<f32>,
+    /// Mean linear luminance of the current content, as last measured
+    content_luminance: f32,
+    /// Exposure the outgoing content had, faded into the new one during a transition
+    prev_exposure: f32,
+    luminance_sampled_at: Option<std::time::Instant>,
+    /// Surface uses a non-opaque alpha mode so transparent wallpapers show what's below
+    transparent: bool,
+    /// `palette` / `palette-command`
+    palette: bool,
+    palette_command: Option<String>,
+    /// File switched to, whose colors are extracted once its transition completes
+    palette_path: Option<std::path::PathBuf>,
+    /// Pixels of its first uploaded frame (see `palette::sample`)
+    palette_samples: Option<Vec<[u8; 3]>>,
+    /// Shader wallpaper drawn into `current_texture` (`ContentType::Shader`)
+    shader: Option<crate::shader_wallpaper::ShaderWallpaper>,
+    /// Time between its frames (`shader-fps`)
+    shader_frame_interval: std::time::Duration,
+    /// Last pointer position over the output in pixels, the shader's `iMouse`
+    pointer: Option<[f32; 2]>,
 
     // Texture Reuse
     current_texture_size: Option<(u32, u32)>,
@@ -549,6 +804,7 @@ pub struct Renderer {
 
     // Metrics tracking
     metrics: Option<Arc<crate::metrics::PerformanceMetrics>>,
+    gpu_timer: Option<crate::gpu_timer::GpuTimer>, // None without metrics or timestamp queries
     video_first_frame_time: Option<std::time::Instant>, // Track when video session starts
 
     // Background task handle for shader precompilation (aborted on drop)
@@ -557,8 +813,30 @@ pub struct Renderer {
     // Reusable buffer for stride conversion to avoid per-frame allocations
     stride_temp_buffer: Vec<u8>,
 
+    // Plane textures NV12 video frames are uploaded into before conversion
+    nv12_planes: Option<crate::yuv::Nv12Planes>,
+
     // Track prev_texture size for returning to pool
     prev_texture_size: Option<(u32, u32)>,
+
+    // Text widgets composited on top of the wallpaper in the blit pass
+    overlay_layers: Vec<OverlayLayer>,
+    /// (overlay generation, surface size) the current layers were built for
+    pub overlay_state: Option<(u64, u32, u32)>,
+
+    /// Low-resource standby (`kldctl stop`): textures released, nothing rendered
+    pub standby: bool,
+    /// Whether the backend surface is currently unmapped for standby (synced by the main loop)
+    pub surface_hidden: bool,
+    /// Wayland surface was remapped; presenting before its configure event is a protocol error
+    pub awaiting_configure: bool,
+}
+
+/// GPU resources for one placed text widget
+struct OverlayLayer {
+    _texture: wgpu::Texture,
+    _uniform_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
 }
 
 impl Renderer {
@@ -585,11 +863,7 @@ impl Renderer {
             .first()
             .cloned()
             .unwrap_or(wgpu::TextureFormat::Rgba8UnormSrgb);
-        let alpha_mode = caps
-            .alpha_modes
-            .first()
-            .cloned()
-            .unwrap_or(wgpu::CompositeAlphaMode::Auto);
+        let alpha_mode = Self::pick_alpha_mode(&caps, false);
         // Prefer Mailbox for lower latency, fallback to Immediate, then Fifo
         let present_mode = caps
             .present_modes
@@ -658,9 +932,18 @@ impl Renderer {
             transition_start_time: None,
             transition_active: false,
             transition_just_completed: false,
+            content_pending: false,
             active_transition: Transition::Fade,
             transition_duration: 1.0,
             transition_stats: None,
+            instant_transitions: false,
+            transition_frame_interval: None,
+            grayscale: false,
+            max_luminance: None,
+            content_luminance: 0.0,
+            prev_exposure: 1.0,
+            luminance_sampled_at: None,
+            transparent: false,
             current_texture_size: None,
             current_texture_view: None,
             prev_texture_view: None,
@@ -674,11 +957,37 @@ impl Renderer {
             active_video_session_id: 0,
             active_batch_id: None,
             batch_start_time: None,
+            gpu_timer: metrics
+                .as_ref()
+                .and_then(|_| crate::gpu_timer::GpuTimer::new(&ctx.device, &ctx.queue)),
             metrics,
             video_first_frame_time: None,
             shader_precompile_handle: None,
             stride_temp_buffer: Vec::new(),
+            nv12_planes: None,
             prev_texture_size: None,
+            overlay_layers: Vec::new(),
+            overlay_state: None,
+            standby: false,
+            solid_color: None,
+            system_uniforms: [[0.0; 4]; 2],
+            system_sampled_at: std::time::Instant::now(),
+            next_crop: NO_CROP,
+            next_focal: None,
+            current_crop: NO_CROP,
+            prev_crop: NO_CROP,
+            safe_area: [0; 4],
+            fit_mode: crate::orchestration::FitMode::Fill,
+            fit_color: [0.0, 0.0, 0.0, 1.0],
+            palette: false,
+            palette_command: None,
+            palette_path: None,
+            palette_samples: None,
+            shader: None,
+            shader_frame_interval: std::time::Duration::from_secs_f64(1.0 / 30.0),
+            pointer: None,
+            surface_hidden: false,
+            awaiting_configure: false,
         };
         // Shader precompilation is deferred to apply_config() which knows
         // the actual configured transition. No need to precompile 10 hardcoded
@@ -704,6 +1013,10 @@ impl Renderer {
                 );
                 self.config.format = caps.formats[0];
             }
+            // Capabilities may not have been known when transparency was configured
+            if self.transparent && !caps.alpha_modes.contains(&self.config.alpha_mode) {
+                self.config.alpha_mode = Self::pick_alpha_mode(&caps, true);
+            }
 
             self.config.width = width;
             self.config.height = height;
@@ -747,6 +1060,54 @@ impl Renderer {
         Ok(())
     }
 
+    /// Opaque outputs keep the surface's preferred mode. Transparent ones need the
+    /// compositor to blend with the alpha channel, preferably premultiplied.
+    fn pick_alpha_mode(
+        caps: &wgpu::SurfaceCapabilities,
+        transparent: bool,
+    ) -> wgpu::CompositeAlphaMode {
+        let preferred: &[wgpu::CompositeAlphaMode] = if transparent {
+            &[
+                wgpu::CompositeAlphaMode::PreMultiplied,
+                wgpu::CompositeAlphaMode::PostMultiplied,
+                wgpu::CompositeAlphaMode::Inherit,
+            ]
+        } else {
+            &[]
+        };
+        preferred
+            .iter()
+            .find(|mode| caps.alpha_modes.contains(mode))
+            .or(caps.alpha_modes.first())
+            .copied()
+            .unwrap_or(wgpu::CompositeAlphaMode::Auto)
+    }
+
+    fn set_transparent(&mut self, transparent: bool) {
+        self.transparent = transparent;
+        let caps = self.target_caps();
+        self.config.alpha_mode = Self::pick_alpha_mode(&caps, transparent);
+        if transparent && self.config.alpha_mode == wgpu::CompositeAlphaMode::Opaque {
+            warn!(
+                "[RENDER] {}: Surface supports no transparent alpha mode ({:?}), staying opaque",
+                self.name, caps.alpha_modes
+            );
+        } else {
+            info!(
+                "[RENDER] {}: Surface alpha mode {:?}",
+                self.name, self.config.alpha_mode
+            );
+        }
+        if self.configured {
+            self.surface.configure(&self.ctx.device, &self.config);
+        }
+    }
+
+    /// Premultiplied surfaces expect color already scaled by alpha
+    fn premultiplied(&self) -> bool {
+        self.config.alpha_mode == wgpu::CompositeAlphaMode::PreMultiplied
+    }
+
     fn target_caps(&self) -> wgpu::SurfaceCapabilities {
         self.surface.get_capabilities(&self.ctx.adapter)
     }
@@ -822,6 +1183,28 @@ impl Renderer {
     pub fn apply_config(&mut self, config: &crate::orchestration::OutputConfig) {
         self.active_transition = config.transition.clone();
         self.transition_duration = (config.transition_time as f32 / 1000.0).max(0.001);
+        self.instant_transitions = config.transition_time == 0;
+        self.transition_frame_interval = config
+            .transition_fps_cap
+            .map(|fps| std::time::Duration::from_secs_f64(1.0 / fps as f64));
+        self.grayscale = config.grayscale;
+        self.max_luminance = config.max_luminance;
+        self.safe_area = config.safe_area;
+        self.fit_mode = config.fit_mode;
+        let [r, g, b, a] = config.fit_color.unwrap_or([0, 0, 0, 255]);
+        self.fit_color = [
+            srgb_to_linear(r),
+            srgb_to_linear(g),
+            srgb_to_linear(b),
+            a as f32 / 255.0,
+        ];
+        self.palette = config.palette;
+        self.palette_command = config.palette_command.clone();
+        self.shader_frame_interval =
+            std::time::Duration::from_secs_f64(1.0 / config.shader_fps as f64);
+        if config.transparent != self.transparent {
+            self.set_transparent(config.transparent);
+        }
         self.needs_redraw = true;
 
         // Pre-compile only the configured transition in background (+ Fade as fallback).
@@ -835,8 +1218,11 @@ impl Renderer {
             let start = std::time::Instant::now();
             // Always precompile Fade (used as fallback on errors)
             let _ = crate::shaders::ShaderManager::get_builtin_shader(&Transition::Fade);
-            // Precompile the user's configured transition (skip if it IS Fade or Random)
-            if !matches!(transition, Transition::Fade | Transition::Random) {
+            // Precompile the user's configured transition (skip if it IS Fade, Random or Auto)
+            if !matches!(
+                transition,
+                Transition::Fade | Transition::Random | Transition::Auto
+            ) {
                 let _ = crate::shaders::ShaderManager::get_builtin_shader(&transition);
             }
             let duration = start.elapsed();
@@ -925,7 +1311,7 @@ impl Renderer {
                         name, e
                     );
                     if let Some(m) = &self.metrics {
-                        m.record_error("shader_compile");
+                        m.record_output_error(&self.name, "shader_compile");
                     }
                     // Fallback to fade
                     match crate::shaders::ShaderManager::get_builtin_shader(&Transition::Fade) {
@@ -933,7 +1319,7 @@ impl Renderer {
                         Err(fe) => {
                             error!("FATAL: Failed to compile fallback fade shader: {}", fe);
                             if let Some(m) = &self.metrics {
-                                m.record_error("shader_compile_fatal");
+                                m.record_output_error(&self.name, "shader_compile_fatal");
                             }
                             return None;
                         }
@@ -1022,6 +1408,9 @@ impl Renderer {
         context: BackendContext,
         frame_time: std::time::Instant,
     ) -> anyhow::Result<()> {
+        if self.standby || self.awaiting_configure {
+            return Ok(());
+        }
         let render_start = std::time::Instant::now();
 
         // CRITICAL: Reset per-frame state at the start of each render cycle
@@ -1090,6 +1479,13 @@ impl Renderer {
             .texture
             .create_view(&wgpu::TextureViewDescriptor::default());
 
+        if let Some(time) = self.gpu_timer.as_mut().and_then(|t| t.begin_frame()) {
+            if let Some(m) = &self.metrics {
+                let transition = time.transition.as_ref().map(|(n, ms)| (n.as_str(), *ms));
+                m.record_gpu_frame_time(&self.name, time.total_ms, transition);
+            }
+        }
+
         let mut encoder = self
             .ctx
             .device
@@ -1123,6 +1519,7 @@ impl Renderer {
                     if self.transition_active {
                         self.transition_active = false;
                         self.transition_just_completed = true;
+                        self.emit_transition_completed();
 
                         // Log Audit Report and record metrics
                         if let Some(stats) = self.transition_stats.take() {
@@ -1131,7 +1528,7 @@ impl Renderer {
 
                             // Record transition duration in metrics
                             if let Some(m) = &self.metrics {
-                                m.record_transition(duration);
+                                m.record_transition(&self.name, duration);
                             }
                             let fps = if duration_secs > 0.001 {
                                 stats.frame_count as f64 / duration_secs
@@ -1189,6 +1586,11 @@ impl Renderer {
 
                 info!("[TRANSITION] {}: Starting transition (duration={:.3}s, initial_progress={:.3})", 
                     self.name, self.transition_duration, self.transition_progress);
+                crate::events::emit(kaleidux_common::Event::TransitionStarted {
+                    output: self.name.clone(),
+                    transition: self.active_transition.name(),
+                    duration_ms: (self.transition_duration * 1000.0).round() as u64,
+                });
             }
         }
 
@@ -1237,6 +1639,12 @@ impl Renderer {
                 prev_aspect: self.prev_aspect,
                 next_aspect: self.current_aspect,
                 params: bytemuck::cast(raw_params),
+                effects: self.effects(),
+                system: self.system_block(),
+                crop: [self.prev_crop, self.current_crop],
+                fit: [self.fit_mode.code(), 0.0, 0.0, 0.0],
+                fit_size: self.fit_sizes(),
+                fit_color: self.fit_color,
             };
             self.ctx
                 .queue
@@ -1254,6 +1662,7 @@ impl Renderer {
                         return Ok(());
                     }
                 };
+                let transition_name = self.active_transition.name();
                 let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                     label: Some("Transition Render Pass"),
                     color_attachments: &[Some(wgpu::RenderPassColorAttachment {
@@ -1265,7 +1674,10 @@ impl Renderer {
                         },
                     })],
                     depth_stencil_attachment: None,
-                    timestamp_writes: None,
+                    timestamp_writes: self
+                        .gpu_timer
+                        .as_mut()
+                        .and_then(|t| t.transition_pass(&transition_name)),
                     occlusion_query_set: None,
                 });
                 render_pass.set_pipeline(&pipeline);
@@ -1321,6 +1733,12 @@ impl Renderer {
                 prev_aspect: 1.0,
                 next_aspect: self.current_aspect,
                 params: [[0.0; 4]; 7],
+                effects: self.effects(),
+                system: self.system_block(),
+                crop: [NO_CROP, self.current_crop],
+                fit: [self.fit_mode.code(), 0.0, 0.0, 0.0],
+                fit_size: self.fit_sizes(),
+                fit_color: self.fit_color,
             };
             self.ctx
                 .queue
@@ -1388,6 +1806,11 @@ impl Renderer {
         // But keep needs_redraw=true so we try again next frame
         let blit_source = match blit_source {
             Some(s) => s,
+            None if self.solid_color.is_some() => {
+                // Cleared to a color: fill the surface procedurally, no texture needed
+                self.present_solid_color(output, &view, encoder, context);
+                return Ok(());
+            }
             None => {
                 // No textures available - can't render, but keep needs_redraw for next attempt
                 debug!(
@@ -1514,6 +1937,11 @@ impl Renderer {
 
         // Get format-specific blit pipeline from shared context
         let blit_pipeline = self.ctx.get_blit_pipeline(self.config.format);
+        let overlay_pipeline = if self.overlay_layers.is_empty() {
+            None
+        } else {
+            Some(self.ctx.get_overlay_pipeline(self.config.format))
+        };
 
         {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
@@ -1527,7 +1955,7 @@ impl Renderer {
                     },
                 })],
                 depth_stencil_attachment: None,
-                timestamp_writes: None,
+                timestamp_writes: self.gpu_timer.as_mut().and_then(|t| t.blit_pass()),
                 occlusion_query_set: None,
             });
 
@@ -1542,6 +1970,15 @@ impl Renderer {
                 );
                 return Ok(()); // Can't render without bind group
             }
+
+            // Text widgets go on top of the final wallpaper image
+            if let Some(pipeline) = &overlay_pipeline {
+                render_pass.set_pipeline(pipeline);
+                for layer in &self.overlay_layers {
+                    render_pass.set_bind_group(0, &layer.bind_group, &[]);
+                    render_pass.draw(0..6, 0..1);
+                }
+            }
         } // render_pass dropped here
 
         // Request frame callback BEFORE presenting/committing to ensure correct ordering
@@ -1559,7 +1996,13 @@ impl Renderer {
             }
         }
 
+        if let Some(timer) = &mut self.gpu_timer {
+            timer.resolve(&mut encoder);
+        }
         self.ctx.queue.submit(std::iter::once(encoder.finish()));
+        if let Some(timer) = &mut self.gpu_timer {
+            timer.submitted();
+        }
         output.present();
 
         // Note: frame_callback_pending is reset by the main loop when callback is received
@@ -1576,9 +2019,7 @@ impl Renderer {
         if self.transition_active {
             // Transition in progress - MUST keep rendering until complete
             self.needs_redraw = true;
-        } else if !self.transition_active
-            && self.valid_content_type != crate::queue::ContentType::Video
-        {
+        } else if !self.transition_active && !self.valid_content_type.is_played() {
             // Transition complete and not video - can reset needs_redraw now that we've presented
             self.needs_redraw = false;
         }
@@ -1588,11 +2029,78 @@ impl Renderer {
         if let Some(m) = &self.metrics {
             let render_duration = render_start.elapsed();
             m.record_renderer_cpu_time(render_duration);
+            m.record_output_frame_time(&self.name, render_duration);
         }
 
         Ok(())
     }
 
+    /// Fills the surface with `solid_color` (plus text widgets) and presents it
+    fn present_solid_color(
+        &mut self,
+        output: wgpu::SurfaceTexture,
+        view: &wgpu::TextureView,
+        mut encoder: wgpu::CommandEncoder,
+        context: BackendContext,
+    ) {
+        let [r, g, b, a] = self.solid_color.unwrap_or([0, 0, 0, 255]);
+        // Clear values are linear; sRGB surfaces encode them on store
+        let srgb = self.config.format.is_srgb();
+        let channel = |c: u8| {
+            if srgb {
+                srgb_to_linear(c) as f64
+            } else {
+                c as f64 / 255.0
+            }
+        };
+        let alpha = a as f64 / 255.0;
+        let scale = if self.premultiplied() { alpha } else { 1.0 };
+        let color = wgpu::Color {
+            r: channel(r) * scale,
+            g: channel(g) * scale,
+            b: channel(b) * scale,
+            a: alpha,
+        };
+
+        let overlay_pipeline = if self.overlay_layers.is_empty() {
+            None
+        } else {
+            Some(self.ctx.get_overlay_pipeline(self.config.format))
+        };
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Solid Color Render Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(color),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            if let Some(pipeline) = &overlay_pipeline {
+                render_pass.set_pipeline(pipeline);
+                for layer in &self.overlay_layers {
+                    render_pass.set_bind_group(0, &layer.bind_group, &[]);
+                    render_pass.draw(0..6, 0..1);
+                }
+            }
+        }
+
+        self.frame_callback_pending = false;
+        if let BackendContext::Wayland { surface, qh } = context {
+            self.request_frame_callback(surface, qh);
+        }
+        self.ctx.queue.submit(std::iter::once(encoder.finish()));
+        output.present();
+        self.last_present_time = std::time::Instant::now();
+        self.needs_redraw = false;
+    }
+
     /// Request a frame callback from Wayland compositor
     /// This should be called when we need to render, and we'll wait for the callback
     pub fn request_frame_callback(
@@ -1623,10 +2131,165 @@ impl Renderer {
             self.name, self.configured, self.needs_redraw, self.transition_progress);
     }
 
+    /// How long a transition frame has to wait to respect `transition-fps-cap`, or None
+    /// if it can be rendered now. Frames up to an eighth of the interval early still go
+    /// out, so vsync jitter doesn't push every other frame to the next refresh.
+    pub fn transition_frame_wait(&self, now: std::time::Instant) -> Option<std::time::Duration> {
+        let interval = self.transition_frame_interval?;
+        if !self.transition_active {
+            return None;
+        }
+        let elapsed = now.saturating_duration_since(self.last_present_time);
+        let due = interval - interval / 8;
+        (elapsed < due).then(|| due - elapsed)
+    }
+
     pub fn set_content_type(&mut self, content_type: crate::queue::ContentType) {
         self.valid_content_type = content_type;
     }
 
+    /// Whether a content switch is still loading or transitioning (used by `kldctl wait`)
+    pub fn is_busy(&self) -> bool {
+        self.content_pending || self.transition_active
+    }
+
+    /// Transition state for `query` (see `kaleidux_common::TransitionStatus`)
+    pub fn transition_status(&self) -> kaleidux_common::TransitionStatus {
+        use kaleidux_common::TransitionState;
+        let running = self.transition_active && self.transition_start_time.is_some();
+        let state = if running {
+            TransitionState::Running
+        } else if self.is_busy() {
+            TransitionState::Loading
+        } else {
+            TransitionState::Idle
+        };
+        let started_at_ms = self
+            .transition_start_time
+            .filter(|_| running)
+            .and_then(|start| std::time::SystemTime::now().checked_sub(start.elapsed()))
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_millis() as u64);
+        kaleidux_common::TransitionStatus {
+            state,
+            name: self.active_transition.name(),
+            // Computed from the clock rather than the last rendered frame, which may lag
+            progress: match (state, self.transition_start_time) {
+                (TransitionState::Running, Some(start)) => {
+                    (start.elapsed().as_secs_f32() / self.transition_duration).clamp(0.0, 1.0)
+                }
+                (TransitionState::Running, None) => self.transition_progress,
+                (TransitionState::Loading, _) => 0.0,
+                (TransitionState::Idle, _) => 1.0,
+            },
+            duration_ms: (self.transition_duration * 1000.0).round() as u64,
+            started_at_ms,
+        }
+    }
+
+    /// The current wallpaper (as uploaded, before cover-cropping) blurred by `iterations`
+    /// dual Kawase passes, or an unblurred copy for 0. None while nothing is displayed.
+    pub fn blurred_current(&self, iterations: u32) -> Option<wgpu::Texture> {
+        let source = self.current_texture.as_ref()?;
+        Some(self.ctx.blur_pass().run(&self.ctx, source, iterations))
+    }
+
+    /// `blurred_current`, read back to the CPU for `kldctl snapshot`
+    pub fn snapshot(&self, blur: u32) -> anyhow::Result<image::RgbaImage> {
+        let texture = self
+            .blurred_current(blur)
+            .ok_or_else(|| anyhow::anyhow!("Nothing is displayed on {}", self.name))?;
+        crate::blur::read_texture(&self.ctx, &texture)
+    }
+
+    /// The last presented frame at output resolution (fit, crop, effects, widgets and a
+    /// running transition included), re-drawn offscreen and read back to the CPU
+    pub fn read_frame(&self) -> anyhow::Result<image::RgbaImage> {
+        self.start_frame_readback()?.wait()
+    }
+
+    /// `read_frame` without waiting for the GPU; the frame is collected later with
+    /// `Readback::try_finish`
+    pub fn start_frame_readback(&self) -> anyhow::Result<crate::blur::Readback> {
+        let (width, height) = (self.config.width, self.config.height);
+        if !self.has_any_content() {
+            if let Some(color) = self.solid_color {
+                return Ok(crate::blur::Readback::done(image::RgbaImage::from_pixel(
+                    width,
+                    height,
+                    image::Rgba(color),
+                )));
+            }
+        }
+        let bind_group = self
+            .blit_bind_group
+            .as_ref()
+            .filter(|_| self.configured && width > 0 && height > 0)
+            .ok_or_else(|| anyhow::anyhow!("Nothing is displayed on {}", self.name))?;
+        // Same encoding as the surface, in a layout read_texture understands
+        let format = if self.config.format.is_srgb() {
+            wgpu::TextureFormat::Rgba8UnormSrgb
+        } else {
+            wgpu::TextureFormat::Rgba8Unorm
+        };
+        let texture = self.ctx.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Frame Readback Texture"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let blit_pipeline = self.ctx.get_blit_pipeline(format);
+        let overlay_pipeline = if self.overlay_layers.is_empty() {
+            None
+        } else {
+            Some(self.ctx.get_overlay_pipeline(format))
+        };
+
+        let mut encoder = self
+            .ctx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Frame Readback Encoder"),
+            });
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Frame Readback Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            render_pass.set_pipeline(&blit_pipeline);
+            render_pass.set_bind_group(0, bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+            if let Some(pipeline) = &overlay_pipeline {
+                render_pass.set_pipeline(pipeline);
+                for layer in &self.overlay_layers {
+                    render_pass.set_bind_group(0, &layer.bind_group, &[]);
+                    render_pass.draw(0..6, 0..1);
+                }
+            }
+        }
+        self.ctx.queue.submit(std::iter::once(encoder.finish()));
+        Ok(crate::blur::Readback::start(&self.ctx, &texture))
+    }
+
     /// Check if current_texture exists (used for throttling logic)
     pub fn has_current_texture(&self) -> bool {
         self.current_texture.is_some()
@@ -1654,6 +2317,135 @@ impl Renderer {
             .map_or(false, |d| d.as_millis() > threshold_ms as u128)
     }
 
+    fn emit_transition_completed(&mut self) {
+        crate::events::emit(kaleidux_common::Event::TransitionCompleted {
+            output: self.name.clone(),
+        });
+        if let (Some(path), Some(samples)) = (self.palette_path.take(), self.palette_samples.take())
+        {
+            crate::palette::on_change(&self.name, &path, samples, self.palette_command.clone());
+        }
+    }
+
+    /// Extracts the colors of `path` (the file just switched to) once its transition
+    /// completes, if `palette` is on. Call after `switch_content`.
+    pub fn expect_palette(&mut self, path: &std::path::Path) {
+        self.palette_path = self.palette.then(|| path.to_path_buf());
+    }
+
+    /// Keeps the first frame of content `expect_palette` waits for
+    fn sample_palette(&mut self, data: &[u8], width: u32, height: u32, stride: u32) {
+        if self.palette_path.is_some() && self.palette_samples.is_none() {
+            self.palette_samples = Some(crate::palette::sample(data, width, height, stride));
+        }
+    }
+
+    /// Switches to the shader wallpaper at `path`; its first frame is drawn by the next
+    /// `advance_shader`. Call after `switch_content`.
+    pub fn load_shader(&mut self, path: &std::path::Path) -> anyhow::Result<()> {
+        self.shader = Some(crate::shader_wallpaper::ShaderWallpaper::load(
+            &self.ctx, path,
+        )?);
+        self.valid_content_type = crate::queue::ContentType::Shader;
+        Ok(())
+    }
+
+    /// File of the shader wallpaper on screen, for mirrors to load it as well
+    pub fn shader_path(&self) -> Option<&std::path::Path> {
+        self.shader.as_ref().map(|s| s.path())
+    }
+
+    /// Pointer position over the output in surface pixels, from its top left corner
+    pub fn set_pointer(&mut self, position: [f32; 2]) {
+        self.pointer = Some(position);
+    }
+
+    /// Draws the next frame of a shader wallpaper if `shader-fps` allows one at `now`.
+    /// Called every tick of the main loop; the frame goes out with the next render.
+    pub fn advance_shader(&mut self, now: std::time::Instant) {
+        let Some(shader) = self.shader.as_mut() else {
+            return;
+        };
+        let (width, height) = (self.config.width, self.config.height);
+        if self.standby
+            || !self.configured
+            || width == 0
+            || height == 0
+            || !shader.due(now, self.shader_frame_interval)
+        {
+            return;
+        }
+
+        // Screen-sized, so it is made again when the output is resized. Like the
+        // composition texture it is not pooled: current_texture_size stays None.
+        let resized = self
+            .current_texture
+            .as_ref()
+            .is_none_or(|t| t.width() != width || t.height() != height);
+        if resized {
+            let texture = self.ctx.device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("Shader Wallpaper Texture"),
+                size: wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: crate::shader_wallpaper::FORMAT,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING
+                    | wgpu::TextureUsages::RENDER_ATTACHMENT
+                    | wgpu::TextureUsages::COPY_SRC,
+                view_formats: &[],
+            });
+            self.current_texture_view =
+                Some(texture.create_view(&wgpu::TextureViewDescriptor::default()));
+            self.current_texture = Some(texture);
+            self.current_texture_size = None;
+            self.current_aspect = width as f32 / height as f32;
+            self.current_crop = NO_CROP;
+            self.transition_bind_group = None;
+            self.blit_bind_group = None;
+        }
+        let Some(view) = self.current_texture_view.as_ref() else {
+            return;
+        };
+        let frame_rate = 1.0 / self.shader_frame_interval.as_secs_f32();
+        shader.render(
+            &self.ctx,
+            view,
+            (width, height),
+            self.pointer,
+            frame_rate,
+            now,
+        );
+        self.needs_redraw = true;
+
+        if self.content_pending {
+            self.content_pending = false;
+            if self.prev_texture.is_some() && !self.instant_transitions {
+                self.transition_start_time = None;
+                self.transition_progress = 0.0;
+                self.transition_active = true;
+                info!(
+                    "[TRANSITION] {}: First shader frame - transition starts on next render frame",
+                    self.name
+                );
+            } else {
+                self.release_prev_texture();
+                self.transition_active = false;
+                self.transition_progress = 1.0;
+                self.transition_just_completed = true;
+                self.emit_transition_completed();
+                info!(
+                    "[TRANSITION] {}: First shader frame (Instant) - transition complete",
+                    self.name
+                );
+            }
+        }
+    }
+
     #[allow(dead_code)]
     pub fn upload_image_file(&mut self, path: &std::path::Path) -> anyhow::Result<()> {
         let _load_start = std::time::Instant::now();
@@ -1662,21 +2454,28 @@ impl Renderer {
         let (width, height) = rgba.dimensions();
         let data = rgba.into_raw();
 
-        self.upload_image_data(data, width, height)
+        self.upload_image_data(&data, width, height)
     }
 
     pub fn upload_image_data(
         &mut self,
-        data: Vec<u8>,
+        data: &[u8],
         width: u32,
         height: u32,
     ) -> anyhow::Result<()> {
         let upload_start = std::time::Instant::now();
+        // Measured even without `max-luminance`, so enabling it on a reload applies
+        // to the wallpaper already on screen
+        self.content_luminance = mean_luminance(data, width, height, width * 4);
+        self.luminance_sampled_at = Some(std::time::Instant::now());
+        self.sample_palette(data, width, height, width * 4);
 
         // CRITICAL: Explicitly drop old image texture before creating new one
         // This prevents memory leaks when switching images rapidly
         // Image textures can't be pooled (they need mipmaps), so we must drop them
         drop(self.current_texture.take());
+        // A late image replaces a shader wallpaper, which would otherwise draw into it
+        self.shader = None;
         drop(self.current_texture_view.take());
 
         // Calculate mip levels
@@ -1711,7 +2510,7 @@ impl Renderer {
                 origin: wgpu::Origin3d::ZERO,
                 aspect: wgpu::TextureAspect::All,
             },
-            &data,
+            data,
             wgpu::ImageDataLayout {
                 offset: 0,
                 bytes_per_row: Some(4 * width),
@@ -1813,6 +2612,7 @@ impl Renderer {
 
         self.current_texture = Some(texture);
         self.current_aspect = width as f32 / height as f32;
+        self.current_crop = self.placed_crop();
         self.current_texture_size = Some((width, height));
         self.needs_redraw = true;
         self.valid_content_type = crate::queue::ContentType::Image;
@@ -1821,7 +2621,8 @@ impl Renderer {
         self.blit_source_is_composition = false;
         self.blit_source_is_prev = false;
 
-        if self.prev_texture.is_some() {
+        self.content_pending = false;
+        if self.prev_texture.is_some() && !self.instant_transitions {
             self.transition_start_time = None;
             self.transition_progress = 0.0;
             self.transition_active = true;
@@ -1830,9 +2631,11 @@ impl Renderer {
                 self.name
             );
         } else {
+            self.release_prev_texture();
             self.transition_active = false;
             self.transition_progress = 1.0;
             self.transition_just_completed = true; // Signal completion for instant switch
+            self.emit_transition_completed();
             info!(
                 "[TRANSITION] {}: Image data uploaded (Instant) - transition signaled as complete",
                 self.name
@@ -1846,8 +2649,7 @@ impl Renderer {
     }
 
     pub fn upload_frame(&mut self, frame: &crate::video::VideoFrame) {
-        if self.valid_content_type != crate::queue::ContentType::Video
-            || frame.session_id != self.active_video_session_id
+        if !self.valid_content_type.is_played() || frame.session_id != self.active_video_session_id
         {
             debug!("[VIDEO] {}: Discarding stale video frame - valid_type={:?}, frame_session={}, active_session={}", 
                 self.name, self.valid_content_type, frame.session_id, self.active_video_session_id);
@@ -1887,8 +2689,8 @@ impl Renderer {
                 self.ctx.get_texture_from_pool(
                     frame.width,
                     frame.height,
-                    wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
-                    self.metrics.as_deref(),
+                    VIDEO_TEXTURE_USAGE,
+                    self.metrics.as_deref().map(|m| (m, self.name.as_str())),
                 )
             }
         } else {
@@ -1896,8 +2698,8 @@ impl Renderer {
             self.ctx.get_texture_from_pool(
                 frame.width,
                 frame.height,
-                wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
-                self.metrics.as_deref(),
+                VIDEO_TEXTURE_USAGE,
+                self.metrics.as_deref().map(|m| (m, self.name.as_str())),
             )
         };
 
@@ -1909,8 +2711,50 @@ impl Renderer {
         let height = frame.height;
         let expected_stride = width * 4;
 
-        // Check if source stride is 256-byte aligned (required for bytes_per_row)
-        if src_stride.is_multiple_of(256) {
+        if let Some(layout) = &frame.nv12 {
+            // Converted on the GPU; the measurements get a small RGBA grid instead
+            let map = match frame.buffer.map_readable() {
+                Ok(m) => m,
+                Err(e) => {
+                    error!("Failed to map video buffer: {}", e);
+                    return;
+                }
+            };
+            let (grid, grid_w, grid_h) = layout.sample_grid(frame, map.as_slice());
+            self.measure_video_luminance(&grid, grid_w, grid_h, grid_w * 4);
+            self.sample_palette(&grid, grid_w, grid_h, grid_w * 4);
+            if self
+                .nv12_planes
+                .as_ref()
+                .is_none_or(|p| p.size() != (width, height))
+            {
+                self.nv12_planes = Some(crate::yuv::Nv12Planes::new(
+                    &self.ctx,
+                    self.ctx.yuv_pass(),
+                    width,
+                    height,
+                ));
+            }
+            let Some(planes) = &self.nv12_planes else {
+                return;
+            };
+            if !planes.upload(&self.ctx, frame, layout, map.as_slice()) {
+                error!(
+                    "[VIDEO] {}: NV12 frame buffer is smaller than its layout, skipping it",
+                    self.name
+                );
+                return;
+            }
+            self.ctx.yuv_pass().run(&self.ctx, planes, &texture);
+        } else if let Ok(map) = frame.buffer.map_readable() {
+            self.measure_video_luminance(map.as_slice(), width, height, src_stride);
+            self.sample_palette(map.as_slice(), width, height, src_stride);
+        }
+
+        if frame.nv12.is_some() {
+            // Already drawn into the texture above
+        } else if src_stride.is_multiple_of(256) {
+            // Check if source stride is 256-byte aligned (required for bytes_per_row)
             // Direct upload possible - map buffer in explicit scope
             {
                 let map = match frame.buffer.map_readable() {
@@ -2045,12 +2889,14 @@ impl Renderer {
         self.current_texture = Some(texture);
         self.current_texture_size = Some((frame.width, frame.height));
         self.current_aspect = frame.width as f32 / frame.height as f32;
+        self.current_crop = self.placed_crop();
         self.needs_redraw = true;
 
         // CRITICAL: If this is the first frame after a switch, mark transition as active
         // but DON'T set transition_start_time - let render() do that on first actual render frame
         // This ensures consistent timing behavior with image transitions
         if is_first_frame_after_switch {
+            self.content_pending = false;
             // Record video first frame timing
             if let Some(m) = &self.metrics {
                 if let Some(start_time) = self.video_first_frame_time {
@@ -2060,16 +2906,18 @@ impl Renderer {
                 }
             }
 
-            if self.prev_texture.is_some() {
+            if self.prev_texture.is_some() && !self.instant_transitions {
                 info!("[TRANSITION] {}: First video frame after switch - transition will start on first render frame", self.name);
                 self.transition_start_time = None; // Will be set on first render
                 self.transition_progress = 0.0;
                 self.transition_active = true;
             } else {
                 info!("[TRANSITION] {}: First video frame after switch (Instant) - transition signaled as complete", self.name);
+                self.release_prev_texture();
                 self.transition_active = false;
                 self.transition_progress = 1.0;
                 self.transition_just_completed = true;
+                self.emit_transition_completed();
             }
         }
 
@@ -2087,29 +2935,118 @@ impl Renderer {
         self.ctx.device.poll(wgpu::Maintain::Poll);
     }
 
+    /// `next_crop` for the current content, moved so its focal point (the sidecar's, or
+    /// the crop center) sits in the middle of the `safe-area`, as far as the content
+    /// reaches past the screen edges in that direction
+    fn placed_crop(&self) -> [f32; 4] {
+        let crop = self.next_crop;
+        let (width, height) = (self.config.width as f32, self.config.height as f32);
+        if (self.next_focal.is_none() && self.safe_area == [0; 4]) || width <= 0.0 || height <= 0.0
+        {
+            return crop;
+        }
+        // Only `fill` crops content to the screen's aspect ratio
+        if self.fit_mode != crate::orchestration::FitMode::Fill {
+            return crop;
+        }
+        let [top, right, bottom, left] = self.safe_area.map(|inset| inset as f32);
+        // Insets that leave nothing of the screen are ignored
+        let center = |near: f32, far: f32, size: f32| {
+            if near + far < size {
+                (near + (size - near - far) / 2.0) / size
+            } else {
+                0.5
+            }
+        };
+        let safe = [center(left, right, width), center(top, bottom, height)];
+        let focal = self.next_focal.unwrap_or([crop[1], crop[2]]);
+
+        // Share of the content `cover` shows on each axis, as in quad.wgsl
+        let scale = (width / height) / self.current_aspect;
+        let shown = if scale > 1.0 {
+            [1.0, 1.0 / scale]
+        } else {
+            [scale, 1.0]
+        };
+        let zoom = crop[0];
+        let place = |axis: usize| {
+            let half = 0.5 * shown[axis] / zoom;
+            (focal[axis] - (safe[axis] - 0.5) * shown[axis] / zoom).clamp(half, 1.0 - half)
+        };
+        [zoom, place(0), place(1), crop[3]]
+    }
+
     pub fn switch_content(&mut self) {
         // Always initialize transition state, even if current_texture is None
         // This ensures transitions work even when switching from empty state
         let had_current = self.current_texture.is_some();
+        // What is on screen now fades out at the exposure it is shown with
+        self.prev_exposure = self.displayed_exposure();
+        self.luminance_sampled_at = None;
+
+        // Interrupted mid-transition: the composition texture holds the blend currently on
+        // screen. It becomes the new prev so the next transition starts from exactly what
+        // the user sees instead of snapping to either side of the old one.
+        let capture_blend = self.transition_active
+            && self.transition_start_time.is_some()
+            && self.composition_texture.is_some()
+            && self.composition_texture_view.is_some();
+
+        // Content that never arrived (e.g. a video still waiting for its first frame) leaves
+        // the previous wallpaper on screen, so that stays the prev until something replaces
+        // it. Dropping it there started the next transition from black.
+        let replaces_prev = capture_blend || self.current_texture.is_some();
 
         // CRITICAL: If prev_texture already exists (from previous switch that didn't complete),
         // return it to pool before setting new one. This prevents accumulation when switching rapidly.
-        if let Some(old_prev) = self.prev_texture.take() {
-            if let Some((w, h)) = self.prev_texture_size.take() {
-                debug!(
-                    "[TRANSITION] {}: Replacing incomplete prev_texture, returning old one to pool",
-                    self.name
-                );
-                self.ctx.return_texture_to_pool(old_prev, w, h);
+        if replaces_prev {
+            if let Some(old_prev) = self.prev_texture.take() {
+                if let Some((w, h)) = self.prev_texture_size.take() {
+                    debug!(
+                        "[TRANSITION] {}: Replacing incomplete prev_texture, returning it to pool",
+                        self.name
+                    );
+                    self.ctx.return_texture_to_pool(old_prev, w, h);
+                }
+                // Drop view as well
+                drop(self.prev_texture_view.take());
             }
-            // Drop view as well
-            drop(self.prev_texture_view.take());
+        } else if self.prev_texture.is_some() {
+            debug!(
+                "[TRANSITION] {}: Pending content never arrived, keeping prev_texture",
+                self.name
+            );
         }
 
-        if let Some(curr) = self.current_texture.take() {
+        if capture_blend {
+            debug!(
+                "[TRANSITION] {}: Interrupted at progress {:.3}, continuing from the blended frame",
+                self.name, self.transition_progress
+            );
+            // The half-shown incoming content is no longer needed
+            if let Some(curr) = self.current_texture.take() {
+                if let Some((w, h)) = self.current_texture_size {
+                    self.ctx.return_texture_to_pool(curr, w, h);
+                }
+            }
+            drop(self.current_texture_view.take());
+
+            self.prev_texture = self.composition_texture.take();
+            self.prev_texture_view = self.composition_texture_view.take();
+            // Screen-sized, so it fits the output exactly
+            let screen_aspect = self.config.width as f32 / self.config.height.max(1) as f32;
+            self.prev_aspect = screen_aspect;
+            self.current_aspect = screen_aspect;
+            self.prev_crop = NO_CROP;
+            self.current_crop = NO_CROP;
+            // Not a pool texture (no COPY_DST), so it is dropped instead of returned;
+            // ensure_composition_texture creates a fresh composition target
+            self.prev_texture_size = None;
+        } else if let Some(curr) = self.current_texture.take() {
             self.prev_texture_view = self.current_texture_view.take();
             self.prev_texture = Some(curr);
             self.prev_aspect = self.current_aspect;
+            self.prev_crop = self.current_crop;
             // Track prev_texture size for returning to pool later
             self.prev_texture_size = self.current_texture_size;
         }
@@ -2121,10 +3058,15 @@ impl Renderer {
         self.transition_start_time = None; // Will be set when content is uploaded
         self.transition_active = false; // Will be set to true when new texture is ready
         self.transition_just_completed = false; // Reset completion flag
+        self.content_pending = true;
+        self.solid_color = None; // New content replaces a cleared color
         self.transition_bind_group = None; // Invalidate
         self.blit_bind_group = None; // Invalidate
         self.batch_start_time = None; // Reset
         self.video_first_frame_time = None; // Reset video timing for new session
+        self.palette_path = None;
+        self.palette_samples = None;
+        self.shader = None;
         self.needs_redraw = true;
 
         // OPTIMIZATION: Don't reset current_texture_size immediately.
@@ -2135,7 +3077,121 @@ impl Renderer {
             self.name, had_current, self.prev_texture.is_some());
     }
 
+    /// Stores the latest `monitor::SystemSampler` block
+    pub fn set_system_uniforms(&mut self, block: [[f32; 4]; 2]) {
+        self.system_uniforms = block;
+        self.system_sampled_at = std::time::Instant::now();
+    }
+
+    /// System block for this frame. The clocks are advanced by the time since sampling
+    /// so shaders animating on them stay smooth between the once-per-second refreshes.
+    fn system_block(&self) -> [[f32; 4]; 2] {
+        let mut block = self.system_uniforms;
+        let since = self.system_sampled_at.elapsed().as_secs_f32();
+        block[0][0] += since;
+        block[1][3] += since;
+        block
+    }
+
+    /// Previous and current content size relative to the screen, for `center` and
+    /// `tile`. The blend an interrupted transition continues from has no recorded
+    /// size and is screen-sized.
+    fn fit_sizes(&self) -> [f32; 4] {
+        let (width, height) = (self.config.width.max(1), self.config.height.max(1));
+        let relative = |size: Option<(u32, u32)>| {
+            size.map_or([1.0, 1.0], |(w, h)| {
+                [w as f32 / width as f32, h as f32 / height as f32]
+            })
+        };
+        let [prev_w, prev_h] = relative(self.prev_texture_size);
+        let [next_w, next_h] = relative(self.current_texture_size);
+        [prev_w, prev_h, next_w, next_h]
+    }
+
+    /// Post effects applied by the blit shader
+    fn effects(&self) -> [f32; 4] {
+        let flag = |on: bool| if on { 1.0 } else { 0.0 };
+        [
+            flag(self.grayscale),
+            flag(self.premultiplied()),
+            self.displayed_exposure(),
+            0.0,
+        ]
+    }
+
+    /// Exposure that keeps the current content's mean brightness under `max-luminance`
+    fn content_exposure(&self) -> f32 {
+        match self.max_luminance {
+            Some(cap) if self.content_luminance > cap => cap / self.content_luminance,
+            _ => 1.0,
+        }
+    }
+
+    /// Exposure of what is on screen: blended from the outgoing content's during a
+    /// transition, since the composition mixes both
+    fn displayed_exposure(&self) -> f32 {
+        let current = self.content_exposure();
+        if self.transition_active {
+            let t = self.transition_progress.clamp(0.0, 1.0);
+            self.prev_exposure + (current - self.prev_exposure) * t
+        } else {
+            current
+        }
+    }
+
+    /// Measures a video frame for `max-luminance`. Frames are sampled every
+    /// `LUMINANCE_SAMPLE_INTERVAL` and smoothed, except the first after a switch.
+    fn measure_video_luminance(&mut self, data: &[u8], width: u32, height: u32, stride: u32) {
+        let now = std::time::Instant::now();
+        let first = self.luminance_sampled_at.is_none();
+        if self
+            .luminance_sampled_at
+            .is_some_and(|at| now.duration_since(at) < LUMINANCE_SAMPLE_INTERVAL)
+        {
+            return;
+        }
+        let luminance = mean_luminance(data, width, height, stride);
+        self.content_luminance = if first {
+            luminance
+        } else {
+            self.content_luminance + (luminance - self.content_luminance) * LUMINANCE_SMOOTHING
+        };
+        self.luminance_sampled_at = Some(now);
+    }
+
+    /// Returns the previous texture to the pool when no transition will use it
+    fn release_prev_texture(&mut self) {
+        if let Some(prev_tex) = self.prev_texture.take() {
+            if let Some((w, h)) = self.prev_texture_size.take() {
+                self.ctx.return_texture_to_pool(prev_tex, w, h);
+            }
+        }
+        self.prev_texture_view = None;
+        self.transition_bind_group = None;
+    }
+
+    /// Idle trim: releases the transition targets, which are recreated on the next
+    /// switch. Only while no transition runs.
+    pub fn trim(&mut self) {
+        if self.transition_active || self.content_pending {
+            return;
+        }
+        self.release_prev_texture();
+        self.composition_texture = None;
+        self.composition_texture_view = None;
+        if !self.valid_content_type.is_played() {
+            self.nv12_planes = None;
+        }
+        if self.blit_source_is_composition || self.blit_source_is_prev {
+            self.blit_bind_group = None;
+            self.needs_redraw = true;
+        }
+    }
+
     pub fn abort_transition(&mut self) {
+        self.content_pending = false;
+        self.palette_path = None;
+        self.palette_samples = None;
         if self.transition_active || self.current_texture.is_none() {
             if self.transition_active {
                 info!(
@@ -2151,7 +3207,7 @@ impl Renderer {
         }
     }
 
-    /// Clears the renderer to black (removes current and previous textures)
+    /// Clears the renderer (removes current and previous textures)
     ///
     /// This explicitly drops all texture resources and forces WGPU to reclaim
     /// GPU memory immediately. Useful for cleanup and preventing memory leaks.
@@ -2165,9 +3221,11 @@ impl Renderer {
         self.composition_texture = None;
         self.composition_texture_view = None;
         self.current_texture_size = None;
+        self.shader = None;
         self.transition_progress = 1.0;
         self.transition_active = false;
         self.transition_just_completed = false; // Reset flag
+        self.content_pending = false;
         self.transition_bind_group = None; // Invalidate
         self.blit_bind_group = None; // Invalidate
         self.needs_redraw = true;
@@ -2178,6 +3236,118 @@ impl Renderer {
         self.ctx.device.poll(wgpu::Maintain::Poll);
     }
 
+    /// Releases every GPU resource held for content and stops rendering until `exit_standby`.
+    /// The main loop unmaps the surface so the compositor shows its default background.
+    pub fn enter_standby(&mut self) {
+        self.clear();
+        self.overlay_layers.clear();
+        self.overlay_state = None;
+        self.frame_callback_pending = false;
+        self.last_frame_request = None;
+        self.needs_redraw = false;
+        self.standby = true;
+    }
+
+    /// Leaves standby; the surface is remapped and reconfigured by the main loop
+    pub fn exit_standby(&mut self) {
+        if self.standby {
+            self.standby = false;
+            self.needs_redraw = true;
+        }
+    }
+
+    /// Replaces the text widget layers drawn over the wallpaper.
+    /// Bitmap positions are in surface pixels; `generation` identifies the overlay content.
+    pub fn set_overlay(&mut self, bitmaps: &[crate::overlay::WidgetBitmap], generation: u64) {
+        self.overlay_layers.clear();
+        self.overlay_state = Some((generation, self.config.width, self.config.height));
+        self.needs_redraw = true;
+
+        let (sw, sh) = (self.config.width as f32, self.config.height as f32);
+        if sw <= 1.0 || sh <= 1.0 {
+            return;
+        }
+
+        for bitmap in bitmaps {
+            let size = wgpu::Extent3d {
+                width: bitmap.width,
+                height: bitmap.height,
+                depth_or_array_layers: 1,
+            };
+            let texture = self.ctx.device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("Overlay Widget Texture"),
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            });
+            self.ctx.queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    texture: &texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All,
+                },
+                &bitmap.data,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(4 * bitmap.width),
+                    rows_per_image: Some(bitmap.height),
+                },
+                size,
+            );
+
+            // Pixel rect -> clip space (y grows downwards on screen)
+            let rect: [f32; 4] = [
+                bitmap.x as f32 / sw * 2.0 - 1.0,
+                1.0 - bitmap.y as f32 / sh * 2.0,
+                (bitmap.x as f32 + bitmap.width as f32) / sw * 2.0 - 1.0,
+                1.0 - (bitmap.y as f32 + bitmap.height as f32) / sh * 2.0,
+            ];
+            let uniform_buffer = self.ctx.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Overlay Uniform Buffer"),
+                size: std::mem::size_of::<[f32; 4]>() as u64,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            self.ctx
+                .queue
+                .write_buffer(&uniform_buffer, 0, bytemuck::cast_slice(&rect));
+
+            let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+            let bind_group = self
+                .ctx
+                .device
+                .create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Overlay Bind Group"),
+                    layout: &self.ctx.blit_bind_group_layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: uniform_buffer.as_entire_binding(),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: wgpu::BindingResource::TextureView(&view),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 2,
+                            resource: wgpu::BindingResource::Sampler(&self.sampler_linear),
+                        },
+                    ],
+                });
+
+            self.overlay_layers.push(OverlayLayer {
+                _texture: texture,
+                _uniform_buffer: uniform_buffer,
+                bind_group,
+            });
+        }
+    }
+
     #[allow(dead_code)]
     pub fn recreate_surface(&mut self, surface: wgpu::Surface<'static>) {
         self.surface = surface;
@@ -22,19 +22,23 @@ impl ScriptManager {
         engine.register_fn("next", move |output: String| {
             let (resp_tx, _) = oneshot::channel();
             let out = if output == "*" { None } else { Some(output) };
-            let _ = tx.send((Request::Next { output: out }, resp_tx));
+            let request = Request::Next {
+                output: out,
+                tags: Vec::new(),
+            };
+            let _ = tx.send((request, resp_tx));
         });
 
         let tx = cmd_tx.clone();
         engine.register_fn("pause", move || {
             let (resp_tx, _) = oneshot::channel();
-            let _ = tx.send((Request::Pause, resp_tx));
+            let _ = tx.send((Request::Pause { output: None }, resp_tx));
         });
 
         let tx = cmd_tx.clone();
         engine.register_fn("resume", move || {
             let (resp_tx, _) = oneshot::channel();
-            let _ = tx.send((Request::Resume, resp_tx));
+            let _ = tx.send((Request::Resume { output: None }, resp_tx));
         });
 
         Self {
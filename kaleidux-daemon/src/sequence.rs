@@ -0,0 +1,158 @@
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+/// Fewer numbered images than this are left as separate wallpapers
+pub const MIN_FRAMES: usize = 8;
+/// Decoded frames of a whole loop are kept (and never decoded again) up to this size
+pub const CACHE_BUDGET: usize = 256 * 1024 * 1024;
+/// Extensions a frame may have; every frame of a sequence has the same one
+const FRAME_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "webp", "bmp"];
+
+/// Splits `frame_0001.png` into ("frame_", 1, digit count 4, "png")
+fn frame_number(path: &Path) -> Option<(String, u64, usize, String)> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    if !FRAME_EXTENSIONS.contains(&ext.as_str()) {
+        return None;
+    }
+    let stem = path.file_stem()?.to_str()?;
+    let prefix = stem.trim_end_matches(|c: char| c.is_ascii_digit());
+    let digits = &stem[prefix.len()..];
+    Some((prefix.to_string(), digits.parse().ok()?, digits.len(), ext))
+}
+
+/// Frames of `dir` in playback order, if it is an image sequence: nothing but at least
+/// `MIN_FRAMES` images named alike with consecutive numbers (`frame_0001.png`,
+/// `frame_0002.png`, ...). Any other file or a subfolder makes it a plain folder again,
+/// so its images stay separate wallpapers.
+pub fn frames(dir: &Path) -> Option<Vec<PathBuf>> {
+    let mut frames = Vec::new();
+    let mut pattern: Option<(String, usize, String)> = None;
+    for entry in std::fs::read_dir(dir).ok()? {
+        let entry = entry.ok()?;
+        if !entry.file_type().ok()?.is_file() {
+            return None;
+        }
+        let path = entry.path();
+        let (prefix, number, width, ext) = frame_number(&path)?;
+        match &pattern {
+            None => pattern = Some((prefix, width, ext)),
+            Some(p) if *p == (prefix, width, ext) => {}
+            Some(_) => return None,
+        }
+        frames.push((number, path));
+    }
+    if frames.len() < MIN_FRAMES {
+        return None;
+    }
+
+    frames.sort_unstable_by_key(|(number, _)| *number);
+    let first = frames[0].0;
+    if frames
+        .iter()
+        .enumerate()
+        .any(|(i, (number, _))| *number != first + i as u64)
+    {
+        return None;
+    }
+    Some(frames.into_iter().map(|(_, path)| path).collect())
+}
+
+/// Decodes the frames of a sequence to RGBA in order, looping forever. Frames of another
+/// size than the first are scaled to it. When the whole loop fits in `CACHE_BUDGET`,
+/// the first pass keeps every frame and later passes only hand them out again.
+pub struct FrameReader {
+    frames: Vec<PathBuf>,
+    next: usize,
+    width: u32,
+    height: u32,
+    /// Decoded frames of the first pass, while the loop fits in the budget
+    cache: Option<Vec<Arc<[u8]>>>,
+    /// First frame, decoded by `open` to learn the size
+    first: Option<Arc<[u8]>>,
+}
+
+impl FrameReader {
+    pub fn open(frames: Vec<PathBuf>) -> Result<Self> {
+        let Some(path) = frames.first() else {
+            bail!("Image sequence has no frames");
+        };
+        let first = image::open(path)
+            .with_context(|| format!("Failed to decode frame {:?}", path))?
+            .to_rgba8();
+        let (width, height) = first.dimensions();
+        let loop_size = width as usize * height as usize * 4 * frames.len();
+        let cache = if loop_size <= CACHE_BUDGET {
+            Some(Vec::with_capacity(frames.len()))
+        } else {
+            debug!(
+                "[SEQUENCE] {} frames of {}x{} exceed the cache budget, decoding every pass",
+                frames.len(),
+                width,
+                height
+            );
+            None
+        };
+        Ok(Self {
+            frames,
+            next: 0,
+            width,
+            height,
+            cache,
+            first: Some(first.into_raw().into()),
+        })
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Next frame as tightly packed RGBA rows
+    pub fn next_frame(&mut self) -> Result<Arc<[u8]>> {
+        let index = self.next;
+        self.next = (self.next + 1) % self.frames.len();
+
+        if let Some(cache) = &self.cache {
+            if let Some(frame) = cache.get(index) {
+                return Ok(frame.clone());
+            }
+        }
+        let frame = match self.first.take() {
+            Some(frame) => frame,
+            None => self.decode(index)?,
+        };
+        if let Some(cache) = &mut self.cache {
+            cache.push(frame.clone());
+        }
+        Ok(frame)
+    }
+
+    fn decode(&self, index: usize) -> Result<Arc<[u8]>> {
+        let path = &self.frames[index];
+        let mut frame = image::open(path)
+            .with_context(|| format!("Failed to decode frame {:?}", path))?
+            .to_rgba8();
+        if frame.dimensions() != (self.width, self.height) {
+            warn!(
+                "[SEQUENCE] Frame {:?} is {}x{}, scaling to {}x{}",
+                path,
+                frame.width(),
+                frame.height(),
+                self.width,
+                self.height
+            );
+            frame = image::imageops::resize(
+                &frame,
+                self.width,
+                self.height,
+                image::imageops::FilterType::Triangle,
+            );
+        }
+        Ok(frame.into_raw().into())
+    }
+}
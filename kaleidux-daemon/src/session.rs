@@ -0,0 +1,37 @@
+use crate::shaders::Transition;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Every output's setup at `kldctl session save` time, by output name
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    /// Unix timestamp of the save
+    pub saved_at: u64,
+    pub outputs: BTreeMap<String, OutputSession>,
+}
+
+/// One output's part of a session. Outputs sharing a queue each store the queue's
+/// playlist and favorites, so restoring any of them brings the queue back.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputSession {
+    /// What the output was showing; None for nothing yet (or a failover)
+    pub wallpaper: Option<PathBuf>,
+    /// Active playlist of its queue, None for the configured content
+    pub playlist: Option<String>,
+    /// Cycling paused on this output (`pause --output`, or everything paused)
+    pub paused: bool,
+    pub favorites: bool,
+    pub transition: Transition,
+    pub transition_time: u32,
+}
+
+impl Session {
+    pub fn info(&self, name: &str) -> kaleidux_common::SessionInfo {
+        kaleidux_common::SessionInfo {
+            name: name.to_string(),
+            saved_at: self.saved_at,
+            outputs: self.outputs.keys().cloned().collect(),
+        }
+    }
+}
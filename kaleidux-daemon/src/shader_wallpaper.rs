@@ -0,0 +1,220 @@
+//! Shader wallpapers (`ContentType::Shader`): a GLSL or WGSL fragment shader from the
+//! wallpaper directory, drawn into the output's content texture up to `shader-fps` times
+//! a second. GLSL is written as for Shadertoy (`mainImage`, `iTime`, `iResolution`,
+//! `iMouse`, ...); WGSL defines `main_image` and reads `uniforms` (see `shaders.rs`).
+
+use crate::renderer::WgpuContext;
+use anyhow::{Context, Result};
+use bytemuck::{Pod, Zeroable};
+use chrono::{Datelike, Timelike};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Format of the texture frames are drawn into, the same as decoded content
+pub const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+/// Must match the uniform block of the preludes in shaders.rs
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+struct ShaderUniforms {
+    resolution: [f32; 3],
+    time: f32,
+    mouse: [f32; 4],
+    date: [f32; 4],
+    time_delta: f32,
+    frame: i32,
+    frame_rate: f32,
+    _pad: f32,
+}
+
+/// Whether `path` is shader source, going by its extension (.glsl or .wgsl)
+pub fn is_shader_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("glsl") || e.eq_ignore_ascii_case("wgsl"))
+}
+
+pub struct ShaderWallpaper {
+    path: PathBuf,
+    pipeline: wgpu::RenderPipeline,
+    uniform_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    /// `iTime` counts from here
+    started: Instant,
+    last_frame: Option<Instant>,
+    frame: i32,
+}
+
+impl ShaderWallpaper {
+    /// Reads and compiles `path`. Runs on the main loop like transition shaders do;
+    /// shader sources are small and naga translates them in a few milliseconds.
+    pub fn load(ctx: &WgpuContext, path: &Path) -> Result<Self> {
+        let source =
+            std::fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+        let name = path.file_name().map_or_else(
+            || path.display().to_string(),
+            |n| n.to_string_lossy().into_owned(),
+        );
+        let wgsl = path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("wgsl"));
+        let code = crate::shaders::ShaderManager::compile_wallpaper(&name, &source, wgsl)?;
+
+        let device = &ctx.device;
+        let vertex_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Quad Vertex Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shaders/quad.wgsl").into()),
+        });
+        let fragment_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(&format!("Shader Wallpaper: {}", name)),
+            source: wgpu::ShaderSource::Wgsl(code.into()),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Shader Wallpaper Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Shader Wallpaper Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(&format!("Shader Wallpaper Pipeline: {}", name)),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &vertex_shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &fragment_shader,
+                entry_point: Some("main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: FORMAT,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Shader Wallpaper Uniforms"),
+            size: std::mem::size_of::<ShaderUniforms>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Shader Wallpaper Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            pipeline,
+            uniform_buffer,
+            bind_group,
+            started: Instant::now(),
+            last_frame: None,
+            frame: 0,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether the next frame is due at `now`, `interval` after the last one. Frames up
+    /// to an eighth of the interval early still go out, so the main loop's tick landing
+    /// just before the deadline doesn't skip a whole tick.
+    pub fn due(&self, now: Instant, interval: Duration) -> bool {
+        match self.last_frame {
+            Some(last) => now.saturating_duration_since(last) >= interval - interval / 8,
+            None => true,
+        }
+    }
+
+    /// Draws the frame for `now` into `target`, `size` pixels large. `pointer` is the
+    /// last pointer position over the output, in pixels from its top left corner.
+    pub fn render(
+        &mut self,
+        ctx: &WgpuContext,
+        target: &wgpu::TextureView,
+        size: (u32, u32),
+        pointer: Option<[f32; 2]>,
+        frame_rate: f32,
+        now: Instant,
+    ) {
+        let (width, height) = (size.0 as f32, size.1 as f32);
+        let date = chrono::Local::now();
+        let uniforms = ShaderUniforms {
+            resolution: [width, height, 1.0],
+            time: now.saturating_duration_since(self.started).as_secs_f32(),
+            // Shadertoy counts from the bottom left
+            mouse: pointer.map_or([0.0; 4], |[x, y]| [x, height - y, 0.0, 0.0]),
+            date: [
+                date.year() as f32,
+                date.month0() as f32,
+                date.day() as f32,
+                date.num_seconds_from_midnight() as f32 + date.nanosecond() as f32 / 1e9,
+            ],
+            time_delta: self.last_frame.map_or(0.0, |last| {
+                now.saturating_duration_since(last).as_secs_f32()
+            }),
+            frame: self.frame,
+            frame_rate,
+            _pad: 0.0,
+        };
+        ctx.queue
+            .write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&uniforms));
+
+        let mut encoder = ctx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Shader Wallpaper Encoder"),
+            });
+        {
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Shader Wallpaper Render Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: target,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            rpass.set_pipeline(&self.pipeline);
+            rpass.set_bind_group(0, &self.bind_group, &[]);
+            rpass.draw(0..3, 0..1);
+        }
+        ctx.queue.submit(Some(encoder.finish()));
+
+        self.frame = self.frame.wrapping_add(1);
+        self.last_frame = Some(now);
+    }
+}
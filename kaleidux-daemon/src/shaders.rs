@@ -13,10 +13,28 @@ layout(set = 0, binding = 0) uniform TransitionUniforms {
     float prev_aspect;
     float next_aspect;
     vec4 params[7];
+    vec4 _effects;
+    vec4 sys_time;  // seconds since local midnight, day of month, month, year
+    vec4 sys_state; // system uptime (s), CPU load (0-1), battery (0-1, -1 = none), daemon time (s)
+    vec4 crop_prev; // random-crop windows: zoom, center x, center y
+    vec4 crop_next;
+    vec4 fit;       // x = fit-mode: 0 fill, 1 contain, 2 center, 3 stretch, 4 tile
+    vec4 fit_size;  // content size relative to the screen: prev w, h, next w, h
+    vec4 fit_color; // shown around contained or centered content
 };
 
 #define ratio screen_aspect
 
+// Machine state for procedural shaders, refreshed about once per second
+#define u_time_of_day sys_time.x
+#define u_day sys_time.y
+#define u_month sys_time.z
+#define u_year sys_time.w
+#define u_uptime sys_state.x
+#define u_cpu_load sys_state.y
+#define u_battery sys_state.z
+#define u_time sys_state.w
+
 layout(set = 0, binding = 1) uniform texture2D t_prev;
 layout(set = 0, binding = 2) uniform texture2D t_next;
 layout(set = 0, binding = 3) uniform sampler s_linear;
@@ -41,14 +59,115 @@ vec2 cover(vec2 uv, float screen_ratio, float content_ratio) {
     }
 }
 
+// Zooms into a window of the content (random-crop)
+vec2 crop_window(vec2 uv, vec4 c) {
+    return (uv - 0.5) / c.x + c.yz;
+}
+
+// Content coordinates of screen `uv` for the output's fit-mode, before tiling wraps them
+vec2 fit_uv(vec2 uv, float content_ratio, vec2 size, vec4 crop) {
+    int mode = int(fit.x + 0.5);
+    float scale = screen_aspect / content_ratio;
+    vec2 placed = cover(uv, screen_aspect, content_ratio);
+    if (mode == 1) {
+        // Contain: the inverse of cover, the whole content with bars on one axis
+        placed = scale > 1.0 ? vec2((uv.x - 0.5) * scale + 0.5, uv.y)
+                             : vec2(uv.x, (uv.y - 0.5) / scale + 0.5);
+    } else if (mode == 2) {
+        placed = (uv - 0.5) / size + 0.5;
+    } else if (mode == 3) {
+        placed = uv;
+    } else if (mode == 4) {
+        placed = uv / size;
+    }
+    return crop_window(placed, crop);
+}
+
+// Tiles repeat the content; sampling uses the gradients of the unwrapped coordinates,
+// so tile seams don't drop to the smallest mip level
+vec2 wrap_fit(vec2 uv_c) {
+    return int(fit.x + 0.5) == 4 ? fract(uv_c) : uv_c;
+}
+
+// fit_color around contained or centered content. Other modes keep clamping to the
+// edge, which transitions that sample past the screen rely on.
+vec4 fit_background(vec4 color, vec2 uv_c) {
+    int mode = int(fit.x + 0.5);
+    bool outside = any(lessThan(uv_c, vec2(0.0))) || any(greaterThan(uv_c, vec2(1.0)));
+    return (outside && (mode == 1 || mode == 2)) ? fit_color : color;
+}
+
 vec4 getFromColor(vec2 uv) {
-    vec2 uv_c = cover(uv, screen_aspect, prev_aspect);
-    return texture(sampler2D(t_prev, s_linear), uv_c);
+    vec2 uv_c = fit_uv(uv, prev_aspect, fit_size.xy, crop_prev);
+    vec4 color = textureGrad(sampler2D(t_prev, s_linear), wrap_fit(uv_c), dFdx(uv_c), dFdy(uv_c));
+    return fit_background(color, uv_c);
 }
 
 vec4 getToColor(vec2 uv) {
-    vec2 uv_c = cover(uv, screen_aspect, next_aspect);
-    return texture(sampler2D(t_next, s_linear), uv_c);
+    vec2 uv_c = fit_uv(uv, next_aspect, fit_size.zw, crop_next);
+    vec4 color = textureGrad(sampler2D(t_next, s_linear), wrap_fit(uv_c), dFdx(uv_c), dFdy(uv_c));
+    return fit_background(color, uv_c);
+}
+"#;
+
+/// Shadertoy's inputs for shader wallpapers. The block layout must match
+/// `shader_wallpaper::ShaderUniforms`.
+const WALLPAPER_GLSL_PRELUDE: &str = r#"
+#version 450
+layout(location = 0) in vec2 v_uv;
+layout(location = 0) out vec4 o_color;
+
+precision highp float;
+
+layout(set = 0, binding = 0) uniform ShaderUniforms {
+    vec3 iResolution;  // output size in pixels, z = 1
+    float iTime;       // seconds since the wallpaper was switched to
+    vec4 iMouse;       // pointer position in pixels (origin bottom left), zw = 0
+    vec4 iDate;        // year, month (0-11), day (1-31), seconds since local midnight
+    float iTimeDelta;  // seconds since the previous frame
+    int iFrame;
+    float iFrameRate;  // shader-fps
+};
+
+vec3 srgb_to_linear(vec3 c) {
+    return mix(c / 12.92, pow((c + 0.055) / 1.055, vec3(2.4)), step(0.04045, c));
+}
+"#;
+
+/// Shadertoy's origin is the bottom left, and its colors are stored as they come out
+/// of `mainImage`; the target is sRGB, so they are decoded to linear first
+const WALLPAPER_GLSL_MAIN: &str = r#"
+void main() {
+    vec4 color = vec4(0.0, 0.0, 0.0, 1.0);
+    mainImage(color, vec2(gl_FragCoord.x, iResolution.y - gl_FragCoord.y));
+    o_color = vec4(srgb_to_linear(clamp(color.rgb, 0.0, 1.0)), 1.0);
+}
+"#;
+
+/// The same inputs for WGSL shader wallpapers, as `uniforms.resolution` and so on
+const WALLPAPER_WGSL_PRELUDE: &str = r#"
+struct ShaderUniforms {
+    resolution: vec3<f32>,
+    time: f32,
+    mouse: vec4<f32>,
+    date: vec4<f32>,
+    time_delta: f32,
+    frame: i32,
+    frame_rate: f32,
+}
+
+@group(0) @binding(0) var<uniform> uniforms: ShaderUniforms;
+
+fn srgb_to_linear(c: vec3<f32>) -> vec3<f32> {
+    return mix(c / 12.92, pow((c + 0.055) / 1.055, vec3<f32>(2.4)), step(vec3<f32>(0.04045), c));
+}
+"#;
+
+const WALLPAPER_WGSL_MAIN: &str = r#"
+@fragment
+fn main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let color = main_image(vec2<f32>(position.x, uniforms.resolution.y - position.y));
+    return vec4<f32>(srgb_to_linear(clamp(color.rgb, vec3<f32>(0.0), vec3<f32>(1.0))), 1.0);
 }
 "#;
 
@@ -114,7 +233,11 @@ impl ShaderManager {
 
         // Log the generated shader for debugging purposes
         tracing::debug!("Compiling GLSL shader '{}'", name);
+        Self::glsl_to_wgsl(name, &full_glsl)
+    }
 
+    /// Fragment shader GLSL, translated to WGSL through naga
+    fn glsl_to_wgsl(name: &str, full_glsl: &str) -> anyhow::Result<String> {
         let mut parser = naga::front::glsl::Frontend::default();
         let module = parser
             .parse(
@@ -122,7 +245,7 @@ impl ShaderManager {
                     stage: naga::ShaderStage::Fragment,
                     defines: naga::FastHashMap::default(),
                 },
-                &full_glsl,
+                full_glsl,
             )
             .map_err(|e| {
                 tracing::error!(
@@ -151,6 +274,52 @@ impl ShaderManager {
         Ok(out)
     }
 
+    /// WGSL for a shader wallpaper: Shadertoy-style GLSL defining `mainImage`, or WGSL
+    /// defining `main_image` (see `WALLPAPER_WGSL_PRELUDE`). The fragment entry point is
+    /// `main`. WGSL is validated here, as wgpu treats an invalid module as fatal.
+    pub fn compile_wallpaper(name: &str, source: &str, wgsl: bool) -> anyhow::Result<String> {
+        if wgsl {
+            let full_wgsl = format!(
+                "{}\n{}\n{}",
+                WALLPAPER_WGSL_PRELUDE, source, WALLPAPER_WGSL_MAIN
+            );
+            let module = naga::front::wgsl::parse_str(&full_wgsl).map_err(|e| {
+                anyhow::anyhow!(
+                    "WGSL Parse Error in {}: {}",
+                    name,
+                    e.emit_to_string(&full_wgsl)
+                )
+            })?;
+            naga::valid::Validator::new(
+                naga::valid::ValidationFlags::all(),
+                naga::valid::Capabilities::all(),
+            )
+            .validate(&module)
+            .map_err(|e| anyhow::anyhow!("Shader Validation Error in {}: {:?}", name, e))?;
+            return Ok(full_wgsl);
+        }
+
+        // Shadertoy code may declare the uniforms the prelude already provides
+        let stripped = source
+            .lines()
+            .map(|line| {
+                let trimmed = line.trim_start();
+                if trimmed.starts_with("uniform ") || trimmed.starts_with("#version") {
+                    format!("// {}", line)
+                } else {
+                    line.to_string()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let full_glsl = format!(
+            "{}\n{}\n{}",
+            WALLPAPER_GLSL_PRELUDE, stripped, WALLPAPER_GLSL_MAIN
+        );
+        tracing::debug!("Compiling shader wallpaper '{}'", name);
+        Self::glsl_to_wgsl(name, &full_glsl)
+    }
+
     #[allow(dead_code)]
     pub fn get_shader(transition: &Transition) -> anyhow::Result<String> {
         match transition {
@@ -162,7 +331,7 @@ impl ShaderManager {
                 }
                 Self::compile_glsl(shader, &glsl, &mapping)
             }
-            Transition::Random => {
+            Transition::Random | Transition::Auto => {
                 // TODO: Pick a random builtin
                 Self::get_builtin_shader(&Transition::Fade)
             }
@@ -424,3 +593,75 @@ impl ShaderManager {
         }
     }
 }
+
+/// Range a transition parameter is drawn from each time the transition runs
+/// (`transition-randomize`)
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+#[serde(untagged)]
+pub enum ParamRange {
+    /// `[min, max]` for a number; integer parameters get a whole number
+    Range([f64; 2]),
+    /// One `[min, max]` per component of a vector parameter, e.g. a wipe direction
+    Components(Vec<[f64; 2]>),
+    /// Any other list is a set of values to pick from, e.g. `[false, true]`
+    Choice(Vec<serde_json::Value>),
+}
+
+/// `transition` with the parameters named in `ranges` replaced by random values.
+/// Works on the serialized transition, so the keys are the same as in its config table
+/// (for custom shaders, the entries of `params`).
+pub fn randomize_params(
+    transition: &Transition,
+    ranges: &std::collections::HashMap<String, ParamRange>,
+) -> anyhow::Result<Transition> {
+    use rand::seq::SliceRandom;
+    use rand::Rng;
+    use serde_json::Value;
+
+    let mut rng = rand::thread_rng();
+    let mut value = serde_json::to_value(transition)?;
+    let custom = matches!(transition, Transition::Custom { .. });
+    let params = if custom {
+        value.get_mut("params")
+    } else {
+        Some(&mut value)
+    }
+    .and_then(Value::as_object_mut)
+    .ok_or_else(|| anyhow::anyhow!("transition has no parameters"))?;
+
+    // Integer parameters (bar counts, grid sizes) keep being integers
+    fn number(rng: &mut impl Rng, current: Option<&Value>, [min, max]: [f64; 2]) -> Value {
+        let (low, high) = (min.min(max), min.max(max));
+        if current.is_some_and(|v| v.is_i64() || v.is_u64()) {
+            Value::from(rng.gen_range(low.round() as i64..=high.round() as i64))
+        } else {
+            Value::from(rng.gen_range(low..=high))
+        }
+    }
+    for (key, range) in ranges {
+        // Custom shaders take any float, the builtins only their own fields
+        if !custom && !params.contains_key(key) {
+            anyhow::bail!("{} has no parameter '{}'", transition.name(), key);
+        }
+        let new = match range {
+            ParamRange::Range(bounds) => number(&mut rng, params.get(key), *bounds),
+            ParamRange::Components(bounds) => {
+                let current = params.get(key).and_then(Value::as_array);
+                Value::Array(
+                    bounds
+                        .iter()
+                        .enumerate()
+                        .map(|(i, b)| number(&mut rng, current.and_then(|c| c.get(i)), *b))
+                        .collect(),
+                )
+            }
+            ParamRange::Choice(values) => match values.choose(&mut rng) {
+                Some(value) => value.clone(),
+                None => continue,
+            },
+        };
+        params.insert(key.clone(), new);
+    }
+
+    serde_json::from_value(value).map_err(|e| anyhow::anyhow!("{}: {}", transition.name(), e))
+}
@@ -0,0 +1,57 @@
+// Dual Kawase blur: each downsample pass halves the image, each upsample pass doubles it
+// back, so a wide blur costs a handful of cheap passes instead of a huge kernel.
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+struct BlurUniforms {
+    // Half a texel of the pass's target, times the sample offset
+    half_pixel: vec2<f32>,
+    _padding: vec2<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) in_vertex_index: u32) -> VertexOutput {
+    var out: VertexOutput;
+    // Full screen triangle
+    var uv = vec2<f32>(0.0, 0.0);
+    if (in_vertex_index == 0u) { uv = vec2<f32>(0.0, 2.0); }
+    if (in_vertex_index == 1u) { uv = vec2<f32>(0.0, 0.0); }
+    if (in_vertex_index == 2u) { uv = vec2<f32>(2.0, 0.0); }
+
+    let pos = vec2<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0);
+    out.position = vec4<f32>(pos, 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+@group(0) @binding(0) var t_source: texture_2d<f32>;
+@group(0) @binding(1) var s_source: sampler;
+@group(0) @binding(2) var<uniform> params: BlurUniforms;
+
+@fragment
+fn fs_down(in: VertexOutput) -> @location(0) vec4<f32> {
+    let hp = params.half_pixel;
+    var sum = textureSample(t_source, s_source, in.uv) * 4.0;
+    sum += textureSample(t_source, s_source, in.uv - hp);
+    sum += textureSample(t_source, s_source, in.uv + hp);
+    sum += textureSample(t_source, s_source, in.uv + vec2<f32>(hp.x, -hp.y));
+    sum += textureSample(t_source, s_source, in.uv - vec2<f32>(hp.x, -hp.y));
+    return sum / 8.0;
+}
+
+@fragment
+fn fs_up(in: VertexOutput) -> @location(0) vec4<f32> {
+    let hp = params.half_pixel;
+    var sum = textureSample(t_source, s_source, in.uv + vec2<f32>(-hp.x * 2.0, 0.0));
+    sum += textureSample(t_source, s_source, in.uv + vec2<f32>(-hp.x, hp.y)) * 2.0;
+    sum += textureSample(t_source, s_source, in.uv + vec2<f32>(0.0, hp.y * 2.0));
+    sum += textureSample(t_source, s_source, in.uv + vec2<f32>(hp.x, hp.y)) * 2.0;
+    sum += textureSample(t_source, s_source, in.uv + vec2<f32>(hp.x * 2.0, 0.0));
+    sum += textureSample(t_source, s_source, in.uv + vec2<f32>(hp.x, -hp.y)) * 2.0;
+    sum += textureSample(t_source, s_source, in.uv + vec2<f32>(0.0, -hp.y * 2.0));
+    sum += textureSample(t_source, s_source, in.uv + vec2<f32>(-hp.x, -hp.y)) * 2.0;
+    return sum / 12.0;
+}
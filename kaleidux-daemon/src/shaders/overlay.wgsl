@@ -0,0 +1,41 @@
+// Draws a single text widget bitmap as an alpha-blended quad on top of the wallpaper.
+
+struct OverlayUniforms {
+    // Widget rectangle in clip space: left, top, right, bottom
+    rect: vec4<f32>,
+}
+
+@group(0) @binding(0) var<uniform> overlay: OverlayUniforms;
+@group(0) @binding(1) var t_overlay: texture_2d<f32>;
+@group(0) @binding(2) var s_overlay: sampler;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) in_vertex_index: u32) -> VertexOutput {
+    // Two triangles covering the widget rectangle
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+    );
+    let corner = corners[in_vertex_index];
+
+    var out: VertexOutput;
+    let x = mix(overlay.rect.x, overlay.rect.z, corner.x);
+    let y = mix(overlay.rect.y, overlay.rect.w, corner.y);
+    out.position = vec4<f32>(x, y, 0.0, 1.0);
+    out.uv = corner;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(t_overlay, s_overlay, in.uv);
+}
@@ -31,6 +31,19 @@ struct TransitionUniforms {
     // params is array<vec4<f32>, 7>.
     // But WGSL array stride rules apply (16 bytes). vec4 is 16 bytes.
     params: array<vec4<f32>, 7>,
+    // Blit-only post effects (x = grayscale, y = premultiply alpha, z = exposure).
+    // Transition shaders don't declare this.
+    effects: vec4<f32>,
+    system: array<vec4<f32>, 2>,
+    // Crop windows (zoom, center x, center y, _); the blit only uses crop_next
+    crop_prev: vec4<f32>,
+    crop_next: vec4<f32>,
+    // fit-mode (x: 0 fill, 1 contain, 2 center, 3 stretch, 4 tile), content size relative
+    // to the screen (prev w, h, next w, h) and the color around contained content.
+    // Must match the GLSL prelude in shaders.rs.
+    fit: vec4<f32>,
+    fit_size: vec4<f32>,
+    fit_color: vec4<f32>,
 }
 
 @group(0) @binding(0) var<uniform> uniforms: TransitionUniforms;
@@ -52,6 +65,33 @@ fn cover(uv: vec2<f32>, screen_ratio: f32, content_ratio: f32) -> vec2<f32> {
     }
 }
 
+// Zooms into a window of the content (random-crop): x = zoom, yz = window center
+fn crop_window(uv: vec2<f32>, c: vec4<f32>) -> vec2<f32> {
+    return (uv - vec2<f32>(0.5)) / c.x + c.yz;
+}
+
+// Content coordinates of screen `uv` for the output's fit-mode, before tiling wraps them
+fn fit_uv(uv: vec2<f32>, content_ratio: f32, size: vec2<f32>, crop: vec4<f32>) -> vec2<f32> {
+    let mode = u32(uniforms.fit.x + 0.5);
+    let scale = uniforms.screen_aspect / content_ratio;
+    var placed = cover(uv, uniforms.screen_aspect, content_ratio);
+    if (mode == 1u) {
+        // Contain: the inverse of cover, the whole content with bars on one axis
+        if (scale > 1.0) {
+            placed = vec2<f32>((uv.x - 0.5) * scale + 0.5, uv.y);
+        } else {
+            placed = vec2<f32>(uv.x, (uv.y - 0.5) / scale + 0.5);
+        }
+    } else if (mode == 2u) {
+        placed = (uv - vec2<f32>(0.5)) / size + vec2<f32>(0.5);
+    } else if (mode == 3u) {
+        placed = uv;
+    } else if (mode == 4u) {
+        placed = uv / size;
+    }
+    return crop_window(placed, crop);
+}
+
 @fragment
 fn fs_blit(in: VertexOutput) -> @location(0) vec4<f32> {
     // Determine which aspect ratio to use.
@@ -73,13 +113,36 @@ fn fs_blit(in: VertexOutput) -> @location(0) vec4<f32> {
     //    Uniforms updated with next_aspect.
     //    We blit current texture. Cover needed.
     
+    let mode = u32(uniforms.fit.x + 0.5);
     var uv = in.uv;
+    var sample_at = uv;
     if (uniforms.progress >= 1.0) {
-        // Blitting raw content (image/video), apply cover
-        uv = cover(uv, uniforms.screen_aspect, uniforms.next_aspect);
+        // Blitting raw content (image/video), apply the fit mode
+        uv = fit_uv(uv, uniforms.next_aspect, uniforms.fit_size.zw, uniforms.crop_next);
+        sample_at = uv;
+        if (mode == 4u) {
+            sample_at = fract(uv);
+        }
     } 
     // Else: Blitting composition texture. UV 0..1 maps 1:1. No cover needed.
     // Note: Transition pass handles cover logic internally via glsl prelude.
     
-    return textureSample(t_diffuse, s_diffuse, uv);
+    // Gradients of the unwrapped coordinates, so tile seams keep their mip level
+    var color = textureSampleGrad(t_diffuse, s_diffuse, sample_at, dpdx(uv), dpdy(uv));
+    let outside = any(uv < vec2<f32>(0.0)) || any(uv > vec2<f32>(1.0));
+    if (uniforms.progress >= 1.0 && outside && (mode == 1u || mode == 2u)) {
+        color = uniforms.fit_color;
+    }
+    // max-luminance dimming, in linear light
+    color = vec4<f32>(color.rgb * uniforms.effects.z, color.a);
+    if (uniforms.effects.x > 0.5) {
+        // Rec. 709 luma
+        let luma = dot(color.rgb, vec3<f32>(0.2126, 0.7152, 0.0722));
+        color = vec4<f32>(luma, luma, luma, color.a);
+    }
+    if (uniforms.effects.y > 0.5) {
+        // Transparent outputs on a premultiplied-alpha surface
+        color = vec4<f32>(color.rgb * color.a, color.a);
+    }
+    return color;
 }
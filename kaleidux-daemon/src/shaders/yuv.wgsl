@@ -0,0 +1,54 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) in_vertex_index: u32) -> VertexOutput {
+    var out: VertexOutput;
+    // Full screen triangle
+    var uv = vec2<f32>(0.0, 0.0);
+    if (in_vertex_index == 0u) { uv = vec2<f32>(0.0, 2.0); }
+    if (in_vertex_index == 1u) { uv = vec2<f32>(0.0, 0.0); }
+    if (in_vertex_index == 2u) { uv = vec2<f32>(2.0, 0.0); }
+
+    let pos = vec2<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0);
+    out.position = vec4<f32>(pos, 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+// Rows of the affine map from (Y, Cb, Cr, 1) to gamma-encoded RGB
+struct Params {
+    r: vec4<f32>,
+    g: vec4<f32>,
+    b: vec4<f32>,
+};
+
+@group(0) @binding(0) var t_y: texture_2d<f32>;
+@group(0) @binding(1) var t_uv: texture_2d<f32>;
+@group(0) @binding(2) var s_planes: sampler;
+@group(0) @binding(3) var<uniform> params: Params;
+
+fn srgb_to_linear(c: vec3<f32>) -> vec3<f32> {
+    let low = c / 12.92;
+    let high = pow((c + 0.055) / 1.055, vec3<f32>(2.4));
+    return select(high, low, c <= vec3<f32>(0.04045));
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let yuv = vec4<f32>(
+        textureSample(t_y, s_planes, in.uv).r,
+        textureSample(t_uv, s_planes, in.uv).rg,
+        1.0,
+    );
+    let rgb = clamp(
+        vec3<f32>(dot(params.r, yuv), dot(params.g, yuv), dot(params.b, yuv)),
+        vec3<f32>(0.0),
+        vec3<f32>(1.0),
+    );
+    // The target is sRGB, which encodes on store: hand it linear values so it ends up
+    // holding the same bytes an RGBA upload of the frame would
+    return vec4<f32>(srgb_to_linear(rgb), 1.0);
+}
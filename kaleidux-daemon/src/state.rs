@@ -0,0 +1,126 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+/// Bumped when a field changes meaning or goes away; new fields may appear any time
+pub const SCHEMA_VERSION: u32 = 1;
+/// Palette colors covering less of the image than this are left out
+const MIN_COLOR_SHARE: f32 = 0.02;
+
+/// What `publish-state` writes for widgets: the state file, and on X11 the
+/// `_KALEIDUX_STATE` root window property
+#[derive(Debug, PartialEq, Serialize)]
+pub struct State {
+    pub version: u32,
+    pub pid: u32,
+    pub outputs: BTreeMap<String, OutputState>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct OutputState {
+    /// File on screen, None before the first pick
+    pub wallpaper: Option<String>,
+    /// "image", "video", "animated" or "shader"
+    #[serde(rename = "type")]
+    pub content_type: Option<&'static str>,
+    /// Dominant colors as "#rrggbb", most common first; empty for videos, animations and
+    /// until the image is measured
+    pub colors: Vec<String>,
+    /// Mean brightness, 0-1
+    pub brightness: Option<f32>,
+    /// Why the output shows its fallback, if it does
+    pub error: Option<String>,
+}
+
+impl OutputState {
+    pub fn new(
+        wallpaper: Option<&Path>,
+        content_type: Option<crate::queue::ContentType>,
+        tone: Option<&crate::tone::Tone>,
+        error: Option<&str>,
+    ) -> Self {
+        Self {
+            wallpaper: wallpaper.map(|p| p.display().to_string()),
+            content_type: content_type.map(crate::queue::ContentType::name),
+            colors: tone.map_or_else(Vec::new, |tone| {
+                tone.palette
+                    .iter()
+                    .filter(|(_, share)| *share >= MIN_COLOR_SHARE)
+                    .map(|(color, _)| hex(color))
+                    .collect()
+            }),
+            brightness: tone.map(|t| (t.brightness * 1000.0).round() / 1000.0),
+            error: error.map(str::to_string),
+        }
+    }
+}
+
+fn hex(color: &[f32; 3]) -> String {
+    let [r, g, b] = color.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// Replaces the state file in one rename, so readers never see half of it
+pub fn write(path: &Path, json: &str) -> Result<()> {
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, json).with_context(|| format!("Failed to write {:?}", tmp))?;
+    std::fs::rename(&tmp, path).with_context(|| format!("Failed to replace {:?}", path))
+}
+
+/// What changed in the published state
+pub enum Change {
+    Published(String),
+    /// `publish-state` was turned off
+    Withdrawn,
+}
+
+/// Keeps the state file current: rewritten whenever the state changes, removed when
+/// publishing is turned off or the daemon exits
+pub struct Publisher {
+    path: PathBuf,
+    last: Option<State>,
+}
+
+impl Publisher {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path, last: None }
+    }
+
+    /// Publishes `state` (None while `publish-state` is off) if it differs from the
+    /// last one; the JSON is returned for other places to publish it
+    pub fn update(&mut self, state: Option<State>) -> Option<Change> {
+        match state {
+            Some(state) if self.last.as_ref() == Some(&state) => None,
+            Some(state) => {
+                let json = match serde_json::to_string_pretty(&state) {
+                    Ok(json) => json,
+                    Err(e) => {
+                        warn!("[STATE] Failed to serialize state: {}", e);
+                        return None;
+                    }
+                };
+                match write(&self.path, &json) {
+                    Ok(()) => debug!("[STATE] Published to {:?}", self.path),
+                    Err(e) => warn!("[STATE] {:#}", e),
+                }
+                self.last = Some(state);
+                Some(Change::Published(json))
+            }
+            None if self.last.take().is_some() => {
+                let _ = std::fs::remove_file(&self.path);
+                Some(Change::Withdrawn)
+            }
+            None => None,
+        }
+    }
+}
+
+impl Drop for Publisher {
+    fn drop(&mut self) {
+        if self.last.is_some() {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
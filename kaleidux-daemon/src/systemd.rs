@@ -0,0 +1,101 @@
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::io::FromRawFd;
+use std::os::unix::net::{UnixDatagram, UnixListener};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+/// First fd passed by socket activation (SD_LISTEN_FDS_START)
+const LISTEN_FDS_START: i32 = 3;
+
+/// The IPC socket passed in by systemd socket activation (`LISTEN_FDS`), if any. The
+/// variables are cleared so hooks and scripts don't inherit them.
+pub fn take_listener() -> Option<UnixListener> {
+    let pid = std::env::var("LISTEN_PID").ok();
+    let fds = std::env::var("LISTEN_FDS").ok();
+    for var in ["LISTEN_PID", "LISTEN_FDS", "LISTEN_FDNAMES"] {
+        std::env::remove_var(var);
+    }
+    let pid: u32 = pid?.parse().ok()?;
+    let fds: i32 = fds?.parse().ok()?;
+    if pid != std::process::id() || fds < 1 {
+        return None;
+    }
+    if fds > 1 {
+        warn!("[SYSTEMD] {} sockets passed, only the first is served", fds);
+    }
+    unsafe { libc::fcntl(LISTEN_FDS_START, libc::F_SETFD, libc::FD_CLOEXEC) };
+    let listener = unsafe { UnixListener::from_raw_fd(LISTEN_FDS_START) };
+    // Fails for anything but a unix socket
+    let addr = match listener.local_addr() {
+        Ok(addr) => addr,
+        Err(e) => {
+            warn!("[SYSTEMD] Passed fd is not a unix socket: {}", e);
+            return None;
+        }
+    };
+    if let Err(e) = listener.set_nonblocking(true) {
+        warn!("[SYSTEMD] Failed to use the passed socket: {}", e);
+        return None;
+    }
+    info!("[STARTUP] IPC socket passed by systemd: {:?}", addr);
+    Some(listener)
+}
+
+/// Sends a state like "READY=1" to the service manager when running as a
+/// `Type=notify` service (`NOTIFY_SOCKET`); does nothing otherwise
+pub fn notify(state: &str) {
+    let Some(path) = std::env::var_os("NOTIFY_SOCKET") else {
+        return;
+    };
+    let send = || -> std::io::Result<usize> {
+        let socket = UnixDatagram::unbound()?;
+        match path.as_bytes().strip_prefix(b"@") {
+            Some(name) => {
+                use std::os::linux::net::SocketAddrExt;
+                let addr = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+                socket.send_to_addr(state.as_bytes(), &addr)
+            }
+            None => socket.send_to(state.as_bytes(), &path),
+        }
+    };
+    match send() {
+        Ok(_) => debug!("[SYSTEMD] Sent {}", state),
+        Err(e) => warn!("[SYSTEMD] Failed to send {}: {}", state, e),
+    }
+}
+
+/// Pings the service manager's watchdog (`WatchdogSec=`) from the main loop, at half
+/// the timeout it asked for, so a hung loop gets the service restarted
+pub struct Watchdog {
+    interval: Option<Duration>,
+    last_ping: Instant,
+}
+
+impl Watchdog {
+    pub fn new() -> Self {
+        let ours =
+            std::env::var("WATCHDOG_PID").map_or(true, |pid| pid.parse() == Ok(std::process::id()));
+        let interval = std::env::var("WATCHDOG_USEC")
+            .ok()
+            .and_then(|usec| usec.parse::<u64>().ok())
+            .filter(|&usec| usec > 0 && ours)
+            .map(|usec| Duration::from_micros(usec) / 2);
+        if let Some(interval) = interval {
+            info!("[SYSTEMD] Watchdog enabled, pinging every {:?}", interval);
+        }
+        Self {
+            interval,
+            last_ping: Instant::now(),
+        }
+    }
+
+    pub fn poll(&mut self) {
+        if self
+            .interval
+            .is_some_and(|interval| self.last_ping.elapsed() >= interval)
+        {
+            notify("WATCHDOG=1");
+            self.last_ping = Instant::now();
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use crate::cache::FileCache;
+use crate::orchestration::{AutoTransitionConfig, AutoTransitionRule};
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tracing::debug;
+
+/// Images are sampled down to about this many pixels before measuring
+const SAMPLE_PIXELS: u32 = 64 * 64;
+/// Histogram bins per channel when looking for the dominant color
+const BINS: usize = 8;
+/// Colors kept per image, most common first
+pub const PALETTE: usize = 4;
+
+/// Overall look of an image: its dominant colors and mean brightness, all 0-1
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Tone {
+    /// Most common colors with their share of the image, most common first; entries
+    /// past the colors the image has are left at a share of 0
+    pub palette: [([f32; 3], f32); PALETTE],
+    pub brightness: f32,
+}
+
+impl Tone {
+    /// The dominant color
+    pub fn color(&self) -> [f32; 3] {
+        self.palette[0].0
+    }
+}
+
+/// What an output shows, as far as `transition = "auto"` cares
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Look {
+    Video,
+    /// An image, with its tone once it has been measured
+    Image(Option<Tone>),
+}
+
+/// Measures `image`: the palette holds the averages of the fullest bins of a coarse
+/// color histogram, so a large uniform area wins over a mix of small ones
+pub fn measure(image: &RgbaImage) -> Tone {
+    let step = ((image.width() * image.height() / SAMPLE_PIXELS) as f32)
+        .sqrt()
+        .max(1.0) as usize;
+    let mut bins = vec![([0u64; 3], 0u64); BINS * BINS * BINS];
+    let mut luma = 0.0f64;
+    let mut count = 0u64;
+    for y in (0..image.height()).step_by(step) {
+        for x in (0..image.width()).step_by(step) {
+            let [r, g, b, _] = image.get_pixel(x, y).0;
+            let bin = &mut bins[(r as usize * BINS / 256) * BINS * BINS
+                + (g as usize * BINS / 256) * BINS
+                + b as usize * BINS / 256];
+            bin.0[0] += r as u64;
+            bin.0[1] += g as u64;
+            bin.0[2] += b as u64;
+            bin.1 += 1;
+            luma += 0.2126 * r as f64 + 0.7152 * g as f64 + 0.0722 * b as f64;
+            count += 1;
+        }
+    }
+
+    bins.sort_unstable_by_key(|(_, n)| std::cmp::Reverse(*n));
+    let mut palette = [([0.0; 3], 0.0); PALETTE];
+    for (entry, (sum, n)) in palette.iter_mut().zip(bins).filter(|(_, (_, n))| *n > 0) {
+        *entry = (
+            sum.map(|c| c as f32 / n as f32 / 255.0),
+            n as f32 / count as f32,
+        );
+    }
+    Tone {
+        palette,
+        brightness: (luma / count.max(1) as f64 / 255.0) as f32,
+    }
+}
+
+/// Tone of `image`, decoded from `path`: the cached one, or measured and cached now
+pub fn lookup(cache: &FileCache, path: &Path, image: &RgbaImage) -> Tone {
+    if let Ok(Some(tone)) = cache.get_tone(path) {
+        return tone;
+    }
+    let tone = measure(image);
+    if let Err(e) = cache.set_tone(path, &tone) {
+        debug!("[TONE] Failed to cache {:?}: {}", path, e);
+    }
+    tone
+}
+
+/// Distance between the dominant colors of `a` and `b` (0 = same, 1 = black vs white)
+pub fn color_distance(a: &Tone, b: &Tone) -> f32 {
+    let (a, b) = (a.color(), b.color());
+    let squared: f32 = (0..3).map(|i| (a[i] - b[i]).powi(2)).sum();
+    (squared / 3.0).sqrt()
+}
+
+fn matches(rule: &AutoTransitionRule, video: bool, tones: Option<(&Tone, &Tone)>) -> bool {
+    if rule.video.is_some_and(|v| v != video) {
+        return false;
+    }
+    if !rule.needs_tones() {
+        return true;
+    }
+    let Some((from, to)) = tones else {
+        return false;
+    };
+    let distance = color_distance(from, to);
+    let change = (from.brightness - to.brightness).abs();
+    rule.max_color_distance.is_none_or(|max| distance <= max)
+        && rule.min_color_distance.is_none_or(|min| distance >= min)
+        && rule.max_brightness_change.is_none_or(|max| change <= max)
+        && rule.min_brightness_change.is_none_or(|min| change >= min)
+}
+
+/// Name of the transition from `from` to `to`: the first matching rule's, else the
+/// default. None while an image rule comes first whose incoming tone isn't known yet,
+/// so the pick has to wait for the decoded image. An unknown outgoing tone (nothing
+/// shown yet, or not measured) counts as not matching.
+pub fn pick(config: &AutoTransitionConfig, from: Option<Look>, to: Look) -> Option<&str> {
+    let video = matches!(from, Some(Look::Video)) || to == Look::Video;
+    let from_tone = match from {
+        Some(Look::Image(tone)) => tone,
+        _ => None,
+    };
+    let to_tone = match to {
+        Look::Image(tone) => tone,
+        Look::Video => None,
+    };
+    for rule in &config.rules {
+        if rule.video.is_some_and(|v| v != video) {
+            continue;
+        }
+        if !video && rule.needs_tones() && from_tone.is_some() && to_tone.is_none() {
+            return None;
+        }
+        let tones = from_tone.as_ref().zip(to_tone.as_ref());
+        if matches(rule, video, tones) {
+            return Some(&rule.transition);
+        }
+    }
+    Some(&config.default)
+}
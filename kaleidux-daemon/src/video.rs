@@ -2,21 +2,117 @@ use gst::prelude::*;
 use gstreamer as gst;
 use gstreamer_app as gst_app;
 use gstreamer_video as gst_video;
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::thread::JoinHandle;
 use tokio::sync::Semaphore;
 use tracing::{debug, info};
 
-/// Video frame containing RGBA pixel data
+/// Decoded frames an image sequence keeps queued ahead of playback
+const SEQUENCE_RING_FRAMES: u64 = 8;
+
+/// Video frame containing RGBA or NV12 pixel data
 /// Uses gst::Buffer to avoid copying data
 #[derive(Clone)]
 pub struct VideoFrame {
     pub buffer: gst::Buffer,
     pub width: u32,
     pub height: u32,
+    /// Row stride of the RGBA data, or of the Y plane for NV12
     pub stride: u32,
     pub session_id: u64,
+    /// Set for NV12 frames, which the renderer converts to RGB on the GPU
+    pub nv12: Option<Nv12Layout>,
+}
+
+/// Where the planes of an NV12 frame sit in its buffer and how its YUV maps to RGB
+#[derive(Debug, Clone, Copy)]
+pub struct Nv12Layout {
+    pub y_offset: usize,
+    /// Interleaved Cb/Cr plane at half the resolution
+    pub uv_offset: usize,
+    pub uv_stride: u32,
+    /// Rows of an affine map from normalized (Y, Cb, Cr, 1) to gamma-encoded RGB, with
+    /// the color matrix and range folded in
+    pub to_rgb: [[f32; 4]; 3],
+}
+
+impl Nv12Layout {
+    fn new(info: &gst_video::VideoInfo) -> Self {
+        let colorimetry = info.colorimetry();
+        // Luma weights (Kr, Kb) of the color matrix; untagged video is HD above 576 lines
+        let (kr, kb) = match colorimetry.matrix() {
+            gst_video::VideoColorMatrix::Bt601 => (0.299, 0.114),
+            gst_video::VideoColorMatrix::Bt2020 => (0.2627, 0.0593),
+            gst_video::VideoColorMatrix::Smpte240m => (0.212, 0.087),
+            gst_video::VideoColorMatrix::Fcc => (0.30, 0.11),
+            gst_video::VideoColorMatrix::Bt709 => (0.2126, 0.0722),
+            _ if info.height() <= 576 => (0.299, 0.114),
+            _ => (0.2126, 0.0722),
+        };
+        let kg = 1.0 - kr - kb;
+        let (r_cr, b_cb) = (2.0 * (1.0 - kr), 2.0 * (1.0 - kb));
+        let (g_cb, g_cr) = (b_cb * kb / kg, r_cr * kr / kg);
+        // Limited ("TV") range unless tagged full, like most decoders assume
+        let (y_scale, y_offset, c_scale) = match colorimetry.range() {
+            gst_video::VideoColorRange::Range0_255 => (1.0, 0.0, 1.0),
+            _ => (255.0 / 219.0, 16.0 / 255.0, 255.0 / 224.0),
+        };
+        let c_offset = 128.0 / 255.0;
+        let y_term = -y_scale * y_offset;
+        Self {
+            y_offset: info.offset()[0],
+            uv_offset: info.offset()[1],
+            uv_stride: info.stride()[1] as u32,
+            to_rgb: [
+                [
+                    y_scale,
+                    0.0,
+                    c_scale * r_cr,
+                    y_term - c_scale * r_cr * c_offset,
+                ],
+                [
+                    y_scale,
+                    -c_scale * g_cb,
+                    -c_scale * g_cr,
+                    y_term + c_scale * (g_cb + g_cr) * c_offset,
+                ],
+                [
+                    y_scale,
+                    c_scale * b_cb,
+                    0.0,
+                    y_term - c_scale * b_cb * c_offset,
+                ],
+            ],
+        }
+    }
+
+    /// RGBA grid of at most 64x64 pixels converted from `frame`'s planes in `data`, with a
+    /// stride of `width * 4`, for the luminance and palette measurements that expect RGBA
+    pub fn sample_grid(&self, frame: &VideoFrame, data: &[u8]) -> (Vec<u8>, u32, u32) {
+        let (step_x, step_y) = ((frame.width / 64).max(1), (frame.height / 64).max(1));
+        let (width, height) = (frame.width.div_ceil(step_x), frame.height.div_ceil(step_y));
+        let mut grid = Vec::with_capacity((width * height * 4) as usize);
+        for y in (0..frame.height).step_by(step_y as usize) {
+            for x in (0..frame.width).step_by(step_x as usize) {
+                let luma = self.y_offset + (y * frame.stride + x) as usize;
+                let chroma = self.uv_offset + ((y / 2) * self.uv_stride + (x / 2) * 2) as usize;
+                let (Some(&luma), Some(chroma)) = (data.get(luma), data.get(chroma..chroma + 2))
+                else {
+                    grid.extend_from_slice(&[0, 0, 0, 255]);
+                    continue;
+                };
+                let yuv = [luma, chroma[0], chroma[1]].map(|c| c as f32 / 255.0);
+                for row in &self.to_rgb {
+                    let c = row[0] * yuv[0] + row[1] * yuv[1] + row[2] * yuv[2] + row[3];
+                    grid.push((c.clamp(0.0, 1.0) * 255.0).round() as u8);
+                }
+                grid.push(255);
+            }
+        }
+        (grid, width, height)
+    }
 }
 
 pub enum VideoEvent {
@@ -51,6 +147,59 @@ pub fn get_bus_watcher_pool() -> Arc<BusWatcherPool> {
     BUS_WATCHER_POOL.clone()
 }
 
+/// Hardware video decoders (VA-API, NVDEC, V4L2, ...) with the rank GStreamer registered
+/// them at, to return to for `video-decoder = "auto"`. Call after `gst::init`.
+fn hardware_decoders() -> &'static [(gst::ElementFactory, gst::Rank)] {
+    static DECODERS: std::sync::OnceLock<Vec<(gst::ElementFactory, gst::Rank)>> =
+        std::sync::OnceLock::new();
+    DECODERS.get_or_init(|| {
+        gst::ElementFactory::factories_with_type(
+            gst::ElementFactoryType::DECODER | gst::ElementFactoryType::MEDIA_VIDEO,
+            gst::Rank::NONE,
+        )
+        .into_iter()
+        .filter(|f| f.klass().contains("Hardware"))
+        .map(|f| {
+            let rank = f.rank();
+            (f, rank)
+        })
+        .collect()
+    })
+}
+
+/// Names of the hardware video decoders GStreamer has, for the doctor
+pub fn hardware_decoder_names() -> Vec<String> {
+    hardware_decoders()
+        .iter()
+        .map(|(f, _)| f.name().to_string())
+        .collect()
+}
+
+/// Re-ranks the hardware decoders so playbin picks them first (`hardware`), never
+/// (`software`) or as GStreamer would (`auto`). Applies to videos started afterwards.
+pub fn prefer_decoders(preference: crate::orchestration::VideoDecoder) {
+    use crate::orchestration::VideoDecoder;
+    // --headless never initializes GStreamer for video
+    if !gst::INITIALIZED.load(Ordering::SeqCst) {
+        return;
+    }
+    let decoders = hardware_decoders();
+    for (factory, default_rank) in decoders {
+        factory.set_rank(match preference {
+            VideoDecoder::Auto => *default_rank,
+            VideoDecoder::Hardware => gst::Rank::PRIMARY + 1,
+            VideoDecoder::Software => gst::Rank::NONE,
+        });
+    }
+    if preference != VideoDecoder::Auto {
+        info!(
+            "[VIDEO] Preferring {:?} decoding ({} hardware decoders)",
+            preference,
+            decoders.len()
+        );
+    }
+}
+
 pub struct VideoPlayer {
     pub pipeline: gst::Element,
     is_running: Arc<AtomicBool>,
@@ -58,6 +207,29 @@ pub struct VideoPlayer {
     frame_tx: tokio::sync::mpsc::Sender<(Arc<String>, VideoEvent)>,
     source_id: Arc<String>,
     start_time: std::time::Instant,
+    /// Played range of the file; playback starts at `clip_start` and loops back to it
+    clip_start: gst::ClockTime,
+    clip_end: Option<gst::ClockTime>,
+    /// Plays an image sequence or animation (silent, no clip ranges) instead of a file
+    sequence: bool,
+    /// Frames of an image sequence or animation, fed into the pipeline once it starts
+    feed: Option<Feed>,
+}
+
+enum Feed {
+    Sequence(SequenceFeed),
+    Animation(AnimationFeed),
+}
+
+struct SequenceFeed {
+    reader: crate::sequence::FrameReader,
+    appsrc: gst_app::AppSrc,
+    fps: u32,
+}
+
+struct AnimationFeed {
+    animation: crate::animation::Animation,
+    appsrc: gst_app::AppSrc,
 }
 
 impl VideoPlayer {
@@ -97,127 +269,155 @@ impl VideoPlayer {
         // Must use set_property_from_str since the flags property expects GstPlayFlags, not u32.
         pipeline.set_property_from_str("flags", "video+audio");
 
-        // Create appsink for video frames - configure like gSlapper does
-        let appsink = gst::ElementFactory::make("appsink")
-            .name("video-sink")
-            .build()?
-            .downcast::<gst_app::AppSink>()
-            .map_err(|_| anyhow::anyhow!("Failed to downcast to AppSink"))?;
-
-        // Configure appsink to output RGBA frames (same as gSlapper)
-        let caps = gst::Caps::builder("video/x-raw")
-            .field("format", "RGBA")
-            .build();
-
-        appsink.set_caps(Some(&caps));
-        appsink.set_sync(true); // Sync to clock
-        appsink.set_drop(true); // Drop frames if late - CRITICAL for preventing buffer accumulation
-        appsink.set_max_buffers(1); // Match gSlapper: 1 buffer to minimize latency and memory
-                                    // CRITICAL: Enable emit-signals to get callbacks, but ensure we handle them quickly
-                                    // The new_sample callback will be called for each frame
-
-        // Keep source_id for closure
-        let cb_source_id = source_id.clone();
-
-        // Set up new-sample callback
-        let frame_tx_clone = frame_tx.clone();
-        let first_frame_logged = Arc::new(AtomicBool::new(false));
-        let creation_time_ref = creation_start;
-
-        appsink.set_callbacks(
-            gst_app::AppSinkCallbacks::builder()
-                .new_sample(move |sink| {
-                    let source_id = cb_source_id.clone();
-
-                    if !first_frame_logged.load(Ordering::SeqCst) {
-                        first_frame_logged.store(true, Ordering::SeqCst);
-                        let duration = creation_time_ref.elapsed();
-                        info!("[ASSET] {}: First video frame produced in {:.3}ms", source_id, duration.as_secs_f64() * 1000.0);
-                    }
+        let appsink = frame_sink(
+            source_id.clone(),
+            session_id,
+            frame_tx.clone(),
+            creation_start,
+        )?;
 
-                    let session_id = session_id;
-
-                    // CRITICAL: Pull sample and extract buffer in explicit scope
-                    // This ensures sample is dropped immediately after buffer extraction
-                    let (buffer, width, height, stride) = {
-                        let sample = match sink.pull_sample() {
-                            Ok(s) => s,
-                            Err(_) => return Err(gst::FlowError::Error),
-                        };
-
-                        let buffer = match sample.buffer() {
-                            Some(b) => b.to_owned(),
-                            None => return Err(gst::FlowError::Error),
-                        };
-
-                        let caps = match sample.caps() {
-                            Some(c) => c,
-                            None => return Err(gst::FlowError::Error),
-                        };
-
-                        let video_info = match gst_video::VideoInfo::from_caps(caps) {
-                            Ok(vi) => vi,
-                            Err(_) => return Err(gst::FlowError::Error),
-                        };
-
-                        let width = video_info.width();
-                        let height = video_info.height();
-                        let stride = video_info.stride()[0] as u32;
-
-                        // sample is dropped here, releasing GStreamer sample resources
-                        (buffer, width, height, stride)
-                    };
+        // Set appsink as the video sink
+        pipeline.set_property("video-sink", &appsink);
 
-                    let frame = VideoFrame {
-                        buffer,
-                        width,
-                        height,
-                        stride,
-                        session_id,
-                    };
+        info!("VideoPlayer created with playbin + appsink (RGBA mode)");
 
-                    // Send frame - if channel is full, drop frame immediately to release gst::Buffer
-                    match frame_tx_clone.try_send((source_id.clone(), VideoEvent::Frame(frame))) {
-                        Ok(()) => {
-                            // Frame sent successfully
-                        }
-                        Err(tokio::sync::mpsc::error::TrySendError::Full(_)) => {
-                            // CRITICAL: Channel full - drop frame immediately to release gst::Buffer
-                            // This prevents buffer accumulation in GStreamer's internal pool
-                            tracing::warn!("[VIDEO] Frame channel full for {}, dropping frame and releasing buffer", source_id);
-                            // frame is dropped here, releasing the gst::Buffer
-                        }
-                        Err(tokio::sync::mpsc::error::TrySendError::Closed(_)) => {
-                            tracing::warn!("[VIDEO] Frame channel closed for {}, stopping", source_id);
-                            // frame is dropped here
-                            return Err(gst::FlowError::Eos);
-                        }
-                    }
+        Ok(Self {
+            pipeline,
+            is_running: Arc::new(AtomicBool::new(false)),
+            thread_handle: None,
+            frame_tx,
+            source_id,
+            start_time: creation_start,
+            clip_start: gst::ClockTime::ZERO,
+            clip_end: None,
+            sequence: false,
+            feed: None,
+        })
+    }
+
+    /// Player for an image sequence (a folder of numbered frames) at `fps`. A feeder
+    /// thread decodes frames ahead into the appsrc queue, a ring of
+    /// `SEQUENCE_RING_FRAMES`, so playback never waits on the decoder; from there it
+    /// plays, pauses and loops like a video.
+    pub fn new_sequence(
+        frames: Vec<PathBuf>,
+        fps: u32,
+        source_id: Arc<String>,
+        session_id: u64,
+        frame_tx: tokio::sync::mpsc::Sender<(Arc<String>, VideoEvent)>,
+    ) -> anyhow::Result<Self> {
+        let creation_start = std::time::Instant::now();
+        let reader = crate::sequence::FrameReader::open(frames)?;
+        let (width, height) = reader.size();
+        let (pipeline, appsrc) = appsrc_pipeline(
+            (width, height),
+            gst::Fraction::new(fps as i32, 1),
+            source_id.clone(),
+            session_id,
+            frame_tx.clone(),
+            creation_start,
+        )?;
 
-                    Ok(gst::FlowSuccess::Ok)
-                })
-                .build(),
+        info!(
+            "[VIDEO] {}: Image sequence of {} frames ({}x{}) at {} fps",
+            source_id,
+            reader.frame_count(),
+            width,
+            height,
+            fps
         );
 
-        // Configure appsink
-        appsink.set_property("drop", true);
-        appsink.set_property("max-buffers", 1u32);
+        Ok(Self {
+            pipeline: pipeline.upcast(),
+            is_running: Arc::new(AtomicBool::new(false)),
+            thread_handle: None,
+            frame_tx,
+            source_id,
+            start_time: creation_start,
+            clip_start: gst::ClockTime::ZERO,
+            clip_end: None,
+            sequence: true,
+            feed: Some(Feed::Sequence(SequenceFeed {
+                reader,
+                appsrc,
+                fps,
+            })),
+        })
+    }
 
-        // Set appsink as the video sink
-        pipeline.set_property("video-sink", &appsink);
+    /// Player for an animated GIF, APNG or WebP: fed like an image sequence, but each
+    /// frame stays up for its own delay
+    pub fn new_animation(
+        path: &std::path::Path,
+        source_id: Arc<String>,
+        session_id: u64,
+        frame_tx: tokio::sync::mpsc::Sender<(Arc<String>, VideoEvent)>,
+    ) -> anyhow::Result<Self> {
+        let creation_start = std::time::Instant::now();
+        let animation = crate::animation::Animation::open(path)?;
+        let (width, height) = animation.size();
+        // Variable frame rate
+        let (pipeline, appsrc) = appsrc_pipeline(
+            (width, height),
+            gst::Fraction::new(0, 1),
+            source_id.clone(),
+            session_id,
+            frame_tx.clone(),
+            creation_start,
+        )?;
 
-        info!("VideoPlayer created with playbin + appsink (RGBA mode)");
+        info!(
+            "[VIDEO] {}: Animation {:?} ({}x{})",
+            source_id, path, width, height
+        );
 
         Ok(Self {
-            pipeline,
+            pipeline: pipeline.upcast(),
             is_running: Arc::new(AtomicBool::new(false)),
             thread_handle: None,
             frame_tx,
             source_id,
             start_time: creation_start,
+            clip_start: gst::ClockTime::ZERO,
+            clip_end: None,
+            sequence: true,
+            feed: Some(Feed::Animation(AnimationFeed { animation, appsrc })),
         })
     }
 
+    /// Restrict playback to part of the file. Must be called before `start()`.
+    pub fn set_clip(
+        &mut self,
+        start: Option<std::time::Duration>,
+        end: Option<std::time::Duration>,
+    ) {
+        if self.sequence {
+            if start.is_some() || end.is_some() {
+                debug!(
+                    "[VIDEO] {}: Clip ranges don't apply to image sequences or animations",
+                    self.source_id
+                );
+            }
+            return;
+        }
+        let to_clock = |d: std::time::Duration| gst::ClockTime::from_nseconds(d.as_nanos() as u64);
+        self.clip_start = start.map(to_clock).unwrap_or(gst::ClockTime::ZERO);
+        self.clip_end = end.map(to_clock);
+        if let Some(end) = self.clip_end.filter(|end| *end <= self.clip_start) {
+            tracing::warn!(
+                "[VIDEO] {}: Clip end {} is not after start {}, playing to the end",
+                self.source_id,
+                end,
+                self.clip_start
+            );
+            self.clip_end = None;
+        }
+    }
+
+    fn has_clip(&self) -> bool {
+        self.clip_start > gst::ClockTime::ZERO || self.clip_end.is_some()
+    }
+
     /// Pre-buffer video by setting pipeline to READY state (buffers but doesn't play)
     pub fn prebuffer(&mut self) -> anyhow::Result<()> {
         debug!("[VIDEO] {}: Pre-buffering video pipeline", self.source_id);
@@ -243,6 +443,28 @@ impl VideoPlayer {
             self.pipeline.name()
         );
 
+        // Clip ranges need a prerolled pipeline to seek in, so go through Paused first.
+        // Appsink only emits samples while playing, so the frame at 0 is never shown.
+        if self.has_clip() {
+            self.pipeline.set_state(gst::State::Paused)?;
+            let (preroll, _, _) = self.pipeline.state(gst::ClockTime::from_seconds(5));
+            preroll?;
+            seek_clip(
+                &self.pipeline,
+                gst::SeekFlags::FLUSH | gst::SeekFlags::ACCURATE | gst::SeekFlags::SEGMENT,
+                self.clip_start,
+                self.clip_end,
+            )?;
+            info!(
+                "[VIDEO] {}: Playing clip {} - {}",
+                self.source_id,
+                self.clip_start,
+                self.clip_end
+                    .map(|end| end.to_string())
+                    .unwrap_or_else(|| "end".to_string())
+            );
+        }
+
         // Start pipeline (or transition from Ready to Playing if pre-buffered)
         let ret = self.pipeline.set_state(gst::State::Playing)?;
         let duration = self.start_time.elapsed();
@@ -275,6 +497,7 @@ impl VideoPlayer {
         let is_running = self.is_running.clone();
         let frame_tx = self.frame_tx.clone();
         let source_id = self.source_id.clone();
+        let (clip_start, clip_end) = (self.clip_start, self.clip_end);
         let pool = get_bus_watcher_pool();
         let semaphore = pool.semaphore.clone();
 
@@ -335,21 +558,26 @@ impl VideoPlayer {
                                 info!("[VIDEO] {}: End of Stream reached, looping...", source_id);
                                 // Use segment-based seeking for seamless audio (like gSlapper)
                                 // SEGMENT flag produces gapless looping, FLUSH causes audio gaps
-                                if pipeline
-                                    .seek_simple(
-                                        gst::SeekFlags::FLUSH | gst::SeekFlags::SEGMENT,
-                                        gst::ClockTime::ZERO,
-                                    )
-                                    .is_err()
+                                if seek_clip(
+                                    &pipeline,
+                                    gst::SeekFlags::FLUSH | gst::SeekFlags::SEGMENT,
+                                    clip_start,
+                                    clip_end,
+                                )
+                                .is_err()
                                 {
                                     tracing::error!("Failed to seek to start for loop");
                                 }
                             }
                             MessageView::SegmentDone(..) => {
                                 // Seamless loop restart when using segment-based seeking
-                                if pipeline
-                                    .seek_simple(gst::SeekFlags::SEGMENT, gst::ClockTime::ZERO)
-                                    .is_err()
+                                if seek_clip(
+                                    &pipeline,
+                                    gst::SeekFlags::SEGMENT,
+                                    clip_start,
+                                    clip_end,
+                                )
+                                .is_err()
                                 {
                                     tracing::error!("Failed to segment seek for loop");
                                 }
@@ -386,6 +614,18 @@ impl VideoPlayer {
 
         self.thread_handle = Some(handle);
 
+        if let Some(feed) = self.feed.take() {
+            // Not joined on stop: it ends by itself once the stopped pipeline refuses
+            // the next frame, which may take until the frame being decoded is done
+            let is_running = self.is_running.clone();
+            let frame_tx = self.frame_tx.clone();
+            let source_id = self.source_id.clone();
+            std::thread::spawn(move || match feed {
+                Feed::Sequence(feed) => feed_sequence(feed, is_running, frame_tx, source_id),
+                Feed::Animation(feed) => feed_animation(feed, is_running, frame_tx, source_id),
+            });
+        }
+
         Ok(())
     }
     pub fn stop(&mut self) -> anyhow::Result<()> {
@@ -395,7 +635,9 @@ impl VideoPlayer {
         info!("Stopping video playback...");
 
         // 1. Fade audio to prevent clicks/pops during transition
-        self.pipeline.set_property("volume", 0.0);
+        if !self.sequence {
+            self.pipeline.set_property("volume", 0.0);
+        }
 
         // 2. Signal thread to stop
         self.is_running.store(false, Ordering::SeqCst);
@@ -428,7 +670,10 @@ impl VideoPlayer {
     }
 
     pub fn set_volume(&mut self, volume: f64) {
-        self.pipeline.set_property("volume", volume);
+        // Image sequences and animations have no audio (and their pipeline no volume)
+        if !self.sequence {
+            self.pipeline.set_property("volume", volume);
+        }
     }
 
     pub fn pause(&self) -> anyhow::Result<()> {
@@ -440,6 +685,323 @@ impl VideoPlayer {
         self.pipeline.set_state(gst::State::Playing)?;
         Ok(())
     }
+
+    /// Playback position and length, when the pipeline knows them (not before it
+    /// prerolled; live sources have no length)
+    pub fn progress(&self) -> (Option<std::time::Duration>, Option<std::time::Duration>) {
+        let to_duration = |t: gst::ClockTime| std::time::Duration::from_nanos(t.nseconds());
+        (
+            self.pipeline
+                .query_position::<gst::ClockTime>()
+                .map(to_duration),
+            self.pipeline
+                .query_duration::<gst::ClockTime>()
+                .map(to_duration),
+        )
+    }
+
+    /// Whether the pipeline is paused (or pausing), so it produces no new frames
+    pub fn is_paused(&self) -> bool {
+        let (_, current, pending) = self.pipeline.state(gst::ClockTime::ZERO);
+        match pending {
+            gst::State::VoidPending => current == gst::State::Paused,
+            pending => pending == gst::State::Paused,
+        }
+    }
+}
+
+/// Appsink that hands decoded frames to `frame_tx`, dropping them when the main loop
+/// falls behind. Shared by video files and image sequences. NV12 is taken as decoded
+/// (what hardware and most software decoders produce) and converted on the GPU; anything
+/// else is converted to RGBA by GStreamer.
+fn frame_sink(
+    source_id: Arc<String>,
+    session_id: u64,
+    frame_tx: tokio::sync::mpsc::Sender<(Arc<String>, VideoEvent)>,
+    creation_start: std::time::Instant,
+) -> anyhow::Result<gst_app::AppSink> {
+    // Create appsink for video frames - configure like gSlapper does
+    let appsink = gst::ElementFactory::make("appsink")
+        .name("video-sink")
+        .build()?
+        .downcast::<gst_app::AppSink>()
+        .map_err(|_| anyhow::anyhow!("Failed to downcast to AppSink"))?;
+
+    // NV12 first, so playbin's converter passes decoder output through untouched
+    let caps = gst::Caps::builder("video/x-raw")
+        .field("format", gst::List::new(["NV12", "RGBA"]))
+        .build();
+
+    appsink.set_caps(Some(&caps));
+    appsink.set_sync(true); // Sync to clock
+    appsink.set_drop(true); // Drop frames if late - CRITICAL for preventing buffer accumulation
+    appsink.set_max_buffers(1); // Match gSlapper: 1 buffer to minimize latency and memory
+                                // CRITICAL: Enable emit-signals to get callbacks, but ensure we handle them quickly
+                                // The new_sample callback will be called for each frame
+
+    // Keep source_id for closure
+    let cb_source_id = source_id;
+
+    // Set up new-sample callback
+    let frame_tx_clone = frame_tx;
+    let first_frame_logged = Arc::new(AtomicBool::new(false));
+    let creation_time_ref = creation_start;
+
+    appsink.set_callbacks(
+        gst_app::AppSinkCallbacks::builder()
+            .new_sample(move |sink| {
+                let source_id = cb_source_id.clone();
+
+                if !first_frame_logged.load(Ordering::SeqCst) {
+                    first_frame_logged.store(true, Ordering::SeqCst);
+                    let duration = creation_time_ref.elapsed();
+                    info!("[ASSET] {}: First video frame produced in {:.3}ms", source_id, duration.as_secs_f64() * 1000.0);
+                }
+
+                let session_id = session_id;
+
+                // CRITICAL: Pull sample and extract buffer in explicit scope
+                // This ensures sample is dropped immediately after buffer extraction
+                let (buffer, width, height, stride, nv12) = {
+                    let sample = match sink.pull_sample() {
+                        Ok(s) => s,
+                        Err(_) => return Err(gst::FlowError::Error),
+                    };
+
+                    let buffer = match sample.buffer() {
+                        Some(b) => b.to_owned(),
+                        None => return Err(gst::FlowError::Error),
+                    };
+
+                    let caps = match sample.caps() {
+                        Some(c) => c,
+                        None => return Err(gst::FlowError::Error),
+                    };
+
+                    let video_info = match gst_video::VideoInfo::from_caps(caps) {
+                        Ok(vi) => vi,
+                        Err(_) => return Err(gst::FlowError::Error),
+                    };
+
+                    let width = video_info.width();
+                    let height = video_info.height();
+                    let stride = video_info.stride()[0] as u32;
+                    let nv12 = (video_info.format() == gst_video::VideoFormat::Nv12)
+                        .then(|| Nv12Layout::new(&video_info));
+
+                    // sample is dropped here, releasing GStreamer sample resources
+                    (buffer, width, height, stride, nv12)
+                };
+
+                let frame = VideoFrame {
+                    buffer,
+                    width,
+                    height,
+                    stride,
+                    session_id,
+                    nv12,
+                };
+
+                // Send frame - if channel is full, drop frame immediately to release gst::Buffer
+                match frame_tx_clone.try_send((source_id.clone(), VideoEvent::Frame(frame))) {
+                    Ok(()) => {
+                        // Frame sent successfully
+                    }
+                    Err(tokio::sync::mpsc::error::TrySendError::Full(_)) => {
+                        // CRITICAL: Channel full - drop frame immediately to release gst::Buffer
+                        // This prevents buffer accumulation in GStreamer's internal pool
+                        tracing::warn!("[VIDEO] Frame channel full for {}, dropping frame and releasing buffer", source_id);
+                        // frame is dropped here, releasing the gst::Buffer
+                    }
+                    Err(tokio::sync::mpsc::error::TrySendError::Closed(_)) => {
+                        tracing::warn!("[VIDEO] Frame channel closed for {}, stopping", source_id);
+                        // frame is dropped here
+                        return Err(gst::FlowError::Eos);
+                    }
+                }
+
+                Ok(gst::FlowSuccess::Ok)
+            })
+            .build(),
+    );
+
+    // Configure appsink
+    appsink.set_property("drop", true);
+    appsink.set_property("max-buffers", 1u32);
+
+    Ok(appsink)
+}
+
+/// Pipeline from an appsrc of `size` RGBA frames at `fps` (0/1: variable) into
+/// `frame_sink`, for image sequences and animations
+fn appsrc_pipeline(
+    (width, height): (u32, u32),
+    fps: gst::Fraction,
+    source_id: Arc<String>,
+    session_id: u64,
+    frame_tx: tokio::sync::mpsc::Sender<(Arc<String>, VideoEvent)>,
+    creation_start: std::time::Instant,
+) -> anyhow::Result<(gst::Pipeline, gst_app::AppSrc)> {
+    let caps = gst_video::VideoInfo::builder(gst_video::VideoFormat::Rgba, width, height)
+        .fps(fps)
+        .build()?
+        .to_caps()?;
+    // Blocking: once the ring is full the feeder waits for playback to catch up
+    let appsrc = gst_app::AppSrc::builder()
+        .name("sequence-src")
+        .caps(&caps)
+        .format(gst::Format::Time)
+        .block(true)
+        .max_bytes(SEQUENCE_RING_FRAMES * width as u64 * height as u64 * 4)
+        .build();
+    let appsink = frame_sink(source_id, session_id, frame_tx, creation_start)?;
+
+    let pipeline = gst::Pipeline::with_name("sequence");
+    pipeline.add_many([appsrc.upcast_ref::<gst::Element>(), appsink.upcast_ref()])?;
+    appsrc.link(&appsink)?;
+    Ok((pipeline, appsrc))
+}
+
+/// Pushes the frames of an image sequence into its appsrc, timestamped at `fps` and
+/// looping, until the player stops
+fn feed_sequence(
+    feed: SequenceFeed,
+    is_running: Arc<AtomicBool>,
+    frame_tx: tokio::sync::mpsc::Sender<(Arc<String>, VideoEvent)>,
+    source_id: Arc<String>,
+) {
+    let SequenceFeed {
+        mut reader,
+        appsrc,
+        fps,
+    } = feed;
+    // Timestamps come from the frame count, so they never drift from the frame rate
+    let at = |frame: u64| gst::ClockTime::from_nseconds(frame * 1_000_000_000 / fps as u64);
+    let mut frame = 0u64;
+    while is_running.load(Ordering::SeqCst) {
+        let data = match reader.next_frame() {
+            Ok(data) => data,
+            Err(e) => {
+                tracing::error!("[VIDEO] {}: {:#}", source_id, e);
+                let _ =
+                    frame_tx.blocking_send((source_id.clone(), VideoEvent::Error(e.to_string())));
+                break;
+            }
+        };
+        let mut buffer = gst::Buffer::from_slice(data);
+        {
+            let buffer = buffer.get_mut().expect("a new buffer is writable");
+            buffer.set_pts(at(frame));
+            buffer.set_duration(at(frame + 1) - at(frame));
+        }
+        // Flushing once the pipeline is stopped
+        if appsrc.push_buffer(buffer).is_err() {
+            break;
+        }
+        frame += 1;
+    }
+    debug!("[VIDEO] {}: Sequence feeder exiting", source_id);
+}
+
+/// Pushes the frames of an animation into its appsrc, each timestamped after the
+/// delays before it, until the player stops. Like image sequences, a loop that fits
+/// in the cache budget is decoded once and replayed from memory after that.
+fn feed_animation(
+    feed: AnimationFeed,
+    is_running: Arc<AtomicBool>,
+    frame_tx: tokio::sync::mpsc::Sender<(Arc<String>, VideoEvent)>,
+    source_id: Arc<String>,
+) {
+    let AnimationFeed { animation, appsrc } = feed;
+    let fail = |e: anyhow::Error| {
+        tracing::error!("[VIDEO] {}: {:#}", source_id, e);
+        let _ = frame_tx.blocking_send((source_id.clone(), VideoEvent::Error(e.to_string())));
+    };
+    let (width, height) = animation.size();
+    let frame_size = width as usize * height as usize * 4;
+    let mut pts = gst::ClockTime::ZERO;
+    // Pushes one frame; false once the pipeline is stopped (and flushing)
+    let mut push = |data: Arc<[u8]>, delay: std::time::Duration| {
+        let duration = gst::ClockTime::from_nseconds(delay.as_nanos() as u64);
+        let mut buffer = gst::Buffer::from_slice(data);
+        {
+            let buffer = buffer.get_mut().expect("a new buffer is writable");
+            buffer.set_pts(pts);
+            buffer.set_duration(duration);
+        }
+        pts += duration;
+        appsrc.push_buffer(buffer).is_ok()
+    };
+
+    'passes: while is_running.load(Ordering::SeqCst) {
+        let frames = match animation.frames() {
+            Ok(frames) => frames,
+            Err(e) => {
+                fail(e);
+                break;
+            }
+        };
+        let mut kept = Some(Vec::new());
+        for frame in frames {
+            if !is_running.load(Ordering::SeqCst) {
+                break 'passes;
+            }
+            let frame = match frame {
+                Ok(frame) => frame,
+                Err(e) => {
+                    fail(e.into());
+                    break 'passes;
+                }
+            };
+            let delay = crate::animation::delay(&frame);
+            let data: Arc<[u8]> = frame.into_buffer().into_raw().into();
+            if let Some(frames) = &mut kept {
+                if (frames.len() + 1) * frame_size <= crate::sequence::CACHE_BUDGET {
+                    frames.push((data.clone(), delay));
+                } else {
+                    debug!(
+                        "[VIDEO] {}: Animation exceeds the cache budget, decoding every pass",
+                        source_id
+                    );
+                    kept = None;
+                }
+            }
+            if !push(data, delay) {
+                break 'passes;
+            }
+        }
+        match kept {
+            Some(frames) if frames.is_empty() => {
+                fail(anyhow::anyhow!("Animation has no frames"));
+                break;
+            }
+            Some(frames) => {
+                for (data, delay) in frames.iter().cycle() {
+                    if !is_running.load(Ordering::SeqCst) || !push(data.clone(), *delay) {
+                        break;
+                    }
+                }
+                break;
+            }
+            None => {}
+        }
+    }
+    debug!("[VIDEO] {}: Animation feeder exiting", source_id);
+}
+
+/// Seeks to `start`, stopping (with SEGMENT: posting SegmentDone) at `end` if set
+fn seek_clip(
+    pipeline: &gst::Element,
+    flags: gst::SeekFlags,
+    start: gst::ClockTime,
+    end: Option<gst::ClockTime>,
+) -> Result<(), gst::glib::BoolError> {
+    let stop_type = if end.is_some() {
+        gst::SeekType::Set
+    } else {
+        gst::SeekType::None
+    };
+    pipeline.seek(1.0, flags, gst::SeekType::Set, Some(start), stop_type, end)
 }
 
 impl Drop for VideoPlayer {
@@ -4,10 +4,15 @@ use raw_window_handle::{
 };
 use smithay_client_toolkit::{
     compositor::{CompositorHandler, CompositorState},
-    delegate_compositor, delegate_layer, delegate_output, delegate_registry, delegate_shm,
+    delegate_compositor, delegate_layer, delegate_output, delegate_pointer, delegate_registry,
+    delegate_seat, delegate_shm,
     output::{OutputHandler, OutputState},
     registry::{ProvidesRegistryState, RegistryState},
     registry_handlers,
+    seat::{
+        pointer::{PointerEvent, PointerEventKind, PointerHandler},
+        Capability, SeatHandler, SeatState,
+    },
     shell::{
         wlr_layer::{
             Anchor, Layer, LayerShell, LayerShellHandler, LayerSurface, LayerSurfaceConfigure,
@@ -16,12 +21,23 @@ use smithay_client_toolkit::{
     },
     shm::{Shm, ShmHandler},
 };
+use std::collections::{HashMap, HashSet};
 use std::ptr::NonNull;
 use tracing::info;
 use wayland_client::{
+    backend::ObjectId,
+    event_created_child,
     globals::GlobalList,
-    protocol::{wl_output, wl_surface},
-    Connection, Proxy, QueueHandle,
+    protocol::{wl_output, wl_pointer, wl_seat, wl_surface},
+    Connection, Dispatch, Proxy, QueueHandle, WEnum,
+};
+use wayland_protocols::ext::idle_notify::v1::client::{
+    ext_idle_notification_v1::{self, ExtIdleNotificationV1},
+    ext_idle_notifier_v1::ExtIdleNotifierV1,
+};
+use wayland_protocols_wlr::foreign_toplevel::v1::client::{
+    zwlr_foreign_toplevel_handle_v1::{self, ZwlrForeignToplevelHandleV1},
+    zwlr_foreign_toplevel_manager_v1::{self, ZwlrForeignToplevelManagerV1},
 };
 
 /// Wrapper around LayerSurface that implements raw_window_handle traits
@@ -104,11 +120,44 @@ pub struct WaylandBackend {
     pub output_state: OutputState,
     pub layer_shell: LayerShell,
     pub shm: Shm,
+    pub seat_state: SeatState,
+    /// The seat's pointer, followed for the `iMouse` of shader wallpapers
+    pointer: Option<wl_pointer::WlPointer>,
+    /// Last pointer position over each surface, in surface-local (logical) coordinates
+    pointer_positions: HashMap<String, (f64, f64)>,
     pub surfaces: Vec<(String, LayerSurface)>,
     // (name, width, height, serial)
     pub pending_resizes: Vec<(String, u32, u32, u32)>,
     // Frame callback notifications: surface name -> should render
     pub frame_callback_ready: std::collections::HashSet<String>,
+    /// Integer scale of the output each surface is on, used as its buffer scale
+    pub scales: HashMap<String, i32>,
+    /// Last configured (logical) size per surface, to re-allocate when only the scale changes
+    logical_sizes: HashMap<String, (u32, u32)>,
+    /// Outputs announced by the compositor, for the main loop to set up (hot-plug)
+    pub new_outputs: Vec<wl_output::WlOutput>,
+    /// Windows the compositor reports through wlr-foreign-toplevel, if it supports it
+    toplevels: HashMap<ObjectId, Toplevel>,
+    /// Number of times a window got the focus, to order `Toplevel::last_activated`
+    activations: u64,
+    idle_notifier: Option<ExtIdleNotifierV1>,
+    /// Notification for the `pause-on-idle` timeout being watched, if any
+    idle_notification: Option<(std::time::Duration, ExtIdleNotificationV1)>,
+    /// Whether the session has been idle for the watched timeout
+    pub idle: bool,
+}
+
+/// State of a window, as last completed by a `done` event
+#[derive(Default)]
+struct Toplevel {
+    outputs: Vec<wl_output::WlOutput>,
+    fullscreen: bool,
+    minimized: bool,
+    /// `WaylandBackend::activations` when the window last had the focus (0 = not
+    /// since the daemon started)
+    last_activated: u64,
+    /// Fullscreen, activated and minimized states sent since the last `done`
+    pending: Option<(bool, bool, bool)>,
 }
 
 impl WaylandBackend {
@@ -118,6 +167,17 @@ impl WaylandBackend {
         let layer_shell = LayerShell::bind(globals, qh)?;
         let shm = Shm::bind(globals, qh)?;
         let output_state = OutputState::new(globals, qh);
+        let seat_state = SeatState::new(globals, qh);
+        // Both optional: without them outputs are never covered or idle
+        if globals
+            .bind::<ZwlrForeignToplevelManagerV1, _, _>(qh, 1..=3, ())
+            .is_err()
+        {
+            info!(
+                "[WAYLAND] wlr-foreign-toplevel not available, fullscreen windows are not tracked"
+            );
+        }
+        let idle_notifier = globals.bind::<ExtIdleNotifierV1, _, _>(qh, 1..=1, ()).ok();
 
         Ok(Self {
             registry_state,
@@ -125,40 +185,230 @@ impl WaylandBackend {
             output_state,
             layer_shell,
             shm,
+            seat_state,
+            pointer: None,
+            pointer_positions: HashMap::new(),
             surfaces: Vec::new(),
             pending_resizes: Vec::new(),
             frame_callback_ready: std::collections::HashSet::new(),
+            scales: HashMap::new(),
+            logical_sizes: HashMap::new(),
+            new_outputs: Vec::new(),
+            toplevels: HashMap::new(),
+            activations: 0,
+            idle_notifier,
+            idle_notification: None,
+            idle: false,
         })
     }
 
+    /// Watches for the session going idle for `timeout` (`pause-on-idle`), or stops
+    /// watching with None. Needs ext-idle-notify; without it the session never idles.
+    pub fn watch_idle(&mut self, timeout: Option<std::time::Duration>, qh: &QueueHandle<Self>) {
+        if self.idle_notification.as_ref().map(|(t, _)| *t) == timeout {
+            return;
+        }
+        if let Some((_, notification)) = self.idle_notification.take() {
+            notification.destroy();
+        }
+        self.idle = false;
+        let Some(timeout) = timeout else {
+            return;
+        };
+        let (Some(notifier), Some(seat)) = (&self.idle_notifier, self.seat_state.seats().next())
+        else {
+            tracing::warn!(
+                "[WAYLAND] pause-on-idle needs ext-idle-notify, which the compositor lacks"
+            );
+            return;
+        };
+        let millis = timeout.as_millis().min(u32::MAX as u128) as u32;
+        let notification = notifier.get_idle_notification(millis, &seat, qh, ());
+        self.idle_notification = Some((timeout, notification));
+    }
+
+    /// Names of outputs a fullscreen window covers entirely. The protocol doesn't say
+    /// which windows are on a visible workspace, so an output counts as covered when
+    /// the window on it that had the focus last is fullscreen: a game stays in front
+    /// while another output has the focus, but focusing a window on the game's output
+    /// (after switching its workspace) reveals the wallpaper. Only switching it to an
+    /// empty workspace goes unnoticed.
+    pub fn covered_outputs(&self) -> HashSet<String> {
+        // The window in front of each output
+        let mut front: Vec<(&wl_output::WlOutput, &Toplevel)> = Vec::new();
+        for toplevel in self.toplevels.values() {
+            for output in &toplevel.outputs {
+                match front.iter_mut().find(|(o, _)| *o == output) {
+                    // Among windows never focused, a fullscreen one wins
+                    Some((_, current))
+                        if (toplevel.last_activated, toplevel.fullscreen)
+                            > (current.last_activated, current.fullscreen) =>
+                    {
+                        *current = toplevel;
+                    }
+                    Some(_) => {}
+                    None => front.push((output, toplevel)),
+                }
+            }
+        }
+        front
+            .into_iter()
+            .filter(|(_, t)| t.fullscreen && !t.minimized)
+            .filter_map(|(output, _)| self.output_state.info(output)?.name)
+            .collect()
+    }
+
     pub fn create_wallpaper_surface(
         &mut self,
         output: &wl_output::WlOutput,
         qh: &QueueHandle<Self>,
         name: String,
-        layer: Layer,
+        config: &crate::orchestration::OutputConfig,
     ) -> anyhow::Result<LayerSurface> {
         let wl_surface = self.compositor.create_surface(qh);
+        let layer: Layer = config.layer.clone().into();
 
         let layer_surface = self.layer_shell.create_layer_surface(
             qh,
             wl_surface,
             layer,
-            Some("kaleidux-wallpaper"),
+            Some(config.namespace.clone()),
             Some(output),
         );
 
-        // Match gSlapper initialization
-        layer_surface.set_size(0, 0);
-        layer_surface.set_anchor(Anchor::all());
-        layer_surface.set_exclusive_zone(-1);
+        // Match gSlapper initialization (fullscreen by default, configurable for
+        // partial-screen wallpapers). The renderer sizes itself from the configure event.
+        let (anchor, size) = surface_geometry(&name, config);
+        let [top, right, bottom, left] = config.margin;
+        layer_surface.set_size(size.0, size.1);
+        layer_surface.set_anchor(anchor);
+        layer_surface.set_margin(top, right, bottom, left);
+        layer_surface.set_exclusive_zone(config.exclusive_zone);
+        layer_surface.set_keyboard_interactivity(config.keyboard_interactivity.into());
         layer_surface.commit();
 
+        // Start from the output's advertised scale; the compositor refines it once the
+        // surface has entered the output (scale_factor_changed)
+        let scale = self
+            .output_state
+            .info(output)
+            .map(|info| info.scale_factor.max(1))
+            .unwrap_or(1);
+        self.scales.insert(name.clone(), scale);
+
         // Keep track of them
         self.surfaces.push((name, layer_surface.clone()));
 
         Ok(layer_surface)
     }
+
+    /// Buffer scale for a surface and the physical size of a configured logical size.
+    /// Sets the scale on the wl_surface; it takes effect with the next commit, which is
+    /// the present of the buffer allocated at that size, so the two always match.
+    pub fn apply_buffer_scale(&self, name: &str, width: u32, height: u32) -> (u32, u32) {
+        let scale = self.scales.get(name).copied().unwrap_or(1);
+        if let Some((_, layer_surface)) = self.surfaces.iter().find(|(n, _)| n == name) {
+            let wl_surface = layer_surface.wl_surface();
+            // set_buffer_scale needs wl_surface v3; older compositors upscale as before
+            if wl_surface.version() >= 3 {
+                wl_surface.set_buffer_scale(scale);
+            } else {
+                return (width, height);
+            }
+        }
+        (width * scale as u32, height * scale as u32)
+    }
+
+    /// Name, description, make and model of an output, once the compositor has sent them
+    pub fn output_identity(
+        &self,
+        output: &wl_output::WlOutput,
+    ) -> Option<crate::orchestration::OutputIdentity> {
+        let info = self.output_state.info(output)?;
+        Some(crate::orchestration::OutputIdentity {
+            name: info.name.as_deref().unwrap_or("unknown").to_string(),
+            description: info.description.as_deref().unwrap_or("unknown").to_string(),
+            make: info.make.clone(),
+            model: info.model.clone(),
+        })
+    }
+
+    /// Forgets the surface of an output that went away; it is destroyed once the
+    /// renderer holding the other handle is dropped too
+    pub fn remove_surface(&mut self, name: &str) {
+        self.surfaces.retain(|(n, _)| n != name);
+        self.scales.remove(name);
+        self.logical_sizes.remove(name);
+        self.pointer_positions.remove(name);
+        self.frame_callback_ready.remove(name);
+        self.pending_resizes.retain(|(n, ..)| n != name);
+    }
+
+    /// Last position of the pointer over a surface in buffer pixels, once it has been
+    /// over it (the desktop showing there)
+    pub fn pointer_position(&self, name: &str) -> Option<[f32; 2]> {
+        let (x, y) = *self.pointer_positions.get(name)?;
+        let scale = self.scales.get(name).copied().unwrap_or(1) as f64;
+        Some([(x * scale) as f32, (y * scale) as f32])
+    }
+
+    /// Unmaps (null buffer) or remaps a wallpaper surface for standby.
+    /// Remapping is an initial commit without a buffer; the compositor answers with a
+    /// configure event, which the main loop handles like any other resize.
+    pub fn set_surface_mapped(&self, name: &str, mapped: bool) {
+        if let Some((_, layer_surface)) = self.surfaces.iter().find(|(n, _)| n == name) {
+            let wl_surface = layer_surface.wl_surface();
+            if !mapped {
+                wl_surface.attach(None, 0, 0);
+            }
+            wl_surface.commit();
+            info!(
+                "[STANDBY] {}: Surface {}",
+                name,
+                if mapped { "remapped" } else { "unmapped" }
+            );
+        }
+    }
+}
+
+/// Resolves anchor edges and requested size for a layer surface.
+///
+/// The protocol requires a non-zero size on any axis that isn't anchored on both
+/// sides, otherwise the compositor kills the client. Such configs are stretched
+/// to the full axis instead.
+fn surface_geometry(
+    name: &str,
+    config: &crate::orchestration::OutputConfig,
+) -> (Anchor, (u32, u32)) {
+    use crate::orchestration::Edge;
+
+    let mut anchor = Anchor::empty();
+    for edge in &config.anchor {
+        anchor |= match edge {
+            Edge::Top => Anchor::TOP,
+            Edge::Right => Anchor::RIGHT,
+            Edge::Bottom => Anchor::BOTTOM,
+            Edge::Left => Anchor::LEFT,
+        };
+    }
+
+    let [width, height] = config.size;
+    if width == 0 && !anchor.contains(Anchor::LEFT | Anchor::RIGHT) {
+        tracing::warn!(
+            "[WAYLAND] Output {}: width is 0 but surface is not anchored left and right, stretching horizontally",
+            name
+        );
+        anchor |= Anchor::LEFT | Anchor::RIGHT;
+    }
+    if height == 0 && !anchor.contains(Anchor::TOP | Anchor::BOTTOM) {
+        tracing::warn!(
+            "[WAYLAND] Output {}: height is 0 but surface is not anchored top and bottom, stretching vertically",
+            name
+        );
+        anchor |= Anchor::TOP | Anchor::BOTTOM;
+    }
+
+    (anchor, (width, height))
 }
 
 // Boilerplate delegates for SCTK
@@ -167,12 +417,14 @@ delegate_compositor!(WaylandBackend);
 delegate_output!(WaylandBackend);
 delegate_shm!(WaylandBackend);
 delegate_layer!(WaylandBackend);
+delegate_seat!(WaylandBackend);
+delegate_pointer!(WaylandBackend);
 
 impl ProvidesRegistryState for WaylandBackend {
     fn registry(&mut self) -> &mut RegistryState {
         &mut self.registry_state
     }
-    registry_handlers![OutputState];
+    registry_handlers![OutputState, SeatState];
 }
 
 impl CompositorHandler for WaylandBackend {
@@ -180,9 +432,26 @@ impl CompositorHandler for WaylandBackend {
         &mut self,
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
-        _surface: &wl_surface::WlSurface,
-        _new_factor: i32,
+        surface: &wl_surface::WlSurface,
+        new_factor: i32,
     ) {
+        let Some(name) = self
+            .surfaces
+            .iter()
+            .find(|(_, s)| s.wl_surface() == surface)
+            .map(|(n, _)| n.clone())
+        else {
+            return;
+        };
+        let new_factor = new_factor.max(1);
+        if self.scales.insert(name.clone(), new_factor) == Some(new_factor) {
+            return;
+        }
+        info!("[WAYLAND] {}: Buffer scale is now {}", name, new_factor);
+        // Re-allocate at the new physical size through the normal resize path
+        if let Some(&(width, height)) = self.logical_sizes.get(&name) {
+            self.pending_resizes.push((name, width, height, 0));
+        }
     }
 
     /// Frame callback handler - called when compositor is ready for a new frame
@@ -248,8 +517,9 @@ impl OutputHandler for WaylandBackend {
         &mut self,
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
-        _output: wl_output::WlOutput,
+        output: wl_output::WlOutput,
     ) {
+        self.new_outputs.push(output);
     }
     fn update_output(
         &mut self,
@@ -319,6 +589,7 @@ impl LayerShellHandler for WaylandBackend {
 
         // Store resize for main loop
         if name != "unknown" {
+            self.logical_sizes.insert(name.clone(), (width, height));
             self.pending_resizes.push((name, width, height, serial));
         }
     }
@@ -329,3 +600,167 @@ impl ShmHandler for WaylandBackend {
         &mut self.shm
     }
 }
+
+impl SeatHandler for WaylandBackend {
+    fn seat_state(&mut self) -> &mut SeatState {
+        &mut self.seat_state
+    }
+    fn new_seat(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _seat: wl_seat::WlSeat) {}
+    fn new_capability(
+        &mut self,
+        _conn: &Connection,
+        qh: &QueueHandle<Self>,
+        seat: wl_seat::WlSeat,
+        capability: Capability,
+    ) {
+        if capability == Capability::Pointer && self.pointer.is_none() {
+            match self.seat_state.get_pointer(qh, &seat) {
+                Ok(pointer) => self.pointer = Some(pointer),
+                Err(e) => tracing::debug!("[WAYLAND] Failed to get the seat's pointer: {}", e),
+            }
+        }
+    }
+    fn remove_capability(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _seat: wl_seat::WlSeat,
+        capability: Capability,
+    ) {
+        if capability == Capability::Pointer {
+            if let Some(pointer) = self.pointer.take() {
+                if pointer.version() >= 3 {
+                    pointer.release();
+                }
+            }
+        }
+    }
+    fn remove_seat(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _seat: wl_seat::WlSeat) {
+    }
+}
+
+impl PointerHandler for WaylandBackend {
+    fn pointer_frame(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _pointer: &wl_pointer::WlPointer,
+        events: &[PointerEvent],
+    ) {
+        for event in events {
+            if !matches!(
+                event.kind,
+                PointerEventKind::Enter { .. } | PointerEventKind::Motion { .. }
+            ) {
+                continue;
+            }
+            if let Some((name, _)) = self
+                .surfaces
+                .iter()
+                .find(|(_, s)| s.wl_surface() == &event.surface)
+            {
+                self.pointer_positions.insert(name.clone(), event.position);
+            }
+        }
+    }
+}
+
+impl Dispatch<ZwlrForeignToplevelManagerV1, ()> for WaylandBackend {
+    fn event(
+        state: &mut Self,
+        _manager: &ZwlrForeignToplevelManagerV1,
+        event: zwlr_foreign_toplevel_manager_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwlr_foreign_toplevel_manager_v1::Event::Toplevel { toplevel } => {
+                state.toplevels.insert(toplevel.id(), Toplevel::default());
+            }
+            zwlr_foreign_toplevel_manager_v1::Event::Finished => state.toplevels.clear(),
+            _ => {}
+        }
+    }
+
+    event_created_child!(WaylandBackend, ZwlrForeignToplevelManagerV1, [
+        zwlr_foreign_toplevel_manager_v1::EVT_TOPLEVEL_OPCODE => (ZwlrForeignToplevelHandleV1, ())
+    ]);
+}
+
+impl Dispatch<ZwlrForeignToplevelHandleV1, ()> for WaylandBackend {
+    fn event(
+        state: &mut Self,
+        handle: &ZwlrForeignToplevelHandleV1,
+        event: zwlr_foreign_toplevel_handle_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        use zwlr_foreign_toplevel_handle_v1::{Event, State};
+        if let Event::Closed = event {
+            state.toplevels.remove(&handle.id());
+            handle.destroy();
+            return;
+        }
+        let Some(toplevel) = state.toplevels.get_mut(&handle.id()) else {
+            return;
+        };
+        match event {
+            Event::OutputEnter { output } => toplevel.outputs.push(output),
+            Event::OutputLeave { output } => toplevel.outputs.retain(|o| o != &output),
+            Event::State { state: raw } => {
+                let states: Vec<WEnum<State>> = raw
+                    .chunks_exact(4)
+                    .map(|c| WEnum::from(u32::from_ne_bytes([c[0], c[1], c[2], c[3]])))
+                    .collect();
+                let has = |s: State| states.contains(&WEnum::Value(s));
+                toplevel.pending = Some((
+                    has(State::Fullscreen),
+                    has(State::Activated),
+                    has(State::Minimized),
+                ));
+            }
+            Event::Done => {
+                if let Some((fullscreen, activated, minimized)) = toplevel.pending.take() {
+                    toplevel.fullscreen = fullscreen;
+                    toplevel.minimized = minimized;
+                    if activated {
+                        state.activations += 1;
+                        toplevel.last_activated = state.activations;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ExtIdleNotifierV1, ()> for WaylandBackend {
+    fn event(
+        _state: &mut Self,
+        _notifier: &ExtIdleNotifierV1,
+        _event: <ExtIdleNotifierV1 as Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ExtIdleNotificationV1, ()> for WaylandBackend {
+    fn event(
+        state: &mut Self,
+        _notification: &ExtIdleNotificationV1,
+        event: ext_idle_notification_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            ext_idle_notification_v1::Event::Idled => state.idle = true,
+            ext_idle_notification_v1::Event::Resumed => state.idle = false,
+            _ => {}
+        }
+    }
+}
@@ -5,10 +5,11 @@ use raw_window_handle::{
 use std::collections::HashMap;
 use std::ptr::NonNull;
 use std::sync::Arc;
-use tracing::info;
-use x11rb::connection::Connection;
+use tracing::{info, warn};
+use x11rb::connection::{Connection, RequestConnection};
 use x11rb::protocol::xproto::{
-    Atom, ConnectionExt, CreateWindowAux, EventMask, PropMode, Window, WindowClass,
+    Atom, AtomEnum, ChangeWindowAttributesAux, CloseDown, ConnectionExt, CreateGCAux,
+    CreateWindowAux, EventMask, ImageFormat, ImageOrder, PropMode, Rectangle, Window, WindowClass,
 };
 use x11rb::wrapper::ConnectionExt as _;
 use x11rb::xcb_ffi::XCBConnection;
@@ -16,15 +17,25 @@ use x11rb::xcb_ffi::XCBConnection;
 /// X11 Backend handling connection and window management
 pub struct X11Backend {
     pub conn: Arc<XCBConnection>,
-    pub screen_num: usize,
-    pub root: Window,
+    /// Root window of every X screen (`:0.0`, `:0.1`, ...), indexed by screen number
+    pub roots: Vec<Window>,
     pub windows: HashMap<String, Window>,
     pub atoms: Atoms,
-    #[allow(clippy::type_complexity)]
-    pub cached_monitors: parking_lot::Mutex<Option<Vec<(String, i16, i16, u16, u16)>>>,
+    pub cached_monitors: parking_lot::Mutex<Option<Vec<X11Monitor>>>,
     pub monitors_dirty: std::sync::atomic::AtomicBool,
 }
 
+/// A RandR monitor (or a whole X screen without RandR), positioned on its screen's root
+#[derive(Clone, Debug)]
+pub struct X11Monitor {
+    pub name: String,
+    pub screen: usize,
+    pub x: i16,
+    pub y: i16,
+    pub width: u16,
+    pub height: u16,
+}
+
 pub struct Atoms {
     pub _net_wm_window_type: Atom,
     pub _net_wm_window_type_desktop: Atom,
@@ -33,6 +44,8 @@ pub struct Atoms {
     pub _net_wm_state_below: Atom,
     pub _net_wm_state_sticky: Atom,
     pub _net_wm_state_skip_taskbar: Atom,
+    pub _kaleidux_state: Atom,
+    pub utf8_string: Atom,
 }
 
 impl X11Backend {
@@ -41,8 +54,14 @@ impl X11Backend {
         let (conn, screen_num) = XCBConnection::connect(None)?;
         let conn = Arc::new(conn);
 
-        let screen = &conn.setup().roots[screen_num];
-        let root = screen.root;
+        let roots: Vec<Window> = conn.setup().roots.iter().map(|s| s.root).collect();
+        if roots.len() > 1 {
+            info!(
+                "[X11] Display has {} screens (default {}), covering all of them",
+                roots.len(),
+                screen_num
+            );
+        }
 
         // Intern atoms
         let _net_wm_window_type = conn
@@ -70,19 +89,22 @@ impl X11Backend {
             .intern_atom(false, b"_NET_WM_STATE_SKIP_TASKBAR")?
             .reply()?
             .atom;
+        let _kaleidux_state = conn.intern_atom(false, b"_KALEIDUX_STATE")?.reply()?.atom;
+        let utf8_string = conn.intern_atom(false, b"UTF8_STRING")?.reply()?.atom;
 
-        // Subscribe to RandR events
+        // Subscribe to RandR events on every screen
         use x11rb::protocol::randr::ConnectionExt as RandrExt;
-        let _ = conn.randr_select_input(
-            root,
-            x11rb::protocol::randr::NotifyMask::OUTPUT_CHANGE
-                | x11rb::protocol::randr::NotifyMask::CRTC_CHANGE,
-        );
+        for &root in &roots {
+            let _ = conn.randr_select_input(
+                root,
+                x11rb::protocol::randr::NotifyMask::OUTPUT_CHANGE
+                    | x11rb::protocol::randr::NotifyMask::CRTC_CHANGE,
+            );
+        }
 
         Ok(Self {
             conn,
-            screen_num,
-            root,
+            roots,
             windows: HashMap::new(),
             atoms: Atoms {
                 _net_wm_window_type,
@@ -92,16 +114,18 @@ impl X11Backend {
                 _net_wm_state_below,
                 _net_wm_state_sticky,
                 _net_wm_state_skip_taskbar,
+                _kaleidux_state,
+                utf8_string,
             },
             cached_monitors: parking_lot::Mutex::new(None),
             monitors_dirty: std::sync::atomic::AtomicBool::new(true),
         })
     }
 
-    #[allow(clippy::type_complexity)]
-    pub fn get_monitors(&self) -> anyhow::Result<Vec<(String, i16, i16, u16, u16)>> {
-        use x11rb::protocol::randr::ConnectionExt as RandrExt;
-
+    /// Monitors on all X screens. Classic multi-screen setups (separate `:0.N` screens
+    /// without Xinerama) have their own RandR resources per screen, so each root is
+    /// queried on its own.
+    pub fn get_monitors(&self) -> anyhow::Result<Vec<X11Monitor>> {
         // Fast path: return cache if not dirty
         if !self
             .monitors_dirty
@@ -112,9 +136,42 @@ impl X11Backend {
             }
         }
 
+        let mut monitors: Vec<X11Monitor> = Vec::new();
+        for screen in 0..self.roots.len() {
+            let found = match self.screen_monitors(screen) {
+                Ok(found) => found,
+                Err(e) => {
+                    warn!("[X11] RandR query failed on screen {}: {}", screen, e);
+                    Vec::new()
+                }
+            };
+            for mut monitor in found {
+                // Output names are usually unique per display, but drivers that run
+                // separate screens may reuse them
+                if monitors.iter().any(|m| m.name == monitor.name) {
+                    monitor.name = format!("{}-{}", monitor.name, screen);
+                }
+                monitors.push(monitor);
+            }
+        }
+
+        // Update cache
+        {
+            let mut cache = self.cached_monitors.lock();
+            *cache = Some(monitors.clone());
+            self.monitors_dirty
+                .store(false, std::sync::atomic::Ordering::SeqCst);
+        }
+
+        Ok(monitors)
+    }
+
+    fn screen_monitors(&self, screen: usize) -> anyhow::Result<Vec<X11Monitor>> {
+        use x11rb::protocol::randr::ConnectionExt as RandrExt;
+
         let screen_res = self
             .conn
-            .randr_get_screen_resources_current(self.root)?
+            .randr_get_screen_resources_current(self.roots[screen])?
             .reply()?;
         let mut monitors = Vec::new();
 
@@ -138,48 +195,43 @@ impl X11Backend {
                 name = String::from_utf8_lossy(&output_info.name).to_string();
             }
 
-            monitors.push((
+            monitors.push(X11Monitor {
                 name,
-                crtc_info.x,
-                crtc_info.y,
-                crtc_info.width,
-                crtc_info.height,
-            ));
+                screen,
+                x: crtc_info.x,
+                y: crtc_info.y,
+                width: crtc_info.width,
+                height: crtc_info.height,
+            });
         }
 
-        // Fallback if no RandR monitors found (rare/failsafe)
+        // Fallback if no RandR monitors found (rare/failsafe, or a screen RandR doesn't drive)
         if monitors.is_empty() {
-            let screen = &self.conn.setup().roots[self.screen_num];
-            monitors.push((
-                "X11-0".to_string(),
-                0,
-                0,
-                screen.width_in_pixels,
-                screen.height_in_pixels,
-            ));
-        }
-
-        // Update cache
-        {
-            let mut cache = self.cached_monitors.lock();
-            *cache = Some(monitors.clone());
-            self.monitors_dirty
-                .store(false, std::sync::atomic::Ordering::SeqCst);
+            let info = &self.conn.setup().roots[screen];
+            monitors.push(X11Monitor {
+                name: format!("X11-{}", screen),
+                screen,
+                x: 0,
+                y: 0,
+                width: info.width_in_pixels,
+                height: info.height_in_pixels,
+            });
         }
 
         Ok(monitors)
     }
 
-    pub fn create_wallpaper_window(
-        &mut self,
-        name: &str,
-        x: i16,
-        y: i16,
-        width: u16,
-        height: u16,
-    ) -> anyhow::Result<Window> {
+    pub fn create_wallpaper_window(&mut self, monitor: &X11Monitor) -> anyhow::Result<Window> {
+        let X11Monitor {
+            ref name,
+            x,
+            y,
+            width,
+            height,
+            ..
+        } = *monitor;
         let win_id = self.conn.generate_id()?;
-        let screen = &self.conn.setup().roots[self.screen_num];
+        let screen = &self.conn.setup().roots[monitor.screen];
 
         // Setup window attributes
         // REMOVED override_redirect(1) to let WM handle stacking (keeping it below apps)
@@ -190,7 +242,7 @@ impl X11Backend {
         self.conn.create_window(
             x11rb::COPY_DEPTH_FROM_PARENT,
             win_id,
-            self.root,
+            screen.root,
             x,
             y,
             width,
@@ -252,12 +304,217 @@ impl X11Backend {
         self.windows.insert(name.to_string(), win_id);
 
         info!(
-            "Created X11 wallpaper window for {}: id={}, rect={}x{}@{},{}",
-            name, win_id, width, height, x, y
+            "Created X11 wallpaper window for {}: id={}, screen={}, rect={}x{}@{},{}",
+            name, win_id, monitor.screen, width, height, x, y
         );
 
         Ok(win_id)
     }
+
+    /// Unmaps or maps a wallpaper window for standby
+    pub fn set_window_mapped(&self, name: &str, mapped: bool) -> anyhow::Result<()> {
+        let Some(&win) = self.windows.get(name) else {
+            return Ok(());
+        };
+        if mapped {
+            self.conn.map_window(win)?;
+            self.conn.configure_window(
+                win,
+                &x11rb::protocol::xproto::ConfigureWindowAux::new()
+                    .stack_mode(x11rb::protocol::xproto::StackMode::BELOW),
+            )?;
+        } else {
+            self.conn.unmap_window(win)?;
+        }
+        self.conn.flush()?;
+        info!(
+            "[STANDBY] {}: Window {}",
+            name,
+            if mapped { "mapped" } else { "unmapped" }
+        );
+        Ok(())
+    }
+
+    /// Pointer position relative to an output's window in pixels, for the `iMouse` of
+    /// shader wallpapers. None while the pointer is on another screen.
+    pub fn pointer_position(&self, name: &str) -> Option<[f32; 2]> {
+        let &win = self.windows.get(name)?;
+        let reply = self.conn.query_pointer(win).ok()?.reply().ok()?;
+        reply
+            .same_screen
+            .then_some([reply.win_x as f32, reply.win_y as f32])
+    }
+
+    /// Publishes `json` (see `publish-state`) as the `_KALEIDUX_STATE` property of every
+    /// root window, or removes it with None
+    pub fn set_state(&self, json: Option<&str>) -> anyhow::Result<()> {
+        for &root in &self.roots {
+            match json {
+                Some(json) => {
+                    self.conn.change_property8(
+                        PropMode::REPLACE,
+                        root,
+                        self.atoms._kaleidux_state,
+                        self.atoms.utf8_string,
+                        json.as_bytes(),
+                    )?;
+                }
+                None => {
+                    self.conn
+                        .delete_property(root, self.atoms._kaleidux_state)?;
+                }
+            }
+        }
+        self.conn.flush()?;
+        Ok(())
+    }
+}
+
+/// Copies each monitor's frame into a new pixmap of its screen's root size (the rest kept
+/// from the current root pixmap), makes it the root background and points
+/// `_XROOTPMAP_ID`/`ESETROOT_PMAP_ID` at it, for pseudo-transparent terminals. Like
+/// other setters it is made on its own connection kept with RetainPermanent, and the
+/// previous setter's pixmap is freed with KillClient.
+pub fn set_root_pixmap(frames: &[(X11Monitor, &image::RgbaImage)]) -> anyhow::Result<()> {
+    let (conn, _) = XCBConnection::connect(None)?;
+    let xrootpmap = conn.intern_atom(false, b"_XROOTPMAP_ID")?.reply()?.atom;
+    let esetroot = conn.intern_atom(false, b"ESETROOT_PMAP_ID")?.reply()?.atom;
+    let mut screens: Vec<usize> = frames.iter().map(|(m, _)| m.screen).collect();
+    screens.sort_unstable();
+    screens.dedup();
+
+    for screen_num in screens {
+        let screen = &conn.setup().roots[screen_num];
+        let (root, width, height) = (screen.root, screen.width_in_pixels, screen.height_in_pixels);
+        let bpp = conn
+            .setup()
+            .pixmap_formats
+            .iter()
+            .find(|f| f.depth == screen.root_depth)
+            .map(|f| f.bits_per_pixel);
+        if bpp != Some(32) {
+            anyhow::bail!(
+                "Screen {} has depth {} ({:?} bits per pixel), only 24/32-bit is supported",
+                screen_num,
+                screen.root_depth,
+                bpp
+            );
+        }
+        let root_property = |atom| -> Option<u32> {
+            let reply = conn
+                .get_property(false, root, atom, AtomEnum::PIXMAP, 0, 1)
+                .ok()?
+                .reply()
+                .ok()?;
+            let pixmap = reply.value32()?.next();
+            pixmap
+        };
+        let old = root_property(xrootpmap);
+        let old_setter = root_property(esetroot);
+
+        let pixmap = conn.generate_id()?;
+        conn.create_pixmap(screen.root_depth, pixmap, root, width, height)?;
+        let gc = conn.generate_id()?;
+        conn.create_gc(
+            gc,
+            pixmap,
+            &CreateGCAux::new().foreground(screen.black_pixel),
+        )?;
+        // Outputs not in `frames` keep what the previous root pixmap shows
+        let old_fits = old.is_some_and(|old| {
+            conn.get_geometry(old)
+                .ok()
+                .and_then(|c| c.reply().ok())
+                .is_some_and(|g| {
+                    g.width == width && g.height == height && g.depth == screen.root_depth
+                })
+        });
+        match old {
+            Some(old) if old_fits => {
+                conn.copy_area(old, pixmap, gc, 0, 0, 0, 0, width, height)?;
+            }
+            _ => {
+                conn.poly_fill_rectangle(
+                    pixmap,
+                    gc,
+                    &[Rectangle {
+                        x: 0,
+                        y: 0,
+                        width,
+                        height,
+                    }],
+                )?;
+            }
+        }
+        for (monitor, frame) in frames.iter().filter(|(m, _)| m.screen == screen_num) {
+            put_frame(&conn, pixmap, gc, screen.root_depth, monitor, frame)?;
+        }
+        conn.free_gc(gc)?;
+
+        conn.change_window_attributes(
+            root,
+            &ChangeWindowAttributesAux::new().background_pixmap(pixmap),
+        )?;
+        conn.clear_area(false, root, 0, 0, 0, 0)?;
+        for atom in [xrootpmap, esetroot] {
+            conn.change_property32(PropMode::REPLACE, root, atom, AtomEnum::PIXMAP, &[pixmap])?;
+        }
+        // Frees the previous setter's pixmap (ours included), as feh and hsetroot do
+        if let Some(old) = old_setter.filter(|&id| id != 0 && Some(id) == old) {
+            let _ = conn.kill_client(old)?.check();
+        }
+    }
+    conn.set_close_down_mode(CloseDown::RETAIN_PERMANENT)?;
+    conn.sync()?;
+    Ok(())
+}
+
+/// Uploads `frame` to the monitor's area of `pixmap`, in strips that fit a request
+fn put_frame(
+    conn: &XCBConnection,
+    pixmap: u32,
+    gc: u32,
+    depth: u8,
+    monitor: &X11Monitor,
+    frame: &image::RgbaImage,
+) -> anyhow::Result<()> {
+    let width = frame.width().min(monitor.width as u32) as usize;
+    let height = frame.height().min(monitor.height as u32) as usize;
+    if width == 0 || height == 0 {
+        return Ok(());
+    }
+    // 32-bit ZPixmap pixels are 0xXXRRGGBB in the server's byte order
+    let lsb_first = conn.setup().image_byte_order == ImageOrder::LSB_FIRST;
+    let row_bytes = width * 4;
+    let rows_per_request = ((conn.maximum_request_bytes() - 64) / row_bytes).clamp(1, height);
+    let mut data = Vec::with_capacity(rows_per_request * row_bytes);
+    for first_row in (0..height).step_by(rows_per_request) {
+        let rows = rows_per_request.min(height - first_row);
+        data.clear();
+        for y in first_row..first_row + rows {
+            for x in 0..width {
+                let [r, g, b, _] = frame.get_pixel(x as u32, y as u32).0;
+                data.extend_from_slice(&if lsb_first {
+                    [b, g, r, 0]
+                } else {
+                    [0, r, g, b]
+                });
+            }
+        }
+        conn.put_image(
+            ImageFormat::Z_PIXMAP,
+            pixmap,
+            gc,
+            width as u16,
+            rows as u16,
+            monitor.x,
+            monitor.y + first_row as i16,
+            0,
+            depth,
+            &data,
+        )?;
+    }
+    Ok(())
 }
 
 /// Wrapper for RawWindowHandle for wgpu
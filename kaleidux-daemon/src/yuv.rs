@@ -0,0 +1,262 @@
+use crate::renderer::WgpuContext;
+use crate::video::{Nv12Layout, VideoFrame};
+
+/// Format of the textures NV12 frames are converted into (same as RGBA video frames)
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+/// Converts NV12 video frames to RGBA on the GPU, so decoded frames are uploaded as
+/// they come out of the decoder instead of being converted on the CPU first
+pub struct YuvPass {
+    bind_group_layout: wgpu::BindGroupLayout,
+    pipeline: wgpu::RenderPipeline,
+    sampler: wgpu::Sampler,
+}
+
+impl YuvPass {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("YUV Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shaders/yuv.wgsl").into()),
+        });
+
+        let plane = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("YUV Bind Group Layout"),
+            entries: &[
+                plane(0),
+                plane(1),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("YUV Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("YUV Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: FORMAT,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        // Chroma is half resolution; linear filtering upsamples it
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("YUV Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        Self {
+            bind_group_layout,
+            pipeline,
+            sampler,
+        }
+    }
+
+    /// Draws the planes last uploaded to `planes` into the first mip level of `target`,
+    /// which must be the frame's size and made with RENDER_ATTACHMENT
+    pub fn run(&self, ctx: &WgpuContext, planes: &Nv12Planes, target: &wgpu::Texture) {
+        let target_view = target.create_view(&wgpu::TextureViewDescriptor {
+            format: Some(FORMAT),
+            base_mip_level: 0,
+            mip_level_count: Some(1),
+            ..Default::default()
+        });
+        let mut encoder = ctx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("YUV Encoder"),
+            });
+        {
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("YUV Render Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &target_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            rpass.set_pipeline(&self.pipeline);
+            rpass.set_bind_group(0, &planes.bind_group, &[]);
+            rpass.draw(0..3, 0..1);
+        }
+        ctx.queue.submit(Some(encoder.finish()));
+    }
+}
+
+/// Plane textures of one output's NV12 video, reused while the frame size stays
+pub struct Nv12Planes {
+    y: wgpu::Texture,
+    uv: wgpu::Texture,
+    uniforms: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+}
+
+impl Nv12Planes {
+    pub fn new(ctx: &WgpuContext, pass: &YuvPass, width: u32, height: u32) -> Self {
+        let plane = |label, width, height, format| {
+            ctx.device.create_texture(&wgpu::TextureDescriptor {
+                label: Some(label),
+                size: wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            })
+        };
+        let y = plane("Y Plane", width, height, wgpu::TextureFormat::R8Unorm);
+        let uv = plane(
+            "UV Plane",
+            width.div_ceil(2),
+            height.div_ceil(2),
+            wgpu::TextureFormat::Rg8Unorm,
+        );
+        let uniforms = ctx.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("YUV Uniforms"),
+            size: std::mem::size_of::<[[f32; 4]; 3]>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("YUV Bind Group"),
+            layout: &pass.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(
+                        &y.create_view(&wgpu::TextureViewDescriptor::default()),
+                    ),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(
+                        &uv.create_view(&wgpu::TextureViewDescriptor::default()),
+                    ),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&pass.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: uniforms.as_entire_binding(),
+                },
+            ],
+        });
+        Self {
+            y,
+            uv,
+            uniforms,
+            bind_group,
+        }
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.y.width(), self.y.height())
+    }
+
+    /// Uploads both planes of `frame`, whose mapped buffer is `data`, straight from the
+    /// decoder's layout. False if the buffer is too short for that layout.
+    pub fn upload(
+        &self,
+        ctx: &WgpuContext,
+        frame: &VideoFrame,
+        layout: &Nv12Layout,
+        data: &[u8],
+    ) -> bool {
+        // Last byte each plane reaches: full rows but the last, which may be unpadded
+        let plane_end = |texture: &wgpu::Texture, offset: usize, stride: u32, texel: u32| {
+            offset
+                + (stride as usize * (texture.height() as usize - 1))
+                + (texture.width() * texel) as usize
+        };
+        if plane_end(&self.y, layout.y_offset, frame.stride, 1) > data.len()
+            || plane_end(&self.uv, layout.uv_offset, layout.uv_stride, 2) > data.len()
+        {
+            return false;
+        }
+        let write = |texture: &wgpu::Texture, offset: usize, stride: u32| {
+            ctx.queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All,
+                },
+                data,
+                wgpu::ImageDataLayout {
+                    offset: offset as u64,
+                    bytes_per_row: Some(stride),
+                    rows_per_image: Some(texture.height()),
+                },
+                texture.size(),
+            );
+        };
+        write(&self.y, layout.y_offset, frame.stride);
+        write(&self.uv, layout.uv_offset, layout.uv_stride);
+        ctx.queue
+            .write_buffer(&self.uniforms, 0, bytemuck::cast_slice(&layout.to_rgb));
+        true
+    }
+}
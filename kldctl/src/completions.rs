@@ -0,0 +1,471 @@
+use clap::{Arg, Command};
+use std::fmt::Write;
+
+/// Shells `kldctl completions` can generate a script for
+#[derive(Clone, Copy, clap::ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+/// Values the daemon is asked for while completing (`kldctl __complete KIND`)
+#[derive(Clone, Copy, clap::ValueEnum)]
+pub enum Dynamic {
+    Outputs,
+    Playlists,
+    Sessions,
+}
+
+/// What completes an option value or positional argument
+#[derive(Clone)]
+enum Values {
+    None,
+    Files,
+    Choices(Vec<String>),
+    Daemon(&'static str),
+}
+
+struct Opt {
+    short: Option<char>,
+    long: Option<String>,
+    help: String,
+    /// None for flags
+    values: Option<Values>,
+}
+
+struct Positional {
+    name: String,
+    required: bool,
+    values: Values,
+}
+
+/// One command of the tree; `path` is empty for kldctl itself
+struct Node {
+    path: Vec<String>,
+    aliases: Vec<String>,
+    about: String,
+    opts: Vec<Opt>,
+    positionals: Vec<Positional>,
+    children: Vec<Node>,
+}
+
+impl Node {
+    fn new(cmd: &Command, path: Vec<String>) -> Self {
+        let mut opts = Vec::new();
+        let mut positionals = Vec::new();
+        for arg in cmd.get_arguments().filter(|a| !a.is_hide_set()) {
+            let values = values_for(&path, arg);
+            if arg.is_positional() {
+                positionals.push(Positional {
+                    name: arg.get_id().to_string(),
+                    required: arg.is_required_set(),
+                    values,
+                });
+            } else {
+                opts.push(Opt {
+                    short: arg.get_short(),
+                    long: arg.get_long().map(str::to_string),
+                    help: one_line(arg.get_help()),
+                    values: arg.get_action().takes_values().then_some(values),
+                });
+            }
+        }
+        // The generated `help` command mirrors the whole tree; it is completed as a leaf
+        let children = if cmd.get_name() == "help" && !path.is_empty() {
+            Vec::new()
+        } else {
+            cmd.get_subcommands()
+                .filter(|c| !c.is_hide_set())
+                .map(|c| {
+                    let mut child_path = path.clone();
+                    child_path.push(c.get_name().to_string());
+                    Node::new(c, child_path)
+                })
+                .collect()
+        };
+        Self {
+            aliases: cmd.get_visible_aliases().map(str::to_string).collect(),
+            about: one_line(cmd.get_about()),
+            path,
+            opts,
+            positionals,
+            children,
+        }
+    }
+
+    fn name(&self) -> &str {
+        self.path.last().map_or("kldctl", String::as_str)
+    }
+
+    /// Identifier used for shell functions and case labels, e.g. `kldctl__playlist__load`
+    fn ident(&self) -> String {
+        std::iter::once("kldctl")
+            .chain(self.path.iter().map(|p| p.as_str()))
+            .collect::<Vec<_>>()
+            .join("__")
+            .replace('-', "_")
+    }
+
+    fn names(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.name()).chain(self.aliases.iter().map(String::as_str))
+    }
+
+    fn walk<'a>(&'a self, nodes: &mut Vec<&'a Node>) {
+        nodes.push(self);
+        for child in &self.children {
+            child.walk(nodes);
+        }
+    }
+}
+
+/// Outputs, playlists and sessions come from the daemon; paths complete as files
+fn values_for(path: &[String], arg: &Arg) -> Values {
+    let choices: Vec<String> = arg
+        .get_possible_values()
+        .iter()
+        .filter(|v| !v.is_hide_set())
+        .map(|v| v.get_name().to_string())
+        .collect();
+    if !choices.is_empty() {
+        return Values::Choices(choices);
+    }
+    let in_playlist = path.first().is_some_and(|p| p == "playlist");
+    let in_session = path.first().is_some_and(|p| p == "session");
+    let in_transition = path.first().is_some_and(|p| p == "transition");
+    match arg.get_id().as_str() {
+        "output" => Values::Daemon("outputs"),
+        // `playlist create` names a new playlist
+        "name" if in_playlist && path.last().is_some_and(|p| p != "create") => {
+            Values::Daemon("playlists")
+        }
+        // `session save` may name a new session too
+        "name" if in_session => Values::Daemon("sessions"),
+        // `set --transition` is one switch, `transition set` lasts
+        "transition" => transitions(&["cut", "random"]),
+        "name" if in_transition => transitions(&["random", "auto"]),
+        "socket" | "path" | "file" => Values::Files,
+        _ => Values::None,
+    }
+}
+
+/// Builtin transition names after the special ones in `extra`
+fn transitions(extra: &[&str]) -> Values {
+    Values::Choices(
+        extra
+            .iter()
+            .chain(kaleidux_common::RANDOM_TRANSITIONS)
+            .map(|name| name.to_string())
+            .collect(),
+    )
+}
+
+fn one_line(text: Option<&clap::builder::StyledStr>) -> String {
+    text.map(|t| t.to_string())
+        .unwrap_or_default()
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_string()
+}
+
+pub fn generate(shell: Shell, mut cmd: Command) -> String {
+    cmd.build();
+    let root = Node::new(&cmd, Vec::new());
+    match shell {
+        Shell::Bash => bash(&root),
+        Shell::Zsh => zsh(&root),
+        Shell::Fish => fish(&root),
+    }
+}
+
+fn bash_values(values: &Values) -> String {
+    match values {
+        Values::None => "COMPREPLY=()".to_string(),
+        Values::Files => "COMPREPLY=($(compgen -f -- \"$cur\"))".to_string(),
+        Values::Choices(choices) => {
+            format!(
+                "COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))",
+                choices.join(" ")
+            )
+        }
+        Values::Daemon(kind) => format!(
+            "COMPREPLY=($(compgen -W \"$(kldctl __complete {} 2>/dev/null)\" -- \"$cur\"))",
+            kind
+        ),
+    }
+}
+
+fn bash(root: &Node) -> String {
+    let mut nodes = Vec::new();
+    root.walk(&mut nodes);
+    let mut out = String::new();
+
+    out.push_str("_kldctl() {\n");
+    out.push_str(
+        "    local cur=\"${COMP_WORDS[COMP_CWORD]}\" prev=\"${COMP_WORDS[COMP_CWORD-1]}\"\n",
+    );
+    out.push_str("    local cmd=kldctl skip=0 i\n");
+    // Find the (sub)command being completed, skipping option values
+    out.push_str("    for ((i = 1; i < COMP_CWORD; i++)); do\n");
+    out.push_str("        if ((skip)); then skip=0; continue; fi\n");
+    out.push_str("        case \"$cmd,${COMP_WORDS[i]}\" in\n");
+    for node in &nodes {
+        let ident = node.ident();
+        let takes_value: Vec<String> = node
+            .opts
+            .iter()
+            .filter(|o| o.values.is_some())
+            .flat_map(bash_flags)
+            .map(|f| format!("\"{},{}\"", ident, f))
+            .collect();
+        if !takes_value.is_empty() {
+            let _ = writeln!(out, "            {}) skip=1 ;;", takes_value.join("|"));
+        }
+        for child in &node.children {
+            let labels: Vec<String> = child
+                .names()
+                .map(|n| format!("\"{},{}\"", ident, n))
+                .collect();
+            let _ = writeln!(
+                out,
+                "            {}) cmd={} ;;",
+                labels.join("|"),
+                child.ident()
+            );
+        }
+    }
+    out.push_str("        esac\n    done\n\n");
+
+    out.push_str("    case \"$cmd,$prev\" in\n");
+    for node in &nodes {
+        for opt in &node.opts {
+            let Some(values) = &opt.values else { continue };
+            let labels: Vec<String> = bash_flags(opt)
+                .into_iter()
+                .map(|f| format!("\"{},{}\"", node.ident(), f))
+                .collect();
+            let _ = writeln!(
+                out,
+                "        {}) {}; return ;;",
+                labels.join("|"),
+                bash_values(values)
+            );
+        }
+    }
+    out.push_str("    esac\n\n");
+
+    out.push_str("    case \"$cmd\" in\n");
+    for node in &nodes {
+        let opts: Vec<String> = node.opts.iter().flat_map(bash_flags).collect();
+        let subcommands: Vec<&str> = node.children.iter().flat_map(|c| c.names()).collect();
+        let _ = writeln!(out, "        {})", node.ident());
+        let _ = writeln!(
+            out,
+            "            if [[ $cur == -* ]]; then COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")); return; fi",
+            opts.join(" ")
+        );
+        if !subcommands.is_empty() {
+            let _ = writeln!(
+                out,
+                "            COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))",
+                subcommands.join(" ")
+            );
+        } else if let Some(positional) = node.positionals.first() {
+            let _ = writeln!(out, "            {}", bash_values(&positional.values));
+        }
+        out.push_str("            ;;\n");
+    }
+    out.push_str("    esac\n}\n\ncomplete -o filenames -F _kldctl kldctl\n");
+    out
+}
+
+fn bash_flags(opt: &Opt) -> Vec<String> {
+    opt.short
+        .map(|s| format!("-{}", s))
+        .into_iter()
+        .chain(opt.long.as_ref().map(|l| format!("--{}", l)))
+        .collect()
+}
+
+/// Escapes text for a single-quoted zsh `_arguments`/`_describe` spec
+fn zsh_escape(text: &str) -> String {
+    text.replace('\'', "'\\''")
+        .replace('[', "\\[")
+        .replace(']', "\\]")
+        .replace(':', "\\:")
+}
+
+fn zsh_action(values: &Values) -> String {
+    match values {
+        Values::None => " ".to_string(),
+        Values::Files => "_files".to_string(),
+        Values::Choices(choices) => format!("({})", choices.join(" ")),
+        Values::Daemon(kind) => format!("_kldctl_{}", kind),
+    }
+}
+
+fn zsh(root: &Node) -> String {
+    let mut nodes = Vec::new();
+    root.walk(&mut nodes);
+    let mut out = String::from("#compdef kldctl\n\n");
+
+    for kind in ["outputs", "playlists"] {
+        let _ = writeln!(
+            out,
+            "_kldctl_{kind}() {{\n    local -a values\n    values=(${{(f)\"$(kldctl __complete {kind} 2>/dev/null)\"}})\n    _describe '{kind}' values\n}}\n"
+        );
+    }
+
+    for node in &nodes {
+        let _ = writeln!(out, "_{}() {{", node.ident());
+        if !node.children.is_empty() {
+            out.push_str("    local curcontext=\"$curcontext\" state line\n");
+        }
+        out.push_str("    _arguments -C \\\n");
+        for opt in &node.opts {
+            let flags = bash_flags(opt);
+            let exclusion = if flags.len() > 1 {
+                format!("({})", flags.join(" "))
+            } else {
+                String::new()
+            };
+            let names = if flags.len() > 1 {
+                format!("{{{}}}", flags.join(","))
+            } else {
+                flags.join("")
+            };
+            let value = opt.values.as_ref().map_or(String::new(), |v| {
+                format!(
+                    ":{}:{}",
+                    opt.long.as_deref().unwrap_or("value"),
+                    zsh_action(v)
+                )
+            });
+            let _ = writeln!(
+                out,
+                "        '{}'{}'[{}]{}' \\",
+                exclusion,
+                names,
+                zsh_escape(&opt.help),
+                value
+            );
+        }
+        if node.children.is_empty() {
+            for positional in &node.positionals {
+                let _ = writeln!(
+                    out,
+                    "        '{}:{}:{}' \\",
+                    if positional.required { "" } else { ":" },
+                    positional.name,
+                    zsh_action(&positional.values)
+                );
+            }
+            out.push_str("        && return 0\n}\n\n");
+            continue;
+        }
+        out.push_str("        '1:command:->command' \\\n        '*::arg:->args'\n");
+        out.push_str("    case $state in\n        command)\n            local -a commands\n            commands=(\n");
+        for child in &node.children {
+            for name in child.names() {
+                let _ = writeln!(
+                    out,
+                    "                '{}:{}'",
+                    zsh_escape(name),
+                    zsh_escape(&child.about)
+                );
+            }
+        }
+        out.push_str(
+            "            )\n            _describe -t commands 'command' commands\n            ;;\n",
+        );
+        out.push_str("        args)\n            case $words[1] in\n");
+        for child in &node.children {
+            let _ = writeln!(
+                out,
+                "                {}) _{} ;;",
+                child.names().collect::<Vec<_>>().join("|"),
+                child.ident()
+            );
+        }
+        out.push_str("            esac\n            ;;\n    esac\n}\n\n");
+    }
+    out.push_str("_kldctl \"$@\"\n");
+    out
+}
+
+fn fish_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('\'', "\\'")
+}
+
+fn fish(root: &Node) -> String {
+    let mut nodes = Vec::new();
+    root.walk(&mut nodes);
+    let mut out = String::from("complete -c kldctl -f\n");
+
+    for node in &nodes {
+        // Fish sees the words typed so far; a command applies once its own name and
+        // those of its parents were typed, and none of its subcommands yet
+        let mut conditions: Vec<String> = node
+            .path
+            .iter()
+            .map(|p| format!("__fish_seen_subcommand_from {}", p))
+            .collect();
+        let nested: Vec<&str> = node.children.iter().flat_map(|c| c.names()).collect();
+        if node.path.is_empty() {
+            conditions.push("__fish_use_subcommand".to_string());
+        } else if !nested.is_empty() {
+            conditions.push(format!(
+                "not __fish_seen_subcommand_from {}",
+                nested.join(" ")
+            ));
+        }
+        let condition = conditions.join("; and ");
+
+        for child in &node.children {
+            for name in child.names() {
+                let _ = writeln!(
+                    out,
+                    "complete -c kldctl -n '{}' -a {} -d '{}'",
+                    condition,
+                    name,
+                    fish_escape(&child.about)
+                );
+            }
+        }
+        for opt in &node.opts {
+            let mut line = format!("complete -c kldctl -n '{}'", condition);
+            if let Some(short) = opt.short {
+                let _ = write!(line, " -s {}", short);
+            }
+            if let Some(long) = &opt.long {
+                let _ = write!(line, " -l {}", long);
+            }
+            if let Some(values) = &opt.values {
+                line.push_str(" -r");
+                line.push_str(&fish_values(values));
+            }
+            let _ = writeln!(line, " -d '{}'", fish_escape(&opt.help));
+            out.push_str(&line);
+        }
+        if nested.is_empty() {
+            if let Some(positional) = node.positionals.first() {
+                let values = fish_values(&positional.values);
+                if !values.is_empty() {
+                    let _ = writeln!(out, "complete -c kldctl -n '{}'{}", condition, values);
+                }
+            }
+        }
+    }
+    out
+}
+
+fn fish_values(values: &Values) -> String {
+    match values {
+        Values::None => String::new(),
+        Values::Files => " -F".to_string(),
+        Values::Choices(choices) => format!(" -a '{}'", choices.join(" ")),
+        Values::Daemon(kind) => format!(" -a '(kldctl __complete {})'", kind),
+    }
+}
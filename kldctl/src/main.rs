@@ -6,11 +6,17 @@
     unused_assignments,
     unused_attributes
 )]
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand};
 use kaleidux_common::{Request, Response};
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt};
 use tokio::net::UnixStream;
 
+mod completions;
+mod manpage;
+
+/// How long `kill --wait` waits for the daemon to exit
+const KILL_WAIT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
 #[derive(Parser)]
 #[command(
     name = "kldctl",
@@ -31,10 +37,12 @@ EXAMPLES:
   kldctl love ~/wallpapers/fav.jpg -m 3.0   3x more likely to appear
   kldctl query                              Show connected outputs
   kldctl pause                              Pause video playback
+  kldctl next -o 'DP-*,HDMI-A-1'            Skip on all DP outputs and HDMI-A-1
+  kldctl status --json                      Playback state as JSON for scripts
 
 TRANSITIONS (configured in config.toml):
   fade, cube, angular, ripple, doom, pixelize, crosswarp, 
-  directional, dreamy, swirl, heart, burn, circle, random, ...
+  directional, dreamy, swirl, heart, burn, circle, random, auto, ...
   (50+ transitions available - see documentation)
 
 CONFIG:
@@ -46,10 +54,15 @@ struct Cli {
     #[command(subcommand)]
     command: Commands,
 
-    /// Daemon socket path (defaults to XDG_RUNTIME_DIR/kaleidux.sock or /tmp/kaleidux-{USER}.sock)
+    /// Daemon socket path (defaults to the current session's, e.g.
+    /// XDG_RUNTIME_DIR/kaleidux-wayland-1.sock)
     #[arg(short, long, global = true)]
     socket: Option<String>,
 
+    /// Print the daemon's reply as JSON instead of a table, for scripts and status bars
+    #[arg(long, global = true)]
+    json: bool,
+
     /// Show version information
     #[arg(short = 'v', long = "version", action = clap::ArgAction::Version)]
     version: Option<bool>,
@@ -64,26 +77,62 @@ enum Commands {
     /// Switch to the next wallpaper in the queue
     #[command(visible_alias = "n")]
     Next {
-        /// Target output (omit for all)
+        /// Target outputs: comma-separated names or globs like "DP-*" (omit for all)
         #[arg(short, long)]
         output: Option<String>,
+
+        /// Pick among files carrying all of these comma-separated tags, this once
+        #[arg(short, long)]
+        tag: Option<String>,
     },
 
     /// Switch to the previous wallpaper (if history exists)
     #[command(visible_alias = "p")]
     Prev {
-        /// Target output (omit for all)
+        /// Target outputs: comma-separated names or globs like "DP-*" (omit for all)
         #[arg(short, long)]
         output: Option<String>,
     },
 
+    /// Show a file right away, outside the queue
+    ///
+    /// Cycling carries on from the queue after the output's duration.
+    Set {
+        /// Image or video to show
+        path: String,
+        /// Target outputs: comma-separated names or globs like "DP-*" (omit for all)
+        #[arg(short, long)]
+        output: Option<String>,
+        /// Transition for this switch only: a builtin, a [transition.NAME], cut or random
+        #[arg(short, long)]
+        transition: Option<String>,
+        /// Transition length for this switch only, in seconds
+        #[arg(short, long)]
+        duration: Option<f32>,
+    },
+
+    /// Jump the [kiosk] sequence to an item on every output
+    Goto {
+        /// Item index in the config's kiosk items, starting at 0
+        index: usize,
+    },
+
     /// Mark a file as "loved" - increases its selection frequency
     ///
     /// Loved files appear more often based on their multiplier.
     /// A multiplier of 2.0 means 2x more likely to be picked.
     Love {
-        /// Path to the file
-        path: String,
+        /// Paths to the files (`-` reads newline-separated paths from stdin)
+        #[arg(required_unless_present_any = ["current", "output"])]
+        path: Vec<String>,
+
+        /// Use the wallpaper currently on screen instead of a path
+        #[arg(short, long, conflicts_with = "path")]
+        current: bool,
+
+        /// Use the wallpaper currently on this output (implies --current)
+        #[arg(short, long, conflicts_with = "path")]
+        output: Option<String>,
 
         /// Frequency multiplier (e.g., 2.0 = 2x more likely)
         #[arg(short, long, default_value = "2.0")]
@@ -92,21 +141,38 @@ enum Commands {
 
     /// Remove a file from the love list (reset to normal frequency)
     Unlove {
-        /// Path to the file
-        path: String,
+        /// Paths to the files (`-` reads newline-separated paths from stdin)
+        #[arg(required_unless_present_any = ["current", "output"])]
+        path: Vec<String>,
+
+        /// Use the wallpaper currently on screen instead of a path
+        #[arg(short, long, conflicts_with = "path")]
+        current: bool,
+
+        /// Use the wallpaper currently on this output (implies --current)
+        #[arg(short, long, conflicts_with = "path")]
+        output: Option<String>,
     },
 
     /// List all loved wallpapers with their multipliers
     #[command(visible_alias = "ll")]
     Lovelist,
 
-    /// Pause video playback (images unaffected)
-    Pause,
+    /// Pause video playback and wallpaper cycling
+    Pause {
+        /// Target outputs: comma-separated names or globs like "DP-*" (omit for all)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
 
-    /// Resume video playback
-    Resume,
+    /// Resume playback and cycling, restoring outputs emptied by stop/clear
+    Resume {
+        /// Target outputs: comma-separated names or globs like "DP-*" (omit for all)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
 
-    /// Stop the current wallpaper
+    /// Low-resource standby: stop videos, free GPU memory, hide wallpapers until resume/next
     Stop,
 
     /// Query connected outputs and their current wallpaper
@@ -117,17 +183,35 @@ enum Commands {
     #[command(name = "check-config", visible_alias = "cc")]
     CheckConfig,
 
+    /// Diagnose the environment (daemon, display protocols, GPU, GStreamer, config)
+    /// and print a pass/fail report to attach to bug reports
+    Doctor,
+
     /// Reload configuration from disk
-    Reload,
+    ///
+    /// With --output only those outputs' sections are re-read; other outputs, global
+    /// settings and groups keep running as they are.
+    Reload {
+        /// Target outputs: comma-separated names or globs like "DP-*" (omit for all)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
 
     /// Stop the daemon gracefully
-    Kill,
+    Kill {
+        /// Wait until the daemon has fully exited
+        #[arg(short, long)]
+        wait: bool,
+    },
 
-    /// Clear wallpaper on output(s) - show black screen
+    /// Clear wallpaper on output(s) - show a solid color (black by default)
     Clear {
-        /// Target output or omit for all
+        /// Target outputs: comma-separated names or globs like "DP-*" (omit for all)
         #[arg(short, long)]
         output: Option<String>,
+        /// Color to fill with, e.g. "#101010" or "#10101080"
+        #[arg(short, long)]
+        color: Option<String>,
     },
 
     /// Manage playlists
@@ -142,12 +226,203 @@ enum Commands {
         command: BlacklistSubcommand,
     },
 
+    /// Label files with tags that outputs' `tags` and `next --tag` pick by
+    Tag {
+        #[command(subcommand)]
+        command: TagSubcommand,
+    },
+
+    /// Review files quarantined after repeatedly failing to load
+    Quarantine {
+        #[command(subcommand)]
+        command: QuarantineSubcommand,
+    },
+
+    /// Save and restore whole desk setups, e.g. "work", "streaming", "demo"
+    ///
+    /// A session holds each output's wallpaper, active playlist, pause and favorites
+    /// state and transition. Restored transitions last until the next reload.
+    Session {
+        #[command(subcommand)]
+        command: SessionSubcommand,
+    },
+
     /// Show recently played wallpapers
     History {
         /// Target output (omit for default/all)
         #[arg(short, long)]
         output: Option<String>,
     },
+
+    /// Preview what the queue would pick next, without picking it
+    ///
+    /// Lists the upcoming files with their loveit weight and chance of being the next
+    /// pick, to find out why some wallpapers never show up. Nothing changes: no play
+    /// counts, history or queue position.
+    Pick {
+        /// Only preview (required; picking for real is `kldctl next`)
+        #[arg(long, required = true)]
+        dry_run: bool,
+        /// Target output (omit for every queue)
+        #[arg(short, long)]
+        output: Option<String>,
+        /// Number of candidates to list
+        #[arg(short = 'n', long, default_value_t = 5)]
+        count: usize,
+    },
+
+    /// Show frame, transition, texture pool and error stats per output
+    Metrics,
+
+    /// Manage the on-disk cache (snapshots, pre-processed files)
+    Cache {
+        #[command(subcommand)]
+        command: CacheSubcommand,
+    },
+
+    /// Pull wallpapers from the configured [fetch] service
+    Fetch {
+        #[command(subcommand)]
+        command: FetchSubcommand,
+    },
+
+    /// Change how outputs share queues, without restarting
+    Behavior {
+        #[command(subcommand)]
+        command: BehaviorSubcommand,
+    },
+
+    /// Change transitions at runtime, without editing the config
+    Transition {
+        #[command(subcommand)]
+        command: TransitionSubcommand,
+    },
+
+    /// Change the video volume at runtime, until the next reload
+    Volume {
+        /// 0-100
+        #[arg(value_parser = clap::value_parser!(u8).range(0..=100))]
+        level: u8,
+
+        /// Target outputs: comma-separated names or globs like "DP-*" (omit for all)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// Silence videos, keeping their volume for `unmute`
+    Mute {
+        /// Target outputs: comma-separated names or globs like "DP-*" (omit for all)
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// Mute outputs that aren't muted and unmute the others
+        #[arg(long)]
+        toggle: bool,
+    },
+
+    /// Play videos at their volume again after `mute`
+    Unmute {
+        /// Target outputs: comma-separated names or globs like "DP-*" (omit for all)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// Only pick loved files (love multiplier above 1) until turned off again
+    Favorites {
+        #[arg(value_parser = ["on", "off"])]
+        state: String,
+
+        /// Target outputs: comma-separated names or globs like "DP-*" (omit for all)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// Save the wallpaper on an output as a PNG, optionally blurred
+    ///
+    /// The image is the file as loaded, before cover-cropping, e.g. for a lock screen:
+    /// `kldctl snapshot --blur ~/.cache/lock.png && swaylock -i ~/.cache/lock.png`.
+    Snapshot {
+        /// Where to write the PNG
+        path: std::path::PathBuf,
+
+        /// Output to take it from (may be omitted with a single output)
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// Blur passes, 0-8; each roughly doubles the radius (`--blur` alone is 4)
+        #[arg(
+            short,
+            long,
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "4",
+            value_parser = clap::value_parser!(u32).range(0..=8)
+        )]
+        blur: Option<u32>,
+    },
+
+    /// Save the frame on screen on an output as a PNG
+    ///
+    /// Unlike `snapshot` this is what is presented, at output resolution: cropping,
+    /// effects, widgets and a running transition included.
+    Capture {
+        /// Where to write the PNG
+        path: std::path::PathBuf,
+
+        /// Output to take it from (may be omitted with a single output)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// Block until in-flight wallpaper switches and transitions have finished
+    ///
+    /// Useful for chaining, e.g. `kldctl next && kldctl wait && grim shot.png`.
+    /// Returns immediately if nothing is transitioning.
+    Wait {
+        /// Target output (omit for all)
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// Give up (exit 6, timeout) after this many seconds
+        #[arg(short, long)]
+        timeout: Option<u64>,
+    },
+
+    /// Print daemon events as they happen, one JSON object per line
+    ///
+    /// Wallpaper changes, transitions, outputs coming and going, pause and resume;
+    /// meant for status bars (waybar, eww) that would otherwise poll `query`.
+    /// Runs until interrupted or the daemon exits.
+    Watch,
+
+    /// Send several requests at once, run back to back with no other client in between
+    ///
+    /// Reads a JSON array of requests, e.g.
+    /// `[{"method":"pause"},{"method":"next","params":{"output":"DP-1"}}]`.
+    /// Requests after a failed one are skipped.
+    Batch {
+        /// File holding the array (default: stdin)
+        file: Option<std::path::PathBuf>,
+    },
+
+    /// Print a shell completion script
+    ///
+    /// Output and playlist names are completed by asking the running daemon, e.g.
+    /// `kldctl completions fish > ~/.config/fish/completions/kldctl.fish`.
+    Completions {
+        #[arg(value_enum)]
+        shell: completions::Shell,
+    },
+
+    /// Print the man page (roff) generated from this binary's commands
+    Man,
+
+    /// Print output, playlist or session names for the completion scripts
+    #[command(name = "__complete", hide = true)]
+    Complete {
+        #[arg(value_enum)]
+        kind: completions::Dynamic,
+    },
 }
 
 #[derive(Subcommand)]
@@ -164,22 +439,177 @@ enum PlaylistSubcommand {
     Load { name: Option<String> },
     /// List all playlists
     List,
+    /// Show a playlist's files, schedule and the outputs that have it loaded
+    Show { name: String },
+    /// Fill a playlist (created if needed) from a plain or M3U list of paths and globs
+    Import {
+        name: String,
+        file: String,
+        /// Add to the playlist instead of replacing its files
+        #[arg(long)]
+        append: bool,
+    },
+    /// Write a playlist's files to a list file (M3U for .m3u/.m3u8)
+    Export { name: String, file: String },
+}
+
+#[derive(Subcommand)]
+enum SessionSubcommand {
+    /// Save the current setup of every output (replaces a session of that name)
+    Save { name: String },
+    /// Bring a saved setup back on the outputs connected now
+    Restore { name: String },
+    /// Delete a saved session
+    Delete { name: String },
+    /// List saved sessions
+    List,
+}
+
+#[derive(Subcommand)]
+enum CacheSubcommand {
+    /// Evict least-recently-used files until the cache fits its size limit
+    Gc {
+        /// Limit to enforce instead of the configured cache-max-size, e.g. "500MB"
+        #[arg(short, long)]
+        max_size: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum FetchSubcommand {
+    /// Pull now instead of waiting for the next interval; waits until it's done
+    Now,
+}
+
+#[derive(Subcommand)]
+enum BehaviorSubcommand {
+    /// Switch monitor-behavior until the next reload
+    ///
+    /// Outputs keep what they show; the regrouped queues take over on the next switch,
+    /// e.g. `kldctl behavior set grouped -g DP-1,DP-2 -g HDMI-A-1`.
+    Set {
+        #[arg(value_parser = ["independent", "synchronized", "grouped"])]
+        mode: String,
+        /// Comma-separated outputs of one group (repeat per group, grouped only)
+        #[arg(short, long = "group")]
+        groups: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum TransitionSubcommand {
+    /// Use a transition until the next reload
+    ///
+    /// A builtin, a [transition.NAME] from the config, random or auto,
+    /// e.g. `kldctl transition set cube -o HDMI-A-1 -d 1.5`.
+    Set {
+        name: String,
+        /// Target outputs: comma-separated names or globs like "DP-*" (omit for all)
+        #[arg(short, long)]
+        output: Option<String>,
+        /// Transition length in seconds (replaces transition-time)
+        #[arg(short, long)]
+        duration: Option<f32>,
+    },
 }
 
 #[derive(Subcommand)]
 enum BlacklistSubcommand {
-    /// Add a file to the blacklist
-    Add { path: String },
-    /// Remove a file from the blacklist
-    Remove { path: String },
+    /// Add files to the blacklist
+    Add {
+        /// Paths to the files (`-` reads newline-separated paths from stdin)
+        #[arg(required_unless_present_any = ["current", "output"])]
+        path: Vec<String>,
+
+        /// Use the wallpaper currently on screen instead of a path
+        #[arg(short, long, conflicts_with = "path")]
+        current: bool,
+
+        /// Use the wallpaper currently on this output (implies --current)
+        #[arg(short, long, conflicts_with = "path")]
+        output: Option<String>,
+    },
+    /// Remove files from the blacklist
+    Remove {
+        /// Paths to the files (`-` reads newline-separated paths from stdin)
+        #[arg(required = true)]
+        path: Vec<String>,
+    },
     /// List blacklisted files
     List,
 }
 
+#[derive(Subcommand)]
+enum TagSubcommand {
+    /// Add tags to files
+    Add {
+        /// Paths to the files (`-` reads newline-separated paths from stdin)
+        #[arg(required_unless_present_any = ["current", "output"])]
+        path: Vec<String>,
+
+        /// Comma-separated tags, e.g. "nature,dark"
+        #[arg(required = true)]
+        tags: String,
+
+        /// Use the wallpaper currently on screen instead of a path
+        #[arg(short, long, conflicts_with = "path")]
+        current: bool,
+
+        /// Use the wallpaper currently on this output (implies --current)
+        #[arg(short, long, conflicts_with = "path")]
+        output: Option<String>,
+    },
+    /// Remove tags from files
+    Remove {
+        /// Paths to the files (`-` reads newline-separated paths from stdin)
+        #[arg(required_unless_present_any = ["current", "output"])]
+        path: Vec<String>,
+
+        /// Comma-separated tags
+        #[arg(required = true)]
+        tags: String,
+
+        /// Use the wallpaper currently on screen instead of a path
+        #[arg(short, long, conflicts_with = "path")]
+        current: bool,
+
+        /// Use the wallpaper currently on this output (implies --current)
+        #[arg(short, long, conflicts_with = "path")]
+        output: Option<String>,
+    },
+    /// List tags with their files
+    List {
+        /// Only this tag
+        tag: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum QuarantineSubcommand {
+    /// List quarantined files with their last error
+    List,
+    /// Put a quarantined file back into rotation
+    Remove { path: String },
+    /// Put every quarantined file back into rotation
+    Clear,
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
 
+    match &cli.command {
+        Commands::Completions { shell } => {
+            print!("{}", completions::generate(*shell, Cli::command()));
+            return Ok(());
+        }
+        Commands::Man => {
+            print!("{}", manpage::generate(Cli::command()));
+            return Ok(());
+        }
+        _ => {}
+    }
+
     // Handle local commands first (don't need daemon connection)
     if let Commands::CheckConfig = &cli.command {
         // Validate configuration without connecting to daemon
@@ -206,22 +636,106 @@ async fn main() -> anyhow::Result<()> {
         return Ok(());
     }
 
+    // Determine socket path (use provided or default, derived like the daemon's:
+    // one socket per graphical session)
+    let socket_path = cli.socket.unwrap_or_else(|| {
+        let file_name = kaleidux_common::runtime_file_name("sock");
+        dirs::runtime_dir()
+            .map(|d| d.join(&file_name).to_string_lossy().to_string())
+            .unwrap_or_else(|| {
+                let uid = std::env::var("USER").unwrap_or_else(|_| "kaleidux".to_string());
+                format!("/tmp/{}-{}", uid, file_name)
+            })
+    });
+
+    if let Commands::Complete { kind } = &cli.command {
+        // Completion must stay quiet; a daemon that isn't running just completes nothing
+        let names = match kind {
+            completions::Dynamic::Outputs => query_outputs(&socket_path)
+                .await
+                .map(|outputs| outputs.into_iter().map(|o| o.name).collect()),
+            completions::Dynamic::Playlists => list_playlists(&socket_path).await,
+            completions::Dynamic::Sessions => list_sessions(&socket_path).await,
+        };
+        for name in names.unwrap_or_default() {
+            println!("{}", name);
+        }
+        return Ok(());
+    }
+
+    if let Commands::Watch = &cli.command {
+        return watch(&socket_path).await;
+    }
+
+    if let Commands::Doctor = &cli.command {
+        if !doctor(&socket_path).await {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    let wait_for_exit = matches!(cli.command, Commands::Kill { wait: true });
+
     let request = match cli.command {
-        Commands::Status => Request::QueryOutputs,
-        Commands::Next { output } => Request::Next { output },
+        Commands::Status => Request::Status,
+        Commands::Next { output, tag } => Request::Next {
+            output,
+            tags: tag.into_iter().collect(),
+        },
         Commands::Prev { output } => Request::Prev { output },
-        Commands::Love { path, multiplier } => Request::Love { path, multiplier },
-        Commands::Unlove { path } => Request::Unlove { path },
+        Commands::Set {
+            path,
+            output,
+            transition,
+            duration,
+        } => Request::Set {
+            // The daemon runs in another working directory
+            path: std::path::absolute(&path)?.to_string_lossy().to_string(),
+            output,
+            transition,
+            duration,
+        },
+        Commands::Goto { index } => Request::Goto { index },
+        Commands::Love {
+            path,
+            multiplier,
+            output,
+            ..
+        } => {
+            let (path, paths) = path_args(path)?;
+            Request::Love {
+                path,
+                paths,
+                multiplier,
+                output,
+            }
+        }
+        Commands::Unlove { path, output, .. } => {
+            let (path, paths) = path_args(path)?;
+            Request::Unlove {
+                path,
+                paths,
+                output,
+            }
+        }
         Commands::Lovelist => Request::LoveitList,
-        Commands::Pause => Request::Pause,
-        Commands::Resume => Request::Resume,
+        Commands::Pause { output } => Request::Pause { output },
+        Commands::Resume { output } => Request::Resume { output },
         Commands::Stop => Request::Stop,
         Commands::Query => Request::QueryOutputs,
-        Commands::Reload => Request::Reload,
-        Commands::Kill => Request::Kill,
-        Commands::Clear { output } => Request::Clear { output },
+        Commands::Reload { output: None } => Request::Reload,
+        Commands::Reload {
+            output: Some(output),
+        } => Request::ReloadOutput { output },
+        Commands::Kill { .. } => Request::Kill,
+        Commands::Clear { output, color } => Request::Clear { output, color },
 
-        Commands::CheckConfig => unreachable!(),
+        Commands::CheckConfig
+        | Commands::Doctor
+        | Commands::Completions { .. }
+        | Commands::Man
+        | Commands::Watch
+        | Commands::Complete { .. } => unreachable!(),
         Commands::Playlist { command } => Request::Playlist(match command {
             PlaylistSubcommand::Create { name } => {
                 kaleidux_common::PlaylistCommand::Create { name }
@@ -237,94 +751,180 @@ async fn main() -> anyhow::Result<()> {
             }
             PlaylistSubcommand::Load { name } => kaleidux_common::PlaylistCommand::Load { name },
             PlaylistSubcommand::List => kaleidux_common::PlaylistCommand::List,
+            PlaylistSubcommand::Show { name } => kaleidux_common::PlaylistCommand::Show { name },
+            PlaylistSubcommand::Import { name, file, append } => {
+                kaleidux_common::PlaylistCommand::Import {
+                    name,
+                    // The daemon runs in another working directory
+                    file: std::path::absolute(&file)?.to_string_lossy().to_string(),
+                    append,
+                }
+            }
+            PlaylistSubcommand::Export { name, file } => kaleidux_common::PlaylistCommand::Export {
+                name,
+                file: std::path::absolute(&file)?.to_string_lossy().to_string(),
+            },
         }),
         Commands::Blacklist { command } => Request::Blacklist(match command {
-            BlacklistSubcommand::Add { path } => kaleidux_common::BlacklistCommand::Add { path },
+            BlacklistSubcommand::Add { path, output, .. } => {
+                let (path, paths) = path_args(path)?;
+                kaleidux_common::BlacklistCommand::Add {
+                    path,
+                    paths,
+                    output,
+                }
+            }
             BlacklistSubcommand::Remove { path } => {
-                kaleidux_common::BlacklistCommand::Remove { path }
+                let (path, paths) = path_args(path)?;
+                kaleidux_common::BlacklistCommand::Remove { path, paths }
             }
             BlacklistSubcommand::List => kaleidux_common::BlacklistCommand::List,
         }),
+        Commands::Tag { command } => Request::Tag(match command {
+            TagSubcommand::Add {
+                path, tags, output, ..
+            } => {
+                let (path, paths) = absolute_path_args(path)?;
+                kaleidux_common::TagCommand::Add {
+                    path,
+                    paths,
+                    output,
+                    tags: vec![tags],
+                }
+            }
+            TagSubcommand::Remove {
+                path, tags, output, ..
+            } => {
+                let (path, paths) = absolute_path_args(path)?;
+                kaleidux_common::TagCommand::Remove {
+                    path,
+                    paths,
+                    output,
+                    tags: vec![tags],
+                }
+            }
+            TagSubcommand::List { tag } => kaleidux_common::TagCommand::List { tag },
+        }),
+        Commands::Quarantine { command } => Request::Quarantine(match command {
+            QuarantineSubcommand::List => kaleidux_common::QuarantineCommand::List,
+            QuarantineSubcommand::Remove { path } => {
+                kaleidux_common::QuarantineCommand::Remove { path }
+            }
+            QuarantineSubcommand::Clear => kaleidux_common::QuarantineCommand::Clear,
+        }),
+        Commands::Session { command } => Request::Session(match command {
+            SessionSubcommand::Save { name } => kaleidux_common::SessionCommand::Save { name },
+            SessionSubcommand::Restore { name } => {
+                kaleidux_common::SessionCommand::Restore { name }
+            }
+            SessionSubcommand::Delete { name } => kaleidux_common::SessionCommand::Delete { name },
+            SessionSubcommand::List => kaleidux_common::SessionCommand::List,
+        }),
         Commands::History { output } => Request::History { output },
+        Commands::Pick { output, count, .. } => Request::PickPreview { output, count },
+        Commands::Cache { command } => Request::Cache(match command {
+            CacheSubcommand::Gc { max_size } => kaleidux_common::CacheCommand::Gc { max_size },
+        }),
+        Commands::Fetch { command } => Request::Fetch(match command {
+            FetchSubcommand::Now => kaleidux_common::FetchCommand::Now,
+        }),
+        Commands::Behavior {
+            command: BehaviorSubcommand::Set { mode, groups },
+        } => Request::Behavior(kaleidux_common::BehaviorCommand::Set {
+            mode,
+            groups: groups
+                .iter()
+                .map(|group| group.split(',').map(|s| s.trim().to_string()).collect())
+                .collect(),
+        }),
+        Commands::Transition {
+            command:
+                TransitionSubcommand::Set {
+                    name,
+                    output,
+                    duration,
+                },
+        } => Request::SetTransition {
+            output,
+            transition: name,
+            duration,
+        },
+        Commands::Volume { level, output } => Request::SetVolume {
+            output,
+            volume: level,
+        },
+        Commands::Mute { output, toggle } => Request::Mute {
+            output,
+            muted: (!toggle).then_some(true),
+        },
+        Commands::Unmute { output } => Request::Mute {
+            output,
+            muted: Some(false),
+        },
+        Commands::Favorites { state, output } => Request::Favorites {
+            enabled: state == "on",
+            output,
+        },
+        Commands::Snapshot { path, output, blur } => Request::Snapshot {
+            output,
+            // The daemon runs in another working directory
+            path: std::path::absolute(&path)?.to_string_lossy().to_string(),
+            blur: blur.unwrap_or(0),
+        },
+        Commands::Capture { path, output } => Request::Capture {
+            output,
+            path: std::path::absolute(&path)?.to_string_lossy().to_string(),
+        },
+        Commands::Wait { output, timeout } => Request::Wait { output, timeout },
+        Commands::Metrics => Request::Metrics,
+        Commands::Batch { file } => {
+            let json = match file {
+                Some(path) => std::fs::read_to_string(path)?,
+                None => std::io::read_to_string(std::io::stdin())?,
+            };
+            Request::Batch(
+                serde_json::from_str(&json)
+                    .map_err(|e| anyhow::anyhow!("Expected a JSON array of requests: {}", e))?,
+            )
+        }
     };
 
-    // Determine socket path (use provided or default)
-    let socket_path = cli.socket.unwrap_or_else(|| {
-        dirs::runtime_dir()
-            .map(|d| d.join("kaleidux.sock").to_string_lossy().to_string())
-            .unwrap_or_else(|| {
-                let uid = std::env::var("USER").unwrap_or_else(|_| "kaleidux".to_string());
-                format!("/tmp/kaleidux-{}.sock", uid)
-            })
-    });
-
     // Connect to daemon
     match UnixStream::connect(&socket_path).await {
         Ok(mut stream) => {
             let req_json = serde_json::to_string(&request)?;
-            stream.write_all(req_json.as_bytes()).await?;
-            stream.write_all(b"\n").await?;
+            // The daemon answers a request that is too large before reading all of it,
+            // so a failed write still leaves a reply to read
+            let sent = async {
+                stream.write_all(req_json.as_bytes()).await?;
+                stream.write_all(b"\n").await
+            }
+            .await;
 
             // Read response
             let mut response = String::new();
-            stream.read_to_string(&mut response).await?;
+            let received = stream.read_to_string(&mut response).await;
 
-            if !response.is_empty() {
+            if response.is_empty() {
+                sent?;
+                received?;
+                eprintln!("The daemon closed the connection without a reply");
+                std::process::exit(1);
+            } else {
                 // Try to parse as Response to pretty print if it's a list
                 if let Ok(resp) = serde_json::from_str::<Response>(&response) {
-                    match resp {
-                        Response::LoveitList(entries) => {
-                            println!("{:<50} | {:<5} | {:<5}", "Path", "Loveit", "Uses");
-                            println!("{}", "-".repeat(66));
-                            for entry in entries {
-                                println!(
-                                    "{:<50} | {:<5.1} | {:<5}",
-                                    entry.path, entry.multiplier, entry.count
-                                );
-                            }
-                        }
-                        Response::OutputInfo(outputs) => {
-                            println!(
-                                "{:<10} | {:<10} | {:<30}",
-                                "Output", "Size", "Current Wallpaper"
-                            );
-                            println!("{}", "-".repeat(56));
-                            for out in outputs {
-                                println!(
-                                    "{:<10} | {}x{} | {:<30}",
-                                    out.name,
-                                    out.width,
-                                    out.height,
-                                    out.current_wallpaper.unwrap_or_else(|| "none".to_string())
-                                );
-                            }
-                        }
-                        Response::Error(e) => eprintln!("Error: {}", e),
-                        Response::Ok => println!("OK"),
-                        Response::Playlists(names) => {
-                            println!("Playlists:");
-                            for name in names {
-                                println!(" - {}", name);
-                            }
-                        }
-                        Response::Blacklist(paths) => {
-                            println!("Blacklisted Files:");
-                            for path in paths {
-                                println!(" - {}", path);
-                            }
-                        }
-                        Response::History(paths) => {
-                            println!("History (most recent last):");
-                            for (i, path) in paths.iter().enumerate() {
-                                println!(" {:>2}. {}", i + 1, path);
-                            }
-                        }
+                    let failure = resp.error_code();
+                    if cli.json {
+                        println!("{}", response.trim_end());
+                    } else {
+                        print_response(resp);
+                    }
+                    if let Some(code) = failure {
+                        std::process::exit(code.exit_status());
                     }
                 } else {
                     println!("{}", response);
                 }
-            } else {
-                println!("OK");
             }
         }
         Err(e) => {
@@ -334,5 +934,543 @@ async fn main() -> anyhow::Result<()> {
         }
     }
 
+    if wait_for_exit {
+        // The socket stops accepting connections once the daemon process is gone
+        let start = std::time::Instant::now();
+        while UnixStream::connect(&socket_path).await.is_ok() {
+            if start.elapsed() >= KILL_WAIT_TIMEOUT {
+                eprintln!(
+                    "Timed out after {}s waiting for the daemon to exit",
+                    KILL_WAIT_TIMEOUT.as_secs()
+                );
+                std::process::exit(1);
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+        println!("Daemon exited");
+    }
+
     Ok(())
 }
+
+/// Pretty-prints a daemon reply; failures go to stderr
+fn print_response(resp: Response) {
+    match resp {
+        Response::Status(outputs) => {
+            println!(
+                "{:<10} | {:<8} | {:<15} | {:<16} | {:<14} | {:<30}",
+                "Output", "Type", "Time", "Transition", "Queue", "Current Wallpaper"
+            );
+            println!("{}", "-".repeat(110));
+            for out in outputs {
+                let time = match (out.elapsed_ms, out.remaining_ms) {
+                    _ if out.paused => "paused".to_string(),
+                    (Some(elapsed), Some(remaining)) => {
+                        format!("{} (-{})", format_ms(elapsed), format_ms(remaining))
+                    }
+                    (Some(elapsed), None) => format_ms(elapsed),
+                    _ => "-".to_string(),
+                };
+                let queue = match out.playlist {
+                    Some(playlist) => format!("{} ({})", out.queue_size, playlist),
+                    None => out.queue_size.to_string(),
+                };
+                let mut wallpaper = match out.error {
+                    Some(error) => format!("fallback: {}", error),
+                    None => out.path.unwrap_or_else(|| "none".to_string()),
+                };
+                if let Some(position) = out.video_position_ms {
+                    match out.video_duration_ms {
+                        Some(duration) => wallpaper.push_str(&format!(
+                            " [{}/{}]",
+                            format_ms(position),
+                            format_ms(duration)
+                        )),
+                        None => wallpaper.push_str(&format!(" [{}]", format_ms(position))),
+                    }
+                }
+                println!(
+                    "{:<10} | {:<8} | {:<15} | {:<16} | {:<14} | {:<30}",
+                    out.name,
+                    out.content_type.as_deref().unwrap_or("-"),
+                    time,
+                    transition_label(out.transition),
+                    queue,
+                    wallpaper
+                );
+            }
+        }
+        Response::LoveitList(entries) => {
+            println!("{:<50} | {:<5} | {:<5}", "Path", "Loveit", "Uses");
+            println!("{}", "-".repeat(66));
+            for entry in entries {
+                println!(
+                    "{:<50} | {:<5.1} | {:<5}",
+                    entry.path, entry.multiplier, entry.count
+                );
+            }
+        }
+        Response::OutputInfo(outputs) => {
+            println!(
+                "{:<10} | {:<10} | {:<16} | {:<30}",
+                "Output", "Size", "Transition", "Current Wallpaper"
+            );
+            println!("{}", "-".repeat(75));
+            for out in outputs {
+                println!(
+                    "{:<10} | {:<10} | {:<16} | {:<30}",
+                    out.name,
+                    format!("{}x{}", out.width, out.height),
+                    transition_label(out.transition),
+                    match out.error {
+                        Some(error) => format!("fallback: {}", error),
+                        None => out.current_wallpaper.unwrap_or_else(|| "none".to_string()),
+                    }
+                );
+            }
+        }
+        Response::Error(e) => eprintln!("Error ({}): {}", e.code, e.message),
+        Response::Ok => println!("OK"),
+        Response::Playlists(names) => {
+            println!("Playlists:");
+            for name in names {
+                println!(" - {}", name);
+            }
+        }
+        Response::Playlist(playlist) => {
+            println!(
+                "Playlist {} ({} files):",
+                playlist.name,
+                playlist.paths.len()
+            );
+            for path in &playlist.paths {
+                println!(" - {}", path);
+            }
+            if playlist.schedule.is_empty() {
+                println!("Schedule: none");
+            } else {
+                println!("Schedule: {}", playlist.schedule.join(", "));
+                if !playlist.schedule_outputs.is_empty() {
+                    println!("  on {}", playlist.schedule_outputs.join(", "));
+                }
+            }
+            if !playlist.loaded_on.is_empty() {
+                println!("Loaded on: {}", playlist.loaded_on.join(", "));
+            }
+        }
+        Response::PlaylistImport(report) => {
+            println!(
+                "Imported {} files into {} ({} total)",
+                report.added, report.name, report.total
+            );
+            if !report.skipped.is_empty() {
+                println!("Skipped (missing, unsupported or matching nothing):");
+                for entry in &report.skipped {
+                    println!(" - {}", entry);
+                }
+            }
+        }
+        Response::Sessions(sessions) if sessions.is_empty() => {
+            println!("No saved sessions");
+        }
+        Response::Sessions(sessions) => {
+            println!("Sessions:");
+            for session in sessions {
+                println!(
+                    " - {} (saved {}; {})",
+                    session.name,
+                    format_age(session.saved_at),
+                    session.outputs.join(", ")
+                );
+            }
+        }
+        Response::Blacklist(paths) => {
+            println!("Blacklisted Files:");
+            for path in paths {
+                println!(" - {}", path);
+            }
+        }
+        Response::Tags(tags) if tags.is_empty() => {
+            println!("No tagged files");
+        }
+        Response::Tags(tags) => {
+            for tag in tags {
+                println!("{} ({} files)", tag.name, tag.files.len());
+                for file in &tag.files {
+                    println!(" - {}", file);
+                }
+            }
+        }
+        Response::Quarantine(entries) if entries.is_empty() => {
+            println!("No quarantined files");
+        }
+        Response::Quarantine(entries) => {
+            println!("Quarantined Files:");
+            for entry in entries {
+                println!(
+                    " - {} ({} failures, {})",
+                    entry.path,
+                    entry.failures,
+                    format_age(entry.since)
+                );
+                println!("   {}", entry.error);
+            }
+        }
+        Response::History(paths) => {
+            println!("History (most recent last):");
+            for (i, path) in paths.iter().enumerate() {
+                println!(" {:>2}. {}", i + 1, path);
+            }
+        }
+        Response::Picks(previews) => {
+            for preview in previews {
+                println!(
+                    "{} ({}, {} files):",
+                    preview.outputs.join(", "),
+                    preview.strategy,
+                    preview.pool_size
+                );
+                if preview.candidates.is_empty() {
+                    println!("   nothing to pick");
+                }
+                for (i, c) in preview.candidates.iter().enumerate() {
+                    let odds = match (c.probability, c.weight) {
+                        (Some(p), Some(w)) => format!("{:>6.2}%  w {:>7.1}", p * 100.0, w),
+                        (Some(p), None) => format!("{:>6.2}%", p * 100.0),
+                        _ => String::new(),
+                    };
+                    println!(" {:>2}. {:<18} {}", i + 1, odds, c.path);
+                    if let Some(reason) = &c.skipped {
+                        println!("     skipped: {}", reason);
+                    }
+                }
+            }
+        }
+        Response::Cache(report) => {
+            println!(
+                "Removed {} files, reclaimed {}",
+                report.files_removed,
+                format_size(report.bytes_reclaimed)
+            );
+            match report.limit {
+                Some(limit) => println!(
+                    "Cache size: {} (limit {})",
+                    format_size(report.size),
+                    format_size(limit)
+                ),
+                None => println!(
+                    "Cache size: {} (no cache-max-size set)",
+                    format_size(report.size)
+                ),
+            }
+        }
+        Response::Fetch(report) => println!(
+            "Fetched {} new wallpapers from {} into playlist '{}' ({} pruned, {} total)",
+            report.added, report.provider, report.playlist, report.removed, report.total
+        ),
+        Response::Metrics(report) => {
+            println!(
+                "Uptime {}s | loop avg {:.2}ms p95 {:.2}ms | {} transitions | \
+                 pool hit rate {:.1}% | {} errors",
+                report.uptime_secs,
+                report.frame_time_avg_ms,
+                report.frame_time_p95_ms,
+                report.transitions,
+                report.texture_pool_hit_rate * 100.0,
+                report.errors
+            );
+            if let Some(resident) = report.resident_mb {
+                let last = report
+                    .last_trim_mb
+                    .map(|(before, after)| format!(", last {:.1}MB -> {:.1}MB", before, after))
+                    .unwrap_or_default();
+                println!(
+                    "Memory {:.1}MB resident | {} idle trims{}",
+                    resident, report.idle_trims, last
+                );
+            }
+            println!(
+                "{:<12} | {:>8} | {:>8} | {:>8} | {:>8} | {:>5} | {:>9} | {:>6} | {:>6}",
+                "Output",
+                "Avg ms",
+                "P95 ms",
+                "Max ms",
+                "GPU ms",
+                "Trans",
+                "Trans ms",
+                "Pool %",
+                "Errors"
+            );
+            println!("{}", "-".repeat(97));
+            for o in &report.outputs {
+                let gpu = o
+                    .gpu_time_avg_ms
+                    .map(|ms| format!("{:.2}", ms))
+                    .unwrap_or_else(|| "-".to_string());
+                println!(
+                    "{:<12} | {:>8.2} | {:>8.2} | {:>8.2} | {:>8} | {:>5} | {:>9.0} | \
+                     {:>6.1} | {:>6}",
+                    o.output,
+                    o.frame_time_avg_ms,
+                    o.frame_time_p95_ms,
+                    o.frame_time_max_ms,
+                    gpu,
+                    o.transitions,
+                    o.transition_avg_ms,
+                    o.texture_pool_hit_rate * 100.0,
+                    o.errors
+                );
+            }
+            let downscaled: Vec<_> = report
+                .outputs
+                .iter()
+                .filter(|o| o.images_downscaled > 0)
+                .collect();
+            if !downscaled.is_empty() {
+                println!();
+                for o in downscaled {
+                    println!(
+                        "{}: {} images scaled down, {:.1}MB of textures saved",
+                        o.output, o.images_downscaled, o.downscale_saved_mb
+                    );
+                }
+            }
+            if !report.transitions_gpu.is_empty() {
+                println!();
+                println!(
+                    "{:<28} | {:>7} | {:>8} | {:>8} | {:>8}",
+                    "Transition (GPU)", "Frames", "Avg ms", "P95 ms", "Max ms"
+                );
+                println!("{}", "-".repeat(71));
+                for t in report.transitions_gpu {
+                    println!(
+                        "{:<28} | {:>7} | {:>8.2} | {:>8.2} | {:>8.2}",
+                        t.transition, t.samples, t.avg_ms, t.p95_ms, t.max_ms
+                    );
+                }
+            }
+        }
+        Response::OutputResults(results) => {
+            for r in results {
+                if r.success {
+                    println!("✓ {:<10} {}", r.output, r.message);
+                } else {
+                    let code = r.code.unwrap_or(kaleidux_common::ErrorCode::Failed);
+                    println!("✗ {:<10} {} ({})", r.output, r.message, code);
+                }
+            }
+        }
+        // Every reply is printed; the first failure picks the exit status
+        Response::Batch(responses) => responses.into_iter().for_each(print_response),
+    }
+}
+
+/// `path_args` made absolute, since the daemon runs in another working directory
+fn absolute_path_args(args: Vec<String>) -> anyhow::Result<(Option<String>, Vec<String>)> {
+    let absolute = |path: String| -> anyhow::Result<String> {
+        Ok(std::path::absolute(&path)?.to_string_lossy().to_string())
+    };
+    let (path, paths) = path_args(args)?;
+    Ok((
+        path.map(absolute).transpose()?,
+        paths
+            .into_iter()
+            .map(absolute)
+            .collect::<anyhow::Result<_>>()?,
+    ))
+}
+
+/// Path arguments of love/unlove/blacklist as sent to the daemon: a single file goes in
+/// `path`, several in `paths` (one request for all of them). `-` is replaced by the
+/// newline-separated paths on stdin.
+fn path_args(args: Vec<String>) -> anyhow::Result<(Option<String>, Vec<String>)> {
+    let given = !args.is_empty();
+    let mut paths = Vec::with_capacity(args.len());
+    for arg in args {
+        if arg == "-" {
+            let stdin = std::io::read_to_string(std::io::stdin())?;
+            paths.extend(
+                stdin
+                    .lines()
+                    .map(str::trim)
+                    .filter(|l| !l.is_empty())
+                    .map(String::from),
+            );
+        } else {
+            paths.push(arg);
+        }
+    }
+    if given && paths.is_empty() {
+        // Sending nothing would target the wallpaper on screen instead
+        anyhow::bail!("No paths on stdin");
+    }
+    if paths.len() == 1 {
+        Ok((paths.pop(), Vec::new()))
+    } else {
+        Ok((None, paths))
+    }
+}
+
+/// Human-readable byte count, e.g. "1.4 GiB"
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// "5m ago"-style age of a Unix timestamp
+fn format_age(timestamp: u64) -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let secs = now.saturating_sub(timestamp);
+    match secs {
+        0..=59 => format!("{}s ago", secs),
+        60..=3599 => format!("{}m ago", secs / 60),
+        3600..=86399 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86400),
+    }
+}
+
+/// `kldctl doctor`: checks the daemon socket here, then runs `kaleidux-daemon --doctor`
+/// for everything that needs the daemon's own libraries (Wayland protocols, wgpu
+/// adapters, GStreamer plugins, config parsing). Returns false if any check failed.
+async fn doctor(socket_path: &str) -> bool {
+    println!(
+        "kldctl {} (session {})",
+        env!("CARGO_PKG_VERSION"),
+        kaleidux_common::session_tag().unwrap_or_else(|| "none".to_string())
+    );
+
+    let mut ok = true;
+    match query_outputs(socket_path).await {
+        Ok(outputs) => println!(
+            "✓ {:<14} reachable at {} ({} outputs)",
+            "daemon",
+            socket_path,
+            outputs.len()
+        ),
+        Err(e) => {
+            println!("✗ {:<14} not reachable at {}: {}", "daemon", socket_path, e);
+            ok = false;
+        }
+    }
+
+    // Prefer the daemon installed next to kldctl so both come from the same build
+    let daemon = std::env::current_exe()
+        .ok()
+        .map(|exe| exe.with_file_name("kaleidux-daemon"))
+        .filter(|path| path.exists())
+        .unwrap_or_else(|| std::path::PathBuf::from("kaleidux-daemon"));
+
+    match std::process::Command::new(&daemon).arg("--doctor").status() {
+        Ok(status) => ok &= status.success(),
+        Err(e) => {
+            println!("✗ {:<14} cannot run {:?}: {}", "daemon binary", daemon, e);
+            ok = false;
+        }
+    }
+
+    println!();
+    if ok {
+        println!("All checks passed");
+    } else {
+        println!("Some checks failed (✗); include this report in bug reports");
+    }
+    ok
+}
+
+/// Transition column of `query` and `status`
+fn transition_label(transition: Option<kaleidux_common::TransitionStatus>) -> String {
+    match transition {
+        Some(t) => match t.state {
+            kaleidux_common::TransitionState::Idle => "idle".to_string(),
+            kaleidux_common::TransitionState::Loading => format!("{} (loading)", t.name),
+            kaleidux_common::TransitionState::Running => {
+                format!("{} {:.0}%", t.name, t.progress * 100.0)
+            }
+        },
+        None => "-".to_string(),
+    }
+}
+
+/// `m:ss` (or `h:mm:ss`) for status times
+fn format_ms(ms: u64) -> String {
+    let secs = ms / 1000;
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+/// Round-trips a QueryOutputs request to check the daemon is alive and answering
+async fn query_outputs(socket_path: &str) -> anyhow::Result<Vec<kaleidux_common::OutputInfo>> {
+    match request_quick(socket_path, &Request::QueryOutputs).await? {
+        Response::OutputInfo(outputs) => Ok(outputs),
+        Response::Error(e) => anyhow::bail!(e.message),
+        _ => anyhow::bail!("unexpected response"),
+    }
+}
+
+async fn list_playlists(socket_path: &str) -> anyhow::Result<Vec<String>> {
+    let request = Request::Playlist(kaleidux_common::PlaylistCommand::List);
+    match request_quick(socket_path, &request).await? {
+        Response::Playlists(names) => Ok(names),
+        Response::Error(e) => anyhow::bail!(e.message),
+        _ => anyhow::bail!("unexpected response"),
+    }
+}
+
+async fn list_sessions(socket_path: &str) -> anyhow::Result<Vec<String>> {
+    let request = Request::Session(kaleidux_common::SessionCommand::List);
+    match request_quick(socket_path, &request).await? {
+        Response::Sessions(sessions) => Ok(sessions.into_iter().map(|s| s.name).collect()),
+        Response::Error(e) => anyhow::bail!(e.message),
+        _ => anyhow::bail!("unexpected response"),
+    }
+}
+
+/// `kldctl watch`: subscribes and echoes every event line until the daemon goes away
+async fn watch(socket_path: &str) -> anyhow::Result<()> {
+    let mut stream = match UnixStream::connect(socket_path).await {
+        Ok(stream) => stream,
+        Err(e) => {
+            eprintln!("Failed to connect to daemon at {}: {}", socket_path, e);
+            eprintln!("Is kaleidux-daemon running?");
+            std::process::exit(1);
+        }
+    };
+    let req_json = serde_json::to_string(&Request::Subscribe)?;
+    stream.write_all(req_json.as_bytes()).await?;
+    stream.write_all(b"\n").await?;
+
+    let mut lines = tokio::io::BufReader::new(stream).lines();
+    while let Some(line) = lines.next_line().await? {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+/// One request with a short timeout, for diagnostics and completion
+async fn request_quick(socket_path: &str, request: &Request) -> anyhow::Result<Response> {
+    let timeout = std::time::Duration::from_secs(2);
+    let mut stream = tokio::time::timeout(timeout, UnixStream::connect(socket_path)).await??;
+    let req_json = serde_json::to_string(request)?;
+    stream.write_all(req_json.as_bytes()).await?;
+    stream.write_all(b"\n").await?;
+
+    let mut response = String::new();
+    tokio::time::timeout(timeout, stream.read_to_string(&mut response)).await??;
+    Ok(serde_json::from_str::<Response>(&response)?)
+}
@@ -0,0 +1,123 @@
+use clap::{Arg, Command};
+use std::fmt::Write;
+
+/// Roff man page for `cmd` and all its subcommands, built from the clap definitions so
+/// it always matches the installed binary
+pub fn generate(mut cmd: Command) -> String {
+    cmd.build();
+    let name = cmd.get_name().to_string();
+    let mut out = String::new();
+
+    let _ = writeln!(
+        out,
+        ".TH {} 1 \"\" \"{}\" \"Kaleidux Manual\"",
+        name.to_uppercase(),
+        cmd.get_version().unwrap_or_default()
+    );
+    out.push_str(".SH NAME\n");
+    let _ = writeln!(out, "{} \\- {}", name, escape(&text(cmd.get_about())));
+    out.push_str(".SH SYNOPSIS\n");
+    let _ = writeln!(out, ".B {}\n[\\fIOPTIONS\\fR] \\fICOMMAND\\fR", name);
+
+    out.push_str(".SH OPTIONS\n");
+    for arg in visible_args(&cmd).filter(|a| !a.is_positional()) {
+        write_arg(&mut out, arg);
+    }
+
+    out.push_str(".SH COMMANDS\n");
+    for sub in cmd.get_subcommands().filter(|c| !c.is_hide_set()) {
+        if sub.get_name() != "help" {
+            write_command(&mut out, sub, "");
+        }
+    }
+
+    out.push_str(".SH EXIT STATUS\n");
+    out.push_str("0 on success. A failed request exits with the status of its error code, ");
+    out.push_str("which JSON replies carry as \\fBcode\\fR:\n");
+    for code in kaleidux_common::ErrorCode::ALL {
+        let _ = writeln!(out, ".TP\n.B {}\n{}", code.exit_status(), code.as_str());
+    }
+    out
+}
+
+fn write_command(out: &mut String, cmd: &Command, parent: &str) {
+    let path = format!("{}{}", parent, cmd.get_name());
+    let mut usage = format!(".B {}", path);
+    for arg in visible_args(cmd).filter(|a| a.is_positional()) {
+        let id = arg.get_id().as_str().to_uppercase();
+        if arg.is_required_set() {
+            let _ = write!(usage, " \\fI{}\\fR", id);
+        } else {
+            let _ = write!(usage, " \\fR[\\fI{}\\fR]", id);
+        }
+    }
+    let aliases: Vec<&str> = cmd.get_visible_aliases().collect();
+    if !aliases.is_empty() {
+        let _ = write!(usage, " \\fR(alias: \\fB{}\\fR)", aliases.join(", "));
+    }
+
+    out.push_str(".TP\n");
+    out.push_str(&usage);
+    out.push('\n');
+    let about = text(cmd.get_long_about().or(cmd.get_about()));
+    let _ = writeln!(out, "{}", escape(&about));
+
+    // Global options (--socket, --help) are listed once under OPTIONS
+    let options: Vec<&Arg> = visible_args(cmd)
+        .filter(|a| !a.is_positional() && !a.is_global_set() && a.get_id() != "help")
+        .collect();
+    if !options.is_empty() {
+        out.push_str(".RS\n");
+        for arg in options {
+            write_arg(out, arg);
+        }
+        out.push_str(".RE\n");
+    }
+
+    for sub in cmd.get_subcommands().filter(|c| !c.is_hide_set()) {
+        if sub.get_name() != "help" {
+            write_command(out, sub, &format!("{} ", path));
+        }
+    }
+}
+
+fn write_arg(out: &mut String, arg: &Arg) {
+    let mut names = Vec::new();
+    if let Some(short) = arg.get_short() {
+        names.push(format!("\\-{}", short));
+    }
+    if let Some(long) = arg.get_long() {
+        names.push(format!("\\-\\-{}", long));
+    }
+    let value = if arg.get_action().takes_values() {
+        format!(" \\fI<{}>\\fR", arg.get_id().as_str().to_uppercase())
+    } else {
+        String::new()
+    };
+    let _ = writeln!(out, ".TP\n.B {}{}", names.join(", "), value);
+    let _ = writeln!(out, "{}", escape(&text(arg.get_help())));
+}
+
+fn visible_args(cmd: &Command) -> impl Iterator<Item = &Arg> {
+    cmd.get_arguments().filter(|a| !a.is_hide_set())
+}
+
+fn text(styled: Option<&clap::builder::StyledStr>) -> String {
+    styled.map(|s| s.to_string()).unwrap_or_default()
+}
+
+/// Escapes backslashes and leading dots/quotes so help text can't turn into roff requests
+fn escape(text: &str) -> String {
+    text.lines()
+        .map(|line| {
+            let line = line.trim().replace('\\', "\\e").replace('-', "\\-");
+            if line.starts_with('.') || line.starts_with('\'') {
+                format!("\\&{}", line)
+            } else {
+                line
+            }
+        })
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}